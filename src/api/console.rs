@@ -150,8 +150,8 @@ impl Console {
     /// Create a counter named "label"
     pub fn count(&mut self, label: &str) {
         let mut cnt = 1;
-        if self.count_map.contains_key(&label.to_owned()) {
-            cnt = self.count_map.get(&label.to_owned()).unwrap() + 1;
+        if self.count_map.contains_key(label) {
+            cnt = self.count_map.get(label).unwrap() + 1;
         }
 
         self.count_map.insert(label.to_owned(), cnt);
@@ -162,7 +162,7 @@ impl Console {
 
     /// Reset count of the given label to 0
     pub fn count_reset(&mut self, label: &str) {
-        if !self.count_map.contains_key(&label.to_owned()) {
+        if !self.count_map.contains_key(label) {
             self.logger(LogLevel::CountReset, &[&"label does not exist"]);
             return;
         }
@@ -214,7 +214,7 @@ impl Console {
 
     /// Create a timer with given label
     pub fn time(&mut self, label: &str) {
-        if self.timer_map.contains_key(&label.to_owned()) {
+        if self.timer_map.contains_key(label) {
             let warning = format!("Timer '{}' already started", label);
             self.logger(LogLevel::Warn, &[&warning]);
             return;
@@ -251,7 +251,7 @@ impl Console {
         let concat = format!(
             "{}: {}ms{}",
             label.to_owned(),
-            cur - self.timer_map.get(&label.to_owned()).unwrap().start,
+            cur - self.timer_map.get(label).unwrap().start,
             message
         );
         self.printer.print(LogLevel::TimeLog, &[&concat], &[]);
@@ -267,7 +267,7 @@ impl Console {
         let concat = format!(
             "{}: {}ms",
             label.to_owned(),
-            end - self.timer_map.get(&label.to_owned()).unwrap().start
+            end - self.timer_map.get(label).unwrap().start
         );
         self.printer.print(LogLevel::TimeEnd, &[&concat], &[]);
     }
@@ -1,10 +1,10 @@
 use anyhow::Result;
 use gosub_engine::html5_parser::parser::document::Document;
-use gosub_engine::html5_parser::{
-    input_stream::{Confidence, Encoding, InputStream},
-    parser::Html5Parser,
-};
-use std::fs;
+use gosub_engine::html5_parser::parser::Html5Parser;
+use gosub_engine::loader::archive::{ArchiveLoader, RecordingLoader};
+use gosub_engine::loader::{ResourceLoader, SchemeLoader};
+use gosub_engine::url::Url;
+use std::fs::File;
 use std::process::exit;
 
 fn bail(message: &str) -> ! {
@@ -12,37 +12,66 @@ fn bail(message: &str) -> ! {
     exit(1);
 }
 
+fn usage() -> ! {
+    bail("Usage: gosub-parser [--record <warc file>] [--replay <warc or mhtml file>] <url>")
+}
+
 fn main() -> Result<()> {
-    let url = std::env::args()
-        .nth(1)
-        .unwrap_or_else(|| bail("Usage: gosub-parser <url>"));
-
-    let html = if url.starts_with("http://") || url.starts_with("https://") {
-        // Fetch the html from the url
-        let response = ureq::get(&url).call()?;
-        if response.status() != 200 {
-            bail(&format!(
-                "Could not get url. Status code {}",
-                response.status()
-            ));
+    let mut url = None;
+    let mut record_file = None;
+    let mut replay_file = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record" => record_file = Some(args.next().unwrap_or_else(|| usage())),
+            "--replay" => replay_file = Some(args.next().unwrap_or_else(|| usage())),
+            _ if arg.starts_with('-') => usage(),
+            _ => url = Some(arg),
+        }
+    }
+
+    let mut loader: Box<dyn ResourceLoader> = match replay_file {
+        Some(path) => {
+            let archive = ArchiveLoader::open(path.as_ref())?;
+            // Without a URL, the main page of the archive is parsed
+            if url.is_none() {
+                url = archive.main_url().map(|url| url.href());
+            }
+            Box::new(archive)
         }
-        response.into_string()?
-    } else {
-        // Get html from the file
-        fs::read_to_string(&url)?
+        None => Box::<SchemeLoader>::default(),
     };
+    if let Some(path) = record_file {
+        loader = Box::new(RecordingLoader::new(loader, File::create(path)?)?);
+    }
+
+    let url = url.unwrap_or_else(|| usage());
 
-    let mut stream = InputStream::new();
-    stream.read_from_str(&html, Some(Encoding::UTF8));
-    stream.set_confidence(Confidence::Certain);
+    // Arguments that are not a URL are treated as local file paths
+    let url = match Url::parse(&url) {
+        Ok(url) => url,
+        Err(_) => Url::from_file_path(&std::env::current_dir()?.join(&url))?,
+    };
+
+    let response = loader.load(&url)?;
+    if !response.is_success() {
+        bail(&format!(
+            "Could not get url. Status code {}",
+            response.status
+        ));
+    }
 
-    // If the encoding confidence is not Confidence::Certain, we should detect the encoding.
-    if !stream.is_certain_encoding() {
-        stream.detect_encoding()
+    // Only HTML documents are fed to the parser
+    let mime_type = response.mime_type();
+    if !mime_type.is_html() {
+        bail(&format!("Not an HTML document: {}", mime_type.essence()));
     }
 
+    let mut stream = response.input_stream();
     let mut parser = Html5Parser::new(&mut stream);
-    let document = Document::shared();
+    let mut document = Document::shared();
+    document.get_mut().set_url(response.url.clone());
     let parse_errors = parser.parse(Document::clone(&document))?;
 
     println!("Generated tree: \n\n {}", document);
@@ -38,12 +38,10 @@ fn main() -> Result<()> {
             fixture_file.path
         );
 
-        let mut test_idx = 1;
-        for test in fixture_file.tests {
+        for (test_idx, test) in (1..).zip(fixture_file.tests.iter()) {
             if test_idx == 27 {
-                run_tree_test(test_idx, &test, &mut results);
+                run_tree_test(test_idx, test, &mut results);
             }
-            test_idx += 1;
         }
     }
 
@@ -1,104 +1,452 @@
 use gosub_engine::{
     html5_parser::{
-        input_stream::{Confidence, Encoding, InputStream},
-        node::{Node, NodeData},
-        parser::{document::Document, Html5Parser},
+        error_logger::ParseError,
+        node::NodeId,
+        parser::{
+            document::{Document, DocumentHandle},
+            Html5Parser,
+        },
+        text_renderer::{RenderedText, TextRenderOptions},
+    },
+    loader::{
+        archive::{ArchiveLoader, RecordingLoader},
+        cache::{CachingLoader, DiskCache, DEFAULT_MAX_SIZE},
+        cookies::CookieJar,
+        ResourceLoader, Response, SchemeLoader,
     },
     types::Result,
+    url::Url,
 };
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::rc::Rc;
 
-fn main() -> Result<()> {
-    let url = std::env::args()
-        .nth(1)
-        .or_else(|| {
-            println!("Usage: gosub-browser <url>");
-            exit(1);
+const HELP: &str = "\
+Commands:
+  open <url or file>   load a page (relative to the current page)
+  <number>             follow the link with that number
+  back, forward        move through the session history
+  reload               load the current page again
+  show                 render the current page again
+  links                list the links of the current page
+  find <text>          show the lines that contain the text (also: /<text>)
+  source               show the source of the current page
+  tree                 show the DOM tree of the current page
+  history              show the session history
+  cookies              show the stored cookies
+  width <columns>      change the width the page is rendered at
+  help                 show this help
+  quit                 leave";
+
+fn bail(message: &str) -> ! {
+    println!("{}", message);
+    exit(1);
+}
+
+fn usage() -> ! {
+    bail("Usage: test-user-agent [--width <columns>] [--no-references] [--cookies <file>] [--cache <dir>] [--offline] [--record <warc file>] [--replay <warc or mhtml file>] [--interactive] [<url or file>]")
+}
+
+/// A loaded page
+struct Page {
+    url: Url,
+    source: String,
+    document: DocumentHandle,
+    rendered: RenderedText,
+}
+
+impl Page {
+    /// Fetches and parses the page, and renders it as text
+    fn load(
+        loader: &dyn ResourceLoader,
+        url: Url,
+        options: &TextRenderOptions,
+    ) -> std::result::Result<Page, String> {
+        let response = fetch(loader, &url)?;
+        let (document, _) = parse(&response).map_err(|e| e.to_string())?;
+        let rendered = document.get().render_text(NodeId::root(), options);
+        Ok(Page {
+            url: response.url.clone(),
+            source: response.text(),
+            document,
+            rendered,
         })
-        .unwrap();
+    }
+
+    fn render(&mut self, options: &TextRenderOptions) {
+        self.rendered = self.document.get().render_text(NodeId::root(), options);
+    }
 
-    // Fetch the html from the url
-    let response = ureq::get(&url).call().map_err(Box::new)?;
-    if !response.status() == 200 {
-        println!("could not get url. Status code {}", response.status());
-        exit(1);
+    fn print(&self) {
+        let title = self.document.get().title().unwrap_or_default();
+        println!("=== {} ({})", title, self.url);
+        println!("{}", self.rendered.text);
+    }
+}
+
+/// Interactive browsing session with a history of visited pages
+struct Session {
+    options: TextRenderOptions,
+    loader: Box<dyn ResourceLoader>,
+    cookies: Rc<RefCell<CookieJar>>,
+    history: Vec<Page>,
+    position: usize,
+}
+
+impl Session {
+    fn new(
+        options: TextRenderOptions,
+        loader: Box<dyn ResourceLoader>,
+        cookies: Rc<RefCell<CookieJar>>,
+    ) -> Self {
+        Self {
+            options,
+            loader,
+            cookies,
+            history: vec![],
+            position: 0,
+        }
     }
-    let html = response.into_string()?;
 
-    let mut stream = InputStream::new();
-    stream.read_from_str(&html, Some(Encoding::UTF8));
-    stream.set_confidence(Confidence::Certain);
+    fn current(&self) -> Option<&Page> {
+        self.history.get(self.position)
+    }
+
+    /// Loads the URL and adds it to the history, dropping the pages that could be reached with
+    /// forward
+    fn navigate(&mut self, url: Url) {
+        match Page::load(self.loader.as_ref(), url, &self.options) {
+            Ok(page) => {
+                if !self.history.is_empty() {
+                    self.history.truncate(self.position + 1);
+                }
+                self.history.push(page);
+                self.position = self.history.len() - 1;
+                self.show();
+            }
+            Err(message) => println!("Could not load page: {}", message),
+        }
+    }
 
-    // If the encoding confidence is not Confidence::Certain, we should detect the encoding.
-    if !stream.is_certain_encoding() {
-        stream.detect_encoding()
+    /// Resolves the input against the URL of the current page. Without a current page, input
+    /// that is not a URL is treated as a local file path.
+    fn resolve(&self, input: &str) -> Option<Url> {
+        if let Some(page) = self.current() {
+            return Url::parse_with_base(input, Some(&page.url)).ok();
+        }
+        match Url::parse(input) {
+            Ok(url) => Some(url),
+            Err(_) => std::env::current_dir()
+                .ok()
+                .and_then(|dir| Url::from_file_path(&dir.join(input)).ok()),
+        }
+    }
+
+    fn follow(&mut self, number: usize) {
+        let link = self
+            .current()
+            .and_then(|page| page.rendered.links.get(number.wrapping_sub(1)))
+            .cloned();
+        match link.map(|link| Url::parse(&link)) {
+            Some(Ok(url)) => self.navigate(url),
+            Some(Err(_)) => println!("Link {} has an invalid URL", number),
+            None => println!("There is no link {}", number),
+        }
+    }
+
+    fn go(&mut self, offset: isize) {
+        let Some(position) = self.position.checked_add_signed(offset) else {
+            return println!("No previous page");
+        };
+        if position >= self.history.len() {
+            return println!("No next page");
+        }
+        self.position = position;
+        self.show();
+    }
+
+    fn reload(&mut self) {
+        let Some(page) = self.current() else {
+            return println!("No page loaded");
+        };
+        match Page::load(self.loader.as_ref(), page.url.clone(), &self.options) {
+            Ok(page) => {
+                self.history[self.position] = page;
+                self.show();
+            }
+            Err(message) => println!("Could not load page: {}", message),
+        }
+    }
+
+    fn show(&self) {
+        match self.current() {
+            Some(page) => page.print(),
+            None => println!("No page loaded"),
+        }
+    }
+
+    fn links(&self) {
+        let Some(page) = self.current() else {
+            return println!("No page loaded");
+        };
+        for (idx, link) in page.rendered.links.iter().enumerate() {
+            println!("{:>4}. {}", idx + 1, link);
+        }
+    }
+
+    fn find(&self, text: &str) {
+        let Some(page) = self.current() else {
+            return println!("No page loaded");
+        };
+        let needle = text.to_lowercase();
+        let mut found = false;
+        for (idx, line) in page.rendered.text.lines().enumerate() {
+            if line.to_lowercase().contains(&needle) {
+                println!("{:>5}: {}", idx + 1, line);
+                found = true;
+            }
+        }
+        if !found {
+            println!("'{}' not found", text);
+        }
     }
 
+    fn history(&self) {
+        for (idx, page) in self.history.iter().enumerate() {
+            let marker = if idx == self.position { '>' } else { ' ' };
+            println!("{} {:>3}. {}", marker, idx + 1, page.url);
+        }
+    }
+
+    fn cookies(&self) {
+        let cookies = self.cookies.borrow();
+        if cookies.cookies().is_empty() {
+            return println!("No cookies stored");
+        }
+        for cookie in cookies.cookies() {
+            println!(
+                "{}={} (domain {}, path {})",
+                cookie.name, cookie.value, cookie.domain, cookie.path
+            );
+        }
+    }
+
+    fn set_width(&mut self, width: usize) {
+        self.options.width = width;
+        for page in &mut self.history {
+            page.render(&self.options);
+        }
+        self.show();
+    }
+
+    /// Executes a single command. Returns false when the session should end.
+    fn execute(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
+
+        if let Ok(number) = command.parse::<usize>() {
+            self.follow(number);
+            return true;
+        }
+        if let Some(text) = line.strip_prefix('/') {
+            self.find(text);
+            return true;
+        }
+
+        match command {
+            "" => {}
+            "open" | "go" if !argument.is_empty() => match self.resolve(argument) {
+                Some(url) => self.navigate(url),
+                None => println!("Invalid URL: {}", argument),
+            },
+            "follow" => match argument.parse() {
+                Ok(number) => self.follow(number),
+                Err(_) => println!("Usage: follow <number>"),
+            },
+            "back" | "b" => self.go(-1),
+            "forward" | "f" => self.go(1),
+            "reload" | "r" => self.reload(),
+            "show" | "s" => self.show(),
+            "links" | "l" => self.links(),
+            "find" if !argument.is_empty() => self.find(argument),
+            "source" => match self.current() {
+                Some(page) => println!("{}", page.source),
+                None => println!("No page loaded"),
+            },
+            "tree" => match self.current() {
+                Some(page) => println!("{}", page.document),
+                None => println!("No page loaded"),
+            },
+            "history" => self.history(),
+            "cookies" => self.cookies(),
+            "width" => match argument.parse() {
+                Ok(width) if width > 0 => self.set_width(width),
+                _ => println!("Usage: width <columns>"),
+            },
+            "help" | "?" => println!("{}", HELP),
+            "quit" | "exit" | "q" => return false,
+            _ => println!(
+                "Unknown command '{}'. Type 'help' for a list of commands.",
+                line
+            ),
+        }
+        true
+    }
+}
+
+/// Fetches the page. Only successful responses with an HTML document are returned.
+fn fetch(loader: &dyn ResourceLoader, url: &Url) -> std::result::Result<Response, String> {
+    let response = loader.load(url).map_err(|e| e.to_string())?;
+    if !response.is_success() {
+        return Err(format!("status code {}", response.status));
+    }
+    let mime_type = response.mime_type();
+    if !mime_type.is_html() {
+        return Err(format!("not an HTML document ({})", mime_type.essence()));
+    }
+    Ok(response)
+}
+
+fn parse(response: &Response) -> Result<(DocumentHandle, Vec<ParseError>)> {
+    let mut stream = response.input_stream();
     let mut parser = Html5Parser::new(&mut stream);
 
-    let document = Document::shared();
+    let mut document = Document::shared();
+    document.get_mut().set_url(response.url.clone());
     let parse_errors = parser.parse(Document::clone(&document))?;
+    Ok((document, parse_errors))
+}
 
-    match get_node_by_path(&document.get(), vec!["html", "body"]) {
-        None => {
-            println!("[No Body Found]");
+fn interactive(session: &mut Session) -> Result<()> {
+    println!("Type 'help' for a list of commands.");
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("gosub> ");
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        if !session.execute(&line?) {
+            return Ok(());
         }
-        Some(node) => display_node(&document.get(), node),
     }
+}
+
+/// Loads and renders a single page
+fn print_page(loader: &dyn ResourceLoader, url: &str, options: &TextRenderOptions) -> Result<()> {
+    // Arguments that are not a URL are treated as local file paths
+    let url = match Url::parse(url) {
+        Ok(url) => url,
+        Err(_) => Url::from_file_path(&std::env::current_dir()?.join(url))?,
+    };
+
+    let response = fetch(loader, &url)
+        .unwrap_or_else(|message| bail(&format!("Could not get url: {}", message)));
+    let (document, parse_errors) = parse(&response)?;
+
+    let rendered = document.get().render_text(NodeId::root(), options);
+    println!("{}", rendered.text);
 
     for e in parse_errors {
-        println!("Parse Error: {}", e.message)
+        eprintln!("Parse Error: {}", e.message)
     }
 
     Ok(())
 }
 
-fn get_node<'a>(document: &'a Document, parent: &'a Node, name: &'a str) -> Option<&'a Node> {
-    for id in &parent.children {
-        match document.get_node_by_id(*id) {
-            None => {}
-            Some(node) => {
-                if node.name.eq(name) {
-                    return Some(node);
-                }
-            }
-        }
+/// Loads the cookie jar from the file. A missing file gives an empty jar.
+fn load_cookies(path: Option<&Path>) -> Result<CookieJar> {
+    match path {
+        Some(path) if path.exists() => CookieJar::load(path),
+        _ => Ok(CookieJar::new()),
     }
-    None
 }
 
-fn get_node_by_path<'a>(document: &'a Document, path: Vec<&'a str>) -> Option<&'a Node> {
-    let mut node = document.get_root();
-    match document.get_node_by_id(node.children[0]) {
-        None => {
-            return None;
-        }
-        Some(child) => {
-            node = child;
+fn main() -> Result<()> {
+    let mut options = TextRenderOptions::default();
+    let mut url = None;
+    let mut interactive_mode = false;
+    let mut cookie_file: Option<PathBuf> = None;
+    let mut cache_dir: Option<PathBuf> = None;
+    let mut offline = false;
+    let mut record_file: Option<PathBuf> = None;
+    let mut replay_file: Option<PathBuf> = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => {
+                options.width = args
+                    .next()
+                    .and_then(|width| width.parse().ok())
+                    .filter(|width| *width > 0)
+                    .unwrap_or_else(|| usage());
+            }
+            "--no-references" => options.references = false,
+            "--cookies" => cookie_file = Some(args.next().unwrap_or_else(|| usage()).into()),
+            "--cache" => cache_dir = Some(args.next().unwrap_or_else(|| usage()).into()),
+            "--offline" => offline = true,
+            "--record" => record_file = Some(args.next().unwrap_or_else(|| usage()).into()),
+            "--replay" => replay_file = Some(args.next().unwrap_or_else(|| usage()).into()),
+            "--interactive" | "-i" => interactive_mode = true,
+            _ if arg.starts_with('-') => usage(),
+            _ => url = Some(arg),
         }
     }
-    for name in path {
-        match get_node(document, node, name) {
-            Some(new_node) => {
-                node = new_node;
-            }
-            None => {
-                return None;
+
+    let cookies = Rc::new(RefCell::new(load_cookies(cookie_file.as_deref())?));
+
+    // Offline mode serves pages from the cache, so it needs one
+    let mut loader: Box<dyn ResourceLoader> = match (replay_file, cache_dir) {
+        (Some(path), _) => {
+            let archive = ArchiveLoader::open(&path)?;
+            // Without a URL, the main page of the archive is shown
+            if url.is_none() {
+                url = archive.main_url().map(|url| url.href());
             }
+            Box::new(archive)
+        }
+        (None, Some(dir)) => {
+            let mut loader = CachingLoader::new(
+                Box::new(SchemeLoader::with_cookies(Rc::clone(&cookies))),
+                DiskCache::open(&dir, DEFAULT_MAX_SIZE)?,
+            );
+            loader.set_offline(offline);
+            Box::new(loader)
         }
+        (None, None) if offline => usage(),
+        (None, None) => Box::new(SchemeLoader::with_cookies(Rc::clone(&cookies))),
+    };
+    if let Some(path) = record_file {
+        loader = Box::new(RecordingLoader::new(loader, File::create(path)?)?);
     }
-    Some(node)
-}
 
-fn display_node(document: &Document, node: &Node) {
-    if let NodeData::Text(text) = &node.data {
-        if !text.value().eq("\n") {
-            println!("{}", text.value());
+    match url {
+        Some(url) if !interactive_mode => print_page(loader.as_ref(), &url, &options)?,
+        url => {
+            let mut session = Session::new(options, loader, Rc::clone(&cookies));
+            if let Some(url) = url {
+                match session.resolve(&url) {
+                    Some(url) => session.navigate(url),
+                    None => println!("Invalid URL: {}", url),
+                }
+            }
+            interactive(&mut session)?;
         }
     }
-    for child_id in &node.children {
-        if let Some(child) = document.get_node_by_id(*child_id) {
-            display_node(document, child);
-        }
+
+    if let Some(path) = cookie_file {
+        cookies.borrow().save(&path)?;
     }
+    Ok(())
 }
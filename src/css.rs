@@ -1,55 +1,37 @@
 //! This module contains the CSS3 parser
 //!
-//! # Token Reference
+//! # Tokens
+//!
+//! The tokenizer follows [CSS Syntax Level 3](https://www.w3.org/TR/css-syntax-3/#tokenization).
+//! Line breaks are normalized before tokenizing and comments do not produce tokens.
 //!
 //! ```txt
-//! IDENT           {ident}
-//! ATKEYWORD       @{ident}
-//! STRING          {string}
-//! INVALID         {invalid}
-//! HASH            #{name}
-//! NUMBER          {num}
-//! PERCENTAGE      {num}%
-//! DIMENSION       {num}{ident}
-//! URI                url\({w}{string}{w}\)
-//!                 |  url\({w}([!#$%&*-~]|{nonascii}|{escape})*{w}\)   
-//! UNICODE-RANGE   U\+[0-9A-F?]{1,6}(-[0-9A-F]{1,6})?
+//! IDENT           foo, -foo, --foo, \66oo
+//! FUNCTION        foo(
+//! AT-KEYWORD      @foo
+//! HASH            #foo ("id" when the name would start an identifier), #123
+//! STRING          "foo", 'foo'
+//! BAD-STRING      a string with an unescaped newline
+//! URL             url(foo), url("foo")
+//! BAD-URL         url(foo bar), url(foo"bar)
+//! DELIM           any other single character
+//! NUMBER          12, +1.5, -.5e3
+//! PERCENTAGE      {number}%
+//! DIMENSION       {number}{ident}
+//! UNICODE-RANGE   U+26, U+0-7F, U+4??
+//! INCLUDE-MATCH   ~=
+//! DASH-MATCH      |=
+//! PREFIX-MATCH    ^=
+//! SUFFIX-MATCH    $=
+//! SUBSTRING-MATCH *=
+//! COLUMN          ||
+//! WHITESPACE      [ \t\n]+
 //! CDO             <!--
 //! CDC             -->
+//! COLON           :
 //! SEMICOLON       ;
-//! LCURLY          \{
-//! RCURLY          \
-//! LPAREN          \(
-//! RPAREN          \)
-//! LBRACKET        \[
-//! RBRACKET        \]
-//! S               [ \t\r\n\f]+
-//! COMMENT         \/\*[^*]*\*+([^/*][^*]*\*+)*\/
-//! FUNCTION        {ident}\(
-//! INCLUDES        ~=
-//! DASHMATCH       |=
-//! DELIM           any other character not matched by the above rules, and neither a single nor a double quotes
-//!
-//! The macros in curly braces ({}) above are defined as follows:
-//!
-//! Macro Definition
-//! ================
-//! ident       [-]?{nmstart}{nmchar}*
-//! name        {nmchar}+
-//! nmstart     [_a-zA-Z] | {nonascii} | {escape}
-//! nonascii    [^\0-\177]
-//! unicode     \\[0-9a-f]{1,6}(\r\n | [ \n\r\t\f])?
-//! escape      {unicode} | \\[^\n\r\f0-9a-f]
-//! nmchar      [_a-zA-Z0-9-] | {nonascii} | {escape}
-//! num         [0-9]+ | [0-9]*\.[0-9]+
-//! string      {string_1} | {string_2}
-//! string_1     \"([^\n\r\f\\"]|\\{nl} | {escape})*\"
-//! string_2     \'([^\n\r\f\\']|\\{nl} | {escape})*\'
-//! invalid     {invalid_1} | {invalid_2}
-//! invalid_1    \"([^\n\r\f\\"] | \\{nl} | {escape})*
-//! invalid_2    \'([^\n\r\f\\'] | \\{nl} | {escape})*
-//! nl          \n |\r\n |\r |\f
-//! w           [ \t\r\n\f]*
+//! COMMA           ,
+//! [ ] ( ) { }
 //! ```
 //!
 //! # Backus Naur Form (BNF)
@@ -111,7 +93,12 @@
 //! ;
 //! ```
 //!
+pub mod cascade;
+pub mod color;
+pub mod media;
 pub mod node;
 pub mod parser;
+pub mod specificity;
 pub mod tokenizer;
 pub mod tokens;
+pub mod values;
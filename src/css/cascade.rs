@@ -0,0 +1,394 @@
+//! The [cascade](https://drafts.csswg.org/css-cascade-5/#cascading): orders the declarations
+//! that apply to an element, so that the last declaration of each property wins.
+//!
+//! Declarations are ordered by origin and importance, then by cascade layer, then by the
+//! specificity of the selector and finally by source order. Important declarations reverse the
+//! order of origins and layers: an important user agent declaration wins over an important
+//! author declaration, and an important declaration in an earlier layer wins over one in a later
+//! layer or outside of any layer.
+use crate::css::media::MediaEnvironment;
+use crate::css::node::{
+    AtRule, AtRulePreludeChild, AtRulePreludeValue, Block, BlockChild, ComplexSelector,
+    Declaration, Rule, StyleSheet, StyleSheetRule,
+};
+use crate::css::specificity::Specificity;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// The [origin](https://drafts.csswg.org/css-cascade-5/#cascading-origins) of a style sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Origin {
+    UserAgent,
+    User,
+    Author,
+}
+
+/// A declaration that applies to an element, with everything needed to order it in the cascade
+#[derive(Debug, Clone, PartialEq)]
+pub struct CascadedDeclaration<'a> {
+    pub declaration: &'a Declaration,
+    pub origin: Origin,
+    /// The position of the cascade layer: the index of each layer among its siblings, outermost
+    /// first. Empty for declarations outside of any layer.
+    pub layer: Vec<usize>,
+    /// The specificity of the most specific selector of the rule that matches the element
+    pub specificity: Specificity,
+    /// The position of the rule among all style sheets
+    pub source_order: usize,
+}
+
+impl CascadedDeclaration<'_> {
+    /// Compares the precedence of two declarations: the greater one wins
+    pub fn cmp_precedence(&self, other: &CascadedDeclaration) -> Ordering {
+        let important = self.declaration.important;
+        important
+            .cmp(&other.declaration.important)
+            .then_with(|| {
+                let origins = self.origin.cmp(&other.origin);
+                if important {
+                    origins.reverse()
+                } else {
+                    origins
+                }
+            })
+            .then_with(|| {
+                let layers = layer_order(&self.layer).cmp(layer_order(&other.layer));
+                if important {
+                    layers.reverse()
+                } else {
+                    layers
+                }
+            })
+            .then_with(|| self.specificity.cmp(&other.specificity))
+            .then_with(|| self.source_order.cmp(&other.source_order))
+    }
+}
+
+/// Declarations directly in a layer come after those in its sublayers, and declarations outside
+/// of any layer come last
+fn layer_order(layer: &[usize]) -> impl Iterator<Item = usize> + '_ {
+    layer.iter().copied().chain(std::iter::once(usize::MAX))
+}
+
+/// Sorts declarations from the lowest to the highest precedence
+pub fn sort(declarations: &mut [CascadedDeclaration]) {
+    // The sort is stable, so equal declarations keep their order
+    declarations.sort_by(|a, b| a.cmp_precedence(b));
+}
+
+/// A style rule in a style sheet that has been added to the cascade
+#[derive(Debug)]
+struct CascadeRule<'a> {
+    rule: &'a Rule,
+    origin: Origin,
+    layer: Vec<usize>,
+}
+
+/// The cascade layers of an origin, in the order in which they are first declared
+#[derive(Debug, Default)]
+struct LayerTree {
+    children: Vec<(String, LayerTree)>,
+}
+
+impl LayerTree {
+    /// Returns the position of the layer with the dotted name inside the layer at the position,
+    /// and declares it if needed. An empty name declares a new anonymous layer.
+    fn declare(&mut self, parent: &[usize], name: &str) -> Vec<usize> {
+        let mut tree = self;
+        for idx in parent {
+            tree = &mut tree.children[*idx].1;
+        }
+
+        let mut position = parent.to_vec();
+        if name.is_empty() {
+            tree.children.push((String::new(), LayerTree::default()));
+            position.push(tree.children.len() - 1);
+            return position;
+        }
+        for part in name.split('.') {
+            let idx = match tree
+                .children
+                .iter()
+                .position(|(child, _)| !child.is_empty() && child == part)
+            {
+                Some(idx) => idx,
+                None => {
+                    tree.children.push((part.to_string(), LayerTree::default()));
+                    tree.children.len() - 1
+                }
+            };
+            position.push(idx);
+            tree = &mut tree.children[idx].1;
+        }
+        position
+    }
+}
+
+/// Collects the style rules of style sheets, and cascades the declarations that apply to an
+/// element
+#[derive(Debug)]
+pub struct Cascade<'a> {
+    /// Decides which `@media` rules apply
+    environment: MediaEnvironment,
+    rules: Vec<CascadeRule<'a>>,
+    layers: HashMap<Origin, LayerTree>,
+}
+
+impl<'a> Cascade<'a> {
+    pub fn new(environment: MediaEnvironment) -> Cascade<'a> {
+        Cascade {
+            environment,
+            rules: Vec::new(),
+            layers: HashMap::new(),
+        }
+    }
+
+    /// Adds the style rules of a style sheet. Style sheets must be added in document order.
+    /// `@media` rules that do not match the environment are skipped, `@supports` conditions are
+    /// not evaluated and `@import` rules are not followed.
+    pub fn add_stylesheet(&mut self, stylesheet: &'a StyleSheet, origin: Origin) {
+        for rule in &stylesheet.children {
+            match rule {
+                StyleSheetRule::Rule(rule) => self.add_rule(rule, origin, &[]),
+                StyleSheetRule::AtRule(rule) => self.add_at_rule(rule, origin, &[]),
+            }
+        }
+    }
+
+    fn add_rule(&mut self, rule: &'a Rule, origin: Origin, layer: &[usize]) {
+        self.rules.push(CascadeRule {
+            rule,
+            origin,
+            layer: layer.to_vec(),
+        });
+    }
+
+    fn add_at_rule(&mut self, rule: &'a AtRule, origin: Origin, layer: &[usize]) {
+        let name = rule.name.to_ascii_lowercase();
+        match name.as_str() {
+            "media" if !self.environment.matches_at_rule(rule) => {}
+            "media" | "supports" => {
+                if let Some(block) = &rule.block {
+                    self.add_block(block, origin, layer);
+                }
+            }
+            "layer" => {
+                let tree = self.layers.entry(origin).or_default();
+                let names: Vec<&str> = match &rule.prelude {
+                    AtRulePreludeValue::AtRulePrelude(prelude) => prelude
+                        .children
+                        .iter()
+                        .filter_map(|child| match child {
+                            AtRulePreludeChild::LayerList(list) => Some(list),
+                            _ => None,
+                        })
+                        .flat_map(|list| list.children.iter().map(|layer| layer.name.as_str()))
+                        .collect(),
+                    _ => vec![""],
+                };
+                // `@layer a, b;` only declares the layers, `@layer a { }` also adds rules to it
+                let positions: Vec<Vec<usize>> = names
+                    .into_iter()
+                    .map(|name| tree.declare(layer, name))
+                    .collect();
+                if let (Some(block), [position]) = (&rule.block, positions.as_slice()) {
+                    self.add_block(block, origin, position);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_block(&mut self, block: &'a Block, origin: Origin, layer: &[usize]) {
+        for child in &block.children {
+            match child {
+                BlockChild::Rule(rule) => self.add_rule(rule, origin, layer),
+                BlockChild::AtRule(rule) => self.add_at_rule(rule, origin, layer),
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the declarations of the rules that match an element, from the lowest to the
+    /// highest precedence. `matches` decides whether a complex selector matches the element.
+    pub fn cascade(
+        &self,
+        matches: impl Fn(&ComplexSelector) -> bool,
+    ) -> Vec<CascadedDeclaration<'a>> {
+        let mut declarations = Vec::new();
+        for (source_order, rule) in self.rules.iter().enumerate() {
+            let Some(specificity) = rule
+                .rule
+                .selectors
+                .children
+                .iter()
+                .filter(|selector| matches(selector))
+                .map(ComplexSelector::specificity)
+                .max()
+            else {
+                continue;
+            };
+
+            for child in &rule.rule.block.children {
+                let BlockChild::DeclarationList(list) = child else {
+                    continue;
+                };
+                for declaration in &list.children {
+                    declarations.push(CascadedDeclaration {
+                        declaration,
+                        origin: rule.origin,
+                        layer: rule.layer.clone(),
+                        specificity,
+                        source_order,
+                    });
+                }
+            }
+        }
+        sort(&mut declarations);
+        declarations
+    }
+
+    /// Returns the winning declaration of each property of the rules that match an element
+    pub fn declared_values(
+        &self,
+        matches: impl Fn(&ComplexSelector) -> bool,
+    ) -> HashMap<&'a str, &'a Declaration> {
+        self.cascade(matches)
+            .into_iter()
+            .map(|cascaded| (cascaded.declaration.property.as_str(), cascaded.declaration))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::css::parser::CSS3Parser;
+
+    /// Cascades the style sheets for an element that matches the selectors in `matching`, and
+    /// returns the winning value of `color`
+    fn color(stylesheets: &[(Origin, &str)], matching: &[&str]) -> Option<String> {
+        let parsed: Vec<(Origin, StyleSheet)> = stylesheets
+            .iter()
+            .map(|(origin, css)| (*origin, CSS3Parser::new().parse(css)))
+            .collect();
+        let mut cascade = Cascade::new(MediaEnvironment::default());
+        for (origin, stylesheet) in &parsed {
+            cascade.add_stylesheet(stylesheet, *origin);
+        }
+        let values =
+            cascade.declared_values(|selector| matching.contains(&selector.to_string().as_str()));
+        values
+            .get("color")
+            .map(|declaration| declaration.value.to_string())
+    }
+
+    #[test]
+    fn specificity_and_source_order() {
+        let css = "p { color: red } .a { color: blue } p { color: green }";
+        assert_eq!(
+            color(&[(Origin::Author, css)], &["p"]).as_deref(),
+            Some("green")
+        );
+        assert_eq!(
+            color(&[(Origin::Author, css)], &["p", ".a"]).as_deref(),
+            Some("blue")
+        );
+        assert_eq!(
+            color(
+                &[(Origin::Author, "#b, p { color: red } .a { color: blue }")],
+                &["p", ".a"]
+            )
+            .as_deref(),
+            Some("blue")
+        );
+        assert_eq!(color(&[(Origin::Author, css)], &["div"]), None);
+    }
+
+    #[test]
+    fn origins_and_importance() {
+        let user_agent = (
+            Origin::UserAgent,
+            "#a { color: red !important } p { color: red }",
+        );
+        let user = (
+            Origin::User,
+            "p { color: blue !important } .a { color: blue }",
+        );
+        let author = (
+            Origin::Author,
+            "p { color: green } #a { color: green !important }",
+        );
+
+        assert_eq!(
+            color(&[user_agent, author], &["p"]).as_deref(),
+            Some("green")
+        );
+        assert_eq!(color(&[author, user], &["p"]).as_deref(), Some("blue"));
+        assert_eq!(
+            color(&[author, user], &["p", "#a"]).as_deref(),
+            Some("blue")
+        );
+        assert_eq!(
+            color(&[user_agent, user, author], &["p", "#a"]).as_deref(),
+            Some("red")
+        );
+        assert_eq!(color(&[author], &["#a"]).as_deref(), Some("green"));
+    }
+
+    #[test]
+    fn layers() {
+        let css = "@layer base, theme;
+             p { color: unlayered }
+             @layer theme { #a { color: theme } }
+             @layer base { #a { color: base } }";
+        assert_eq!(
+            color(&[(Origin::Author, css)], &["p", "#a"]).as_deref(),
+            Some("unlayered")
+        );
+        assert_eq!(
+            color(&[(Origin::Author, css)], &["#a"]).as_deref(),
+            Some("theme")
+        );
+
+        let important = "@layer base, theme;
+             p { color: unlayered !important }
+             @layer theme { p { color: theme !important } }
+             @layer base { p { color: base !important } }";
+        assert_eq!(
+            color(&[(Origin::Author, important)], &["p"]).as_deref(),
+            Some("base")
+        );
+
+        // Declarations directly in a layer win over those in its sublayers
+        let nested = "@layer a { p { color: a } @layer b { #a { color: b } } }
+             @layer a.c { #a { color: c } }
+             @layer { #a { color: anonymous } }";
+        assert_eq!(
+            color(&[(Origin::Author, nested)], &["p", "#a"]).as_deref(),
+            Some("anonymous")
+        );
+        assert_eq!(
+            color(
+                &[(
+                    Origin::Author,
+                    "@layer a { p { color: a } @layer b { #a { color: b } } }"
+                )],
+                &["p", "#a"]
+            )
+            .as_deref(),
+            Some("a")
+        );
+    }
+
+    #[test]
+    fn media_rules() {
+        let css = "p { color: red }
+             @media print { p { color: blue } }
+             @media (min-width: 600px) { @supports (color: red) { p { color: green } } }";
+        assert_eq!(
+            color(&[(Origin::Author, css)], &["p"]).as_deref(),
+            Some("green")
+        );
+    }
+}
@@ -0,0 +1,786 @@
+//! CSS [colors](https://www.w3.org/TR/css-color-4/)
+//!
+//! Named colors, hex colors, `rgb()`, `hsl()` and `hwb()` are sRGB colors that serialize with
+//! the legacy `rgb()` syntax. `lab()`, `lch()`, `oklab()`, `oklch()` and `color()` keep the
+//! color space they were written in.
+mod convert;
+mod named;
+
+use crate::css::node::{Function, Value};
+use crate::css::parser::CSS3Parser;
+use crate::css::values::calc::{CalcNode, CalcType};
+use std::fmt::{self, Display, Formatter};
+
+pub use named::NAMED_COLORS;
+
+/// A color in the sRGB color space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba {
+    /// Red, from 0 to 255
+    pub red: f32,
+    /// Green, from 0 to 255
+    pub green: f32,
+    /// Blue, from 0 to 255
+    pub blue: f32,
+    /// Alpha, from 0 (transparent) to 1 (opaque)
+    pub alpha: f32,
+}
+
+impl Rgba {
+    pub fn new(red: f32, green: f32, blue: f32, alpha: f32) -> Rgba {
+        Rgba {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    pub fn to_absolute(&self) -> AbsoluteColor {
+        AbsoluteColor::new(
+            ColorSpace::Srgb,
+            [self.red / 255.0, self.green / 255.0, self.blue / 255.0],
+            self.alpha,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Red, green and blue from 0 to 1, like the other RGB spaces
+    Srgb,
+    SrgbLinear,
+    DisplayP3,
+    A98Rgb,
+    ProphotoRgb,
+    Rec2020,
+    XyzD50,
+    XyzD65,
+    /// Lightness from 0 to 100
+    Lab,
+    /// Lightness from 0 to 100, chroma and hue in degrees
+    Lch,
+    /// Lightness from 0 to 1
+    Oklab,
+    /// Lightness from 0 to 1, chroma and hue in degrees
+    Oklch,
+    /// Hue in degrees, saturation and lightness from 0 to 100
+    Hsl,
+    /// Hue in degrees, whiteness and blackness from 0 to 100
+    Hwb,
+}
+
+impl ColorSpace {
+    /// Returns the space for a name in `color()`
+    pub fn from_name(name: &str) -> Option<ColorSpace> {
+        let space = match name.to_ascii_lowercase().as_str() {
+            "srgb" => ColorSpace::Srgb,
+            "srgb-linear" => ColorSpace::SrgbLinear,
+            "display-p3" => ColorSpace::DisplayP3,
+            "a98-rgb" => ColorSpace::A98Rgb,
+            "prophoto-rgb" => ColorSpace::ProphotoRgb,
+            "rec2020" => ColorSpace::Rec2020,
+            "xyz-d50" => ColorSpace::XyzD50,
+            "xyz" | "xyz-d65" => ColorSpace::XyzD65,
+            _ => return None,
+        };
+        Some(space)
+    }
+
+    /// Returns the name in `color()`, or the function name for the other spaces
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorSpace::Srgb => "srgb",
+            ColorSpace::SrgbLinear => "srgb-linear",
+            ColorSpace::DisplayP3 => "display-p3",
+            ColorSpace::A98Rgb => "a98-rgb",
+            ColorSpace::ProphotoRgb => "prophoto-rgb",
+            ColorSpace::Rec2020 => "rec2020",
+            ColorSpace::XyzD50 => "xyz-d50",
+            ColorSpace::XyzD65 => "xyz-d65",
+            ColorSpace::Lab => "lab",
+            ColorSpace::Lch => "lch",
+            ColorSpace::Oklab => "oklab",
+            ColorSpace::Oklch => "oklch",
+            ColorSpace::Hsl => "hsl",
+            ColorSpace::Hwb => "hwb",
+        }
+    }
+}
+
+/// A color in a given color space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AbsoluteColor {
+    pub space: ColorSpace,
+    /// The components, `None` when missing (`none`)
+    pub components: [Option<f32>; 3],
+    /// Alpha from 0 to 1, `None` when missing
+    pub alpha: Option<f32>,
+}
+
+impl AbsoluteColor {
+    pub fn new(space: ColorSpace, components: [f32; 3], alpha: f32) -> AbsoluteColor {
+        AbsoluteColor {
+            space,
+            components: components.map(Some),
+            alpha: Some(alpha),
+        }
+    }
+
+    /// Creates a color from components where NaN is a missing component
+    fn with_missing(space: ColorSpace, components: [f64; 3], alpha: f64) -> AbsoluteColor {
+        let component = |value: f64| (!value.is_nan()).then_some(value as f32);
+        AbsoluteColor {
+            space,
+            components: components.map(component),
+            alpha: component(alpha),
+        }
+    }
+
+    /// Converts the color to another color space. Missing components are treated as zero.
+    pub fn to_space(&self, space: ColorSpace) -> AbsoluteColor {
+        let components = self
+            .components
+            .map(|component| component.map_or(0.0, |value| value as f64));
+        let converted = convert::convert(self.space, space, components);
+        AbsoluteColor::new(
+            space,
+            converted.map(|value| value as f32),
+            self.alpha.unwrap_or(0.0),
+        )
+    }
+
+    /// Converts the color to sRGB, clipping it to the sRGB gamut
+    pub fn to_rgba(&self) -> Rgba {
+        let srgb = self.to_space(ColorSpace::Srgb);
+        let [red, green, blue] = srgb
+            .components
+            .map(|c| c.unwrap_or(0.0).clamp(0.0, 1.0) * 255.0);
+        Rgba::new(red, green, blue, srgb.alpha.unwrap_or(0.0).clamp(0.0, 1.0))
+    }
+}
+
+/// The [system colors](https://www.w3.org/TR/css-color-4/#css-system-colors)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemColor {
+    AccentColor,
+    AccentColorText,
+    ActiveText,
+    ButtonBorder,
+    ButtonFace,
+    ButtonText,
+    Canvas,
+    CanvasText,
+    Field,
+    FieldText,
+    GrayText,
+    Highlight,
+    HighlightText,
+    LinkText,
+    Mark,
+    MarkText,
+    SelectedItem,
+    SelectedItemText,
+    VisitedText,
+}
+
+const SYSTEM_COLORS: &[(&str, SystemColor)] = &[
+    ("accentcolor", SystemColor::AccentColor),
+    ("accentcolortext", SystemColor::AccentColorText),
+    ("activetext", SystemColor::ActiveText),
+    ("buttonborder", SystemColor::ButtonBorder),
+    ("buttonface", SystemColor::ButtonFace),
+    ("buttontext", SystemColor::ButtonText),
+    ("canvas", SystemColor::Canvas),
+    ("canvastext", SystemColor::CanvasText),
+    ("field", SystemColor::Field),
+    ("fieldtext", SystemColor::FieldText),
+    ("graytext", SystemColor::GrayText),
+    ("highlight", SystemColor::Highlight),
+    ("highlighttext", SystemColor::HighlightText),
+    ("linktext", SystemColor::LinkText),
+    ("mark", SystemColor::Mark),
+    ("marktext", SystemColor::MarkText),
+    ("selecteditem", SystemColor::SelectedItem),
+    ("selecteditemtext", SystemColor::SelectedItemText),
+    ("visitedtext", SystemColor::VisitedText),
+];
+
+impl SystemColor {
+    pub fn from_name(name: &str) -> Option<SystemColor> {
+        SYSTEM_COLORS
+            .iter()
+            .find(|(keyword, _)| name.eq_ignore_ascii_case(keyword))
+            .map(|(_, color)| *color)
+    }
+
+    /// Returns the keyword in lowercase
+    pub fn name(&self) -> &'static str {
+        SYSTEM_COLORS
+            .iter()
+            .find(|(_, color)| color == self)
+            .map(|(keyword, _)| *keyword)
+            .unwrap_or_default()
+    }
+
+    /// Returns the color in the default (light) color scheme
+    pub fn to_rgba(&self) -> Rgba {
+        let (red, green, blue) = match self {
+            SystemColor::Canvas | SystemColor::Field | SystemColor::HighlightText => {
+                (255, 255, 255)
+            }
+            SystemColor::CanvasText
+            | SystemColor::FieldText
+            | SystemColor::ButtonText
+            | SystemColor::MarkText => (0, 0, 0),
+            SystemColor::AccentColor | SystemColor::SelectedItem | SystemColor::Highlight => {
+                (0, 117, 255)
+            }
+            SystemColor::AccentColorText | SystemColor::SelectedItemText => (255, 255, 255),
+            SystemColor::ActiveText => (255, 0, 0),
+            SystemColor::ButtonBorder => (118, 118, 118),
+            SystemColor::ButtonFace => (239, 239, 239),
+            SystemColor::GrayText => (128, 128, 128),
+            SystemColor::LinkText => (0, 0, 238),
+            SystemColor::Mark => (255, 255, 0),
+            SystemColor::VisitedText => (85, 26, 139),
+        };
+        Rgba::new(red as f32, green as f32, blue as f32, 1.0)
+    }
+}
+
+/// A specified color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    /// A color that serializes with the legacy `rgb()` syntax: named and hex colors, `rgb()`,
+    /// `hsl()` and `hwb()`
+    Rgba(Rgba),
+    /// `lab()`, `lch()`, `oklab()`, `oklch()` and `color()`
+    Absolute(AbsoluteColor),
+    /// `currentcolor`: the value of the `color` property
+    CurrentColor,
+    System(SystemColor),
+}
+
+impl Color {
+    pub fn rgb(red: u8, green: u8, blue: u8) -> Color {
+        Color::Rgba(Rgba::new(red as f32, green as f32, blue as f32, 1.0))
+    }
+
+    /// Parses a color from CSS text, like `#fff` or `lab(50% 10 20)`
+    pub fn parse(css: &str) -> Option<Color> {
+        let values = CSS3Parser::new().parse_value_list(css).ok()?;
+        match values.children.as_slice() {
+            [value] => Color::from_value(value),
+            _ => None,
+        }
+    }
+
+    /// Parses a color from a single component value: a hex color, a color keyword or a color
+    /// function
+    pub fn from_value(value: &Value) -> Option<Color> {
+        match value {
+            Value::Hash(hash) => parse_hex(&hash.value),
+            Value::Identifier(ident) => named_color(&ident.name),
+            Value::Function(function) => parse_function(function),
+            _ => None,
+        }
+    }
+
+    /// Returns the color in sRGB. `currentcolor` depends on the element, so it returns `None`.
+    pub fn to_rgba(&self) -> Option<Rgba> {
+        match self {
+            Color::Rgba(rgba) => Some(*rgba),
+            Color::Absolute(color) => Some(color.to_rgba()),
+            Color::CurrentColor => None,
+            Color::System(color) => Some(color.to_rgba()),
+        }
+    }
+
+    /// Converts the color to the given color space
+    pub fn to_space(&self, space: ColorSpace) -> Option<AbsoluteColor> {
+        match self {
+            Color::Absolute(color) => Some(color.to_space(space)),
+            _ => Some(self.to_rgba()?.to_absolute().to_space(space)),
+        }
+    }
+}
+
+/// Serializes the color as in [CSS Color 4](https://www.w3.org/TR/css-color-4/#serializing-color-values)
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::Rgba(rgba) => write_legacy(f, rgba),
+            Color::Absolute(color) => match color.space {
+                ColorSpace::Lab | ColorSpace::Lch | ColorSpace::Oklab | ColorSpace::Oklch => {
+                    write!(f, "{}(", color.space.name())?;
+                    write_components(f, color)?;
+                    write!(f, ")")
+                }
+                ColorSpace::Hsl | ColorSpace::Hwb => write_legacy(f, &color.to_rgba()),
+                _ => {
+                    write!(f, "color({} ", color.space.name())?;
+                    write_components(f, color)?;
+                    write!(f, ")")
+                }
+            },
+            Color::CurrentColor => write!(f, "currentcolor"),
+            Color::System(color) => write!(f, "{}", color.name()),
+        }
+    }
+}
+
+/// Writes `rgb(r, g, b)` or `rgba(r, g, b, a)`
+fn write_legacy(f: &mut Formatter<'_>, rgba: &Rgba) -> fmt::Result {
+    let channel = |value: f32| value.clamp(0.0, 255.0).round();
+    let (red, green, blue) = (channel(rgba.red), channel(rgba.green), channel(rgba.blue));
+    if rgba.alpha >= 1.0 {
+        write!(f, "rgb({}, {}, {})", red, green, blue)
+    } else {
+        let alpha = round_alpha(rgba.alpha.max(0.0));
+        write!(f, "rgba({}, {}, {}, {})", red, green, blue, alpha)
+    }
+}
+
+/// Writes the components and the alpha of a modern color function, like `50 10 20 / 0.5`
+fn write_components(f: &mut Formatter<'_>, color: &AbsoluteColor) -> fmt::Result {
+    let number = |value: Option<f32>| match value {
+        // Adding zero turns -0 into 0
+        Some(value) => (value + 0.0).to_string(),
+        None => "none".to_string(),
+    };
+    let [first, second, third] = color.components.map(number);
+    write!(f, "{} {} {}", first, second, third)?;
+    if color.alpha != Some(1.0) {
+        write!(f, " / {}", number(color.alpha))?;
+    }
+    Ok(())
+}
+
+/// Rounds the alpha to the shortest number that survives a round trip through 8 bits
+fn round_alpha(alpha: f32) -> f32 {
+    let to_byte = |alpha: f32| (alpha * 255.0).round();
+    let rounded = (alpha * 100.0).round() / 100.0;
+    if to_byte(rounded) == to_byte(alpha) {
+        return rounded;
+    }
+    (alpha * 1000.0).round() / 1000.0
+}
+
+/// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa` (without the `#`)
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |idx: usize| u8::from_str_radix(&hex[idx..idx + 1], 16).ok();
+    let pair = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+
+    let (red, green, blue, alpha) = match hex.len() {
+        3 | 4 => {
+            let alpha = if hex.len() == 4 { digit(3)? * 17 } else { 255 };
+            (digit(0)? * 17, digit(1)? * 17, digit(2)? * 17, alpha)
+        }
+        6 | 8 => {
+            let alpha = if hex.len() == 8 { pair(6)? } else { 255 };
+            (pair(0)?, pair(2)?, pair(4)?, alpha)
+        }
+        _ => return None,
+    };
+    Some(Color::Rgba(Rgba::new(
+        red as f32,
+        green as f32,
+        blue as f32,
+        alpha as f32 / 255.0,
+    )))
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    let name = name.to_ascii_lowercase();
+    match name.as_str() {
+        "transparent" => Some(Color::Rgba(Rgba::new(0.0, 0.0, 0.0, 0.0))),
+        "currentcolor" => Some(Color::CurrentColor),
+        _ => match NAMED_COLORS.get(name.as_str()) {
+            Some((red, green, blue)) => Some(Color::rgb(*red, *green, *blue)),
+            None => SystemColor::from_name(&name).map(Color::System),
+        },
+    }
+}
+
+/// A component of a color function. `none` is NaN once resolved.
+#[derive(Debug, Clone, Copy)]
+enum Component {
+    Number(f64),
+    Percentage(f64),
+    /// An angle, in degrees
+    Angle(f64),
+    None,
+}
+
+impl Component {
+    fn parse(value: &Value) -> Option<Component> {
+        let component = match value {
+            Value::Dimension(dimension) => {
+                let number = dimension.value.parse::<f64>().ok()?;
+                match dimension
+                    .unit
+                    .as_deref()
+                    .map(str::to_ascii_lowercase)
+                    .as_deref()
+                {
+                    None => Component::Number(number),
+                    Some("deg") => Component::Angle(number),
+                    Some("grad") => Component::Angle(number * 0.9),
+                    Some("rad") => Component::Angle(number.to_degrees()),
+                    Some("turn") => Component::Angle(number * 360.0),
+                    Some(_) => return None,
+                }
+            }
+            Value::Percentage(percentage) => Component::Percentage(percentage.value.parse().ok()?),
+            Value::Identifier(ident) if ident.name.eq_ignore_ascii_case("none") => Component::None,
+            Value::Function(function) => {
+                let node = CalcNode::parse(function)?;
+                let value = node.evaluate(&|_| 0.0, 100.0) as f64;
+                match node.calc_type() {
+                    CalcType::Number => Component::Number(value),
+                    CalcType::Percentage => Component::Percentage(value),
+                    CalcType::Angle => Component::Angle(value),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        Some(component)
+    }
+
+    /// Resolves a number, or a percentage where 100% is `reference`
+    fn number(self, reference: f64) -> Option<f64> {
+        match self {
+            Component::Number(number) => Some(number),
+            Component::Percentage(percentage) => Some(percentage / 100.0 * reference),
+            Component::Angle(_) => None,
+            Component::None => Some(f64::NAN),
+        }
+    }
+
+    /// Resolves a hue in degrees, from a number or an angle
+    fn hue(self) -> Option<f64> {
+        match self {
+            Component::Number(degrees) | Component::Angle(degrees) => {
+                Some(convert::normalize_hue(degrees))
+            }
+            Component::Percentage(_) => None,
+            Component::None => Some(f64::NAN),
+        }
+    }
+}
+
+/// The arguments of a color function
+struct Arguments {
+    components: Vec<Component>,
+    alpha: Option<Component>,
+    /// Whether the arguments are comma-separated
+    legacy: bool,
+}
+
+impl Arguments {
+    /// Splits the arguments into components and alpha. Commas are only accepted when
+    /// `allow_legacy` is set, and then `none` is not.
+    fn parse(values: &[Value], allow_legacy: bool) -> Option<Arguments> {
+        let is_operator = |value: &Value, operator: &str| matches!(value, Value::Operator(op) if op.value == operator);
+
+        let legacy = values.iter().any(|value| is_operator(value, ","));
+        let (components, alpha) = if legacy {
+            // r, g, b[, a]
+            let is_separated = values
+                .iter()
+                .enumerate()
+                .all(|(idx, value)| is_operator(value, ",") == (idx % 2 == 1));
+            if !allow_legacy || !is_separated || values.len() % 2 == 0 {
+                return None;
+            }
+            let parts: Vec<&Value> = values.iter().step_by(2).collect();
+            match parts.as_slice() {
+                [first, second, third] => (vec![*first, *second, *third], None),
+                [first, second, third, alpha] => (vec![*first, *second, *third], Some(*alpha)),
+                _ => return None,
+            }
+        } else {
+            // r g b[ / a]
+            match values.iter().position(|value| is_operator(value, "/")) {
+                Some(slash) if slash + 2 == values.len() => {
+                    (values[..slash].iter().collect(), values.last())
+                }
+                Some(_) => return None,
+                None => (values.iter().collect(), None),
+            }
+        };
+
+        let components = components
+            .into_iter()
+            .map(Component::parse)
+            .collect::<Option<Vec<_>>>()?;
+        let alpha = match alpha {
+            Some(alpha) => Some(Component::parse(alpha)?),
+            None => None,
+        };
+        let has_none = components
+            .iter()
+            .chain(alpha.iter())
+            .any(|component| matches!(component, Component::None));
+        if legacy && has_none {
+            return None;
+        }
+        Some(Arguments {
+            components,
+            alpha,
+            legacy,
+        })
+    }
+
+    /// Returns the three components, if there are three
+    fn three(&self) -> Option<[Component; 3]> {
+        self.components.as_slice().try_into().ok()
+    }
+
+    /// Returns the alpha from 0 to 1, where a missing alpha is NaN
+    fn alpha(&self) -> Option<f64> {
+        match self.alpha {
+            None => Some(1.0),
+            Some(alpha) => Some(alpha.number(1.0)?.clamp(0.0, 1.0)),
+        }
+    }
+}
+
+fn parse_function(function: &Function) -> Option<Color> {
+    let values = &function.children.children;
+    match function.name.to_ascii_lowercase().as_str() {
+        "rgb" | "rgba" => parse_rgb(&Arguments::parse(values, true)?),
+        "hsl" | "hsla" => parse_hsl(&Arguments::parse(values, true)?),
+        "hwb" => parse_hwb(&Arguments::parse(values, false)?),
+        "lab" => parse_lab(ColorSpace::Lab, &Arguments::parse(values, false)?),
+        "lch" => parse_lab(ColorSpace::Lch, &Arguments::parse(values, false)?),
+        "oklab" => parse_lab(ColorSpace::Oklab, &Arguments::parse(values, false)?),
+        "oklch" => parse_lab(ColorSpace::Oklch, &Arguments::parse(values, false)?),
+        "color" => parse_color_function(values),
+        _ => None,
+    }
+}
+
+/// Creates a legacy color from sRGB components (0..1), where missing components are zero
+fn legacy_color(rgb: [f64; 3], alpha: f64) -> Color {
+    let channel = |value: f64| {
+        let value = if value.is_nan() { 0.0 } else { value };
+        (value * 255.0).clamp(0.0, 255.0) as f32
+    };
+    let alpha = if alpha.is_nan() { 0.0 } else { alpha };
+    Color::Rgba(Rgba::new(
+        channel(rgb[0]),
+        channel(rgb[1]),
+        channel(rgb[2]),
+        alpha as f32,
+    ))
+}
+
+/// Replaces a missing component with zero
+fn or_zero(value: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value
+    }
+}
+
+/// `rgb()`: numbers from 0 to 255 or percentages. The legacy syntax can not mix them.
+fn parse_rgb(args: &Arguments) -> Option<Color> {
+    let channels = args.three()?;
+    if args.legacy {
+        let is_percentage = |c: &Component| matches!(c, Component::Percentage(_));
+        if channels.iter().any(is_percentage) && !channels.iter().all(is_percentage) {
+            return None;
+        }
+    }
+    let mut rgb = [0.0; 3];
+    for (idx, channel) in channels.iter().enumerate() {
+        rgb[idx] = channel.number(255.0)? / 255.0;
+    }
+    Some(legacy_color(rgb, args.alpha()?))
+}
+
+/// `hsl()`: saturation and lightness are percentages, or numbers in the modern syntax
+fn parse_hsl(args: &Arguments) -> Option<Color> {
+    let [hue, saturation, lightness] = args.three()?;
+    if args.legacy
+        && !matches!(
+            (saturation, lightness),
+            (Component::Percentage(_), Component::Percentage(_))
+        )
+    {
+        return None;
+    }
+    let hsl = [
+        or_zero(hue.hue()?),
+        or_zero(saturation.number(100.0)?).max(0.0),
+        or_zero(lightness.number(100.0)?).clamp(0.0, 100.0),
+    ];
+    Some(legacy_color(convert::hsl_to_srgb(hsl), args.alpha()?))
+}
+
+fn parse_hwb(args: &Arguments) -> Option<Color> {
+    let [hue, whiteness, blackness] = args.three()?;
+    let hwb = [
+        or_zero(hue.hue()?),
+        or_zero(whiteness.number(100.0)?).clamp(0.0, 100.0),
+        or_zero(blackness.number(100.0)?).clamp(0.0, 100.0),
+    ];
+    Some(legacy_color(convert::hwb_to_srgb(hwb), args.alpha()?))
+}
+
+/// `lab()`, `lch()`, `oklab()` and `oklch()`
+fn parse_lab(space: ColorSpace, args: &Arguments) -> Option<Color> {
+    let [lightness, second, third] = args.three()?;
+    // The values of 100% for lightness and for the other components
+    let (max_lightness, reference) = match space {
+        ColorSpace::Lab => (100.0, 125.0),
+        ColorSpace::Lch => (100.0, 150.0),
+        _ => (1.0, 0.4),
+    };
+    let is_polar = matches!(space, ColorSpace::Lch | ColorSpace::Oklch);
+
+    // NaN (`none`) stays NaN when clamped, where `max` would turn it into zero
+    let lightness = lightness.number(max_lightness)?.clamp(0.0, max_lightness);
+    let (second, third) = if is_polar {
+        (
+            second.number(reference)?.clamp(0.0, f64::INFINITY),
+            third.hue()?,
+        )
+    } else {
+        (second.number(reference)?, third.number(reference)?)
+    };
+    Some(Color::Absolute(AbsoluteColor::with_missing(
+        space,
+        [lightness, second, third],
+        args.alpha()?,
+    )))
+}
+
+/// `color(<space> c1 c2 c3 [/ alpha])`
+fn parse_color_function(values: &[Value]) -> Option<Color> {
+    let (Value::Identifier(name), rest) = values.split_first()? else {
+        return None;
+    };
+    let space = ColorSpace::from_name(&name.name)?;
+    let args = Arguments::parse(rest, false)?;
+    let [first, second, third] = args.three()?;
+    Some(Color::Absolute(AbsoluteColor::with_missing(
+        space,
+        [first.number(1.0)?, second.number(1.0)?, third.number(1.0)?],
+        args.alpha()?,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(css: &str) -> Option<String> {
+        Color::parse(css).map(|color| color.to_string())
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(color("#f00").as_deref(), Some("rgb(255, 0, 0)"));
+        assert_eq!(color("#FF000080").as_deref(), Some("rgba(255, 0, 0, 0.5)"));
+        assert_eq!(color("#0f08").as_deref(), Some("rgba(0, 255, 0, 0.533)"));
+        assert_eq!(color("RebeccaPurple").as_deref(), Some("rgb(102, 51, 153)"));
+        assert_eq!(color("transparent").as_deref(), Some("rgba(0, 0, 0, 0)"));
+        assert_eq!(color("currentColor").as_deref(), Some("currentcolor"));
+        assert_eq!(color("CanvasText").as_deref(), Some("canvastext"));
+        assert_eq!(color("rgb(1, 2, 3)").as_deref(), Some("rgb(1, 2, 3)"));
+        assert_eq!(
+            color("rgba(100%, 0%, 50%, .25)").as_deref(),
+            Some("rgba(255, 0, 128, 0.25)")
+        );
+        assert_eq!(
+            color("rgb(1 2 300 / 10%)").as_deref(),
+            Some("rgba(1, 2, 255, 0.1)")
+        );
+        assert_eq!(
+            color("rgb(none 50% 10)").as_deref(),
+            Some("rgb(0, 128, 10)")
+        );
+        assert_eq!(
+            color("hsl(120deg 100% 25%)").as_deref(),
+            Some("rgb(0, 128, 0)")
+        );
+        assert_eq!(
+            color("hwb(0 20% 20% / 0.5)").as_deref(),
+            Some("rgba(204, 51, 51, 0.5)")
+        );
+        assert_eq!(
+            color("rgb(calc(255 / 2) 0 0)").as_deref(),
+            Some("rgb(128, 0, 0)")
+        );
+
+        assert_eq!(color("#ff"), None);
+        assert_eq!(color("#ggg"), None);
+        assert_eq!(color("notacolor"), None);
+        assert_eq!(color("rgb(1, 2)"), None);
+        assert_eq!(color("rgb(1, 2 3)"), None);
+        assert_eq!(color("rgb(1 2 3 / )"), None);
+        assert_eq!(color("rgb(1%, 2, 3)"), None);
+        assert_eq!(color("rgb(none, 2, 3)"), None);
+        assert_eq!(color("hsl(120, 100, 50)"), None);
+        assert_eq!(color("hwb(0, 20%, 20%)"), None);
+    }
+
+    #[test]
+    fn absolute_colors() {
+        assert_eq!(color("lab(50% 40 -20)").as_deref(), Some("lab(50 40 -20)"));
+        assert_eq!(
+            color("LCH(120 50% 400deg / 25%)").as_deref(),
+            Some("lch(100 75 40 / 0.25)")
+        );
+        assert_eq!(
+            color("oklab(50% 100% none)").as_deref(),
+            Some("oklab(0.5 0.4 none)")
+        );
+        assert_eq!(
+            color("oklch(0.5 -1 0.5turn)").as_deref(),
+            Some("oklch(0.5 0 180)")
+        );
+        assert_eq!(
+            color("color(display-p3 1 50% 0)").as_deref(),
+            Some("color(display-p3 1 0.5 0)")
+        );
+        assert_eq!(
+            color("color(xyz 0.5 0.5 0.5 / none)").as_deref(),
+            Some("color(xyz-d65 0.5 0.5 0.5 / none)")
+        );
+
+        assert_eq!(color("lab(50, 40, -20)"), None);
+        assert_eq!(color("lch(50 40 20%)"), None);
+        assert_eq!(color("color(unknown 1 1 1)"), None);
+        assert_eq!(color("color(srgb 1 1)"), None);
+    }
+
+    #[test]
+    fn conversions() {
+        let red = Color::parse("red").unwrap();
+        let lab = red.to_space(ColorSpace::Lab).unwrap();
+        let lightness = lab.components[0].unwrap();
+        assert!((lightness - 54.29).abs() < 0.01);
+
+        let rgba = Color::parse("oklch(62.8% 0.2577 29.23)")
+            .unwrap()
+            .to_rgba()
+            .unwrap();
+        assert_eq!(Color::Rgba(rgba).to_string(), "rgb(255, 0, 0)");
+
+        let p3 = Color::parse("color(display-p3 1 0 0)").unwrap();
+        assert_eq!(
+            Color::Rgba(p3.to_rgba().unwrap()).to_string(),
+            "rgb(255, 0, 0)"
+        );
+        assert_eq!(Color::CurrentColor.to_rgba(), None);
+    }
+}
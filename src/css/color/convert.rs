@@ -0,0 +1,533 @@
+//! Conversions between color spaces, following the
+//! [sample code](https://www.w3.org/TR/css-color-4/#color-conversion-code) of CSS Color 4
+//!
+//! Every space converts to and from XYZ with a D65 white point. A missing component (`none`) is
+//! NaN and is treated as zero.
+use super::ColorSpace;
+
+type Matrix = [[f64; 3]; 3];
+
+const SRGB_TO_XYZ: Matrix = [
+    [506752.0 / 1228815.0, 87881.0 / 245763.0, 12673.0 / 70218.0],
+    [87098.0 / 409605.0, 175762.0 / 245763.0, 12673.0 / 175545.0],
+    [7918.0 / 409605.0, 87881.0 / 737289.0, 1001167.0 / 1053270.0],
+];
+
+const XYZ_TO_SRGB: Matrix = [
+    [12831.0 / 3959.0, -329.0 / 214.0, -1974.0 / 3959.0],
+    [
+        -851781.0 / 878810.0,
+        1648619.0 / 878810.0,
+        36519.0 / 878810.0,
+    ],
+    [705.0 / 12673.0, -2585.0 / 12673.0, 705.0 / 667.0],
+];
+
+const P3_TO_XYZ: Matrix = [
+    [
+        608311.0 / 1250200.0,
+        189793.0 / 714400.0,
+        198249.0 / 1000160.0,
+    ],
+    [
+        35783.0 / 156275.0,
+        247089.0 / 357200.0,
+        198249.0 / 2500400.0,
+    ],
+    [0.0, 32229.0 / 714400.0, 5220557.0 / 5000800.0],
+];
+
+const XYZ_TO_P3: Matrix = [
+    [
+        446124.0 / 178915.0,
+        -333277.0 / 357830.0,
+        -72051.0 / 178915.0,
+    ],
+    [-14852.0 / 17905.0, 63121.0 / 35810.0, 423.0 / 17905.0],
+    [11844.0 / 330415.0, -50337.0 / 660830.0, 316169.0 / 330415.0],
+];
+
+const A98_TO_XYZ: Matrix = [
+    [
+        573536.0 / 994567.0,
+        263643.0 / 1420810.0,
+        187206.0 / 994567.0,
+    ],
+    [
+        591459.0 / 1989134.0,
+        6239551.0 / 9945670.0,
+        374412.0 / 4972835.0,
+    ],
+    [
+        53769.0 / 1989134.0,
+        351524.0 / 4972835.0,
+        4929758.0 / 4972835.0,
+    ],
+];
+
+const XYZ_TO_A98: Matrix = [
+    [
+        1829569.0 / 896150.0,
+        -506331.0 / 896150.0,
+        -308931.0 / 896150.0,
+    ],
+    [
+        -851781.0 / 878810.0,
+        1648619.0 / 878810.0,
+        36519.0 / 878810.0,
+    ],
+    [
+        16779.0 / 1248040.0,
+        -147721.0 / 1248040.0,
+        1266979.0 / 1248040.0,
+    ],
+];
+
+/// ProPhoto RGB has a D50 white point
+const PROPHOTO_TO_XYZ_D50: Matrix = [
+    [0.7977666449006423, 0.13518129740053308, 0.0313477341283922],
+    [0.2880748288194013, 0.711835234241873, 0.00008993693872564],
+    [0.0, 0.0, 0.8251046025104602],
+];
+
+const XYZ_D50_TO_PROPHOTO: Matrix = [
+    [
+        1.3457868816471583,
+        -0.25557208737979464,
+        -0.05110186497554526,
+    ],
+    [-0.5446307051249019, 1.5082477428451468, 0.02052744743642139],
+    [0.0, 0.0, 1.2119675456389452],
+];
+
+const REC2020_TO_XYZ: Matrix = [
+    [
+        63426534.0 / 99577255.0,
+        20160776.0 / 139408157.0,
+        47086771.0 / 278816314.0,
+    ],
+    [
+        26158966.0 / 99577255.0,
+        472592308.0 / 697040785.0,
+        8267143.0 / 139408157.0,
+    ],
+    [0.0, 19567812.0 / 697040785.0, 295819943.0 / 278816314.0],
+];
+
+const XYZ_TO_REC2020: Matrix = [
+    [
+        30757411.0 / 17917100.0,
+        -6372589.0 / 17917100.0,
+        -4539589.0 / 17917100.0,
+    ],
+    [
+        -19765991.0 / 29648200.0,
+        47925759.0 / 29648200.0,
+        467509.0 / 29648200.0,
+    ],
+    [
+        792561.0 / 44930125.0,
+        -1921689.0 / 44930125.0,
+        42328811.0 / 44930125.0,
+    ],
+];
+
+/// Bradford chromatic adaptation
+const D65_TO_D50: Matrix = [
+    [
+        1.0479297925449969,
+        0.022946870601609652,
+        -0.05019226628920524,
+    ],
+    [
+        0.02962780877005599,
+        0.9904344267538799,
+        -0.017073799063418826,
+    ],
+    [
+        -0.009243040646204504,
+        0.015055191490298152,
+        0.7518742814281371,
+    ],
+];
+
+const D50_TO_D65: Matrix = [
+    [0.955473421488075, -0.02309845494876471, 0.06325924320057072],
+    [
+        -0.0283697093338637,
+        1.0099953980813041,
+        0.021041441191917323,
+    ],
+    [
+        0.012314014864481998,
+        -0.020507649298898964,
+        1.330365926242124,
+    ],
+];
+
+const XYZ_TO_LMS: Matrix = [
+    [0.819022437996703, 0.3619062600528904, -0.1288737815209879],
+    [0.0329836539323885, 0.9292868615863434, 0.0361446663506424],
+    [0.0481771893596242, 0.2642395317527308, 0.6335478284694309],
+];
+
+const LMS_TO_OKLAB: Matrix = [
+    [0.210454268309314, 0.7936177747023054, -0.0040720430116193],
+    [1.9779985324311684, -2.42859224204858, 0.450593709617411],
+    [0.0259040424655478, 0.7827717124575296, -0.8086757549230774],
+];
+
+const OKLAB_TO_LMS: Matrix = [
+    [1.0, 0.3963377773761749, 0.2158037573099136],
+    [1.0, -0.1055613458156586, -0.0638541728258133],
+    [1.0, -0.0894841775298119, -1.2914855480194092],
+];
+
+const LMS_TO_XYZ: Matrix = [
+    [1.2268798758459243, -0.5578149944602171, 0.2813910456659647],
+    [-0.0405757452148008, 1.112286803280317, -0.0717110580655164],
+    [-0.0763729366746601, -0.4214933324022432, 1.5869240198367816],
+];
+
+const D50_WHITE: [f64; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+/// Converts the components from one space to another
+pub(super) fn convert(from: ColorSpace, to: ColorSpace, components: [f64; 3]) -> [f64; 3] {
+    let components = components.map(|component| if component.is_nan() { 0.0 } else { component });
+    if from == to {
+        return components;
+    }
+    from_xyz_d65(to, to_xyz_d65(from, components))
+}
+
+fn to_xyz_d65(space: ColorSpace, c: [f64; 3]) -> [f64; 3] {
+    match space {
+        ColorSpace::Srgb => multiply(&SRGB_TO_XYZ, c.map(srgb_to_linear)),
+        ColorSpace::SrgbLinear => multiply(&SRGB_TO_XYZ, c),
+        ColorSpace::DisplayP3 => multiply(&P3_TO_XYZ, c.map(srgb_to_linear)),
+        ColorSpace::A98Rgb => multiply(&A98_TO_XYZ, c.map(a98_to_linear)),
+        ColorSpace::ProphotoRgb => multiply(
+            &D50_TO_D65,
+            multiply(&PROPHOTO_TO_XYZ_D50, c.map(prophoto_to_linear)),
+        ),
+        ColorSpace::Rec2020 => multiply(&REC2020_TO_XYZ, c.map(rec2020_to_linear)),
+        ColorSpace::XyzD50 => multiply(&D50_TO_D65, c),
+        ColorSpace::XyzD65 => c,
+        ColorSpace::Lab => multiply(&D50_TO_D65, lab_to_xyz_d50(c)),
+        ColorSpace::Lch => multiply(&D50_TO_D65, lab_to_xyz_d50(lch_to_lab(c))),
+        ColorSpace::Oklab => oklab_to_xyz(c),
+        ColorSpace::Oklch => oklab_to_xyz(lch_to_lab(c)),
+        ColorSpace::Hsl => to_xyz_d65(ColorSpace::Srgb, hsl_to_srgb(c)),
+        ColorSpace::Hwb => to_xyz_d65(ColorSpace::Srgb, hwb_to_srgb(c)),
+    }
+}
+
+fn from_xyz_d65(space: ColorSpace, xyz: [f64; 3]) -> [f64; 3] {
+    match space {
+        ColorSpace::Srgb => multiply(&XYZ_TO_SRGB, xyz).map(srgb_from_linear),
+        ColorSpace::SrgbLinear => multiply(&XYZ_TO_SRGB, xyz),
+        ColorSpace::DisplayP3 => multiply(&XYZ_TO_P3, xyz).map(srgb_from_linear),
+        ColorSpace::A98Rgb => multiply(&XYZ_TO_A98, xyz).map(a98_from_linear),
+        ColorSpace::ProphotoRgb => {
+            multiply(&XYZ_D50_TO_PROPHOTO, multiply(&D65_TO_D50, xyz)).map(prophoto_from_linear)
+        }
+        ColorSpace::Rec2020 => multiply(&XYZ_TO_REC2020, xyz).map(rec2020_from_linear),
+        ColorSpace::XyzD50 => multiply(&D65_TO_D50, xyz),
+        ColorSpace::XyzD65 => xyz,
+        ColorSpace::Lab => xyz_d50_to_lab(multiply(&D65_TO_D50, xyz)),
+        ColorSpace::Lch => lab_to_lch(xyz_d50_to_lab(multiply(&D65_TO_D50, xyz))),
+        ColorSpace::Oklab => xyz_to_oklab(xyz),
+        ColorSpace::Oklch => lab_to_lch(xyz_to_oklab(xyz)),
+        ColorSpace::Hsl => srgb_to_hsl(from_xyz_d65(ColorSpace::Srgb, xyz)),
+        ColorSpace::Hwb => srgb_to_hwb(from_xyz_d65(ColorSpace::Srgb, xyz)),
+    }
+}
+
+fn multiply(matrix: &Matrix, vector: [f64; 3]) -> [f64; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+/// Applies `f` to the absolute value and keeps the sign, for components outside of 0..1
+fn signed(value: f64, f: impl Fn(f64) -> f64) -> f64 {
+    value.signum() * f(value.abs())
+}
+
+fn srgb_to_linear(value: f64) -> f64 {
+    signed(value, |abs| {
+        if abs <= 0.04045 {
+            abs / 12.92
+        } else {
+            ((abs + 0.055) / 1.055).powf(2.4)
+        }
+    })
+}
+
+fn srgb_from_linear(value: f64) -> f64 {
+    signed(value, |abs| {
+        if abs > 0.0031308 {
+            1.055 * abs.powf(1.0 / 2.4) - 0.055
+        } else {
+            12.92 * abs
+        }
+    })
+}
+
+fn a98_to_linear(value: f64) -> f64 {
+    signed(value, |abs| abs.powf(563.0 / 256.0))
+}
+
+fn a98_from_linear(value: f64) -> f64 {
+    signed(value, |abs| abs.powf(256.0 / 563.0))
+}
+
+fn prophoto_to_linear(value: f64) -> f64 {
+    signed(value, |abs| {
+        if abs <= 16.0 / 512.0 {
+            abs / 16.0
+        } else {
+            abs.powf(1.8)
+        }
+    })
+}
+
+fn prophoto_from_linear(value: f64) -> f64 {
+    signed(value, |abs| {
+        if abs >= 1.0 / 512.0 {
+            abs.powf(1.0 / 1.8)
+        } else {
+            16.0 * abs
+        }
+    })
+}
+
+const REC2020_ALPHA: f64 = 1.09929682680944;
+const REC2020_BETA: f64 = 0.018053968510807;
+
+fn rec2020_to_linear(value: f64) -> f64 {
+    signed(value, |abs| {
+        if abs < REC2020_BETA * 4.5 {
+            abs / 4.5
+        } else {
+            ((abs + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45)
+        }
+    })
+}
+
+fn rec2020_from_linear(value: f64) -> f64 {
+    signed(value, |abs| {
+        if abs > REC2020_BETA {
+            REC2020_ALPHA * abs.powf(0.45) - (REC2020_ALPHA - 1.0)
+        } else {
+            4.5 * abs
+        }
+    })
+}
+
+const EPSILON: f64 = 216.0 / 24389.0;
+const KAPPA: f64 = 24389.0 / 27.0;
+
+fn xyz_d50_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let f = [0, 1, 2].map(|idx| {
+        let value = xyz[idx] / D50_WHITE[idx];
+        if value > EPSILON {
+            value.cbrt()
+        } else {
+            (KAPPA * value + 16.0) / 116.0
+        }
+    });
+    [
+        116.0 * f[1] - 16.0,
+        500.0 * (f[0] - f[1]),
+        200.0 * (f[1] - f[2]),
+    ]
+}
+
+fn lab_to_xyz_d50([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let f1 = (lightness + 16.0) / 116.0;
+    let f0 = a / 500.0 + f1;
+    let f2 = f1 - b / 200.0;
+    let inverse = |f: f64| {
+        if f.powi(3) > EPSILON {
+            f.powi(3)
+        } else {
+            (116.0 * f - 16.0) / KAPPA
+        }
+    };
+    let y = if lightness > KAPPA * EPSILON {
+        f1.powi(3)
+    } else {
+        lightness / KAPPA
+    };
+    [
+        inverse(f0) * D50_WHITE[0],
+        y * D50_WHITE[1],
+        inverse(f2) * D50_WHITE[2],
+    ]
+}
+
+fn lab_to_lch([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let hue = b.atan2(a).to_degrees();
+    [lightness, (a * a + b * b).sqrt(), normalize_hue(hue)]
+}
+
+fn lch_to_lab([lightness, chroma, hue]: [f64; 3]) -> [f64; 3] {
+    let hue = hue.to_radians();
+    [lightness, chroma * hue.cos(), chroma * hue.sin()]
+}
+
+fn xyz_to_oklab(xyz: [f64; 3]) -> [f64; 3] {
+    multiply(&LMS_TO_OKLAB, multiply(&XYZ_TO_LMS, xyz).map(f64::cbrt))
+}
+
+fn oklab_to_xyz(oklab: [f64; 3]) -> [f64; 3] {
+    multiply(
+        &LMS_TO_XYZ,
+        multiply(&OKLAB_TO_LMS, oklab).map(|c| c.powi(3)),
+    )
+}
+
+/// Returns the hue in 0..360 degrees
+pub(super) fn normalize_hue(hue: f64) -> f64 {
+    let hue = hue % 360.0;
+    if hue < 0.0 {
+        hue + 360.0
+    } else {
+        hue
+    }
+}
+
+/// Converts hue, saturation and lightness (0..100) to sRGB (0..1)
+pub(super) fn hsl_to_srgb([hue, saturation, lightness]: [f64; 3]) -> [f64; 3] {
+    let hue = normalize_hue(hue);
+    let saturation = saturation / 100.0;
+    let lightness = lightness / 100.0;
+    let f = |n: f64| {
+        let k = (n + hue / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [f(0.0), f(8.0), f(4.0)]
+}
+
+fn srgb_to_hsl([red, green, blue]: [f64; 3]) -> [f64; 3] {
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let lightness = (min + max) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return [0.0, 0.0, lightness * 100.0];
+    }
+
+    let saturation = if lightness == 0.0 || lightness == 1.0 {
+        0.0
+    } else {
+        (max - lightness) / lightness.min(1.0 - lightness)
+    };
+    let hue = if max == red {
+        (green - blue) / delta + if green < blue { 6.0 } else { 0.0 }
+    } else if max == green {
+        (blue - red) / delta + 2.0
+    } else {
+        (red - green) / delta + 4.0
+    };
+    [hue * 60.0, saturation * 100.0, lightness * 100.0]
+}
+
+/// Converts hue, whiteness and blackness (0..100) to sRGB (0..1)
+pub(super) fn hwb_to_srgb([hue, whiteness, blackness]: [f64; 3]) -> [f64; 3] {
+    let whiteness = whiteness / 100.0;
+    let blackness = blackness / 100.0;
+    if whiteness + blackness >= 1.0 {
+        let gray = whiteness / (whiteness + blackness);
+        return [gray; 3];
+    }
+    hsl_to_srgb([hue, 100.0, 50.0]).map(|c| c * (1.0 - whiteness - blackness) + whiteness)
+}
+
+fn srgb_to_hwb(rgb: [f64; 3]) -> [f64; 3] {
+    let [hue, _, _] = srgb_to_hsl(rgb);
+    let whiteness = rgb[0].min(rgb[1]).min(rgb[2]);
+    let blackness = 1.0 - rgb[0].max(rgb[1]).max(rgb[2]);
+    [hue, whiteness * 100.0, blackness * 100.0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: [f64; 3], expected: [f64; 3], tolerance: f64) {
+        for idx in 0..3 {
+            assert!(
+                (actual[idx] - expected[idx]).abs() <= tolerance,
+                "expected {:?}, got {:?}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn known_conversions() {
+        let red = [1.0, 0.0, 0.0];
+        let white = [1.0, 1.0, 1.0];
+        assert_close(
+            convert(ColorSpace::Srgb, ColorSpace::Lab, red),
+            [54.29, 80.80, 69.89],
+            0.01,
+        );
+        assert_close(
+            convert(ColorSpace::Srgb, ColorSpace::Oklch, red),
+            [0.628, 0.2577, 29.234],
+            0.001,
+        );
+        assert_close(
+            convert(ColorSpace::Srgb, ColorSpace::Lab, white),
+            [100.0, 0.0, 0.0],
+            0.001,
+        );
+        assert_close(
+            convert(ColorSpace::Srgb, ColorSpace::Oklab, white),
+            [1.0, 0.0, 0.0],
+            0.001,
+        );
+        assert_close(
+            convert(ColorSpace::Srgb, ColorSpace::XyzD65, white),
+            [0.9505, 1.0, 1.089],
+            0.001,
+        );
+        assert_close(
+            convert(ColorSpace::Srgb, ColorSpace::Hsl, [0.0, 0.5, 0.0]),
+            [120.0, 100.0, 25.0],
+            1e-9,
+        );
+        assert_close(
+            convert(ColorSpace::Hwb, ColorSpace::Srgb, [0.0, 60.0, 60.0]),
+            [0.5, 0.5, 0.5],
+            1e-9,
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let color = [0.2, 0.4, 0.6];
+        let spaces = [
+            ColorSpace::SrgbLinear,
+            ColorSpace::DisplayP3,
+            ColorSpace::A98Rgb,
+            ColorSpace::ProphotoRgb,
+            ColorSpace::Rec2020,
+            ColorSpace::XyzD50,
+            ColorSpace::XyzD65,
+            ColorSpace::Lab,
+            ColorSpace::Lch,
+            ColorSpace::Oklab,
+            ColorSpace::Oklch,
+            ColorSpace::Hsl,
+            ColorSpace::Hwb,
+        ];
+        for space in spaces {
+            let converted = convert(ColorSpace::Srgb, space, color);
+            assert_close(convert(space, ColorSpace::Srgb, converted), color, 1e-6);
+        }
+    }
+}
@@ -0,0 +1,542 @@
+//! Evaluation of [media queries](https://drafts.csswg.org/mediaqueries-4/)
+//!
+//! Media conditions use three-valued logic: a feature that is not known, or that has a value
+//! that is not valid for it, is unknown instead of false. `not` keeps an unknown result
+//! unknown, and a media query that ends up unknown does not match.
+use crate::css::node::{
+    AtRule, AtRulePreludeChild, AtRulePreludeValue, Condition, MediaCondition, MediaFeature,
+    MediaFeatureValue, MediaQuery, MediaQueryList,
+};
+use crate::css::parser::CSS3Parser;
+use crate::css::values::calc::{CalcNode, CalcType};
+use crate::css::values::{Length, LengthUnit};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+/// The result of evaluating a media condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchResult {
+    True,
+    False,
+    Unknown,
+}
+
+impl std::ops::Not for MatchResult {
+    type Output = MatchResult;
+
+    fn not(self) -> MatchResult {
+        match self {
+            MatchResult::True => MatchResult::False,
+            MatchResult::False => MatchResult::True,
+            MatchResult::Unknown => MatchResult::Unknown,
+        }
+    }
+}
+
+impl MatchResult {
+    pub fn and(self, other: MatchResult) -> MatchResult {
+        match (self, other) {
+            (MatchResult::False, _) | (_, MatchResult::False) => MatchResult::False,
+            (MatchResult::True, MatchResult::True) => MatchResult::True,
+            _ => MatchResult::Unknown,
+        }
+    }
+
+    pub fn or(self, other: MatchResult) -> MatchResult {
+        match (self, other) {
+            (MatchResult::True, _) | (_, MatchResult::True) => MatchResult::True,
+            (MatchResult::False, MatchResult::False) => MatchResult::False,
+            _ => MatchResult::Unknown,
+        }
+    }
+
+    /// Whether the result is true. Unknown counts as false.
+    pub fn is_true(self) -> bool {
+        self == MatchResult::True
+    }
+}
+
+impl From<bool> for MatchResult {
+    fn from(value: bool) -> MatchResult {
+        if value {
+            MatchResult::True
+        } else {
+            MatchResult::False
+        }
+    }
+}
+
+/// The environment that media queries are evaluated against
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaEnvironment {
+    /// The media type, like `screen` or `print`
+    pub media_type: String,
+    /// The width of the viewport, in CSS pixels
+    pub width: f32,
+    /// The height of the viewport, in CSS pixels
+    pub height: f32,
+    /// Device pixels per CSS pixel
+    pub resolution: f32,
+    pub color_scheme: ColorScheme,
+    /// Whether the user prefers reduced motion
+    pub reduced_motion: bool,
+    /// The initial font size in pixels, for `em` and `rem` in media queries
+    pub font_size: f32,
+}
+
+impl Default for MediaEnvironment {
+    fn default() -> Self {
+        Self {
+            media_type: "screen".to_string(),
+            width: 1280.0,
+            height: 720.0,
+            resolution: 1.0,
+            color_scheme: ColorScheme::Light,
+            reduced_motion: false,
+            font_size: 16.0,
+        }
+    }
+}
+
+/// The value of a media feature in the environment
+enum FeatureValue {
+    /// A length in pixels, a resolution in dppx, a ratio or an integer
+    Number(f32),
+    Keyword(&'static str),
+}
+
+/// How a media feature compares its value: range features accept `min-` and `max-` prefixes and
+/// the range syntax, discrete features only accept keywords
+#[derive(Clone, Copy, PartialEq)]
+enum FeatureType {
+    Length,
+    Ratio,
+    Resolution,
+    Integer,
+    Discrete,
+}
+
+impl MediaEnvironment {
+    /// Whether the media query list matches. An empty list matches all media.
+    pub fn matches(&self, media_query_list: &MediaQueryList) -> bool {
+        media_query_list.children.is_empty()
+            || media_query_list
+                .children
+                .iter()
+                .any(|query| self.matches_query(query))
+    }
+
+    pub fn matches_query(&self, query: &MediaQuery) -> bool {
+        let media_type = match query.media_type.as_deref() {
+            None | Some("all") => MatchResult::True,
+            Some(media_type) => MatchResult::from(media_type == self.media_type),
+        };
+        let condition = match &query.condition {
+            Some(condition) => self.evaluate(condition),
+            None => MatchResult::True,
+        };
+        let result = media_type.and(condition);
+        match query.modifier.as_deref() {
+            Some("not") => (!result).is_true(),
+            _ => result.is_true(),
+        }
+    }
+
+    /// Whether the `media` attribute of a `<link>` or `<style>` element matches. A missing
+    /// attribute matches all media.
+    pub fn matches_media_attribute(&self, media: Option<&str>) -> bool {
+        match media {
+            Some(media) => self.matches(&CSS3Parser::new().parse_media_query_list(media)),
+            None => true,
+        }
+    }
+
+    /// Whether the rules in the block of an `@media` rule apply, or whether an `@import` rule
+    /// imports its style sheet. Other at-rules always apply.
+    pub fn matches_at_rule(&self, rule: &AtRule) -> bool {
+        let AtRulePreludeValue::AtRulePrelude(prelude) = &rule.prelude else {
+            return true;
+        };
+        prelude.children.iter().all(|child| match child {
+            AtRulePreludeChild::MediaQueryList(media_query_list) => self.matches(media_query_list),
+            _ => true,
+        })
+    }
+
+    /// Evaluates a media condition
+    pub fn evaluate(&self, condition: &MediaCondition) -> MatchResult {
+        match condition {
+            Condition::Test(feature) => self.evaluate_feature(feature),
+            Condition::Not(condition) => !self.evaluate(condition),
+            Condition::And(conditions) => conditions
+                .iter()
+                .fold(MatchResult::True, |result, condition| {
+                    result.and(self.evaluate(condition))
+                }),
+            Condition::Or(conditions) => conditions
+                .iter()
+                .fold(MatchResult::False, |result, condition| {
+                    result.or(self.evaluate(condition))
+                }),
+            Condition::GeneralEnclosed(_) => MatchResult::Unknown,
+        }
+    }
+
+    /// Evaluates a media feature. Unknown features and invalid values are unknown.
+    pub fn evaluate_feature(&self, feature: &MediaFeature) -> MatchResult {
+        let (prefix, name) = match feature.name.split_once('-') {
+            Some((prefix @ ("min" | "max"), name)) => (Some(prefix), name),
+            _ => (None, feature.name.as_str()),
+        };
+        let Some((feature_type, actual)) = self.feature(name) else {
+            return MatchResult::Unknown;
+        };
+
+        match (&feature.value, feature.range.as_slice()) {
+            // Boolean context: (color)
+            (None, []) if prefix.is_none() => MatchResult::from(match actual {
+                FeatureValue::Number(number) => number != 0.0,
+                FeatureValue::Keyword(keyword) => !["none", "no-preference"].contains(&keyword),
+            }),
+            // Plain: (min-width: 600px) or (orientation: portrait)
+            (Some(value), []) => {
+                let operator = match prefix {
+                    Some("min") => ">=",
+                    Some("max") => "<=",
+                    _ => "=",
+                };
+                if prefix.is_some() && feature_type == FeatureType::Discrete {
+                    return MatchResult::Unknown;
+                }
+                self.compare(feature_type, &actual, operator, value)
+            }
+            // Range: (400px < width <= 700px)
+            (None, range) if prefix.is_none() && feature_type != FeatureType::Discrete => {
+                range.iter().fold(MatchResult::True, |result, comparison| {
+                    result.and(self.compare(
+                        feature_type,
+                        &actual,
+                        &comparison.operator,
+                        &comparison.value,
+                    ))
+                })
+            }
+            _ => MatchResult::Unknown,
+        }
+    }
+
+    /// Returns the type and the value of a known media feature
+    fn feature(&self, name: &str) -> Option<(FeatureType, FeatureValue)> {
+        let feature = match name {
+            "width" | "device-width" => (FeatureType::Length, FeatureValue::Number(self.width)),
+            "height" | "device-height" => (FeatureType::Length, FeatureValue::Number(self.height)),
+            "aspect-ratio" | "device-aspect-ratio" => (
+                FeatureType::Ratio,
+                FeatureValue::Number(self.width / self.height),
+            ),
+            "resolution" => (
+                FeatureType::Resolution,
+                FeatureValue::Number(self.resolution),
+            ),
+            "orientation" => (
+                FeatureType::Discrete,
+                FeatureValue::Keyword(if self.height >= self.width {
+                    "portrait"
+                } else {
+                    "landscape"
+                }),
+            ),
+            "prefers-color-scheme" => (
+                FeatureType::Discrete,
+                FeatureValue::Keyword(match self.color_scheme {
+                    ColorScheme::Light => "light",
+                    ColorScheme::Dark => "dark",
+                }),
+            ),
+            "prefers-reduced-motion" => (
+                FeatureType::Discrete,
+                FeatureValue::Keyword(if self.reduced_motion {
+                    "reduce"
+                } else {
+                    "no-preference"
+                }),
+            ),
+            // The rest describes a desktop screen (or a printer for `print`)
+            "color" => (FeatureType::Integer, FeatureValue::Number(8.0)),
+            "color-index" | "monochrome" | "grid" => {
+                (FeatureType::Integer, FeatureValue::Number(0.0))
+            }
+            "color-gamut" => (FeatureType::Discrete, FeatureValue::Keyword("srgb")),
+            "hover" | "any-hover" => (
+                FeatureType::Discrete,
+                FeatureValue::Keyword(if self.media_type == "print" {
+                    "none"
+                } else {
+                    "hover"
+                }),
+            ),
+            "pointer" | "any-pointer" => (
+                FeatureType::Discrete,
+                FeatureValue::Keyword(if self.media_type == "print" {
+                    "none"
+                } else {
+                    "fine"
+                }),
+            ),
+            "scan" => (FeatureType::Discrete, FeatureValue::Keyword("progressive")),
+            "update" => (
+                FeatureType::Discrete,
+                FeatureValue::Keyword(if self.media_type == "print" {
+                    "none"
+                } else {
+                    "fast"
+                }),
+            ),
+            "prefers-contrast" => (
+                FeatureType::Discrete,
+                FeatureValue::Keyword("no-preference"),
+            ),
+            "forced-colors" => (FeatureType::Discrete, FeatureValue::Keyword("none")),
+            _ => return None,
+        };
+        Some(feature)
+    }
+
+    /// Compares the value in the environment with the value in the query, as
+    /// `actual <operator> expected`
+    fn compare(
+        &self,
+        feature_type: FeatureType,
+        actual: &FeatureValue,
+        operator: &str,
+        expected: &MediaFeatureValue,
+    ) -> MatchResult {
+        let actual = match actual {
+            FeatureValue::Keyword(keyword) => {
+                return match expected {
+                    MediaFeatureValue::Identifier(ident) if operator == "=" => {
+                        MatchResult::from(ident.name.eq_ignore_ascii_case(keyword))
+                    }
+                    _ => MatchResult::Unknown,
+                };
+            }
+            FeatureValue::Number(number) => *number,
+        };
+        let Some(expected) = self.resolve(feature_type, expected) else {
+            return MatchResult::Unknown;
+        };
+        MatchResult::from(match operator {
+            "<" => actual < expected,
+            "<=" => actual <= expected,
+            ">" => actual > expected,
+            ">=" => actual >= expected,
+            _ => actual == expected,
+        })
+    }
+
+    /// Resolves the value in a query to a length in pixels, a resolution in dppx, a ratio or
+    /// an integer
+    fn resolve(&self, feature_type: FeatureType, value: &MediaFeatureValue) -> Option<f32> {
+        match (feature_type, value) {
+            (FeatureType::Length, MediaFeatureValue::Dimension(dimension)) => {
+                let length = Length::new(
+                    dimension.value.parse().ok()?,
+                    LengthUnit::parse(dimension.unit.as_deref()?)?,
+                );
+                Some(self.length_to_px(&length))
+            }
+            (FeatureType::Length, MediaFeatureValue::Number(number)) => {
+                // Only a zero length can be written without a unit
+                let number: f32 = number.value.parse().ok()?;
+                (number == 0.0).then_some(0.0)
+            }
+            (FeatureType::Length, MediaFeatureValue::Function(function)) => {
+                let node = CalcNode::parse(function)?;
+                (node.calc_type() == CalcType::Length)
+                    .then(|| node.evaluate(&|length| self.length_to_px(length), 0.0))
+            }
+            (FeatureType::Ratio, MediaFeatureValue::Ratio(ratio)) => {
+                let left: f32 = ratio.left.value.parse().ok()?;
+                let right: f32 = ratio.right.value.parse().ok()?;
+                Some(left / right)
+            }
+            (FeatureType::Ratio, MediaFeatureValue::Number(number)) => number.value.parse().ok(),
+            (FeatureType::Resolution, MediaFeatureValue::Dimension(dimension)) => {
+                let value: f32 = dimension.value.parse().ok()?;
+                match dimension.unit.as_deref()?.to_ascii_lowercase().as_str() {
+                    "dppx" | "x" => Some(value),
+                    "dpi" => Some(value / 96.0),
+                    "dpcm" => Some(value * 2.54 / 96.0),
+                    _ => None,
+                }
+            }
+            (FeatureType::Integer, MediaFeatureValue::Number(number)) => {
+                number.value.parse::<i32>().ok().map(|number| number as f32)
+            }
+            _ => None,
+        }
+    }
+
+    fn length_to_px(&self, length: &Length) -> f32 {
+        if let Some(px) = length.to_px() {
+            return px;
+        }
+        let factor = match length.unit {
+            LengthUnit::Em | LengthUnit::Rem => self.font_size,
+            LengthUnit::Ex | LengthUnit::Ch => self.font_size / 2.0,
+            LengthUnit::Vw => self.width / 100.0,
+            LengthUnit::Vh => self.height / 100.0,
+            LengthUnit::Vmin => self.width.min(self.height) / 100.0,
+            _ => self.width.max(self.height) / 100.0,
+        };
+        length.value * factor
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::css::node::{BlockChild, StyleSheetRule};
+
+    fn matches(environment: &MediaEnvironment, media: &str) -> bool {
+        environment.matches_media_attribute(Some(media))
+    }
+
+    #[test]
+    fn media_types_and_features() {
+        let screen = MediaEnvironment::default();
+        let print = MediaEnvironment {
+            media_type: "print".to_string(),
+            ..MediaEnvironment::default()
+        };
+
+        assert!(matches(&screen, ""));
+        assert!(matches(&screen, "all"));
+        assert!(matches(&screen, "screen"));
+        assert!(!matches(&screen, "print"));
+        assert!(matches(&screen, "print, screen"));
+        assert!(matches(&screen, "not print"));
+        assert!(matches(&print, "only print"));
+        assert!(!matches(&screen, "tv"));
+        assert!(matches(&screen, "screen and (min-width: 1000px)"));
+        assert!(!matches(&screen, "screen and (max-width: 1000px)"));
+        assert!(matches(&screen, "(width: 1280px)"));
+        assert!(matches(
+            &screen,
+            "(min-width: 40em) and (max-height: 45rem)"
+        ));
+        assert!(matches(&screen, "(orientation: landscape)"));
+        assert!(!matches(&screen, "(orientation: portrait)"));
+        assert!(matches(&screen, "(min-aspect-ratio: 16/10)"));
+        assert!(matches(&screen, "(aspect-ratio: 16/9)"));
+        assert!(matches(&screen, "(color)"));
+        assert!(!matches(&screen, "(monochrome)"));
+        assert!(matches(&screen, "(hover: hover) and (pointer: fine)"));
+        assert!(!matches(&print, "(hover)"));
+        assert!(matches(&screen, "(min-resolution: 96dpi)"));
+        assert!(!matches(&screen, "(min-resolution: 2dppx)"));
+        assert!(matches(&screen, "(max-width: calc(1000px + 50vw))"));
+    }
+
+    #[test]
+    fn range_syntax_and_logic() {
+        let environment = MediaEnvironment::default();
+        assert!(matches(&environment, "(width >= 600px)"));
+        assert!(matches(&environment, "(600px <= width)"));
+        assert!(matches(&environment, "(1000px < width <= 1280px)"));
+        assert!(!matches(&environment, "(1280px < width < 2000px)"));
+        assert!(matches(&environment, "(height = 720px)"));
+        assert!(matches(&environment, "(width > 0)"));
+        assert!(matches(&environment, "(color > 4)"));
+        assert!(matches(&environment, "not (width < 600px)"));
+        assert!(matches(&environment, "(width < 600px) or (height > 600px)"));
+        assert!(!matches(
+            &environment,
+            "(width > 600px) and (not (height > 600px))"
+        ));
+        assert!(matches(&environment, "screen and (not (color: 2))"));
+    }
+
+    #[test]
+    fn unknown_features() {
+        let environment = MediaEnvironment::default();
+        let feature = |css: &str| {
+            let list = CSS3Parser::new().parse_media_query_list(css);
+            match &list.children[0].condition {
+                Some(condition) => environment.evaluate(condition),
+                None => panic!("no condition in {}", css),
+            }
+        };
+
+        assert_eq!(feature("(unknown)"), MatchResult::Unknown);
+        assert_eq!(feature("(width: red)"), MatchResult::Unknown);
+        assert_eq!(feature("(width: 10)"), MatchResult::Unknown);
+        assert_eq!(feature("(min-orientation: portrait)"), MatchResult::Unknown);
+        assert_eq!(feature("(min-width)"), MatchResult::Unknown);
+        assert_eq!(feature("(orientation > portrait)"), MatchResult::Unknown);
+        assert_eq!(feature("foo(bar)"), MatchResult::Unknown);
+        assert_eq!(feature("not (unknown)"), MatchResult::Unknown);
+        assert_eq!(feature("(unknown) or (color)"), MatchResult::True);
+        assert_eq!(feature("(unknown) and (monochrome)"), MatchResult::False);
+
+        // An unknown query does not match, with or without `not`
+        assert!(!matches(&environment, "(unknown)"));
+        assert!(!matches(&environment, "not all and (unknown)"));
+        assert!(!matches(&environment, "screen; print"));
+        assert!(matches(&environment, "screen { }, all"));
+    }
+
+    #[test]
+    fn preferences() {
+        let environment = MediaEnvironment {
+            width: 400.0,
+            height: 800.0,
+            resolution: 2.0,
+            color_scheme: ColorScheme::Dark,
+            reduced_motion: true,
+            ..MediaEnvironment::default()
+        };
+        assert!(matches(&environment, "(prefers-color-scheme: dark)"));
+        assert!(!matches(&environment, "(prefers-color-scheme: light)"));
+        assert!(matches(&environment, "(prefers-reduced-motion: reduce)"));
+        assert!(matches(&environment, "(prefers-reduced-motion)"));
+        assert!(matches(&environment, "(orientation: portrait)"));
+        assert!(matches(&environment, "(resolution: 2x)"));
+        assert!(matches(&environment, "(min-resolution: 192dpi)"));
+        assert!(!matches(
+            &MediaEnvironment::default(),
+            "(prefers-reduced-motion)"
+        ));
+    }
+
+    #[test]
+    fn media_rules() {
+        let stylesheet = CSS3Parser::new().parse(
+            "@import url(print.css) print;
+             @media (max-width: 600px) { a { color: red } }
+             @media screen and (min-width: 600px) { a { color: blue } }
+             @font-face { font-family: x }",
+        );
+        let at_rules: Vec<&AtRule> = stylesheet
+            .children
+            .iter()
+            .filter_map(|rule| match rule {
+                StyleSheetRule::AtRule(rule) => Some(rule),
+                _ => None,
+            })
+            .collect();
+        let environment = MediaEnvironment::default();
+        let applies: Vec<bool> = at_rules
+            .iter()
+            .map(|rule| environment.matches_at_rule(rule))
+            .collect();
+        assert_eq!(applies, vec![false, false, true, true]);
+
+        let matching = at_rules[2].block.as_ref().unwrap();
+        assert!(matches!(matching.children[0], BlockChild::Rule(_)));
+    }
+}
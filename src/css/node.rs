@@ -1,12 +1,37 @@
 // Note: every node should have a "loc" property
 
-use std::fmt::{self, Debug, Formatter};
+use std::fmt::{self, Debug, Display, Formatter};
 
 /// Used for the [An+B microsyntax](https://drafts.csswg.org/css-syntax/#anb-microsyntax).
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct AnPlusB {
-    a: Option<String>,
-    b: Option<String>,
+    pub a: i32,
+    pub b: i32,
+}
+
+impl AnPlusB {
+    pub fn new(a: i32, b: i32) -> AnPlusB {
+        AnPlusB { a, b }
+    }
+
+    /// Returns true if the (1-based) index is `a*n + b` for some n >= 0
+    pub fn matches(&self, index: i32) -> bool {
+        let offset = index - self.b;
+        if self.a == 0 {
+            return offset == 0;
+        }
+        offset % self.a == 0 && offset / self.a >= 0
+    }
+}
+
+impl Display for AnPlusB {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (self.a, self.b) {
+            (0, b) => write!(f, "{}", b),
+            (a, 0) => write!(f, "{}n", a),
+            (a, b) => write!(f, "{}n{:+}", a, b),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -20,41 +45,95 @@ pub enum AtRulePreludeValue {
 /// E.g. @import @media @keyframes @supports
 #[derive(Debug, PartialEq)]
 pub struct AtRule {
-    name: String,
-    prelude: AtRulePreludeValue,
-    block: Option<Block>,
+    pub name: String,
+    pub prelude: AtRulePreludeValue,
+    pub block: Option<Block>,
+}
+
+impl AtRule {
+    pub fn new(name: String, prelude: AtRulePreludeValue, block: Option<Block>) -> AtRule {
+        AtRule {
+            name,
+            prelude,
+            block,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum AtRulePreludeChild {
     MediaQueryList(MediaQueryList),
+    LayerList(LayerList),
+    SupportsCondition(SupportsCondition),
+    /// The url of `@import` and `@namespace`
+    Url(Url),
+    /// The name of `@keyframes` or the prefix of `@namespace`
+    Identifier(Identifier),
+    /// The encoding of `@charset` or the (quoted) name of `@keyframes`
+    String(CssString),
 }
 
 #[derive(Debug, PartialEq, Default)]
 pub struct AtRulePrelude {
-    children: Vec<AtRulePrelude>,
+    pub children: Vec<AtRulePreludeChild>,
+}
+
+impl AtRulePrelude {
+    pub fn new(children: Vec<AtRulePreludeChild>) -> AtRulePrelude {
+        AtRulePrelude { children }
+    }
+
+    pub fn add_child(&mut self, child: AtRulePreludeChild) {
+        self.children.push(child)
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum AttributeSelectorValue {
     String(CssString),
-    Identifier(IdSelector),
+    Identifier(Identifier),
     None,
 }
 
 /// [Attribute Selector](https://drafts.csswg.org/selectors/#attribute-selectors)
 #[derive(Debug, PartialEq)]
 pub struct AttributeSelector {
-    name: Identifier,
-    matcher: Option<String>,
-    value: AttributeSelectorValue,
-    flags: Option<String>,
+    /// The namespace prefix: `Some("")` for `[|name]` and `Some("*")` for `[*|name]`
+    pub namespace: Option<String>,
+    pub name: Identifier,
+    /// `=`, `~=`, `|=`, `^=`, `$=` or `*=`
+    pub matcher: Option<String>,
+    pub value: AttributeSelectorValue,
+    /// `i` (ASCII case-insensitive) or `s` (case-sensitive)
+    pub flags: Option<String>,
+}
+
+impl Display for AttributeSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        if let Some(namespace) = &self.namespace {
+            write!(f, "{}|", namespace)?;
+        }
+        write!(f, "{:?}", self.name)?;
+        if let Some(matcher) = &self.matcher {
+            write!(f, "{}", matcher)?;
+            match &self.value {
+                AttributeSelectorValue::String(value) => write!(f, "{}", value)?,
+                AttributeSelectorValue::Identifier(value) => write!(f, "{:?}", value)?,
+                AttributeSelectorValue::None => {}
+            }
+        }
+        if let Some(flags) = &self.flags {
+            write!(f, " {}", flags)?;
+        }
+        write!(f, "]")
+    }
 }
 
 /// [Id Selector](https://drafts.csswg.org/selectors/#id-selectors)
 #[derive(PartialEq, Default)]
 pub struct IdSelector {
-    name: String,
+    pub name: String,
 }
 
 impl Debug for IdSelector {
@@ -72,7 +151,7 @@ impl IdSelector {
 /// [Class Selector](https://drafts.csswg.org/selectors/#class-html)
 #[derive(Debug, PartialEq, Default)]
 pub struct ClassSelector {
-    name: String,
+    pub name: String,
 }
 
 impl ClassSelector {
@@ -81,10 +160,23 @@ impl ClassSelector {
     }
 }
 
-/// [TypeSelector](https://drafts.csswg.org/selectors/#type-selectors)
+/// [TypeSelector](https://drafts.csswg.org/selectors/#type-selectors). The universal selector
+/// is a type selector with the name `*`.
 #[derive(Debug, PartialEq)]
 pub struct TypeSelector {
-    name: String,
+    /// The namespace prefix: `Some("")` for `|name` and `Some("*")` for `*|name`
+    pub namespace: Option<String>,
+    pub name: String,
+}
+
+impl TypeSelector {
+    pub fn new(namespace: Option<String>, name: String) -> TypeSelector {
+        TypeSelector { namespace, name }
+    }
+
+    pub fn is_universal(&self) -> bool {
+        self.name == "*"
+    }
 }
 
 /// [Nesting Selector](https://drafts.csswg.org/css-nesting/#nest-selector)
@@ -96,11 +188,14 @@ pub enum BlockChild {
     Rule(Rule),
     AtRule(AtRule),
     DeclarationList(DeclarationList),
+    KeyframeRule(KeyframeRule),
+    /// The contents of the block of an unknown at-rule, as written
+    Raw(Raw),
 }
 
 #[derive(Debug, PartialEq, Default)]
 pub struct Block {
-    children: Vec<BlockChild>,
+    pub children: Vec<BlockChild>,
 }
 
 impl Block {
@@ -115,7 +210,7 @@ impl Block {
 
 #[derive(PartialEq, Default)]
 pub struct Identifier {
-    name: String,
+    pub name: String,
 }
 
 impl Debug for Identifier {
@@ -135,9 +230,60 @@ pub struct CDC;
 #[derive(Debug, PartialEq)]
 pub struct CDO;
 
+/// A boolean combination of tests, as used by
+/// [media queries](https://drafts.csswg.org/mediaqueries-4/#media-conditions) and
+/// [`@supports`](https://drafts.csswg.org/css-conditional-3/#at-supports)
+#[derive(Debug, PartialEq)]
+pub enum Condition<T> {
+    Test(T),
+    Not(Box<Condition<T>>),
+    And(Vec<Condition<T>>),
+    Or(Vec<Condition<T>>),
+    /// A [general enclosed](https://drafts.csswg.org/mediaqueries-4/#typedef-general-enclosed)
+    /// test that is not understood, as written
+    GeneralEnclosed(Raw),
+}
+
+impl<T: Display> Display for Condition<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn in_parens<T: Display>(f: &mut Formatter<'_>, condition: &Condition<T>) -> fmt::Result {
+            match condition {
+                Condition::Test(_) | Condition::GeneralEnclosed(_) => write!(f, "{}", condition),
+                _ => write!(f, "({})", condition),
+            }
+        }
+
+        let (operator, children) = match self {
+            Condition::Test(test) => return write!(f, "{}", test),
+            Condition::GeneralEnclosed(raw) => return write!(f, "{}", raw.value),
+            Condition::Not(condition) => {
+                write!(f, "not ")?;
+                return in_parens(f, condition);
+            }
+            Condition::And(children) => (" and ", children),
+            Condition::Or(children) => (" or ", children),
+        };
+        for (idx, child) in children.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "{}", operator)?;
+            }
+            in_parens(f, child)?;
+        }
+        Ok(())
+    }
+}
+
+/// [Combinator](https://drafts.csswg.org/selectors/#combinators): ` ` (descendant), `>`, `+`,
+/// `~` or `||` (column)
 #[derive(Debug, PartialEq)]
 pub struct Combinator {
-    name: String,
+    pub name: String,
+}
+
+impl Combinator {
+    pub fn new(name: String) -> Combinator {
+        Combinator { name }
+    }
 }
 
 #[derive(Debug, PartialEq, Default)]
@@ -169,9 +315,19 @@ impl Declaration {
     }
 }
 
+impl Display for Declaration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.property, self.value)?;
+        if self.important {
+            write!(f, " !important")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq, Default)]
 pub struct DeclarationList {
-    children: Vec<Declaration>,
+    pub children: Vec<Declaration>,
 }
 
 impl DeclarationList {
@@ -186,8 +342,9 @@ impl DeclarationList {
 
 #[derive(PartialEq, Default)]
 pub struct Dimension {
-    value: String,
-    unit: Option<String>,
+    /// The number as it was written
+    pub value: String,
+    pub unit: Option<String>,
 }
 
 impl Debug for Dimension {
@@ -211,54 +368,215 @@ pub enum MediaFeatureValue {
     Function(Function),
 }
 
+impl Display for MediaFeatureValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaFeatureValue::Identifier(value) => write!(f, "{:?}", value),
+            MediaFeatureValue::Number(value) => write!(f, "{}", value.value),
+            MediaFeatureValue::Dimension(value) => write!(f, "{:?}", value),
+            MediaFeatureValue::Ratio(value) => {
+                write!(f, "{}/{}", value.left.value, value.right.value)
+            }
+            MediaFeatureValue::Function(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// A [media feature](https://drafts.csswg.org/mediaqueries-4/#media-feature): `(color)`
+/// (boolean), `(min-width: 600px)` (plain) or `(400px < width <= 700px)` (range)
 #[derive(Debug, PartialEq)]
 pub struct MediaFeature {
-    name: String,
-    value: Option<MediaFeatureValue>,
+    pub name: String,
+    /// The value of a plain media feature
+    pub value: Option<MediaFeatureValue>,
+    /// The comparisons of a range media feature, with the feature on the left side
+    pub range: Vec<MediaFeatureRange>,
 }
 
+impl MediaFeature {
+    pub fn new(name: String, value: Option<MediaFeatureValue>) -> MediaFeature {
+        MediaFeature {
+            name,
+            value,
+            range: vec![],
+        }
+    }
+}
+
+impl Display for MediaFeature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (&self.value, self.range.as_slice()) {
+            (Some(value), _) => write!(f, "({}: {})", self.name, value),
+            (None, [range]) => write!(f, "({} {} {})", self.name, range.operator, range.value),
+            (None, [first, last]) => write!(
+                f,
+                "({} {} {} {} {})",
+                first.value,
+                flip_comparison(&first.operator),
+                self.name,
+                last.operator,
+                last.value
+            ),
+            (None, _) => write!(f, "({})", self.name),
+        }
+    }
+}
+
+/// A comparison of a range media feature, like `>= 600px`
 #[derive(Debug, PartialEq)]
-pub enum FunctionChild {
-    Identifier(Identifier),
-    Operator(Operator),
-    Percentage(Percentage),
+pub struct MediaFeatureRange {
+    /// `<`, `<=`, `>`, `>=` or `=`
+    pub operator: String,
+    pub value: MediaFeatureValue,
+}
+
+impl MediaFeatureRange {
+    pub fn new(operator: String, value: MediaFeatureValue) -> MediaFeatureRange {
+        MediaFeatureRange { operator, value }
+    }
+}
+
+/// Returns the comparison operator with its sides swapped: `<` becomes `>`
+pub fn flip_comparison(operator: &str) -> &str {
+    match operator {
+        "<" => ">",
+        "<=" => ">=",
+        ">" => "<",
+        ">=" => "<=",
+        operator => operator,
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Function {
-    name: String,
-    children: Vec<FunctionChild>,
+    pub name: String,
+    pub children: ValueList,
+}
+
+impl Function {
+    pub fn new(name: String, children: ValueList) -> Function {
+        Function { name, children }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.name, self.children)
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Hash {
-    value: String,
+    pub value: String,
+}
+
+impl Hash {
+    pub fn new(value: String) -> Hash {
+        Hash { value }
+    }
 }
 
+/// A rule of a `@keyframes` block, like `from, 50% { opacity: 0; }`
 #[derive(Debug, PartialEq)]
-pub struct Layer {
-    name: String,
+pub struct KeyframeRule {
+    /// The keyframe selectors, with `from` as `0` and `to` as `100`
+    pub selectors: Vec<Percentage>,
+    pub block: Block,
+}
+
+impl KeyframeRule {
+    pub fn new(selectors: Vec<Percentage>, block: Block) -> KeyframeRule {
+        KeyframeRule { selectors, block }
+    }
 }
 
+/// A [cascade layer](https://drafts.csswg.org/css-cascade-5/#layering) name, like `base.reset`.
+/// The name of an anonymous layer is empty.
 #[derive(Debug, PartialEq)]
+pub struct Layer {
+    pub name: String,
+}
+
+impl Layer {
+    pub fn new(name: String) -> Layer {
+        Layer { name }
+    }
+}
+
+#[derive(Debug, PartialEq, Default)]
 pub struct LayerList {
-    children: Vec<Layer>,
+    pub children: Vec<Layer>,
 }
 
-#[derive(Debug, PartialEq)]
-pub enum MediaQueryChild {
-    Identifier(Identifier),
-    MediaFeature(MediaFeature),
+impl LayerList {
+    pub fn new(children: Vec<Layer>) -> LayerList {
+        LayerList { children }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+pub type MediaCondition = Condition<MediaFeature>;
+
+/// A [media query](https://drafts.csswg.org/mediaqueries-4/#media), like
+/// `only screen and (min-width: 600px)`
+#[derive(Debug, PartialEq, Default)]
 pub struct MediaQuery {
-    children: Vec<MediaQueryChild>,
+    /// `not` or `only`
+    pub modifier: Option<String>,
+    pub media_type: Option<String>,
+    pub condition: Option<MediaCondition>,
+}
+
+impl MediaQuery {
+    /// The `not all` query that replaces an invalid media query
+    pub fn not_all() -> MediaQuery {
+        MediaQuery {
+            modifier: Some("not".to_string()),
+            media_type: Some("all".to_string()),
+            condition: None,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+impl Display for MediaQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(modifier) = &self.modifier {
+            write!(f, "{} ", modifier)?;
+        }
+        match (&self.media_type, &self.condition) {
+            (Some(media_type), Some(condition)) => write!(f, "{} and {}", media_type, condition),
+            (Some(media_type), None) => write!(f, "{}", media_type),
+            (None, Some(condition)) => write!(f, "{}", condition),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// A comma-separated list of media queries. An empty list matches all media.
+#[derive(Debug, PartialEq, Default)]
 pub struct MediaQueryList {
-    children: Vec<MediaQuery>,
+    pub children: Vec<MediaQuery>,
+}
+
+impl MediaQueryList {
+    pub fn new(children: Vec<MediaQuery>) -> MediaQueryList {
+        MediaQueryList { children }
+    }
+
+    pub fn add_child(&mut self, query: MediaQuery) {
+        self.children.push(query)
+    }
+}
+
+impl Display for MediaQueryList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (idx, query) in self.children.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", query)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -266,62 +584,175 @@ pub enum NthValue {
     AnPlusB(AnPlusB),
     Identifier(Identifier),
 }
+/// The argument of the `:nth-*` pseudo-classes, like `2n+1 of .item`
 #[derive(Debug, PartialEq)]
 pub struct Nth {
-    nth: NthValue,
-    selector: Option<SelectorList>,
+    pub nth: NthValue,
+    pub selector: Option<SelectorList>,
+}
+
+impl Nth {
+    pub fn new(nth: NthValue, selector: Option<SelectorList>) -> Nth {
+        Nth { nth, selector }
+    }
+
+    /// Returns the An+B value, with `odd` and `even` converted
+    pub fn an_plus_b(&self) -> AnPlusB {
+        match &self.nth {
+            NthValue::AnPlusB(an_plus_b) => *an_plus_b,
+            NthValue::Identifier(name) if name.name.eq_ignore_ascii_case("odd") => {
+                AnPlusB::new(2, 1)
+            }
+            NthValue::Identifier(_) => AnPlusB::new(2, 0),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct CssNumber {
-    value: String,
+    /// The number as it was written
+    pub value: String,
+}
+
+impl CssNumber {
+    pub fn new(value: String) -> CssNumber {
+        CssNumber { value }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct CssString {
-    value: String,
+    pub value: String,
+}
+
+impl CssString {
+    pub fn new(value: String) -> CssString {
+        CssString { value }
+    }
+}
+
+/// [Serializes](https://drafts.csswg.org/cssom/#serialize-a-string) the string with double quotes
+impl Display for CssString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "\"")?;
+        for c in self.value.chars() {
+            match c {
+                '"' | '\\' => write!(f, "\\{}", c)?,
+                '\x01'..='\x1F' | '\x7F' => write!(f, "\\{:x} ", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "\"")
+    }
 }
 
 // todo: should be "enum"
 #[derive(Debug, PartialEq)]
 pub struct Operator {
-    value: String,
+    pub value: String,
+}
+
+impl Operator {
+    pub fn new(value: String) -> Operator {
+        Operator { value }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Percentage {
-    value: String,
+    /// The number as it was written, without `%`
+    pub value: String,
+}
+
+impl Percentage {
+    pub fn new(value: String) -> Percentage {
+        Percentage { value }
+    }
+}
+
+/// The argument of a functional pseudo-class or pseudo-element
+#[derive(Debug, PartialEq)]
+pub enum PseudoSelectorArgument {
+    SelectorList(SelectorList),
+    Nth(Nth),
+    Identifier(Identifier),
+    /// Arguments of unknown pseudo-classes are kept as written
+    Raw(Raw),
+}
+
+impl Display for PseudoSelectorArgument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PseudoSelectorArgument::SelectorList(list) => write!(f, "{}", list),
+            PseudoSelectorArgument::Nth(nth) => {
+                match &nth.nth {
+                    NthValue::AnPlusB(an_plus_b) => write!(f, "{}", an_plus_b)?,
+                    NthValue::Identifier(name) => write!(f, "{:?}", name)?,
+                }
+                match &nth.selector {
+                    Some(selector) => write!(f, " of {}", selector),
+                    None => Ok(()),
+                }
+            }
+            PseudoSelectorArgument::Identifier(name) => write!(f, "{:?}", name),
+            PseudoSelectorArgument::Raw(raw) => write!(f, "{}", raw.value),
+        }
+    }
 }
 
 /// [Pseudo-classes](https://drafts.csswg.org/selectors/#pseudo-classes)
 #[derive(Debug, PartialEq)]
 pub struct PseudoClassSelector {
-    name: String,
-    children: Option<SelectorList>,
+    pub name: String,
+    pub argument: Option<PseudoSelectorArgument>,
+}
+
+impl PseudoClassSelector {
+    pub fn new(name: String, argument: Option<PseudoSelectorArgument>) -> PseudoClassSelector {
+        PseudoClassSelector { name, argument }
+    }
 }
 
 /// [Pseudo-elements](https://drafts.csswg.org/selectors/#pseudo-elements)
 #[derive(Debug, PartialEq)]
 pub struct PseudoElementSelector {
-    name: String,
-    children: Option<SelectorList>,
+    pub name: String,
+    pub argument: Option<PseudoSelectorArgument>,
+}
+
+impl PseudoElementSelector {
+    pub fn new(name: String, argument: Option<PseudoSelectorArgument>) -> PseudoElementSelector {
+        PseudoElementSelector { name, argument }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Ratio {
-    left: CssNumber,
-    right: CssNumber,
+    pub left: CssNumber,
+    pub right: CssNumber,
+}
+
+impl Ratio {
+    pub fn new(left: CssNumber, right: CssNumber) -> Ratio {
+        Ratio { left, right }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Raw {
-    value: String,
+    pub value: String,
+}
+
+impl Raw {
+    pub fn new(value: String) -> Raw {
+        Raw { value }
+    }
 }
 
 #[derive(Debug, PartialEq, Default)]
 pub struct Rule {
-    selectors: SelectorList,
-    block: Block,
+    pub selectors: SelectorList,
+    pub block: Block,
 }
 
 impl Rule {
@@ -330,6 +761,7 @@ impl Rule {
     }
 }
 
+/// A simple selector, pseudo-element or combinator
 #[derive(Debug, PartialEq)]
 pub enum Selector {
     IdSelector(IdSelector),
@@ -337,32 +769,138 @@ pub enum Selector {
     AttributeSelector(AttributeSelector),
     TypeSelector(TypeSelector),
     NestingSelector(NestingSelector),
+    PseudoClassSelector(PseudoClassSelector),
+    PseudoElementSelector(PseudoElementSelector),
+    Combinator(Combinator),
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let argument =
+            |f: &mut Formatter<'_>, argument: &Option<PseudoSelectorArgument>| match argument {
+                Some(argument) => write!(f, "({})", argument),
+                None => Ok(()),
+            };
+        match self {
+            Selector::IdSelector(selector) => write!(f, "{:?}", selector),
+            Selector::ClassSelector(selector) => write!(f, ".{}", selector.name),
+            Selector::AttributeSelector(selector) => write!(f, "{}", selector),
+            Selector::TypeSelector(selector) => match &selector.namespace {
+                Some(namespace) => write!(f, "{}|{}", namespace, selector.name),
+                None => write!(f, "{}", selector.name),
+            },
+            Selector::NestingSelector(_) => write!(f, "&"),
+            Selector::PseudoClassSelector(selector) => {
+                write!(f, ":{}", selector.name)?;
+                argument(f, &selector.argument)
+            }
+            Selector::PseudoElementSelector(selector) => {
+                write!(f, "::{}", selector.name)?;
+                argument(f, &selector.argument)
+            }
+            Selector::Combinator(combinator) if combinator.name == " " => write!(f, " "),
+            Selector::Combinator(combinator) => write!(f, " {} ", combinator.name),
+        }
+    }
+}
+
+/// A [complex selector](https://drafts.csswg.org/selectors/#complex): compound selectors
+/// separated by combinators. Relative selectors (in `:has()`) start with a combinator.
+#[derive(Debug, PartialEq, Default)]
+pub struct ComplexSelector {
+    pub children: Vec<Selector>,
+}
+
+impl ComplexSelector {
+    pub fn new(children: Vec<Selector>) -> ComplexSelector {
+        ComplexSelector { children }
+    }
+
+    pub fn add_child(&mut self, selector: Selector) {
+        self.children.push(selector)
+    }
+}
+
+impl Display for ComplexSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut output = String::new();
+        for selector in &self.children {
+            output.push_str(&selector.to_string());
+        }
+        write!(f, "{}", output.trim_start())
+    }
 }
 
+/// A comma-separated list of complex selectors
 #[derive(Debug, PartialEq, Default)]
 pub struct SelectorList {
-    children: Vec<Selector>,
+    pub children: Vec<ComplexSelector>,
 }
 
 impl SelectorList {
-    pub fn new(children: Vec<Selector>) -> SelectorList {
+    pub fn new(children: Vec<ComplexSelector>) -> SelectorList {
         SelectorList { children }
     }
 
-    pub fn add_child(&mut self, selector: Selector) {
+    pub fn add_child(&mut self, selector: ComplexSelector) {
         self.children.push(selector)
     }
 }
 
+impl Display for SelectorList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (idx, selector) in self.children.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", selector)?;
+        }
+        Ok(())
+    }
+}
+
+pub type SupportsCondition = Condition<SupportsFeature>;
+
+/// A test of a [`@supports`](https://drafts.csswg.org/css-conditional-4/#at-supports) condition
+#[derive(Debug, PartialEq)]
+pub enum SupportsFeature {
+    /// `(display: grid)`
+    Declaration(Declaration),
+    /// `selector(a > b)`
+    Selector(SelectorList),
+}
+
+impl Display for SupportsFeature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SupportsFeature::Declaration(declaration) => write!(f, "({})", declaration),
+            SupportsFeature::Selector(selector) => write!(f, "selector({})", selector),
+        }
+    }
+}
+
 /// Used for the [Unicode-Range microsyntax](https://drafts.csswg.org/css-syntax/#urange).
 #[derive(Debug, PartialEq)]
 pub struct UnicodeRange {
-    value: String,
+    /// The range as it was written
+    pub value: String,
+}
+
+impl UnicodeRange {
+    pub fn new(value: String) -> UnicodeRange {
+        UnicodeRange { value }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Url {
-    value: String,
+    pub value: String,
+}
+
+impl Url {
+    pub fn new(value: String) -> Url {
+        Url { value }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -370,6 +908,35 @@ pub enum Value {
     Dimension(Dimension),
     Identifier(Identifier),
     Function(Function),
+    Percentage(Percentage),
+    Hash(Hash),
+    String(CssString),
+    Url(Url),
+    UnicodeRange(UnicodeRange),
+    /// Delimiters like `,` and `/`
+    Operator(Operator),
+    /// A parenthesized group, like `(1px + 2px)` in `calc()`
+    Parentheses(ValueList),
+    /// Simple blocks, like `[a]` and `{ a: b }` in custom properties, as written
+    Raw(Raw),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Dimension(value) => write!(f, "{:?}", value),
+            Value::Identifier(value) => write!(f, "{:?}", value),
+            Value::Function(value) => write!(f, "{}", value),
+            Value::Percentage(value) => write!(f, "{}%", value.value),
+            Value::Hash(value) => write!(f, "#{}", value.value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Url(value) => write!(f, "url({})", CssString::new(value.value.clone())),
+            Value::UnicodeRange(value) => write!(f, "{}", value.value),
+            Value::Operator(value) => write!(f, "{}", value.value),
+            Value::Parentheses(value) => write!(f, "({})", value),
+            Value::Raw(value) => write!(f, "{}", value.value),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Default)]
@@ -387,6 +954,19 @@ impl ValueList {
     }
 }
 
+impl Display for ValueList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (idx, value) in self.children.iter().enumerate() {
+            let is_comma = matches!(value, Value::Operator(operator) if operator.value == ",");
+            if idx > 0 && !is_comma {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum StyleSheetRule {
     AtRule(AtRule),
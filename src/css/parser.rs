@@ -1,9 +1,42 @@
 use crate::css::node::{
-    Block, BlockChild, Declaration, DeclarationList, Dimension, IdSelector, Identifier, Rule,
-    Selector, SelectorList, StyleSheet, StyleSheetRule, Value, ValueList,
+    flip_comparison, AnPlusB, AtRule, AtRulePrelude, AtRulePreludeChild, AtRulePreludeValue,
+    AttributeSelector, AttributeSelectorValue, Block, BlockChild, ClassSelector, Combinator,
+    ComplexSelector, Condition, CssNumber, CssString, Declaration, DeclarationList, Dimension,
+    Function, Hash, IdSelector, Identifier, KeyframeRule, Layer, LayerList, MediaFeature,
+    MediaFeatureRange, MediaFeatureValue, MediaQuery, MediaQueryList, NestingSelector, Nth,
+    NthValue, Operator, Percentage, PseudoClassSelector, PseudoElementSelector,
+    PseudoSelectorArgument, Ratio, Raw, Rule, Selector, SelectorList, StyleSheet, StyleSheetRule,
+    SupportsFeature, TypeSelector, UnicodeRange, Url, Value, ValueList,
 };
-use crate::css::tokenizer::Tokenizer;
+use crate::css::tokenizer::{ParseError, Tokenizer};
 use crate::css::tokens::{Token, TokenType};
+use crate::html5_parser::error_logger::{self, ErrorLogger};
+
+/// Pseudo-elements that can be written with a single colon
+const LEGACY_PSEUDO_ELEMENTS: [&str; 4] = ["before", "after", "first-line", "first-letter"];
+
+/// At-rules that end with a semicolon
+const STATEMENT_AT_RULES: [&str; 3] = ["import", "charset", "namespace"];
+
+/// At-rules that end with a block
+const BLOCK_AT_RULES: [&str; 14] = [
+    "media",
+    "supports",
+    "container",
+    "scope",
+    "starting-style",
+    "document",
+    "font-face",
+    "page",
+    "counter-style",
+    "property",
+    "font-palette-values",
+    "viewport",
+    "keyframes",
+    "font-feature-values",
+];
+
+type ParseResult<T> = Result<T, ParseError>;
 
 /// # CSS3 Parser
 /// The parser using the Recursive Descent Parser algorithm (predictive parser).
@@ -11,263 +44,1753 @@ use crate::css::tokens::{Token, TokenType};
 #[derive(Debug, PartialEq)]
 pub struct CSS3Parser {
     tokenizer: Tokenizer,
-    lookahead: Option<Token>,
+    /// All tokens of the input, ending with an EOF token
+    tokens: Vec<Token>,
+    /// Offset after the last character of every token, before the comments that follow it
+    token_ends: Vec<usize>,
+    /// Index of the lookahead token
+    position: usize,
     raw: String,
+    error_logger: ErrorLogger,
 }
 
 impl Default for CSS3Parser {
     fn default() -> Self {
         Self::new()
     }
-}
+}
+
+impl CSS3Parser {
+    pub fn new() -> CSS3Parser {
+        CSS3Parser {
+            tokenizer: Tokenizer::new(),
+            tokens: vec![],
+            token_ends: vec![],
+            position: 0,
+            raw: "".to_string(),
+            error_logger: ErrorLogger::new(),
+        }
+    }
+
+    /// Parses the stylesheet. Invalid rules and declarations are left out and reported in
+    /// [`CSS3Parser::errors`], as described in
+    /// [CSS Syntax](https://www.w3.org/TR/css-syntax-3/#error-handling).
+    pub fn parse(&mut self, raw: &str) -> StyleSheet {
+        self.init(raw);
+        self.style_sheet()
+    }
+
+    /// Returns the errors of the last parse, ordered by their position
+    pub fn errors(&self) -> Vec<error_logger::ParseError> {
+        let mut errors = self.error_logger.get_errors();
+        errors.sort_by_key(|error| error.offset);
+        errors
+    }
+
+    /// Parses a selector list, like the argument of `querySelectorAll()`
+    pub fn parse_selector_list(&mut self, raw: &str) -> Result<SelectorList, ParseError> {
+        self.init(raw);
+        let selector_list = self.selector_list()?;
+        self.skip_whitespace();
+        if !self.lookahead().is_eof() {
+            return Err(self.error("unexpected token after selector"));
+        }
+        Ok(selector_list)
+    }
+
+    /// Parses a declaration value, like `1px solid red`
+    pub fn parse_value_list(&mut self, raw: &str) -> Result<ValueList, ParseError> {
+        self.init(raw);
+        self.skip_whitespace();
+        let value_list = self.value_ist();
+        if !self.lookahead().is_eof() {
+            return Err(self.error("unexpected token in value"));
+        }
+        Ok(value_list)
+    }
+
+    /// Parses a media query list, like the `media` attribute of `<link>` and `<style>`. Invalid
+    /// media queries are replaced with `not all`.
+    pub fn parse_media_query_list(&mut self, raw: &str) -> MediaQueryList {
+        self.init(raw);
+        let mut media_query_list = MediaQueryList::default();
+        loop {
+            let mut queries = self.media_query_list();
+            if self.lookahead().is_eof() {
+                media_query_list.children.append(&mut queries.children);
+                return media_query_list;
+            }
+
+            // A block or a semicolon makes the media query that contains it invalid
+            queries.children.pop();
+            queries.add_child(MediaQuery::not_all());
+            media_query_list.children.append(&mut queries.children);
+            self.skip_component_values(|token_type| *token_type == TokenType::Comma);
+            if self.lookahead().is_eof() {
+                return media_query_list;
+            }
+            self.consume_any();
+        }
+    }
+
+    fn init(&mut self, raw: &str) {
+        self.raw = raw.to_string();
+        self.tokenizer.init(raw);
+        self.tokens.clear();
+        self.token_ends.clear();
+        self.position = 0;
+        loop {
+            let token = self.tokenizer.next_token();
+            let is_eof = token.is_eof();
+            self.tokens.push(token);
+            self.token_ends.push(self.tokenizer.cursor);
+            if is_eof {
+                break;
+            }
+        }
+
+        self.error_logger = ErrorLogger::new();
+        for error in self.tokenizer.errors() {
+            self.error_logger.add_error(error.position, &error.message);
+        }
+    }
+
+    /// ```txt
+    /// SytleSheet
+    ///     : RulesList
+    ///     ;
+    /// ```
+    fn style_sheet(&mut self) -> StyleSheet {
+        StyleSheet::new(self.rules_list())
+    }
+
+    /// ```txt
+    /// RulesList
+    ///     : [CDO | CDC | Rule | AtRule]*
+    ///     ;
+    /// ```
+    fn rules_list(&mut self) -> Vec<StyleSheetRule> {
+        let mut rules: Vec<StyleSheetRule> = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            let start = self.lookahead().position;
+            match self.lookahead().token_type {
+                TokenType::EOF => return rules,
+                TokenType::CDO | TokenType::CDC => {
+                    self.consume_any();
+                }
+                _ => match self.rule_or_at_rule(false) {
+                    Some(StyleSheetRule::AtRule(rule))
+                        if rule.name.eq_ignore_ascii_case("import")
+                            && !rules.iter().all(may_precede_import) =>
+                    {
+                        let message = "@import must come before all other rules";
+                        self.error_logger.add_error(start, message);
+                    }
+                    Some(rule) => rules.push(rule),
+                    None => {}
+                },
+            }
+        }
+    }
+
+    /// Parses a style rule or an at-rule. Invalid rules are reported and skipped.
+    fn rule_or_at_rule(&mut self, nested: bool) -> Option<StyleSheetRule> {
+        match self.lookahead().token_type {
+            TokenType::AtKeyword(_) => self.at_rule().map(StyleSheetRule::AtRule),
+            _ => self.rule(nested).map(StyleSheetRule::Rule),
+        }
+    }
+
+    /// ```txt
+    /// Rule
+    ///     : SelectorList Block
+    ///     ;
+    /// ```
+    fn rule(&mut self, nested: bool) -> Option<Rule> {
+        // The block is found first, so that an invalid selector only drops this rule
+        let start = self.position;
+        if nested {
+            self.skip_component_values(|token_type| {
+                matches!(token_type, TokenType::LCurly | TokenType::RCurly)
+            });
+        } else {
+            self.skip_component_values(|token_type| *token_type == TokenType::LCurly);
+        }
+        if !self.is_next_token(TokenType::LCurly) {
+            self.log(self.error("expected '{' after selector"));
+            return None;
+        }
+        let block_start = self.position;
+
+        self.position = start;
+        let selectors = self.rule_prelude(block_start);
+        self.position = block_start;
+        let block = self.block();
+
+        match selectors {
+            Ok(selectors) => Some(Rule::new(selectors, block)),
+            Err(error) => {
+                self.log(error);
+                None
+            }
+        }
+    }
+
+    /// Parses the selector list of a rule, which must end at the token index
+    fn rule_prelude(&mut self, end: usize) -> ParseResult<SelectorList> {
+        let selectors = self.selector_list()?;
+        self.skip_whitespace();
+        if self.position != end {
+            return Err(self.error("unexpected token in selector"));
+        }
+        Ok(selectors)
+    }
+
+    /// ```txt
+    /// AtRule
+    ///     : ATKEYWORD AtRulePrelude [Block | SEMICOLON]
+    ///     ;
+    /// ```
+    fn at_rule(&mut self) -> Option<AtRule> {
+        let TokenType::AtKeyword(name) = self.consume_any().token_type else {
+            return None;
+        };
+        self.skip_whitespace();
+
+        let kind = unprefixed(&name).to_ascii_lowercase();
+        let start = self.position;
+        let prelude = match self.at_rule_prelude(&kind) {
+            Ok(_)
+                if self.is_next_token(TokenType::LCurly)
+                    && STATEMENT_AT_RULES.contains(&kind.as_str()) =>
+            {
+                Err(self.error("unexpected block"))
+            }
+            Ok(_)
+                if !self.is_next_token(TokenType::LCurly)
+                    && BLOCK_AT_RULES.contains(&kind.as_str()) =>
+            {
+                Err(self.error("expected '{'"))
+            }
+            prelude => prelude,
+        };
+        let prelude = match prelude {
+            Ok(prelude) => prelude,
+            Err(error) => {
+                self.log(error);
+                self.position = start;
+                self.skip_component_values(|token_type| {
+                    matches!(
+                        token_type,
+                        TokenType::LCurly | TokenType::Semicolon | TokenType::RCurly
+                    )
+                });
+                match self.lookahead().token_type {
+                    TokenType::LCurly => {
+                        self.consume_any();
+                        self.skip_component_values(|token_type| *token_type == TokenType::RCurly);
+                        self.close_block();
+                    }
+                    TokenType::Semicolon => {
+                        self.consume_any();
+                    }
+                    _ => {}
+                }
+                return None;
+            }
+        };
+
+        if !self.is_next_token(TokenType::LCurly) {
+            if self.is_next_token(TokenType::Semicolon) {
+                self.consume_any();
+            }
+            return Some(AtRule::new(name, prelude, None));
+        }
+
+        let block = match kind.as_str() {
+            "media" | "supports" | "layer" | "container" | "scope" | "starting-style"
+            | "document" => self.rules_block(),
+            "font-face"
+            | "page"
+            | "counter-style"
+            | "property"
+            | "font-palette-values"
+            | "viewport" => self.block(),
+            "keyframes" => self.keyframes_block(),
+            _ => self.raw_block(),
+        };
+        Some(AtRule::new(name, prelude, Some(block)))
+    }
+
+    /// Parses the prelude of the at-rule (with its vendor prefix removed and lowercased)
+    fn at_rule_prelude(&mut self, kind: &str) -> ParseResult<AtRulePreludeValue> {
+        let mut prelude = AtRulePrelude::default();
+
+        match kind {
+            "media" => {
+                prelude.add_child(AtRulePreludeChild::MediaQueryList(self.media_query_list()))
+            }
+            "import" => self.import_prelude(&mut prelude)?,
+            "supports" => prelude.add_child(AtRulePreludeChild::SupportsCondition(
+                self.condition(Self::supports_feature, true)?,
+            )),
+            "layer" if !self.is_at_prelude_end() => {
+                prelude.add_child(AtRulePreludeChild::LayerList(self.layer_list()?))
+            }
+            "charset" => match self.consume_any().token_type {
+                TokenType::String(charset) => {
+                    prelude.add_child(AtRulePreludeChild::String(CssString::new(charset)))
+                }
+                _ => return Err(self.error("expected string")),
+            },
+            "namespace" => {
+                if let Some(prefix) = self.ident() {
+                    self.consume_any();
+                    self.skip_whitespace();
+                    prelude.add_child(AtRulePreludeChild::Identifier(Identifier::new(prefix)));
+                }
+                prelude.add_child(AtRulePreludeChild::Url(self.url()?));
+            }
+            "keyframes" => match self.consume_any().token_type {
+                TokenType::Ident(name) => {
+                    prelude.add_child(AtRulePreludeChild::Identifier(Identifier::new(name)))
+                }
+                TokenType::String(name) => {
+                    prelude.add_child(AtRulePreludeChild::String(CssString::new(name)))
+                }
+                _ => return Err(self.error("expected keyframes name")),
+            },
+            "layer" | "font-face" => {}
+            _ => {
+                let start = self.lookahead().position.offset;
+                self.skip_component_values(|token_type| {
+                    matches!(
+                        token_type,
+                        TokenType::LCurly | TokenType::Semicolon | TokenType::RCurly
+                    )
+                });
+                let end = self.lookahead().position.offset;
+                let raw = self.tokenizer.source(start, end).trim_end().to_string();
+                if raw.is_empty() {
+                    return Ok(AtRulePreludeValue::None);
+                }
+                return Ok(AtRulePreludeValue::Raw(Raw::new(raw)));
+            }
+        }
+
+        self.skip_whitespace();
+        if !self.is_at_prelude_end() {
+            return Err(self.error("unexpected token in at-rule prelude"));
+        }
+        if prelude.children.is_empty() {
+            return Ok(AtRulePreludeValue::None);
+        }
+        Ok(AtRulePreludeValue::AtRulePrelude(prelude))
+    }
+
+    fn is_at_prelude_end(&self) -> bool {
+        matches!(
+            self.lookahead().token_type,
+            TokenType::LCurly | TokenType::Semicolon | TokenType::RCurly | TokenType::EOF
+        )
+    }
+
+    /// ```txt
+    /// ImportPrelude
+    ///     : [URL | STRING] [layer | layer(LayerName)]? [supports(SupportsCondition)]?
+    ///         MediaQueryList?
+    ///     ;
+    /// ```
+    fn import_prelude(&mut self, prelude: &mut AtRulePrelude) -> ParseResult<()> {
+        prelude.add_child(AtRulePreludeChild::Url(self.url()?));
+        self.skip_whitespace();
+
+        if self.lookahead().is_ident("layer") {
+            self.consume_any();
+            let layer = Layer::new(String::new());
+            prelude.add_child(AtRulePreludeChild::LayerList(LayerList::new(vec![layer])));
+        } else if self.is_function("layer") {
+            self.consume_any();
+            self.skip_whitespace();
+            let layer = Layer::new(self.layer_name()?);
+            self.consume_closing_paren()?;
+            prelude.add_child(AtRulePreludeChild::LayerList(LayerList::new(vec![layer])));
+        }
+        self.skip_whitespace();
+
+        if self.is_function("supports") {
+            self.consume_any();
+            self.skip_whitespace();
+            let condition = if self.peek_past_whitespace(1).token_type == TokenType::Colon {
+                Condition::Test(SupportsFeature::Declaration(self.declaration_body()?))
+            } else {
+                self.condition(Self::supports_feature, true)?
+            };
+            self.consume_closing_paren()?;
+            prelude.add_child(AtRulePreludeChild::SupportsCondition(condition));
+        }
+        self.skip_whitespace();
+
+        if !self.is_at_prelude_end() {
+            prelude.add_child(AtRulePreludeChild::MediaQueryList(self.media_query_list()));
+        }
+        Ok(())
+    }
+
+    /// Parses the url of `@import` and `@namespace`, written as url or string
+    fn url(&mut self) -> ParseResult<Url> {
+        match self.consume_any().token_type {
+            TokenType::Url(url) | TokenType::String(url) => Ok(Url::new(url)),
+            // `url("a.css")`
+            TokenType::Function(name) if name.eq_ignore_ascii_case("url") => {
+                self.skip_whitespace();
+                let TokenType::String(url) = self.consume_any().token_type else {
+                    return Err(self.error("expected url"));
+                };
+                self.consume_closing_paren()?;
+                Ok(Url::new(url))
+            }
+            _ => Err(self.error("expected url")),
+        }
+    }
+
+    /// ```txt
+    /// LayerList
+    ///     : LayerName [COMMA LayerName]*
+    ///     ;
+    /// ```
+    fn layer_list(&mut self) -> ParseResult<LayerList> {
+        let mut layers = LayerList::default();
+
+        loop {
+            layers.children.push(Layer::new(self.layer_name()?));
+            self.skip_whitespace();
+            if !self.is_next_token(TokenType::Comma) {
+                return Ok(layers);
+            }
+            self.consume_any();
+            self.skip_whitespace();
+        }
+    }
+
+    /// ```txt
+    /// LayerName
+    ///     : IDENT ["." IDENT]*
+    ///     ;
+    /// ```
+    fn layer_name(&mut self) -> ParseResult<String> {
+        let mut name = self
+            .ident()
+            .ok_or_else(|| self.error("expected layer name"))?;
+        self.consume_any();
+
+        while self.lookahead().is_delim('.') {
+            self.consume_any();
+            let part = self
+                .ident()
+                .ok_or_else(|| self.error("expected layer name"))?;
+            self.consume_any();
+            name.push('.');
+            name.push_str(&part);
+        }
+        Ok(name)
+    }
+
+    /// A list of media queries. Invalid media queries are replaced with `not all`.
+    ///
+    /// ```txt
+    /// MediaQueryList
+    ///     : [MediaQuery [COMMA MediaQuery]*]?
+    ///     ;
+    /// ```
+    fn media_query_list(&mut self) -> MediaQueryList {
+        let mut media_query_list = MediaQueryList::default();
+        self.skip_whitespace();
+        if self.is_at_prelude_end() {
+            return media_query_list;
+        }
+
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            match self.media_query() {
+                Ok(query) if self.is_at_media_query_end() => media_query_list.add_child(query),
+                _ => {
+                    self.position = start;
+                    self.skip_until(|token_type| {
+                        matches!(
+                            token_type,
+                            TokenType::Comma | TokenType::LCurly | TokenType::Semicolon
+                        )
+                    });
+                    media_query_list.add_child(MediaQuery::not_all());
+                }
+            }
+            if !self.is_next_token(TokenType::Comma) {
+                return media_query_list;
+            }
+            self.consume_any();
+        }
+    }
+
+    fn is_at_media_query_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.is_next_token(TokenType::Comma) || self.is_at_prelude_end()
+    }
+
+    /// ```txt
+    /// MediaQuery
+    ///     : MediaCondition
+    ///     | [not | only]? MediaType [and MediaConditionWithoutOr]?
+    ///     ;
+    /// ```
+    fn media_query(&mut self) -> ParseResult<MediaQuery> {
+        let mut query = MediaQuery::default();
+
+        let starts_with_type = match self.ident() {
+            Some(ident) if ident.eq_ignore_ascii_case("not") => {
+                matches!(self.peek_past_whitespace(1).token_type, TokenType::Ident(_))
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if !starts_with_type {
+            query.condition = Some(self.condition(Self::media_feature, true)?);
+            return Ok(query);
+        }
+
+        let mut media_type = self.ident().unwrap_or_default().to_ascii_lowercase();
+        self.consume_any();
+        if media_type == "not" || media_type == "only" {
+            query.modifier = Some(media_type);
+            self.skip_whitespace();
+            media_type = self
+                .ident()
+                .ok_or_else(|| self.error("expected media type"))?
+                .to_ascii_lowercase();
+            self.consume_any();
+        }
+        if ["not", "only", "and", "or", "layer"].contains(&media_type.as_str()) {
+            return Err(self.error("invalid media type"));
+        }
+        query.media_type = Some(media_type);
+
+        let start = self.position;
+        self.skip_whitespace();
+        if self.lookahead().is_ident("and") {
+            self.consume_any();
+            self.skip_whitespace();
+            query.condition = Some(self.condition(Self::media_feature, false)?);
+        } else {
+            self.position = start;
+        }
+        Ok(query)
+    }
+
+    /// ```txt
+    /// MediaFeature
+    ///     : LPAREN [MfName | MfName COLON MfValue | MfRange] RPAREN
+    ///     ;
+    ///
+    /// MfRange
+    ///     : MfName MfComparison MfValue
+    ///     | MfValue MfComparison MfName [MfComparison MfValue]?
+    ///     ;
+    /// ```
+    fn media_feature(&mut self) -> ParseResult<MediaFeature> {
+        if !self.is_next_token(TokenType::LParen) {
+            return Err(self.error("expected '('"));
+        }
+        self.consume_any();
+        self.skip_whitespace();
+
+        let feature = if let Some(name) = self.ident() {
+            let mut feature = MediaFeature::new(name.to_ascii_lowercase(), None);
+            self.consume_any();
+            self.skip_whitespace();
+            if self.is_next_token(TokenType::Colon) {
+                self.consume_any();
+                self.skip_whitespace();
+                feature.value = Some(self.media_feature_value()?);
+            } else if let Some(operator) = self.comparison() {
+                self.skip_whitespace();
+                let value = self.media_feature_value()?;
+                feature.range.push(MediaFeatureRange::new(operator, value));
+            }
+            feature
+        } else {
+            let value = self.media_feature_value()?;
+            self.skip_whitespace();
+            let operator = self
+                .comparison()
+                .ok_or_else(|| self.error("expected comparison"))?;
+            self.skip_whitespace();
+            let name = self
+                .ident()
+                .ok_or_else(|| self.error("expected media feature name"))?;
+            self.consume_any();
+
+            let mut feature = MediaFeature::new(name.to_ascii_lowercase(), None);
+            let flipped = flip_comparison(&operator).to_string();
+            feature.range.push(MediaFeatureRange::new(flipped, value));
+
+            self.skip_whitespace();
+            if let Some(second) = self.comparison() {
+                let is_same_direction = operator != "="
+                    && second != "="
+                    && operator.starts_with('<') == second.starts_with('<');
+                if !is_same_direction {
+                    return Err(self.error("invalid range"));
+                }
+                self.skip_whitespace();
+                let value = self.media_feature_value()?;
+                feature.range.push(MediaFeatureRange::new(second, value));
+            }
+            feature
+        };
+
+        self.consume_closing_paren()?;
+        Ok(feature)
+    }
+
+    /// ```txt
+    /// MfValue
+    ///     : NUMBER | DIMENSION | IDENT | Ratio | Function
+    ///     ;
+    /// ```
+    fn media_feature_value(&mut self) -> ParseResult<MediaFeatureValue> {
+        let token = self.consume_any();
+        let value = match token.token_type {
+            TokenType::Number(number) => {
+                let left = CssNumber::new(number.repr);
+                let start = self.position;
+                self.skip_whitespace();
+                if !self.lookahead().is_delim('/') {
+                    self.position = start;
+                    return Ok(MediaFeatureValue::Number(left));
+                }
+                self.consume_any();
+                self.skip_whitespace();
+                match self.consume_any().token_type {
+                    TokenType::Number(right) => {
+                        MediaFeatureValue::Ratio(Ratio::new(left, CssNumber::new(right.repr)))
+                    }
+                    _ => return Err(self.error("expected number")),
+                }
+            }
+            TokenType::Dimension(number, unit) => {
+                MediaFeatureValue::Dimension(Dimension::new(number.repr, Some(unit)))
+            }
+            TokenType::Ident(name) => MediaFeatureValue::Identifier(Identifier::new(name)),
+            TokenType::Function(name) => MediaFeatureValue::Function(self.function(name)),
+            _ => return Err(self.error("expected media feature value")),
+        };
+        Ok(value)
+    }
+
+    /// Consumes a comparison operator: `<`, `<=`, `>`, `>=` or `=`
+    fn comparison(&mut self) -> Option<String> {
+        let mut operator = match self.lookahead().token_type {
+            TokenType::Delim(c @ ('<' | '>' | '=')) => c.to_string(),
+            _ => return None,
+        };
+        self.consume_any();
+        if operator != "=" && self.lookahead().is_delim('=') {
+            self.consume_any();
+            operator.push('=');
+        }
+        Some(operator)
+    }
+
+    /// ```txt
+    /// SupportsFeature
+    ///     : LPAREN Declaration RPAREN
+    ///     | selector(ComplexSelector)
+    ///     ;
+    /// ```
+    fn supports_feature(&mut self) -> ParseResult<SupportsFeature> {
+        let feature = if self.is_function("selector") {
+            self.consume_any();
+            self.skip_whitespace();
+            let selector = self.complex_selector(false)?;
+            SupportsFeature::Selector(SelectorList::new(vec![selector]))
+        } else if self.is_next_token(TokenType::LParen) {
+            self.consume_any();
+            self.skip_whitespace();
+            SupportsFeature::Declaration(self.declaration_body()?)
+        } else {
+            return Err(self.error("expected '('"));
+        };
+
+        self.consume_closing_paren()?;
+        Ok(feature)
+    }
+
+    /// ```txt
+    /// DeclarationBody
+    ///     : IDENT COLON ValueList ["!" IMPORTANT]?
+    ///     ;
+    /// ```
+    fn declaration_body(&mut self) -> ParseResult<Declaration> {
+        let property = self
+            .ident()
+            .ok_or_else(|| self.error("expected property name"))?;
+        self.consume_any();
+        self.skip_whitespace();
+        if !self.is_next_token(TokenType::Colon) {
+            return Err(self.error("expected ':'"));
+        }
+        self.consume_any();
+        self.skip_whitespace();
+
+        let mut declaration = Declaration::new(property, self.value_ist());
+        declaration.set_important_as(self.important());
+        Ok(declaration)
+    }
+
+    /// Parses a boolean combination of tests, as used by media queries and `@supports`. Tests
+    /// that cannot be parsed are kept as "general enclosed".
+    ///
+    /// ```txt
+    /// Condition
+    ///     : not ConditionInParens
+    ///     | ConditionInParens [and ConditionInParens]*
+    ///     | ConditionInParens [or ConditionInParens]*
+    ///     ;
+    /// ```
+    fn condition<T>(
+        &mut self,
+        test: fn(&mut Self) -> ParseResult<T>,
+        allow_or: bool,
+    ) -> ParseResult<Condition<T>> {
+        if self.lookahead().is_ident("not") {
+            self.consume_any();
+            self.skip_whitespace();
+            let condition = self.condition_in_parens(test)?;
+            return Ok(Condition::Not(Box::new(condition)));
+        }
+
+        let mut children = vec![self.condition_in_parens(test)?];
+        let mut operator: Option<String> = None;
+        loop {
+            let start = self.position;
+            self.skip_whitespace();
+            let next = match self.ident() {
+                Some(ident) if ident.eq_ignore_ascii_case("and") => "and",
+                Some(ident) if allow_or && ident.eq_ignore_ascii_case("or") => "or",
+                _ => {
+                    self.position = start;
+                    break;
+                }
+            };
+            if operator.as_deref().is_some_and(|operator| operator != next) {
+                return Err(self.error("cannot mix 'and' and 'or' without parentheses"));
+            }
+            operator = Some(next.to_string());
+            self.consume_any();
+            self.skip_whitespace();
+            children.push(self.condition_in_parens(test)?);
+        }
+
+        Ok(match operator.as_deref() {
+            None => children.remove(0),
+            Some("and") => Condition::And(children),
+            Some(_) => Condition::Or(children),
+        })
+    }
+
+    /// ```txt
+    /// ConditionInParens
+    ///     : Test
+    ///     | LPAREN Condition RPAREN
+    ///     | GeneralEnclosed
+    ///     ;
+    /// ```
+    fn condition_in_parens<T>(
+        &mut self,
+        test: fn(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<Condition<T>> {
+        let start = self.position;
+        if let Ok(test) = test(self) {
+            return Ok(Condition::Test(test));
+        }
+
+        self.position = start;
+        if self.is_next_token(TokenType::LParen) {
+            self.consume_any();
+            self.skip_whitespace();
+            if let Ok(condition) = self.condition(test, true) {
+                if self.consume_closing_paren().is_ok() {
+                    return Ok(condition);
+                }
+            }
+            self.position = start;
+        }
+
+        match self.lookahead().token_type {
+            TokenType::LParen | TokenType::Function(_) => {
+                let start = self.lookahead().position.offset;
+                self.consume_any();
+                self.skip_until(|_| false);
+                self.consume_closing_paren()?;
+                let end = self.lookahead().position.offset;
+                let raw = self.tokenizer.source(start, end).trim_end().to_string();
+                Ok(Condition::GeneralEnclosed(Raw::new(raw)))
+            }
+            _ => Err(self.error("expected '('")),
+        }
+    }
+
+    ///```txt
+    /// SelectorList
+    ///     : ComplexSelector [COMMA ComplexSelector]*
+    ///     ;
+    /// ```
+    fn selector_list(&mut self) -> ParseResult<SelectorList> {
+        let mut selector_list = SelectorList::default();
+
+        loop {
+            self.skip_whitespace();
+            selector_list.add_child(self.complex_selector(false)?);
+            self.skip_whitespace();
+            if !self.is_next_token(TokenType::Comma) {
+                return Ok(selector_list);
+            }
+            self.consume_any();
+        }
+    }
+
+    /// A [forgiving selector list](https://drafts.csswg.org/selectors/#forgiving-selector), as
+    /// used by `:is()` and `:where()`. Invalid selectors are left out.
+    fn forgiving_selector_list(&mut self) -> SelectorList {
+        let mut selector_list = SelectorList::default();
+
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            match self.complex_selector(false) {
+                Ok(selector) if self.is_at_list_item_end() => selector_list.add_child(selector),
+                _ => {
+                    self.position = start;
+                    self.skip_list_item();
+                }
+            }
+            if !self.is_next_token(TokenType::Comma) {
+                return selector_list;
+            }
+            self.consume_any();
+        }
+    }
+
+    ///```txt
+    /// RelativeSelectorList
+    ///     : RelativeSelector [COMMA RelativeSelector]*
+    ///     ;
+    /// ```
+    fn relative_selector_list(&mut self) -> ParseResult<SelectorList> {
+        let mut selector_list = SelectorList::default();
+
+        loop {
+            self.skip_whitespace();
+            selector_list.add_child(self.complex_selector(true)?);
+            self.skip_whitespace();
+            if !self.is_next_token(TokenType::Comma) {
+                return Ok(selector_list);
+            }
+            self.consume_any();
+        }
+    }
+
+    ///```txt
+    /// CompoundSelectorList
+    ///     : CompoundSelector [COMMA CompoundSelector]*
+    ///     ;
+    /// ```
+    fn compound_selector_list(&mut self) -> ParseResult<SelectorList> {
+        let mut selector_list = SelectorList::default();
+
+        loop {
+            self.skip_whitespace();
+            let mut selector = ComplexSelector::default();
+            self.compound_selector(&mut selector.children)?;
+            selector_list.add_child(selector);
+            self.skip_whitespace();
+            if !self.is_next_token(TokenType::Comma) {
+                return Ok(selector_list);
+            }
+            self.consume_any();
+        }
+    }
+
+    ///```txt
+    /// ComplexSelector
+    ///     : CompoundSelector [Combinator? CompoundSelector]*
+    ///     ;
+    ///
+    /// RelativeSelector
+    ///     : Combinator? ComplexSelector
+    ///     ;
+    /// ```
+    fn complex_selector(&mut self, relative: bool) -> ParseResult<ComplexSelector> {
+        let mut selector = ComplexSelector::default();
+
+        if relative {
+            if let Some(combinator) = self.combinator() {
+                self.consume_any();
+                self.skip_whitespace();
+                selector.add_child(Selector::Combinator(Combinator::new(combinator)));
+            }
+        }
+
+        loop {
+            self.compound_selector(&mut selector.children)?;
+
+            let whitespace = self.skip_whitespace();
+            let combinator = match self.combinator() {
+                Some(combinator) => {
+                    self.consume_any();
+                    self.skip_whitespace();
+                    combinator
+                }
+                None if whitespace && self.starts_compound_selector() => " ".to_string(),
+                None => return Ok(selector),
+            };
+            selector.add_child(Selector::Combinator(Combinator::new(combinator)));
+        }
+    }
+
+    /// Returns the combinator (other than the descendant combinator) at the lookahead
+    fn combinator(&self) -> Option<String> {
+        match self.lookahead().token_type {
+            TokenType::Delim(c @ ('>' | '+' | '~')) => Some(c.to_string()),
+            TokenType::Column => Some("||".to_string()),
+            _ => None,
+        }
+    }
+
+    fn starts_compound_selector(&self) -> bool {
+        match &self.lookahead().token_type {
+            TokenType::Ident(_) | TokenType::Hash { .. } => true,
+            TokenType::LBracket | TokenType::Colon => true,
+            TokenType::Delim('*' | '.' | '&') => true,
+            TokenType::Delim('|') => self.namespaced_name(0).is_some(),
+            _ => false,
+        }
+    }
+
+    ///```txt
+    /// CompoundSelector
+    ///     : TypeSelector? [IdSelector | ClassSelector | AttributeSelector | PseudoClassSelector
+    ///         | PseudoElementSelector | NestingSelector]*
+    ///     ;
+    /// ```
+    fn compound_selector(&mut self, children: &mut Vec<Selector>) -> ParseResult<()> {
+        let start = children.len();
+
+        if let Some((namespace, name)) = self.namespaced_name(0) {
+            self.position += namespaced_name_len(&namespace);
+            children.push(Selector::TypeSelector(TypeSelector::new(namespace, name)));
+        }
+
+        loop {
+            let selector = match self.lookahead().token_type.clone() {
+                TokenType::Hash { value, is_id } => {
+                    if !is_id {
+                        return Err(self.error("invalid id selector"));
+                    }
+                    self.consume_any();
+                    Selector::IdSelector(IdSelector::new(value))
+                }
+                TokenType::Delim('.') => {
+                    self.consume_any();
+                    let name = self
+                        .ident()
+                        .ok_or_else(|| self.error("expected class name"))?;
+                    self.consume_any();
+                    Selector::ClassSelector(ClassSelector::new(name))
+                }
+                TokenType::Delim('&') => {
+                    self.consume_any();
+                    Selector::NestingSelector(NestingSelector)
+                }
+                TokenType::LBracket => Selector::AttributeSelector(self.attribute_selector()?),
+                TokenType::Colon => self.pseudo_selector()?,
+                _ => break,
+            };
+            children.push(selector);
+        }
+
+        if children.len() == start {
+            return Err(self.error("expected selector"));
+        }
+        Ok(())
+    }
+
+    /// Returns the (optional) namespace and name of a type selector or attribute name at the
+    /// offset from the lookahead: `name`, `*`, `ns|name`, `*|name` or `|name`
+    fn namespaced_name(&self, offset: usize) -> Option<(Option<String>, String)> {
+        let name = |token: &Token| match &token.token_type {
+            TokenType::Ident(name) => Some(name.clone()),
+            TokenType::Delim('*') => Some("*".to_string()),
+            _ => None,
+        };
+
+        let first = self.peek(offset);
+        if first.is_delim('|') {
+            return name(self.peek(offset + 1)).map(|name| (Some(String::new()), name));
+        }
+        let first = name(first)?;
+        if self.peek(offset + 1).is_delim('|') {
+            if let Some(second) = name(self.peek(offset + 2)) {
+                return Some((Some(first), second));
+            }
+        }
+        Some((None, first))
+    }
+
+    /// ```bnf
+    ///  AttributeSelector
+    ///     : LBRACKET [NamespacePrefix? IDENT] [AttributeMatcher [STRING | IDENT] [i | s]?]?
+    ///         RBRACKET
+    ///     ;
+    /// ```
+    fn attribute_selector(&mut self) -> ParseResult<AttributeSelector> {
+        self.consume_any();
+        self.skip_whitespace();
+
+        let (namespace, name) = match self.namespaced_name(0) {
+            Some((namespace, name)) if name != "*" => {
+                self.position += namespaced_name_len(&namespace);
+                (namespace, name)
+            }
+            _ => return Err(self.error("expected attribute name")),
+        };
+        self.skip_whitespace();
+
+        let mut selector = AttributeSelector {
+            namespace,
+            name: Identifier::new(name),
+            matcher: None,
+            value: AttributeSelectorValue::None,
+            flags: None,
+        };
+
+        if !self.is_next_token(TokenType::RBracket) {
+            let matcher = match self.lookahead().token_type {
+                TokenType::Delim('=') => "=",
+                TokenType::IncludeMatch => "~=",
+                TokenType::DashMatch => "|=",
+                TokenType::PrefixMatch => "^=",
+                TokenType::SuffixMatch => "$=",
+                TokenType::SubstringMatch => "*=",
+                _ => return Err(self.error("expected attribute matcher")),
+            };
+            selector.matcher = Some(matcher.to_string());
+            self.consume_any();
+            self.skip_whitespace();
+
+            selector.value = match self.consume_any().token_type {
+                TokenType::String(value) => AttributeSelectorValue::String(CssString::new(value)),
+                TokenType::Ident(value) => {
+                    AttributeSelectorValue::Identifier(Identifier::new(value))
+                }
+                _ => return Err(self.error("expected attribute value")),
+            };
+            self.skip_whitespace();
+
+            if let Some(flags) = self.ident() {
+                if !flags.eq_ignore_ascii_case("i") && !flags.eq_ignore_ascii_case("s") {
+                    return Err(self.error("invalid attribute selector flags"));
+                }
+                selector.flags = Some(flags.to_ascii_lowercase());
+                self.consume_any();
+                self.skip_whitespace();
+            }
+        }
+
+        if !self.is_next_token(TokenType::RBracket) {
+            return Err(self.error("expected ']'"));
+        }
+        self.consume_any();
+        Ok(selector)
+    }
+
+    /// ```bnf
+    ///  PseudoSelector
+    ///     : COLON COLON? [IDENT | FUNCTION PseudoSelectorArgument RPAREN]
+    ///     ;
+    /// ```
+    fn pseudo_selector(&mut self) -> ParseResult<Selector> {
+        self.consume_any();
+        let mut is_element = false;
+        if self.is_next_token(TokenType::Colon) {
+            self.consume_any();
+            is_element = true;
+        }
+
+        let (name, argument) = match self.consume_any().token_type {
+            TokenType::Ident(name) => (name, None),
+            TokenType::Function(name) => {
+                let argument = self.pseudo_selector_argument(&name, is_element)?;
+                self.skip_whitespace();
+                if !self.is_next_token(TokenType::RParen) {
+                    return Err(self.error("expected ')'"));
+                }
+                self.consume_any();
+                (name, Some(argument))
+            }
+            _ => return Err(self.error("expected pseudo-class name")),
+        };
+
+        let is_legacy = LEGACY_PSEUDO_ELEMENTS
+            .iter()
+            .any(|legacy| legacy.eq_ignore_ascii_case(&name));
+        if is_element || (is_legacy && argument.is_none()) {
+            return Ok(Selector::PseudoElementSelector(PseudoElementSelector::new(
+                name, argument,
+            )));
+        }
+        Ok(Selector::PseudoClassSelector(PseudoClassSelector::new(
+            name, argument,
+        )))
+    }
+
+    fn pseudo_selector_argument(
+        &mut self,
+        name: &str,
+        is_element: bool,
+    ) -> ParseResult<PseudoSelectorArgument> {
+        self.skip_whitespace();
+        let name = name.to_ascii_lowercase();
+
+        let argument = match name.as_str() {
+            "slotted" if is_element => PseudoSelectorArgument::SelectorList(self.selector_list()?),
+            _ if is_element => PseudoSelectorArgument::Raw(self.raw_argument()),
+            "not" => PseudoSelectorArgument::SelectorList(self.selector_list()?),
+            "is" | "where" | "matches" | "-webkit-any" | "-moz-any" => {
+                PseudoSelectorArgument::SelectorList(self.forgiving_selector_list())
+            }
+            "has" => PseudoSelectorArgument::SelectorList(self.relative_selector_list()?),
+            "host" | "host-context" => {
+                PseudoSelectorArgument::SelectorList(self.compound_selector_list()?)
+            }
+            "nth-child" | "nth-last-child" => PseudoSelectorArgument::Nth(self.nth(true)?),
+            "nth-of-type" | "nth-last-of-type" | "nth-col" | "nth-last-col" => {
+                PseudoSelectorArgument::Nth(self.nth(false)?)
+            }
+            _ => match self.ident() {
+                Some(ident) if self.peek_past_whitespace(1).token_type == TokenType::RParen => {
+                    self.consume_any();
+                    PseudoSelectorArgument::Identifier(Identifier::new(ident))
+                }
+                _ => PseudoSelectorArgument::Raw(self.raw_argument()),
+            },
+        };
+        Ok(argument)
+    }
+
+    /// ```bnf
+    ///  Nth
+    ///     : [AnPlusB | odd | even] [of SelectorList]?
+    ///     ;
+    /// ```
+    fn nth(&mut self, with_selector: bool) -> ParseResult<Nth> {
+        let nth = match self.ident() {
+            Some(ident)
+                if ident.eq_ignore_ascii_case("odd") || ident.eq_ignore_ascii_case("even") =>
+            {
+                self.consume_any();
+                NthValue::Identifier(Identifier::new(ident.to_ascii_lowercase()))
+            }
+            _ => NthValue::AnPlusB(self.an_plus_b()?),
+        };
+
+        self.skip_whitespace();
+        let mut selector = None;
+        if with_selector && self.lookahead().is_ident("of") {
+            self.consume_any();
+            self.skip_whitespace();
+            selector = Some(self.selector_list()?);
+        }
+        Ok(Nth::new(nth, selector))
+    }
+
+    /// Parses the [An+B microsyntax](https://www.w3.org/TR/css-syntax-3/#anb-microsyntax)
+    fn an_plus_b(&mut self) -> ParseResult<AnPlusB> {
+        let token = self.consume_any();
+        let (a, rest) = match token.token_type {
+            TokenType::Number(number) if number.is_integer() => {
+                return Ok(AnPlusB::new(0, number.int_value.unwrap_or_default()));
+            }
+            TokenType::Dimension(number, unit) if number.is_integer() => {
+                (number.int_value.unwrap_or_default(), unit)
+            }
+            TokenType::Ident(ident) => match ident.strip_prefix('-') {
+                Some(rest) => (-1, rest.to_string()),
+                None => (1, ident),
+            },
+            TokenType::Delim('+') => match self.consume_any().token_type {
+                TokenType::Ident(ident) if !ident.starts_with('-') => (1, ident),
+                _ => return Err(self.error("invalid An+B value")),
+            },
+            _ => return Err(self.error("invalid An+B value")),
+        };
+
+        let rest = rest.to_ascii_lowercase();
+        let Some(rest) = rest.strip_prefix('n') else {
+            return Err(self.error("invalid An+B value"));
+        };
+
+        // Forms like `n-3` and `n- 3`
+        if let Some(digits) = rest.strip_prefix('-') {
+            if !digits.is_empty() {
+                return match digits.parse::<i32>() {
+                    Ok(b) if digits.bytes().all(|byte| byte.is_ascii_digit()) => {
+                        Ok(AnPlusB::new(a, -b))
+                    }
+                    _ => Err(self.error("invalid An+B value")),
+                };
+            }
+            self.skip_whitespace();
+            let b = self.unsigned_integer()?;
+            return Ok(AnPlusB::new(a, -b));
+        }
+        if !rest.is_empty() {
+            return Err(self.error("invalid An+B value"));
+        }
+
+        // Forms like `n`, `n+3`, `n + 3` and `n -3`
+        let start = self.position;
+        self.skip_whitespace();
+        match self.lookahead().token_type.clone() {
+            TokenType::Number(number) if number.has_sign && number.is_integer() => {
+                self.consume_any();
+                Ok(AnPlusB::new(a, number.int_value.unwrap_or_default()))
+            }
+            TokenType::Delim(sign @ ('+' | '-')) => {
+                self.consume_any();
+                self.skip_whitespace();
+                let b = self.unsigned_integer()?;
+                Ok(AnPlusB::new(a, if sign == '-' { -b } else { b }))
+            }
+            _ => {
+                self.position = start;
+                Ok(AnPlusB::new(a, 0))
+            }
+        }
+    }
 
-impl CSS3Parser {
-    pub fn new() -> CSS3Parser {
-        CSS3Parser {
-            tokenizer: Tokenizer::new(),
-            lookahead: None,
-            raw: "".to_string(),
+    fn unsigned_integer(&mut self) -> ParseResult<i32> {
+        match self.consume_any().token_type {
+            TokenType::Number(number) if number.is_integer() && !number.has_sign => {
+                Ok(number.int_value.unwrap_or_default())
+            }
+            _ => Err(self.error("expected integer")),
         }
     }
 
-    pub fn parse(&mut self, raw: &str) -> StyleSheet {
-        self.raw = raw.to_string();
-        self.tokenizer.init(raw);
-        self.lookahead = self.tokenizer.get_next_token();
-        self.style_sheet()
+    /// Consumes the tokens up to the closing parenthesis and returns them as written
+    fn raw_argument(&mut self) -> Raw {
+        let start = self.lookahead().position.offset;
+        self.skip_until(|_| false);
+        let end = self.lookahead().position.offset;
+        Raw::new(self.tokenizer.source(start, end).trim_end().to_string())
     }
 
-    /// ```txt
-    /// SytleSheet
-    ///     : RulesList
+    /// ```bnf
+    ///  Block
+    ///     : LCURLY [AtRule | DeclarationList]* RCURLY
     ///     ;
     /// ```
-    fn style_sheet(&mut self) -> StyleSheet {
-        StyleSheet::new(self.rules_list())
+    fn block(&mut self) -> Block {
+        let mut block = Block::default();
+
+        self.consume_any();
+        loop {
+            self.skip_whitespace();
+            match self.lookahead().token_type {
+                TokenType::RCurly | TokenType::EOF => break,
+                TokenType::AtKeyword(_) => {
+                    if let Some(at_rule) = self.at_rule() {
+                        block.add_child(BlockChild::AtRule(at_rule));
+                    }
+                }
+                _ => block.add_child(BlockChild::DeclarationList(self.declaration_list())),
+            }
+        }
+        self.close_block();
+
+        block
     }
 
-    /// ```txt
-    /// RulesList
-    ///     : [Rule | AtRule]+
+    /// The block of a grouping at-rule like `@media`
+    ///
+    /// ```bnf
+    ///  RulesBlock
+    ///     : LCURLY [Rule | AtRule]* RCURLY
     ///     ;
     /// ```
-    fn rules_list(&mut self) -> Vec<StyleSheetRule> {
-        // note: support only "Rule" for now
-        let mut rules: Vec<StyleSheetRule> = Vec::new();
+    fn rules_block(&mut self) -> Block {
+        let mut block = Block::default();
 
-        while !self.is_next_token(TokenType::LCurly) & self.lookahead.is_some() {
-            rules.push(StyleSheetRule::Rule(self.rule()));
+        self.consume_any();
+        loop {
+            self.skip_whitespace();
+            match self.lookahead().token_type {
+                TokenType::RCurly | TokenType::EOF => break,
+                _ => match self.rule_or_at_rule(true) {
+                    Some(StyleSheetRule::Rule(rule)) => block.add_child(BlockChild::Rule(rule)),
+                    Some(StyleSheetRule::AtRule(rule)) => block.add_child(BlockChild::AtRule(rule)),
+                    None => {}
+                },
+            }
         }
+        self.close_block();
 
-        rules
+        block
     }
 
-    /// ```txt
-    /// Rule
-    ///     : SelectorList Block
+    /// ```bnf
+    ///  KeyframesBlock
+    ///     : LCURLY [KeyframeSelector [COMMA KeyframeSelector]* Block]* RCURLY
     ///     ;
     /// ```
-    fn rule(&mut self) -> Rule {
-        let selectors = self.selector_list();
-        let block = self.block();
-        Rule::new(selectors, block)
-    }
+    fn keyframes_block(&mut self) -> Block {
+        let mut block = Block::default();
 
-    ///```txt
-    /// SelectorList
-    ///     : [Selector]*
-    ///     ;
-    /// ```
-    fn selector_list(&mut self) -> SelectorList {
-        let mut selector_list = SelectorList::default();
+        self.consume_any();
+        loop {
+            self.skip_whitespace();
+            if matches!(
+                self.lookahead().token_type,
+                TokenType::RCurly | TokenType::EOF
+            ) {
+                break;
+            }
 
-        while !self.is_next_token(TokenType::LCurly) {
-            selector_list.add_child(self.selector())
-        }
+            let start = self.position;
+            self.skip_component_values(|token_type| {
+                matches!(token_type, TokenType::LCurly | TokenType::RCurly)
+            });
+            if !self.is_next_token(TokenType::LCurly) {
+                self.log(self.error("expected '{' after keyframe selector"));
+                continue;
+            }
+            let block_start = self.position;
 
-        selector_list
-    }
+            self.position = start;
+            let selectors = self.keyframe_selectors(block_start);
+            self.position = block_start;
+            let rule_block = self.block();
 
-    ///```txt
-    /// Selector
-    ///     : IdSelector
-    ///     | ClassSelector
-    ///     | AttributeSelector
-    ///     | TypeSelector
-    ///     | NestingSelector
-    ///     ;
-    /// ```
-    fn selector(&mut self) -> Selector {
-        // note: support class & id selectors for now
-        Selector::IdSelector(self.id_selector())
-    }
+            match selectors {
+                Ok(selectors) => {
+                    let rule = KeyframeRule::new(selectors, rule_block);
+                    block.add_child(BlockChild::KeyframeRule(rule));
+                }
+                Err(error) => self.log(error),
+            }
+        }
+        self.close_block();
 
-    /// ```bnf
-    ///  IdSelector
-    ///     : HASH IDENT
-    ///     ;   
-    /// ```
-    fn id_selector(&mut self) -> IdSelector {
-        self.consume(TokenType::Hash);
-        let name = self.consume(TokenType::Ident).value;
-        IdSelector::new(name)
+        block
     }
 
-    /// ```bnf
-    ///  Block
-    ///     : LCURLY [Rule | AtRule | DeclarationList]* RCURLY
-    ///     ;   
-    /// ```
-    fn block(&mut self) -> Block {
-        // note: add support for 'DeclarationList' for now
-        let mut block = Block::default();
+    /// Parses the selectors of a keyframe rule, which must end at the token index
+    fn keyframe_selectors(&mut self, end: usize) -> ParseResult<Vec<Percentage>> {
+        let mut selectors = vec![];
+
+        loop {
+            let selector = match &self.lookahead().token_type {
+                TokenType::Percentage(number) if (0.0..=100.0).contains(&number.value) => {
+                    number.repr.clone()
+                }
+                TokenType::Ident(name) if name.eq_ignore_ascii_case("from") => "0".to_string(),
+                TokenType::Ident(name) if name.eq_ignore_ascii_case("to") => "100".to_string(),
+                _ => return Err(self.error("invalid keyframe selector")),
+            };
+            self.consume_any();
+            selectors.push(Percentage::new(selector));
 
-        self.consume(TokenType::LCurly);
+            self.skip_whitespace();
+            if !self.is_next_token(TokenType::Comma) {
+                break;
+            }
+            self.consume_any();
+            self.skip_whitespace();
+        }
 
-        while !self.is_next_token(TokenType::RCurly) {
-            block.add_child(BlockChild::DeclarationList(self.declaration_list()))
+        if self.position != end {
+            return Err(self.error("unexpected token in keyframe selector"));
         }
+        Ok(selectors)
+    }
 
-        self.consume(TokenType::RCurly);
+    /// The block of an unknown at-rule, kept as written
+    fn raw_block(&mut self) -> Block {
+        self.consume_any();
+        let start = self.lookahead().position.offset;
+        self.skip_component_values(|token_type| *token_type == TokenType::RCurly);
+        let end = self.lookahead().position.offset;
+        self.close_block();
 
-        block
+        let raw = self.tokenizer.source(start, end).trim().to_string();
+        Block::new(vec![BlockChild::Raw(Raw::new(raw))])
     }
 
     /// ```bnf
     ///  DeclarationList
-    ///     : [Declaration]*
-    ///     ;   
+    ///     : [Declaration | SEMICOLON]*
+    ///     ;
     /// ```
     fn declaration_list(&mut self) -> DeclarationList {
         let mut declaration_list = DeclarationList::default();
 
-        while !self.is_next_token(TokenType::RCurly) {
-            declaration_list.add_child(self.declaration())
+        loop {
+            self.skip_whitespace();
+            match self.lookahead().token_type {
+                TokenType::RCurly | TokenType::EOF | TokenType::AtKeyword(_) => {
+                    return declaration_list
+                }
+                TokenType::Semicolon => {
+                    self.consume_any();
+                }
+                _ => {
+                    if let Some(declaration) = self.declaration() {
+                        declaration_list.add_child(declaration);
+                    }
+                }
+            }
         }
-
-        declaration_list
     }
 
+    /// Parses a declaration up to the next semicolon (or the end of the block). Invalid
+    /// declarations are reported and skipped.
+    ///
     /// ```bnf
     ///  Declaration
-    ///     : IDENT COLON ValueList IMPORTANT SEMICOLON
-    ///     ;   
+    ///     : DeclarationBody [SEMICOLON | RCURLY]
+    ///     ;
     /// ```
-    fn declaration(&mut self) -> Declaration {
-        let mut declaration = Declaration::default();
+    fn declaration(&mut self) -> Option<Declaration> {
+        let start = self.position;
+        self.skip_component_values(|token_type| {
+            matches!(token_type, TokenType::Semicolon | TokenType::RCurly)
+        });
+        let end = self.position;
 
-        declaration.set_property(self.consume(TokenType::Ident).value);
-        self.consume(TokenType::Colon);
-        declaration.set_value(self.value_ist());
+        self.position = start;
+        let declaration = self.declaration_body().and_then(|declaration| {
+            self.skip_whitespace();
+            if self.position != end {
+                return Err(self.error("unexpected token in declaration"));
+            }
+            let invalid = self.tokens[start..end]
+                .iter()
+                .find(|token| matches!(token.token_type, TokenType::BadString | TokenType::BadUrl));
+            match invalid {
+                Some(token) => Err(ParseError {
+                    message: "invalid string or url in declaration".to_string(),
+                    position: token.position,
+                }),
+                None => Ok(declaration),
+            }
+        });
 
-        if self.is_next_token(TokenType::Important) {
-            self.consume(TokenType::Important);
-            declaration.set_important_as(true);
+        self.position = end;
+        if self.is_next_token(TokenType::Semicolon) {
+            self.consume_any();
         }
 
-        self.consume(TokenType::Semicolon);
+        match declaration {
+            Ok(declaration) => Some(declaration),
+            Err(error) => {
+                self.log(error);
+                None
+            }
+        }
+    }
 
-        declaration
+    /// Consumes `!important`, if it is next
+    fn important(&mut self) -> bool {
+        if !self.lookahead().is_delim('!') || !self.peek_past_whitespace(1).is_ident("important") {
+            return false;
+        }
+        self.consume_any();
+        self.skip_whitespace();
+        self.consume_any();
+        self.skip_whitespace();
+        true
     }
 
     /// ```bnf
     ///  ValueList
     ///     : [Value]*
-    ///     ;   
+    ///     ;
     /// ```
     fn value_ist(&mut self) -> ValueList {
         let mut value_list = ValueList::default();
 
-        while !self.is_next_tokens(vec![TokenType::Semicolon, TokenType::Important]) {
-            value_list.add_child(self.value());
+        loop {
+            match self.lookahead().token_type {
+                TokenType::Semicolon
+                | TokenType::RCurly
+                | TokenType::RParen
+                | TokenType::RBracket
+                | TokenType::EOF
+                | TokenType::Delim('!') => return value_list,
+                _ => value_list.add_child(self.value()),
+            }
+            self.skip_whitespace();
         }
-
-        value_list
     }
 
     /// ```bnf
     ///  Value
-    ///     : [Dimension | Identifier | Function]
-    ///     ;   
+    ///     : [Dimension | Identifier | Function | Percentage | Hash | STRING | URL
+    ///         | UNICODE-RANGE | Operator | SimpleBlock]
+    ///     ;
     /// ```
     fn value(&mut self) -> Value {
-        // note: support only "Identifier" and "Dimension" for now
-
-        if self.is_next_token(TokenType::Ident) {
-            return Value::Identifier(self.identifier());
+        let token = self.consume_any();
+        let start = token.position.offset;
+        match token.token_type {
+            TokenType::Ident(name) => Value::Identifier(Identifier::new(name)),
+            TokenType::Number(number) => Value::Dimension(Dimension::new(number.repr, None)),
+            TokenType::Dimension(number, unit) => {
+                Value::Dimension(Dimension::new(number.repr, Some(unit)))
+            }
+            TokenType::Percentage(number) => Value::Percentage(Percentage::new(number.repr)),
+            TokenType::Hash { value, .. } => Value::Hash(Hash::new(value)),
+            TokenType::String(value) => Value::String(CssString::new(value)),
+            TokenType::Url(value) => Value::Url(Url::new(value)),
+            TokenType::Function(name) => Value::Function(self.function(name)),
+            TokenType::LParen => {
+                let inner = self.position;
+                self.skip_whitespace();
+                let children = self.value_ist();
+                if self.is_next_token(TokenType::RParen) {
+                    self.consume_any();
+                    return Value::Parentheses(children);
+                }
+                // Keep a block with semicolons (in a custom property) as written
+                self.position = inner;
+                self.skip_component_values(|token_type| *token_type == TokenType::RParen);
+                self.consume_any();
+                Value::Raw(Raw::new(self.consumed_source(start)))
+            }
+            TokenType::LBracket | TokenType::LCurly => {
+                let closing = match token.token_type {
+                    TokenType::LBracket => TokenType::RBracket,
+                    _ => TokenType::RCurly,
+                };
+                self.skip_component_values(|token_type| *token_type == closing);
+                self.consume_any();
+                Value::Raw(Raw::new(self.consumed_source(start)))
+            }
+            TokenType::UnicodeRange(..) => {
+                Value::UnicodeRange(UnicodeRange::new(self.consumed_source(start)))
+            }
+            _ => Value::Operator(Operator::new(self.consumed_source(start))),
         }
-
-        Value::Dimension(self.dimension())
     }
 
+    /// Parses the arguments of a function, after the function token
+    ///
     /// ```bnf
-    ///  Identifier
-    ///     : IDENT
-    ///     ;   
+    ///  Function
+    ///     : FUNCTION ValueList RPAREN
+    ///     ;
     /// ```
-    fn identifier(&mut self) -> Identifier {
-        Identifier::new(self.consume(TokenType::Ident).value)
+    fn function(&mut self, name: String) -> Function {
+        self.skip_whitespace();
+        let children = self.value_ist();
+        if self.is_next_token(TokenType::RParen) {
+            self.consume_any();
+        }
+        Function::new(name, children)
     }
 
-    /// ```bnf
-    ///  Dimension
-    ///     : NUMBER IDENT
-    ///     ;   
-    /// ```
-    fn dimension(&mut self) -> Dimension {
-        let value = self.consume(TokenType::Number).value;
+    fn lookahead(&self) -> &Token {
+        self.peek(0)
+    }
 
-        let unit = if self.is_next_token(TokenType::Ident) {
-            Some(self.consume(TokenType::Ident).value)
-        } else {
-            None
-        };
+    /// Returns the token at the offset from the lookahead. Past the end, the EOF token is
+    /// returned.
+    fn peek(&self, offset: usize) -> &Token {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.position + offset).min(last)]
+    }
+
+    /// Returns the token after the offset, skipping whitespace
+    fn peek_past_whitespace(&self, offset: usize) -> &Token {
+        let mut offset = offset;
+        while self.peek(offset).is_whitespace() {
+            offset += 1;
+        }
+        self.peek(offset)
+    }
+
+    /// Returns the name of the lookahead token if it is an ident
+    fn ident(&self) -> Option<String> {
+        match &self.lookahead().token_type {
+            TokenType::Ident(name) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    /// Skips whitespace tokens. Returns true if there was whitespace.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while self.lookahead().is_whitespace() {
+            self.position += 1;
+        }
+        self.position > start
+    }
+
+    fn is_at_list_item_end(&mut self) -> bool {
+        self.skip_whitespace();
+        matches!(
+            self.lookahead().token_type,
+            TokenType::Comma | TokenType::RParen | TokenType::EOF
+        )
+    }
 
-        Dimension::new(value, unit)
+    /// Skips tokens up to the next comma or closing parenthesis that is not nested in a block
+    fn skip_list_item(&mut self) {
+        self.skip_until(|token_type| *token_type == TokenType::Comma)
+    }
+
+    /// Skips tokens up to the first token that is not nested in a block and matches `stop`, or
+    /// up to the token that closes the current block
+    fn skip_until(&mut self, stop: fn(&TokenType) -> bool) {
+        let mut depth = 0;
+        loop {
+            let token_type = &self.lookahead().token_type;
+            if *token_type == TokenType::EOF || (depth == 0 && stop(token_type)) {
+                return;
+            }
+            match token_type {
+                TokenType::RParen | TokenType::RBracket | TokenType::RCurly if depth == 0 => return,
+                TokenType::LParen
+                | TokenType::LBracket
+                | TokenType::LCurly
+                | TokenType::Function(_) => depth += 1,
+                TokenType::RParen | TokenType::RBracket | TokenType::RCurly => depth -= 1,
+                _ => {}
+            }
+            self.position += 1;
+        }
     }
 
-    fn consume(&mut self, token_type: TokenType) -> Token {
-        if let Some(token) = self.lookahead.clone() {
-            if token.token_type != token_type {
-                panic!(
-                    "Unexpected token: '{:?}', expected: '{:?}'. Got '{}' at '{}'",
-                    token.token_type, token_type, token.value, self.tokenizer.cursor
-                )
+    /// Skips component values up to the first token that is not nested in a block or function
+    /// and matches `stop`. Unlike [`CSS3Parser::skip_until`], closing tokens without an opening
+    /// token are skipped as well.
+    fn skip_component_values(&mut self, stop: impl Fn(&TokenType) -> bool) {
+        let mut closing: Vec<TokenType> = vec![];
+        loop {
+            let token_type = &self.lookahead().token_type;
+            if *token_type == TokenType::EOF || (closing.is_empty() && stop(token_type)) {
+                return;
+            }
+            match token_type {
+                TokenType::LParen | TokenType::Function(_) => closing.push(TokenType::RParen),
+                TokenType::LBracket => closing.push(TokenType::RBracket),
+                TokenType::LCurly => closing.push(TokenType::RCurly),
+                token_type if Some(token_type) == closing.last() => {
+                    closing.pop();
+                }
+                _ => {}
             }
+            self.position += 1;
+        }
+    }
+
+    /// Returns true if the lookahead is a function token with the name (case-insensitive)
+    fn is_function(&self, name: &str) -> bool {
+        matches!(&self.lookahead().token_type, TokenType::Function(function) if function.eq_ignore_ascii_case(name))
+    }
 
-            // Advance to the next token
-            self.lookahead = self.tokenizer.get_next_token();
+    fn consume_closing_paren(&mut self) -> ParseResult<()> {
+        self.skip_whitespace();
+        if !self.is_next_token(TokenType::RParen) {
+            return Err(self.error("expected ')'"));
+        }
+        self.consume_any();
+        Ok(())
+    }
 
-            println!("next token: {:#?}", self.lookahead);
-            return token.clone();
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            position: self.lookahead().position,
         }
+    }
 
-        panic!("Unexpected end of input, expected: {:?}", token_type)
+    fn log(&mut self, error: ParseError) {
+        self.error_logger.add_error(error.position, &error.message);
     }
 
-    fn is_next_token(&self, token_type: TokenType) -> bool {
-        if let Some(token) = self.lookahead.clone() {
-            return token.token_type == token_type;
+    /// Consumes the `}` that closes a block. A block that is still open at the end of the input
+    /// is closed, with an error.
+    fn close_block(&mut self) {
+        if self.is_next_token(TokenType::RCurly) {
+            self.consume_any();
+        } else {
+            self.log(self.error("unexpected end of input in block"));
         }
+    }
 
-        false
+    /// Returns the input from the offset up to the end of the last consumed token, without the
+    /// comments after it
+    fn consumed_source(&self, start: usize) -> String {
+        let end = self.token_ends[self.position.saturating_sub(1)];
+        self.tokenizer.source(start, end)
     }
 
-    fn is_next_tokens(&self, token_types: Vec<TokenType>) -> bool {
-        for token_type in token_types {
-            if self.is_next_token(token_type) {
-                return true;
-            }
+    /// Consumes the next token, whatever it is
+    fn consume_any(&mut self) -> Token {
+        let token = self.lookahead().clone();
+        if !token.is_eof() {
+            self.position += 1;
         }
-        false
+        token
+    }
+
+    fn is_next_token(&self, token_type: TokenType) -> bool {
+        self.lookahead().token_type == token_type
     }
+}
 
-    fn get_next_token_type(&self) -> Option<TokenType> {
-        if let Some(token) = self.lookahead.clone() {
-            return Some(token.token_type);
+/// Returns true for the rules that may come before `@import`: `@charset`, `@import` and
+/// `@layer` statements
+fn may_precede_import(rule: &StyleSheetRule) -> bool {
+    match rule {
+        StyleSheetRule::AtRule(rule) => {
+            let name = rule.name.to_ascii_lowercase();
+            name == "charset" || name == "import" || (name == "layer" && rule.block.is_none())
         }
+        StyleSheetRule::Rule(_) => false,
+    }
+}
 
-        None
+/// Returns the name of the at-rule without its vendor prefix, like `keyframes` for
+/// `-webkit-keyframes`
+fn unprefixed(name: &str) -> &str {
+    match name.strip_prefix('-').and_then(|name| name.split_once('-')) {
+        Some((_, name)) => name,
+        None => name,
+    }
+}
+
+/// Returns the number of tokens of a (namespaced) name with the namespace
+fn namespaced_name_len(namespace: &Option<String>) -> usize {
+    match namespace.as_deref() {
+        None => 1,
+        Some("") => 2,
+        Some(_) => 3,
     }
 }
 
@@ -275,12 +1798,20 @@ impl CSS3Parser {
 mod test {
     use super::*;
 
+    /// Parses the selector list and serializes it again
+    fn selectors(raw: &str) -> String {
+        match CSS3Parser::new().parse_selector_list(raw) {
+            Ok(selector_list) => selector_list.to_string(),
+            Err(error) => format!("error: {}", error.message),
+        }
+    }
+
     #[test]
     fn parse_css() {
         let mut parser = CSS3Parser::new();
         let style_sheet = parser.parse(
             r#"
-            
+
                 #header {
                     display: flex;
                     width: 100px;
@@ -292,9 +1823,9 @@ mod test {
         assert_eq!(
             style_sheet,
             StyleSheet::new(vec![StyleSheetRule::Rule(Rule::new(
-                SelectorList::new(vec![Selector::IdSelector(IdSelector::new(
-                    "header".to_string()
-                ))]),
+                SelectorList::new(vec![ComplexSelector::new(vec![Selector::IdSelector(
+                    IdSelector::new("header".to_string())
+                )])]),
                 Block::new(vec![BlockChild::DeclarationList(DeclarationList::new(
                     vec![
                         Declaration::new(
@@ -323,4 +1854,423 @@ mod test {
             ))])
         )
     }
+
+    #[test]
+    fn simple_selectors_and_combinators() {
+        assert_eq!(selectors("div"), "div");
+        assert_eq!(selectors("*.a#b.c"), "*.a#b.c");
+        assert_eq!(
+            selectors(" a , b>c  d+e~f || g "),
+            "a, b > c d + e ~ f || g"
+        );
+        assert_eq!(selectors("ul li>a"), "ul li > a");
+        assert_eq!(selectors("& > .child"), "& > .child");
+        assert_eq!(selectors("svg|rect *|* |a ns|*"), "svg|rect *|* |a ns|*");
+
+        let list = CSS3Parser::new().parse_selector_list("a b, c").unwrap();
+        assert_eq!(list.children.len(), 2);
+        assert_eq!(
+            list.children[0].children[1],
+            Selector::Combinator(Combinator::new(" ".to_string()))
+        );
+
+        assert_eq!(selectors("#1a"), "error: invalid id selector");
+        assert_eq!(selectors("a,"), "error: expected selector");
+        assert_eq!(selectors("a >"), "error: expected selector");
+        assert_eq!(selectors(". a"), "error: expected class name");
+        assert_eq!(selectors("a b)"), "error: unexpected token after selector");
+    }
+
+    #[test]
+    fn attribute_selectors() {
+        assert_eq!(selectors("[href]"), "[href]");
+        assert_eq!(selectors("[ lang |= en ]"), "[lang|=en]");
+        assert_eq!(selectors("a[href^='http' i]"), "a[href^=\"http\" i]");
+        assert_eq!(selectors("[a=\"b\\\"c\" S]"), "[a=\"b\\\"c\" s]");
+        assert_eq!(selectors("[a~=b][c$=d][e*=f]"), "[a~=b][c$=d][e*=f]");
+        assert_eq!(selectors("[xlink|href][*|a][|b]"), "[xlink|href][*|a][|b]");
+
+        assert_eq!(
+            selectors("[a=b x]"),
+            "error: invalid attribute selector flags"
+        );
+        assert_eq!(selectors("[a=]"), "error: expected attribute value");
+        assert_eq!(selectors("[a b]"), "error: expected attribute matcher");
+        assert_eq!(selectors("[*]"), "error: expected attribute name");
+    }
+
+    #[test]
+    fn pseudo_selectors() {
+        assert_eq!(selectors("a:hover::before"), "a:hover::before");
+        assert_eq!(selectors("p:first-line"), "p::first-line");
+        assert_eq!(selectors(":not(.a, b > c)"), ":not(.a, b > c)");
+        assert_eq!(selectors(":is(a, 1x, b, :not(#1))"), ":is(a, b)");
+        assert_eq!(selectors(":where(#1)"), ":where()");
+        assert_eq!(selectors("a:has(> img, + p b)"), "a:has(> img, + p b)");
+        assert_eq!(selectors(":lang(en) :dir( rtl )"), ":lang(en) :dir(rtl)");
+        assert_eq!(
+            selectors("::slotted(span)::part(a b)"),
+            "::slotted(span)::part(a b)"
+        );
+        assert_eq!(
+            selectors(":host(.a) :host-context( body.dark )"),
+            ":host(.a) :host-context(body.dark)"
+        );
+        assert_eq!(selectors(":foo(1 + [x])"), ":foo(1 + [x])");
+
+        assert_eq!(selectors(":not(a,)"), "error: expected selector");
+        assert_eq!(selectors(":has()"), "error: expected selector");
+        assert_eq!(selectors("a:"), "error: expected pseudo-class name");
+        assert_eq!(selectors(":host(a b)"), "error: expected ')'");
+    }
+
+    #[test]
+    fn nth_selectors() {
+        let cases = [
+            ("odd", "odd"),
+            ("EVEN", "even"),
+            ("3", "3"),
+            ("-3", "-3"),
+            ("n", "1n"),
+            ("-n", "-1n"),
+            ("+n", "1n"),
+            ("2n", "2n"),
+            ("2n+1", "2n+1"),
+            ("2n-1", "2n-1"),
+            ("-2n- 1", "-2n-1"),
+            ("n- 1", "1n-1"),
+            ("-n-3", "-1n-3"),
+            ("3n + 4", "3n+4"),
+            ("3n - 4", "3n-4"),
+            ("+N+2", "1n+2"),
+            ("0n", "0"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                selectors(&format!(":nth-child({})", input)),
+                format!(":nth-child({})", expected),
+                "{}",
+                input
+            );
+        }
+
+        assert_eq!(
+            selectors("li:nth-last-child(2n+1 of .a, .b)"),
+            "li:nth-last-child(2n+1 of .a, .b)"
+        );
+        assert_eq!(selectors(":nth-of-type(2n of a)"), "error: expected ')'");
+        assert_eq!(selectors(":nth-child(+ n)"), "error: invalid An+B value");
+        assert_eq!(selectors(":nth-child(2n + -1)"), "error: expected integer");
+        assert_eq!(selectors(":nth-child(1.5)"), "error: invalid An+B value");
+        assert_eq!(selectors(":nth-child(n-1a)"), "error: invalid An+B value");
+
+        let nth = AnPlusB::new(2, 1);
+        assert!(nth.matches(1) && nth.matches(3) && !nth.matches(2));
+        let nth = AnPlusB::new(-1, 3);
+        assert!(nth.matches(1) && nth.matches(3) && !nth.matches(4));
+        assert!(AnPlusB::new(0, 2).matches(2) && !AnPlusB::new(0, 2).matches(4));
+    }
+
+    /// Parses the stylesheet and returns its first at-rule
+    fn at_rule(css: &str) -> AtRule {
+        let mut style_sheet = CSS3Parser::new().parse(css);
+        match style_sheet.children.remove(0) {
+            StyleSheetRule::AtRule(at_rule) => at_rule,
+            rule => panic!("expected at-rule, got {:?}", rule),
+        }
+    }
+
+    /// Returns the prelude of the at-rule as a string
+    fn prelude(css: &str) -> String {
+        match at_rule(css).prelude {
+            AtRulePreludeValue::AtRulePrelude(prelude) => prelude
+                .children
+                .iter()
+                .map(|child| match child {
+                    AtRulePreludeChild::MediaQueryList(list) => list.to_string(),
+                    AtRulePreludeChild::SupportsCondition(condition) => condition.to_string(),
+                    AtRulePreludeChild::LayerList(list) => {
+                        let names: Vec<_> = list.children.iter().map(|l| l.name.clone()).collect();
+                        format!("layer({})", names.join(", "))
+                    }
+                    AtRulePreludeChild::Url(url) => format!("url({})", url.value),
+                    AtRulePreludeChild::Identifier(ident) => format!("{:?}", ident),
+                    AtRulePreludeChild::String(string) => string.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            AtRulePreludeValue::Raw(raw) => format!("raw: {}", raw.value),
+            AtRulePreludeValue::None => "none".to_string(),
+        }
+    }
+
+    #[test]
+    fn media_queries() {
+        let cases = [
+            ("screen", "screen"),
+            (
+                "ONLY Screen and (Min-Width:100px)",
+                "only screen and (min-width: 100px)",
+            ),
+            ("not print, (color)", "not print, (color)"),
+            ("(aspect-ratio: 16 / 9)", "(aspect-ratio: 16/9)"),
+            ("(width>=600px)", "(width >= 600px)"),
+            ("(600px <= width)", "(width >= 600px)"),
+            ("(400px < width <= 700px)", "(400px < width <= 700px)"),
+            ("(400px < width > 700px)", "(400px < width > 700px)"),
+            (
+                "screen and (color) and (hover: hover)",
+                "screen and (color) and (hover: hover)",
+            ),
+            ("(a) or (not (b) and (c))", "(a) or (not (b) and (c))"),
+            ("(a) or (b) and (c)", "not all"),
+            ("(a) or ((b) and (c))", "(a) or ((b) and (c))"),
+            ("not ((a) or (b))", "not ((a) or (b))"),
+            ("screen and (a) or (b)", "not all"),
+            ("(example, all,), speech", "(example, all,), speech"),
+            (
+                "foo(1) or (width: calc(1px + 2px))",
+                "foo(1) or (width: calc(1px + 2px))",
+            ),
+            ("screen and, print", "not all, print"),
+            ("and, or", "not all, not all"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                prelude(&format!("@media {} {{}}", input)),
+                expected,
+                "{}",
+                input
+            );
+        }
+        assert_eq!(prelude("@media {}"), "");
+    }
+
+    #[test]
+    fn at_rules() {
+        assert_eq!(prelude("@charset \"utf-8\";"), "\"utf-8\"");
+        assert_eq!(
+            prelude("@import url(\"a.css\") layer(base.reset) supports(display: grid) screen;"),
+            "url(a.css) layer(base.reset) (display: grid) screen"
+        );
+        assert_eq!(
+            prelude("@import 'b.css' layer supports((a: b) or selector(a > b))"),
+            "url(b.css) layer() (a: b) or selector(a > b)"
+        );
+        assert_eq!(
+            prelude("@namespace svg url(http://www.w3.org/2000/svg);"),
+            "svg url(http://www.w3.org/2000/svg)"
+        );
+        assert_eq!(prelude("@layer a.b, c;"), "layer(a.b, c)");
+        assert_eq!(prelude("@layer { a { b: c } }"), "none");
+        assert_eq!(
+            prelude("@supports not ((display: grid) and (gap: 1px !important)) {}"),
+            "not ((display: grid) and (gap: 1px !important))"
+        );
+        assert_eq!(
+            prelude("@supports font-tech(color-COLRv1) {}"),
+            "font-tech(color-COLRv1)"
+        );
+        assert_eq!(prelude("@page :first { margin: 1in; }"), "raw: :first");
+        assert_eq!(prelude("@-webkit-keyframes \"spin\" {}"), "\"spin\"");
+
+        let rule = at_rule("@media print { a { color: red } @media (color) { b { c: d } } }");
+        let block = rule.block.unwrap();
+        assert!(matches!(block.children[0], BlockChild::Rule(_)));
+        assert!(matches!(&block.children[1], BlockChild::AtRule(rule) if rule.name == "media"));
+
+        let rule = at_rule("@keyframes spin { from, 50% { opacity: 0 } TO { opacity: 1; } }");
+        let block = rule.block.unwrap();
+        assert_eq!(block.children.len(), 2);
+        let BlockChild::KeyframeRule(keyframe) = &block.children[0] else {
+            panic!("expected keyframe rule")
+        };
+        assert_eq!(
+            keyframe.selectors,
+            vec![
+                Percentage::new("0".to_string()),
+                Percentage::new("50".to_string())
+            ]
+        );
+
+        let rule = at_rule("@font-face { font-family: \"A\"; unicode-range: U+0-7F, U+4??; }");
+        let Some(Block { children }) = rule.block else {
+            panic!("expected block")
+        };
+        let BlockChild::DeclarationList(declarations) = &children[0] else {
+            panic!("expected declarations")
+        };
+        assert_eq!(
+            declarations.children[1].to_string(),
+            "unicode-range: U+0-7F, U+4??"
+        );
+
+        let rule = at_rule("@unknown foo { bar { baz } }");
+        assert_eq!(
+            rule.prelude,
+            AtRulePreludeValue::Raw(Raw::new("foo".to_string()))
+        );
+        assert_eq!(
+            rule.block,
+            Some(Block::new(vec![BlockChild::Raw(Raw::new(
+                "bar { baz }".to_string()
+            ))]))
+        );
+    }
+
+    #[test]
+    fn real_world_stylesheet() {
+        let style_sheet = CSS3Parser::new().parse(
+            r#"@charset "UTF-8";
+            <!-- :root,[data-bs-theme=light]{--bs-blue:#0d6efd;--bs-font-sans-serif:system-ui,-apple-system,"Segoe UI",sans-serif;--bs-gradient:linear-gradient(180deg, rgba(255, 255, 255, 0.15), rgba(255, 255, 255, 0))} -->
+            *,::after,::before{box-sizing:border-box}
+            @media (prefers-reduced-motion:no-preference){:root{scroll-behavior:smooth}}
+            .btn{--bs-btn-font-family: ;display:inline-block;font-size:calc(1.275rem + .3vw)!important}
+            .btn-close{background:transparent url("data:image/svg+xml,%3csvg%3e") center/1em auto no-repeat}
+            .table>:not(caption)>*>*{padding:.5rem .5rem}
+            .form-select[multiple],.form-select[size]:not([size="1"]){padding-right:.75rem}
+            @supports ((position:-webkit-sticky) or (position:sticky)){.sticky-top{position:sticky;top:0;z-index:1020}}
+            @keyframes progress-bar-stripes{0%{background-position-x:1rem}}
+            @media (min-width:768px) and (max-width:991.98px){.container{max-width:720px}}
+            @-ms-viewport{width:device-width}"#,
+        );
+        assert_eq!(style_sheet.children.len(), 12);
+
+        let StyleSheetRule::Rule(rule) = &style_sheet.children[4] else {
+            panic!("expected rule")
+        };
+        let BlockChild::DeclarationList(declarations) = &rule.block.children[0] else {
+            panic!("expected declarations")
+        };
+        assert_eq!(
+            declarations.children[0].to_string(),
+            "--bs-btn-font-family: "
+        );
+        assert_eq!(
+            declarations.children[2].to_string(),
+            "font-size: calc(1.275rem + .3vw) !important"
+        );
+    }
+
+    /// Returns the rules of the stylesheet as strings, like `a { b: c; d: e }`
+    fn rules(style_sheet: &StyleSheet) -> Vec<String> {
+        fn block(rule_block: &Block) -> String {
+            let children: Vec<String> = rule_block
+                .children
+                .iter()
+                .map(|child| match child {
+                    BlockChild::Rule(rule) => rule_to_string(rule),
+                    BlockChild::AtRule(rule) => format!("@{}", rule.name),
+                    BlockChild::DeclarationList(list) => list
+                        .children
+                        .iter()
+                        .map(|declaration| declaration.to_string())
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                    BlockChild::KeyframeRule(rule) => {
+                        let selectors: Vec<_> =
+                            rule.selectors.iter().map(|s| s.value.clone()).collect();
+                        format!("{}% {}", selectors.join("%, "), block(&rule.block))
+                    }
+                    BlockChild::Raw(raw) => raw.value.clone(),
+                })
+                .collect();
+            format!("{{ {} }}", children.join(" "))
+        }
+        fn rule_to_string(rule: &Rule) -> String {
+            format!("{} {}", rule.selectors, block(&rule.block))
+        }
+
+        style_sheet
+            .children
+            .iter()
+            .map(|rule| match rule {
+                StyleSheetRule::Rule(rule) => rule_to_string(rule),
+                StyleSheetRule::AtRule(rule) => match &rule.block {
+                    Some(rule_block) => format!("@{} {}", rule.name, block(rule_block)),
+                    None => format!("@{}", rule.name),
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn invalid_declarations() {
+        let mut parser = CSS3Parser::new();
+        let style_sheet = parser.parse(
+            "a { color: red; color blue; x: 1px) ; ; width: 1px !imp; y: \"bad\n; height: 2px }",
+        );
+        assert_eq!(rules(&style_sheet), ["a { color: red; height: 2px }"]);
+
+        let errors: Vec<_> = parser
+            .errors()
+            .into_iter()
+            .map(|error| (error.message, error.line, error.col))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("expected ':'".to_string(), 1, 23),
+                ("unexpected token in declaration".to_string(), 1, 35),
+                ("unexpected token in declaration".to_string(), 1, 52),
+                ("invalid string or url in declaration".to_string(), 1, 61),
+                ("newline in string".to_string(), 1, 65),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let mut parser = CSS3Parser::new();
+        let style_sheet = parser.parse(
+            r#"
+            @charset "utf-8";
+            @import "a.css";
+            b c, 1d { color: red }
+            e { color: blue }
+            } f { a: b }
+            @media screen;
+            g { a: b }
+            @import "late.css";
+            @import "block.css" {}
+            @media print { ) { } i { c: d } }
+            @keyframes k { 150% { a: b } to { a: c } }
+            j { a: b"#,
+        );
+        assert_eq!(
+            rules(&style_sheet),
+            [
+                "@charset",
+                "@import",
+                "e { color: blue }",
+                "g { a: b }",
+                "@media { i { c: d } }",
+                "@keyframes { 100% { a: c } }",
+                "j { a: b }",
+            ]
+        );
+
+        let messages: Vec<_> = parser.errors().into_iter().map(|e| e.message).collect();
+        assert_eq!(
+            messages,
+            [
+                "expected selector",
+                "expected selector",
+                "expected '{'",
+                "@import must come before all other rules",
+                "unexpected block",
+                "expected selector",
+                "invalid keyframe selector",
+                "unexpected end of input in block",
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_in_values() {
+        let values = CSS3Parser::new()
+            .parse_value_list("a,/* b */c /**// /**/[d]/* e */ u+1-2/**/ ,f")
+            .unwrap();
+        assert_eq!(values.to_string(), "a, c / [d] u+1-2, f");
+    }
 }
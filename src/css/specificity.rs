@@ -0,0 +1,168 @@
+//! [Selector specificity](https://drafts.csswg.org/selectors/#specificity-rules)
+use crate::css::node::{ComplexSelector, PseudoSelectorArgument, Selector, SelectorList};
+use std::fmt::{self, Display, Formatter};
+use std::ops::Add;
+
+/// The specificity of a selector: the number of ID selectors, the number of class selectors,
+/// attribute selectors and pseudo-classes, and the number of type selectors and pseudo-elements.
+/// Specificities compare component by component.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+impl Specificity {
+    pub const ZERO: Specificity = Specificity(0, 0, 0);
+    const ID: Specificity = Specificity(1, 0, 0);
+    const CLASS: Specificity = Specificity(0, 1, 0);
+    const TYPE: Specificity = Specificity(0, 0, 1);
+}
+
+impl Add for Specificity {
+    type Output = Specificity;
+
+    fn add(self, other: Specificity) -> Specificity {
+        Specificity(
+            self.0.saturating_add(other.0),
+            self.1.saturating_add(other.1),
+            self.2.saturating_add(other.2),
+        )
+    }
+}
+
+impl Display for Specificity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.0, self.1, self.2)
+    }
+}
+
+impl Selector {
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            Selector::IdSelector(_) => Specificity::ID,
+            Selector::ClassSelector(_) | Selector::AttributeSelector(_) => Specificity::CLASS,
+            Selector::TypeSelector(selector) if selector.is_universal() => Specificity::ZERO,
+            Selector::TypeSelector(_) => Specificity::TYPE,
+            // Style rules are not nested, so `&` only appears at the top level, where it adds
+            // nothing
+            Selector::NestingSelector(_) | Selector::Combinator(_) => Specificity::ZERO,
+            Selector::PseudoClassSelector(selector) => {
+                let argument = selector_list(&selector.argument);
+                match selector.name.to_ascii_lowercase().as_str() {
+                    "where" => Specificity::ZERO,
+                    // The pseudo-class itself does not count, only its most specific argument
+                    "is" | "not" | "has" | "matches" | "-webkit-any" | "-moz-any" => {
+                        argument.map_or(Specificity::ZERO, SelectorList::specificity)
+                    }
+                    // `:nth-child(2n of .item)` and `:host(.dark)` count like a pseudo-class
+                    // plus their most specific argument
+                    _ => {
+                        Specificity::CLASS
+                            + argument.map_or(Specificity::ZERO, SelectorList::specificity)
+                    }
+                }
+            }
+            // `::slotted(.item)` counts like a pseudo-element plus its argument
+            Selector::PseudoElementSelector(selector) => {
+                Specificity::TYPE
+                    + selector_list(&selector.argument)
+                        .map_or(Specificity::ZERO, SelectorList::specificity)
+            }
+        }
+    }
+}
+
+impl ComplexSelector {
+    /// The sum of the specificities of its simple selectors
+    pub fn specificity(&self) -> Specificity {
+        self.children
+            .iter()
+            .fold(Specificity::ZERO, |total, selector| {
+                total + selector.specificity()
+            })
+    }
+}
+
+impl SelectorList {
+    /// The specificity of its most specific complex selector. When a selector list matches an
+    /// element, use the most specific complex selector that matches instead.
+    pub fn specificity(&self) -> Specificity {
+        self.children
+            .iter()
+            .map(ComplexSelector::specificity)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// The selector list in the argument of a pseudo-class or pseudo-element, if any
+fn selector_list(argument: &Option<PseudoSelectorArgument>) -> Option<&SelectorList> {
+    match argument {
+        Some(PseudoSelectorArgument::SelectorList(list)) => Some(list),
+        Some(PseudoSelectorArgument::Nth(nth)) => nth.selector.as_ref(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::css::node::StyleSheetRule;
+    use crate::css::parser::CSS3Parser;
+
+    fn specificity(selector: &str) -> Specificity {
+        let stylesheet = CSS3Parser::new().parse(&format!("{} {{}}", selector));
+        match &stylesheet.children[..] {
+            [StyleSheetRule::Rule(rule)] => rule.selectors.specificity(),
+            _ => panic!("invalid selector {}", selector),
+        }
+    }
+
+    #[test]
+    fn simple_selectors() {
+        assert_eq!(specificity("*"), Specificity(0, 0, 0));
+        assert_eq!(specificity("li"), Specificity(0, 0, 1));
+        assert_eq!(specificity("ul li"), Specificity(0, 0, 2));
+        assert_eq!(specificity("ul ol+li"), Specificity(0, 0, 3));
+        assert_eq!(specificity("h1 + *[rel=up]"), Specificity(0, 1, 1));
+        assert_eq!(specificity("ul ol li.red"), Specificity(0, 1, 3));
+        assert_eq!(specificity("li.red.level"), Specificity(0, 2, 1));
+        assert_eq!(specificity("#x34y"), Specificity(1, 0, 0));
+        assert_eq!(specificity("a:hover::before"), Specificity(0, 1, 2));
+        assert_eq!(specificity("p:first-line"), Specificity(0, 0, 2));
+        assert_eq!(specificity("*|* > svg|rect"), Specificity(0, 0, 1));
+        assert_eq!(specificity("& .a"), Specificity(0, 1, 0));
+        assert_eq!(specificity("a, #b, .c"), Specificity(1, 0, 0));
+    }
+
+    #[test]
+    fn functional_pseudo_classes() {
+        assert_eq!(specificity("#s12:not(FOO)"), Specificity(1, 0, 1));
+        assert_eq!(specificity(".foo :is(.bar, #baz)"), Specificity(1, 1, 0));
+        assert_eq!(specificity(":is(p, ul li)"), Specificity(0, 0, 2));
+        assert_eq!(specificity("a:where(#x, .y) b"), Specificity(0, 0, 2));
+        assert_eq!(specificity("div:has(> img.icon)"), Specificity(0, 1, 2));
+        assert_eq!(specificity(":not(:is(.a, #b))"), Specificity(1, 0, 0));
+        assert_eq!(specificity("li:nth-child(2n+1)"), Specificity(0, 1, 1));
+        assert_eq!(
+            specificity("li:nth-child(even of li.important)"),
+            Specificity(0, 2, 2)
+        );
+        assert_eq!(specificity(":lang(en)"), Specificity(0, 1, 0));
+        assert_eq!(specificity("::slotted(span.x)"), Specificity(0, 1, 2));
+        assert_eq!(specificity(":host"), Specificity(0, 1, 0));
+        assert_eq!(specificity(":host(.a)"), Specificity(0, 2, 0));
+        assert_eq!(specificity(":host-context(.x) .y"), Specificity(0, 3, 0));
+        assert_eq!(specificity(":is()"), Specificity(0, 0, 0));
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(Specificity(1, 0, 0) > Specificity(0, 12, 12));
+        assert!(Specificity(0, 1, 0) > Specificity(0, 0, 9));
+        assert!(Specificity(0, 1, 2) < Specificity(0, 2, 0));
+        assert_eq!(
+            Specificity(0, 1, 0) + Specificity(1, 0, 3),
+            Specificity(1, 1, 3)
+        );
+        assert_eq!(Specificity(1, 2, 3).to_string(), "(1, 2, 3)");
+    }
+}
@@ -1,103 +1,740 @@
-use crate::css::tokens::{Token, TOKEN_REFS};
-use regex::{self, Regex};
+use crate::css::tokens::{NumericValue, Token, TokenType};
+use crate::html5_parser::input_stream::Position;
 
-/// CSS Tokenizer
+/// Largest code point, escapes above it are replaced
+const MAX_CODE_POINT: u32 = 0x10FFFF;
+
+/// A parse error found while tokenizing. Tokenizing always continues after an error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub position: Position,
+}
+
+/// CSS Tokenizer, following [CSS Syntax Level 3](https://www.w3.org/TR/css-syntax-3/#tokenization).
+/// Comments are consumed without producing tokens.
 #[derive(Debug, Default, PartialEq)]
 pub struct Tokenizer {
+    /// Index of the next character in the preprocessed input
     pub cursor: usize,
-    raw: String,
+    input: Vec<char>,
+    /// Offsets of the first character of every line
+    line_offsets: Vec<usize>,
+    errors: Vec<ParseError>,
 }
 
 impl Tokenizer {
     pub fn new() -> Self {
         Tokenizer {
             cursor: 0,
-            raw: String::new(),
+            input: vec![],
+            line_offsets: vec![0],
+            errors: vec![],
         }
     }
 
     pub fn init(&mut self, raw: &str) {
-        self.raw = raw.to_string();
+        self.input = preprocess(raw);
         self.cursor = 0;
+        self.errors.clear();
+        self.line_offsets = vec![0];
+        for (idx, c) in self.input.iter().enumerate() {
+            if *c == '\n' {
+                self.line_offsets.push(idx + 1);
+            }
+        }
+    }
+
+    /// Returns all tokens of the input, without the final EOF token
+    pub fn tokenize(raw: &str) -> Vec<Token> {
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.init(raw);
+        let mut tokens = vec![];
+        loop {
+            let token = tokenizer.next_token();
+            if token.is_eof() {
+                return tokens;
+            }
+            tokens.push(token);
+        }
     }
 
     pub fn has_more_tokens(&self) -> bool {
-        self.cursor < self.raw.len()
+        self.cursor < self.input.len()
     }
 
     pub fn is_eof(&self) -> bool {
-        self.cursor == self.raw.len()
+        self.cursor >= self.input.len()
+    }
+
+    /// Returns the parse errors found so far
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Returns the (preprocessed) input between the offsets
+    pub fn source(&self, start: usize, end: usize) -> String {
+        self.input[start.min(self.input.len())..end.min(self.input.len())]
+            .iter()
+            .collect()
+    }
+
+    /// Returns the position of the character at the offset
+    pub fn position(&self, offset: usize) -> Position {
+        let line = self.line_offsets.partition_point(|start| *start <= offset);
+        Position::new(offset, line, offset - self.line_offsets[line - 1] + 1)
+    }
+
+    /// Consumes and returns the next token. At the end of the input, EOF tokens are returned.
+    pub fn next_token(&mut self) -> Token {
+        self.consume_comments();
+        let start = self.cursor;
+        let token_type = self.consume_token();
+        Token::new(token_type, self.position(start))
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.input.get(self.cursor + offset).copied()
+    }
+
+    fn consume(&mut self) -> Option<char> {
+        let c = self.peek(0);
+        self.cursor += 1;
+        c
     }
 
-    pub fn get_next_token(&mut self) -> Option<Token> {
-        if !self.has_more_tokens() {
-            return None;
+    fn parse_error(&mut self, message: &str) {
+        let position = self.position(self.cursor.min(self.input.len()));
+        self.errors.push(ParseError {
+            message: message.to_string(),
+            position,
+        });
+    }
+
+    /// [4.3.2. Consume comments](https://www.w3.org/TR/css-syntax-3/#consume-comments)
+    fn consume_comments(&mut self) {
+        while self.peek(0) == Some('/') && self.peek(1) == Some('*') {
+            self.cursor += 2;
+            loop {
+                match self.consume() {
+                    Some('*') if self.peek(0) == Some('/') => {
+                        self.cursor += 1;
+                        break;
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.cursor = self.input.len();
+                        self.parse_error("eof in comment");
+                        return;
+                    }
+                }
+            }
         }
+    }
 
-        let raw = &self.raw[self.cursor..];
-        for (regex, token_type) in TOKEN_REFS.iter() {
-            let re = Regex::new(regex).unwrap();
-            let result = re.captures(raw);
+    /// [4.3.1. Consume a token](https://www.w3.org/TR/css-syntax-3/#consume-token)
+    fn consume_token(&mut self) -> TokenType {
+        let Some(c) = self.consume() else {
+            self.cursor = self.input.len();
+            return TokenType::EOF;
+        };
 
-            println!(
-                "[get_next_token] value={:?}, token_type={:?} for raw={}",
-                result,
-                token_type,
-                &raw.trim(),
-            );
+        match c {
+            c if is_whitespace(c) => {
+                while self.peek(0).is_some_and(is_whitespace) {
+                    self.cursor += 1;
+                }
+                TokenType::Whitespace
+            }
+            '"' | '\'' => self.consume_string(c),
+            '#' => {
+                if self.peek(0).is_some_and(is_name) || self.is_valid_escape(0) {
+                    let is_id = self.would_start_identifier(0);
+                    TokenType::Hash {
+                        value: self.consume_name(),
+                        is_id,
+                    }
+                } else {
+                    TokenType::Delim('#')
+                }
+            }
+            '$' => self.consume_match(TokenType::SuffixMatch, c),
+            '(' => TokenType::LParen,
+            ')' => TokenType::RParen,
+            '*' => self.consume_match(TokenType::SubstringMatch, c),
+            '+' | '.' => {
+                if self.would_start_number(-1) {
+                    self.cursor -= 1;
+                    self.consume_numeric()
+                } else {
+                    TokenType::Delim(c)
+                }
+            }
+            ',' => TokenType::Comma,
+            '-' => {
+                if self.would_start_number(-1) {
+                    self.cursor -= 1;
+                    self.consume_numeric()
+                } else if self.peek(0) == Some('-') && self.peek(1) == Some('>') {
+                    self.cursor += 2;
+                    TokenType::CDC
+                } else if self.would_start_identifier(-1) {
+                    self.cursor -= 1;
+                    self.consume_ident_like()
+                } else {
+                    TokenType::Delim(c)
+                }
+            }
+            ':' => TokenType::Colon,
+            ';' => TokenType::Semicolon,
+            '<' => {
+                if self.peek(0) == Some('!')
+                    && self.peek(1) == Some('-')
+                    && self.peek(2) == Some('-')
+                {
+                    self.cursor += 3;
+                    TokenType::CDO
+                } else {
+                    TokenType::Delim(c)
+                }
+            }
+            '@' => {
+                if self.would_start_identifier(0) {
+                    TokenType::AtKeyword(self.consume_name())
+                } else {
+                    TokenType::Delim(c)
+                }
+            }
+            '[' => TokenType::LBracket,
+            '\\' => {
+                if self.is_valid_escape(-1) {
+                    self.cursor -= 1;
+                    self.consume_ident_like()
+                } else {
+                    self.parse_error("invalid escape");
+                    TokenType::Delim(c)
+                }
+            }
+            ']' => TokenType::RBracket,
+            '^' => self.consume_match(TokenType::PrefixMatch, c),
+            '{' => TokenType::LCurly,
+            '}' => TokenType::RCurly,
+            '0'..='9' => {
+                self.cursor -= 1;
+                self.consume_numeric()
+            }
+            'u' | 'U'
+                if self.peek(0) == Some('+')
+                    && self
+                        .peek(1)
+                        .is_some_and(|c| c == '?' || c.is_ascii_hexdigit()) =>
+            {
+                self.cursor += 1;
+                self.consume_unicode_range()
+            }
+            c if is_name_start(c) => {
+                self.cursor -= 1;
+                self.consume_ident_like()
+            }
+            '|' => match self.peek(0) {
+                Some('=') => {
+                    self.cursor += 1;
+                    TokenType::DashMatch
+                }
+                Some('|') => {
+                    self.cursor += 1;
+                    TokenType::Column
+                }
+                _ => TokenType::Delim(c),
+            },
+            '~' => self.consume_match(TokenType::IncludeMatch, c),
+            _ => TokenType::Delim(c),
+        }
+    }
+
+    /// Returns the match token when the next character is `=`, otherwise a delim token
+    fn consume_match(&mut self, token_type: TokenType, c: char) -> TokenType {
+        if self.peek(0) == Some('=') {
+            self.cursor += 1;
+            token_type
+        } else {
+            TokenType::Delim(c)
+        }
+    }
+
+    /// Character at the offset from the cursor. Negative offsets look back.
+    fn at(&self, offset: isize) -> Option<char> {
+        self.cursor
+            .checked_add_signed(offset)
+            .and_then(|idx| self.input.get(idx).copied())
+    }
+
+    /// [4.3.8. Check if two code points are a valid escape](https://www.w3.org/TR/css-syntax-3/#starts-with-a-valid-escape)
+    fn is_valid_escape(&self, offset: isize) -> bool {
+        self.at(offset) == Some('\\') && self.at(offset + 1) != Some('\n')
+    }
+
+    /// [4.3.9. Check if three code points would start an identifier](https://www.w3.org/TR/css-syntax-3/#would-start-an-identifier)
+    fn would_start_identifier(&self, offset: isize) -> bool {
+        match self.at(offset) {
+            Some('-') => {
+                self.at(offset + 1)
+                    .is_some_and(|c| is_name_start(c) || c == '-')
+                    || self.is_valid_escape(offset + 1)
+            }
+            Some('\\') => self.is_valid_escape(offset),
+            Some(c) => is_name_start(c),
+            None => false,
+        }
+    }
+
+    /// [4.3.10. Check if three code points would start a number](https://www.w3.org/TR/css-syntax-3/#starts-with-a-number)
+    fn would_start_number(&self, offset: isize) -> bool {
+        let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+        match self.at(offset) {
+            Some('+' | '-') => {
+                is_digit(self.at(offset + 1))
+                    || (self.at(offset + 1) == Some('.') && is_digit(self.at(offset + 2)))
+            }
+            Some('.') => is_digit(self.at(offset + 1)),
+            c => is_digit(c),
+        }
+    }
+
+    /// [4.3.7. Consume an escaped code point](https://www.w3.org/TR/css-syntax-3/#consume-escaped-code-point).
+    /// The backslash has already been consumed.
+    fn consume_escaped(&mut self) -> char {
+        let Some(c) = self.consume() else {
+            self.cursor = self.input.len();
+            self.parse_error("eof in escape");
+            return char::REPLACEMENT_CHARACTER;
+        };
+        if !c.is_ascii_hexdigit() {
+            return c;
+        }
 
-            if let Some(cap) = result {
-                let value = cap.get(0).unwrap().as_str();
-                self.cursor += value.len();
+        let mut hex = c.to_string();
+        while hex.len() < 6 && self.peek(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+            hex.extend(self.consume());
+        }
+        if self.peek(0).is_some_and(is_whitespace) {
+            self.cursor += 1;
+        }
+
+        let code_point = u32::from_str_radix(&hex, 16).unwrap_or_default();
+        if code_point == 0 || code_point > MAX_CODE_POINT {
+            return char::REPLACEMENT_CHARACTER;
+        }
+        // Surrogates are not valid chars, so they are replaced as well
+        char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
 
-                if token_type.is_none() {
-                    return self.get_next_token();
+    /// [4.3.11. Consume a name](https://www.w3.org/TR/css-syntax-3/#consume-name)
+    fn consume_name(&mut self) -> String {
+        let mut name = String::new();
+        loop {
+            match self.peek(0) {
+                Some(c) if is_name(c) => {
+                    self.cursor += 1;
+                    name.push(c);
+                }
+                Some('\\') if self.is_valid_escape(0) => {
+                    self.cursor += 1;
+                    name.push(self.consume_escaped());
                 }
+                _ => return name,
+            }
+        }
+    }
 
-                return Some(Token::new(token_type.unwrap(), value.to_string()));
+    /// [4.3.12. Consume a number](https://www.w3.org/TR/css-syntax-3/#consume-number)
+    fn consume_number(&mut self) -> NumericValue {
+        let start = self.cursor;
+        let mut is_integer = true;
+        let digits = |tokenizer: &mut Self| {
+            while tokenizer.peek(0).is_some_and(|c| c.is_ascii_digit()) {
+                tokenizer.cursor += 1;
+            }
+        };
+
+        let has_sign = matches!(self.peek(0), Some('+' | '-'));
+        if has_sign {
+            self.cursor += 1;
+        }
+        digits(self);
+        if self.peek(0) == Some('.') && self.peek(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.cursor += 1;
+            digits(self);
+            is_integer = false;
+        }
+        if matches!(self.peek(0), Some('e' | 'E')) {
+            let exponent = match self.peek(1) {
+                Some('+' | '-') => self
+                    .peek(2)
+                    .is_some_and(|c| c.is_ascii_digit())
+                    .then_some(2),
+                Some(c) if c.is_ascii_digit() => Some(1),
+                _ => None,
+            };
+            if let Some(length) = exponent {
+                self.cursor += length;
+                digits(self);
+                is_integer = false;
             }
         }
 
-        None
+        let repr: String = self.input[start..self.cursor].iter().collect();
+        let value: f64 = repr.parse().unwrap_or_default();
+        NumericValue {
+            // Values that do not fit are clamped to the largest finite value
+            value: value.clamp(f32::MIN as f64, f32::MAX as f64) as f32,
+            int_value: is_integer.then(|| value.clamp(i32::MIN as f64, i32::MAX as f64) as i32),
+            has_sign,
+            repr,
+        }
+    }
+
+    /// [4.3.3. Consume a numeric token](https://www.w3.org/TR/css-syntax-3/#consume-numeric-token)
+    fn consume_numeric(&mut self) -> TokenType {
+        let number = self.consume_number();
+        if self.would_start_identifier(0) {
+            let unit = self.consume_name();
+            return TokenType::Dimension(number, unit);
+        }
+        if self.peek(0) == Some('%') {
+            self.cursor += 1;
+            return TokenType::Percentage(number);
+        }
+        TokenType::Number(number)
+    }
+
+    /// [4.3.4. Consume an ident-like token](https://www.w3.org/TR/css-syntax-3/#consume-ident-like-token)
+    fn consume_ident_like(&mut self) -> TokenType {
+        let name = self.consume_name();
+        if self.peek(0) != Some('(') {
+            return TokenType::Ident(name);
+        }
+        self.cursor += 1;
+        if !name.eq_ignore_ascii_case("url") {
+            return TokenType::Function(name);
+        }
+
+        // A quoted url is a function with a string argument, only one whitespace is kept in
+        // front of the string
+        while self.peek(0).is_some_and(is_whitespace) && self.peek(1).is_some_and(is_whitespace) {
+            self.cursor += 1;
+        }
+        let is_quote = |c: Option<char>| matches!(c, Some('"' | '\''));
+        if is_quote(self.peek(0))
+            || self.peek(0).is_some_and(is_whitespace) && is_quote(self.peek(1))
+        {
+            return TokenType::Function(name);
+        }
+        self.consume_url()
+    }
+
+    /// [4.3.5. Consume a string token](https://www.w3.org/TR/css-syntax-3/#consume-string-token).
+    /// The opening quote has already been consumed.
+    fn consume_string(&mut self, quote: char) -> TokenType {
+        let mut value = String::new();
+        loop {
+            match self.consume() {
+                Some(c) if c == quote => return TokenType::String(value),
+                None => {
+                    self.cursor = self.input.len();
+                    self.parse_error("eof in string");
+                    return TokenType::String(value);
+                }
+                Some('\n') => {
+                    self.cursor -= 1;
+                    self.parse_error("newline in string");
+                    return TokenType::BadString;
+                }
+                Some('\\') => match self.peek(0) {
+                    None => {}
+                    // An escaped newline continues the string on the next line
+                    Some('\n') => self.cursor += 1,
+                    Some(_) => value.push(self.consume_escaped()),
+                },
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    /// [4.3.6. Consume a url token](https://www.w3.org/TR/css-syntax-3/#consume-url-token).
+    /// `url(` has already been consumed, and the url is not quoted.
+    fn consume_url(&mut self) -> TokenType {
+        self.consume_whitespace();
+
+        let mut value = String::new();
+        loop {
+            match self.consume() {
+                Some(')') => return TokenType::Url(value),
+                None => {
+                    self.cursor = self.input.len();
+                    self.parse_error("eof in url");
+                    return TokenType::Url(value);
+                }
+                Some(c) if is_whitespace(c) => {
+                    self.consume_whitespace();
+                    match self.consume() {
+                        Some(')') => return TokenType::Url(value),
+                        None => {
+                            self.cursor = self.input.len();
+                            self.parse_error("eof in url");
+                            return TokenType::Url(value);
+                        }
+                        Some(_) => {
+                            self.parse_error("whitespace in url");
+                            self.consume_bad_url_remnants();
+                            return TokenType::BadUrl;
+                        }
+                    }
+                }
+                Some('"' | '\'' | '(') => {
+                    self.parse_error("unexpected character in url");
+                    self.consume_bad_url_remnants();
+                    return TokenType::BadUrl;
+                }
+                Some(c) if is_non_printable(c) => {
+                    self.parse_error("non-printable character in url");
+                    self.consume_bad_url_remnants();
+                    return TokenType::BadUrl;
+                }
+                Some('\\') => {
+                    if self.is_valid_escape(-1) {
+                        value.push(self.consume_escaped());
+                    } else {
+                        self.parse_error("invalid escape in url");
+                        self.consume_bad_url_remnants();
+                        return TokenType::BadUrl;
+                    }
+                }
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    /// [4.3.14. Consume the remnants of a bad url](https://www.w3.org/TR/css-syntax-3/#consume-remnants-of-bad-url)
+    fn consume_bad_url_remnants(&mut self) {
+        loop {
+            match self.consume() {
+                Some(')') => return,
+                None => {
+                    self.cursor = self.input.len();
+                    return;
+                }
+                Some('\\') if self.is_valid_escape(-1) => {
+                    self.consume_escaped();
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// [4.3.7. Consume a unicode-range token](https://www.w3.org/TR/2014/CR-css-syntax-3-20140220/#consume-a-unicode-range-token).
+    /// `U+` has already been consumed.
+    fn consume_unicode_range(&mut self) -> TokenType {
+        let mut start = String::new();
+        while start.len() < 6 && self.peek(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+            start.extend(self.consume());
+        }
+        while start.len() < 6 && self.peek(0) == Some('?') {
+            start.extend(self.consume());
+        }
+
+        let parse = |hex: &str| u32::from_str_radix(hex, 16).unwrap_or_default();
+        if start.contains('?') {
+            return TokenType::UnicodeRange(
+                parse(&start.replace('?', "0")),
+                parse(&start.replace('?', "F")),
+            );
+        }
+
+        let start = parse(&start);
+        if self.peek(0) == Some('-') && self.peek(1).is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.cursor += 1;
+            let mut end = String::new();
+            while end.len() < 6 && self.peek(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+                end.extend(self.consume());
+            }
+            return TokenType::UnicodeRange(start, parse(&end));
+        }
+        TokenType::UnicodeRange(start, start)
+    }
+
+    fn consume_whitespace(&mut self) {
+        while self.peek(0).is_some_and(is_whitespace) {
+            self.cursor += 1;
+        }
+    }
+}
+
+/// [3.3. Preprocessing the input stream](https://www.w3.org/TR/css-syntax-3/#input-preprocessing).
+/// Line breaks are normalized to `\n` and NULL characters are replaced.
+fn preprocess(raw: &str) -> Vec<char> {
+    let mut input = Vec::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                input.push('\n');
+            }
+            '\x0C' => input.push('\n'),
+            '\0' => input.push(char::REPLACEMENT_CHARACTER),
+            c => input.push(c),
+        }
     }
+    input
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n')
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+fn is_non_printable(c: char) -> bool {
+    matches!(c, '\0'..='\x08' | '\x0B' | '\x0E'..='\x1F' | '\x7F')
 }
 
 #[cfg(test)]
 mod test {
-    use crate::css::tokens::TokenType;
-
     use super::*;
 
-    macro_rules! assert_next_token {
-        ($self:expr, $token_type:expr, $token_value:expr) => {
-            let token = $self.get_next_token();
-
-            if token.is_none() {
-                assert_eq!($token_type.is_none(), true);
-            } else {
-                assert_eq!(
-                    token.unwrap(),
-                    Token {
-                        token_type: $token_type.unwrap(),
-                        value: $token_value.unwrap().to_string(),
-                    },
-                )
-            }
-        };
+    fn token_types(raw: &str) -> Vec<TokenType> {
+        Tokenizer::tokenize(raw)
+            .into_iter()
+            .map(|token| token.token_type)
+            .collect()
+    }
+
+    fn number(repr: &str, value: f32, int_value: Option<i32>) -> NumericValue {
+        NumericValue {
+            value,
+            int_value,
+            has_sign: repr.starts_with(['+', '-']),
+            repr: repr.to_string(),
+        }
     }
 
     #[test]
-    fn match_macro_tokens() {
-        let mut tokenizer = Tokenizer::default();
-        tokenizer.init("123 -ident-test-1");
+    fn tokens_and_positions() {
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.init("a {\r\n  color: red; /* c */\n}");
 
         assert!(!tokenizer.is_eof());
         assert!(tokenizer.has_more_tokens());
 
-        assert_next_token!(tokenizer, Some(TokenType::Number), Some("123"));
-        assert_next_token!(tokenizer, Some(TokenType::Ident), Some("-ident-test-1"));
-
+        let mut tokens = vec![];
+        loop {
+            let token = tokenizer.next_token();
+            if token.is_eof() {
+                break;
+            }
+            tokens.push((token.token_type, token.position.line, token.position.col));
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenType::Ident("a".to_string()), 1, 1),
+                (TokenType::Whitespace, 1, 2),
+                (TokenType::LCurly, 1, 3),
+                (TokenType::Whitespace, 1, 4),
+                (TokenType::Ident("color".to_string()), 2, 3),
+                (TokenType::Colon, 2, 8),
+                (TokenType::Whitespace, 2, 9),
+                (TokenType::Ident("red".to_string()), 2, 10),
+                (TokenType::Semicolon, 2, 13),
+                (TokenType::Whitespace, 2, 14),
+                (TokenType::Whitespace, 2, 22),
+                (TokenType::RCurly, 3, 1),
+            ]
+        );
         assert!(tokenizer.is_eof());
         assert!(!tokenizer.has_more_tokens());
+        assert!(tokenizer.next_token().is_eof());
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            token_types("12 -3.5e2% .5em +7 1e x"),
+            vec![
+                TokenType::Number(number("12", 12.0, Some(12))),
+                TokenType::Whitespace,
+                TokenType::Percentage(number("-3.5e2", -350.0, None)),
+                TokenType::Whitespace,
+                TokenType::Dimension(number(".5", 0.5, None), "em".to_string()),
+                TokenType::Whitespace,
+                TokenType::Number(number("+7", 7.0, Some(7))),
+                TokenType::Whitespace,
+                TokenType::Dimension(number("1", 1.0, Some(1)), "e".to_string()),
+                TokenType::Whitespace,
+                TokenType::Ident("x".to_string()),
+            ]
+        );
+
+        // Numbers that do not fit in an f32 are clamped
+        assert_eq!(
+            token_types("1e40 -1e39px 1e-50"),
+            vec![
+                TokenType::Number(number("1e40", f32::MAX, None)),
+                TokenType::Whitespace,
+                TokenType::Dimension(number("-1e39", f32::MIN, None), "px".to_string()),
+                TokenType::Whitespace,
+                TokenType::Number(number("1e-50", 0.0, None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_strings_and_urls() {
+        assert_eq!(
+            token_types("\\31 a\\\"b 'x\\\ny' \"z\nurl( a\\)b ) url(\"q\") url(a b)"),
+            vec![
+                TokenType::Ident("1a\"b".to_string()),
+                TokenType::Whitespace,
+                TokenType::String("xy".to_string()),
+                TokenType::Whitespace,
+                TokenType::BadString,
+                TokenType::Whitespace,
+                TokenType::Url("a)b".to_string()),
+                TokenType::Whitespace,
+                TokenType::Function("url".to_string()),
+                TokenType::String("q".to_string()),
+                TokenType::RParen,
+                TokenType::Whitespace,
+                TokenType::BadUrl,
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.init("'open\n/* unclosed");
+        while !tokenizer.next_token().is_eof() {}
+        let errors: Vec<_> = tokenizer
+            .errors()
+            .iter()
+            .map(|error| (error.message.as_str(), error.position.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![("newline in string", 1), ("eof in comment", 2)]
+        );
+    }
+
+    #[test]
+    fn preprocessing() {
+        assert_eq!(
+            preprocess("a\r\nb\rc\x0Cd\0"),
+            "a\nb\nc\nd\u{FFFD}".chars().collect::<Vec<_>>()
+        );
     }
 }
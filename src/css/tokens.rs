@@ -1,66 +1,100 @@
-#[derive(PartialEq, Debug, Clone, Copy)]
+use crate::html5_parser::input_stream::Position;
+
+/// Token types of [CSS Syntax Level 3](https://www.w3.org/TR/css-syntax-3/#tokenization)
+#[derive(PartialEq, Debug, Clone)]
 pub enum TokenType {
-    At,
-    Hash,
-    Dot,
-    Number,
-    String,
+    Ident(String),
+    Function(String),
+    AtKeyword(String),
+    /// `#name`. The hash is an "id" hash when the name would start an identifier.
+    Hash {
+        value: String,
+        is_id: bool,
+    },
+    String(String),
+    BadString,
+    Url(String),
+    BadUrl,
+    Delim(char),
+    Number(NumericValue),
+    Percentage(NumericValue),
+    Dimension(NumericValue, String),
+    /// `U+26`, `u+0-7F` or `U+4??`, with the first and last code point of the range
+    UnicodeRange(u32, u32),
+    /// `~=`
+    IncludeMatch,
+    /// `|=`
+    DashMatch,
+    /// `^=`
+    PrefixMatch,
+    /// `$=`
+    SuffixMatch,
+    /// `*=`
+    SubstringMatch,
+    /// `||`
+    Column,
+    Whitespace,
     CDO,
     CDC,
-    Semicolon,
     Colon,
-    LCurly,
-    RCurly,
-    LParen,
-    RParen,
+    Semicolon,
+    Comma,
     LBracket,
     RBracket,
-    Comment,
-    Includes,
-    Dashmatch,
-    Ident,
-    Important,
-    // todo: add unicode & ascii
+    LParen,
+    RParen,
+    LCurly,
+    RCurly,
+    EOF,
+}
+
+/// The value of a number, percentage or dimension token
+#[derive(PartialEq, Debug, Clone)]
+pub struct NumericValue {
+    pub value: f32,
+    /// The value, if the number was written as an integer (without `.` or exponent)
+    pub int_value: Option<i32>,
+    /// Whether the number started with `+` or `-`
+    pub has_sign: bool,
+    /// The number as it was written
+    pub repr: String,
+}
+
+impl NumericValue {
+    pub fn is_integer(&self) -> bool {
+        self.int_value.is_some()
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
-    pub value: String,
+    /// Position of the first character of the token in the (preprocessed) input
+    pub position: Position,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, value: String) -> Token {
-        Token { token_type, value }
+    pub fn new(token_type: TokenType, position: Position) -> Token {
+        Token {
+            token_type,
+            position,
+        }
     }
-}
 
-// todo: support float numbers: ^([0-9]*\.[0-9]+)|[0-9]+
-static NUMBER: &str = r"^[0-9]+";
-static NUM_CHAR: &str = r"[_a-zA-Z0-9-]";
-static NM_START: &str = r"[_a-zA-Z]";
-static IDENT: &str = r"^[-]?[_a-zA-Z][_a-zA-Z0-9-]*";
-static NAME: &str = r"^[_a-zA-Z0-9-]*";
-static HASH: &str = r"^#";
-static AT: &str = r"^@";
-static DOT: &str = r"^\.";
-static SEMICOLON: &str = r"^;";
-static COLON: &str = r"^:";
-static LCURLY: &str = r"^\{";
-static RCURLY: &str = r"^\}";
-static SPACE: &str = r"^[ \t\r\n\f]+";
-static IMPORTANT: &str = "^!important";
+    pub fn is_eof(&self) -> bool {
+        self.token_type == TokenType::EOF
+    }
 
-pub static TOKEN_REFS: [(&str, Option<TokenType>); 11] = [
-    (SPACE, None),
-    (NUMBER, Some(TokenType::Number)),
-    (IDENT, Some(TokenType::Ident)),
-    (HASH, Some(TokenType::Hash)),
-    (AT, Some(TokenType::At)),
-    (DOT, Some(TokenType::Dot)),
-    (SEMICOLON, Some(TokenType::Semicolon)),
-    (LCURLY, Some(TokenType::LCurly)),
-    (RCURLY, Some(TokenType::RCurly)),
-    (COLON, Some(TokenType::Colon)),
-    (IMPORTANT, Some(TokenType::Important)),
-];
+    pub fn is_whitespace(&self) -> bool {
+        self.token_type == TokenType::Whitespace
+    }
+
+    /// Returns true for an ident token with the name, matched case-insensitively
+    pub fn is_ident(&self, name: &str) -> bool {
+        matches!(&self.token_type, TokenType::Ident(ident) if ident.eq_ignore_ascii_case(name))
+    }
+
+    pub fn is_delim(&self, delim: char) -> bool {
+        self.token_type == TokenType::Delim(delim)
+    }
+}
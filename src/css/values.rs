@@ -0,0 +1,1088 @@
+//! Typed [CSS values](https://www.w3.org/TR/css-values-4/)
+//!
+//! The parser keeps declaration values as generic component values. This module checks them
+//! against the grammar of their property and turns them into lengths, colors, keywords and so
+//! on. Values that use `var()` or `env()` can only be checked after substitution, so they are
+//! kept as written.
+pub mod calc;
+pub mod transform;
+
+use crate::css::color::Color;
+use crate::css::node::{Declaration, Value, ValueList};
+use calc::{CalcNode, CalcType};
+use thiserror::Error;
+use transform::TransformFunction;
+
+/// Reasons why a declaration value could not be parsed
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ValueError {
+    #[error("unknown property: {0}")]
+    UnknownProperty(String),
+    #[error("invalid value for {property}: {value}")]
+    InvalidValue { property: String, value: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    Px,
+    Em,
+    Rem,
+    Ex,
+    Ch,
+    Vw,
+    Vh,
+    Vmin,
+    Vmax,
+    Cm,
+    Mm,
+    Q,
+    In,
+    Pt,
+    Pc,
+}
+
+impl LengthUnit {
+    /// Returns the unit for the (case-insensitive) name
+    pub fn parse(unit: &str) -> Option<LengthUnit> {
+        let unit = match unit.to_ascii_lowercase().as_str() {
+            "px" => LengthUnit::Px,
+            "em" => LengthUnit::Em,
+            "rem" => LengthUnit::Rem,
+            "ex" => LengthUnit::Ex,
+            "ch" => LengthUnit::Ch,
+            "vw" => LengthUnit::Vw,
+            "vh" => LengthUnit::Vh,
+            "vmin" => LengthUnit::Vmin,
+            "vmax" => LengthUnit::Vmax,
+            "cm" => LengthUnit::Cm,
+            "mm" => LengthUnit::Mm,
+            "q" => LengthUnit::Q,
+            "in" => LengthUnit::In,
+            "pt" => LengthUnit::Pt,
+            "pc" => LengthUnit::Pc,
+            _ => return None,
+        };
+        Some(unit)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LengthUnit::Px => "px",
+            LengthUnit::Em => "em",
+            LengthUnit::Rem => "rem",
+            LengthUnit::Ex => "ex",
+            LengthUnit::Ch => "ch",
+            LengthUnit::Vw => "vw",
+            LengthUnit::Vh => "vh",
+            LengthUnit::Vmin => "vmin",
+            LengthUnit::Vmax => "vmax",
+            LengthUnit::Cm => "cm",
+            LengthUnit::Mm => "mm",
+            LengthUnit::Q => "q",
+            LengthUnit::In => "in",
+            LengthUnit::Pt => "pt",
+            LengthUnit::Pc => "pc",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length {
+    pub value: f32,
+    pub unit: LengthUnit,
+}
+
+impl Length {
+    pub fn new(value: f32, unit: LengthUnit) -> Length {
+        Length { value, unit }
+    }
+
+    pub fn px(value: f32) -> Length {
+        Length::new(value, LengthUnit::Px)
+    }
+
+    /// Converts an absolute length to pixels. Font and viewport relative lengths need the
+    /// computed style, so they return `None`.
+    pub fn to_px(&self) -> Option<f32> {
+        let factor = match self.unit {
+            LengthUnit::Px => 1.0,
+            LengthUnit::In => 96.0,
+            LengthUnit::Cm => 96.0 / 2.54,
+            LengthUnit::Mm => 96.0 / 25.4,
+            LengthUnit::Q => 96.0 / 101.6,
+            LengthUnit::Pt => 96.0 / 72.0,
+            LengthUnit::Pc => 16.0,
+            _ => return None,
+        };
+        Some(self.value * factor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AngleUnit {
+    Deg,
+    Grad,
+    Rad,
+    Turn,
+}
+
+impl AngleUnit {
+    pub fn parse(unit: &str) -> Option<AngleUnit> {
+        let unit = match unit.to_ascii_lowercase().as_str() {
+            "deg" => AngleUnit::Deg,
+            "grad" => AngleUnit::Grad,
+            "rad" => AngleUnit::Rad,
+            "turn" => AngleUnit::Turn,
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Angle {
+    pub value: f32,
+    pub unit: AngleUnit,
+}
+
+impl Angle {
+    pub fn new(value: f32, unit: AngleUnit) -> Angle {
+        Angle { value, unit }
+    }
+
+    pub fn deg(value: f32) -> Angle {
+        Angle::new(value, AngleUnit::Deg)
+    }
+
+    pub fn to_degrees(&self) -> f32 {
+        match self.unit {
+            AngleUnit::Deg => self.value,
+            AngleUnit::Grad => self.value * 0.9,
+            AngleUnit::Rad => self.value.to_degrees(),
+            AngleUnit::Turn => self.value * 360.0,
+        }
+    }
+}
+
+/// A `<length-percentage>`
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthPercentage {
+    Length(Length),
+    Percentage(f32),
+    Calc(CalcNode),
+}
+
+impl From<LengthPercentage> for CssValue {
+    fn from(value: LengthPercentage) -> CssValue {
+        match value {
+            LengthPercentage::Length(length) => CssValue::Length(length),
+            LengthPercentage::Percentage(percentage) => CssValue::Percentage(percentage),
+            LengthPercentage::Calc(node) => CssValue::Calc(node),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontFamily {
+    /// A family name, quoted or not
+    Named(String),
+    /// A generic family, like `serif`
+    Generic(String),
+}
+
+/// The typed value of a declaration
+#[derive(Debug, Clone, PartialEq)]
+pub enum CssValue {
+    /// A keyword, in lowercase
+    Keyword(String),
+    Length(Length),
+    /// A percentage, where 50% is 50.0
+    Percentage(f32),
+    Number(f32),
+    Integer(i32),
+    Angle(Angle),
+    Color(Color),
+    Url(String),
+    String(String),
+    Ratio(f32, f32),
+    FontFamilies(Vec<FontFamily>),
+    Transform(Vec<TransformFunction>),
+    Calc(CalcNode),
+    /// The components of a shorthand or a value with several parts, like `margin: 1px 2px`
+    List(Vec<CssValue>),
+    /// A value that can only be checked at computed-value time, like a custom property or a
+    /// value using `var()`
+    Unparsed(String),
+}
+
+/// Keywords that are valid for every property
+const CSS_WIDE_KEYWORDS: &[&str] = &["initial", "inherit", "unset", "revert", "revert-layer"];
+
+const LINE_STYLES: &[&str] = &[
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+const OVERFLOW: &[&str] = &["visible", "hidden", "clip", "scroll", "auto"];
+
+const GENERIC_FAMILIES: &[&str] = &[
+    "serif",
+    "sans-serif",
+    "monospace",
+    "cursive",
+    "fantasy",
+    "system-ui",
+    "ui-serif",
+    "ui-sans-serif",
+    "ui-monospace",
+    "ui-rounded",
+    "math",
+    "emoji",
+    "fangsong",
+];
+
+/// Properties whose value is a single keyword
+const KEYWORD_PROPERTIES: &[(&str, &[&str])] = &[
+    (
+        "display",
+        &[
+            "none",
+            "block",
+            "inline",
+            "inline-block",
+            "flex",
+            "inline-flex",
+            "grid",
+            "inline-grid",
+            "flow-root",
+            "contents",
+            "list-item",
+            "table",
+            "inline-table",
+            "table-row",
+            "table-cell",
+            "table-column",
+            "table-caption",
+            "table-row-group",
+            "table-column-group",
+            "table-header-group",
+            "table-footer-group",
+        ],
+    ),
+    (
+        "position",
+        &["static", "relative", "absolute", "fixed", "sticky"],
+    ),
+    (
+        "float",
+        &["none", "left", "right", "inline-start", "inline-end"],
+    ),
+    (
+        "clear",
+        &[
+            "none",
+            "left",
+            "right",
+            "both",
+            "inline-start",
+            "inline-end",
+        ],
+    ),
+    ("visibility", &["visible", "hidden", "collapse"]),
+    ("overflow-x", OVERFLOW),
+    ("overflow-y", OVERFLOW),
+    (
+        "text-align",
+        &[
+            "start",
+            "end",
+            "left",
+            "right",
+            "center",
+            "justify",
+            "match-parent",
+        ],
+    ),
+    (
+        "text-transform",
+        &["none", "capitalize", "uppercase", "lowercase", "full-width"],
+    ),
+    (
+        "white-space",
+        &[
+            "normal",
+            "pre",
+            "nowrap",
+            "pre-wrap",
+            "pre-line",
+            "break-spaces",
+        ],
+    ),
+    ("box-sizing", &["content-box", "border-box"]),
+    ("font-style", &["normal", "italic", "oblique"]),
+    (
+        "flex-direction",
+        &["row", "row-reverse", "column", "column-reverse"],
+    ),
+    ("flex-wrap", &["nowrap", "wrap", "wrap-reverse"]),
+    (
+        "justify-content",
+        &[
+            "normal",
+            "start",
+            "end",
+            "left",
+            "right",
+            "center",
+            "flex-start",
+            "flex-end",
+            "stretch",
+            "space-between",
+            "space-around",
+            "space-evenly",
+        ],
+    ),
+    (
+        "align-items",
+        &[
+            "normal",
+            "stretch",
+            "start",
+            "end",
+            "center",
+            "flex-start",
+            "flex-end",
+            "self-start",
+            "self-end",
+            "baseline",
+        ],
+    ),
+    (
+        "align-self",
+        &[
+            "auto",
+            "normal",
+            "stretch",
+            "start",
+            "end",
+            "center",
+            "flex-start",
+            "flex-end",
+            "self-start",
+            "self-end",
+            "baseline",
+        ],
+    ),
+    (
+        "align-content",
+        &[
+            "normal",
+            "stretch",
+            "start",
+            "end",
+            "center",
+            "flex-start",
+            "flex-end",
+            "baseline",
+            "space-between",
+            "space-around",
+            "space-evenly",
+        ],
+    ),
+    ("pointer-events", &["auto", "none"]),
+    ("border-collapse", &["separate", "collapse"]),
+    ("table-layout", &["auto", "fixed"]),
+    ("border-top-style", LINE_STYLES),
+    ("border-right-style", LINE_STYLES),
+    ("border-bottom-style", LINE_STYLES),
+    ("border-left-style", LINE_STYLES),
+    ("outline-style", LINE_STYLES),
+];
+
+type Grammar = fn(&mut Input) -> Option<CssValue>;
+
+/// Returns the grammar of the properties that are not a single keyword
+fn grammar(property: &str) -> Option<Grammar> {
+    let grammar: Grammar = match property {
+        "color"
+        | "background-color"
+        | "border-top-color"
+        | "border-right-color"
+        | "border-bottom-color"
+        | "border-left-color"
+        | "outline-color"
+        | "text-decoration-color"
+        | "caret-color" => color,
+        "border-color" => |input| one_to_four(input, color),
+        "border-style" => |input| one_to_four(input, line_style),
+        "border-width" => |input| one_to_four(input, line_width),
+        "border-top-width"
+        | "border-right-width"
+        | "border-bottom-width"
+        | "border-left-width"
+        | "outline-width" => line_width,
+        "border" | "border-top" | "border-right" | "border-bottom" | "border-left" | "outline" => {
+            border
+        }
+        "border-radius" => border_radius,
+        "border-top-left-radius"
+        | "border-top-right-radius"
+        | "border-bottom-right-radius"
+        | "border-bottom-left-radius" => |input| {
+            let horizontal = length_percentage(input, false)?;
+            match input.try_parse(|input| length_percentage(input, false)) {
+                Some(vertical) => Some(CssValue::List(vec![horizontal, vertical])),
+                None => Some(horizontal),
+            }
+        },
+        "width" | "height" | "min-width" | "min-height" => |input| size(input, "auto"),
+        "max-width" | "max-height" => |input| size(input, "none"),
+        "margin-top" | "margin-right" | "margin-bottom" | "margin-left" => margin,
+        "margin" => |input| one_to_four(input, margin),
+        "padding-top" | "padding-right" | "padding-bottom" | "padding-left" => {
+            |input| length_percentage(input, false)
+        }
+        "padding" => |input| one_to_four(input, |input| length_percentage(input, false)),
+        "top" | "right" | "bottom" | "left" => margin,
+        "font-family" => font_family,
+        "font-size" => font_size,
+        "font-weight" => font_weight,
+        "line-height" => |input| {
+            input
+                .keyword(&["normal"])
+                .or_else(|| number(input, false))
+                .or_else(|| length_percentage(input, false))
+        },
+        "letter-spacing" => |input| input.keyword(&["normal"]).or_else(|| length(input, true)),
+        "word-spacing" => |input| {
+            input
+                .keyword(&["normal"])
+                .or_else(|| length_percentage(input, true))
+        },
+        "text-indent" => |input| length_percentage(input, true),
+        "vertical-align" => |input| {
+            input
+                .keyword(&[
+                    "baseline",
+                    "sub",
+                    "super",
+                    "text-top",
+                    "text-bottom",
+                    "middle",
+                    "top",
+                    "bottom",
+                ])
+                .or_else(|| length_percentage(input, true))
+        },
+        "opacity" => |input| number(input, true).or_else(|| percentage(input)),
+        "z-index" => |input| input.keyword(&["auto"]).or_else(|| integer(input)),
+        "order" => integer,
+        "flex-grow" | "flex-shrink" => |input| number(input, false),
+        "flex-basis" => |input| input.keyword(&["content"]).or_else(|| size(input, "auto")),
+        "row-gap" | "column-gap" => gap,
+        "gap" => |input| {
+            let row = gap(input)?;
+            match input.try_parse(gap) {
+                Some(column) => Some(CssValue::List(vec![row, column])),
+                None => Some(CssValue::List(vec![row])),
+            }
+        },
+        "overflow" => |input| {
+            let x = input.keyword(OVERFLOW)?;
+            match input.keyword(OVERFLOW) {
+                Some(y) => Some(CssValue::List(vec![x, y])),
+                None => Some(CssValue::List(vec![x])),
+            }
+        },
+        "background-image" | "list-style-image" => image,
+        "transform" => |input| {
+            input
+                .keyword(&["none"])
+                .or_else(|| transform::transform_list(input).map(CssValue::Transform))
+        },
+        "aspect-ratio" => aspect_ratio,
+        _ => return None,
+    };
+    Some(grammar)
+}
+
+/// Parses the value of a declaration
+pub fn parse_declaration(declaration: &Declaration) -> Result<CssValue, ValueError> {
+    parse_property_value(&declaration.property, &declaration.value)
+}
+
+/// Parses the value of `property` according to its grammar
+pub fn parse_property_value(property: &str, value: &ValueList) -> Result<CssValue, ValueError> {
+    if property.starts_with("--") {
+        return Ok(CssValue::Unparsed(value.to_string()));
+    }
+
+    let property = property.to_ascii_lowercase();
+    let keywords = KEYWORD_PROPERTIES
+        .iter()
+        .find(|(name, _)| *name == property)
+        .map(|(_, keywords)| *keywords);
+    let grammar = grammar(&property);
+    if keywords.is_none() && grammar.is_none() {
+        return Err(ValueError::UnknownProperty(property));
+    }
+
+    if contains_substitution(&value.children) {
+        return Ok(CssValue::Unparsed(value.to_string()));
+    }
+
+    let mut input = Input::new(&value.children);
+    let parsed = match input.try_parse(|input| input.keyword(CSS_WIDE_KEYWORDS)) {
+        Some(keyword) => Some(keyword),
+        None => match (keywords, grammar) {
+            (Some(keywords), _) => input.keyword(keywords),
+            (_, Some(grammar)) => grammar(&mut input),
+            _ => None,
+        },
+    };
+    match parsed {
+        Some(parsed) if input.is_empty() => Ok(parsed),
+        _ => Err(ValueError::InvalidValue {
+            property,
+            value: value.to_string(),
+        }),
+    }
+}
+
+/// Checks for `var()` or `env()`, anywhere in the value
+fn contains_substitution(values: &[Value]) -> bool {
+    values.iter().any(|value| match value {
+        Value::Function(function) => {
+            function.name.eq_ignore_ascii_case("var")
+                || function.name.eq_ignore_ascii_case("env")
+                || contains_substitution(&function.children.children)
+        }
+        Value::Parentheses(children) => contains_substitution(&children.children),
+        _ => false,
+    })
+}
+
+/// A cursor over component values
+struct Input<'a> {
+    values: &'a [Value],
+    position: usize,
+}
+
+impl<'a> Input<'a> {
+    fn new(values: &'a [Value]) -> Input<'a> {
+        Input {
+            values,
+            position: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.values.len()
+    }
+
+    fn peek(&self) -> Option<&'a Value> {
+        self.values.get(self.position)
+    }
+
+    fn consume(&mut self) -> Option<&'a Value> {
+        let value = self.values.get(self.position)?;
+        self.position += 1;
+        Some(value)
+    }
+
+    /// Runs `parse`, and rewinds when it fails
+    fn try_parse<T>(&mut self, parse: impl FnOnce(&mut Input<'a>) -> Option<T>) -> Option<T> {
+        let start = self.position;
+        let result = parse(self);
+        if result.is_none() {
+            self.position = start;
+        }
+        result
+    }
+
+    /// Consumes the next value when `convert` accepts it
+    fn consume_if<T>(&mut self, convert: impl FnOnce(&'a Value) -> Option<T>) -> Option<T> {
+        let result = convert(self.peek()?)?;
+        self.position += 1;
+        Some(result)
+    }
+
+    /// Consumes one of `keywords` (ASCII case-insensitive)
+    fn keyword(&mut self, keywords: &[&str]) -> Option<CssValue> {
+        self.consume_if(|value| match value {
+            Value::Identifier(ident) => keywords
+                .iter()
+                .find(|keyword| ident.name.eq_ignore_ascii_case(keyword))
+                .map(|keyword| CssValue::Keyword(keyword.to_string())),
+            _ => None,
+        })
+    }
+
+    /// Consumes a delimiter, like `,` or `/`
+    fn operator(&mut self, operator: &str) -> bool {
+        self.consume_if(|value| match value {
+            Value::Operator(op) if op.value == operator => Some(()),
+            _ => None,
+        })
+        .is_some()
+    }
+}
+
+/// Splits function arguments at the commas
+fn arguments(values: &[Value]) -> Vec<&[Value]> {
+    values
+        .split(|value| matches!(value, Value::Operator(op) if op.value == ","))
+        .collect()
+}
+
+fn number_value(value: &Value) -> Option<f32> {
+    match value {
+        Value::Dimension(dimension) if dimension.unit.is_none() => dimension.value.parse().ok(),
+        _ => None,
+    }
+}
+
+fn percentage_value(value: &Value) -> Option<f32> {
+    match value {
+        Value::Percentage(percentage) => percentage.value.parse().ok(),
+        _ => None,
+    }
+}
+
+/// A length, or a unitless zero
+fn length_value(value: &Value) -> Option<Length> {
+    match value {
+        Value::Dimension(dimension) => match &dimension.unit {
+            Some(unit) => Some(Length::new(
+                dimension.value.parse().ok()?,
+                LengthUnit::parse(unit)?,
+            )),
+            None if dimension.value.parse::<f32>().ok()? == 0.0 => Some(Length::px(0.0)),
+            None => None,
+        },
+        _ => None,
+    }
+}
+
+/// An angle, or a unitless zero when `allow_zero` is set
+fn angle_value(value: &Value, allow_zero: bool) -> Option<Angle> {
+    match value {
+        Value::Dimension(dimension) => match &dimension.unit {
+            Some(unit) => Some(Angle::new(
+                dimension.value.parse().ok()?,
+                AngleUnit::parse(unit)?,
+            )),
+            None if allow_zero && dimension.value.parse::<f32>().ok()? == 0.0 => {
+                Some(Angle::deg(0.0))
+            }
+            None => None,
+        },
+        _ => None,
+    }
+}
+
+/// A math function, like `calc()`, of one of the given types
+fn calc_value(value: &Value, types: &[CalcType]) -> Option<CalcNode> {
+    match value {
+        Value::Function(function) => {
+            let node = CalcNode::parse(function)?;
+            types.contains(&node.calc_type()).then_some(node)
+        }
+        _ => None,
+    }
+}
+
+fn length_percentage_value(value: &Value, allow_negative: bool) -> Option<LengthPercentage> {
+    if let Some(length) = length_value(value) {
+        return (allow_negative || length.value >= 0.0).then_some(LengthPercentage::Length(length));
+    }
+    if let Some(percentage) = percentage_value(value) {
+        return (allow_negative || percentage >= 0.0)
+            .then_some(LengthPercentage::Percentage(percentage));
+    }
+    // Negative results of math functions are clamped when the value is computed
+    let types = [
+        CalcType::Length,
+        CalcType::Percentage,
+        CalcType::LengthPercentage,
+    ];
+    calc_value(value, &types).map(LengthPercentage::Calc)
+}
+
+fn length(input: &mut Input, allow_negative: bool) -> Option<CssValue> {
+    input.consume_if(|value| {
+        if let Some(length) = length_value(value) {
+            return (allow_negative || length.value >= 0.0).then_some(CssValue::Length(length));
+        }
+        calc_value(value, &[CalcType::Length]).map(CssValue::Calc)
+    })
+}
+
+fn length_percentage(input: &mut Input, allow_negative: bool) -> Option<CssValue> {
+    input
+        .consume_if(|value| length_percentage_value(value, allow_negative))
+        .map(CssValue::from)
+}
+
+fn percentage(input: &mut Input) -> Option<CssValue> {
+    input.consume_if(percentage_value).map(CssValue::Percentage)
+}
+
+fn number(input: &mut Input, allow_negative: bool) -> Option<CssValue> {
+    input.consume_if(|value| {
+        if let Some(number) = number_value(value) {
+            return (allow_negative || number >= 0.0).then_some(CssValue::Number(number));
+        }
+        calc_value(value, &[CalcType::Number]).map(CssValue::Calc)
+    })
+}
+
+fn integer(input: &mut Input) -> Option<CssValue> {
+    input.consume_if(|value| match value {
+        Value::Dimension(dimension) if dimension.unit.is_none() => {
+            dimension.value.parse().ok().map(CssValue::Integer)
+        }
+        _ => None,
+    })
+}
+
+fn color(input: &mut Input) -> Option<CssValue> {
+    input.consume_if(Color::from_value).map(CssValue::Color)
+}
+
+/// Parses one to four values for the top, right, bottom and left sides, like `margin: 1px 2px`
+fn one_to_four(
+    input: &mut Input,
+    parse: impl Fn(&mut Input) -> Option<CssValue>,
+) -> Option<CssValue> {
+    let mut values = vec![parse(input)?];
+    while values.len() < 4 {
+        match input.try_parse(&parse) {
+            Some(value) => values.push(value),
+            None => break,
+        }
+    }
+    Some(CssValue::List(values))
+}
+
+fn line_style(input: &mut Input) -> Option<CssValue> {
+    input.keyword(LINE_STYLES)
+}
+
+fn line_width(input: &mut Input) -> Option<CssValue> {
+    input
+        .keyword(&["thin", "medium", "thick"])
+        .or_else(|| length(input, false))
+}
+
+/// `<line-width> || <line-style> || <color>`, in that order
+fn border(input: &mut Input) -> Option<CssValue> {
+    let mut width = None;
+    let mut style = None;
+    let mut color_value = None;
+    loop {
+        if width.is_none() {
+            if let Some(value) = line_width(input) {
+                width = Some(value);
+                continue;
+            }
+        }
+        if style.is_none() {
+            if let Some(value) = line_style(input) {
+                style = Some(value);
+                continue;
+            }
+        }
+        if color_value.is_none() {
+            if let Some(value) = color(input) {
+                color_value = Some(value);
+                continue;
+            }
+        }
+        break;
+    }
+    let values: Vec<CssValue> = [width, style, color_value].into_iter().flatten().collect();
+    (!values.is_empty()).then_some(CssValue::List(values))
+}
+
+/// `<length-percentage>{1,4} [ / <length-percentage>{1,4} ]?`
+///
+/// Without a slash this is a list of the radii, with a slash it is a list of the horizontal and
+/// the vertical radii.
+fn border_radius(input: &mut Input) -> Option<CssValue> {
+    let radius = |input: &mut Input| length_percentage(input, false);
+    let horizontal = one_to_four(input, radius)?;
+    if !input.operator("/") {
+        return Some(horizontal);
+    }
+    let vertical = one_to_four(input, radius)?;
+    Some(CssValue::List(vec![horizontal, vertical]))
+}
+
+/// `width`, `height` and their minimum and maximum, where `none_keyword` is `auto` or `none`
+fn size(input: &mut Input, none_keyword: &str) -> Option<CssValue> {
+    input
+        .keyword(&[none_keyword, "min-content", "max-content", "fit-content"])
+        .or_else(|| length_percentage(input, false))
+}
+
+fn margin(input: &mut Input) -> Option<CssValue> {
+    input
+        .keyword(&["auto"])
+        .or_else(|| length_percentage(input, true))
+}
+
+fn gap(input: &mut Input) -> Option<CssValue> {
+    input
+        .keyword(&["normal"])
+        .or_else(|| length_percentage(input, false))
+}
+
+/// A comma-separated list of family names and generic families
+fn font_family(input: &mut Input) -> Option<CssValue> {
+    let mut families = vec![];
+    loop {
+        let family = match input.consume()? {
+            Value::String(name) => FontFamily::Named(name.value.clone()),
+            Value::Identifier(first) => {
+                // An unquoted name can have several words
+                let mut words = vec![first.name.clone()];
+                while let Some(Value::Identifier(word)) = input.peek() {
+                    words.push(word.name.clone());
+                    input.consume();
+                }
+                let is_generic = GENERIC_FAMILIES
+                    .iter()
+                    .any(|generic| first.name.eq_ignore_ascii_case(generic));
+                if words.len() == 1 && is_generic {
+                    FontFamily::Generic(first.name.to_ascii_lowercase())
+                } else {
+                    FontFamily::Named(words.join(" "))
+                }
+            }
+            _ => return None,
+        };
+        families.push(family);
+        if !input.operator(",") {
+            break;
+        }
+    }
+    Some(CssValue::FontFamilies(families))
+}
+
+fn font_size(input: &mut Input) -> Option<CssValue> {
+    input
+        .keyword(&[
+            "xx-small",
+            "x-small",
+            "small",
+            "medium",
+            "large",
+            "x-large",
+            "xx-large",
+            "xxx-large",
+            "larger",
+            "smaller",
+        ])
+        .or_else(|| length_percentage(input, false))
+}
+
+fn font_weight(input: &mut Input) -> Option<CssValue> {
+    if let Some(keyword) = input.keyword(&["normal", "bold", "bolder", "lighter"]) {
+        return Some(keyword);
+    }
+    input.consume_if(|value| {
+        let weight = number_value(value)?;
+        (1.0..=1000.0)
+            .contains(&weight)
+            .then_some(CssValue::Number(weight))
+    })
+}
+
+/// `none`, a URL or an image function. Gradients are kept as written for now.
+fn image(input: &mut Input) -> Option<CssValue> {
+    if let Some(keyword) = input.keyword(&["none"]) {
+        return Some(keyword);
+    }
+    input.consume_if(|value| match value {
+        Value::Url(url) => Some(CssValue::Url(url.value.clone())),
+        Value::Function(function) if function.name.eq_ignore_ascii_case("url") => {
+            match function.children.children.as_slice() {
+                [Value::String(url)] => Some(CssValue::Url(url.value.clone())),
+                _ => None,
+            }
+        }
+        Value::Function(function) if function.name.to_ascii_lowercase().ends_with("gradient") => {
+            Some(CssValue::Unparsed(value.to_string()))
+        }
+        _ => None,
+    })
+}
+
+/// `<number [0,∞]> [ / <number [0,∞]> ]?`
+fn ratio(input: &mut Input) -> Option<CssValue> {
+    let non_negative = |value: &Value| number_value(value).filter(|number| *number >= 0.0);
+    let left = input.consume_if(non_negative)?;
+    let right = match input.try_parse(|input| input.operator("/").then_some(())) {
+        Some(()) => input.consume_if(non_negative)?,
+        None => 1.0,
+    };
+    Some(CssValue::Ratio(left, right))
+}
+
+/// `auto || <ratio>`
+fn aspect_ratio(input: &mut Input) -> Option<CssValue> {
+    if input.keyword(&["auto"]).is_some() {
+        return match input.try_parse(ratio) {
+            Some(ratio) => Some(CssValue::List(vec![
+                CssValue::Keyword("auto".into()),
+                ratio,
+            ])),
+            None => Some(CssValue::Keyword("auto".into())),
+        };
+    }
+    let ratio = ratio(input)?;
+    match input.keyword(&["auto"]) {
+        Some(auto) => Some(CssValue::List(vec![auto, ratio])),
+        None => Some(ratio),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::CSS3Parser;
+
+    fn value(property: &str, css: &str) -> Result<CssValue, ValueError> {
+        let values = CSS3Parser::new().parse_value_list(css).unwrap();
+        parse_property_value(property, &values)
+    }
+
+    fn px(value: f32) -> CssValue {
+        CssValue::Length(Length::px(value))
+    }
+
+    fn keyword(name: &str) -> CssValue {
+        CssValue::Keyword(name.to_string())
+    }
+
+    #[test]
+    fn lengths_and_keywords() {
+        assert_eq!(value("width", "10px"), Ok(px(10.0)));
+        assert_eq!(
+            value("width", "2.5EM"),
+            Ok(CssValue::Length(Length::new(2.5, LengthUnit::Em)))
+        );
+        assert_eq!(value("width", "50%"), Ok(CssValue::Percentage(50.0)));
+        assert_eq!(value("height", "Auto"), Ok(keyword("auto")));
+        assert_eq!(value("max-width", "none"), Ok(keyword("none")));
+        assert_eq!(value("min-width", "0"), Ok(px(0.0)));
+        assert_eq!(
+            value("display", "inline-block"),
+            Ok(keyword("inline-block"))
+        );
+        assert_eq!(value("position", "inherit"), Ok(keyword("inherit")));
+        assert_eq!(
+            value("margin", "0 auto"),
+            Ok(CssValue::List(vec![px(0.0), keyword("auto")]))
+        );
+        assert_eq!(
+            value("padding", "1px 2px 3px 4px"),
+            Ok(CssValue::List(vec![px(1.0), px(2.0), px(3.0), px(4.0)]))
+        );
+        assert_eq!(value("z-index", "-1"), Ok(CssValue::Integer(-1)));
+        assert_eq!(value("line-height", "1.5"), Ok(CssValue::Number(1.5)));
+        assert_eq!(value("font-weight", "700"), Ok(CssValue::Number(700.0)));
+        assert_eq!(value("opacity", ".5"), Ok(CssValue::Number(0.5)));
+        assert_eq!(
+            value("--custom", "{ a: b }"),
+            Ok(CssValue::Unparsed("{ a: b }".into()))
+        );
+        assert_eq!(
+            value("width", "var(--width, 10px)"),
+            Ok(CssValue::Unparsed("var(--width, 10px)".into()))
+        );
+    }
+
+    #[test]
+    fn typed_values() {
+        assert_eq!(
+            value("border", "1px solid #000"),
+            Ok(CssValue::List(vec![
+                px(1.0),
+                keyword("solid"),
+                CssValue::Color(Color::rgb(0, 0, 0))
+            ]))
+        );
+        assert_eq!(
+            value("border-top", "red thin"),
+            Ok(CssValue::List(vec![
+                keyword("thin"),
+                CssValue::Color(Color::rgb(255, 0, 0))
+            ]))
+        );
+        assert_eq!(
+            value("border-radius", "50% / 10px"),
+            Ok(CssValue::List(vec![
+                CssValue::List(vec![CssValue::Percentage(50.0)]),
+                CssValue::List(vec![px(10.0)])
+            ]))
+        );
+        assert_eq!(
+            value("font-family", "\"Helvetica Neue\", Segoe UI, SANS-SERIF"),
+            Ok(CssValue::FontFamilies(vec![
+                FontFamily::Named("Helvetica Neue".into()),
+                FontFamily::Named("Segoe UI".into()),
+                FontFamily::Generic("sans-serif".into()),
+            ]))
+        );
+        assert_eq!(
+            value("background-image", "url(a.png)"),
+            Ok(CssValue::Url("a.png".into()))
+        );
+        assert_eq!(
+            value("aspect-ratio", "16 / 9"),
+            Ok(CssValue::Ratio(16.0, 9.0))
+        );
+        assert_eq!(
+            value("aspect-ratio", "auto 2"),
+            Ok(CssValue::List(vec![
+                keyword("auto"),
+                CssValue::Ratio(2.0, 1.0)
+            ]))
+        );
+        assert_eq!(
+            value("letter-spacing", "calc(1em - 2px)"),
+            Ok(CssValue::Calc(CalcNode::Difference(
+                Box::new(CalcNode::Length(Length::new(1.0, LengthUnit::Em))),
+                Box::new(CalcNode::Length(Length::px(2.0))),
+            )))
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let invalid = |property: &str, css: &str| {
+            value(property, css)
+                == Err(ValueError::InvalidValue {
+                    property: property.to_string(),
+                    value: css.to_string(),
+                })
+        };
+        assert!(invalid("width", "-10px"));
+        assert!(invalid("width", "10"));
+        assert!(invalid("width", "10deg"));
+        assert!(invalid("width", "10px 20px"));
+        assert!(invalid("padding", "1px 2px 3px 4px 5px"));
+        assert!(invalid("display", "blocky"));
+        assert!(invalid("color", "10px"));
+        assert!(invalid("border", "solid solid"));
+        assert!(invalid("font-weight", "1001"));
+        assert!(invalid("z-index", "1.5"));
+        assert!(invalid("letter-spacing", "calc(1px + 2)"));
+        assert!(invalid("aspect-ratio", "16 /"));
+        assert_eq!(
+            value("colour", "red"),
+            Err(ValueError::UnknownProperty("colour".into()))
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! [Math functions](https://www.w3.org/TR/css-values-4/#math): `calc()`, `min()`, `max()` and
+//! `clamp()`
+use super::{angle_value, arguments, length_value, number_value, percentage_value, Input};
+use super::{Angle, Length};
+use crate::css::node::{Function, Value};
+
+/// The type a math expression resolves to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalcType {
+    Number,
+    Length,
+    Percentage,
+    /// A mix of lengths and percentages, like `100% - 10px`
+    LengthPercentage,
+    Angle,
+}
+
+impl CalcType {
+    /// Returns the type of a sum of both types, if they can be added
+    fn add(self, other: CalcType) -> Option<CalcType> {
+        use CalcType::*;
+
+        match (self, other) {
+            (left, right) if left == right => Some(left),
+            (Length | Percentage | LengthPercentage, Length | Percentage | LengthPercentage) => {
+                Some(LengthPercentage)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A math expression tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcNode {
+    Number(f32),
+    Length(Length),
+    Percentage(f32),
+    Angle(Angle),
+    Sum(Box<CalcNode>, Box<CalcNode>),
+    Difference(Box<CalcNode>, Box<CalcNode>),
+    Product(Box<CalcNode>, Box<CalcNode>),
+    Quotient(Box<CalcNode>, Box<CalcNode>),
+    Min(Vec<CalcNode>),
+    Max(Vec<CalcNode>),
+    /// `clamp(min, value, max)`
+    Clamp(Box<CalcNode>, Box<CalcNode>, Box<CalcNode>),
+}
+
+impl CalcNode {
+    /// Parses a math function. Returns `None` for other functions, for invalid expressions and
+    /// for expressions that mix incompatible types, like `1px + 2`.
+    pub fn parse(function: &Function) -> Option<CalcNode> {
+        let node = math_function(function)?;
+        node.checked_type()?;
+        Some(node)
+    }
+
+    /// Returns the type of an expression that came from [`CalcNode::parse`]
+    pub fn calc_type(&self) -> CalcType {
+        self.checked_type().unwrap_or(CalcType::Number)
+    }
+
+    fn checked_type(&self) -> Option<CalcType> {
+        match self {
+            CalcNode::Number(_) => Some(CalcType::Number),
+            CalcNode::Length(_) => Some(CalcType::Length),
+            CalcNode::Percentage(_) => Some(CalcType::Percentage),
+            CalcNode::Angle(_) => Some(CalcType::Angle),
+            CalcNode::Sum(left, right) | CalcNode::Difference(left, right) => {
+                left.checked_type()?.add(right.checked_type()?)
+            }
+            CalcNode::Product(left, right) => match (left.checked_type()?, right.checked_type()?) {
+                (CalcType::Number, other) | (other, CalcType::Number) => Some(other),
+                _ => None,
+            },
+            CalcNode::Quotient(left, right) => match right.checked_type()? {
+                CalcType::Number => left.checked_type(),
+                _ => None,
+            },
+            CalcNode::Min(nodes) | CalcNode::Max(nodes) => {
+                let (first, rest) = nodes.split_first()?;
+                rest.iter()
+                    .try_fold(first.checked_type()?, |calc_type, node| {
+                        calc_type.add(node.checked_type()?)
+                    })
+            }
+            CalcNode::Clamp(min, value, max) => min
+                .checked_type()?
+                .add(value.checked_type()?)?
+                .add(max.checked_type()?),
+        }
+    }
+
+    /// Evaluates the expression. Lengths are converted to pixels with `resolve_length`,
+    /// percentages are relative to `percentage_basis` and angles are in degrees.
+    pub fn evaluate(&self, resolve_length: &dyn Fn(&Length) -> f32, percentage_basis: f32) -> f32 {
+        let evaluate = |node: &CalcNode| node.evaluate(resolve_length, percentage_basis);
+        match self {
+            CalcNode::Number(number) => *number,
+            CalcNode::Length(length) => resolve_length(length),
+            CalcNode::Percentage(percentage) => percentage * percentage_basis / 100.0,
+            CalcNode::Angle(angle) => angle.to_degrees(),
+            CalcNode::Sum(left, right) => evaluate(left) + evaluate(right),
+            CalcNode::Difference(left, right) => evaluate(left) - evaluate(right),
+            CalcNode::Product(left, right) => evaluate(left) * evaluate(right),
+            CalcNode::Quotient(left, right) => evaluate(left) / evaluate(right),
+            CalcNode::Min(nodes) => nodes.iter().map(evaluate).fold(f32::INFINITY, f32::min),
+            CalcNode::Max(nodes) => nodes.iter().map(evaluate).fold(f32::NEG_INFINITY, f32::max),
+            // The minimum wins when it is larger than the maximum
+            CalcNode::Clamp(min, value, max) => {
+                evaluate(value).min(evaluate(max)).max(evaluate(min))
+            }
+        }
+    }
+}
+
+fn math_function(function: &Function) -> Option<CalcNode> {
+    let args = arguments(&function.children.children);
+    let sums = || {
+        args.iter()
+            .map(|arg| complete_sum(arg))
+            .collect::<Option<Vec<_>>>()
+    };
+    match function.name.to_ascii_lowercase().as_str() {
+        "calc" if args.len() == 1 => complete_sum(args[0]),
+        "min" => Some(CalcNode::Min(sums()?)),
+        "max" => Some(CalcNode::Max(sums()?)),
+        "clamp" if args.len() == 3 => {
+            let mut sums = sums()?.into_iter().map(Box::new);
+            Some(CalcNode::Clamp(sums.next()?, sums.next()?, sums.next()?))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a sum that spans all of `values`
+fn complete_sum(values: &[Value]) -> Option<CalcNode> {
+    let mut input = Input::new(values);
+    let node = sum(&mut input)?;
+    input.is_empty().then_some(node)
+}
+
+/// ```bnf
+///  CalcSum
+///     : CalcProduct [ ['+' | '-'] CalcProduct ]*
+///     ;
+/// ```
+fn sum(input: &mut Input) -> Option<CalcNode> {
+    let mut node = product(input)?;
+    loop {
+        if input.operator("+") {
+            node = CalcNode::Sum(Box::new(node), Box::new(product(input)?));
+        } else if input.operator("-") {
+            node = CalcNode::Difference(Box::new(node), Box::new(product(input)?));
+        } else {
+            return Some(node);
+        }
+    }
+}
+
+/// ```bnf
+///  CalcProduct
+///     : CalcValue [ ['*' | '/'] CalcValue ]*
+///     ;
+/// ```
+fn product(input: &mut Input) -> Option<CalcNode> {
+    let mut node = calc_value(input)?;
+    loop {
+        if input.operator("*") {
+            node = CalcNode::Product(Box::new(node), Box::new(calc_value(input)?));
+        } else if input.operator("/") {
+            node = CalcNode::Quotient(Box::new(node), Box::new(calc_value(input)?));
+        } else {
+            return Some(node);
+        }
+    }
+}
+
+/// A number, a dimension, a percentage, a parenthesized sum or a nested math function
+fn calc_value(input: &mut Input) -> Option<CalcNode> {
+    let value = input.consume()?;
+    if let Some(number) = number_value(value) {
+        return Some(CalcNode::Number(number));
+    }
+    if let Some(percentage) = percentage_value(value) {
+        return Some(CalcNode::Percentage(percentage));
+    }
+    if let Value::Dimension(dimension) = value {
+        if dimension.unit.is_some() {
+            return length_value(value)
+                .map(CalcNode::Length)
+                .or_else(|| angle_value(value, false).map(CalcNode::Angle));
+        }
+    }
+    match value {
+        Value::Parentheses(children) => complete_sum(&children.children),
+        Value::Function(function) => math_function(function),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::CSS3Parser;
+    use crate::css::values::LengthUnit;
+
+    fn calc(css: &str) -> Option<CalcNode> {
+        let values = CSS3Parser::new().parse_value_list(css).ok()?;
+        match values.children.as_slice() {
+            [Value::Function(function)] => CalcNode::parse(function),
+            _ => None,
+        }
+    }
+
+    fn evaluate(css: &str) -> Option<f32> {
+        let resolve_length = |length: &Length| match length.unit {
+            LengthUnit::Em => length.value * 16.0,
+            _ => length.to_px().unwrap_or_default(),
+        };
+        Some(calc(css)?.evaluate(&resolve_length, 200.0))
+    }
+
+    #[test]
+    fn calc_types() {
+        let calc_type = |css: &str| calc(css).map(|node| node.calc_type());
+        assert_eq!(calc_type("calc(1px + 2em)"), Some(CalcType::Length));
+        assert_eq!(
+            calc_type("calc(100% - 2 * 10px)"),
+            Some(CalcType::LengthPercentage)
+        );
+        assert_eq!(calc_type("calc(50% / 2)"), Some(CalcType::Percentage));
+        assert_eq!(calc_type("calc((1 + 2) * 3)"), Some(CalcType::Number));
+        assert_eq!(calc_type("calc(90deg - .5turn)"), Some(CalcType::Angle));
+        assert_eq!(calc_type("min(10px, 5vw)"), Some(CalcType::Length));
+        assert_eq!(
+            calc_type("clamp(1rem, 2.5vw, 2rem)"),
+            Some(CalcType::Length)
+        );
+
+        assert_eq!(calc_type("calc(1px + 2)"), None);
+        assert_eq!(calc_type("calc(1px * 2px)"), None);
+        assert_eq!(calc_type("calc(2 / 1px)"), None);
+        assert_eq!(calc_type("calc(1px +2px)"), None);
+        assert_eq!(calc_type("calc(1px, 2px)"), None);
+        assert_eq!(calc_type("calc(1px + 1deg)"), None);
+        assert_eq!(calc_type("clamp(1px, 2px)"), None);
+        assert_eq!(calc_type("rgb(1px)"), None);
+    }
+
+    #[test]
+    fn calc_evaluation() {
+        assert_eq!(evaluate("calc(1px + 2 * 3px)"), Some(7.0));
+        assert_eq!(evaluate("calc((1px + 2px) * 3)"), Some(9.0));
+        assert_eq!(evaluate("calc(100% - 1em)"), Some(184.0));
+        assert_eq!(evaluate("calc(1in / 2)"), Some(48.0));
+        assert_eq!(evaluate("min(10px, 2%, 1em)"), Some(4.0));
+        assert_eq!(evaluate("max(10px, calc(2% + 1px))"), Some(10.0));
+        assert_eq!(evaluate("clamp(10px, 50%, 20px)"), Some(20.0));
+        assert_eq!(evaluate("clamp(30px, 50%, 20px)"), Some(30.0));
+    }
+}
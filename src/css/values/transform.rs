@@ -0,0 +1,167 @@
+//! [Transform functions](https://www.w3.org/TR/css-transforms-2/#transform-functions)
+use super::{angle_value, arguments, length_percentage_value, length_value, number_value};
+use super::{percentage_value, Angle, Input, Length, LengthPercentage};
+use crate::css::node::{Function, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformFunction {
+    Matrix([f32; 6]),
+    Matrix3d([f32; 16]),
+    Translate(LengthPercentage, LengthPercentage),
+    TranslateX(LengthPercentage),
+    TranslateY(LengthPercentage),
+    TranslateZ(Length),
+    Translate3d(LengthPercentage, LengthPercentage, Length),
+    Scale(f32, f32),
+    ScaleX(f32),
+    ScaleY(f32),
+    ScaleZ(f32),
+    Scale3d(f32, f32, f32),
+    Rotate(Angle),
+    RotateX(Angle),
+    RotateY(Angle),
+    RotateZ(Angle),
+    /// The axis and the angle
+    Rotate3d(f32, f32, f32, Angle),
+    Skew(Angle, Angle),
+    SkewX(Angle),
+    SkewY(Angle),
+    /// `None` for `perspective(none)`
+    Perspective(Option<Length>),
+}
+
+/// Parses a space-separated list of transform functions
+pub(super) fn transform_list(input: &mut Input) -> Option<Vec<TransformFunction>> {
+    let mut functions = vec![];
+    while let Some(Value::Function(function)) = input.peek() {
+        functions.push(transform_function(function)?);
+        input.consume();
+    }
+    (!functions.is_empty()).then_some(functions)
+}
+
+fn transform_function(function: &Function) -> Option<TransformFunction> {
+    // Every argument is a single component value
+    let args = arguments(&function.children.children)
+        .into_iter()
+        .map(|arg| match arg {
+            [value] => Some(value),
+            _ => None,
+        })
+        .collect::<Option<Vec<&Value>>>()?;
+
+    let lp = |value: &Value| length_percentage_value(value, true);
+    // Angles in transform functions may be a unitless zero
+    let angle = |value: &Value| angle_value(value, true);
+    let numbers = || {
+        args.iter()
+            .map(|arg| number_value(arg))
+            .collect::<Option<Vec<_>>>()
+    };
+
+    let function = match (function.name.to_ascii_lowercase().as_str(), args.as_slice()) {
+        ("matrix", _) => TransformFunction::Matrix(numbers()?.try_into().ok()?),
+        ("matrix3d", _) => TransformFunction::Matrix3d(numbers()?.try_into().ok()?),
+        ("translate", [x]) => {
+            TransformFunction::Translate(lp(x)?, LengthPercentage::Length(Length::px(0.0)))
+        }
+        ("translate", [x, y]) => TransformFunction::Translate(lp(x)?, lp(y)?),
+        ("translatex", [x]) => TransformFunction::TranslateX(lp(x)?),
+        ("translatey", [y]) => TransformFunction::TranslateY(lp(y)?),
+        ("translatez", [z]) => TransformFunction::TranslateZ(length_value(z)?),
+        ("translate3d", [x, y, z]) => {
+            TransformFunction::Translate3d(lp(x)?, lp(y)?, length_value(z)?)
+        }
+        ("scale", [x]) => {
+            let x = scale(x)?;
+            TransformFunction::Scale(x, x)
+        }
+        ("scale", [x, y]) => TransformFunction::Scale(scale(x)?, scale(y)?),
+        ("scalex", [x]) => TransformFunction::ScaleX(scale(x)?),
+        ("scaley", [y]) => TransformFunction::ScaleY(scale(y)?),
+        ("scalez", [z]) => TransformFunction::ScaleZ(scale(z)?),
+        ("scale3d", [x, y, z]) => TransformFunction::Scale3d(scale(x)?, scale(y)?, scale(z)?),
+        ("rotate", [a]) => TransformFunction::Rotate(angle(a)?),
+        ("rotatex", [a]) => TransformFunction::RotateX(angle(a)?),
+        ("rotatey", [a]) => TransformFunction::RotateY(angle(a)?),
+        ("rotatez", [a]) => TransformFunction::RotateZ(angle(a)?),
+        ("rotate3d", [x, y, z, a]) => TransformFunction::Rotate3d(
+            number_value(x)?,
+            number_value(y)?,
+            number_value(z)?,
+            angle(a)?,
+        ),
+        ("skew", [x]) => TransformFunction::Skew(angle(x)?, Angle::deg(0.0)),
+        ("skew", [x, y]) => TransformFunction::Skew(angle(x)?, angle(y)?),
+        ("skewx", [x]) => TransformFunction::SkewX(angle(x)?),
+        ("skewy", [y]) => TransformFunction::SkewY(angle(y)?),
+        ("perspective", [Value::Identifier(ident)]) if ident.name.eq_ignore_ascii_case("none") => {
+            TransformFunction::Perspective(None)
+        }
+        ("perspective", [depth]) => {
+            TransformFunction::Perspective(Some(length_value(depth).filter(|d| d.value >= 0.0)?))
+        }
+        _ => return None,
+    };
+    Some(function)
+}
+
+/// A number, or a percentage where 100% is 1
+fn scale(value: &Value) -> Option<f32> {
+    number_value(value).or_else(|| percentage_value(value).map(|percentage| percentage / 100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::CSS3Parser;
+    use crate::css::values::{AngleUnit, LengthUnit};
+
+    fn transform(css: &str) -> Option<Vec<TransformFunction>> {
+        let values = CSS3Parser::new().parse_value_list(css).ok()?;
+        let mut input = Input::new(&values.children);
+        let functions = transform_list(&mut input)?;
+        input.is_empty().then_some(functions)
+    }
+
+    #[test]
+    fn transform_functions() {
+        assert_eq!(
+            transform("translate(10px, 50%) rotate(.25turn) scale(150%)"),
+            Some(vec![
+                TransformFunction::Translate(
+                    LengthPercentage::Length(Length::px(10.0)),
+                    LengthPercentage::Percentage(50.0)
+                ),
+                TransformFunction::Rotate(Angle::new(0.25, AngleUnit::Turn)),
+                TransformFunction::Scale(1.5, 1.5),
+            ])
+        );
+        assert_eq!(
+            transform("matrix(1, 0, 0, 1, 10, 20) skewX(0)"),
+            Some(vec![
+                TransformFunction::Matrix([1.0, 0.0, 0.0, 1.0, 10.0, 20.0]),
+                TransformFunction::SkewX(Angle::deg(0.0)),
+            ])
+        );
+        assert_eq!(
+            transform("translate3d(0, 0, 1cm) perspective(none)"),
+            Some(vec![
+                TransformFunction::Translate3d(
+                    LengthPercentage::Length(Length::px(0.0)),
+                    LengthPercentage::Length(Length::px(0.0)),
+                    Length::new(1.0, LengthUnit::Cm)
+                ),
+                TransformFunction::Perspective(None),
+            ])
+        );
+
+        assert_eq!(transform("matrix(1, 0, 0, 1, 10)"), None);
+        assert_eq!(transform("rotate(10px)"), None);
+        assert_eq!(transform("translate(1px 2px)"), None);
+        assert_eq!(transform("translateZ(10%)"), None);
+        assert_eq!(transform("perspective(-1px)"), None);
+        assert_eq!(transform("wobble(1deg)"), None);
+        assert_eq!(transform("rotate(1deg) 1px"), None);
+    }
+}
@@ -2,10 +2,19 @@
 //!
 //! The parser's job is to take a stream of bytes and turn it into a DOM tree. The parser is
 //! implemented as a state machine and runs in the current thread.
+pub mod diff;
 pub mod dom;
 pub mod element_class;
 pub mod error_logger;
+pub mod forms;
+pub mod inner_text;
 pub mod input_stream;
+pub mod metadata;
+pub mod microdata;
+pub mod mutation_observer;
 pub mod node;
 pub mod parser;
+pub mod sanitizer;
+pub mod serializer;
+pub mod text_renderer;
 pub mod tokenizer;
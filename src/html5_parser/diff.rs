@@ -0,0 +1,851 @@
+//! Structural diff between two documents
+//!
+//! The diff compares the trees of two documents and reports the nodes that were inserted, removed
+//! or moved, and the attributes and text that changed. Nodes are identified by their path: the
+//! list of child indices from the document root down to the node. The contents of a shadow root
+//! are found below a `shadow-root` step after the path of its host.
+//!
+//! Children of two matching nodes are paired up by their type, name and `id` attribute (using the
+//! longest common subsequence, so the order of the kept nodes is preserved). Children that cannot
+//! be paired are removed or inserted, unless an identical subtree is removed on one place and
+//! inserted on another, in which case it is reported as a move. Elements are only paired when
+//! both have no shadow root, or both have one with the same options; the shadow trees of paired
+//! elements are compared like their children.
+//!
+//! The resulting `Patch` can be serialized and applied to the old document to turn it into the
+//! new one. Paths of removed nodes, moved nodes (`from`) and changed attributes or text refer to
+//! the old document; paths of inserted nodes and the destination of moves (`to`) refer to the new
+//! document.
+use crate::html5_parser::node::{Node, NodeData, NodeId};
+use crate::html5_parser::parser::document::{Document, DocumentHandle};
+use crate::html5_parser::serializer::json::{build_node, JsonNode, JsonShadowRoot};
+use crate::types::{Error, Result};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+/// Path of a node in the document: the steps from the root down to the node
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NodePath(pub Vec<PathSegment>);
+
+/// A single step of a node path. Children are written as their index, the shadow root of an
+/// element as `"shadow-root"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PathSegment {
+    /// The child at the index
+    Child(usize),
+    /// The shadow root attached to the element
+    ShadowRoot(ShadowRootSegment),
+}
+
+/// Marker for the shadow root segment, which (de)serializes as `"shadow-root"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ShadowRootSegment {
+    #[serde(rename = "shadow-root")]
+    ShadowRoot,
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "/");
+        }
+        for segment in &self.0 {
+            match segment {
+                PathSegment::Child(index) => write!(f, "/{}", index)?,
+                PathSegment::ShadowRoot(_) => write!(f, "/shadow-root")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NodePath {
+    /// Returns the path of the child at the given index
+    fn child(&self, index: usize) -> NodePath {
+        self.join(PathSegment::Child(index))
+    }
+
+    /// Returns the path of the shadow root attached to the element at this path
+    fn shadow_root(&self) -> NodePath {
+        self.join(PathSegment::ShadowRoot(ShadowRootSegment::ShadowRoot))
+    }
+
+    fn join(&self, segment: PathSegment) -> NodePath {
+        let mut path = self.0.clone();
+        path.push(segment);
+        NodePath(path)
+    }
+}
+
+/// Options for comparing documents
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiffOptions {
+    /// Skip text nodes that only contain whitespace. Paths will not count these nodes either.
+    pub ignore_whitespace: bool,
+}
+
+/// A single change between two documents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+pub enum Change {
+    /// A node (and its children) was inserted at the path in the new document
+    Inserted { path: NodePath, node: JsonNode },
+    /// A node (and its children) was removed from the path in the old document
+    Removed { path: NodePath, node: JsonNode },
+    /// An identical subtree was moved to another place in the document
+    Moved { from: NodePath, to: NodePath },
+    /// An attribute was added (no old value), removed (no new value) or changed
+    AttributeChanged {
+        path: NodePath,
+        name: String,
+        old_value: Option<String>,
+        new_value: Option<String>,
+    },
+    /// The data of a text or comment node changed
+    TextChanged {
+        path: NodePath,
+        old_value: String,
+        new_value: String,
+    },
+}
+
+/// List of changes that turn one document into another
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Patch {
+    /// Whether whitespace-only text nodes were skipped (and are not counted in the paths)
+    pub ignore_whitespace: bool,
+    pub changes: Vec<Change>,
+}
+
+/// Compares two documents and returns the changes needed to turn the old document into the new one
+pub fn diff(old: &Document, new: &Document, options: DiffOptions) -> Patch {
+    let mut differ = Differ {
+        old,
+        new,
+        options,
+        updates: vec![],
+        removed: vec![],
+        inserted: vec![],
+    };
+    differ.diff_children(
+        NodeId::root(),
+        NodeId::root(),
+        &NodePath::default(),
+        &NodePath::default(),
+    );
+    differ.into_patch()
+}
+
+struct Differ<'a> {
+    old: &'a Document,
+    new: &'a Document,
+    options: DiffOptions,
+    /// Attribute and text changes
+    updates: Vec<Change>,
+    /// Subtrees that exist only in the old document
+    removed: Vec<(NodePath, JsonNode)>,
+    /// Subtrees that exist only in the new document
+    inserted: Vec<(NodePath, JsonNode)>,
+}
+
+impl<'a> Differ<'a> {
+    fn diff_children(
+        &mut self,
+        old_id: NodeId,
+        new_id: NodeId,
+        old_path: &NodePath,
+        new_path: &NodePath,
+    ) {
+        let ignore_whitespace = self.options.ignore_whitespace;
+        let old_children = significant_children(self.old, old_id, ignore_whitespace);
+        let new_children = significant_children(self.new, new_id, ignore_whitespace);
+
+        let old_keys: Vec<String> = old_children
+            .iter()
+            .map(|n| match_key(self.old, n))
+            .collect();
+        let new_keys: Vec<String> = new_children
+            .iter()
+            .map(|n| match_key(self.new, n))
+            .collect();
+        let pairs = longest_common_subsequence(&old_keys, &new_keys);
+
+        let mut old_matched = vec![false; old_children.len()];
+        let mut new_matched = vec![false; new_children.len()];
+        for (old_idx, new_idx) in pairs {
+            old_matched[old_idx] = true;
+            new_matched[new_idx] = true;
+            self.diff_node(
+                old_children[old_idx],
+                new_children[new_idx],
+                &old_path.child(old_idx),
+                &new_path.child(new_idx),
+            );
+        }
+
+        for (idx, node) in old_children.iter().enumerate() {
+            if !old_matched[idx] {
+                if let Some(json) = self.old.json_node(node) {
+                    self.removed.push((old_path.child(idx), json));
+                }
+            }
+        }
+        for (idx, node) in new_children.iter().enumerate() {
+            if !new_matched[idx] {
+                if let Some(json) = self.new.json_node(node) {
+                    self.inserted.push((new_path.child(idx), json));
+                }
+            }
+        }
+    }
+
+    /// Compares two nodes that have the same match key
+    fn diff_node(&mut self, old: &Node, new: &Node, old_path: &NodePath, new_path: &NodePath) {
+        match (&old.data, &new.data) {
+            (NodeData::Element(old_element), NodeData::Element(new_element)) => {
+                let mut names: Vec<&String> = old_element
+                    .attributes
+                    .iter()
+                    .chain(new_element.attributes.iter())
+                    .map(|(name, _)| name)
+                    .collect();
+                names.sort();
+                names.dedup();
+
+                for name in names {
+                    let old_value = old_element.attributes.get(name);
+                    let new_value = new_element.attributes.get(name);
+                    if old_value != new_value {
+                        self.updates.push(Change::AttributeChanged {
+                            path: old_path.clone(),
+                            name: name.clone(),
+                            old_value: old_value.cloned(),
+                            new_value: new_value.cloned(),
+                        });
+                    }
+                }
+
+                self.diff_children(old.id, new.id, old_path, new_path);
+
+                // The match key makes sure both have a shadow root with the same options, or
+                // neither has one
+                if let (Some(old_root), Some(new_root)) =
+                    (old_element.shadow_root, new_element.shadow_root)
+                {
+                    self.diff_children(
+                        old_root,
+                        new_root,
+                        &old_path.shadow_root(),
+                        &new_path.shadow_root(),
+                    );
+                }
+            }
+            (NodeData::Text(old_text), NodeData::Text(new_text)) => {
+                self.diff_text(old_path, old_text.value(), new_text.value());
+            }
+            (NodeData::Comment(old_comment), NodeData::Comment(new_comment)) => {
+                self.diff_text(old_path, old_comment.value(), new_comment.value());
+            }
+            _ => {}
+        }
+    }
+
+    fn diff_text(&mut self, path: &NodePath, old_value: &str, new_value: &str) {
+        if old_value != new_value {
+            self.updates.push(Change::TextChanged {
+                path: path.clone(),
+                old_value: old_value.to_string(),
+                new_value: new_value.to_string(),
+            });
+        }
+    }
+
+    /// Pairs identical removed and inserted subtrees as moves, and orders the changes so they can
+    /// be applied: updates and removals first, then insertions in document order.
+    fn into_patch(self) -> Patch {
+        let ignore_whitespace = self.options.ignore_whitespace;
+        let signature = |json: &JsonNode| {
+            if ignore_whitespace {
+                strip_whitespace(json)
+            } else {
+                json.clone()
+            }
+        };
+
+        let inserted_signatures: Vec<JsonNode> = self
+            .inserted
+            .iter()
+            .map(|(_, json)| signature(json))
+            .collect();
+        let mut inserted_used = vec![false; self.inserted.len()];
+
+        let mut changes = self.updates;
+        let mut placements = vec![];
+        for (from, json) in self.removed {
+            let removed_signature = signature(&json);
+            let found = inserted_signatures
+                .iter()
+                .enumerate()
+                .position(|(idx, s)| !inserted_used[idx] && *s == removed_signature);
+
+            match found {
+                Some(idx) => {
+                    inserted_used[idx] = true;
+                    placements.push(Change::Moved {
+                        from,
+                        to: self.inserted[idx].0.clone(),
+                    });
+                }
+                None => changes.push(Change::Removed {
+                    path: from,
+                    node: json,
+                }),
+            }
+        }
+
+        for (idx, (path, json)) in self.inserted.into_iter().enumerate() {
+            if !inserted_used[idx] {
+                placements.push(Change::Inserted { path, node: json });
+            }
+        }
+        placements.sort_by(|a, b| destination(a).cmp(&destination(b)));
+        changes.extend(placements);
+
+        Patch {
+            ignore_whitespace,
+            changes,
+        }
+    }
+}
+
+/// Returns the path in the new document where the change places a node
+fn destination(change: &Change) -> Option<&NodePath> {
+    match change {
+        Change::Inserted { path, .. } => Some(path),
+        Change::Moved { to, .. } => Some(to),
+        _ => None,
+    }
+}
+
+impl Patch {
+    /// Returns true when the documents were equal
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Applies the patch to the (old) document. Changes are made through the DOM manipulation
+    /// API, so they are reported to mutation observers. Fails when the document does not match
+    /// the document the patch was created from.
+    pub fn apply(&self, document: &mut DocumentHandle) -> Result<()> {
+        // Resolve all paths into the old document before anything is changed
+        let mut resolved = Vec::with_capacity(self.changes.len());
+        {
+            let doc = document.get();
+            for change in &self.changes {
+                let path = match change {
+                    Change::Inserted { .. } => None,
+                    Change::Removed { path, .. }
+                    | Change::AttributeChanged { path, .. }
+                    | Change::TextChanged { path, .. } => Some(path),
+                    Change::Moved { from, .. } => Some(from),
+                };
+                resolved.push(match path {
+                    Some(path) => Some(self.resolve(&doc, path)?),
+                    None => None,
+                });
+            }
+        }
+
+        for (change, node_id) in self.changes.iter().zip(resolved.iter()) {
+            let Some(node_id) = *node_id else {
+                continue;
+            };
+
+            let mut doc = document.get_mut();
+            match change {
+                Change::AttributeChanged {
+                    path,
+                    name,
+                    old_value,
+                    new_value,
+                } => {
+                    let current = match doc.get_node_by_id(node_id).map(|n| &n.data) {
+                        Some(NodeData::Element(element)) => element.attributes.get(name).cloned(),
+                        _ => return Err(Error::Patch(format!("{} is not an element", path))),
+                    };
+                    if current != *old_value {
+                        return Err(Error::Patch(format!(
+                            "attribute {} of {} does not match",
+                            name, path
+                        )));
+                    }
+                    match new_value {
+                        Some(value) => doc.set_attribute(node_id, name, value),
+                        None => doc.remove_attribute(node_id, name),
+                    };
+                }
+                Change::TextChanged {
+                    path,
+                    old_value,
+                    new_value,
+                } => {
+                    let current = match doc.get_node_by_id(node_id).map(|n| &n.data) {
+                        Some(NodeData::Text(text)) => text.value(),
+                        Some(NodeData::Comment(comment)) => comment.value(),
+                        _ => return Err(Error::Patch(format!("{} is not a text node", path))),
+                    };
+                    if current != old_value {
+                        return Err(Error::Patch(format!("text of {} does not match", path)));
+                    }
+                    doc.set_character_data(node_id, new_value);
+                }
+                Change::Removed { .. } | Change::Moved { .. } => {
+                    if let Some(parent_id) = doc.get_node_by_id(node_id).and_then(|n| n.parent) {
+                        doc.remove_child(parent_id, node_id);
+                    }
+                    if matches!(change, Change::Removed { .. }) {
+                        doc.delete_node(node_id);
+                    }
+                }
+                Change::Inserted { .. } => {}
+            }
+        }
+
+        // Place the inserted and moved nodes. These are sorted by their path in the new document,
+        // so all preceding siblings and the parent are already in place.
+        for (change, node_id) in self.changes.iter().zip(resolved.iter()) {
+            let Some(path) = destination(change) else {
+                continue;
+            };
+            let Some((PathSegment::Child(index), parent_path)) = path.0.split_last() else {
+                return Err(Error::Patch(format!("cannot insert a node at {}", path)));
+            };
+            let parent_id = self.resolve(&document.get(), &NodePath(parent_path.to_vec()))?;
+
+            let node_id = match (change, node_id) {
+                (Change::Moved { .. }, Some(node_id)) => *node_id,
+                (Change::Inserted { node, .. }, _) => {
+                    // Build the node below the parent, and take it out again so it can be
+                    // inserted at the right position.
+                    let node_id = build_node(document, parent_id, node)?;
+                    let mut doc = document.get_mut();
+                    if let Some(parent) = doc.get_node_by_id_mut(parent_id) {
+                        parent.children.retain(|id| *id != node_id);
+                    }
+                    if let Some(node) = doc.get_node_by_id_mut(node_id) {
+                        node.parent = None;
+                    }
+                    node_id
+                }
+                _ => continue,
+            };
+
+            let mut doc = document.get_mut();
+            let reference_id = significant_children(&doc, parent_id, self.ignore_whitespace)
+                .get(*index)
+                .map(|n| n.id);
+            if !doc.insert_before(parent_id, node_id, reference_id) {
+                return Err(Error::Patch(format!("cannot insert node at {}", path)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, document: &Document, path: &NodePath) -> Result<NodeId> {
+        document
+            .node_at_path(path, self.ignore_whitespace)
+            .ok_or_else(|| Error::Patch(format!("no node found at {}", path)))
+    }
+}
+
+impl Document {
+    /// Returns the node found at the given path
+    pub fn node_at_path(&self, path: &NodePath, ignore_whitespace: bool) -> Option<NodeId> {
+        let mut current = NodeId::root();
+        for segment in &path.0 {
+            current = match segment {
+                PathSegment::Child(index) => {
+                    significant_children(self, current, ignore_whitespace)
+                        .get(*index)?
+                        .id
+                }
+                PathSegment::ShadowRoot(_) => self.shadow_root_of(current)?,
+            };
+        }
+        Some(current)
+    }
+
+    /// Returns the path of the node, or None when the node is not connected to the document root
+    pub fn path_of(&self, node_id: NodeId, ignore_whitespace: bool) -> Option<NodePath> {
+        let mut path = vec![];
+        let mut current = node_id;
+        while !current.is_root() {
+            let node = self.get_node_by_id(current)?;
+            if let NodeData::ShadowRoot(shadow_root) = &node.data {
+                path.push(PathSegment::ShadowRoot(ShadowRootSegment::ShadowRoot));
+                current = shadow_root.host;
+                continue;
+            }
+
+            let parent_id = node.parent?;
+            let index = significant_children(self, parent_id, ignore_whitespace)
+                .iter()
+                .position(|n| n.id == current)?;
+            path.push(PathSegment::Child(index));
+            current = parent_id;
+        }
+        path.reverse();
+        Some(NodePath(path))
+    }
+}
+
+/// Returns the children of the node, without whitespace-only text nodes when these are ignored
+fn significant_children(
+    document: &Document,
+    node_id: NodeId,
+    ignore_whitespace: bool,
+) -> Vec<&Node> {
+    let Some(node) = document.get_node_by_id(node_id) else {
+        return vec![];
+    };
+
+    node.children
+        .iter()
+        .filter_map(|id| document.get_node_by_id(*id))
+        .filter(|child| !(ignore_whitespace && is_whitespace_text(child)))
+        .collect()
+}
+
+fn is_whitespace_text(node: &Node) -> bool {
+    match &node.data {
+        NodeData::Text(text) => text.value().chars().all(|c| c.is_ascii_whitespace()),
+        _ => false,
+    }
+}
+
+/// Key that decides which nodes can be paired: the same type, name and id, and for elements a
+/// shadow root with the same options
+fn match_key(document: &Document, node: &Node) -> String {
+    match &node.data {
+        NodeData::Element(element) => format!(
+            "element:{}:{}#{}{}",
+            node.namespace.as_deref().unwrap_or_default(),
+            node.name,
+            element
+                .attributes
+                .get("id")
+                .map(|id| id.as_str())
+                .unwrap_or_default(),
+            element
+                .shadow_root
+                .and_then(|id| document.shadow_root_data(id))
+                .map(|shadow_root| format!(
+                    ":{:?}:{}:{:?}:{}:{}",
+                    shadow_root.mode,
+                    shadow_root.delegates_focus,
+                    shadow_root.slot_assignment,
+                    shadow_root.clonable,
+                    shadow_root.serializable
+                ))
+                .unwrap_or_default()
+        ),
+        NodeData::Text(_) => "text".to_string(),
+        NodeData::Comment(_) => "comment".to_string(),
+        NodeData::Document(_) => "document".to_string(),
+        NodeData::ShadowRoot(_) => "shadow-root".to_string(),
+    }
+}
+
+/// Removes all whitespace-only text nodes from the json node
+fn strip_whitespace(json: &JsonNode) -> JsonNode {
+    let strip = |children: &Vec<JsonNode>| {
+        children
+            .iter()
+            .filter(|child| {
+                !matches!(child, JsonNode::Text { value } if value.chars().all(|c| c.is_ascii_whitespace()))
+            })
+            .map(strip_whitespace)
+            .collect::<Vec<_>>()
+    };
+
+    match json {
+        JsonNode::Element {
+            name,
+            namespace,
+            attributes,
+            children,
+            template_contents,
+            shadow_root,
+        } => JsonNode::Element {
+            name: name.clone(),
+            namespace: namespace.clone(),
+            attributes: attributes.clone(),
+            children: strip(children),
+            template_contents: template_contents.as_ref().map(strip),
+            shadow_root: shadow_root.as_ref().map(|shadow_root| JsonShadowRoot {
+                children: strip(&shadow_root.children),
+                ..shadow_root.clone()
+            }),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Returns the index pairs of the longest common subsequence of both lists
+fn longest_common_subsequence(old: &[String], new: &[String]) -> Vec<(usize, usize)> {
+    // lengths[i][j] is the length of the LCS of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html5_parser::node::HTML_NAMESPACE;
+    use crate::testing::parse_html;
+
+    fn path(indices: &[usize]) -> NodePath {
+        NodePath(indices.iter().map(|idx| PathSegment::Child(*idx)).collect())
+    }
+
+    /// Diffs both documents, applies the patch on the old one and checks it equals the new one
+    fn assert_patch_applies(old: &str, new: &str, options: DiffOptions) -> Patch {
+        let mut old = parse_html(old);
+        let new = parse_html(new);
+
+        let patch = diff(&old.get(), &new.get(), options);
+        patch.apply(&mut old).expect("apply patch");
+        assert!(diff(&old.get(), &new.get(), options).is_empty());
+        if !options.ignore_whitespace {
+            assert_eq!(old.get().to_json().unwrap(), new.get().to_json().unwrap());
+        }
+        patch
+    }
+
+    #[test]
+    fn equal_documents() {
+        let html = "<div id=\"a\"><p>hello</p></div>";
+        let patch = diff(
+            &parse_html(html).get(),
+            &parse_html(html).get(),
+            DiffOptions::default(),
+        );
+        assert!(patch.is_empty());
+    }
+
+    #[test]
+    fn attribute_and_text_changes() {
+        let patch = assert_patch_applies(
+            "<p class=\"a\" title=\"t\">hello</p>",
+            "<p class=\"b\" lang=\"en\">world</p>",
+            DiffOptions::default(),
+        );
+
+        let p = path(&[0, 1, 0]);
+        assert_eq!(
+            patch.changes,
+            vec![
+                Change::AttributeChanged {
+                    path: p.clone(),
+                    name: "class".to_string(),
+                    old_value: Some("a".to_string()),
+                    new_value: Some("b".to_string()),
+                },
+                Change::AttributeChanged {
+                    path: p.clone(),
+                    name: "lang".to_string(),
+                    old_value: None,
+                    new_value: Some("en".to_string()),
+                },
+                Change::AttributeChanged {
+                    path: p.clone(),
+                    name: "title".to_string(),
+                    old_value: Some("t".to_string()),
+                    new_value: None,
+                },
+                Change::TextChanged {
+                    path: p.child(0),
+                    old_value: "hello".to_string(),
+                    new_value: "world".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn inserted_and_removed_nodes() {
+        let patch = assert_patch_applies(
+            "<div><span>1</span><span id=\"x\">2</span><span>3</span></div>",
+            "<div><span>1</span><span>3</span><span>4<b>!</b></span></div><p>end</p>",
+            DiffOptions::default(),
+        );
+        assert!(matches!(
+            &patch.changes[0],
+            Change::Removed { path, .. } if path.to_string() == "/0/1/0/1"
+        ));
+        assert!(patch
+            .changes
+            .iter()
+            .any(|c| matches!(c, Change::Inserted { path, .. } if path.to_string() == "/0/1/1")));
+    }
+
+    #[test]
+    fn moved_nodes() {
+        let patch = assert_patch_applies(
+            "<div id=\"a\"><span>x</span></div><div id=\"b\"></div><p id=\"c\">c</p>",
+            "<p id=\"c\">c</p><div id=\"a\"></div><div id=\"b\"><span>x</span></div>",
+            DiffOptions::default(),
+        );
+
+        assert!(patch
+            .changes
+            .iter()
+            .all(|c| matches!(c, Change::Moved { .. })));
+        assert!(patch.changes.contains(&Change::Moved {
+            from: path(&[0, 1, 0, 0]),
+            to: path(&[0, 1, 2, 0]),
+        }));
+    }
+
+    #[test]
+    fn shadow_roots() {
+        let host = |contents: &str| {
+            format!(
+                "<div><template shadowrootmode=open>{}</template><span>light</span></div>",
+                contents
+            )
+        };
+        let patch = assert_patch_applies(
+            &host("<p>a</p>"),
+            &host("<p>b</p><slot></slot>"),
+            DiffOptions::default(),
+        );
+        let p = path(&[0, 1, 0]).shadow_root().child(0);
+        assert_eq!(
+            patch.changes,
+            vec![
+                Change::TextChanged {
+                    path: p.child(0),
+                    old_value: "a".to_string(),
+                    new_value: "b".to_string(),
+                },
+                Change::Inserted {
+                    path: path(&[0, 1, 0]).shadow_root().child(1),
+                    node: JsonNode::Element {
+                        name: "slot".to_string(),
+                        namespace: Some(HTML_NAMESPACE.to_string()),
+                        attributes: Default::default(),
+                        children: vec![],
+                        template_contents: None,
+                        shadow_root: None,
+                    },
+                },
+            ]
+        );
+        assert!(serde_json::to_string(&patch)
+            .unwrap()
+            .contains("[0,1,0,\"shadow-root\",0,0]"));
+
+        // A shadow root with other options replaces the host
+        let patch = assert_patch_applies(
+            &host("<p>a</p>"),
+            "<div><template shadowrootmode=closed><p>a</p></template><span>light</span></div>",
+            DiffOptions::default(),
+        );
+        assert!(matches!(
+            &patch.changes[..],
+            [Change::Removed { .. }, Change::Inserted { .. }]
+        ));
+
+        // Only whitespace changed in the shadow tree
+        let options = DiffOptions {
+            ignore_whitespace: true,
+        };
+        assert_patch_applies(&host("<p>a</p>"), &host("\n  <p>c</p>\n"), options);
+    }
+
+    #[test]
+    fn ignore_whitespace() {
+        let old = "<div>\n  <p>a</p>\n</div>";
+        let new = "<div><p>a</p></div>";
+
+        let patch = diff(
+            &parse_html(old).get(),
+            &parse_html(new).get(),
+            DiffOptions::default(),
+        );
+        assert!(!patch.is_empty());
+
+        let options = DiffOptions {
+            ignore_whitespace: true,
+        };
+        let patch = diff(&parse_html(old).get(), &parse_html(new).get(), options);
+        assert!(patch.is_empty());
+
+        assert_patch_applies(old, "<div>\n <p>b</p> <i>c</i></div>", options);
+    }
+
+    #[test]
+    fn patch_roundtrip_and_conflicts() {
+        let old = parse_html("<p title=\"a\">x</p>");
+        let new = parse_html("<p title=\"b\">y</p><hr>");
+        let patch = diff(&old.get(), &new.get(), DiffOptions::default());
+
+        let json = serde_json::to_string(&patch).unwrap();
+        let loaded: Patch = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, patch);
+
+        // Applying to a document that differs from the original fails
+        let mut other = parse_html("<p title=\"c\">x</p>");
+        assert!(loaded.apply(&mut other).is_err());
+    }
+
+    #[test]
+    fn paths() {
+        let document = parse_html("<div>\n<p id=\"p\">a</p></div>");
+        let doc = document.get();
+        let p = doc.get_node_by_named_id("p").unwrap().id;
+
+        let path = doc.path_of(p, false).unwrap();
+        assert_eq!(path.to_string(), "/0/1/0/1");
+        assert_eq!(doc.node_at_path(&path, false), Some(p));
+
+        let path = doc.path_of(p, true).unwrap();
+        assert_eq!(path.to_string(), "/0/1/0/0");
+        assert_eq!(doc.node_at_path(&path, true), Some(p));
+
+        let document =
+            parse_html("<div><template shadowrootmode=open><b id=\"b\"></b></template></div>");
+        let doc = document.get();
+        let b = doc.get_node_by_named_id("b").unwrap().id;
+        let path = doc.path_of(b, false).unwrap();
+        assert_eq!(path.to_string(), "/0/1/0/shadow-root/0");
+        assert_eq!(doc.node_at_path(&path, false), Some(b));
+    }
+}
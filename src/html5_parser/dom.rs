@@ -1,23 +1,419 @@
+use crate::html5_parser::node::{Node, NodeData, NodeId, HTML_NAMESPACE};
+use crate::html5_parser::parser::document::{Document, DocumentHandle};
 use std::collections::HashMap;
 
+/// HTML elements that are allowed to host a shadow root (besides valid custom element names)
+/// <https://dom.spec.whatwg.org/#dom-element-attachshadow>
+pub static VALID_SHADOW_HOST_ELEMENTS: [&str; 18] = [
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "div",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "main",
+    "nav",
+    "p",
+    "section",
+    "span",
+];
+
+/// Names that match the custom element name production but are reserved by SVG and MathML
+/// <https://html.spec.whatwg.org/multipage/custom-elements.html#valid-custom-element-name>
+static RESERVED_CUSTOM_ELEMENT_NAMES: [&str; 8] = [
+    "annotation-xml",
+    "color-profile",
+    "font-face",
+    "font-face-format",
+    "font-face-name",
+    "font-face-src",
+    "font-face-uri",
+    "missing-glyph",
+];
+
+/// Data for a shadow root node. The shadow root itself is a node in the arena without a parent,
+/// and its host element points back to it.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ShadowRoot {
     pub mode: ShadowRootMode,
     pub delegates_focus: bool,
     pub slot_assignment: SlotAssignmentMode,
-    pub host: Box<Element>,
+    pub clonable: bool,
+    pub serializable: bool,
+    /// Element that hosts this shadow root
+    pub host: NodeId,
     // pub onslotchange: Option<EventHandler>,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum SlotAssignmentMode {
     Manual,
     Named,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ShadowRootMode {
     Open,
     Closed,
 }
 
+impl ShadowRootMode {
+    /// Parses the value of a "shadowrootmode" attribute (ASCII case-insensitive)
+    pub fn from_attribute(value: &str) -> Option<Self> {
+        if value.eq_ignore_ascii_case("open") {
+            Some(ShadowRootMode::Open)
+        } else if value.eq_ignore_ascii_case("closed") {
+            Some(ShadowRootMode::Closed)
+        } else {
+            None
+        }
+    }
+}
+
+/// Options for attaching a shadow root (the ShadowRootInit dictionary)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ShadowRootInit {
+    pub mode: ShadowRootMode,
+    pub delegates_focus: bool,
+    pub slot_assignment: SlotAssignmentMode,
+    pub clonable: bool,
+    pub serializable: bool,
+}
+
+impl ShadowRootInit {
+    pub fn new(mode: ShadowRootMode) -> Self {
+        Self {
+            mode,
+            delegates_focus: false,
+            slot_assignment: SlotAssignmentMode::Named,
+            clonable: false,
+            serializable: false,
+        }
+    }
+}
+
+/// Returns true when the name is a valid custom element name (simplified check)
+fn is_valid_custom_element_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.contains('-')
+        && !name.contains(|c: char| c.is_ascii_uppercase())
+        && !RESERVED_CUSTOM_ELEMENT_NAMES.contains(&name)
+}
+
+impl DocumentHandle {
+    /// Attaches a new shadow root to the given host element. Returns the node id of the shadow
+    /// root, or None when the element cannot host a shadow root or already has one.
+    pub fn attach_shadow(&mut self, host_id: NodeId, init: ShadowRootInit) -> Option<NodeId> {
+        {
+            let doc = self.get();
+            let host = doc.get_node_by_id(host_id)?;
+            let NodeData::Element(element) = &host.data else {
+                return None;
+            };
+
+            if host.namespace.as_deref() != Some(HTML_NAMESPACE) {
+                return None;
+            }
+            if !VALID_SHADOW_HOST_ELEMENTS.contains(&host.name.as_str())
+                && !is_valid_custom_element_name(&host.name)
+            {
+                return None;
+            }
+            if element.shadow_root.is_some() {
+                return None;
+            }
+        }
+
+        let shadow_root = ShadowRoot {
+            mode: init.mode,
+            delegates_focus: init.delegates_focus,
+            slot_assignment: init.slot_assignment,
+            clonable: init.clonable,
+            serializable: init.serializable,
+            host: host_id,
+        };
+        let node = Node::new_shadow_root(self, shadow_root);
+
+        let mut doc = self.get_mut();
+        let shadow_root_id = doc.add_detached_node(node);
+        if let Some(host) = doc.get_node_by_id_mut(host_id) {
+            if let NodeData::Element(element) = &mut host.data {
+                element.shadow_root = Some(shadow_root_id);
+            }
+        }
+
+        Some(shadow_root_id)
+    }
+}
+
+impl Document {
+    /// Returns the id of the shadow root attached to the given element, regardless of its mode
+    pub fn shadow_root_of(&self, host_id: NodeId) -> Option<NodeId> {
+        match &self.get_node_by_id(host_id)?.data {
+            NodeData::Element(element) => element.shadow_root,
+            _ => None,
+        }
+    }
+
+    /// Returns the shadow root of the element as exposed by the `shadowRoot` getter: only open
+    /// shadow roots are returned.
+    pub fn open_shadow_root_of(&self, host_id: NodeId) -> Option<NodeId> {
+        let shadow_root_id = self.shadow_root_of(host_id)?;
+        match self.shadow_root_data(shadow_root_id)?.mode {
+            ShadowRootMode::Open => Some(shadow_root_id),
+            ShadowRootMode::Closed => None,
+        }
+    }
+
+    /// Returns the shadow root data when the given node is a shadow root
+    pub fn shadow_root_data(&self, node_id: NodeId) -> Option<&ShadowRoot> {
+        match &self.get_node_by_id(node_id)?.data {
+            NodeData::ShadowRoot(shadow_root) => Some(shadow_root),
+            _ => None,
+        }
+    }
+
+    /// Returns the root of the tree the node is in (the document, a shadow root, or the topmost
+    /// node of a detached tree)
+    pub fn tree_root_of(&self, node_id: NodeId) -> NodeId {
+        let mut current = node_id;
+        while let Some(parent_id) = self.get_node_by_id(current).and_then(|node| node.parent) {
+            current = parent_id;
+        }
+        current
+    }
+
+    /// Returns the shadow root the node is in, if any
+    pub fn containing_shadow_root(&self, node_id: NodeId) -> Option<NodeId> {
+        let root_id = self.tree_root_of(node_id);
+        self.shadow_root_data(root_id).map(|_| root_id)
+    }
+
+    /// Returns all descendants of the given node in tree order (not crossing shadow boundaries)
+    pub(crate) fn descendants(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut result = Vec::new();
+        if let Some(node) = self.get_node_by_id(node_id) {
+            for child_id in &node.children {
+                result.push(*child_id);
+                result.extend(self.descendants(*child_id));
+            }
+        }
+        result
+    }
+
+    /// Returns true when the node is a slot element
+    fn is_slot(&self, node_id: NodeId) -> bool {
+        self.get_node_by_id(node_id).is_some_and(|node| {
+            matches!(node.data, NodeData::Element(_))
+                && node.name == "slot"
+                && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+        })
+    }
+
+    /// Returns the name of a slot element ("name" attribute) or the slot name of a slottable
+    /// ("slot" attribute). Text nodes always have the empty name.
+    fn slot_attribute(&self, node_id: NodeId, attribute: &str) -> String {
+        match self.get_node_by_id(node_id).map(|node| &node.data) {
+            Some(NodeData::Element(element)) => element
+                .attributes
+                .get(attribute)
+                .cloned()
+                .unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+
+    /// Finds the slot the slottable is assigned to. When `open` is true, slots inside closed
+    /// shadow roots are not returned.
+    /// <https://dom.spec.whatwg.org/#find-a-slot>
+    pub fn find_slot(&self, slottable_id: NodeId, open: bool) -> Option<NodeId> {
+        let slottable = self.get_node_by_id(slottable_id)?;
+        if !matches!(slottable.data, NodeData::Element(_) | NodeData::Text(_)) {
+            return None;
+        }
+
+        let shadow_root_id = self.shadow_root_of(slottable.parent?)?;
+        let shadow_root = self.shadow_root_data(shadow_root_id)?;
+        if open && shadow_root.mode != ShadowRootMode::Open {
+            return None;
+        }
+
+        let mut slots = self
+            .descendants(shadow_root_id)
+            .into_iter()
+            .filter(|id| self.is_slot(*id));
+
+        match shadow_root.slot_assignment {
+            SlotAssignmentMode::Manual => slots.find(|slot_id| {
+                self.manually_assigned_nodes(*slot_id)
+                    .contains(&slottable_id)
+            }),
+            SlotAssignmentMode::Named => {
+                let name = self.slot_attribute(slottable_id, "slot");
+                slots.find(|slot_id| self.slot_attribute(*slot_id, "name") == name)
+            }
+        }
+    }
+
+    /// Returns the nodes that were manually assigned to the slot with `assign()`
+    fn manually_assigned_nodes(&self, slot_id: NodeId) -> Vec<NodeId> {
+        match self.get_node_by_id(slot_id).map(|node| &node.data) {
+            Some(NodeData::Element(element)) => element.manually_assigned_nodes.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// Finds the slottables that are assigned to the given slot
+    /// <https://dom.spec.whatwg.org/#find-slottables>
+    pub fn find_slottables(&self, slot_id: NodeId) -> Vec<NodeId> {
+        let Some(shadow_root_id) = self.containing_shadow_root(slot_id) else {
+            return vec![];
+        };
+        let Some(shadow_root) = self.shadow_root_data(shadow_root_id) else {
+            return vec![];
+        };
+        let host_id = shadow_root.host;
+
+        match shadow_root.slot_assignment {
+            SlotAssignmentMode::Manual => self
+                .manually_assigned_nodes(slot_id)
+                .into_iter()
+                .filter(|id| self.get_node_by_id(*id).and_then(|node| node.parent) == Some(host_id))
+                .collect(),
+            SlotAssignmentMode::Named => {
+                let Some(host) = self.get_node_by_id(host_id) else {
+                    return vec![];
+                };
+                host.children
+                    .iter()
+                    .filter(|id| self.find_slot(**id, false) == Some(slot_id))
+                    .copied()
+                    .collect()
+            }
+        }
+    }
+
+    /// Finds the slottables of the slot, where nested slots are replaced by their own assigned
+    /// nodes.
+    /// <https://dom.spec.whatwg.org/#find-flattened-slottables>
+    pub fn find_flattened_slottables(&self, slot_id: NodeId) -> Vec<NodeId> {
+        if self.containing_shadow_root(slot_id).is_none() {
+            return vec![];
+        }
+
+        let mut slottables = self.find_slottables(slot_id);
+        if slottables.is_empty() {
+            slottables = self
+                .get_node_by_id(slot_id)
+                .map(|node| node.children.clone())
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|id| {
+                    matches!(
+                        self.get_node_by_id(*id).map(|node| &node.data),
+                        Some(NodeData::Element(_) | NodeData::Text(_))
+                    )
+                })
+                .collect();
+        }
+
+        let mut result = Vec::new();
+        for node_id in slottables {
+            if self.is_slot(node_id) && self.containing_shadow_root(node_id).is_some() {
+                result.extend(self.find_flattened_slottables(node_id));
+            } else {
+                result.push(node_id);
+            }
+        }
+        result
+    }
+
+    /// Returns the slot this node is assigned to, as exposed by the `assignedSlot` getter
+    pub fn assigned_slot(&self, slottable_id: NodeId) -> Option<NodeId> {
+        self.find_slot(slottable_id, true)
+    }
+
+    /// Returns the nodes assigned to the slot, like `slot.assignedNodes({ flatten })`
+    pub fn assigned_nodes(&self, slot_id: NodeId, flatten: bool) -> Vec<NodeId> {
+        if flatten {
+            self.find_flattened_slottables(slot_id)
+        } else {
+            self.find_slottables(slot_id)
+        }
+    }
+
+    /// Manually assigns the given nodes to the slot (`slot.assign()`). Nodes are removed from
+    /// any other slot they were manually assigned to.
+    pub fn assign_slot(&mut self, slot_id: NodeId, nodes: &[NodeId]) {
+        let mut unique: Vec<NodeId> = Vec::new();
+        for node_id in nodes {
+            if !unique.contains(node_id) {
+                unique.push(*node_id);
+            }
+        }
+
+        let slot_ids: Vec<NodeId> = self
+            .all_node_ids()
+            .into_iter()
+            .filter(|id| *id != slot_id && self.is_slot(*id))
+            .collect();
+        for other_id in slot_ids {
+            if let Some(node) = self.get_node_by_id_mut(other_id) {
+                if let NodeData::Element(element) = &mut node.data {
+                    element
+                        .manually_assigned_nodes
+                        .retain(|id| !unique.contains(id));
+                }
+            }
+        }
+
+        if let Some(node) = self.get_node_by_id_mut(slot_id) {
+            if let NodeData::Element(element) = &mut node.data {
+                element.manually_assigned_nodes = unique;
+            }
+        }
+    }
+
+    /// Returns the children of the node in the flat tree: shadow hosts expose the children of
+    /// their shadow root, and slots expose their assigned nodes (or their fallback content).
+    /// <https://drafts.csswg.org/css-scoping/#flat-tree>
+    pub fn flat_tree_children(&self, node_id: NodeId) -> Vec<NodeId> {
+        if let Some(shadow_root_id) = self.shadow_root_of(node_id) {
+            return self
+                .get_node_by_id(shadow_root_id)
+                .map(|node| node.children.clone())
+                .unwrap_or_default();
+        }
+
+        if self.is_slot(node_id) && self.containing_shadow_root(node_id).is_some() {
+            let assigned = self.find_slottables(node_id);
+            if !assigned.is_empty() {
+                return assigned;
+            }
+        }
+
+        self.get_node_by_id(node_id)
+            .map(|node| node.children.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns all nodes of the flat tree starting at the given node, in pre-order
+    pub fn flat_tree_traverse(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut result = vec![node_id];
+        for child_id in self.flat_tree_children(node_id) {
+            result.extend(self.flat_tree_traverse(child_id));
+        }
+        result
+    }
+}
+
 pub struct Element {
     pub namespace_uri: Option<String>,
     pub prefix: Option<String>,
@@ -63,3 +459,213 @@ pub struct HtmlElement {
 
     pub popover: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_html;
+
+    fn add_element(
+        document: &mut DocumentHandle,
+        parent_id: NodeId,
+        name: &str,
+        attributes: &[(&str, &str)],
+    ) -> NodeId {
+        let attributes = attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let node = Node::new_element(document, name, attributes, HTML_NAMESPACE);
+        document.get_mut().add_node(node, parent_id)
+    }
+
+    fn new_document() -> DocumentHandle {
+        let mut document = Document::shared();
+        let root = Document::clone(&document);
+        document.get_mut().create_root(&root);
+        document
+    }
+
+    #[test]
+    fn attach_shadow() {
+        let mut document = new_document();
+        let host_id = add_element(&mut document, NodeId::root(), "div", &[]);
+        let input_id = add_element(&mut document, NodeId::root(), "input", &[]);
+
+        let init = ShadowRootInit::new(ShadowRootMode::Open);
+        let shadow_root_id = document.attach_shadow(host_id, init).unwrap();
+
+        // Only a single shadow root per host, and only on valid hosts
+        assert!(document.attach_shadow(host_id, init).is_none());
+        assert!(document.attach_shadow(input_id, init).is_none());
+
+        let span_id = add_element(&mut document, NodeId::root(), "span", &[]);
+        assert!(document.attach_shadow(span_id, init).is_some());
+        for name in ["font-face", "annotation-xml", "missing-glyph"] {
+            let reserved_id = add_element(&mut document, NodeId::root(), name, &[]);
+            assert!(document.attach_shadow(reserved_id, init).is_none());
+        }
+
+        let doc = document.get();
+        assert_eq!(doc.shadow_root_of(host_id), Some(shadow_root_id));
+        assert_eq!(doc.open_shadow_root_of(host_id), Some(shadow_root_id));
+        assert_eq!(doc.shadow_root_data(shadow_root_id).unwrap().host, host_id);
+        assert_eq!(doc.get_node_by_id(shadow_root_id).unwrap().parent, None);
+    }
+
+    #[test]
+    fn closed_shadow_root_is_hidden() {
+        let mut document = new_document();
+        let host_id = add_element(&mut document, NodeId::root(), "my-element", &[]);
+        let shadow_root_id = document
+            .attach_shadow(host_id, ShadowRootInit::new(ShadowRootMode::Closed))
+            .unwrap();
+        let slot_id = add_element(&mut document, shadow_root_id, "slot", &[]);
+        let child_id = add_element(&mut document, host_id, "span", &[]);
+
+        let doc = document.get();
+        assert_eq!(doc.shadow_root_of(host_id), Some(shadow_root_id));
+        assert_eq!(doc.open_shadow_root_of(host_id), None);
+        assert_eq!(doc.assigned_slot(child_id), None);
+        assert_eq!(doc.find_slot(child_id, false), Some(slot_id));
+    }
+
+    #[test]
+    fn named_slot_assignment() {
+        let mut document = new_document();
+        let host_id = add_element(&mut document, NodeId::root(), "div", &[]);
+        let shadow_root_id = document
+            .attach_shadow(host_id, ShadowRootInit::new(ShadowRootMode::Open))
+            .unwrap();
+        let default_slot = add_element(&mut document, shadow_root_id, "slot", &[]);
+        let title_slot = add_element(&mut document, shadow_root_id, "slot", &[("name", "title")]);
+
+        let title = add_element(&mut document, host_id, "h1", &[("slot", "title")]);
+        let body = add_element(&mut document, host_id, "p", &[]);
+        let missing = add_element(&mut document, host_id, "p", &[("slot", "missing")]);
+
+        let doc = document.get();
+        assert_eq!(doc.assigned_nodes(title_slot, false), vec![title]);
+        assert_eq!(doc.assigned_nodes(default_slot, false), vec![body]);
+        assert_eq!(doc.assigned_slot(title), Some(title_slot));
+        assert_eq!(doc.assigned_slot(missing), None);
+    }
+
+    #[test]
+    fn manual_slot_assignment() {
+        let mut document = new_document();
+        let host_id = add_element(&mut document, NodeId::root(), "div", &[]);
+        let mut init = ShadowRootInit::new(ShadowRootMode::Open);
+        init.slot_assignment = SlotAssignmentMode::Manual;
+        let shadow_root_id = document.attach_shadow(host_id, init).unwrap();
+        let slot1 = add_element(&mut document, shadow_root_id, "slot", &[]);
+        let slot2 = add_element(&mut document, shadow_root_id, "slot", &[]);
+
+        let a = add_element(&mut document, host_id, "span", &[("slot", "ignored")]);
+        let b = add_element(&mut document, host_id, "span", &[]);
+
+        document.get_mut().assign_slot(slot1, &[a, b]);
+        assert_eq!(document.get().assigned_nodes(slot1, false), vec![a, b]);
+
+        // Assigning to another slot removes the node from the first one
+        document.get_mut().assign_slot(slot2, &[b]);
+        let doc = document.get();
+        assert_eq!(doc.assigned_nodes(slot1, false), vec![a]);
+        assert_eq!(doc.assigned_nodes(slot2, false), vec![b]);
+        assert_eq!(doc.assigned_slot(b), Some(slot2));
+    }
+
+    #[test]
+    fn flat_tree() {
+        let mut document = new_document();
+        let host_id = add_element(&mut document, NodeId::root(), "div", &[]);
+        let shadow_root_id = document
+            .attach_shadow(host_id, ShadowRootInit::new(ShadowRootMode::Open))
+            .unwrap();
+        let header = add_element(&mut document, shadow_root_id, "header", &[]);
+        let slot = add_element(&mut document, shadow_root_id, "slot", &[]);
+        let fallback = add_element(&mut document, slot, "em", &[]);
+
+        let doc = document.get();
+        assert_eq!(doc.flat_tree_children(host_id), vec![header, slot]);
+        // Nothing is slotted, so the fallback content is shown
+        assert_eq!(doc.flat_tree_children(slot), vec![fallback]);
+        drop(doc);
+
+        let light = add_element(&mut document, host_id, "span", &[]);
+        let doc = document.get();
+        assert_eq!(doc.flat_tree_children(slot), vec![light]);
+        assert_eq!(
+            doc.flat_tree_traverse(host_id),
+            vec![host_id, header, slot, light]
+        );
+    }
+
+    #[test]
+    fn declarative_shadow_root() {
+        let document = parse_html(
+            "<div id=\"host\"><template shadowrootmode=\"open\"><slot></slot></template><p>light</p></div>",
+        );
+        let doc = document.get();
+        let host = doc.get_node_by_named_id("host").unwrap();
+        let shadow_root_id = doc.open_shadow_root_of(host.id).unwrap();
+
+        // The template itself is not part of the tree
+        assert_eq!(host.children.len(), 1);
+        let p = doc.get_node_by_id(host.children[0]).unwrap();
+        assert_eq!(p.name, "p");
+
+        let shadow_root = doc.get_node_by_id(shadow_root_id).unwrap();
+        assert_eq!(shadow_root.children.len(), 1);
+        let slot_id = shadow_root.children[0];
+        assert_eq!(doc.get_node_by_id(slot_id).unwrap().name, "slot");
+        assert_eq!(doc.assigned_nodes(slot_id, false), vec![p.id]);
+    }
+
+    #[test]
+    fn declarative_shadow_root_invalid_mode() {
+        let document = parse_html(
+            "<div id=\"host\"><template shadowrootmode=\"foo\"><slot></slot></template></div>",
+        );
+        let doc = document.get();
+        let host = doc.get_node_by_named_id("host").unwrap();
+        assert!(doc.shadow_root_of(host.id).is_none());
+        assert_eq!(
+            doc.get_node_by_id(host.children[0]).unwrap().name,
+            "template"
+        );
+    }
+
+    #[test]
+    fn declarative_shadow_root_attached_at_start_tag() {
+        let document = parse_html(
+            "<div id=\"host\"><template shadowrootmode=\"open\">\
+               <span id=\"inner\"><template shadowrootmode=\"closed\">deep</template></span>\
+             </template><template shadowrootmode=\"open\">second</template></div>",
+        );
+        let doc = document.get();
+        let host = doc.get_node_by_named_id("host").unwrap();
+        let shadow_root_id = doc.open_shadow_root_of(host.id).unwrap();
+
+        // The shadow root exists before its contents are parsed into it
+        let shadow_root = doc.get_node_by_id(shadow_root_id).unwrap();
+        let inner_id = shadow_root.children[0];
+        let node_ids = doc.all_node_ids();
+        let position = |id: NodeId| node_ids.iter().position(|node_id| *node_id == id);
+        assert!(position(shadow_root_id) < position(inner_id));
+
+        // Nested declarative shadow roots attach to their host inside the shadow tree
+        let inner = doc.get_node_by_id(inner_id).unwrap();
+        assert!(inner.children.is_empty());
+        let inner_shadow_root_id = doc.shadow_root_of(inner_id).unwrap();
+        assert!(doc.open_shadow_root_of(inner_id).is_none());
+        assert_eq!(doc.text_content(inner_shadow_root_id), "deep");
+
+        // A host only gets one shadow root, so the second template stays a regular template
+        assert_eq!(host.children.len(), 1);
+        assert_eq!(
+            doc.get_node_by_id(host.children[0]).unwrap().name,
+            "template"
+        );
+    }
+}
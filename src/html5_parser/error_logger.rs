@@ -165,7 +165,7 @@ pub struct ParseError {
     pub offset: usize,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ErrorLogger {
     /// List of errors that occurred during parsing
     errors: Vec<ParseError>,
@@ -211,7 +211,6 @@ impl ErrorLogger {
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
 
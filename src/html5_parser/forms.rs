@@ -0,0 +1,1426 @@
+//! Forms: form owners, control values, constraint validation and form submission
+//!
+//! Form-associated elements are associated with a form owner
+//! (<https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#form-owner>): the
+//! element referenced by the `form` attribute, the form element the parser associated the element
+//! with, or the nearest form ancestor. The parser association is kept as long as the form is in
+//! the same tree as the element.
+//!
+//! Values of controls start out as their default values (taken from the attributes and
+//! contents of the elements) and can be changed afterwards, just like a user would do. Changed
+//! values are stored in the document and are cleared again when the form is reset.
+//!
+//! Submitting a form constructs the entry list
+//! (<https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#constructing-the-form-data-set>)
+//! and encodes it as `application/x-www-form-urlencoded`, `multipart/form-data` or `text/plain`.
+//! Constraint validation is not done as part of the submission; use
+//! [`Document::invalid_controls`] for that.
+use crate::html5_parser::node::{Node, NodeData, NodeId, HTML_NAMESPACE};
+use crate::html5_parser::parser::document::Document;
+use crate::url::percent_encoding::{utf8_percent_encode, EncodeSet};
+use crate::url::Url;
+use lazy_static::lazy_static;
+use regex::Regex;
+use uuid::Uuid;
+
+/// Form-associated elements
+static FORM_ASSOCIATED_ELEMENTS: [&str; 8] = [
+    "button", "fieldset", "img", "input", "object", "output", "select", "textarea",
+];
+
+/// Form-associated elements that have a `form` attribute and are listed in the form's elements
+static LISTED_ELEMENTS: [&str; 7] = [
+    "button", "fieldset", "input", "object", "output", "select", "textarea",
+];
+
+/// Listed elements that can be used for constructing the entry list (objects are not supported)
+static SUBMITTABLE_ELEMENTS: [&str; 4] = ["button", "input", "select", "textarea"];
+
+/// Known input types. Inputs with a missing or unknown type are text inputs.
+static INPUT_TYPES: [&str; 22] = [
+    "button",
+    "checkbox",
+    "color",
+    "date",
+    "datetime-local",
+    "email",
+    "file",
+    "hidden",
+    "image",
+    "month",
+    "number",
+    "password",
+    "radio",
+    "range",
+    "reset",
+    "search",
+    "submit",
+    "tel",
+    "text",
+    "time",
+    "url",
+    "week",
+];
+
+/// Input types with a single line of text as value. The `pattern` attribute applies to these.
+static TEXT_INPUT_TYPES: [&str; 6] = ["text", "search", "url", "tel", "email", "password"];
+
+/// Input types with a date or time as value
+static DATE_INPUT_TYPES: [&str; 5] = ["date", "month", "week", "time", "datetime-local"];
+
+lazy_static! {
+    static ref EMAIL_RE: Regex = Regex::new(
+        r"^[a-zA-Z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)*$"
+    )
+    .unwrap();
+    static ref FLOAT_RE: Regex = Regex::new(r"^-?(?:[0-9]+|[0-9]*\.[0-9]+)(?:[eE][+-]?[0-9]+)?$").unwrap();
+    static ref DATE_RE: Regex = Regex::new(r"^[0-9]{4,}-(?:0[1-9]|1[0-2])-(?:0[1-9]|[12][0-9]|3[01])$").unwrap();
+    static ref COLOR_RE: Regex = Regex::new(r"^#[0-9a-fA-F]{6}$").unwrap();
+}
+
+/// Returns true when the element with the given (HTML) name is form-associated
+pub(crate) fn is_form_associated(name: &str) -> bool {
+    FORM_ASSOCIATED_ELEMENTS.contains(&name)
+}
+
+/// Returns true when the element with the given (HTML) name is a listed element
+pub(crate) fn is_listed(name: &str) -> bool {
+    LISTED_ELEMENTS.contains(&name)
+}
+
+/// State of a form control that has been changed after parsing
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct ControlState {
+    /// Dirty value of inputs and textareas
+    value: Option<String>,
+    /// Dirty checkedness of checkboxes and radio buttons, or dirty selectedness of options
+    checked: Option<bool>,
+}
+
+/// Result of the constraint validation of a control
+/// (<https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#validitystate>)
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ValidityState {
+    /// A required control has no value
+    pub value_missing: bool,
+    /// The value is not a valid email address or URL
+    pub type_mismatch: bool,
+    /// The value does not match the `pattern` attribute
+    pub pattern_mismatch: bool,
+    /// The value was changed and is longer than `maxlength`
+    pub too_long: bool,
+    /// The value was changed and is shorter than `minlength`
+    pub too_short: bool,
+    /// The value is lower than `min`
+    pub range_underflow: bool,
+    /// The value is higher than `max`
+    pub range_overflow: bool,
+    /// The value does not fit the `step` attribute
+    pub step_mismatch: bool,
+}
+
+impl ValidityState {
+    /// Returns true when none of the constraints are violated
+    pub fn valid(&self) -> bool {
+        *self == ValidityState::default()
+    }
+}
+
+/// Value of an entry in the entry list
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormEntryValue {
+    String(String),
+    File {
+        filename: String,
+        content_type: String,
+        data: Vec<u8>,
+    },
+}
+
+/// Name-value pair that is submitted with a form
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormEntry {
+    pub name: String,
+    pub value: FormEntryValue,
+}
+
+impl FormEntry {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: FormEntryValue::String(value.to_string()),
+        }
+    }
+
+    /// Returns the value as a string. Files are represented by their filename.
+    fn string_value(&self) -> &str {
+        match &self.value {
+            FormEntryValue::String(value) => value,
+            FormEntryValue::File { filename, .. } => filename,
+        }
+    }
+}
+
+/// HTTP method used for submitting a form
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FormMethod {
+    Get,
+    Post,
+    /// The form closes the dialog it is in instead of navigating
+    Dialog,
+}
+
+impl FormMethod {
+    /// Parses the `method` attribute. Missing and invalid values default to GET.
+    pub fn from_attribute(value: Option<&str>) -> Self {
+        match value.map(|value| value.to_ascii_lowercase()).as_deref() {
+            Some("post") => FormMethod::Post,
+            Some("dialog") => FormMethod::Dialog,
+            _ => FormMethod::Get,
+        }
+    }
+}
+
+/// Encoding of the entry list when submitting a form
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FormEnctype {
+    UrlEncoded,
+    MultipartFormData,
+    TextPlain,
+}
+
+impl FormEnctype {
+    /// Parses the `enctype` attribute. Missing and invalid values default to urlencoded.
+    pub fn from_attribute(value: Option<&str>) -> Self {
+        match value.map(|value| value.to_ascii_lowercase()).as_deref() {
+            Some("multipart/form-data") => FormEnctype::MultipartFormData,
+            Some("text/plain") => FormEnctype::TextPlain,
+            _ => FormEnctype::UrlEncoded,
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            FormEnctype::UrlEncoded => "application/x-www-form-urlencoded",
+            FormEnctype::MultipartFormData => "multipart/form-data",
+            FormEnctype::TextPlain => "text/plain",
+        }
+    }
+}
+
+/// The request that results from submitting a form
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormSubmission {
+    pub method: FormMethod,
+    pub enctype: FormEnctype,
+    /// URL to navigate to. For GET submissions the query holds the entry list.
+    pub action: Url,
+    /// Value of the Content-Type header of the body
+    pub content_type: Option<String>,
+    /// Request body (only for POST submissions)
+    pub body: Option<Vec<u8>>,
+}
+
+/// Encodes the entry list as `application/x-www-form-urlencoded`. Files are submitted by their
+/// filename.
+pub fn urlencoded(entries: &[FormEntry]) -> String {
+    let encode = |value: &str| {
+        // Spaces are encoded as plus. A literal "%20" cannot occur, as the % is encoded as well.
+        utf8_percent_encode(&normalize_newlines(value), EncodeSet::FormUrlencoded)
+            .replace("%20", "+")
+    };
+
+    entries
+        .iter()
+        .map(|entry| format!("{}={}", encode(&entry.name), encode(entry.string_value())))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Encodes the entry list as `multipart/form-data` with the given boundary
+pub fn multipart_form_data(entries: &[FormEntry], boundary: &str) -> Vec<u8> {
+    let escape = |value: &str| {
+        normalize_newlines(value)
+            .replace('\n', "%0A")
+            .replace('\r', "%0D")
+            .replace('"', "%22")
+    };
+
+    let mut body = vec![];
+    for entry in entries {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        body.extend_from_slice(
+            format!(
+                "Content-Disposition: form-data; name=\"{}\"",
+                escape(&entry.name)
+            )
+            .as_bytes(),
+        );
+
+        match &entry.value {
+            FormEntryValue::String(value) => {
+                body.extend_from_slice(b"\r\n\r\n");
+                body.extend_from_slice(normalize_newlines(value).as_bytes());
+            }
+            FormEntryValue::File {
+                filename,
+                content_type,
+                data,
+            } => {
+                body.extend_from_slice(
+                    format!(
+                        "; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        escape(filename),
+                        content_type
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(data);
+            }
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+    body
+}
+
+/// Encodes the entry list as `text/plain`. Files are submitted by their filename.
+pub fn text_plain(entries: &[FormEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{}={}\r\n",
+                normalize_newlines(&entry.name),
+                normalize_newlines(entry.string_value())
+            )
+        })
+        .collect()
+}
+
+/// Converts all line breaks (CR, LF or CRLF) into CRLF
+fn normalize_newlines(value: &str) -> String {
+    value
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\r\n")
+}
+
+/// Parses a valid floating-point number
+fn parse_float(value: &str) -> Option<f64> {
+    if !FLOAT_RE.is_match(value) {
+        return None;
+    }
+    value.parse().ok().filter(|value: &f64| value.is_finite())
+}
+
+/// Parses a valid non-negative integer
+fn parse_non_negative_integer(value: &str) -> Option<usize> {
+    let value = value.trim_matches(|c: char| c.is_ascii_whitespace());
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn is_html(node: &Node) -> bool {
+    matches!(node.data, NodeData::Element(_)) && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+}
+
+/// Form owners and the elements of forms
+impl Document {
+    /// Returns all form elements in the document in tree order
+    pub fn forms(&self) -> Vec<NodeId> {
+        self.descendants(NodeId::root())
+            .into_iter()
+            .filter(|id| self.is_html_element(*id, "form"))
+            .collect()
+    }
+
+    /// Returns the form owner of a form-associated element
+    pub fn form_owner(&self, node_id: NodeId) -> Option<NodeId> {
+        let node = self.get_node_by_id(node_id)?;
+        let NodeData::Element(element) = &node.data else {
+            return None;
+        };
+        if !is_html(node) || !is_form_associated(&node.name) {
+            return None;
+        }
+
+        let root_id = self.tree_root_of(node_id);
+        if is_listed(&node.name) {
+            if let Some(form_id) = element.attributes.get("form") {
+                // The first element in the tree with the ID, which must be a form element
+                return self
+                    .descendants(root_id)
+                    .into_iter()
+                    .find(|id| self.attribute(*id, "id") == Some(form_id.as_str()))
+                    .filter(|id| self.is_html_element(*id, "form"));
+            }
+        }
+
+        if let Some(form_id) = element.form_owner {
+            if self.get_node_by_id(form_id).is_some() && self.tree_root_of(form_id) == root_id {
+                return Some(form_id);
+            }
+        }
+
+        self.ancestors(node_id)
+            .into_iter()
+            .find(|id| self.is_html_element(*id, "form"))
+    }
+
+    /// Returns the listed elements of the form in tree order, like `form.elements` (image
+    /// buttons are not included)
+    pub fn form_elements(&self, form_id: NodeId) -> Vec<NodeId> {
+        self.listed_elements(form_id)
+            .into_iter()
+            .filter(|id| self.input_type(*id).as_deref() != Some("image"))
+            .collect()
+    }
+
+    /// Returns all listed elements that have the form as their form owner, in tree order
+    fn listed_elements(&self, form_id: NodeId) -> Vec<NodeId> {
+        if !self.is_html_element(form_id, "form") {
+            return vec![];
+        }
+
+        self.descendants(self.tree_root_of(form_id))
+            .into_iter()
+            .filter(|id| {
+                self.get_node_by_id(*id)
+                    .is_some_and(|node| is_html(node) && is_listed(&node.name))
+            })
+            .filter(|id| self.form_owner(*id) == Some(form_id))
+            .collect()
+    }
+
+    /// Returns the type of an input element. Missing and unknown types result in "text". Returns
+    /// None when the node is not an input element.
+    pub fn input_type(&self, node_id: NodeId) -> Option<String> {
+        if !self.is_html_element(node_id, "input") {
+            return None;
+        }
+
+        let input_type = self
+            .attribute(node_id, "type")
+            .map(|value| value.to_ascii_lowercase())
+            .unwrap_or_default();
+        if INPUT_TYPES.contains(&input_type.as_str()) {
+            Some(input_type)
+        } else {
+            Some("text".to_string())
+        }
+    }
+
+    /// Returns true when the control is disabled, either by its own `disabled` attribute or by
+    /// a disabled fieldset (except for controls in the first legend of the fieldset). Options are
+    /// also disabled by a disabled optgroup.
+    pub fn is_disabled(&self, node_id: NodeId) -> bool {
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return false;
+        };
+        if !is_html(node) {
+            return false;
+        }
+
+        match node.name.as_str() {
+            "option" => {
+                self.has_attribute(node_id, "disabled")
+                    || node.parent.is_some_and(|parent_id| {
+                        self.is_html_element(parent_id, "optgroup")
+                            && self.has_attribute(parent_id, "disabled")
+                    })
+            }
+            "optgroup" => self.has_attribute(node_id, "disabled"),
+            "button" | "input" | "select" | "textarea" | "fieldset" => {
+                if self.has_attribute(node_id, "disabled") {
+                    return true;
+                }
+
+                let mut child_id = node_id;
+                for ancestor_id in self.ancestors(node_id) {
+                    if self.is_html_element(ancestor_id, "fieldset")
+                        && self.has_attribute(ancestor_id, "disabled")
+                    {
+                        let first_legend = self.get_node_by_id(ancestor_id).and_then(|fieldset| {
+                            fieldset
+                                .children
+                                .iter()
+                                .find(|id| self.is_html_element(**id, "legend"))
+                                .copied()
+                        });
+                        if first_legend != Some(child_id) {
+                            return true;
+                        }
+                    }
+                    child_id = ancestor_id;
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true when the element is a submit button (a button of type submit, or an input
+    /// of type submit or image)
+    pub fn is_submit_button(&self, node_id: NodeId) -> bool {
+        if self.is_html_element(node_id, "button") {
+            return !matches!(
+                self.attribute(node_id, "type")
+                    .map(|value| value.to_ascii_lowercase())
+                    .as_deref(),
+                Some("reset" | "button")
+            );
+        }
+        matches!(
+            self.input_type(node_id).as_deref(),
+            Some("submit" | "image")
+        )
+    }
+
+    /// Returns true when the node is an HTML element with the given name
+    fn is_html_element(&self, node_id: NodeId, name: &str) -> bool {
+        self.get_node_by_id(node_id)
+            .is_some_and(|node| is_html(node) && node.name == name)
+    }
+
+    /// Returns the value of the attribute of the element
+    fn attribute(&self, node_id: NodeId, name: &str) -> Option<&str> {
+        match &self.get_node_by_id(node_id)?.data {
+            NodeData::Element(element) => element.attributes.get(name).map(|value| value.as_str()),
+            _ => None,
+        }
+    }
+
+    fn has_attribute(&self, node_id: NodeId, name: &str) -> bool {
+        self.attribute(node_id, name).is_some()
+    }
+
+    /// Returns the ancestors of the node, starting with its parent
+    fn ancestors(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut result = vec![];
+        let mut current = self.get_node_by_id(node_id).and_then(|node| node.parent);
+        while let Some(parent_id) = current {
+            result.push(parent_id);
+            current = self.get_node_by_id(parent_id).and_then(|node| node.parent);
+        }
+        result
+    }
+
+    /// Returns true when the element has a datalist ancestor
+    fn in_datalist(&self, node_id: NodeId) -> bool {
+        self.ancestors(node_id)
+            .into_iter()
+            .any(|id| self.is_html_element(id, "datalist"))
+    }
+}
+
+/// Values of form controls
+impl Document {
+    /// Returns the default value of a control, which is the value it has before it is changed.
+    /// For selects, this is None as the value depends on the selected options.
+    pub fn default_value(&self, node_id: NodeId) -> Option<String> {
+        let node = self.get_node_by_id(node_id)?;
+        if !is_html(node) {
+            return None;
+        }
+
+        match node.name.as_str() {
+            "input" => {
+                let value = self.attribute(node_id, "value");
+                match self.input_type(node_id).as_deref() {
+                    Some("checkbox" | "radio") => Some(value.unwrap_or("on").to_string()),
+                    _ => Some(value.unwrap_or_default().to_string()),
+                }
+            }
+            "button" => Some(
+                self.attribute(node_id, "value")
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            "textarea" | "output" => Some(self.text_content(node_id)),
+            "option" => match self.attribute(node_id, "value") {
+                Some(value) => Some(value.to_string()),
+                None => Some(
+                    self.text_content(node_id)
+                        .split_ascii_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the current value of a control
+    pub fn value(&self, node_id: NodeId) -> Option<String> {
+        let node = self.get_node_by_id(node_id)?;
+        if !is_html(node) {
+            return None;
+        }
+
+        let dirty_value = self
+            .form_controls
+            .get(&node_id)
+            .and_then(|state| state.value.clone());
+
+        match node.name.as_str() {
+            "input" => {
+                let input_type = self.input_type(node_id)?;
+                if !self.has_value_mode(&input_type) {
+                    return match input_type.as_str() {
+                        "file" => Some(String::new()),
+                        _ => self.default_value(node_id),
+                    };
+                }
+                let value = dirty_value.or_else(|| self.default_value(node_id))?;
+                Some(self.sanitize_value(node_id, &input_type, &value))
+            }
+            "textarea" => {
+                let value = dirty_value.or_else(|| self.default_value(node_id))?;
+                Some(value.replace("\r\n", "\n").replace('\r', "\n"))
+            }
+            "select" => Some(
+                self.selected_options(node_id)
+                    .first()
+                    .and_then(|option_id| self.default_value(*option_id))
+                    .unwrap_or_default(),
+            ),
+            _ => self.default_value(node_id),
+        }
+    }
+
+    /// Changes the value of an input, textarea or select (which selects the first option with
+    /// the value). For inputs without a dirty value, like checkboxes and buttons, the value
+    /// attribute is changed instead. Returns false when the value cannot be set.
+    pub fn set_value(&mut self, node_id: NodeId, value: &str) -> bool {
+        if self.is_html_element(node_id, "textarea") {
+            self.form_controls.entry(node_id).or_default().value = Some(value.to_string());
+            return true;
+        }
+
+        if self.is_html_element(node_id, "select") {
+            let options = self.options(node_id);
+            let Some(option_id) = options
+                .iter()
+                .find(|id| self.default_value(**id).as_deref() == Some(value))
+                .copied()
+            else {
+                return false;
+            };
+            for id in options {
+                self.form_controls.entry(id).or_default().checked = Some(id == option_id);
+            }
+            return true;
+        }
+
+        let Some(input_type) = self.input_type(node_id) else {
+            return false;
+        };
+        match input_type.as_str() {
+            "file" => value.is_empty(),
+            _ if self.has_value_mode(&input_type) => {
+                let value = self.sanitize_value(node_id, &input_type, value);
+                self.form_controls.entry(node_id).or_default().value = Some(value);
+                true
+            }
+            _ => self.set_attribute(node_id, "value", value),
+        }
+    }
+
+    /// Returns true when the checkbox or radio button is checked. Of the radio buttons in a
+    /// group that have a `checked` attribute, only the last one is checked.
+    pub fn is_checked(&self, node_id: NodeId) -> bool {
+        if let Some(checked) = self
+            .form_controls
+            .get(&node_id)
+            .and_then(|state| state.checked)
+        {
+            return checked;
+        }
+
+        match self.input_type(node_id).as_deref() {
+            Some("checkbox") => self.has_attribute(node_id, "checked"),
+            Some("radio") => {
+                let group = self.radio_group(node_id);
+                if group.iter().any(|id| {
+                    self.form_controls
+                        .get(id)
+                        .is_some_and(|s| s.checked.is_some())
+                }) {
+                    return false;
+                }
+                group
+                    .into_iter()
+                    .rev()
+                    .find(|id| self.has_attribute(*id, "checked"))
+                    == Some(node_id)
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks or unchecks the checkbox or radio button. Checking a radio button unchecks the
+    /// other radio buttons in its group.
+    pub fn set_checked(&mut self, node_id: NodeId, checked: bool) -> bool {
+        match self.input_type(node_id).as_deref() {
+            Some("checkbox") => {}
+            Some("radio") => {
+                if checked {
+                    for id in self.radio_group(node_id) {
+                        self.form_controls.entry(id).or_default().checked = Some(false);
+                    }
+                }
+            }
+            _ => return false,
+        }
+
+        self.form_controls.entry(node_id).or_default().checked = Some(checked);
+        true
+    }
+
+    /// Returns the options of the select element: its option children and the options in its
+    /// optgroup children
+    pub fn options(&self, select_id: NodeId) -> Vec<NodeId> {
+        let Some(select) = self.get_node_by_id(select_id) else {
+            return vec![];
+        };
+
+        let mut options = vec![];
+        for child_id in &select.children {
+            if self.is_html_element(*child_id, "option") {
+                options.push(*child_id);
+            } else if self.is_html_element(*child_id, "optgroup") {
+                if let Some(optgroup) = self.get_node_by_id(*child_id) {
+                    options.extend(
+                        optgroup
+                            .children
+                            .iter()
+                            .filter(|id| self.is_html_element(**id, "option")),
+                    );
+                }
+            }
+        }
+        options
+    }
+
+    /// Returns the selected options of the select element. When a select without `multiple`
+    /// has no selected option, the first enabled option is selected (unless it is displayed as
+    /// a list box). When it has multiple, only the last one is selected.
+    pub fn selected_options(&self, select_id: NodeId) -> Vec<NodeId> {
+        if !self.is_html_element(select_id, "select") {
+            return vec![];
+        }
+
+        let options = self.options(select_id);
+        let mut selected: Vec<NodeId> = options
+            .iter()
+            .filter(
+                |id| match self.form_controls.get(id).and_then(|s| s.checked) {
+                    Some(checked) => checked,
+                    None => self.has_attribute(**id, "selected"),
+                },
+            )
+            .copied()
+            .collect();
+
+        if self.has_attribute(select_id, "multiple") {
+            return selected;
+        }
+        if selected.len() > 1 {
+            selected.drain(..selected.len() - 1);
+        }
+        if selected.is_empty() && self.display_size(select_id) == 1 {
+            selected.extend(options.into_iter().find(|id| !self.is_disabled(*id)));
+        }
+        selected
+    }
+
+    /// Selects or deselects the option. Selecting an option of a select without `multiple`
+    /// deselects the other options.
+    pub fn set_selected(&mut self, option_id: NodeId, selected: bool) -> bool {
+        if !self.is_html_element(option_id, "option") {
+            return false;
+        }
+
+        let select_id = self
+            .ancestors(option_id)
+            .into_iter()
+            .take(2)
+            .find(|id| self.is_html_element(*id, "select"));
+        if let Some(select_id) = select_id {
+            if selected && !self.has_attribute(select_id, "multiple") {
+                for id in self.options(select_id) {
+                    self.form_controls.entry(id).or_default().checked = Some(false);
+                }
+            }
+        }
+
+        self.form_controls.entry(option_id).or_default().checked = Some(selected);
+        true
+    }
+
+    /// Resets all controls of the form to their default values
+    pub fn reset_form(&mut self, form_id: NodeId) {
+        for id in self.listed_elements(form_id) {
+            self.reset_control(id);
+        }
+    }
+
+    /// Resets the control (and the options of a select) to its default value
+    pub fn reset_control(&mut self, node_id: NodeId) {
+        self.form_controls.remove(&node_id);
+        for option_id in self.options(node_id) {
+            self.form_controls.remove(&option_id);
+        }
+    }
+
+    /// Returns true when the input type uses the dirty value, instead of the value attribute
+    fn has_value_mode(&self, input_type: &str) -> bool {
+        !matches!(
+            input_type,
+            "hidden" | "submit" | "image" | "reset" | "button" | "checkbox" | "radio" | "file"
+        )
+    }
+
+    /// Applies the value sanitization algorithm of the input type. Date and time values are
+    /// only checked for the date type.
+    fn sanitize_value(&self, node_id: NodeId, input_type: &str, value: &str) -> String {
+        let strip_newlines = |value: &str| value.replace(['\r', '\n'], "");
+
+        match input_type {
+            "text" | "search" | "tel" | "password" => strip_newlines(value),
+            "url" | "email" => strip_newlines(value)
+                .trim_matches(|c: char| c.is_ascii_whitespace())
+                .to_string(),
+            "number" => match parse_float(value) {
+                Some(_) => value.to_string(),
+                None => String::new(),
+            },
+            "range" => {
+                let (min, max) = self.range_bounds(node_id);
+                let value = parse_float(value).unwrap_or(min + (max - min) / 2.0);
+                value.clamp(min, max).to_string()
+            }
+            "color" if COLOR_RE.is_match(value) => value.to_ascii_lowercase(),
+            "color" => "#000000".to_string(),
+            "date" if DATE_RE.is_match(value) => value.to_string(),
+            "date" => String::new(),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Returns the minimum and maximum of a range input
+    fn range_bounds(&self, node_id: NodeId) -> (f64, f64) {
+        let min = self
+            .attribute(node_id, "min")
+            .and_then(parse_float)
+            .unwrap_or(0.0);
+        let max = self
+            .attribute(node_id, "max")
+            .and_then(parse_float)
+            .unwrap_or(100.0);
+        (min, max.max(min))
+    }
+
+    /// Returns the display size of a select element
+    fn display_size(&self, select_id: NodeId) -> usize {
+        match self
+            .attribute(select_id, "size")
+            .and_then(parse_non_negative_integer)
+        {
+            Some(size) if size > 0 => size,
+            _ if self.has_attribute(select_id, "multiple") => 4,
+            _ => 1,
+        }
+    }
+
+    /// Returns the radio buttons in the same group as the radio button (including itself): the
+    /// radio buttons in the same tree with the same name and form owner
+    fn radio_group(&self, node_id: NodeId) -> Vec<NodeId> {
+        let Some(name) = self
+            .attribute(node_id, "name")
+            .filter(|name| !name.is_empty())
+        else {
+            return vec![node_id];
+        };
+        let form_owner = self.form_owner(node_id);
+
+        self.descendants(self.tree_root_of(node_id))
+            .into_iter()
+            .filter(|id| {
+                self.input_type(*id).as_deref() == Some("radio")
+                    && self.attribute(*id, "name") == Some(name)
+                    && self.form_owner(*id) == form_owner
+            })
+            .collect()
+    }
+}
+
+/// Constraint validation
+/// (<https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#constraints>)
+impl Document {
+    /// Returns true when the control is a candidate for constraint validation
+    pub fn will_validate(&self, node_id: NodeId) -> bool {
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return false;
+        };
+        if !is_html(node)
+            || !SUBMITTABLE_ELEMENTS.contains(&node.name.as_str())
+            || self.is_disabled(node_id)
+            || self.in_datalist(node_id)
+        {
+            return false;
+        }
+
+        match node.name.as_str() {
+            "button" => self.is_submit_button(node_id),
+            "textarea" => !self.has_attribute(node_id, "readonly"),
+            "input" => {
+                let input_type = self.input_type(node_id).unwrap_or_default();
+                if matches!(input_type.as_str(), "hidden" | "reset" | "button") {
+                    return false;
+                }
+                let supports_readonly = TEXT_INPUT_TYPES.contains(&input_type.as_str())
+                    || DATE_INPUT_TYPES.contains(&input_type.as_str())
+                    || input_type == "number";
+                !(supports_readonly && self.has_attribute(node_id, "readonly"))
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the validity state of the control
+    pub fn validity(&self, node_id: NodeId) -> ValidityState {
+        let mut validity = ValidityState::default();
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return validity;
+        };
+        if !is_html(node) {
+            return validity;
+        }
+
+        match node.name.as_str() {
+            "input" => self.input_validity(node_id, &mut validity),
+            "textarea" => {
+                let value = self.value(node_id).unwrap_or_default();
+                validity.value_missing =
+                    self.has_attribute(node_id, "required") && value.is_empty();
+                self.length_validity(node_id, &value, &mut validity);
+            }
+            "select" => {
+                validity.value_missing =
+                    self.has_attribute(node_id, "required") && self.has_no_selected_value(node_id);
+            }
+            _ => {}
+        }
+
+        validity
+    }
+
+    /// Returns true when the control is not a candidate for constraint validation or satisfies
+    /// its constraints
+    pub fn check_validity(&self, node_id: NodeId) -> bool {
+        !self.will_validate(node_id) || self.validity(node_id).valid()
+    }
+
+    /// Returns the controls of the form that do not satisfy their constraints, in tree order
+    pub fn invalid_controls(&self, form_id: NodeId) -> Vec<NodeId> {
+        self.listed_elements(form_id)
+            .into_iter()
+            .filter(|id| !self.check_validity(*id))
+            .collect()
+    }
+
+    fn input_validity(&self, node_id: NodeId, validity: &mut ValidityState) {
+        let input_type = self.input_type(node_id).unwrap_or_default();
+        let value = self.value(node_id).unwrap_or_default();
+        let required = self.has_attribute(node_id, "required");
+
+        validity.value_missing = match input_type.as_str() {
+            "checkbox" => required && !self.is_checked(node_id),
+            "radio" => {
+                let group = self.radio_group(node_id);
+                group.iter().any(|id| self.has_attribute(*id, "required"))
+                    && !group.iter().any(|id| self.is_checked(*id))
+            }
+            // Selecting files is not supported, so a file input never has a value
+            "file" => required,
+            "hidden" | "range" | "color" | "submit" | "image" | "reset" | "button" => false,
+            _ => required && value.is_empty(),
+        };
+
+        if value.is_empty() {
+            return;
+        }
+
+        // Email inputs with `multiple` contain a comma-separated list of addresses
+        let values: Vec<&str> = if input_type == "email" && self.has_attribute(node_id, "multiple")
+        {
+            value
+                .split(',')
+                .map(|value| value.trim_matches(|c: char| c.is_ascii_whitespace()))
+                .collect()
+        } else {
+            vec![value.as_str()]
+        };
+
+        validity.type_mismatch = match input_type.as_str() {
+            "email" => values.iter().any(|value| !EMAIL_RE.is_match(value)),
+            "url" => Url::parse(&value).is_err(),
+            _ => false,
+        };
+
+        if TEXT_INPUT_TYPES.contains(&input_type.as_str()) {
+            let pattern = self
+                .attribute(node_id, "pattern")
+                .and_then(|pattern| Regex::new(&format!("^(?:{})$", pattern)).ok());
+            if let Some(pattern) = pattern {
+                validity.pattern_mismatch = values.iter().any(|value| !pattern.is_match(value));
+            }
+            self.length_validity(node_id, &value, validity);
+        }
+
+        match input_type.as_str() {
+            "number" | "range" => {
+                let Some(number) = parse_float(&value) else {
+                    return;
+                };
+                let min = self.attribute(node_id, "min").and_then(parse_float);
+                let max = self.attribute(node_id, "max").and_then(parse_float);
+                validity.range_underflow = min.is_some_and(|min| number < min);
+                validity.range_overflow = max.is_some_and(|max| number > max);
+
+                let step = match self.attribute(node_id, "step") {
+                    Some(step) if step.eq_ignore_ascii_case("any") => None,
+                    Some(step) => parse_float(step).filter(|step| *step > 0.0).or(Some(1.0)),
+                    None => Some(1.0),
+                };
+                if let Some(step) = step {
+                    let base = min
+                        .or_else(|| self.attribute(node_id, "value").and_then(parse_float))
+                        .unwrap_or(0.0);
+                    let steps = (number - base) / step;
+                    validity.step_mismatch = (steps - steps.round()).abs() > 1e-9;
+                }
+            }
+            // Valid dates can be compared as strings
+            "date" => {
+                let bound = |name| {
+                    self.attribute(node_id, name)
+                        .filter(|value| DATE_RE.is_match(value))
+                };
+                validity.range_underflow = bound("min").is_some_and(|min| value.as_str() < min);
+                validity.range_overflow = bound("max").is_some_and(|max| value.as_str() > max);
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks `maxlength` and `minlength`. These only apply to values that have been changed.
+    fn length_validity(&self, node_id: NodeId, value: &str, validity: &mut ValidityState) {
+        let is_dirty = self
+            .form_controls
+            .get(&node_id)
+            .is_some_and(|state| state.value.is_some());
+        if !is_dirty || value.is_empty() {
+            return;
+        }
+
+        let length = value.chars().count();
+        let limit = |name| {
+            self.attribute(node_id, name)
+                .and_then(parse_non_negative_integer)
+        };
+        validity.too_long = limit("maxlength").is_some_and(|max| length > max);
+        validity.too_short = limit("minlength").is_some_and(|min| length < min);
+    }
+
+    /// Returns true when a select has no selected options, or when only its placeholder label
+    /// option is selected
+    fn has_no_selected_value(&self, select_id: NodeId) -> bool {
+        let selected = self.selected_options(select_id);
+        if selected.is_empty() {
+            return true;
+        }
+        if self.has_attribute(select_id, "multiple") || self.display_size(select_id) != 1 {
+            return false;
+        }
+
+        let placeholder = self.options(select_id).first().copied().filter(|id| {
+            self.default_value(*id).as_deref() == Some("")
+                && self.get_node_by_id(*id).and_then(|node| node.parent) == Some(select_id)
+        });
+        placeholder.is_some() && selected == vec![placeholder.unwrap_or_default()]
+    }
+}
+
+/// Form submission
+impl Document {
+    /// Constructs the entry list of the form. The submitter is the submit button that was used
+    /// to submit the form, if any.
+    pub fn form_entry_list(&self, form_id: NodeId, submitter: Option<NodeId>) -> Vec<FormEntry> {
+        let mut entries = vec![];
+
+        for id in self.listed_elements(form_id) {
+            let Some(node) = self.get_node_by_id(id) else {
+                continue;
+            };
+            if !SUBMITTABLE_ELEMENTS.contains(&node.name.as_str())
+                || self.in_datalist(id)
+                || self.is_disabled(id)
+            {
+                continue;
+            }
+
+            let input_type = self.input_type(id);
+            let is_button = node.name == "button"
+                || matches!(
+                    input_type.as_deref(),
+                    Some("submit" | "image" | "reset" | "button")
+                );
+            if is_button && (submitter != Some(id) || !self.is_submit_button(id)) {
+                continue;
+            }
+            if matches!(input_type.as_deref(), Some("checkbox" | "radio")) && !self.is_checked(id) {
+                continue;
+            }
+
+            let name = self.attribute(id, "name").unwrap_or_default();
+            if input_type.as_deref() == Some("image") {
+                // The coordinates of the click, which are always 0 as there is no pointer
+                let prefix = if name.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}.", name)
+                };
+                entries.push(FormEntry::new(&format!("{}x", prefix), "0"));
+                entries.push(FormEntry::new(&format!("{}y", prefix), "0"));
+                continue;
+            }
+            if name.is_empty() {
+                continue;
+            }
+
+            match (node.name.as_str(), input_type.as_deref()) {
+                ("select", _) => {
+                    for option_id in self.selected_options(id) {
+                        if !self.is_disabled(option_id) {
+                            let value = self.default_value(option_id).unwrap_or_default();
+                            entries.push(FormEntry::new(name, &value));
+                        }
+                    }
+                }
+                (_, Some("file")) => entries.push(FormEntry {
+                    name: name.to_string(),
+                    value: FormEntryValue::File {
+                        filename: String::new(),
+                        content_type: "application/octet-stream".to_string(),
+                        data: vec![],
+                    },
+                }),
+                (_, Some("hidden"))
+                    if name.eq_ignore_ascii_case("_charset_")
+                        && !self.has_attribute(id, "value") =>
+                {
+                    entries.push(FormEntry::new(name, "UTF-8"))
+                }
+                _ => {
+                    let value = self.value(id).unwrap_or_default();
+                    entries.push(FormEntry::new(name, &value));
+                }
+            }
+
+            // The directionality of the value, which is always left-to-right for now
+            let has_dirname =
+                node.name == "textarea" || matches!(input_type.as_deref(), Some("text" | "search"));
+            if let Some(dirname) = self.attribute(id, "dirname").filter(|d| !d.is_empty()) {
+                if has_dirname {
+                    entries.push(FormEntry::new(dirname, "ltr"));
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// Submits the form and returns the resulting request. The `formaction`, `formmethod` and
+    /// `formenctype` attributes of the submitter override the attributes of the form. Returns
+    /// None when the node is not a form or the action URL cannot be resolved.
+    pub fn form_submission(
+        &self,
+        form_id: NodeId,
+        submitter: Option<NodeId>,
+    ) -> Option<FormSubmission> {
+        if !self.is_html_element(form_id, "form") {
+            return None;
+        }
+        let submitter = submitter.filter(|id| self.is_submit_button(*id));
+        let attribute = |name: &str| {
+            submitter
+                .and_then(|id| self.attribute(id, &format!("form{}", name)))
+                .or_else(|| self.attribute(form_id, name))
+        };
+
+        let method = FormMethod::from_attribute(attribute("method"));
+        let enctype = FormEnctype::from_attribute(attribute("enctype"));
+        let mut action = match attribute("action").filter(|action| !action.is_empty()) {
+            Some(action) => self.resolve_url(action)?,
+            None => self.url()?.clone(),
+        };
+
+        let entries = self.form_entry_list(form_id, submitter);
+        let (content_type, body) = match method {
+            FormMethod::Dialog => (None, None),
+            FormMethod::Get => {
+                action.set_query(Some(&urlencoded(&entries)));
+                (None, None)
+            }
+            FormMethod::Post => match enctype {
+                FormEnctype::UrlEncoded => (
+                    Some(enctype.mime_type().to_string()),
+                    Some(urlencoded(&entries).into_bytes()),
+                ),
+                FormEnctype::MultipartFormData => {
+                    let boundary = format!("----gosub-{}", Uuid::new_v4().simple());
+                    (
+                        Some(format!("{}; boundary={}", enctype.mime_type(), boundary)),
+                        Some(multipart_form_data(&entries, &boundary)),
+                    )
+                }
+                FormEnctype::TextPlain => (
+                    Some(enctype.mime_type().to_string()),
+                    Some(text_plain(&entries).into_bytes()),
+                ),
+            },
+        };
+
+        Some(FormSubmission {
+            method,
+            enctype,
+            action,
+            content_type,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_html;
+
+    fn id(doc: &Document, named_id: &str) -> NodeId {
+        doc.get_node_by_named_id(named_id).expect("node").id
+    }
+
+    #[test]
+    fn form_owner() {
+        let document = parse_html(
+            r#"<form id="a"><input id="i1"><fieldset id="fs"><input id="i2" form="b"></fieldset></form>
+            <form id="b"></form><input id="i3" form="b"><input id="i4"><input id="i5" form="x">"#,
+        );
+        let binding = document.get();
+        let doc = &*binding;
+        let (a, b) = (id(doc, "a"), id(doc, "b"));
+
+        assert_eq!(doc.forms(), vec![a, b]);
+        assert_eq!(doc.form_owner(id(doc, "i1")), Some(a));
+        assert_eq!(doc.form_owner(id(doc, "i2")), Some(b));
+        assert_eq!(doc.form_owner(id(doc, "i3")), Some(b));
+        assert_eq!(doc.form_owner(id(doc, "i4")), None);
+        assert_eq!(doc.form_owner(id(doc, "i5")), None);
+        assert_eq!(doc.form_elements(a), vec![id(doc, "i1"), id(doc, "fs")]);
+        assert_eq!(doc.form_elements(b), vec![id(doc, "i2"), id(doc, "i3")]);
+    }
+
+    #[test]
+    fn parser_form_association() {
+        // The form is closed by the table, but the parser still associates the input with it
+        let document =
+            parse_html(r#"<table><form id="f"><tr><td><input id="i"></td></tr></form></table>"#);
+        let binding = document.get();
+        let doc = &*binding;
+
+        let input_id = id(doc, "i");
+        let form_id = id(doc, "f");
+        assert!(!doc.ancestors(input_id).contains(&form_id));
+        assert_eq!(doc.form_owner(input_id), Some(form_id));
+        assert_eq!(doc.form_elements(form_id), vec![input_id]);
+    }
+
+    #[test]
+    fn values() {
+        let mut document = parse_html(
+            r##"<form id="f">
+            <input id="text" value="a&#10;b">
+            <input id="number" type="number" value="abc">
+            <input id="range" type="range" min="10" max="20">
+            <input id="color" type="color" value="#FFAA00">
+            <input id="checkbox" type="checkbox" checked>
+            <input id="r1" type="radio" name="r" checked><input id="r2" type="radio" name="r" checked>
+            <textarea id="ta">line 1
+line 2</textarea>
+            <select id="s"><option>one</option><optgroup><option value="2" selected>two</option></optgroup></select>
+            </form>"##,
+        );
+        let mut binding = document.get_mut();
+        let doc = &mut *binding;
+
+        assert_eq!(doc.value(id(doc, "text")).unwrap(), "ab");
+        assert_eq!(doc.value(id(doc, "number")).unwrap(), "");
+        assert_eq!(doc.value(id(doc, "range")).unwrap(), "15");
+        assert_eq!(doc.value(id(doc, "color")).unwrap(), "#ffaa00");
+        assert_eq!(doc.value(id(doc, "checkbox")).unwrap(), "on");
+        assert_eq!(doc.value(id(doc, "ta")).unwrap(), "line 1\nline 2");
+        assert_eq!(doc.value(id(doc, "s")).unwrap(), "2");
+
+        assert!(doc.is_checked(id(doc, "checkbox")));
+        assert!(!doc.is_checked(id(doc, "r1")));
+        assert!(doc.is_checked(id(doc, "r2")));
+
+        let text_id = id(doc, "text");
+        assert!(doc.set_value(text_id, "changed"));
+        assert_eq!(doc.value(text_id).unwrap(), "changed");
+        assert_eq!(doc.default_value(text_id).unwrap(), "a\nb");
+
+        assert!(doc.set_checked(id(doc, "r1"), true));
+        assert!(doc.is_checked(id(doc, "r1")));
+        assert!(!doc.is_checked(id(doc, "r2")));
+
+        assert!(doc.set_value(id(doc, "s"), "one"));
+        assert_eq!(doc.value(id(doc, "s")).unwrap(), "one");
+        assert!(!doc.set_value(id(doc, "s"), "three"));
+
+        doc.reset_form(id(doc, "f"));
+        assert_eq!(doc.value(text_id).unwrap(), "ab");
+        assert!(doc.is_checked(id(doc, "r2")));
+        assert_eq!(doc.value(id(doc, "s")).unwrap(), "2");
+    }
+
+    #[test]
+    fn constraint_validation() {
+        let mut document = parse_html(
+            r#"<form id="f">
+            <input id="required" required>
+            <input id="email" type="email" value="not an email">
+            <input id="emails" type="email" multiple value="a@example.com, b@example.com">
+            <input id="url" type="url" value="/relative">
+            <input id="pattern" pattern="[a-z]+" value="abc1">
+            <input id="number" type="number" min="1" max="10" value="11">
+            <input id="step" type="number" min="0" step="0.5" value="1.25">
+            <input id="date" type="date" min="2020-01-01" value="2019-12-31">
+            <input id="maxlength" maxlength="3">
+            <input id="disabled" required disabled>
+            <input id="checkbox" type="checkbox" required>
+            <select id="select" required><option value="">Choose</option><option>one</option></select>
+            </form>"#,
+        );
+        let mut binding = document.get_mut();
+        let doc = &mut *binding;
+        let validity = |doc: &Document, name: &str| doc.validity(id(doc, name));
+
+        assert!(validity(doc, "required").value_missing);
+        assert!(validity(doc, "email").type_mismatch);
+        assert!(validity(doc, "emails").valid());
+        assert!(validity(doc, "url").type_mismatch);
+        assert!(validity(doc, "pattern").pattern_mismatch);
+        assert!(validity(doc, "number").range_overflow);
+        assert!(validity(doc, "step").step_mismatch);
+        assert!(validity(doc, "date").range_underflow);
+        assert!(validity(doc, "checkbox").value_missing);
+        assert!(validity(doc, "select").value_missing);
+        assert!(!doc.will_validate(id(doc, "disabled")));
+
+        doc.set_value(id(doc, "maxlength"), "abcd");
+        assert!(validity(doc, "maxlength").too_long);
+
+        doc.set_value(id(doc, "required"), "value");
+        doc.set_value(id(doc, "email"), "user@example.com");
+        doc.set_value(id(doc, "url"), "https://example.com/");
+        doc.set_value(id(doc, "pattern"), "abc");
+        doc.set_value(id(doc, "number"), "10");
+        doc.set_value(id(doc, "step"), "1.5");
+        doc.set_value(id(doc, "date"), "2020-01-01");
+        doc.set_value(id(doc, "maxlength"), "abc");
+        doc.set_checked(id(doc, "checkbox"), true);
+        doc.set_value(id(doc, "select"), "one");
+        assert_eq!(doc.invalid_controls(id(doc, "f")), vec![]);
+    }
+
+    #[test]
+    fn entry_list() {
+        let document = parse_html(
+            r#"<form id="f">
+            <input name="q" value="a b&amp;c">
+            <input name="unchecked" type="checkbox">
+            <input name="checked" type="checkbox" checked>
+            <input name="disabled" disabled value="x">
+            <input value="no name">
+            <input name="_charset_" type="hidden">
+            <select name="s" multiple><option selected>1</option><option>2</option><option selected>3</option></select>
+            <textarea name="t" dirname="t.dir">x&#13;&#10;y</textarea>
+            <input name="file" type="file">
+            <button name="b1" id="b1" value="first">1</button><button name="b2" value="second">2</button>
+            </form>"#,
+        );
+        let binding = document.get();
+        let doc = &*binding;
+        let form_id = id(doc, "f");
+
+        let entries = doc.form_entry_list(form_id, Some(id(doc, "b1")));
+        assert_eq!(
+            urlencoded(&entries),
+            "q=a+b%26c&checked=on&_charset_=UTF-8&s=1&s=3&t=x%0D%0Ay&t.dir=ltr&file=&b1=first"
+        );
+        assert_eq!(text_plain(&entries[..2]), "q=a b&c\r\nchecked=on\r\n");
+
+        let body = multipart_form_data(&entries[7..], "BOUNDARY");
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--BOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; filename=\"\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n\r\n\
+             --BOUNDARY\r\nContent-Disposition: form-data; name=\"b1\"\r\n\r\nfirst\r\n\
+             --BOUNDARY--\r\n"
+        );
+    }
+
+    #[test]
+    fn submission() {
+        let mut document = parse_html(
+            r#"<form id="f" action="search?old=1#top"><input name="q" value="rust">
+            <button id="post" formmethod="post" formaction="/submit" formenctype="multipart/form-data">Go</button></form>"#,
+        );
+        document
+            .get_mut()
+            .set_url(Url::parse("https://example.com/dir/page.html").unwrap());
+        let binding = document.get();
+        let doc = &*binding;
+        let form_id = id(doc, "f");
+
+        let get = doc.form_submission(form_id, None).unwrap();
+        assert_eq!(get.method, FormMethod::Get);
+        assert_eq!(
+            get.action.href(),
+            "https://example.com/dir/search?q=rust#top"
+        );
+        assert!(get.body.is_none());
+
+        let post = doc.form_submission(form_id, Some(id(doc, "post"))).unwrap();
+        assert_eq!(post.method, FormMethod::Post);
+        assert_eq!(post.enctype, FormEnctype::MultipartFormData);
+        assert_eq!(post.action.href(), "https://example.com/submit");
+
+        let content_type = post.content_type.unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+        let body = String::from_utf8(post.body.unwrap()).unwrap();
+        assert!(body.starts_with(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"q\"\r\n\r\nrust\r\n",
+            boundary
+        )));
+    }
+}
@@ -0,0 +1,565 @@
+//! Text content of nodes: `textContent` and `innerText`
+//!
+//! `textContent` is simply the concatenation of all descendant text nodes. `innerText` follows the
+//! rendered text collection steps from the spec
+//! (<https://html.spec.whatwg.org/multipage/dom.html#the-innertext-idl-attribute>): whitespace is
+//! collapsed, block-level elements start on a new line, paragraphs are separated by an empty line,
+//! table cells are separated by tabs, and content that is not rendered is skipped. Like rendering,
+//! it walks the flat tree, so shadow trees are included and slotted nodes appear in their slots.
+//!
+//! There is no style engine yet, so the `display` and `white-space` values of elements are taken
+//! from the defaults of the user agent stylesheet.
+use crate::html5_parser::node::{Node, NodeData, NodeId, HTML_NAMESPACE};
+use crate::html5_parser::parser::document::{Document, DocumentHandle};
+use std::collections::HashMap;
+
+/// Elements that are `display: none` in the user agent stylesheet
+static HIDDEN_ELEMENTS: [&str; 16] = [
+    "area", "base", "basefont", "datalist", "head", "link", "meta", "noembed", "noframes", "param",
+    "rp", "script", "style", "template", "title", "track",
+];
+
+/// Elements that are block-level in the user agent stylesheet (excluding tables, which are handled
+/// separately)
+static BLOCK_ELEMENTS: [&str; 44] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "legend",
+    "li",
+    "listing",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "optgroup",
+    "option",
+    "plaintext",
+    "pre",
+    "search",
+    "section",
+    "summary",
+    "ul",
+    "xmp",
+];
+
+/// Elements that preserve whitespace (`white-space: pre` or `pre-wrap`) in the user agent stylesheet
+static PREFORMATTED_ELEMENTS: [&str; 5] = ["listing", "plaintext", "pre", "textarea", "xmp"];
+
+/// Single part of the rendered text
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    /// Text from a text node, with whitespace that still needs collapsing
+    Text(String),
+    /// Text that must be kept as-is (preformatted text, `<br>`, tabs and newlines of tables)
+    Preserved(String),
+    /// Number of newlines that must at least separate the text before and after this item
+    RequiredLineBreaks(usize),
+}
+
+impl Node {
+    /// Returns the text content of the node: the concatenated values of all descendant text
+    /// nodes, or the value of the node itself for text and comment nodes.
+    pub fn text_content(&self) -> String {
+        match &self.data {
+            NodeData::Text(text) => text.value().to_string(),
+            NodeData::Comment(comment) => comment.value().to_string(),
+            _ => self.document.get().text_content(self.id),
+        }
+    }
+
+    /// Returns the text of the node as it would be rendered
+    pub fn inner_text(&self) -> String {
+        self.document.get().inner_text(self.id)
+    }
+}
+
+impl Document {
+    /// Returns the text content of the node with the given id
+    pub fn text_content(&self, node_id: NodeId) -> String {
+        let mut value = String::new();
+        self.collect_text(node_id, &mut value);
+        value
+    }
+
+    fn collect_text(&self, node_id: NodeId, value: &mut String) {
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return;
+        };
+        if let NodeData::Text(text) = &node.data {
+            value.push_str(text.value());
+        }
+        for child_id in &node.children {
+            self.collect_text(*child_id, value);
+        }
+    }
+
+    /// Returns the rendered text of the node with the given id. When the node itself is not
+    /// rendered, its text content is returned instead.
+    pub fn inner_text(&self, node_id: NodeId) -> String {
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return String::new();
+        };
+        if !self.is_rendered(node) {
+            return self.text_content(node_id);
+        }
+
+        let mut items = vec![];
+        let preformatted = self.is_preformatted(node);
+        for child_id in self.flat_tree_children(node_id) {
+            self.rendered_text_collection(child_id, preformatted, &mut items);
+        }
+        join_items(&items)
+    }
+
+    /// Rendered text collection steps for a single node
+    fn rendered_text_collection(&self, node_id: NodeId, preformatted: bool, items: &mut Vec<Item>) {
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return;
+        };
+
+        match &node.data {
+            NodeData::Text(_) if !self.is_rendered(node) => {}
+            NodeData::Text(text) if preformatted => {
+                items.push(Item::Preserved(text.value().to_string()));
+            }
+            NodeData::Text(text) => items.push(Item::Text(text.value().to_string())),
+            NodeData::Element(_) => {
+                if !self.is_rendered(node) {
+                    return;
+                }
+                if is_html_element(node, "br") {
+                    items.push(Item::Preserved("\n".to_string()));
+                    return;
+                }
+
+                let breaks = if is_html_element(node, "p") {
+                    2
+                } else if self.is_block_level(node) {
+                    1
+                } else {
+                    0
+                };
+                if breaks > 0 {
+                    items.push(Item::RequiredLineBreaks(breaks));
+                }
+
+                let preformatted = preformatted || self.is_preformatted(node);
+                for child_id in self.flat_tree_children(node_id) {
+                    self.rendered_text_collection(child_id, preformatted, items);
+                }
+
+                if is_table_cell(node) && !self.is_last_cell(node) {
+                    items.push(Item::Preserved("\t".to_string()));
+                }
+                if is_html_element(node, "tr") && !self.is_last_row(node) {
+                    items.push(Item::Preserved("\n".to_string()));
+                }
+                if breaks > 0 {
+                    items.push(Item::RequiredLineBreaks(breaks));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns false when the element is `display: none`, either by the user agent stylesheet or
+    /// by being hidden
+    pub(crate) fn is_rendered(&self, node: &Node) -> bool {
+        let NodeData::Element(element) = &node.data else {
+            return self.is_rendered_in_details(node);
+        };
+        if node.namespace.as_deref() != Some(HTML_NAMESPACE) {
+            return true;
+        }
+        if element.attributes.contains("hidden") {
+            return false;
+        }
+
+        match node.name.as_str() {
+            name if HIDDEN_ELEMENTS.contains(&name) => false,
+            "dialog" => element.attributes.contains("open"),
+            "input" => element
+                .attributes
+                .get("type")
+                .map_or(true, |t| !t.eq_ignore_ascii_case("hidden")),
+            _ => self.is_rendered_in_details(node),
+        }
+    }
+
+    /// Only the summary of a closed details element is rendered
+    fn is_rendered_in_details(&self, node: &Node) -> bool {
+        let Some(parent) = node.parent.and_then(|id| self.get_node_by_id(id)) else {
+            return true;
+        };
+        if !is_html_element(parent, "details") {
+            return true;
+        }
+        let NodeData::Element(details) = &parent.data else {
+            return true;
+        };
+        if details.attributes.contains("open") {
+            return true;
+        }
+
+        let first_summary = parent
+            .children
+            .iter()
+            .filter_map(|id| self.get_node_by_id(*id))
+            .find(|child| is_html_element(child, "summary"));
+        first_summary.is_some_and(|summary| summary.id == node.id)
+    }
+
+    pub(crate) fn is_block_level(&self, node: &Node) -> bool {
+        node.namespace.as_deref() == Some(HTML_NAMESPACE)
+            && (BLOCK_ELEMENTS.contains(&node.name.as_str())
+                || node.name == "table"
+                || node.name == "caption")
+    }
+
+    fn is_preformatted(&self, node: &Node) -> bool {
+        node.namespace.as_deref() == Some(HTML_NAMESPACE)
+            && PREFORMATTED_ELEMENTS.contains(&node.name.as_str())
+    }
+
+    /// Returns true when there is no rendered table cell after this cell in its row
+    fn is_last_cell(&self, node: &Node) -> bool {
+        let Some(row) = node.parent.and_then(|id| self.get_node_by_id(id)) else {
+            return true;
+        };
+        row.children
+            .iter()
+            .skip_while(|id| **id != node.id)
+            .skip(1)
+            .filter_map(|id| self.get_node_by_id(*id))
+            .all(|sibling| !is_table_cell(sibling) || !self.is_rendered(sibling))
+    }
+
+    /// Returns true when there is no rendered row after this row in its table
+    fn is_last_row(&self, node: &Node) -> bool {
+        let mut table_id = node.parent;
+        while let Some(id) = table_id {
+            match self.get_node_by_id(id) {
+                Some(parent) if is_html_element(parent, "table") => break,
+                Some(parent) => table_id = parent.parent,
+                None => return true,
+            }
+        }
+        let Some(table_id) = table_id else {
+            return true;
+        };
+
+        let mut rows = vec![];
+        self.collect_rows(table_id, &mut rows);
+        rows.last() == Some(&node.id)
+    }
+
+    /// Collects the rendered rows of a table, without the rows of nested tables
+    pub(crate) fn collect_rows(&self, node_id: NodeId, rows: &mut Vec<NodeId>) {
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return;
+        };
+        for child in node
+            .children
+            .iter()
+            .filter_map(|id| self.get_node_by_id(*id))
+        {
+            if !self.is_rendered(child) || is_html_element(child, "table") {
+                continue;
+            }
+            if is_html_element(child, "tr") {
+                rows.push(child.id);
+            } else {
+                self.collect_rows(child.id, rows);
+            }
+        }
+    }
+}
+
+impl DocumentHandle {
+    /// Replaces all children of the node with a single text node (or nothing when the value is
+    /// empty). For text and comment nodes, the value of the node is replaced instead.
+    pub fn set_text_content(&mut self, node_id: NodeId, value: &str) -> bool {
+        let is_character_data = match self.get().get_node_by_id(node_id) {
+            Some(node) => matches!(node.data, NodeData::Text(_) | NodeData::Comment(_)),
+            None => return false,
+        };
+        if is_character_data {
+            return self.get_mut().set_character_data(node_id, value);
+        }
+
+        let mut nodes = vec![];
+        if !value.is_empty() {
+            nodes.push(Node::new_text(self, value));
+        }
+        self.get_mut().replace_all(node_id, nodes)
+    }
+
+    /// Replaces all children of the element with the text, where line breaks are converted into
+    /// `<br>` elements
+    pub fn set_inner_text(&mut self, node_id: NodeId, value: &str) -> bool {
+        match self.get().get_node_by_id(node_id) {
+            Some(node) if matches!(node.data, NodeData::Element(_)) => {}
+            _ => return false,
+        }
+
+        let value = value.replace("\r\n", "\n").replace('\r', "\n");
+        let mut nodes = vec![];
+        for (idx, line) in value.split('\n').enumerate() {
+            if idx > 0 {
+                nodes.push(Node::new_element(
+                    self,
+                    "br",
+                    HashMap::new(),
+                    HTML_NAMESPACE,
+                ));
+            }
+            if !line.is_empty() {
+                nodes.push(Node::new_text(self, line));
+            }
+        }
+        self.get_mut().replace_all(node_id, nodes)
+    }
+}
+
+fn is_html_element(node: &Node, name: &str) -> bool {
+    matches!(node.data, NodeData::Element(_))
+        && node.name == name
+        && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+}
+
+fn is_table_cell(node: &Node) -> bool {
+    is_html_element(node, "td") || is_html_element(node, "th")
+}
+
+/// Joins the collected items into the final text. Whitespace in text items is collapsed, and
+/// removed at the start and end of lines. Consecutive required line breaks are replaced by the
+/// largest number of them, except at the start and end of the text.
+fn join_items(items: &[Item]) -> String {
+    let mut result = String::new();
+    let mut pending_space = false;
+    let mut at_line_start = true;
+    let mut pending_breaks = 0;
+
+    let flush_breaks = |result: &mut String, pending_breaks: &mut usize| {
+        if !result.is_empty() {
+            result.push_str(&"\n".repeat(*pending_breaks));
+        }
+        *pending_breaks = 0;
+    };
+
+    for item in items {
+        match item {
+            Item::Text(value) => {
+                for c in value.chars() {
+                    if c.is_ascii_whitespace() {
+                        pending_space = !at_line_start;
+                        continue;
+                    }
+                    flush_breaks(&mut result, &mut pending_breaks);
+                    if pending_space {
+                        result.push(' ');
+                        pending_space = false;
+                    }
+                    result.push(c);
+                    at_line_start = false;
+                }
+            }
+            Item::Preserved(value) => {
+                if value.is_empty() {
+                    continue;
+                }
+                flush_breaks(&mut result, &mut pending_breaks);
+                pending_space = false;
+                result.push_str(value);
+                at_line_start = value.ends_with('\n') || value.ends_with('\t');
+            }
+            Item::RequiredLineBreaks(count) => {
+                pending_breaks = pending_breaks.max(*count);
+                pending_space = false;
+                at_line_start = true;
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html5_parser::mutation_observer::MutationObserverInit;
+    use crate::testing::parse_html;
+
+    fn inner_text_of(document: &DocumentHandle, id: &str) -> String {
+        document
+            .get()
+            .get_node_by_named_id(id)
+            .expect("node")
+            .inner_text()
+    }
+
+    #[test]
+    fn text_content() {
+        let document = parse_html("<div id=\"d\">a <b>b</b><!-- c --><style>d</style></div>");
+        let doc = document.get();
+        let div = doc.get_node_by_named_id("d").unwrap();
+        assert_eq!(div.text_content(), "a bd");
+
+        let text = doc.get_node_by_id(div.children[0]).unwrap();
+        assert_eq!(text.text_content(), "a ");
+    }
+
+    #[test]
+    fn collapses_whitespace() {
+        let document = parse_html("<div id=\"d\">\n   hello   <b> big </b>\n\n  world  </div>");
+        assert_eq!(inner_text_of(&document, "d"), "hello big world");
+    }
+
+    #[test]
+    fn block_elements_and_paragraphs() {
+        let document = parse_html(
+            "<div id=\"d\">intro<div>block</div><p>para 1</p><p>para 2</p>a<br>b<br>\
+             <h1>title</h1></div>",
+        );
+        assert_eq!(
+            inner_text_of(&document, "d"),
+            "intro\nblock\n\npara 1\n\npara 2\n\na\nb\n\ntitle"
+        );
+    }
+
+    #[test]
+    fn preformatted() {
+        let document = parse_html("<div id=\"d\">a   b<pre>  x\n   y</pre></div>");
+        assert_eq!(inner_text_of(&document, "d"), "a b\n  x\n   y");
+    }
+
+    #[test]
+    fn tables() {
+        // The tree is built by hand, as the parser does not handle table cells correctly yet
+        let mut document = parse_html("<div id=\"d\">before</div>");
+        let div_id = document.get().get_node_by_named_id("d").unwrap().id;
+
+        let mut add = |parent_id: NodeId, name: &str, text: Option<&str>| {
+            let node = Node::new_element(&document, name, HashMap::new(), HTML_NAMESPACE);
+            let node_id = document.get_mut().add_node(node, parent_id);
+            if let Some(text) = text {
+                let node = Node::new_text(&document, text);
+                document.get_mut().add_node(node, node_id);
+            }
+            node_id
+        };
+        let table_id = add(div_id, "table", None);
+        let tbody_id = add(table_id, "tbody", None);
+        for cells in [["a", "b"], [" 1 ", "2"]] {
+            let tr_id = add(tbody_id, "tr", None);
+            for cell in cells {
+                add(tr_id, "td", Some(cell));
+            }
+        }
+
+        assert_eq!(inner_text_of(&document, "d"), "before\na\tb\n1\t2");
+    }
+
+    #[test]
+    fn hidden_content() {
+        let document = parse_html(
+            "<div id=\"d\">visible<span hidden>hidden</span><style>p {}</style>\
+             <details><summary>more</summary>details</details></div>",
+        );
+        assert_eq!(inner_text_of(&document, "d"), "visible\nmore");
+
+        // Elements that are not rendered return their text content
+        let document = parse_html("<div id=\"d\" hidden>a <b>b</b></div>");
+        assert_eq!(inner_text_of(&document, "d"), "a b");
+    }
+
+    #[test]
+    fn shadow_trees() {
+        let document = parse_html(
+            "<div id=\"d\"><template shadowrootmode=open><p>Shadow content</p><slot></slot>\
+             </template><span>Light</span></div>",
+        );
+        assert_eq!(inner_text_of(&document, "d"), "Shadow content\n\nLight");
+        // textContent only has the light tree
+        let doc = document.get();
+        assert_eq!(
+            doc.get_node_by_named_id("d").unwrap().text_content(),
+            "Light"
+        );
+    }
+
+    #[test]
+    fn set_text_content() {
+        let mut document = parse_html("<div id=\"d\">a <b>b</b></div>");
+        let div_id = document.get().get_node_by_named_id("d").unwrap().id;
+        let observer = document.get_mut().create_mutation_observer();
+        let options = MutationObserverInit {
+            child_list: true,
+            ..Default::default()
+        };
+        document.get_mut().observe(observer, div_id, options);
+
+        assert!(document.set_text_content(div_id, "<new>"));
+        let doc = document.get();
+        let div = doc.get_node_by_id(div_id).unwrap();
+        assert_eq!(div.children.len(), 1);
+        assert_eq!(div.text_content(), "<new>");
+        drop(doc);
+
+        let records = document.get_mut().take_mutation_records(observer);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].removed_nodes.len(), 2);
+        assert_eq!(records[0].added_nodes.len(), 1);
+
+        assert!(document.set_text_content(div_id, ""));
+        assert!(document
+            .get()
+            .get_node_by_id(div_id)
+            .unwrap()
+            .children
+            .is_empty());
+    }
+
+    #[test]
+    fn set_inner_text() {
+        let mut document = parse_html("<div id=\"d\">old</div>");
+        let div_id = document.get().get_node_by_named_id("d").unwrap().id;
+
+        assert!(document.set_inner_text(div_id, "line 1\r\nline 2\n"));
+        let doc = document.get();
+        let div = doc.get_node_by_id(div_id).unwrap();
+        let names: Vec<String> = div
+            .children
+            .iter()
+            .map(|id| doc.get_node_by_id(*id).unwrap().name.clone())
+            .collect();
+        assert_eq!(names, vec!["", "br", "", "br"]);
+        assert_eq!(div.inner_text(), "line 1\nline 2\n");
+    }
+}
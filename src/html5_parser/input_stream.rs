@@ -4,7 +4,7 @@ use std::io::Read;
 use std::{fmt, io};
 
 /// Encoding defines the way the buffer stream is read, as what defines a "character".
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug, Copy, Clone)]
 pub enum Encoding {
     /// Stream is of UTF8 characters
     UTF8,
@@ -12,6 +12,24 @@ pub enum Encoding {
     ASCII,
 }
 
+impl Encoding {
+    /// Returns the encoding for a label as found in a charset parameter
+    /// (<https://encoding.spec.whatwg.org/#concept-encoding-get>). Returns None for labels of
+    /// encodings that are not supported. Note that "ascii" and "us-ascii" are labels of
+    /// windows-1252, not of ASCII.
+    pub fn from_label(label: &str) -> Option<Encoding> {
+        match label
+            .trim_matches(|c: char| c.is_ascii_whitespace())
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "utf-8" | "utf8"
+            | "x-unicode20utf8" => Some(Encoding::UTF8),
+            _ => None,
+        }
+    }
+}
+
 /// The confidence decides how confident we are that the input stream is of this encoding
 #[derive(PartialEq)]
 pub enum Confidence {
@@ -278,12 +296,10 @@ impl InputStream {
     pub fn force_set_encoding(&mut self, e: Encoding) {
         match e {
             Encoding::UTF8 => {
-                let str_buf;
-                unsafe {
-                    str_buf = std::str::from_utf8_unchecked(&self.u8_buffer)
-                        .replace("\u{000D}\u{000A}", "\u{000A}")
-                        .replace('\u{000D}', "\u{000A}");
-                }
+                // Invalid UTF-8 sequences are replaced with U+FFFD
+                let str_buf = String::from_utf8_lossy(&self.u8_buffer)
+                    .replace("\u{000D}\u{000A}", "\u{000A}")
+                    .replace('\u{000D}', "\u{000A}");
 
                 // Convert the utf8 string into characters so we can use easy indexing
                 self.buffer = vec![];
@@ -318,7 +334,7 @@ impl InputStream {
     }
 
     /// Normalizes newlines (CRLF/CR => LF) and converts high ascii to '?'
-    fn normalize_newlines_and_ascii(&self, buffer: &Vec<u8>) -> Vec<Element> {
+    fn normalize_newlines_and_ascii(&self, buffer: &[u8]) -> Vec<Element> {
         let mut result = Vec::with_capacity(buffer.len());
 
         for i in 0..buffer.len() {
@@ -356,6 +372,13 @@ impl InputStream {
         self.reset();
     }
 
+    /// Populates the current buffer with the given bytes
+    pub fn read_from_bytes(&mut self, bytes: &[u8], e: Option<Encoding>) {
+        self.u8_buffer = bytes.to_vec();
+        self.force_set_encoding(e.unwrap_or(Encoding::UTF8));
+        self.reset();
+    }
+
     /// Returns the number of characters left in the buffer
     pub(crate) fn chars_left(&self) -> usize {
         self.length - self.position.offset
@@ -440,6 +463,19 @@ impl InputStream {
 mod test {
     use super::*;
 
+    #[test]
+    fn encoding_labels() {
+        assert_eq!(Encoding::from_label("utf-8"), Some(Encoding::UTF8));
+        assert_eq!(Encoding::from_label(" UTF8\t"), Some(Encoding::UTF8));
+        assert_eq!(
+            Encoding::from_label("unicode-1-1-utf-8"),
+            Some(Encoding::UTF8)
+        );
+        assert_eq!(Encoding::from_label("ascii"), None);
+        assert_eq!(Encoding::from_label("US-ASCII"), None);
+        assert_eq!(Encoding::from_label("latin1"), None);
+    }
+
     #[test]
     fn test_stream() {
         let mut is = InputStream::new();
@@ -0,0 +1,378 @@
+//! Document metadata: title, language, charset, meta tags, link relations and JSON-LD
+//!
+//! These helpers look at the HTML elements in the document the same way a browser (or a search
+//! engine) would: the first `<title>` element gives the title, `<meta>` elements give name/content
+//! pairs, and `<link>` elements give relations to other resources. URLs are resolved against the
+//! base URL of the document.
+use crate::html5_parser::node::{Node, NodeData, NodeId, HTML_NAMESPACE};
+use crate::html5_parser::parser::document::Document;
+use crate::url::Url;
+use serde_json::Value;
+
+/// A `<meta>` element with a name (or property) and content
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetaTag {
+    /// The name attribute (lowercased), or the property attribute as used by OpenGraph
+    pub name: String,
+    /// The content attribute
+    pub content: String,
+}
+
+/// A relation from a `<link>` element. A link with multiple link types (like `rel="shortcut
+/// icon"`) results in one relation for each type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkRelation {
+    /// The link element
+    pub node_id: NodeId,
+    /// The link type, lowercased
+    pub rel: String,
+    /// The resolved href
+    pub href: Url,
+    /// The type attribute (MIME type of the resource), if any
+    pub mime_type: Option<String>,
+    /// The sizes attribute (for icons), if any
+    pub sizes: Option<String>,
+    /// The hreflang attribute, if any
+    pub hreflang: Option<String>,
+    /// The media attribute, if any
+    pub media: Option<String>,
+}
+
+/// Link types that refer to an icon of the page
+static ICON_RELATIONS: [&str; 3] = ["icon", "apple-touch-icon", "apple-touch-icon-precomposed"];
+
+/// Returns the value of the attribute of the element
+fn attribute<'a>(node: &'a Node, name: &str) -> Option<&'a str> {
+    match &node.data {
+        NodeData::Element(element) => element.attributes.get(name).map(|value| value.as_str()),
+        _ => None,
+    }
+}
+
+/// Strips and collapses ASCII whitespace
+fn collapse_whitespace(value: &str) -> String {
+    value.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns the charset from a content type like `text/html; charset=utf-8`
+/// (<https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element>)
+fn charset_from_content_type(content: &str) -> Option<String> {
+    let lowercase = content.to_ascii_lowercase();
+    let start = lowercase.find("charset")? + "charset".len();
+    let rest = lowercase[start..].trim_start();
+    let rest = rest.strip_prefix('=')?.trim_start();
+
+    let value = match rest.chars().next()? {
+        quote @ ('"' | '\'') => rest[1..].split(quote).next()?,
+        _ => rest
+            .split(|c: char| c == ';' || c.is_ascii_whitespace())
+            .next()?,
+    };
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+impl Document {
+    /// Returns the title of the document: the text of the first title element, with whitespace
+    /// stripped and collapsed
+    /// (<https://html.spec.whatwg.org/multipage/dom.html#document.title>)
+    pub fn title(&self) -> Option<String> {
+        self.html_elements("title")
+            .next()
+            .map(|node| collapse_whitespace(&self.text_content(node.id)))
+    }
+
+    /// Returns the document element (the html element), if any
+    pub fn document_element(&self) -> Option<&Node> {
+        self.get_root()
+            .children
+            .iter()
+            .filter_map(|id| self.get_node_by_id(*id))
+            .find(|node| matches!(node.data, NodeData::Element(_)) && node.name == "html")
+    }
+
+    /// Returns the language of the document, from the lang attribute of the html element
+    pub fn language(&self) -> Option<String> {
+        let lang = attribute(self.document_element()?, "lang")?.trim();
+        (!lang.is_empty()).then(|| lang.to_string())
+    }
+
+    /// Returns the directionality of the document (`ltr`, `rtl` or `auto`), from the dir
+    /// attribute of the html element. Invalid values are ignored.
+    pub fn direction(&self) -> Option<String> {
+        let dir = attribute(self.document_element()?, "dir")?.to_ascii_lowercase();
+        ["ltr", "rtl", "auto"]
+            .contains(&dir.as_str())
+            .then_some(dir)
+    }
+
+    /// Returns the character encoding declared in the document, either by `<meta charset>` or
+    /// by `<meta http-equiv="content-type">`. The label is returned lowercased, as declared.
+    pub fn charset(&self) -> Option<String> {
+        self.html_elements("meta").find_map(|node| {
+            if let Some(charset) = attribute(node, "charset") {
+                let charset = charset.trim().to_ascii_lowercase();
+                return (!charset.is_empty()).then_some(charset);
+            }
+            let http_equiv = attribute(node, "http-equiv")?;
+            if !http_equiv.trim().eq_ignore_ascii_case("content-type") {
+                return None;
+            }
+            charset_from_content_type(attribute(node, "content")?)
+        })
+    }
+
+    /// Returns the canonical URL of the document (`<link rel="canonical">`)
+    pub fn canonical_url(&self) -> Option<Url> {
+        self.link_relations()
+            .into_iter()
+            .find(|link| link.rel == "canonical")
+            .map(|link| link.href)
+    }
+
+    /// Returns all `<meta>` elements with a name or property and a content attribute, in tree
+    /// order. This includes OpenGraph (`og:*` properties) and Twitter card (`twitter:*` names)
+    /// metadata.
+    pub fn meta_tags(&self) -> Vec<MetaTag> {
+        self.html_elements("meta")
+            .filter_map(|node| {
+                let content = attribute(node, "content")?;
+                let name = match attribute(node, "name") {
+                    Some(name) => name.trim().to_ascii_lowercase(),
+                    None => attribute(node, "property")?.trim().to_string(),
+                };
+                (!name.is_empty()).then(|| MetaTag {
+                    name,
+                    content: content.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the content of the first meta tag with the name (or property). Names are matched
+    /// case-insensitively.
+    pub fn meta_content(&self, name: &str) -> Option<String> {
+        self.meta_tags()
+            .into_iter()
+            .find(|tag| tag.name.eq_ignore_ascii_case(name))
+            .map(|tag| tag.content)
+    }
+
+    /// Returns the OpenGraph metadata (`og:*` properties) in tree order. Properties like
+    /// `og:image` can occur multiple times.
+    pub fn open_graph(&self) -> Vec<MetaTag> {
+        self.meta_tags_with_prefix("og:")
+    }
+
+    /// Returns the Twitter card metadata (`twitter:*` names) in tree order
+    pub fn twitter_card(&self) -> Vec<MetaTag> {
+        self.meta_tags_with_prefix("twitter:")
+    }
+
+    fn meta_tags_with_prefix(&self, prefix: &str) -> Vec<MetaTag> {
+        self.meta_tags()
+            .into_iter()
+            .filter(|tag| tag.name.starts_with(prefix))
+            .collect()
+    }
+
+    /// Returns the relations of all `<link>` elements with a rel and a valid href, in tree order
+    pub fn link_relations(&self) -> Vec<LinkRelation> {
+        let base_url = self.base_url();
+
+        self.html_elements("link")
+            .flat_map(|node| {
+                let href = attribute(node, "href")
+                    .and_then(|href| Url::parse_with_base(href, base_url.as_ref()).ok());
+                let rel = attribute(node, "rel").unwrap_or_default();
+
+                let mut relations = vec![];
+                let Some(href) = href else {
+                    return relations;
+                };
+                for rel in rel.split_ascii_whitespace() {
+                    let rel = rel.to_ascii_lowercase();
+                    if relations.iter().any(|link: &LinkRelation| link.rel == rel) {
+                        continue;
+                    }
+                    relations.push(LinkRelation {
+                        node_id: node.id,
+                        rel,
+                        href: href.clone(),
+                        mime_type: attribute(node, "type").map(|value| value.to_string()),
+                        sizes: attribute(node, "sizes").map(|value| value.to_string()),
+                        hreflang: attribute(node, "hreflang").map(|value| value.to_string()),
+                        media: attribute(node, "media").map(|value| value.to_string()),
+                    });
+                }
+                relations
+            })
+            .collect()
+    }
+
+    /// Returns the URLs of the icons of the page, in tree order. When there are no icon links,
+    /// this is `/favicon.ico` relative to the document URL (if the document has an http(s) URL).
+    pub fn favicons(&self) -> Vec<Url> {
+        let mut icons: Vec<Url> = vec![];
+        for link in self.link_relations() {
+            if ICON_RELATIONS.contains(&link.rel.as_str()) && !icons.contains(&link.href) {
+                icons.push(link.href);
+            }
+        }
+
+        if icons.is_empty() {
+            let fallback = self
+                .url()
+                .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+                .and_then(|url| Url::parse_with_base("/favicon.ico", Some(url)).ok());
+            icons.extend(fallback);
+        }
+        icons
+    }
+
+    /// Returns the parsed payloads of all `<script type="application/ld+json">` elements, in tree
+    /// order. Scripts that do not contain valid JSON are skipped.
+    pub fn json_ld(&self) -> Vec<Value> {
+        self.html_elements("script")
+            .filter(|node| {
+                attribute(node, "type").is_some_and(|mime_type| {
+                    mime_type.trim().eq_ignore_ascii_case("application/ld+json")
+                })
+            })
+            .filter_map(|node| serde_json::from_str(&self.text_content(node.id)).ok())
+            .collect()
+    }
+
+    /// Returns all HTML elements with the name, in tree order
+    fn html_elements<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Node> + 'a {
+        self.descendants(NodeId::root())
+            .into_iter()
+            .filter_map(|id| self.get_node_by_id(id))
+            .filter(move |node| {
+                node.name == name
+                    && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+                    && matches!(node.data, NodeData::Element(_))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{parse_html, parse_html_with_url};
+
+    #[test]
+    fn title_language_and_charset() {
+        let document = parse_html(
+            "<!DOCTYPE html><html lang=\" en-US \" dir=\"RTL\"><head>\
+             <meta http-equiv=\"Content-Type\" content=\"text/html; charset='ISO-8859-1'\">\
+             <title>\n  Hello \t world </title><title>Second</title></head><body></body></html>",
+        );
+        let doc = document.get();
+
+        assert_eq!(doc.title().as_deref(), Some("Hello world"));
+        assert_eq!(doc.language().as_deref(), Some("en-US"));
+        assert_eq!(doc.direction().as_deref(), Some("rtl"));
+        assert_eq!(doc.charset().as_deref(), Some("iso-8859-1"));
+
+        let document = parse_html("<meta charset=UTF-8><html dir=sideways>");
+        let doc = document.get();
+        assert_eq!(doc.title(), None);
+        assert_eq!(doc.language(), None);
+        assert_eq!(doc.direction(), None);
+        assert_eq!(doc.charset().as_deref(), Some("utf-8"));
+        assert_eq!(
+            charset_from_content_type("text/html;charset = utf-8 ;x").as_deref(),
+            Some("utf-8")
+        );
+        assert_eq!(charset_from_content_type("text/html"), None);
+    }
+
+    #[test]
+    fn meta_tags_and_links() {
+        let document = parse_html_with_url(
+            "<head><meta name=\"Description\" content=\"A page\">\
+             <meta property=\"og:title\" content=\"OG title\">\
+             <meta property=\"og:image\" content=\"/a.png\"><meta property=\"og:image\" content=\"/b.png\">\
+             <meta name=\"twitter:card\" content=\"summary\"><meta name=\"robots\">\
+             <link rel=\"canonical\" href=\"/page?id=1\">\
+             <link rel=\"shortcut icon\" href=\"favicon.png\" type=\"image/png\">\
+             <link rel=\"apple-touch-icon\" href=\"touch.png\" sizes=\"180x180\">\
+             <link rel=\"alternate\" href=\"/fr/\" hreflang=\"fr\" media=\"print\"><link rel=\"stylesheet\">\
+             </head>",
+            "https://example.com/dir/index.html",
+        );
+        let doc = document.get();
+
+        assert_eq!(doc.meta_tags().len(), 5);
+        assert_eq!(doc.meta_content("description").as_deref(), Some("A page"));
+        assert_eq!(doc.meta_content("robots"), None);
+        assert_eq!(
+            doc.open_graph()
+                .iter()
+                .map(|tag| (tag.name.as_str(), tag.content.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("og:title", "OG title"),
+                ("og:image", "/a.png"),
+                ("og:image", "/b.png")
+            ]
+        );
+        assert_eq!(doc.twitter_card()[0].content, "summary");
+
+        assert_eq!(
+            doc.canonical_url().map(|url| url.href()).as_deref(),
+            Some("https://example.com/page?id=1")
+        );
+        let relations = doc.link_relations();
+        assert_eq!(
+            relations
+                .iter()
+                .map(|link| link.rel.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "canonical",
+                "shortcut",
+                "icon",
+                "apple-touch-icon",
+                "alternate"
+            ]
+        );
+        assert_eq!(relations[2].mime_type.as_deref(), Some("image/png"));
+        assert_eq!(relations[3].sizes.as_deref(), Some("180x180"));
+        assert_eq!(relations[4].hreflang.as_deref(), Some("fr"));
+        assert_eq!(relations[4].media.as_deref(), Some("print"));
+        assert_eq!(
+            doc.favicons()
+                .iter()
+                .map(|url| url.href())
+                .collect::<Vec<_>>(),
+            vec![
+                "https://example.com/dir/favicon.png",
+                "https://example.com/dir/touch.png"
+            ]
+        );
+
+        let document = parse_html_with_url("<p>", "https://example.com/a/b");
+        assert_eq!(
+            document.get().favicons()[0].href(),
+            "https://example.com/favicon.ico"
+        );
+        assert!(parse_html("<p>").get().favicons().is_empty());
+    }
+
+    #[test]
+    fn json_ld() {
+        let document = parse_html(
+            "<script type=\"application/ld+json\">{\"@type\": \"Article\", \"name\": \"<x>\"}</script>\
+             <script type=\"APPLICATION/LD+JSON \">[1, 2]</script>\
+             <script type=\"application/ld+json\">{invalid</script>\
+             <script>{\"a\": 1}</script>",
+        );
+        let items = document.get().json_ld();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["@type"], "Article");
+        assert_eq!(items[0]["name"], "<x>");
+        assert_eq!(items[1], serde_json::json!([1, 2]));
+    }
+}
@@ -0,0 +1,555 @@
+//! Extraction of items from microdata and RDFa Lite annotations
+//!
+//! Microdata items are built with the algorithms from the HTML specification
+//! (<https://html.spec.whatwg.org/multipage/microdata.html>): every element with an `itemscope`
+//! attribute is an item, and its properties are found by crawling its descendants and the
+//! elements it references with `itemref`. Items that are not the value of a property of another
+//! item are the top-level items.
+//!
+//! RDFa Lite (<https://www.w3.org/TR/rdfa-lite/>) uses the `vocab`, `typeof`, `property`,
+//! `resource` and `prefix` attributes instead. Every element with `typeof` is an item, and
+//! `property` attributes add properties to the nearest enclosing item. Terms are expanded into
+//! URLs using the vocabulary and prefixes in scope.
+//!
+//! Both formats produce the same structures, which serialize to the JSON format from the
+//! microdata specification:
+//!
+//! ```json
+//! { "items": [ { "type": ["https://schema.org/Person"], "properties": { "name": ["Alice"] } } ] }
+//! ```
+use crate::html5_parser::node::{Node, NodeData, NodeId, HTML_NAMESPACE};
+use crate::html5_parser::parser::document::Document;
+use crate::url::Url;
+use serde_derive::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Value of a property that refers back to an item that contains it
+const CYCLE_VALUE: &str = "ERROR";
+
+/// Prefixes from the RDFa initial context that are commonly used
+static RDFA_INITIAL_PREFIXES: [(&str, &str); 8] = [
+    ("dc", "http://purl.org/dc/terms/"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("og", "http://ogp.me/ns#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("schema", "http://schema.org/"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("xhv", "http://www.w3.org/1999/xhtml/vocab#"),
+];
+
+/// The items found in a document
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ItemGraph {
+    /// The top-level items in tree order
+    pub items: Vec<Item>,
+}
+
+/// A microdata or RDFa item
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Item {
+    /// The types of the item (from `itemtype` or `typeof`)
+    #[serde(rename = "type", skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<String>,
+    /// The global identifier of the item (from `itemid` or `resource`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The values of each property, in tree order
+    pub properties: BTreeMap<String, Vec<PropertyValue>>,
+}
+
+impl Item {
+    /// Adds a value to the property
+    pub fn add_property(&mut self, name: &str, value: PropertyValue) {
+        self.properties
+            .entry(name.to_string())
+            .or_default()
+            .push(value);
+    }
+
+    /// Returns the first value of the property
+    pub fn property(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties.get(name)?.first()
+    }
+}
+
+/// The value of a property: a string, or a nested item
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Text(String),
+    Item(Item),
+}
+
+impl PropertyValue {
+    /// Returns the string value, or None for nested items
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            PropertyValue::Text(value) => Some(value),
+            PropertyValue::Item(_) => None,
+        }
+    }
+
+    /// Returns the nested item, or None for string values
+    pub fn as_item(&self) -> Option<&Item> {
+        match self {
+            PropertyValue::Text(_) => None,
+            PropertyValue::Item(item) => Some(item),
+        }
+    }
+}
+
+/// Vocabulary and prefixes in scope while walking a tree for RDFa
+#[derive(Debug, Clone)]
+struct RdfaContext {
+    vocab: Option<String>,
+    prefixes: HashMap<String, String>,
+}
+
+impl Default for RdfaContext {
+    fn default() -> Self {
+        Self {
+            vocab: None,
+            prefixes: RDFA_INITIAL_PREFIXES
+                .iter()
+                .map(|(prefix, iri)| (prefix.to_string(), iri.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl RdfaContext {
+    /// Returns the context for the children of the element
+    fn enter(&self, node: &Node) -> Self {
+        let mut context = self.clone();
+        if let Some(vocab) = attribute(node, "vocab") {
+            let vocab = vocab.trim();
+            context.vocab = (!vocab.is_empty()).then(|| vocab.to_string());
+        }
+        if let Some(prefix) = attribute(node, "prefix") {
+            let tokens: Vec<&str> = prefix.split_ascii_whitespace().collect();
+            for pair in tokens.chunks(2) {
+                if let [prefix, iri] = pair {
+                    if let Some(prefix) = prefix.strip_suffix(':') {
+                        context
+                            .prefixes
+                            .insert(prefix.to_ascii_lowercase(), iri.to_string());
+                    }
+                }
+            }
+        }
+        context
+    }
+
+    /// Expands a term or compact URL into a URL. Terms are expanded with the vocabulary; when
+    /// there is no vocabulary they are kept as they are.
+    fn expand(&self, term: &str) -> String {
+        if let Some((prefix, reference)) = term.split_once(':') {
+            if let Some(iri) = self.prefixes.get(&prefix.to_ascii_lowercase()) {
+                return format!("{}{}", iri, reference);
+            }
+            return term.to_string();
+        }
+        match &self.vocab {
+            Some(vocab) => format!("{}{}", vocab, term),
+            None => term.to_string(),
+        }
+    }
+}
+
+/// Returns the value of the attribute of the element
+fn attribute<'a>(node: &'a Node, name: &str) -> Option<&'a str> {
+    match &node.data {
+        NodeData::Element(element) => element.attributes.get(name).map(|value| value.as_str()),
+        _ => None,
+    }
+}
+
+/// Returns the unique whitespace-separated tokens of the attribute
+fn tokens(node: &Node, name: &str) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    for token in attribute(node, name)
+        .unwrap_or_default()
+        .split_ascii_whitespace()
+    {
+        if !result.iter().any(|existing| existing == token) {
+            result.push(token.to_string());
+        }
+    }
+    result
+}
+
+fn is_html_element(node: &Node) -> bool {
+    matches!(node.data, NodeData::Element(_)) && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+}
+
+/// Microdata
+impl Document {
+    /// Returns all top-level microdata items of the document
+    pub fn microdata(&self) -> ItemGraph {
+        let items = self
+            .descendants(NodeId::root())
+            .into_iter()
+            .filter_map(|id| self.get_node_by_id(id))
+            .filter(|node| {
+                is_html_element(node)
+                    && attribute(node, "itemscope").is_some()
+                    && attribute(node, "itemprop").is_none()
+            })
+            .map(|node| self.microdata_item_with_memory(node.id, &mut vec![]))
+            .collect();
+        ItemGraph { items }
+    }
+
+    /// Returns the microdata item created by the element, or None when the element has no
+    /// itemscope attribute
+    pub fn microdata_item(&self, node_id: NodeId) -> Option<Item> {
+        let node = self.get_node_by_id(node_id)?;
+        if !is_html_element(node) || attribute(node, "itemscope").is_none() {
+            return None;
+        }
+        Some(self.microdata_item_with_memory(node_id, &mut vec![]))
+    }
+
+    /// Builds the item. The memory holds the items that are being built, to detect items that
+    /// (indirectly) contain themselves.
+    fn microdata_item_with_memory(&self, node_id: NodeId, memory: &mut Vec<NodeId>) -> Item {
+        let mut item = Item::default();
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return item;
+        };
+
+        item.types = tokens(node, "itemtype")
+            .into_iter()
+            .filter(|token| Url::parse(token).is_ok())
+            .collect();
+        if !item.types.is_empty() {
+            item.id = attribute(node, "itemid")
+                .and_then(|id| self.resolve_url(id))
+                .map(|url| url.href());
+        }
+
+        memory.push(node_id);
+        for property_id in self.item_properties(node_id) {
+            let Some(property) = self.get_node_by_id(property_id) else {
+                continue;
+            };
+
+            let value = if attribute(property, "itemscope").is_some() {
+                if memory.contains(&property_id) {
+                    PropertyValue::Text(CYCLE_VALUE.to_string())
+                } else {
+                    PropertyValue::Item(self.microdata_item_with_memory(property_id, memory))
+                }
+            } else {
+                PropertyValue::Text(self.microdata_property_value(property))
+            };
+
+            for name in tokens(property, "itemprop") {
+                item.add_property(&name, value.clone());
+            }
+        }
+        memory.pop();
+
+        item
+    }
+
+    /// Returns the elements that add properties to the item, in tree order
+    /// (<https://html.spec.whatwg.org/multipage/microdata.html#associating-names-with-items>)
+    fn item_properties(&self, root_id: NodeId) -> Vec<NodeId> {
+        let Some(root) = self.get_node_by_id(root_id) else {
+            return vec![];
+        };
+
+        let tree_order = self.descendants(self.tree_root_of(root_id));
+        let mut pending: Vec<NodeId> = root.children.clone();
+        for id in tokens(root, "itemref") {
+            let referenced = tree_order.iter().copied().find(|node_id| {
+                self.get_node_by_id(*node_id)
+                    .is_some_and(|node| attribute(node, "id") == Some(id.as_str()))
+            });
+            pending.extend(referenced);
+        }
+
+        let mut memory = vec![root_id];
+        let mut results = vec![];
+        while let Some(current_id) = pending.pop() {
+            if memory.contains(&current_id) {
+                continue;
+            }
+            memory.push(current_id);
+
+            let Some(current) = self.get_node_by_id(current_id) else {
+                continue;
+            };
+            if !is_html_element(current) {
+                continue;
+            }
+            if attribute(current, "itemscope").is_none() {
+                pending.extend(current.children.iter().copied());
+            }
+            if !tokens(current, "itemprop").is_empty() {
+                results.push(current_id);
+            }
+        }
+
+        results.sort_by_key(|id| tree_order.iter().position(|node_id| node_id == id));
+        results
+    }
+
+    /// Returns the value of a property element that is not an item
+    /// (<https://html.spec.whatwg.org/multipage/microdata.html#values>)
+    fn microdata_property_value(&self, node: &Node) -> String {
+        let url_attribute = match node.name.as_str() {
+            "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => Some("src"),
+            "a" | "area" | "link" => Some("href"),
+            "object" => Some("data"),
+            _ => None,
+        };
+        if let Some(url_attribute) = url_attribute {
+            return self
+                .resolve_attribute_url(node.id, url_attribute)
+                .map(|url| url.href())
+                .unwrap_or_default();
+        }
+
+        let value = match node.name.as_str() {
+            "meta" => attribute(node, "content"),
+            "data" | "meter" => attribute(node, "value"),
+            "time" => attribute(node, "datetime"),
+            _ => None,
+        };
+        match value {
+            Some(value) => value.to_string(),
+            None if node.name == "meta" || node.name == "data" || node.name == "meter" => {
+                String::new()
+            }
+            None => self.text_content(node.id),
+        }
+    }
+}
+
+/// RDFa Lite
+impl Document {
+    /// Returns all top-level RDFa Lite items of the document
+    pub fn rdfa(&self) -> ItemGraph {
+        let mut items = vec![];
+        self.collect_rdfa(NodeId::root(), &RdfaContext::default(), None, &mut items);
+        ItemGraph { items }
+    }
+
+    /// Walks the children of the node. Properties are added to the current item; items that are
+    /// not the value of a property are added to the top-level items.
+    fn collect_rdfa(
+        &self,
+        node_id: NodeId,
+        context: &RdfaContext,
+        mut current: Option<&mut Item>,
+        items: &mut Vec<Item>,
+    ) {
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return;
+        };
+
+        for child_id in &node.children {
+            let Some(child) = self.get_node_by_id(*child_id) else {
+                continue;
+            };
+            if !is_html_element(child) {
+                continue;
+            }
+
+            let context = context.enter(child);
+            let properties: Vec<String> = tokens(child, "property")
+                .iter()
+                .map(|term| context.expand(term))
+                .collect();
+
+            if attribute(child, "typeof").is_some() {
+                let mut item = Item {
+                    types: tokens(child, "typeof")
+                        .iter()
+                        .map(|term| context.expand(term))
+                        .collect(),
+                    id: attribute(child, "resource")
+                        .and_then(|resource| self.resolve_url(resource))
+                        .map(|url| url.href()),
+                    properties: BTreeMap::new(),
+                };
+                self.collect_rdfa(child.id, &context, Some(&mut item), items);
+
+                match current.as_deref_mut() {
+                    Some(parent) if !properties.is_empty() => {
+                        for name in &properties {
+                            parent.add_property(name, PropertyValue::Item(item.clone()));
+                        }
+                    }
+                    _ => items.push(item),
+                }
+                continue;
+            }
+
+            if let Some(parent) = current.as_deref_mut() {
+                if !properties.is_empty() {
+                    let value = self.rdfa_property_value(child);
+                    for name in &properties {
+                        parent.add_property(name, PropertyValue::Text(value.clone()));
+                    }
+                }
+            }
+            self.collect_rdfa(child.id, &context, current.as_deref_mut(), items);
+        }
+    }
+
+    /// Returns the value of a property element that is not an item: its content attribute, the
+    /// URL it refers to, or its text
+    fn rdfa_property_value(&self, node: &Node) -> String {
+        if let Some(content) = attribute(node, "content") {
+            return content.to_string();
+        }
+        for url_attribute in ["resource", "href", "src"] {
+            if attribute(node, url_attribute).is_some() {
+                return self
+                    .resolve_attribute_url(node.id, url_attribute)
+                    .map(|url| url.href())
+                    .unwrap_or_default();
+            }
+        }
+        if node.name == "time" {
+            if let Some(datetime) = attribute(node, "datetime") {
+                return datetime.to_string();
+            }
+        }
+        self.text_content(node.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_html_with_url;
+
+    const DOCUMENT_URL: &str = "https://example.com/dir/page.html";
+
+    fn text<'a>(item: &'a Item, name: &str) -> Option<&'a str> {
+        item.property(name)?.as_text()
+    }
+
+    #[test]
+    fn microdata_items() {
+        let document = parse_html_with_url(
+            "<div itemscope itemtype=\"https://schema.org/Person invalid\" itemid=\"/people/1\" itemref=\"extra\">\
+               <p>Name: <span itemprop=\"name given-name\">Alice</span></p>\
+               <img itemprop=\"image\" src=\"alice.jpg\">\
+               <a itemprop=\"url\" href=\"https://alice.example/\">site</a>\
+               <meta itemprop=\"age\" content=\"42\"><data itemprop=\"rank\" value=\"7\">seven</data>\
+               <time itemprop=\"birthDate\" datetime=\"1980-01-01\">Jan 1</time>\
+               <div itemprop=\"address\" itemscope><span itemprop=\"locality\">Amsterdam</span></div>\
+             </div>\
+             <p id=\"extra\"><span itemprop=\"nickname\">Al</span></p>\
+             <section itemscope><b itemprop=\"x\">untyped</b></section>",
+            DOCUMENT_URL,
+        );
+        let graph = document.get().microdata();
+
+        assert_eq!(graph.items.len(), 2);
+        let person = &graph.items[0];
+        assert_eq!(person.types, vec!["https://schema.org/Person"]);
+        assert_eq!(person.id.as_deref(), Some("https://example.com/people/1"));
+        assert_eq!(text(person, "name"), Some("Alice"));
+        assert_eq!(text(person, "given-name"), Some("Alice"));
+        assert_eq!(
+            text(person, "image"),
+            Some("https://example.com/dir/alice.jpg")
+        );
+        assert_eq!(text(person, "url"), Some("https://alice.example/"));
+        assert_eq!(text(person, "age"), Some("42"));
+        assert_eq!(text(person, "rank"), Some("7"));
+        assert_eq!(text(person, "birthDate"), Some("1980-01-01"));
+        assert_eq!(text(person, "nickname"), Some("Al"));
+        let address = person.property("address").and_then(|value| value.as_item());
+        assert_eq!(
+            address.and_then(|address| text(address, "locality")),
+            Some("Amsterdam")
+        );
+        assert_eq!(person.properties.len(), 9);
+
+        let untyped = &graph.items[1];
+        assert!(untyped.types.is_empty());
+        assert_eq!(text(untyped, "x"), Some("untyped"));
+    }
+
+    #[test]
+    fn microdata_cycles_and_json() {
+        let document = parse_html_with_url(
+            "<div itemscope><span itemprop=\"name\">A</span>\
+             <div id=\"c\" itemprop=\"child\" itemscope itemref=\"p\"></div></div>\
+             <div id=\"p\" itemprop=\"parent\" itemscope itemref=\"c\"></div>",
+            DOCUMENT_URL,
+        );
+        let graph = document.get().microdata();
+
+        assert_eq!(
+            serde_json::to_value(&graph).unwrap(),
+            serde_json::json!({
+                "items": [{
+                    "properties": {
+                        "child": [{
+                            "properties": {
+                                "parent": [{ "properties": { "child": ["ERROR"] } }]
+                            }
+                        }],
+                        "name": ["A"]
+                    }
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn rdfa_items() {
+        let document = parse_html_with_url(
+            "<div vocab=\"https://schema.org/\" typeof=\"Person\" resource=\"#me\">\
+               <span property=\"name\">Bob</span>\
+               <a property=\"url\" href=\"/bob\">home</a>\
+               <meta property=\"dc:created\" content=\"2020\">\
+               <div property=\"address\" typeof=\"PostalAddress\">\
+                 <span property=\"addressLocality\">Utrecht</span>\
+               </div>\
+               <p prefix=\"ex: https://example.org/ns#\"><span property=\"ex:hobby\">chess</span></p>\
+             </div>\
+             <span property=\"orphan\">ignored</span>",
+            DOCUMENT_URL,
+        );
+        let graph = document.get().rdfa();
+
+        assert_eq!(graph.items.len(), 1);
+        let person = &graph.items[0];
+        assert_eq!(person.types, vec!["https://schema.org/Person"]);
+        assert_eq!(
+            person.id.as_deref(),
+            Some("https://example.com/dir/page.html#me")
+        );
+        assert_eq!(text(person, "https://schema.org/name"), Some("Bob"));
+        assert_eq!(
+            text(person, "https://schema.org/url"),
+            Some("https://example.com/bob")
+        );
+        assert_eq!(
+            text(person, "http://purl.org/dc/terms/created"),
+            Some("2020")
+        );
+        assert_eq!(text(person, "https://example.org/ns#hobby"), Some("chess"));
+
+        let address = person
+            .property("https://schema.org/address")
+            .and_then(|value| value.as_item())
+            .expect("address");
+        assert_eq!(address.types, vec!["https://schema.org/PostalAddress"]);
+        assert_eq!(
+            text(address, "https://schema.org/addressLocality"),
+            Some("Utrecht")
+        );
+        assert_eq!(person.properties.len(), 5);
+    }
+}
@@ -0,0 +1,371 @@
+//! Mutation observers as described in <https://dom.spec.whatwg.org/#mutation-observers>
+//!
+//! Observers are registered on the document for a target node. Every change that is made through
+//! the DOM API of the document (appending/removing children, setting attributes and changing
+//! character data) is queued as a record for each interested observer. Since there is no event
+//! loop yet, the records are not delivered through a callback but must be fetched with
+//! `Document::take_mutation_records()`.
+use crate::html5_parser::node::NodeId;
+
+/// The kind of mutation that a record describes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MutationType {
+    ChildList,
+    Attributes,
+    CharacterData,
+}
+
+/// A single change to the DOM tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutationRecord {
+    /// Type of the mutation
+    pub kind: MutationType,
+    /// Node that was affected (the parent node for child list changes)
+    pub target: NodeId,
+    /// Nodes that were added (child list changes only)
+    pub added_nodes: Vec<NodeId>,
+    /// Nodes that were removed (child list changes only)
+    pub removed_nodes: Vec<NodeId>,
+    /// Previous sibling of the added or removed nodes
+    pub previous_sibling: Option<NodeId>,
+    /// Next sibling of the added or removed nodes
+    pub next_sibling: Option<NodeId>,
+    /// Local name of the changed attribute
+    pub attribute_name: Option<String>,
+    /// Namespace of the changed attribute
+    pub attribute_namespace: Option<String>,
+    /// Previous value of the attribute or character data (only when requested in the options)
+    pub old_value: Option<String>,
+}
+
+impl MutationRecord {
+    /// Creates a record for a child list change
+    pub fn child_list(
+        target: NodeId,
+        added_nodes: Vec<NodeId>,
+        removed_nodes: Vec<NodeId>,
+        previous_sibling: Option<NodeId>,
+        next_sibling: Option<NodeId>,
+    ) -> Self {
+        Self {
+            kind: MutationType::ChildList,
+            target,
+            added_nodes,
+            removed_nodes,
+            previous_sibling,
+            next_sibling,
+            attribute_name: None,
+            attribute_namespace: None,
+            old_value: None,
+        }
+    }
+
+    /// Creates a record for an attribute change
+    pub fn attributes(target: NodeId, name: &str, old_value: Option<String>) -> Self {
+        Self {
+            kind: MutationType::Attributes,
+            target,
+            added_nodes: vec![],
+            removed_nodes: vec![],
+            previous_sibling: None,
+            next_sibling: None,
+            attribute_name: Some(name.to_string()),
+            attribute_namespace: None,
+            old_value,
+        }
+    }
+
+    /// Creates a record for a character data (text or comment) change
+    pub fn character_data(target: NodeId, old_value: Option<String>) -> Self {
+        Self {
+            kind: MutationType::CharacterData,
+            target,
+            added_nodes: vec![],
+            removed_nodes: vec![],
+            previous_sibling: None,
+            next_sibling: None,
+            attribute_name: None,
+            attribute_namespace: None,
+            old_value,
+        }
+    }
+}
+
+/// Options that define which mutations an observer is interested in (MutationObserverInit)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MutationObserverInit {
+    /// Observe additions and removals of the target's children
+    pub child_list: bool,
+    /// Observe attribute changes
+    pub attributes: bool,
+    /// Observe character data changes
+    pub character_data: bool,
+    /// Also observe the descendants of the target
+    pub subtree: bool,
+    /// Record the previous value of changed attributes
+    pub attribute_old_value: bool,
+    /// Record the previous value of changed character data
+    pub character_data_old_value: bool,
+    /// Only observe the attributes with these names
+    pub attribute_filter: Option<Vec<String>>,
+}
+
+impl MutationObserverInit {
+    /// Applies the implied options from the spec: requesting old values or an attribute filter
+    /// implies observing attributes or character data. Returns None when the options would not
+    /// observe anything.
+    fn normalize(mut self) -> Option<Self> {
+        if self.attribute_old_value || self.attribute_filter.is_some() {
+            self.attributes = true;
+        }
+        if self.character_data_old_value {
+            self.character_data = true;
+        }
+
+        if !self.child_list && !self.attributes && !self.character_data {
+            return None;
+        }
+
+        Some(self)
+    }
+
+    /// Returns true when a mutation of the given type (and attribute name) matches these options
+    fn is_interested(&self, record: &MutationRecord, is_target: bool) -> bool {
+        if !is_target && !self.subtree {
+            return false;
+        }
+
+        match record.kind {
+            MutationType::ChildList => self.child_list,
+            MutationType::CharacterData => self.character_data,
+            MutationType::Attributes => {
+                if !self.attributes {
+                    return false;
+                }
+                match (&self.attribute_filter, &record.attribute_name) {
+                    (Some(filter), Some(name)) => {
+                        record.attribute_namespace.is_none() && filter.contains(name)
+                    }
+                    _ => true,
+                }
+            }
+        }
+    }
+
+    /// Returns true when the old value should be stored in the record
+    fn wants_old_value(&self, kind: MutationType) -> bool {
+        match kind {
+            MutationType::Attributes => self.attribute_old_value,
+            MutationType::CharacterData => self.character_data_old_value,
+            MutationType::ChildList => false,
+        }
+    }
+}
+
+/// Id used to identify a mutation observer
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MutationObserverId(usize);
+
+/// A single registered observer with its pending records
+#[derive(Debug, Clone, PartialEq)]
+struct Observer {
+    id: MutationObserverId,
+    /// Observed nodes with their options
+    registrations: Vec<(NodeId, MutationObserverInit)>,
+    /// Record queue that has not been taken yet
+    records: Vec<MutationRecord>,
+}
+
+/// List of all mutation observers of a document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MutationObservers {
+    observers: Vec<Observer>,
+    next_id: usize,
+}
+
+impl MutationObservers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new observer that does not observe anything yet
+    pub fn create(&mut self) -> MutationObserverId {
+        let id = MutationObserverId(self.next_id);
+        self.next_id += 1;
+        self.observers.push(Observer {
+            id,
+            registrations: vec![],
+            records: vec![],
+        });
+        id
+    }
+
+    /// Registers the observer for the target. Observing the same target again replaces the
+    /// options. Returns false when the observer does not exist or the options are invalid.
+    pub fn observe(
+        &mut self,
+        id: MutationObserverId,
+        target: NodeId,
+        options: MutationObserverInit,
+    ) -> bool {
+        let Some(options) = options.normalize() else {
+            return false;
+        };
+        let Some(observer) = self.observers.iter_mut().find(|o| o.id == id) else {
+            return false;
+        };
+
+        match observer
+            .registrations
+            .iter_mut()
+            .find(|(t, _)| *t == target)
+        {
+            Some(registration) => registration.1 = options,
+            None => observer.registrations.push((target, options)),
+        }
+        true
+    }
+
+    /// Stops the observer from observing any node. Pending records are dropped.
+    pub fn disconnect(&mut self, id: MutationObserverId) {
+        if let Some(observer) = self.observers.iter_mut().find(|o| o.id == id) {
+            observer.registrations.clear();
+            observer.records.clear();
+        }
+    }
+
+    /// Returns and empties the record queue of the observer
+    pub fn take_records(&mut self, id: MutationObserverId) -> Vec<MutationRecord> {
+        self.observers
+            .iter_mut()
+            .find(|o| o.id == id)
+            .map(|o| std::mem::take(&mut o.records))
+            .unwrap_or_default()
+    }
+
+    /// Returns true when there is at least one observer registered on any node
+    pub fn has_registrations(&self) -> bool {
+        self.observers.iter().any(|o| !o.registrations.is_empty())
+    }
+
+    /// Queues the record for every interested observer. The given nodes are the inclusive
+    /// ancestors of the record's target, starting with the target itself.
+    /// <https://dom.spec.whatwg.org/#queue-a-mutation-record>
+    pub fn queue(&mut self, inclusive_ancestors: &[NodeId], record: MutationRecord) {
+        for observer in self.observers.iter_mut() {
+            let mut interested = false;
+            let mut want_old_value = false;
+
+            for (target, options) in observer.registrations.iter() {
+                let Some(pos) = inclusive_ancestors.iter().position(|id| id == target) else {
+                    continue;
+                };
+                if !options.is_interested(&record, pos == 0) {
+                    continue;
+                }
+                interested = true;
+                want_old_value |= options.wants_old_value(record.kind);
+            }
+
+            if !interested {
+                continue;
+            }
+
+            let mut record = record.clone();
+            if !want_old_value {
+                record.old_value = None;
+            }
+            observer.records.push(record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute_options() -> MutationObserverInit {
+        MutationObserverInit {
+            attributes: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn observe_requires_something_to_observe() {
+        let mut observers = MutationObservers::new();
+        let id = observers.create();
+        assert!(!observers.observe(id, NodeId::root(), MutationObserverInit::default()));
+        assert!(!observers.has_registrations());
+
+        // old value implies observing attributes
+        let options = MutationObserverInit {
+            attribute_old_value: true,
+            ..Default::default()
+        };
+        assert!(observers.observe(id, NodeId::root(), options));
+        assert!(observers.has_registrations());
+    }
+
+    #[test]
+    fn queue_respects_subtree() {
+        let mut observers = MutationObservers::new();
+        let id = observers.create();
+        observers.observe(id, NodeId::from(1), attribute_options());
+
+        // Target is a descendant, and subtree is not set
+        let record = MutationRecord::attributes(NodeId::from(2), "class", None);
+        observers.queue(&[NodeId::from(2), NodeId::from(1)], record.clone());
+        assert!(observers.take_records(id).is_empty());
+
+        let options = MutationObserverInit {
+            subtree: true,
+            ..attribute_options()
+        };
+        observers.observe(id, NodeId::from(1), options);
+        observers.queue(&[NodeId::from(2), NodeId::from(1)], record.clone());
+        assert_eq!(observers.take_records(id), vec![record]);
+        assert!(observers.take_records(id).is_empty());
+    }
+
+    #[test]
+    fn queue_attribute_filter_and_old_value() {
+        let mut observers = MutationObservers::new();
+        let id = observers.create();
+        let options = MutationObserverInit {
+            attribute_filter: Some(vec!["class".to_string()]),
+            ..Default::default()
+        };
+        observers.observe(id, NodeId::from(1), options);
+
+        let target = [NodeId::from(1)];
+        observers.queue(
+            &target,
+            MutationRecord::attributes(NodeId::from(1), "id", Some("old".into())),
+        );
+        observers.queue(
+            &target,
+            MutationRecord::attributes(NodeId::from(1), "class", Some("old".into())),
+        );
+
+        let records = observers.take_records(id);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].attribute_name.as_deref(), Some("class"));
+        // old value was not requested
+        assert_eq!(records[0].old_value, None);
+    }
+
+    #[test]
+    fn disconnect_drops_records() {
+        let mut observers = MutationObservers::new();
+        let id = observers.create();
+        observers.observe(id, NodeId::from(1), attribute_options());
+        observers.queue(
+            &[NodeId::from(1)],
+            MutationRecord::attributes(NodeId::from(1), "id", None),
+        );
+        observers.disconnect(id);
+        assert!(observers.take_records(id).is_empty());
+        assert!(!observers.has_registrations());
+    }
+}
@@ -1,4 +1,5 @@
 use super::parser::document::{Document, DocumentHandle};
+use crate::html5_parser::dom::ShadowRoot;
 use crate::html5_parser::node::data::comment::CommentData;
 use crate::html5_parser::node::data::document::DocumentData;
 use crate::html5_parser::node::data::element::ElementData;
@@ -13,6 +14,9 @@ pub const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
 pub const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
 pub const XMLNS_NAMESPACE: &str = "http://www.w3.org/2000/xmlns/";
 
+/// Doctypes are stored as elements with this prefix in front of the doctype name
+pub(crate) const DOCTYPE_PREFIX: &str = "!DOCTYPE";
+
 pub mod arena;
 pub mod data;
 
@@ -23,6 +27,7 @@ pub enum NodeType {
     Text,
     Comment,
     Element,
+    ShadowRoot,
 }
 
 /// Different type of node data
@@ -32,6 +37,7 @@ pub enum NodeData {
     Text(TextData),
     Comment(CommentData),
     Element(Box<ElementData>),
+    ShadowRoot(Box<ShadowRoot>),
 }
 
 /// Id used to identify a node
@@ -198,6 +204,25 @@ impl Node {
         }
     }
 
+    /// Create a new shadow root node. The host is stored in the shadow root data, not as parent.
+    pub fn new_shadow_root(document: &DocumentHandle, shadow_root: ShadowRoot) -> Self {
+        Node {
+            id: Default::default(),
+            named_id: None,
+            parent: None,
+            children: vec![],
+            data: NodeData::ShadowRoot(Box::new(shadow_root)),
+            name: "".to_string(),
+            namespace: None,
+            document: Document::clone(document),
+        }
+    }
+
+    /// Returns true if the node is a doctype
+    pub fn is_doctype(&self) -> bool {
+        matches!(self.data, NodeData::Element(_)) && self.name.starts_with(DOCTYPE_PREFIX)
+    }
+
     /// Returns true if the given node is a "formatting" node
     pub fn is_formatting(&self) -> bool {
         self.namespace == Some(HTML_NAMESPACE.into())
@@ -272,6 +297,7 @@ impl NodeTrait for Node {
             NodeData::Text { .. } => NodeType::Text,
             NodeData::Comment { .. } => NodeType::Comment,
             NodeData::Element { .. } => NodeType::Element,
+            NodeData::ShadowRoot { .. } => NodeType::ShadowRoot,
         }
     }
 }
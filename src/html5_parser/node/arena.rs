@@ -77,8 +77,13 @@ impl NodeArena {
         true
     }
 
+    /// Returns the ids of all nodes in the arena, in the order they were added
+    pub fn node_ids(&self) -> Vec<NodeId> {
+        self.order.clone()
+    }
+
     /// Removes the node with the given id from the arena
-    fn detach_node(&mut self, node_id: NodeId) {
+    pub(crate) fn detach_node(&mut self, node_id: NodeId) {
         // Remove children
         if let Some(node) = self.nodes.get_mut(&node_id) {
             for child_id in node.children.clone() {
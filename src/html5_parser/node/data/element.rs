@@ -108,6 +108,12 @@ pub struct ElementData {
     pub(crate) force_async: bool,
     // Template contents (when it's a template element)
     pub(crate) template_contents: Option<DocumentFragment>,
+    /// Shadow root attached to this element (when it's a shadow host)
+    pub(crate) shadow_root: Option<NodeId>,
+    /// Nodes assigned with `assign()` (only used for <slot> elements in manual assignment mode)
+    pub(crate) manually_assigned_nodes: Vec<NodeId>,
+    /// Form element the parser associated this element with (only for form-associated elements)
+    pub(crate) form_owner: Option<NodeId>,
     /// Pointer to the document the node associated with this data is tied to
     pub(crate) document: DocumentHandle,
 }
@@ -121,6 +127,9 @@ impl ElementData {
             classes: ElementClass::new(),
             force_async: false,
             template_contents: None,
+            shadow_root: None,
+            manually_assigned_nodes: vec![],
+            form_owner: None,
             document,
         }
     }
@@ -142,6 +151,9 @@ impl ElementData {
             classes: ElementClass::new(),
             force_async: false,
             template_contents: None,
+            shadow_root: None,
+            manually_assigned_nodes: vec![],
+            form_owner: None,
             document,
         }
     }
@@ -1,20 +1,22 @@
-mod attr_replacements;
+pub(crate) mod attr_replacements;
 pub mod document;
-mod quirks;
+pub mod quirks;
 
 // ------------------------------------------------------------
 
 use self::document::DocumentHandle;
 
 use super::node::NodeId;
+use crate::html5_parser::dom::{ShadowRootInit, ShadowRootMode};
 use crate::html5_parser::element_class::ElementClass;
 use crate::html5_parser::error_logger::{ErrorLogger, ParseError, ParserError};
+use crate::html5_parser::forms;
 use crate::html5_parser::input_stream::InputStream;
 use crate::html5_parser::node::data::text::TextData;
 use crate::html5_parser::node::{Node, NodeData, HTML_NAMESPACE, MATHML_NAMESPACE, SVG_NAMESPACE};
 use crate::html5_parser::parser::adoption_agency::AdoptionResult;
 use crate::html5_parser::parser::attr_replacements::{
-    MATHML_ADJUSTMENTS, SVG_ADJUSTMENTS, XML_ADJUSTMENTS,
+    MATHML_ADJUSTMENTS, SVG_ADJUSTMENTS, SVG_TAG_ADJUSTMENTS, XML_ADJUSTMENTS,
 };
 use crate::html5_parser::parser::document::{Document, DocumentFragment, DocumentType};
 use crate::html5_parser::parser::quirks::QuirksMode;
@@ -56,6 +58,84 @@ enum InsertionMode {
     AfterAfterFrameset,
 }
 
+/// HTML start tags that break out of foreign content
+static BREAKOUT_ELEMENTS: [&str; 44] = [
+    "b",
+    "big",
+    "blockquote",
+    "body",
+    "br",
+    "center",
+    "code",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "listing",
+    "menu",
+    "meta",
+    "nobr",
+    "ol",
+    "p",
+    "pre",
+    "ruby",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "strike",
+    "sub",
+    "sup",
+    "table",
+    "tt",
+    "u",
+    "ul",
+    "var",
+];
+
+/// Returns true when the node is a MathML text integration point (mi, mo, mn, ms or mtext)
+pub(crate) fn is_mathml_text_integration_point(node: &Node) -> bool {
+    node.namespace.as_deref() == Some(MATHML_NAMESPACE)
+        && ["mi", "mo", "mn", "ms", "mtext"].contains(&node.name.as_str())
+}
+
+/// Returns true when the node is an HTML template element. Foreign content can contain elements
+/// named template, which must not be mistaken for one.
+pub(crate) fn is_html_template(node: &Node) -> bool {
+    node.name == "template" && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+}
+
+/// Returns true when the node is an HTML integration point: an annotation-xml element with an
+/// HTML encoding, or an SVG foreignObject, desc or title element
+pub(crate) fn is_html_integration_point(node: &Node) -> bool {
+    match node.namespace.as_deref() {
+        Some(MATHML_NAMESPACE) if node.name == "annotation-xml" => {
+            let NodeData::Element(element) = &node.data else {
+                return false;
+            };
+            element.attributes.get("encoding").is_some_and(|encoding| {
+                encoding.eq_ignore_ascii_case("text/html")
+                    || encoding.eq_ignore_ascii_case("application/xhtml+xml")
+            })
+        }
+        Some(SVG_NAMESPACE) => ["foreignObject", "desc", "title"].contains(&node.name.as_str()),
+        _ => false,
+    }
+}
+
 /// Additional extensions to the Vec type so we can do some stack operations
 trait VecExtensions<T> {
     fn pop_until<F>(&mut self, f: F)
@@ -204,6 +284,10 @@ pub struct Html5Parser<'stream> {
     active_formatting_elements: Vec<ActiveElement>,
     /// Is the current parsing a fragment case
     is_fragment_case: bool,
+    /// Context element when parsing a fragment
+    context_element: Option<Node>,
+    /// Open declarative shadow root templates, mapped onto the shadow root their contents go into
+    declarative_shadow_roots: HashMap<NodeId, NodeId>,
     /// A reference to the document we are parsing
     document: DocumentHandle,
     /// Error logger, which is shared with the tokenizer
@@ -252,6 +336,8 @@ impl<'stream> Html5Parser<'stream> {
             ack_self_closing: false,
             active_formatting_elements: vec![],
             is_fragment_case: false,
+            context_element: None,
+            declarative_shadow_roots: HashMap::new(),
             document,
             error_logger,
         }
@@ -264,6 +350,58 @@ impl<'stream> Html5Parser<'stream> {
         let root = Document::clone(&self.document);
         self.document.get_mut().create_root(&root);
 
+        self.run()
+    }
+
+    /// Parses the input stream as a fragment in the context of an (HTML) element with the given
+    /// name, like setting innerHTML on that element
+    /// (<https://html.spec.whatwg.org/multipage/parsing.html#parsing-html-fragments>). The parsed
+    /// nodes end up as the children of the html element of the document, which is returned.
+    pub fn parse_fragment(
+        &mut self,
+        document: DocumentHandle,
+        context: &str,
+    ) -> Result<(NodeId, Vec<ParseError>)> {
+        self.document = document;
+        let root = Document::clone(&self.document);
+        self.document.get_mut().create_root(&root);
+
+        self.is_fragment_case = true;
+        self.context_element = Some(Node::new_element(
+            &self.document,
+            context,
+            HashMap::new(),
+            HTML_NAMESPACE,
+        ));
+
+        self.tokenizer.state = match context {
+            "title" | "textarea" => State::RcDataState,
+            "style" | "xmp" | "iframe" | "noembed" | "noframes" => State::RawTextState,
+            "noscript" if self.scripting_enabled => State::RawTextState,
+            "script" => State::ScriptDataState,
+            "plaintext" => State::PlaintextState,
+            _ => State::DataState,
+        };
+        self.tokenizer.last_start_token = context.to_string();
+
+        let html_token = Token::StartTagToken {
+            name: "html".to_string(),
+            is_self_closing: false,
+            attributes: HashMap::new(),
+        };
+        let html_id = self.insert_html_element(&html_token);
+
+        if context == "template" {
+            self.template_insertion_mode.push(InsertionMode::InTemplate);
+        }
+        self.reset_insertion_mode();
+
+        let errors = self.run()?;
+        Ok((html_id, errors))
+    }
+
+    /// Runs the tree construction stage until the end of the token stream
+    fn run(&mut self) -> Result<Vec<ParseError>> {
         loop {
             // If reprocess_token is true, we should process the same token again
             if !self.reprocess_token {
@@ -278,6 +416,12 @@ impl<'stream> Html5Parser<'stream> {
 
             // println!("Token: {}", self.current_token);
 
+            // Tokens in SVG and MathML content are handled by the rules for foreign content,
+            // unless these hand the token back to the current insertion mode
+            if self.use_foreign_content_rules() && self.handle_in_foreign_content() {
+                continue;
+            }
+
             match self.insertion_mode {
                 // Checked: 1
                 InsertionMode::Initial => {
@@ -413,7 +557,7 @@ impl<'stream> Html5Parser<'stream> {
                         }
                         Token::CommentToken { .. } => {
                             let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-                            let parent_id = current_node!(self).id;
+                            let parent_id = self.adjusted_insert_location(None);
                             self.document.get_mut().add_node(node, parent_id);
                         }
                         Token::DocTypeToken { .. } => {
@@ -535,7 +679,7 @@ impl<'stream> Html5Parser<'stream> {
                         }
                         Token::CommentToken { .. } => {
                             let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-                            let parent_id = current_node!(self).id;
+                            let parent_id = self.adjusted_insert_location(None);
                             self.document.get_mut().add_node(node, parent_id);
                         }
                         Token::DocTypeToken { .. } => {
@@ -641,6 +785,8 @@ impl<'stream> Html5Parser<'stream> {
                         }
                         Token::EndTagToken { name, .. } if name == "script" => {
                             // @TODO: do script stuff!!!!
+                            self.open_elements.pop();
+                            self.insertion_mode = self.original_insertion_mode;
                         }
                         _ => {
                             self.open_elements.pop();
@@ -694,7 +840,7 @@ impl<'stream> Html5Parser<'stream> {
                                     &Token::TextToken { value: tokens },
                                     HTML_NAMESPACE,
                                 );
-                                let parent_id = current_node!(self).id;
+                                let parent_id = self.adjusted_insert_location(None);
                                 self.document.get_mut().add_node(node, parent_id);
                             }
 
@@ -778,7 +924,7 @@ impl<'stream> Html5Parser<'stream> {
                         }
                         Token::CommentToken { .. } => {
                             let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-                            let parent_id = current_node!(self).id;
+                            let parent_id = self.adjusted_insert_location(None);
                             self.document.get_mut().add_node(node, parent_id);
                         }
                         Token::DocTypeToken { .. } => {
@@ -1056,7 +1202,7 @@ impl<'stream> Html5Parser<'stream> {
                 // Checked: 1
                 InsertionMode::InCell => {
                     match &self.current_token {
-                        Token::StartTagToken { name, .. } if name == "th" || name == "td" => {
+                        Token::EndTagToken { name, .. } if name == "th" || name == "td" => {
                             let token_name = name.clone();
 
                             if !self.is_in_scope(name.as_str(), Scope::Table) {
@@ -1393,7 +1539,7 @@ impl<'stream> Html5Parser<'stream> {
                             continue;
                         }
                         Token::EofToken => {
-                            if !self.open_elements_has("template") {
+                            if !self.open_elements_has_template() {
                                 self.stop_parsing();
                                 continue;
                             }
@@ -1610,6 +1756,32 @@ impl<'stream> Html5Parser<'stream> {
         }
     }
 
+    /// Closes an open list item with one of the given names before a new one is inserted (the
+    /// steps for li, dd and dt start tags in the "in body" insertion mode)
+    fn close_list_item(&mut self, names: &[&str]) {
+        self.frameset_ok = false;
+
+        for idx in (0..self.open_elements.len()).rev() {
+            let node = open_elements_get!(self, idx);
+            if names.contains(&node.name.as_str()) {
+                self.generate_all_implied_end_tags(Some(&node.name), false);
+                if current_node!(self).name != node.name {
+                    self.parse_error("list item end tag not at top of stack");
+                }
+                self.pop_until(&node.name);
+                break;
+            }
+
+            if node.is_special() && !["address", "div", "p"].contains(&node.name.as_str()) {
+                break;
+            }
+        }
+
+        if self.is_in_scope("p", Scope::Button) {
+            self.close_p_element();
+        }
+    }
+
     /// Pops the last element from the open elements until we reach $name
     fn pop_until(&mut self, name: &str) {
         loop {
@@ -1685,6 +1857,13 @@ impl<'stream> Html5Parser<'stream> {
         })
     }
 
+    /// Returns true when the open elements has an HTML template element
+    fn open_elements_has_template(&self) -> bool {
+        self.open_elements
+            .iter()
+            .any(|node_id| is_html_template(&get_node_by_id!(self, *node_id)))
+    }
+
     fn open_elements_has_id(&self, id: NodeId) -> bool {
         self.open_elements
             .iter()
@@ -1716,11 +1895,16 @@ impl<'stream> Html5Parser<'stream> {
                     // sys_identifier.as_deref().unwrap_or(""),
                 );
 
-                return Node::new_element(&self.document, val.as_str(), HashMap::new(), namespace);
+                Node::new_element(&self.document, val.as_str(), HashMap::new(), namespace)
             }
             Token::StartTagToken {
                 name, attributes, ..
-            } => Node::new_element(&self.document, name, attributes.clone(), namespace),
+            } => {
+                let mut node =
+                    Node::new_element(&self.document, name, attributes.clone(), namespace);
+                self.associate_form_owner(&mut node);
+                node
+            }
             Token::EndTagToken { name, .. } => {
                 Node::new_element(&self.document, name, HashMap::new(), namespace)
             }
@@ -1734,6 +1918,26 @@ impl<'stream> Html5Parser<'stream> {
         }
     }
 
+    /// Associates a form-associated element with the form element pointer
+    /// https://html.spec.whatwg.org/multipage/parsing.html#create-an-element-for-the-token
+    fn associate_form_owner(&self, node: &mut Node) {
+        let Some(form_id) = self.form_element else {
+            return;
+        };
+        if node.namespace.as_deref() != Some(HTML_NAMESPACE)
+            || !forms::is_form_associated(&node.name)
+            || self.open_elements_has_template()
+        {
+            return;
+        }
+
+        if let NodeData::Element(element) = &mut node.data {
+            if !forms::is_listed(&element.name) || !element.attributes.contains("form") {
+                element.form_owner = Some(form_id);
+            }
+        }
+    }
+
     fn flush_pending_table_character_tokens(&mut self) {}
 
     /// This function will pop elements off the stack until it reaches the first element that matches
@@ -1774,13 +1978,12 @@ impl<'stream> Html5Parser<'stream> {
         let mut idx = self.open_elements.len() - 1;
 
         loop {
-            let node = open_elements_get!(self, idx);
+            let mut node = open_elements_get!(self, idx);
             if idx == 0 {
                 last = true;
-                // @TODO:
-                // if fragment_case {
-                //   node = context element !???
-                // }
+                if let Some(context) = &self.context_element {
+                    node = context.clone();
+                }
             }
 
             if node.name == "select" {
@@ -1799,7 +2002,7 @@ impl<'stream> Html5Parser<'stream> {
                     ancestor_idx -= 1;
                     let ancestor = open_elements_get!(self, ancestor_idx);
 
-                    if ancestor.name == "template" {
+                    if is_html_template(&ancestor) {
                         self.insertion_mode = InsertionMode::InSelect;
                         return;
                     }
@@ -1838,7 +2041,7 @@ impl<'stream> Html5Parser<'stream> {
                 self.insertion_mode = InsertionMode::InTable;
                 return;
             }
-            if node.name == "template" {
+            if is_html_template(&node) {
                 self.insertion_mode = *self.template_insertion_mode.last().unwrap();
                 return;
             }
@@ -2012,7 +2215,7 @@ impl<'stream> Html5Parser<'stream> {
             } if name == "html" => {
                 self.parse_error("html tag not allowed in in body insertion mode");
 
-                if self.open_elements_has("template") {
+                if self.open_elements_has_template() {
                     // ignore token
                     return;
                 }
@@ -2053,7 +2256,7 @@ impl<'stream> Html5Parser<'stream> {
                     return;
                 }
 
-                if self.open_elements_has("template") {
+                if self.open_elements_has_template() {
                     // ignore token
                     return;
                 }
@@ -2184,7 +2387,7 @@ impl<'stream> Html5Parser<'stream> {
             }
             Token::StartTagToken { name, .. } if name == "form" => {
                 {
-                    if self.form_element.is_some() && !self.open_elements_has("template") {
+                    if self.form_element.is_some() && !self.open_elements_has_template() {
                         self.parse_error("error with template, form shzzl");
                         // ignore token
                     }
@@ -2195,12 +2398,18 @@ impl<'stream> Html5Parser<'stream> {
                 }
 
                 let node_id = self.insert_html_element(&self.current_token.clone());
-                if !self.open_elements_has("template") {
+                if !self.open_elements_has_template() {
                     self.form_element = Some(node_id);
                 }
             }
-            Token::StartTagToken { name, .. } if name == "li" => {}
-            Token::StartTagToken { name, .. } if name == "dd" || name == "dt" => {}
+            Token::StartTagToken { name, .. } if name == "li" => {
+                self.close_list_item(&["li"]);
+                self.insert_html_element(&self.current_token.clone());
+            }
+            Token::StartTagToken { name, .. } if name == "dd" || name == "dt" => {
+                self.close_list_item(&["dd", "dt"]);
+                self.insert_html_element(&self.current_token.clone());
+            }
             Token::StartTagToken { name, .. } if name == "plaintext" => {
                 if self.is_in_scope("p", Scope::Button) {
                     self.close_p_element();
@@ -2265,7 +2474,7 @@ impl<'stream> Html5Parser<'stream> {
                 self.pop_until(name);
             }
             Token::EndTagToken { name, .. } if name == "form" => {
-                if !self.open_elements_has("template") {
+                if !self.open_elements_has_template() {
                     let node_id = self.form_element;
                     self.form_element = None;
 
@@ -2286,6 +2495,8 @@ impl<'stream> Html5Parser<'stream> {
                     if node_id != cn.id {
                         self.parse_error("end tag not at top of stack");
                     }
+
+                    self.open_elements_remove(node_id);
                 } else {
                     if !self.is_in_scope(name, Scope::Regular) {
                         self.parse_error("end tag not in scope");
@@ -2356,11 +2567,11 @@ impl<'stream> Html5Parser<'stream> {
                     || name == "h6" =>
             {
                 if !self.is_in_scope("h1", Scope::Regular)
-                    || !self.is_in_scope("h2", Scope::Regular)
-                    || !self.is_in_scope("h3", Scope::Regular)
-                    || !self.is_in_scope("h4", Scope::Regular)
-                    || !self.is_in_scope("h5", Scope::Regular)
-                    || !self.is_in_scope("h6", Scope::Regular)
+                    && !self.is_in_scope("h2", Scope::Regular)
+                    && !self.is_in_scope("h3", Scope::Regular)
+                    && !self.is_in_scope("h4", Scope::Regular)
+                    && !self.is_in_scope("h5", Scope::Regular)
+                    && !self.is_in_scope("h6", Scope::Regular)
                 {
                     self.parse_error("end tag not in scope");
                     // ignore token
@@ -2553,8 +2764,7 @@ impl<'stream> Html5Parser<'stream> {
             } if name == "input" => {
                 self.reconstruct_formatting();
 
-                let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-                self.add_node(node);
+                self.insert_html_element(&self.current_token.clone());
                 self.open_elements.pop();
 
                 self.acknowledge_closing_tag(*is_self_closing);
@@ -2606,15 +2816,10 @@ impl<'stream> Html5Parser<'stream> {
                 self.reprocess_token = true;
             }
             Token::StartTagToken { name, .. } if name == "textarea" => {
-                let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-                self.add_node(node);
-                self.open_elements.pop();
-
                 // @TODO: if next token == LF, ignore and move on to the next one
 
-                self.tokenizer.state = State::RcDataState;
                 self.frameset_ok = false;
-                self.insertion_mode = InsertionMode::Text;
+                self.parse_rcdata();
             }
             Token::StartTagToken { name, .. } if name == "xmp" => {
                 if self.is_in_scope("p", Scope::Button) {
@@ -2639,9 +2844,7 @@ impl<'stream> Html5Parser<'stream> {
             Token::StartTagToken { name, .. } if name == "select" => {
                 self.reconstruct_formatting();
 
-                let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-                self.add_node(node);
-                self.open_elements.pop();
+                self.insert_html_element(&self.current_token.clone());
 
                 self.frameset_ok = false;
 
@@ -2663,8 +2866,7 @@ impl<'stream> Html5Parser<'stream> {
 
                 self.reconstruct_formatting();
 
-                let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-                self.add_node(node);
+                self.insert_html_element(&self.current_token.clone());
             }
             Token::StartTagToken { name, .. } if name == "rb" || name == "rtc" => {
                 if self.is_in_scope("ruby", Scope::Regular) {
@@ -2801,10 +3003,195 @@ impl<'stream> Html5Parser<'stream> {
     }
 
     fn add_node(&mut self, node: Node) {
-        let node_id = current_node!(self).id;
+        let node_id = self.adjusted_insert_location(None);
         self.document.get_mut().add_node(node, node_id);
     }
 
+    /// Returns the adjusted current node, which is the context element when parsing a fragment
+    /// and only the html element is open
+    fn adjusted_current_node(&self) -> Option<Node> {
+        if self.open_elements.len() == 1 {
+            if let Some(context) = &self.context_element {
+                return Some(context.clone());
+            }
+        }
+        let node_id = self.open_elements.last()?;
+        self.document.get().get_node_by_id(*node_id).cloned()
+    }
+
+    /// Returns true when the current token must be processed with the rules for foreign content
+    /// (the tree construction dispatcher)
+    fn use_foreign_content_rules(&self) -> bool {
+        let Some(node) = self.adjusted_current_node() else {
+            return false;
+        };
+        if node.namespace.as_deref() == Some(HTML_NAMESPACE) {
+            return false;
+        }
+
+        match &self.current_token {
+            Token::EofToken => false,
+            Token::StartTagToken { name, .. }
+                if is_mathml_text_integration_point(&node)
+                    && name != "mglyph"
+                    && name != "malignmark" =>
+            {
+                false
+            }
+            Token::TextToken { .. } if is_mathml_text_integration_point(&node) => false,
+            Token::StartTagToken { name, .. }
+                if name == "svg"
+                    && node.name == "annotation-xml"
+                    && node.namespace.as_deref() == Some(MATHML_NAMESPACE) =>
+            {
+                false
+            }
+            Token::StartTagToken { .. } | Token::TextToken { .. }
+                if is_html_integration_point(&node) =>
+            {
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Handles the current token with the rules for parsing tokens in foreign content. Returns
+    /// false when the token must be processed by the current insertion mode instead.
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inforeign
+    fn handle_in_foreign_content(&mut self) -> bool {
+        match self.current_token.clone() {
+            Token::TextToken { value } => {
+                if value.contains(CHAR_NUL) {
+                    self.parse_error("null character not allowed in foreign content");
+                }
+                if value
+                    .chars()
+                    .any(|c| !c.is_ascii_whitespace() && c != CHAR_NUL)
+                {
+                    self.frameset_ok = false;
+                }
+                self.current_token = Token::TextToken {
+                    value: value.replace(CHAR_NUL, "\u{FFFD}"),
+                };
+                self.create_or_merge_text(self.current_token.clone());
+            }
+            Token::CommentToken { .. } => {
+                let node = self.create_node(&self.current_token, HTML_NAMESPACE);
+                self.add_node(node);
+            }
+            Token::DocTypeToken { .. } => {
+                self.parse_error("doctype not allowed in foreign content");
+                // ignore token
+            }
+            Token::StartTagToken {
+                ref name,
+                ref attributes,
+                ..
+            } if BREAKOUT_ELEMENTS.contains(&name.as_str())
+                || (name == "font"
+                    && ["color", "face", "size"]
+                        .iter()
+                        .any(|attr| attributes.contains_key(*attr))) =>
+            {
+                self.parse_error("html start tag not allowed in foreign content");
+                self.pop_until_html_or_integration_point();
+                self.reprocess_token = true;
+            }
+            Token::EndTagToken { ref name, .. } if name == "br" || name == "p" => {
+                self.parse_error("html end tag not allowed in foreign content");
+                self.pop_until_html_or_integration_point();
+                self.reprocess_token = true;
+            }
+            Token::StartTagToken {
+                name,
+                attributes,
+                is_self_closing,
+            } => {
+                let Some(adjusted_node) = self.adjusted_current_node() else {
+                    return false;
+                };
+                let namespace = adjusted_node
+                    .namespace
+                    .clone()
+                    .unwrap_or(HTML_NAMESPACE.to_string());
+
+                let mut token = Token::StartTagToken {
+                    name: name.clone(),
+                    attributes,
+                    is_self_closing,
+                };
+                if namespace == MATHML_NAMESPACE {
+                    self.adjust_mathml_attributes(&mut token);
+                }
+                if namespace == SVG_NAMESPACE {
+                    if let Token::StartTagToken { name, .. } = &mut token {
+                        if let Some(adjusted_name) = SVG_TAG_ADJUSTMENTS.get(name.as_str()) {
+                            *name = adjusted_name.to_string();
+                        }
+                    }
+                    self.adjust_svg_attributes(&mut token);
+                }
+                self.adjust_foreign_attributes(&mut token);
+
+                self.insert_foreign_element(&token, Some(&namespace));
+
+                if is_self_closing {
+                    self.open_elements.pop();
+                    self.acknowledge_closing_tag(is_self_closing);
+                }
+            }
+            Token::EndTagToken { ref name, .. }
+                if name == "script"
+                    && current_node!(self).name == "script"
+                    && current_node!(self).namespace.as_deref() == Some(SVG_NAMESPACE) =>
+            {
+                self.open_elements.pop();
+            }
+            Token::EndTagToken { ref name, .. } => {
+                let mut idx = self.open_elements.len() - 1;
+                if open_elements_get!(self, idx).name.to_ascii_lowercase() != *name {
+                    self.parse_error("end tag does not match current node in foreign content");
+                }
+
+                loop {
+                    if idx == 0 {
+                        // fragment case
+                        return true;
+                    }
+
+                    let node = open_elements_get!(self, idx);
+                    if node.name.to_ascii_lowercase() == *name {
+                        self.open_elements.truncate(idx);
+                        return true;
+                    }
+
+                    idx -= 1;
+                    if open_elements_get!(self, idx).namespace.as_deref() == Some(HTML_NAMESPACE) {
+                        return false;
+                    }
+                }
+            }
+            Token::EofToken => return false,
+        }
+
+        true
+    }
+
+    /// Pops elements until the current node is an HTML element, a MathML text integration point
+    /// or an HTML integration point
+    fn pop_until_html_or_integration_point(&mut self) {
+        while self.open_elements.len() > 1 {
+            let node = current_node!(self);
+            if node.namespace.as_deref() == Some(HTML_NAMESPACE)
+                || is_mathml_text_integration_point(&node)
+                || is_html_integration_point(&node)
+            {
+                break;
+            }
+            self.open_elements.pop();
+        }
+    }
+
     /// Handle insertion mode "in_head"
     fn handle_in_head(&mut self) {
         let mut anything_else = false;
@@ -2865,10 +3252,11 @@ impl<'stream> Html5Parser<'stream> {
                 // TODO If parser is created as part of HTML fragment parsing algorithm, set the element's "already started" flag to true
                 // TODO if the parser was invoked by document.write/writln, set script's element already started flag to true
 
-                self.open_elements.push(node.id);
-                self.document
+                let node_id = self
+                    .document
                     .get_mut()
                     .add_node(node, adjusted_insertion_location);
+                self.open_elements.push(node_id);
 
                 self.tokenizer.state = State::ScriptDataState;
                 self.original_insertion_mode = self.insertion_mode;
@@ -2882,9 +3270,9 @@ impl<'stream> Html5Parser<'stream> {
                 anything_else = true;
             }
             Token::StartTagToken { name, .. } if name == "template" => {
-                let node_id = self.insert_html_element(&self.current_token.clone());
+                if !self.insert_declarative_shadow_root() {
+                    let node_id = self.insert_html_element(&self.current_token.clone());
 
-                {
                     let current_node_id = current_node!(self).id;
 
                     let mut node = get_node_by_id_mut!(self, node_id);
@@ -2900,7 +3288,7 @@ impl<'stream> Html5Parser<'stream> {
                 self.template_insertion_mode.push(InsertionMode::InTemplate);
             }
             Token::EndTagToken { name, .. } if name == "template" => {
-                if !self.open_elements_has("template") {
+                if !self.open_elements_has_template() {
                     self.parse_error("could not find template tag in open element stack");
                     // ignore token
                     return;
@@ -2908,14 +3296,29 @@ impl<'stream> Html5Parser<'stream> {
 
                 self.generate_all_implied_end_tags(None, true);
 
-                if current_node!(self).name != "template" {
+                if !is_html_template(&current_node!(self)) {
                     self.parse_error("template end tag not at top of stack");
                 }
 
-                self.pop_until("template");
+                let template_id = self
+                    .open_elements
+                    .iter()
+                    .rev()
+                    .find(|node_id| is_html_template(&get_node_by_id!(self, **node_id)))
+                    .copied();
+
+                if let Some(template_id) = template_id {
+                    self.open_elements
+                        .pop_until(|node_id| *node_id == template_id);
+                    self.open_elements.pop();
+                }
                 self.active_formatting_elements_clear_until_marker();
                 self.template_insertion_mode.pop();
 
+                if let Some(template_id) = template_id {
+                    self.declarative_shadow_roots.remove(&template_id);
+                }
+
                 self.reset_insertion_mode();
             }
             Token::StartTagToken { name, .. } if name == "head" => {
@@ -2939,6 +3342,47 @@ impl<'stream> Html5Parser<'stream> {
         }
     }
 
+    /// When the template start tag has a valid "shadowrootmode" attribute, a shadow root is
+    /// attached to the current node, and the template is pushed onto the stack of open elements
+    /// without being inserted into the tree. Its contents are parsed straight into the shadow root.
+    /// Returns false when the template must be inserted as a regular template element.
+    /// <https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inhead> (declarative shadow DOM)
+    fn insert_declarative_shadow_root(&mut self) -> bool {
+        let Token::StartTagToken { attributes, .. } = &self.current_token else {
+            return false;
+        };
+        let Some(mode) = attributes
+            .get("shadowrootmode")
+            .and_then(|value| ShadowRootMode::from_attribute(value))
+        else {
+            return false;
+        };
+
+        // The host must not be the topmost element in the stack of open elements
+        if self.open_elements.len() < 2 {
+            return false;
+        }
+        let host_id = current_node!(self).id;
+
+        let mut init = ShadowRootInit::new(mode);
+        init.delegates_focus = attributes.contains_key("shadowrootdelegatesfocus");
+        init.clonable = attributes.contains_key("shadowrootclonable");
+        init.serializable = attributes.contains_key("shadowrootserializable");
+
+        let Some(shadow_root_id) = self.document.attach_shadow(host_id, init) else {
+            self.parse_error("could not attach declarative shadow root");
+            return false;
+        };
+
+        let template = self.create_node(&self.current_token, HTML_NAMESPACE);
+        let template_id = self.document.get_mut().add_detached_node(template);
+        self.open_elements.push(template_id);
+        self.declarative_shadow_roots
+            .insert(template_id, shadow_root_id);
+
+        true
+    }
+
     /// Handle insertion mode "in_template"
     fn handle_in_template(&mut self) {
         todo!()
@@ -2961,7 +3405,7 @@ impl<'stream> Html5Parser<'stream> {
             }
             Token::CommentToken { .. } => {
                 let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-                let parent_id = current_node!(self).id;
+                let parent_id = self.adjusted_insert_location(None);
                 self.document.get_mut().add_node(node, parent_id);
             }
             Token::DocTypeToken { .. } => {
@@ -3082,7 +3526,7 @@ impl<'stream> Html5Parser<'stream> {
             Token::StartTagToken { name, .. } if name == "form" => {
                 self.parse_error("form tag not allowed in in table insertion mode");
 
-                if self.open_elements_has("template") || self.form_element.is_some() {
+                if self.open_elements_has_template() || self.form_element.is_some() {
                     // ignore token
                     return;
                 }
@@ -3280,7 +3724,7 @@ impl<'stream> Html5Parser<'stream> {
         new_node.children = Vec::new();
         new_node.parent = None;
 
-        let parent_id = current_node!(self).id;
+        let parent_id = self.adjusted_insert_location(None);
         let new_node_id = self.document.get_mut().add_node(new_node, parent_id);
         if let NodeData::Element { .. } = org_node.data {
             self.open_elements.push(new_node_id);
@@ -3326,7 +3770,7 @@ impl<'stream> Html5Parser<'stream> {
             let mut new_attributes = HashMap::new();
             for (name, value) in attributes.iter() {
                 if MATHML_ADJUSTMENTS.contains_key(name) {
-                    let new_name = SVG_ADJUSTMENTS.get(name).expect("svg adjustments");
+                    let new_name = MATHML_ADJUSTMENTS.get(name).expect("mathml adjustments");
                     new_attributes.insert(new_name.to_string(), value.clone());
                 } else {
                     new_attributes.insert(name.clone(), value.clone());
@@ -3341,8 +3785,9 @@ impl<'stream> Html5Parser<'stream> {
             let mut new_attributes = HashMap::new();
             for (name, value) in attributes.iter() {
                 if XML_ADJUSTMENTS.contains_key(name) {
-                    let new_name = SVG_ADJUSTMENTS.get(name).expect("svg adjustments");
-                    new_attributes.insert(new_name.to_string(), value.clone());
+                    // Attributes are stored by their qualified name (e.g. "xlink:href"), so only
+                    // the namespace changes, which is not stored (yet)
+                    new_attributes.insert(name.clone(), value.clone());
                 } else {
                     new_attributes.insert(name.clone(), value.clone());
                 }
@@ -3424,6 +3869,14 @@ impl<'stream> Html5Parser<'stream> {
 
         let adjusted_insertion_location = target.id;
 
+        // Contents of a declarative shadow root template are inserted into the shadow root
+        if let Some(shadow_root_id) = self
+            .declarative_shadow_roots
+            .get(&adjusted_insertion_location)
+        {
+            return *shadow_root_id;
+        }
+
         //     && ["table", "tbody", "thead", "tfoot", "tr"].contains(&target.name.as_str())
         // {
         //     /*
@@ -3450,8 +3903,8 @@ impl<'stream> Html5Parser<'stream> {
         // }
 
         let node = get_node_by_id!(self, adjusted_insertion_location);
-        if node.parent.is_some() {
-            let node = get_node_by_id!(self, node.parent.unwrap());
+        if let Some(parent_id) = node.parent {
+            let node = get_node_by_id!(self, parent_id);
             if node.name == "template" {
                 // Store in the document fragment
                 // be the content
@@ -3463,7 +3916,8 @@ impl<'stream> Html5Parser<'stream> {
 
     /// Merges the text with the last child of the current node if that is also a text node
     fn create_or_merge_text(&mut self, token: Token) {
-        let node = current_node!(self);
+        let parent_id = self.adjusted_insert_location(None);
+        let node = get_node_by_id!(self, parent_id);
 
         if let Some(last_child_id) = node.children.last() {
             let mut last_child = get_node_by_id_mut!(self, *last_child_id);
@@ -3474,7 +3928,6 @@ impl<'stream> Html5Parser<'stream> {
         }
 
         let node = self.create_node(&self.current_token, HTML_NAMESPACE);
-        let parent_id = current_node!(self).id;
         self.document.get_mut().add_node(node, parent_id);
     }
 
@@ -3516,6 +3969,8 @@ impl<'stream> Html5Parser<'stream> {
 mod test {
     use super::*;
     use crate::html5_parser::input_stream::Encoding;
+    use crate::testing::parse_html;
+    use crate::testing::tree_construction::document_tree;
 
     macro_rules! node_create {
         ($self:expr, $name:expr) => {{
@@ -3525,6 +3980,11 @@ mod test {
         }};
     }
 
+    /// Parses the input and returns the tree in the format of the html5lib tests
+    fn tree(input: &str) -> Vec<String> {
+        document_tree(&parse_html(input))
+    }
+
     #[test]
     fn is_in_scope() {
         let mut stream = InputStream::new();
@@ -3867,4 +4327,155 @@ mod test {
         binding.set_node_named_id(NodeId(4), "otherid");
         assert!(binding.get_node_by_named_id("myid").is_none());
     }
+
+    #[test]
+    fn foreign_content() {
+        assert_eq!(
+            tree("<!DOCTYPE html><body><table><caption><svg><g>foo</g><g>bar</g><p>baz</table><p>quux"),
+            [
+                "| <!DOCTYPE html>",
+                "| <html>",
+                "|   <head>",
+                "|   <body>",
+                "|     <table>",
+                "|       <caption>",
+                "|         <svg svg>",
+                "|           <svg g>",
+                "|             \"foo\"",
+                "|           <svg g>",
+                "|             \"bar\"",
+                "|         <p>",
+                "|           \"baz\"",
+                "|     <p>",
+                "|       \"quux\"",
+            ]
+        );
+        assert_eq!(
+            tree("<svg><foreignobject><div>a</div></foreignobject><clippath/></svg><math><mi>b</mi></math>"),
+            [
+                "| <html>",
+                "|   <head>",
+                "|   <body>",
+                "|     <svg svg>",
+                "|       <svg foreignObject>",
+                "|         <div>",
+                "|           \"a\"",
+                "|       <svg clipPath>",
+                "|     <math math>",
+                "|       <math mi>",
+                "|         \"b\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn foreign_attributes() {
+        assert_eq!(
+            tree("<svg xml:base xml:lang xml:space xml:baaah definitionurl>"),
+            [
+                "| <html>",
+                "|   <head>",
+                "|   <body>",
+                "|     <svg svg>",
+                "|       definitionurl=\"\"",
+                "|       xml lang=\"\"",
+                "|       xml space=\"\"",
+                "|       xml:baaah=\"\"",
+                "|       xml:base=\"\"",
+            ]
+        );
+        assert_eq!(
+            tree("<!DOCTYPE html><body xlink:href=foo><math definitionurl xlink:href=foo></math>"),
+            [
+                "| <!DOCTYPE html>",
+                "| <html>",
+                "|   <head>",
+                "|   <body>",
+                "|     xlink:href=\"foo\"",
+                "|     <math math>",
+                "|       definitionURL=\"\"",
+                "|       xlink href=\"foo\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn template_end_tag_in_foreign_content() {
+        // The SVG template element must not close the HTML template element
+        assert_eq!(
+            tree("<template><svg><foo><template><foreignObject><div></template><div>"),
+            [
+                "| <html>",
+                "|   <head>",
+                "|     <template>",
+                "|       content",
+                "|         <svg svg>",
+                "|           <svg foo>",
+                "|             <svg template>",
+                "|               <svg foreignObject>",
+                "|                 <div>",
+                "|   <body>",
+                "|     <div>",
+            ]
+        );
+    }
+
+    #[test]
+    fn list_items() {
+        assert_eq!(
+            tree("<ul><li>a<li>b</ul><dl><dt>c<dd>d<dt>e</dl>"),
+            [
+                "| <html>",
+                "|   <head>",
+                "|   <body>",
+                "|     <ul>",
+                "|       <li>",
+                "|         \"a\"",
+                "|       <li>",
+                "|         \"b\"",
+                "|     <dl>",
+                "|       <dt>",
+                "|         \"c\"",
+                "|       <dd>",
+                "|         \"d\"",
+                "|       <dt>",
+                "|         \"e\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_cells() {
+        assert_eq!(
+            tree("<table><tr><td>a</td><td>b</table>"),
+            [
+                "| <html>",
+                "|   <head>",
+                "|   <body>",
+                "|     <table>",
+                "|       <tbody>",
+                "|         <tr>",
+                "|           <td>",
+                "|             \"a\"",
+                "|           <td>",
+                "|             \"b\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn script_end_tag() {
+        assert_eq!(
+            tree("<script>x</script><p>a"),
+            [
+                "| <html>",
+                "|   <head>",
+                "|     <script>",
+                "|       \"x\"",
+                "|   <body>",
+                "|     <p>",
+                "|       \"a\"",
+            ]
+        );
+    }
 }
@@ -269,10 +269,7 @@ impl<'stream> Html5Parser<'stream> {
             .iter()
             .position(|&element_id| element_id == formatting_element_id);
 
-        let element_idx_oe = match element_idx_oe {
-            Some(idx) => idx,
-            None => return None,
-        };
+        let element_idx_oe = element_idx_oe?;
 
         // Iterate
         ((element_idx_oe + 1)..self.open_elements.len())
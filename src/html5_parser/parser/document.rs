@@ -1,9 +1,15 @@
+use crate::html5_parser::element_class::ElementClass;
+use crate::html5_parser::forms::ControlState;
+use crate::html5_parser::mutation_observer::{
+    MutationObserverId, MutationObserverInit, MutationObservers, MutationRecord,
+};
 use crate::html5_parser::node::arena::NodeArena;
 use crate::html5_parser::node::data::{comment::CommentData, text::TextData};
 use crate::html5_parser::node::NodeType;
 use crate::html5_parser::node::{Node, NodeData, NodeId};
 use crate::html5_parser::node::{NodeTrait, HTML_NAMESPACE};
 use crate::html5_parser::parser::quirks::QuirksMode;
+use crate::url::Url;
 use alloc::rc::Rc;
 use core::fmt;
 use core::fmt::Debug;
@@ -60,6 +66,9 @@ pub struct Document {
     named_id_elements: HashMap<String, NodeId>, // HTML elements with ID (e.g., <div id="myid">)
     pub doctype: DocumentType,                  // Document type
     pub quirks_mode: QuirksMode,                // Quirks mode
+    mutation_observers: MutationObservers,      // Registered mutation observers
+    url: Option<Url>,                           // URL the document was loaded from
+    pub(crate) form_controls: HashMap<NodeId, ControlState>, // State of form controls
 }
 
 impl Default for Document {
@@ -69,6 +78,9 @@ impl Default for Document {
             named_id_elements: HashMap::new(),
             doctype: DocumentType::HTML,
             quirks_mode: QuirksMode::NoQuirks,
+            mutation_observers: MutationObservers::new(),
+            url: None,
+            form_controls: HashMap::new(),
         }
     }
 }
@@ -82,6 +94,9 @@ impl Document {
             named_id_elements: HashMap::new(),
             doctype: DocumentType::HTML,
             quirks_mode: QuirksMode::NoQuirks,
+            mutation_observers: MutationObservers::new(),
+            url: None,
+            form_controls: HashMap::new(),
         }
     }
 
@@ -208,6 +223,26 @@ impl Document {
         node_id
     }
 
+    /// Add a node to the document without attaching it to a parent (e.g. shadow roots)
+    pub fn add_detached_node(&mut self, node: Node) -> NodeId {
+        self.arena.add_node(node)
+    }
+
+    /// Removes the node, and all of its children, from the document
+    pub fn delete_node(&mut self, node_id: NodeId) {
+        if let Some(named_id) = self.get_node_by_id(node_id).and_then(|n| n.get_named_id()) {
+            if self.named_id_elements.get(&named_id) == Some(&node_id) {
+                self.named_id_elements.remove(&named_id);
+            }
+        }
+        self.arena.detach_node(node_id);
+    }
+
+    /// Returns the ids of all nodes in the document, in the order they were created
+    pub fn all_node_ids(&self) -> Vec<NodeId> {
+        self.arena.node_ids()
+    }
+
     pub fn append(&mut self, node_id: NodeId, parent_id: NodeId) {
         self.arena.attach_node(parent_id, node_id);
     }
@@ -240,6 +275,366 @@ impl Document {
     }
 }
 
+/// URLs of the document and the resources it refers to
+impl Document {
+    /// Returns the URL the document was loaded from
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
+    pub fn set_url(&mut self, url: Url) {
+        self.url = Some(url);
+    }
+
+    /// Returns the base URL that is used to resolve relative URLs in the document. This is the
+    /// href of the first `<base>` element with an href attribute, or the document URL.
+    /// <https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url>
+    pub fn base_url(&self) -> Option<Url> {
+        let fallback = self.url.clone();
+
+        let base_href = self
+            .descendants(NodeId::root())
+            .into_iter()
+            .filter_map(|id| self.get_node_by_id(id))
+            .filter(|node| node.name == "base" && node.namespace.as_deref() == Some(HTML_NAMESPACE))
+            .find_map(|node| match &node.data {
+                NodeData::Element(element) => element.attributes.get("href").cloned(),
+                _ => None,
+            });
+
+        let Some(base_href) = base_href else {
+            return fallback;
+        };
+        match Url::parse_with_base(&base_href, fallback.as_ref()) {
+            Ok(url) if url.scheme() != "data" && url.scheme() != "javascript" => Some(url),
+            _ => fallback,
+        }
+    }
+
+    /// Resolves a (relative) URL against the base URL of the document
+    pub fn resolve_url(&self, input: &str) -> Option<Url> {
+        Url::parse_with_base(input, self.base_url().as_ref()).ok()
+    }
+
+    /// Resolves the URL in the attribute of the element, e.g. the href of a link
+    pub fn resolve_attribute_url(&self, node_id: NodeId, attribute: &str) -> Option<Url> {
+        match &self.get_node_by_id(node_id)?.data {
+            NodeData::Element(element) => self.resolve_url(element.attributes.get(attribute)?),
+            _ => None,
+        }
+    }
+
+    /// Returns the resolved URLs of all hyperlinks (`<a href>` and `<area href>`) in tree order
+    pub fn links(&self) -> Vec<(NodeId, Url)> {
+        self.resolved_element_urls(&["a", "area"], "href")
+    }
+
+    /// Returns the resolved URLs of all images (`<img src>`) in tree order
+    pub fn images(&self) -> Vec<(NodeId, Url)> {
+        self.resolved_element_urls(&["img"], "src")
+    }
+
+    /// Returns the resolved URLs of all external scripts (`<script src>`) in tree order
+    pub fn scripts(&self) -> Vec<(NodeId, Url)> {
+        self.resolved_element_urls(&["script"], "src")
+    }
+
+    /// Returns the resolved URLs of the attribute of all HTML elements with one of the names.
+    /// Elements with URLs that cannot be parsed are skipped.
+    fn resolved_element_urls(&self, names: &[&str], attribute: &str) -> Vec<(NodeId, Url)> {
+        let base_url = self.base_url();
+
+        self.descendants(NodeId::root())
+            .into_iter()
+            .filter_map(|id| self.get_node_by_id(id))
+            .filter(|node| {
+                names.contains(&node.name.as_str())
+                    && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+            })
+            .filter_map(|node| {
+                let NodeData::Element(element) = &node.data else {
+                    return None;
+                };
+                let value = element.attributes.get(attribute)?;
+                let url = Url::parse_with_base(value, base_url.as_ref()).ok()?;
+                Some((node.id, url))
+            })
+            .collect()
+    }
+}
+
+/// DOM manipulation API. Changes made through these functions are reported to mutation observers.
+impl Document {
+    /// Creates a new mutation observer
+    pub fn create_mutation_observer(&mut self) -> MutationObserverId {
+        self.mutation_observers.create()
+    }
+
+    /// Starts observing the target node. Returns false when the options do not observe anything.
+    pub fn observe(
+        &mut self,
+        observer: MutationObserverId,
+        target: NodeId,
+        options: MutationObserverInit,
+    ) -> bool {
+        self.mutation_observers.observe(observer, target, options)
+    }
+
+    /// Stops the observer from observing anything
+    pub fn disconnect(&mut self, observer: MutationObserverId) {
+        self.mutation_observers.disconnect(observer);
+    }
+
+    /// Returns all records that are queued for the observer, and empties its queue
+    pub fn take_mutation_records(&mut self, observer: MutationObserverId) -> Vec<MutationRecord> {
+        self.mutation_observers.take_records(observer)
+    }
+
+    /// Queues a mutation record for all observers that are interested in the target
+    fn queue_mutation_record(&mut self, record: MutationRecord) {
+        if !self.mutation_observers.has_registrations() {
+            return;
+        }
+
+        let mut ancestors = vec![record.target];
+        let mut current = record.target;
+        while let Some(parent_id) = self.get_node_by_id(current).and_then(|node| node.parent) {
+            ancestors.push(parent_id);
+            current = parent_id;
+        }
+
+        self.mutation_observers.queue(&ancestors, record);
+    }
+
+    /// Returns true when the ancestor is the node itself, or one of its ancestors
+    fn is_inclusive_ancestor(&self, ancestor_id: NodeId, node_id: NodeId) -> bool {
+        let mut current = Some(node_id);
+        while let Some(id) = current {
+            if id == ancestor_id {
+                return true;
+            }
+            current = self.get_node_by_id(id).and_then(|node| node.parent);
+        }
+        false
+    }
+
+    /// Appends the node as the last child of the parent. When the node already has a parent,
+    /// it is removed from there first.
+    pub fn append_child(&mut self, parent_id: NodeId, node_id: NodeId) -> bool {
+        self.insert_before(parent_id, node_id, None)
+    }
+
+    /// Inserts the node into the parent before the reference child, or as the last child when
+    /// there is no reference. Returns false when the insertion is not allowed (for instance when
+    /// it would create a cycle, the node cannot be a child of the parent, or the reference is not
+    /// a child of the parent).
+    pub fn insert_before(
+        &mut self,
+        parent_id: NodeId,
+        node_id: NodeId,
+        reference_id: Option<NodeId>,
+    ) -> bool {
+        let Some(parent) = self.get_node_by_id(parent_id) else {
+            return false;
+        };
+        if matches!(parent.data, NodeData::Text(_) | NodeData::Comment(_)) {
+            return false;
+        }
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return false;
+        };
+        // The HierarchyRequestError cases of
+        // https://dom.spec.whatwg.org/#concept-node-ensure-pre-insertion-validity
+        if matches!(node.data, NodeData::Document(_) | NodeData::ShadowRoot(_))
+            || (node.is_doctype() && !matches!(parent.data, NodeData::Document(_)))
+            || self.is_inclusive_ancestor(node_id, parent_id)
+        {
+            return false;
+        }
+        if let Some(reference_id) = reference_id {
+            if reference_id == node_id {
+                // Inserting a node before itself keeps it in place
+                return true;
+            }
+            if !parent.children.contains(&reference_id) {
+                return false;
+            }
+        }
+
+        if let Some(old_parent_id) = self.get_node_by_id(node_id).and_then(|node| node.parent) {
+            self.remove_child(old_parent_id, node_id);
+        }
+
+        let parent = self
+            .get_node_by_id_mut(parent_id)
+            .expect("parent not found");
+        let index = match reference_id {
+            Some(reference_id) => parent
+                .children
+                .iter()
+                .position(|id| *id == reference_id)
+                .expect("reference not found"),
+            None => parent.children.len(),
+        };
+        let previous_sibling = index.checked_sub(1).map(|i| parent.children[i]);
+        parent.children.insert(index, node_id);
+
+        if let Some(node) = self.get_node_by_id_mut(node_id) {
+            node.parent = Some(parent_id);
+        }
+
+        self.queue_mutation_record(MutationRecord::child_list(
+            parent_id,
+            vec![node_id],
+            vec![],
+            previous_sibling,
+            reference_id,
+        ));
+        true
+    }
+
+    /// Removes the child from the parent. The node stays in the document arena so it can be
+    /// inserted again. Returns false when the node is not a child of the parent.
+    pub fn remove_child(&mut self, parent_id: NodeId, node_id: NodeId) -> bool {
+        let Some(parent) = self.get_node_by_id_mut(parent_id) else {
+            return false;
+        };
+        let Some(index) = parent.children.iter().position(|id| *id == node_id) else {
+            return false;
+        };
+
+        let previous_sibling = index.checked_sub(1).map(|i| parent.children[i]);
+        let next_sibling = parent.children.get(index + 1).copied();
+        parent.children.remove(index);
+
+        // Queue the record before detaching, so subtree observers of the parent are notified
+        self.queue_mutation_record(MutationRecord::child_list(
+            parent_id,
+            vec![],
+            vec![node_id],
+            previous_sibling,
+            next_sibling,
+        ));
+
+        if let Some(node) = self.get_node_by_id_mut(node_id) {
+            node.parent = None;
+        }
+        true
+    }
+
+    /// Sets (or replaces) an attribute on an element node
+    pub fn set_attribute(&mut self, node_id: NodeId, name: &str, value: &str) -> bool {
+        let Some(node) = self.get_node_by_id_mut(node_id) else {
+            return false;
+        };
+        let NodeData::Element(element) = &mut node.data else {
+            return false;
+        };
+
+        let old_value = element.attributes.get(name).cloned();
+        element.attributes.insert(name, value);
+        if name == "class" {
+            element.classes = ElementClass::from_string(value);
+        }
+
+        if name == "id" {
+            let old_named_id = node.named_id.take();
+            if let Some(old_named_id) = old_named_id {
+                if self.named_id_elements.get(&old_named_id) == Some(&node_id) {
+                    self.named_id_elements.remove(&old_named_id);
+                }
+            }
+            self.set_node_named_id(node_id, value);
+        }
+
+        self.queue_mutation_record(MutationRecord::attributes(node_id, name, old_value));
+        true
+    }
+
+    /// Removes an attribute from an element node. Returns false when there was no such attribute.
+    pub fn remove_attribute(&mut self, node_id: NodeId, name: &str) -> bool {
+        let Some(node) = self.get_node_by_id_mut(node_id) else {
+            return false;
+        };
+        let NodeData::Element(element) = &mut node.data else {
+            return false;
+        };
+        let Some(old_value) = element.attributes.get(name).cloned() else {
+            return false;
+        };
+
+        element.attributes.remove(name);
+        if name == "class" {
+            element.classes = ElementClass::new();
+        }
+        if name == "id" {
+            if let Some(old_named_id) = node.named_id.take() {
+                if self.named_id_elements.get(&old_named_id) == Some(&node_id) {
+                    self.named_id_elements.remove(&old_named_id);
+                }
+            }
+        }
+
+        self.queue_mutation_record(MutationRecord::attributes(node_id, name, Some(old_value)));
+        true
+    }
+
+    /// Replaces the data of a text or comment node
+    pub fn set_character_data(&mut self, node_id: NodeId, data: &str) -> bool {
+        let Some(node) = self.get_node_by_id_mut(node_id) else {
+            return false;
+        };
+
+        let old_value = match &mut node.data {
+            NodeData::Text(TextData { value, .. })
+            | NodeData::Comment(CommentData { value, .. }) => {
+                std::mem::replace(value, data.to_string())
+            }
+            _ => return false,
+        };
+
+        self.queue_mutation_record(MutationRecord::character_data(node_id, Some(old_value)));
+        true
+    }
+
+    /// Replaces all children of the parent with the given (new) nodes. The removed children stay
+    /// in the document arena, like with `remove_child()`.
+    pub fn replace_all(&mut self, parent_id: NodeId, nodes: Vec<Node>) -> bool {
+        match self.get_node_by_id(parent_id).map(|parent| &parent.data) {
+            None | Some(NodeData::Text(_)) | Some(NodeData::Comment(_)) => return false,
+            _ => {}
+        }
+
+        let removed_nodes = std::mem::take(
+            &mut self
+                .get_node_by_id_mut(parent_id)
+                .expect("parent not found")
+                .children,
+        );
+        for node_id in removed_nodes.iter() {
+            if let Some(node) = self.get_node_by_id_mut(*node_id) {
+                node.parent = None;
+            }
+        }
+
+        let added_nodes: Vec<NodeId> = nodes
+            .into_iter()
+            .map(|node| self.add_node(node, parent_id))
+            .collect();
+
+        if !removed_nodes.is_empty() || !added_nodes.is_empty() {
+            self.queue_mutation_record(MutationRecord::child_list(
+                parent_id,
+                added_nodes,
+                removed_nodes,
+                None,
+                None,
+            ));
+        }
+        true
+    }
+}
+
 impl Document {
     /// Print a node and all its children in a tree-like structure
     pub fn print_tree(&self, node: &Node, prefix: String, last: bool, f: &mut fmt::Formatter) {
@@ -267,6 +662,9 @@ impl Document {
                 }
                 _ = writeln!(f, ">");
             }
+            NodeData::ShadowRoot(shadow_root) => {
+                _ = writeln!(f, "{}#shadow-root ({:?})", buffer, shadow_root.mode);
+            }
         }
 
         if prefix.len() > 40 {
@@ -281,8 +679,16 @@ impl Document {
             buffer.push_str("│  ");
         }
 
-        let len = node.children.len();
-        for (i, child) in node.children.iter().enumerate() {
+        // A shadow root is displayed as the first child of its host
+        let mut children = node.children.clone();
+        if let NodeData::Element(element) = &node.data {
+            if let Some(shadow_root_id) = element.shadow_root {
+                children.insert(0, shadow_root_id);
+            }
+        }
+
+        let len = children.len();
+        for (i, child) in children.iter().enumerate() {
             let child = self.arena.get_node(*child).expect("Child not found");
             self.print_tree(child, buffer.clone(), i == len - 1, f);
         }
@@ -345,8 +751,14 @@ impl DocumentHandle {
 
 #[cfg(test)]
 mod tests {
+    use super::DocumentHandle;
+    use crate::html5_parser::dom::{ShadowRootInit, ShadowRootMode};
+    use crate::html5_parser::mutation_observer::{
+        MutationObserverId, MutationObserverInit, MutationType,
+    };
     use crate::html5_parser::node::HTML_NAMESPACE;
     use crate::html5_parser::parser::{Document, Node, NodeData, NodeId};
+    use crate::url::Url;
     use std::collections::HashMap;
 
     #[ignore]
@@ -517,6 +929,155 @@ mod tests {
         assert!(document.get().get_node_by_named_id("myid").is_none());
     }
 
+    fn observed_document() -> (DocumentHandle, NodeId, MutationObserverId) {
+        let mut document = Document::shared();
+        let document_clone = Document::clone(&document);
+        document.get_mut().create_root(&document_clone);
+        let div_id = document.add_element(NodeId::root(), "div");
+        let observer = document.get_mut().create_mutation_observer();
+        (document, div_id, observer)
+    }
+
+    #[test]
+    fn observe_child_list() {
+        let (mut document, div_id, observer) = observed_document();
+        let options = MutationObserverInit {
+            child_list: true,
+            ..Default::default()
+        };
+        assert!(document.get_mut().observe(observer, div_id, options));
+
+        let p_id = document.add_element(NodeId::root(), "p");
+        let span_id = document.add_element(NodeId::root(), "span");
+        let mut doc = document.get_mut();
+        assert!(doc.append_child(div_id, p_id));
+        assert!(doc.insert_before(div_id, span_id, Some(p_id)));
+        assert!(doc.remove_child(div_id, p_id));
+        assert_eq!(doc.get_node_by_id(div_id).unwrap().children, vec![span_id]);
+        assert_eq!(doc.get_node_by_id(p_id).unwrap().parent, None);
+
+        let records = doc.take_mutation_records(observer);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].added_nodes, vec![p_id]);
+        assert_eq!(records[1].added_nodes, vec![span_id]);
+        assert_eq!(records[1].next_sibling, Some(p_id));
+        assert_eq!(records[2].removed_nodes, vec![p_id]);
+        assert_eq!(records[2].previous_sibling, Some(span_id));
+        assert!(records.iter().all(|r| r.kind == MutationType::ChildList));
+    }
+
+    #[test]
+    fn insert_before_rejects_cycles() {
+        let (mut document, div_id, _) = observed_document();
+        let p_id = document.add_element(div_id, "p");
+        let mut doc = document.get_mut();
+        assert!(!doc.append_child(p_id, div_id));
+        assert!(!doc.append_child(div_id, div_id));
+        assert!(!doc.insert_before(NodeId::root(), p_id, Some(p_id.next())));
+    }
+
+    #[test]
+    fn insert_before_rejects_documents() {
+        let (mut document, div_id, _) = observed_document();
+        let mut doc = document.get_mut();
+        assert!(!doc.append_child(div_id, NodeId::root()));
+        assert!(doc.get_node_by_id(NodeId::root()).unwrap().parent.is_none());
+    }
+
+    #[test]
+    fn insert_before_rejects_shadow_roots() {
+        let (mut document, div_id, _) = observed_document();
+        let span_id = document.add_element(NodeId::root(), "span");
+        let init = ShadowRootInit::new(ShadowRootMode::Open);
+        let shadow_root_id = document.attach_shadow(div_id, init).unwrap();
+
+        let mut doc = document.get_mut();
+        assert!(!doc.append_child(span_id, shadow_root_id));
+        assert!(!doc.append_child(NodeId::root(), shadow_root_id));
+        assert!(doc.get_node_by_id(span_id).unwrap().children.is_empty());
+        // Children can still be added to the shadow root itself
+        assert!(doc.append_child(shadow_root_id, span_id));
+    }
+
+    #[test]
+    fn insert_before_rejects_doctypes_under_elements() {
+        let (mut document, div_id, _) = observed_document();
+        let doctype_id = document.add_element(NodeId::root(), "!DOCTYPE html");
+        let mut doc = document.get_mut();
+        assert!(!doc.append_child(div_id, doctype_id));
+        assert_eq!(
+            doc.get_node_by_id(doctype_id).unwrap().parent,
+            Some(NodeId::root())
+        );
+        assert!(doc.insert_before(NodeId::root(), doctype_id, Some(div_id)));
+    }
+
+    #[test]
+    fn observe_attributes_in_subtree() {
+        let (mut document, div_id, observer) = observed_document();
+        let p_id = document.add_element(div_id, "p");
+        let options = MutationObserverInit {
+            subtree: true,
+            attribute_old_value: true,
+            ..Default::default()
+        };
+        assert!(document.get_mut().observe(observer, div_id, options));
+
+        let mut doc = document.get_mut();
+        assert!(doc.set_attribute(p_id, "class", "one two"));
+        assert!(doc.set_attribute(p_id, "class", "three"));
+        assert!(doc.set_attribute(p_id, "id", "para"));
+        assert!(doc.remove_attribute(p_id, "id"));
+        assert!(!doc.remove_attribute(p_id, "id"));
+        assert!(doc.get_node_by_named_id("para").is_none());
+
+        let NodeData::Element(element) = &doc.get_node_by_id(p_id).unwrap().data else {
+            panic!()
+        };
+        assert!(element.classes.contains("three"));
+        assert!(!element.classes.contains("one"));
+
+        let records = doc.take_mutation_records(observer);
+        let old_values: Vec<Option<String>> = records.iter().map(|r| r.old_value.clone()).collect();
+        assert_eq!(
+            old_values,
+            vec![
+                None,
+                Some("one two".to_string()),
+                None,
+                Some("para".to_string())
+            ]
+        );
+        assert!(records.iter().all(|r| r.target == p_id));
+    }
+
+    #[test]
+    fn observe_character_data() {
+        let (mut document, div_id, observer) = observed_document();
+        let text_id = document.add_text(div_id, "hello");
+        let options = MutationObserverInit {
+            character_data: true,
+            subtree: true,
+            ..Default::default()
+        };
+        assert!(document.get_mut().observe(observer, div_id, options));
+
+        let mut doc = document.get_mut();
+        assert!(doc.set_character_data(text_id, "world"));
+        assert!(!doc.set_character_data(div_id, "nope"));
+
+        let records = doc.take_mutation_records(observer);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, MutationType::CharacterData);
+        // old value was not requested
+        assert_eq!(records[0].old_value, None);
+
+        let NodeData::Text(text) = &doc.get_node_by_id(text_id).unwrap().data else {
+            panic!()
+        };
+        assert_eq!(text.value(), "world");
+    }
+
     #[test]
     fn verify_node_ids_in_element_data() {
         let mut document = Document::shared();
@@ -546,4 +1107,64 @@ mod tests {
 
         assert_eq!(element2.node_id, NodeId::from(2));
     }
+
+    #[test]
+    fn resolve_urls_with_base() {
+        let mut document = Document::shared();
+        let document_clone = Document::clone(&document);
+        document.get_mut().create_root(&document_clone);
+
+        let element = |document: &DocumentHandle, name: &str, attribute: &str, value: &str| {
+            let attributes = HashMap::from([(attribute.to_string(), value.to_string())]);
+            Node::new_element(document, name, attributes, HTML_NAMESPACE)
+        };
+        let link = element(&document, "a", "href", "page.html#top");
+        let link_id = document.get_mut().add_node(link, NodeId::root());
+        let image = element(&document, "img", "src", "/logo.png");
+        let image_id = document.get_mut().add_node(image, NodeId::root());
+        let script = element(&document, "script", "src", "http://[invalid/");
+        document.get_mut().add_node(script, NodeId::root());
+
+        // Without a document URL, relative URLs cannot be resolved
+        assert!(document.get().base_url().is_none());
+        assert!(document.get().links().is_empty());
+
+        let url = Url::parse("https://example.com/dir/index.html").unwrap();
+        document.get_mut().set_url(url);
+        assert_eq!(
+            document.get().links(),
+            vec![(
+                link_id,
+                Url::parse("https://example.com/dir/page.html#top").unwrap()
+            )]
+        );
+        assert_eq!(
+            document.get().images(),
+            vec![(
+                image_id,
+                Url::parse("https://example.com/logo.png").unwrap()
+            )]
+        );
+        // Invalid URLs are skipped
+        assert!(document.get().scripts().is_empty());
+
+        // The first base element with an href decides the base URL
+        let base = element(&document, "base", "target", "_blank");
+        document.get_mut().add_node(base, NodeId::root());
+        let base = element(&document, "base", "href", "/other/");
+        document.get_mut().add_node(base, NodeId::root());
+        let base = element(&document, "base", "href", "/ignored/");
+        document.get_mut().add_node(base, NodeId::root());
+
+        let doc = document.get();
+        assert_eq!(doc.base_url().unwrap().href(), "https://example.com/other/");
+        assert_eq!(
+            doc.resolve_attribute_url(link_id, "href").unwrap().href(),
+            "https://example.com/other/page.html#top"
+        );
+        assert_eq!(
+            doc.resolve_url("//cdn.example.com/x.js").unwrap().href(),
+            "https://cdn.example.com/x.js"
+        );
+    }
 }
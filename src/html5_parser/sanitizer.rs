@@ -0,0 +1,752 @@
+//! Allowlist-based HTML sanitizer
+//!
+//! The input is parsed as a fragment in the context of a body element, just like a browser would
+//! parse it when it is assigned to innerHTML. Afterwards the tree is walked and everything that is
+//! not allowed by the [`SanitizerConfig`] is removed:
+//!
+//! - HTML elements that are not allowed are replaced by their children, unless they are in the
+//!   list of elements that are removed together with their contents. SVG and MathML elements that
+//!   are not allowed are always removed with their contents.
+//! - Attributes that are not allowed are removed. Event handler attributes (`on*`) are never kept.
+//! - URL attributes are removed when their URL has a scheme that is not allowed (like
+//!   `javascript:`).
+//! - Comments are removed unless they are allowed.
+//!
+//! Some elements (like script and iframe) are never kept, regardless of the configuration.
+//!
+//! The sanitizer also guards against namespace confusion (mutation XSS): elements only survive
+//! in places where the parser could have put them. For instance, an HTML element directly inside
+//! an SVG element that is not an HTML integration point, or an `mglyph` element in the HTML
+//! namespace, cannot be reproduced when the sanitized output is parsed again, and is removed.
+//! Raw text elements (like style) whose text looks like markup are removed as well.
+//!
+//! The default configuration follows the baseline of the Sanitizer API
+//! (<https://wicg.github.io/sanitizer-api/#default-configuration>).
+use crate::html5_parser::input_stream::{Encoding, InputStream};
+use crate::html5_parser::node::{
+    Node, NodeData, NodeId, HTML_NAMESPACE, MATHML_NAMESPACE, SVG_NAMESPACE,
+};
+use crate::html5_parser::parser::document::Document;
+use crate::html5_parser::parser::{
+    is_html_integration_point, is_mathml_text_integration_point, Html5Parser,
+};
+use crate::url::Url;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+/// Elements that are never kept, whatever the configuration says
+static UNSAFE_ELEMENTS: [&str; 11] = [
+    "script", "iframe", "frame", "frameset", "object", "embed", "applet", "base", "meta", "link",
+    "portal",
+];
+
+/// HTML elements whose text is not escaped when serialized
+static RAW_TEXT_ELEMENTS: [&str; 8] = [
+    "style",
+    "script",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "plaintext",
+    "noscript",
+];
+
+/// Element names that only exist in the SVG or MathML namespace. An HTML element with one of
+/// these names is a sign of namespace confusion.
+static FOREIGN_ONLY_ELEMENTS: [&str; 56] = [
+    "svg",
+    "math",
+    "foreignobject",
+    "desc",
+    "circle",
+    "ellipse",
+    "line",
+    "path",
+    "polygon",
+    "polyline",
+    "rect",
+    "g",
+    "defs",
+    "symbol",
+    "use",
+    "text",
+    "tspan",
+    "textpath",
+    "lineargradient",
+    "radialgradient",
+    "stop",
+    "clippath",
+    "mask",
+    "pattern",
+    "marker",
+    "filter",
+    "animate",
+    "animatemotion",
+    "animatetransform",
+    "set",
+    "mi",
+    "mo",
+    "mn",
+    "ms",
+    "mtext",
+    "mglyph",
+    "malignmark",
+    "annotation",
+    "annotation-xml",
+    "semantics",
+    "mrow",
+    "mfrac",
+    "msqrt",
+    "mroot",
+    "mstyle",
+    "mpadded",
+    "mphantom",
+    "msub",
+    "msup",
+    "msubsup",
+    "munder",
+    "mover",
+    "munderover",
+    "mtable",
+    "mtr",
+    "mtd",
+];
+
+lazy_static! {
+    /// Text that would be parsed as a tag or comment
+    static ref MARKUP_RE: Regex = Regex::new(r"<[/!\w]").unwrap();
+}
+
+/// The policy of the sanitizer. All element names are local names as the parser produces them,
+/// so SVG elements use their adjusted case (like `foreignObject`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SanitizerConfig {
+    /// HTML elements that are kept
+    pub html_elements: HashSet<String>,
+    /// SVG elements that are kept
+    pub svg_elements: HashSet<String>,
+    /// MathML elements that are kept
+    pub mathml_elements: HashSet<String>,
+    /// HTML elements that are removed together with their contents. Other HTML elements that
+    /// are not allowed are replaced by their children.
+    pub remove_elements: HashSet<String>,
+    /// Attributes that are allowed on all elements
+    pub attributes: HashSet<String>,
+    /// Attributes that are allowed on specific elements, by element name
+    pub element_attributes: HashMap<String, HashSet<String>>,
+    /// Attributes that contain a URL
+    pub url_attributes: HashSet<String>,
+    /// Schemes that are allowed in URL attributes. Relative URLs are always allowed.
+    pub url_schemes: HashSet<String>,
+    /// Keep data-* attributes on HTML elements
+    pub data_attributes: bool,
+    /// Keep comments
+    pub comments: bool,
+}
+
+fn set(values: &[&str]) -> HashSet<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+impl SanitizerConfig {
+    /// Returns a configuration that allows nothing but text
+    pub fn empty() -> Self {
+        Self {
+            html_elements: HashSet::new(),
+            svg_elements: HashSet::new(),
+            mathml_elements: HashSet::new(),
+            remove_elements: set(&[
+                "style",
+                "template",
+                "noscript",
+                "noembed",
+                "noframes",
+                "xmp",
+                "plaintext",
+                "title",
+                "textarea",
+                "select",
+            ]),
+            attributes: HashSet::new(),
+            element_attributes: HashMap::new(),
+            url_attributes: set(&[
+                "href",
+                "src",
+                "action",
+                "formaction",
+                "cite",
+                "poster",
+                "background",
+                "longdesc",
+                "xlink:href",
+            ]),
+            url_schemes: set(&["http", "https", "mailto", "tel"]),
+            data_attributes: false,
+            comments: false,
+        }
+    }
+}
+
+impl Default for SanitizerConfig {
+    fn default() -> Self {
+        let mut config = Self::empty();
+
+        config.html_elements = set(&[
+            "a",
+            "abbr",
+            "address",
+            "article",
+            "aside",
+            "b",
+            "bdi",
+            "bdo",
+            "blockquote",
+            "br",
+            "caption",
+            "cite",
+            "code",
+            "col",
+            "colgroup",
+            "data",
+            "dd",
+            "del",
+            "dfn",
+            "div",
+            "dl",
+            "dt",
+            "em",
+            "figcaption",
+            "figure",
+            "footer",
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "header",
+            "hgroup",
+            "hr",
+            "i",
+            "ins",
+            "kbd",
+            "li",
+            "main",
+            "mark",
+            "menu",
+            "nav",
+            "ol",
+            "p",
+            "pre",
+            "q",
+            "rp",
+            "rt",
+            "ruby",
+            "s",
+            "samp",
+            "search",
+            "section",
+            "small",
+            "span",
+            "strong",
+            "sub",
+            "sup",
+            "table",
+            "tbody",
+            "td",
+            "tfoot",
+            "th",
+            "thead",
+            "time",
+            "tr",
+            "u",
+            "ul",
+            "var",
+            "wbr",
+        ]);
+        config.svg_elements = set(&[
+            "svg",
+            "a",
+            "circle",
+            "defs",
+            "desc",
+            "ellipse",
+            "g",
+            "line",
+            "linearGradient",
+            "path",
+            "polygon",
+            "polyline",
+            "radialGradient",
+            "rect",
+            "stop",
+            "text",
+            "title",
+            "tspan",
+        ]);
+        config.mathml_elements = set(&[
+            "math",
+            "merror",
+            "mfrac",
+            "mi",
+            "mmultiscripts",
+            "mn",
+            "mo",
+            "mover",
+            "mpadded",
+            "mphantom",
+            "mprescripts",
+            "mroot",
+            "mrow",
+            "ms",
+            "mspace",
+            "msqrt",
+            "mstyle",
+            "msub",
+            "msubsup",
+            "msup",
+            "mtable",
+            "mtd",
+            "mtext",
+            "mtr",
+            "munder",
+            "munderover",
+            "semantics",
+            "annotation",
+        ]);
+
+        config.attributes = set(&[
+            "dir",
+            "lang",
+            "title",
+            // SVG presentation attributes
+            "cx",
+            "cy",
+            "d",
+            "fill",
+            "fill-opacity",
+            "fill-rule",
+            "font-family",
+            "font-size",
+            "font-weight",
+            "gradientTransform",
+            "gradientUnits",
+            "height",
+            "offset",
+            "opacity",
+            "points",
+            "preserveAspectRatio",
+            "r",
+            "rx",
+            "ry",
+            "stop-color",
+            "stop-opacity",
+            "stroke",
+            "stroke-linecap",
+            "stroke-linejoin",
+            "stroke-opacity",
+            "stroke-width",
+            "text-anchor",
+            "transform",
+            "viewBox",
+            "width",
+            "x",
+            "x1",
+            "x2",
+            "y",
+            "y1",
+            "y2",
+            // MathML attributes
+            "accent",
+            "accentunder",
+            "columnspan",
+            "depth",
+            "display",
+            "displaystyle",
+            "encoding",
+            "fence",
+            "form",
+            "largeop",
+            "linethickness",
+            "lspace",
+            "mathbackground",
+            "mathcolor",
+            "mathsize",
+            "mathvariant",
+            "maxsize",
+            "minsize",
+            "movablelimits",
+            "rowspan",
+            "rspace",
+            "scriptlevel",
+            "separator",
+            "stretchy",
+            "symmetric",
+            "voffset",
+        ]);
+
+        let element_attributes: [(&str, &[&str]); 15] = [
+            ("a", &["href", "hreflang", "type"]),
+            ("blockquote", &["cite"]),
+            ("col", &["span"]),
+            ("colgroup", &["span"]),
+            ("data", &["value"]),
+            ("del", &["cite", "datetime"]),
+            ("ins", &["cite", "datetime"]),
+            ("li", &["value"]),
+            ("ol", &["reversed", "start", "type"]),
+            ("q", &["cite"]),
+            ("td", &["colspan", "headers", "rowspan"]),
+            ("th", &["abbr", "colspan", "headers", "rowspan", "scope"]),
+            ("time", &["datetime"]),
+            ("mtd", &["columnspan", "rowspan"]),
+            ("annotation", &["encoding"]),
+        ];
+        config.element_attributes = element_attributes
+            .iter()
+            .map(|(element, attributes)| (element.to_string(), set(attributes)))
+            .collect();
+
+        config.data_attributes = true;
+        config
+    }
+}
+
+/// What happens to an element during sanitizing
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Action {
+    /// Keep the element (its attributes and children are sanitized)
+    Keep,
+    /// Replace the element by its (sanitized) children
+    Unwrap,
+    /// Remove the element together with its children
+    Remove,
+}
+
+/// Removes everything from HTML that is not allowed by its configuration
+pub struct Sanitizer {
+    config: SanitizerConfig,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self::new(SanitizerConfig::default())
+    }
+}
+
+impl Sanitizer {
+    /// Creates a new sanitizer with the given configuration
+    pub fn new(config: SanitizerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns the configuration of the sanitizer
+    pub fn config(&self) -> &SanitizerConfig {
+        &self.config
+    }
+
+    /// Parses the HTML as a body fragment, sanitizes it and serializes the result
+    pub fn sanitize(&self, html: &str) -> String {
+        let mut stream = InputStream::new();
+        stream.read_from_str(html, Some(Encoding::UTF8));
+
+        let mut parser = Html5Parser::new(&mut stream);
+        let mut document = Document::shared();
+        let Ok((root_id, _)) = parser.parse_fragment(Document::clone(&document), "body") else {
+            return String::new();
+        };
+
+        let mut binding = document.get_mut();
+        self.sanitize_children(&mut binding, root_id);
+        binding.inner_html(root_id)
+    }
+
+    /// Sanitizes the descendants of the node in place
+    pub fn sanitize_children(&self, document: &mut Document, parent_id: NodeId) {
+        let Some(parent) = document.get_node_by_id(parent_id) else {
+            return;
+        };
+
+        for child_id in parent.children.clone() {
+            let Some(child) = document.get_node_by_id(child_id) else {
+                continue;
+            };
+
+            let action = match &child.data {
+                NodeData::Text(_) => Action::Keep,
+                NodeData::Comment(_) if self.config.comments => Action::Keep,
+                NodeData::Element(_) => {
+                    let parent = document.get_node_by_id(parent_id);
+                    self.element_action(document, child, parent)
+                }
+                _ => Action::Remove,
+            };
+            let is_element = matches!(child.data, NodeData::Element(_));
+
+            match action {
+                Action::Keep => {
+                    if is_element {
+                        self.sanitize_attributes(document, child_id);
+                        self.sanitize_children(document, child_id);
+                    }
+                }
+                Action::Unwrap => {
+                    self.sanitize_children(document, child_id);
+                    let grandchildren = document
+                        .get_node_by_id(child_id)
+                        .map(|node| node.children.clone())
+                        .unwrap_or_default();
+                    for grandchild_id in grandchildren {
+                        document.insert_before(parent_id, grandchild_id, Some(child_id));
+                    }
+                    document.remove_child(parent_id, child_id);
+                }
+                Action::Remove => {
+                    document.remove_child(parent_id, child_id);
+                }
+            }
+        }
+    }
+
+    /// Decides what happens to the element, based on its name and the parent it ended up in
+    fn element_action(&self, document: &Document, node: &Node, parent: Option<&Node>) -> Action {
+        let name = node.name.as_str();
+        if UNSAFE_ELEMENTS.contains(&name.to_ascii_lowercase().as_str()) {
+            return Action::Remove;
+        }
+        if !has_valid_namespace(node, parent) || has_markup_in_raw_text(document, node) {
+            return Action::Remove;
+        }
+
+        match node.namespace.as_deref() {
+            Some(HTML_NAMESPACE) if self.config.html_elements.contains(name) => Action::Keep,
+            Some(HTML_NAMESPACE) if self.config.remove_elements.contains(name) => Action::Remove,
+            Some(HTML_NAMESPACE) => Action::Unwrap,
+            Some(SVG_NAMESPACE) if self.config.svg_elements.contains(name) => Action::Keep,
+            Some(MATHML_NAMESPACE) if self.config.mathml_elements.contains(name) => Action::Keep,
+            _ => Action::Remove,
+        }
+    }
+
+    fn sanitize_attributes(&self, document: &mut Document, node_id: NodeId) {
+        let Some(node) = document.get_node_by_id(node_id) else {
+            return;
+        };
+        let NodeData::Element(element) = &node.data else {
+            return;
+        };
+
+        let disallowed: Vec<String> = element
+            .attributes
+            .iter()
+            .filter(|(name, value)| !self.is_allowed_attribute(node, name, value))
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in disallowed {
+            document.remove_attribute(node_id, &name);
+        }
+    }
+
+    fn is_allowed_attribute(&self, node: &Node, name: &str, value: &str) -> bool {
+        if name.to_ascii_lowercase().starts_with("on") {
+            return false;
+        }
+
+        let allowed = self.config.attributes.contains(name)
+            || self
+                .config
+                .element_attributes
+                .get(&node.name)
+                .is_some_and(|attributes| attributes.contains(name))
+            || (self.config.data_attributes
+                && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+                && is_data_attribute(name));
+        if !allowed {
+            return false;
+        }
+
+        !self.config.url_attributes.contains(name) || self.is_allowed_url(value)
+    }
+
+    /// Returns true when the URL is relative, or has a scheme that is allowed
+    fn is_allowed_url(&self, value: &str) -> bool {
+        match Url::parse(value) {
+            Ok(url) => self.config.url_schemes.contains(url.scheme()),
+            Err(_) => !has_scheme(value),
+        }
+    }
+}
+
+/// Returns true when the attribute name is a valid data-* attribute name
+fn is_data_attribute(name: &str) -> bool {
+    name.strip_prefix("data-")
+        .is_some_and(|rest| !rest.is_empty() && !rest.contains(|c: char| c.is_ascii_uppercase()))
+}
+
+/// Returns true when the (unparsable) URL starts with something that looks like a scheme. Such
+/// a URL is never treated as relative.
+fn has_scheme(value: &str) -> bool {
+    let value: String = value
+        .trim_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    let Some((scheme, _)) = value.split_once(':') else {
+        return false;
+    };
+    !scheme.is_empty()
+        && !scheme.contains(['/', '?', '#'])
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Returns true when the parser could have created the element inside its parent. A missing
+/// parent is treated like an HTML parent.
+fn has_valid_namespace(node: &Node, parent: Option<&Node>) -> bool {
+    let parent_namespace = parent
+        .and_then(|parent| parent.namespace.as_deref())
+        .unwrap_or(HTML_NAMESPACE);
+    let is_integration_point = parent.is_some_and(is_html_integration_point);
+
+    match node.namespace.as_deref() {
+        Some(SVG_NAMESPACE) => match parent_namespace {
+            HTML_NAMESPACE => node.name == "svg",
+            MATHML_NAMESPACE => {
+                node.name == "svg"
+                    && parent.is_some_and(|parent| {
+                        parent.name == "annotation-xml" || is_mathml_text_integration_point(parent)
+                    })
+            }
+            _ => true,
+        },
+        Some(MATHML_NAMESPACE) => match parent_namespace {
+            HTML_NAMESPACE => node.name == "math",
+            SVG_NAMESPACE => node.name == "math" && is_integration_point,
+            _ => true,
+        },
+        Some(HTML_NAMESPACE) => {
+            let parent_allows_html = match parent_namespace {
+                SVG_NAMESPACE => is_integration_point,
+                MATHML_NAMESPACE => {
+                    is_integration_point || parent.is_some_and(is_mathml_text_integration_point)
+                }
+                _ => true,
+            };
+            parent_allows_html && !FOREIGN_ONLY_ELEMENTS.contains(&node.name.as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Returns true when the element is a raw text element whose text would be parsed as markup
+/// outside of that element
+fn has_markup_in_raw_text(document: &Document, node: &Node) -> bool {
+    if node.namespace.as_deref() != Some(HTML_NAMESPACE)
+        || !RAW_TEXT_ELEMENTS.contains(&node.name.as_str())
+    {
+        return false;
+    }
+    MARKUP_RE.is_match(&document.text_content(node.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_disallowed_content() {
+        let sanitizer = Sanitizer::default();
+
+        assert_eq!(
+            sanitizer.sanitize(
+                "<p onclick=\"alert(1)\" class=\"x\" data-id=\"1\" title=\"t\">Hi <u>there</u>\
+                 <script>alert(1)</script><font color=\"red\">red</font><!-- c --></p>\
+                 <style>p {}</style>"
+            ),
+            "<p data-id=\"1\" title=\"t\">Hi <u>there</u>red</p>"
+        );
+        assert_eq!(
+            sanitizer.sanitize("<ul><li>a</li></ul><table><tr><td colspan=2 width=1>x</table>"),
+            "<ul><li>a</li></ul><table><tbody><tr><td colspan=\"2\" width=\"1\">x</td></tr>\
+             </tbody></table>"
+        );
+        assert_eq!(sanitizer.sanitize("a < b"), "a &lt; b");
+    }
+
+    #[test]
+    fn removes_unsafe_urls() {
+        let sanitizer = Sanitizer::default();
+
+        assert_eq!(
+            sanitizer.sanitize(
+                "<a href=\"javascript:alert(1)\">1</a><a href=\" JaVa&#x0A;ScRiPt:alert(1)\">2</a>\
+                 <a href=\"data:text/html,x\">3</a><a href=\"/relative?q\">4</a>\
+                 <a href=\"https://example.com/\">5</a><a href=\"mailto:a@example.com\">6</a>"
+            ),
+            "<a>1</a><a>2</a><a>3</a><a href=\"/relative?q\">4</a>\
+             <a href=\"https://example.com/\">5</a><a href=\"mailto:a@example.com\">6</a>"
+        );
+        assert_eq!(
+            sanitizer.sanitize("<svg><a xlink:href=\"javascript:alert(1)\" href=\"#x\"></a></svg>"),
+            "<svg><a href=\"#x\"></a></svg>"
+        );
+        assert!(sanitizer.is_allowed_url("page.html"));
+        assert!(!sanitizer.is_allowed_url("vbscript:msgbox"));
+        assert!(!sanitizer.is_allowed_url("http://[::1"));
+    }
+
+    #[test]
+    fn namespace_confusion() {
+        let sanitizer = Sanitizer::default();
+
+        assert_eq!(
+            sanitizer.sanitize("<svg><style><img src=x onerror=alert(1)></style></svg>"),
+            "<svg></svg>"
+        );
+        assert_eq!(
+            sanitizer.sanitize(
+                "<form><math><mtext></form><form><mglyph><style></math>\
+                 <img src onerror=alert(1)>"
+            ),
+            "<math><mtext></mtext></math>"
+        );
+        assert_eq!(
+            sanitizer.sanitize(
+                "<math><mi><b>x</b></mi><mo><svg><circle r=\"1\"></circle></svg></mo></math>"
+            ),
+            "<math><mi><b>x</b></mi><mo><svg><circle r=\"1\"></circle></svg></mo></math>"
+        );
+        assert_eq!(
+            sanitizer.sanitize("<svg><p>breakout</p><desc><i>ok</i></desc></svg>"),
+            "<svg></svg><p>breakout</p>"
+        );
+    }
+
+    #[test]
+    fn custom_config() {
+        let mut config = SanitizerConfig::empty();
+        config.html_elements = set(&["img", "style", "p"]);
+        config
+            .element_attributes
+            .insert("img".into(), set(&["src", "alt"]));
+        config.url_schemes = set(&["https"]);
+        config.comments = true;
+        let sanitizer = Sanitizer::new(config);
+
+        assert_eq!(
+            sanitizer.sanitize(
+                "<p><img src=\"https://example.com/a.png\" alt=\"a\" width=\"1\"><img src=\"http://x\">\
+                 <!--c--></p><style>p { color: red }</style><style></style><b>b</b>"
+            ),
+            "<p><img alt=\"a\" src=\"https://example.com/a.png\"><img><!--c--></p>\
+             <style>p { color: red }</style><style></style>b"
+        );
+        assert_eq!(
+            sanitizer.sanitize("<style>a{}</style>"),
+            "<style>a{}</style>"
+        );
+    }
+}
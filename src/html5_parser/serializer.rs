@@ -0,0 +1,3 @@
+//! Serializers that turn a parsed document into other representations, and back.
+pub mod html;
+pub mod json;
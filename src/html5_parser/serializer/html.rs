@@ -0,0 +1,164 @@
+//! HTML serialization of documents and nodes
+//! (<https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments>)
+//!
+//! Attributes are stored in a hash map, so their original order is lost. They are serialized in
+//! alphabetical order to keep the output stable.
+use crate::html5_parser::node::{Node, NodeData, NodeId, DOCTYPE_PREFIX, HTML_NAMESPACE};
+use crate::html5_parser::parser::document::Document;
+
+/// Elements that have no end tag and cannot have children
+static VOID_ELEMENTS: [&str; 18] = [
+    "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr", "img", "input",
+    "keygen", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose text content is serialized without escaping
+static RAW_TEXT_ELEMENTS: [&str; 8] = [
+    "style",
+    "script",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "plaintext",
+    "noscript",
+];
+
+impl Document {
+    /// Serializes the whole document to HTML
+    pub fn to_html(&self) -> String {
+        self.inner_html(NodeId::root())
+    }
+
+    /// Serializes the node itself and its descendants to HTML
+    pub fn outer_html(&self, node_id: NodeId) -> String {
+        let mut output = String::new();
+        if let Some(node) = self.get_node_by_id(node_id) {
+            self.serialize_node(node, &mut output);
+        }
+        output
+    }
+
+    /// Serializes the descendants of the node to HTML
+    pub fn inner_html(&self, node_id: NodeId) -> String {
+        let mut output = String::new();
+        if let Some(node) = self.get_node_by_id(node_id) {
+            self.serialize_children(node, &mut output);
+        }
+        output
+    }
+
+    fn serialize_children(&self, node: &Node, output: &mut String) {
+        for child_id in &node.children {
+            if let Some(child) = self.get_node_by_id(*child_id) {
+                self.serialize_node(child, output);
+            }
+        }
+    }
+
+    fn serialize_node(&self, node: &Node, output: &mut String) {
+        match &node.data {
+            NodeData::Document(_) | NodeData::ShadowRoot(_) => {
+                self.serialize_children(node, output)
+            }
+            NodeData::Comment(comment) => {
+                output.push_str("<!--");
+                output.push_str(comment.value());
+                output.push_str("-->");
+            }
+            NodeData::Text(text) => {
+                let is_raw_text = node
+                    .parent
+                    .and_then(|parent_id| self.get_node_by_id(parent_id))
+                    .is_some_and(|parent| {
+                        parent.namespace.as_deref() == Some(HTML_NAMESPACE)
+                            && RAW_TEXT_ELEMENTS.contains(&parent.name.as_str())
+                    });
+                if is_raw_text {
+                    output.push_str(text.value());
+                } else {
+                    output.push_str(&escape(text.value(), false));
+                }
+            }
+            NodeData::Element(element) => {
+                if let Some(name) = element.name.strip_prefix(DOCTYPE_PREFIX) {
+                    output.push_str("<!DOCTYPE ");
+                    output.push_str(name.trim_start());
+                    output.push('>');
+                    return;
+                }
+
+                output.push('<');
+                output.push_str(&node.name);
+
+                let mut attributes: Vec<_> = element.attributes.iter().collect();
+                attributes.sort();
+                for (name, value) in attributes {
+                    output.push(' ');
+                    output.push_str(name);
+                    output.push_str("=\"");
+                    output.push_str(&escape(value, true));
+                    output.push('"');
+                }
+                output.push('>');
+
+                let is_html = node.namespace.as_deref() == Some(HTML_NAMESPACE);
+                if is_html && VOID_ELEMENTS.contains(&node.name.as_str()) {
+                    return;
+                }
+
+                self.serialize_children(node, output);
+
+                output.push_str("</");
+                output.push_str(&node.name);
+                output.push('>');
+            }
+        }
+    }
+}
+
+/// Escapes a string for use in text or attribute values
+/// (<https://html.spec.whatwg.org/multipage/parsing.html#escapingString>)
+pub fn escape(value: &str, attribute_mode: bool) -> String {
+    let mut output = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '\u{A0}' => output.push_str("&nbsp;"),
+            '"' if attribute_mode => output.push_str("&quot;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html5_parser::node::NodeId;
+    use crate::testing::parse_html;
+
+    #[test]
+    fn serialize_document() {
+        let document = parse_html(
+            "<!DOCTYPE html><p id=\"x\" class='a \"b\"'>1 &lt; 2 &amp;&nbsp;3<br><!--c--></p><style>a > b {}</style>",
+        );
+        assert_eq!(
+            document.get().to_html(),
+            "<!DOCTYPE html><html><head></head><body><p class=\"a &quot;b&quot;\" id=\"x\">\
+             1 &lt; 2 &amp;&nbsp;3<br><!--c--></p><style>a > b {}</style></body></html>"
+        );
+    }
+
+    #[test]
+    fn inner_and_outer_html() {
+        let document = parse_html("<div id=\"d\"><span>a</span>b</div>");
+        let doc = document.get();
+        let div = doc.get_node_by_named_id("d").expect("div").id;
+
+        assert_eq!(doc.inner_html(div), "<span>a</span>b");
+        assert_eq!(doc.outer_html(div), "<div id=\"d\"><span>a</span>b</div>");
+        assert_eq!(doc.outer_html(NodeId::from(9999)), "");
+    }
+}
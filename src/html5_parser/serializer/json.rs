@@ -0,0 +1,418 @@
+//! JSON representation of a document
+//!
+//! Nodes in the document arena point back to their document and parents, so they cannot be
+//! serialized directly. Instead, the tree is converted into the plain structures below, which
+//! only contain the information needed to rebuild the document: node types, names, namespaces,
+//! attributes, text, the doctype and template contents.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "document_type": "html",
+//!   "quirks_mode": "no-quirks",
+//!   "children": [
+//!     { "type": "doctype", "name": "html" },
+//!     { "type": "element", "name": "html", "namespace": "http://www.w3.org/1999/xhtml",
+//!       "attributes": {}, "children": [ ... ] }
+//!   ]
+//! }
+//! ```
+use crate::html5_parser::dom::{ShadowRootInit, ShadowRootMode, SlotAssignmentMode};
+use crate::html5_parser::element_class::ElementClass;
+use crate::html5_parser::node::{Node, NodeData, NodeId, DOCTYPE_PREFIX, HTML_NAMESPACE};
+use crate::html5_parser::parser::document::{Document, DocumentHandle, DocumentType};
+use crate::html5_parser::parser::quirks::QuirksMode;
+use crate::types::{Error, Result};
+use crate::url::Url;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version of the JSON format. Increase when the format changes in an incompatible way.
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+/// Serializable representation of a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonDocument {
+    pub version: u32,
+    pub document_type: String,
+    pub quirks_mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub children: Vec<JsonNode>,
+}
+
+/// Serializable representation of a single node and its children
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum JsonNode {
+    Doctype {
+        name: String,
+    },
+    Element {
+        name: String,
+        namespace: Option<String>,
+        attributes: BTreeMap<String, String>,
+        children: Vec<JsonNode>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        template_contents: Option<Vec<JsonNode>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        shadow_root: Option<JsonShadowRoot>,
+    },
+    Text {
+        value: String,
+    },
+    Comment {
+        value: String,
+    },
+}
+
+/// Serializable representation of a shadow root attached to an element
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonShadowRoot {
+    pub mode: String,
+    pub delegates_focus: bool,
+    pub slot_assignment: String,
+    pub clonable: bool,
+    pub serializable: bool,
+    pub children: Vec<JsonNode>,
+}
+
+impl Document {
+    /// Converts the document into its serializable representation
+    pub fn to_json_document(&self) -> JsonDocument {
+        JsonDocument {
+            version: JSON_FORMAT_VERSION,
+            document_type: match self.doctype {
+                DocumentType::HTML => "html",
+                DocumentType::IframeSrcDoc => "iframe-srcdoc",
+            }
+            .to_string(),
+            quirks_mode: match self.quirks_mode {
+                QuirksMode::Quirks => "quirks",
+                QuirksMode::LimitedQuirks => "limited-quirks",
+                QuirksMode::NoQuirks => "no-quirks",
+            }
+            .to_string(),
+            url: self.url().map(|url| url.href()),
+            children: self.json_children(&self.get_root().children),
+        }
+    }
+
+    /// Serializes the document to a JSON string
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.to_json_document())?)
+    }
+
+    /// Serializes the document to an indented JSON string
+    pub fn to_json_pretty(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.to_json_document())?)
+    }
+
+    /// Rebuilds a document from a JSON string generated by `to_json()`
+    pub fn from_json(json: &str) -> Result<DocumentHandle> {
+        let json_document: JsonDocument = serde_json::from_str(json)?;
+        Self::from_json_document(&json_document)
+    }
+
+    /// Rebuilds a document from its serializable representation
+    pub fn from_json_document(json_document: &JsonDocument) -> Result<DocumentHandle> {
+        if json_document.version != JSON_FORMAT_VERSION {
+            return Err(Error::Parse(format!(
+                "unsupported json document version {}",
+                json_document.version
+            )));
+        }
+
+        let mut document = Document::shared();
+        let root = Document::clone(&document);
+        document.get_mut().create_root(&root);
+
+        {
+            let mut doc = document.get_mut();
+            doc.doctype = match json_document.document_type.as_str() {
+                "html" => DocumentType::HTML,
+                "iframe-srcdoc" => DocumentType::IframeSrcDoc,
+                other => return Err(Error::Parse(format!("unknown document type: {}", other))),
+            };
+            doc.quirks_mode = match json_document.quirks_mode.as_str() {
+                "quirks" => QuirksMode::Quirks,
+                "limited-quirks" => QuirksMode::LimitedQuirks,
+                "no-quirks" => QuirksMode::NoQuirks,
+                other => return Err(Error::Parse(format!("unknown quirks mode: {}", other))),
+            };
+            if let Some(url) = &json_document.url {
+                doc.set_url(Url::parse(url)?);
+            }
+        }
+
+        for child in &json_document.children {
+            build_node(&mut document, NodeId::root(), child)?;
+        }
+
+        Ok(document)
+    }
+
+    fn json_children(&self, children: &[NodeId]) -> Vec<JsonNode> {
+        children
+            .iter()
+            .filter_map(|id| self.get_node_by_id(*id))
+            .filter_map(|node| self.json_node(node))
+            .collect()
+    }
+
+    /// Converts a single node and its children into its serializable representation. Returns None
+    /// for nodes that are not part of the tree itself (documents and shadow roots).
+    pub(crate) fn json_node(&self, node: &Node) -> Option<JsonNode> {
+        match &node.data {
+            NodeData::Document(_) | NodeData::ShadowRoot(_) => None,
+            NodeData::Text(text) => Some(JsonNode::Text {
+                value: text.value().to_string(),
+            }),
+            NodeData::Comment(comment) => Some(JsonNode::Comment {
+                value: comment.value().to_string(),
+            }),
+            NodeData::Element(element) => {
+                if let Some(name) = element.name.strip_prefix(DOCTYPE_PREFIX) {
+                    return Some(JsonNode::Doctype {
+                        name: name.trim_start().to_string(),
+                    });
+                }
+
+                let attributes = element
+                    .attributes
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+
+                // The parser stores the contents of a template as its children
+                let is_template =
+                    node.name == "template" && node.namespace.as_deref() == Some(HTML_NAMESPACE);
+                let (children, template_contents) = if is_template {
+                    (vec![], Some(self.json_children(&node.children)))
+                } else {
+                    (self.json_children(&node.children), None)
+                };
+
+                let shadow_root = element.shadow_root.and_then(|shadow_root_id| {
+                    let data = self.shadow_root_data(shadow_root_id)?;
+                    let shadow_node = self.get_node_by_id(shadow_root_id)?;
+                    Some(JsonShadowRoot {
+                        mode: match data.mode {
+                            ShadowRootMode::Open => "open",
+                            ShadowRootMode::Closed => "closed",
+                        }
+                        .to_string(),
+                        delegates_focus: data.delegates_focus,
+                        slot_assignment: match data.slot_assignment {
+                            SlotAssignmentMode::Named => "named",
+                            SlotAssignmentMode::Manual => "manual",
+                        }
+                        .to_string(),
+                        clonable: data.clonable,
+                        serializable: data.serializable,
+                        children: self.json_children(&shadow_node.children),
+                    })
+                });
+
+                Some(JsonNode::Element {
+                    name: node.name.clone(),
+                    namespace: node.namespace.clone(),
+                    attributes,
+                    children,
+                    template_contents,
+                    shadow_root,
+                })
+            }
+        }
+    }
+}
+
+/// Adds the given json node (and its children) to the parent in the document and returns the id
+/// of the new node
+pub(crate) fn build_node(
+    document: &mut DocumentHandle,
+    parent_id: NodeId,
+    json: &JsonNode,
+) -> Result<NodeId> {
+    let node_id = match json {
+        JsonNode::Doctype { name } => {
+            let name = format!("{} {}", DOCTYPE_PREFIX, name);
+            let node = Node::new_element(document, &name, Default::default(), HTML_NAMESPACE);
+            document.get_mut().add_node(node, parent_id)
+        }
+        JsonNode::Text { value } => {
+            let node = Node::new_text(document, value);
+            document.get_mut().add_node(node, parent_id)
+        }
+        JsonNode::Comment { value } => {
+            let node = Node::new_comment(document, value);
+            document.get_mut().add_node(node, parent_id)
+        }
+        JsonNode::Element {
+            name,
+            namespace,
+            attributes,
+            children,
+            template_contents,
+            shadow_root,
+        } => {
+            let mut node = Node::new_element(
+                document,
+                name,
+                attributes.clone().into_iter().collect(),
+                namespace.as_deref().unwrap_or(HTML_NAMESPACE),
+            );
+            node.namespace = namespace.clone();
+            if let NodeData::Element(element) = &mut node.data {
+                if let Some(class_string) = attributes.get("class") {
+                    element.classes = ElementClass::from_string(class_string);
+                }
+            }
+            let node_id = document.get_mut().add_node(node, parent_id);
+
+            if let Some(shadow_root) = shadow_root {
+                let mut init = ShadowRootInit::new(match shadow_root.mode.as_str() {
+                    "open" => ShadowRootMode::Open,
+                    "closed" => ShadowRootMode::Closed,
+                    other => return Err(Error::Parse(format!("unknown shadow mode: {}", other))),
+                });
+                init.delegates_focus = shadow_root.delegates_focus;
+                init.clonable = shadow_root.clonable;
+                init.serializable = shadow_root.serializable;
+                init.slot_assignment = match shadow_root.slot_assignment.as_str() {
+                    "named" => SlotAssignmentMode::Named,
+                    "manual" => SlotAssignmentMode::Manual,
+                    other => {
+                        return Err(Error::Parse(format!("unknown slot assignment: {}", other)))
+                    }
+                };
+
+                let shadow_root_id = document.attach_shadow(node_id, init).ok_or_else(|| {
+                    Error::Parse(format!("cannot attach shadow root to <{}>", name))
+                })?;
+                for child in &shadow_root.children {
+                    build_node(document, shadow_root_id, child)?;
+                }
+            }
+
+            for child in children.iter().chain(template_contents.iter().flatten()) {
+                build_node(document, node_id, child)?;
+            }
+
+            node_id
+        }
+    };
+
+    Ok(node_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_html;
+
+    #[test]
+    fn serialize_document() {
+        let document = parse_html("<!DOCTYPE html><p class=\"a\" id=\"x\">hello<!-- note --></p>");
+        let json = document.get().to_json_document();
+
+        assert_eq!(json.version, JSON_FORMAT_VERSION);
+        assert_eq!(json.quirks_mode, "no-quirks");
+        assert_eq!(
+            json.children[0],
+            JsonNode::Doctype {
+                name: "html".to_string()
+            }
+        );
+
+        let JsonNode::Element { name, children, .. } = &json.children[1] else {
+            panic!()
+        };
+        assert_eq!(name, "html");
+        let JsonNode::Element { children, .. } = &children[1] else {
+            panic!()
+        };
+        let JsonNode::Element {
+            name,
+            attributes,
+            children,
+            ..
+        } = &children[0]
+        else {
+            panic!()
+        };
+        assert_eq!(name, "p");
+        assert_eq!(attributes.get("class").unwrap(), "a");
+        assert_eq!(
+            children,
+            &vec![
+                JsonNode::Text {
+                    value: "hello".to_string()
+                },
+                JsonNode::Comment {
+                    value: " note ".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn json_format() {
+        let document = parse_html("<div title=\"t\">x</div>");
+        let value: serde_json::Value =
+            serde_json::from_str(&document.get().to_json().unwrap()).unwrap();
+
+        let div = &value["children"][0]["children"][1]["children"][0];
+        assert_eq!(div["type"], "element");
+        assert_eq!(div["name"], "div");
+        assert_eq!(div["namespace"], HTML_NAMESPACE);
+        assert_eq!(div["attributes"]["title"], "t");
+        assert_eq!(div["children"][0]["type"], "text");
+        assert_eq!(div["children"][0]["value"], "x");
+        assert!(div.get("template_contents").is_none());
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut document = parse_html(
+            "<!DOCTYPE html><html><head><title>t</title></head>\
+             <body><div id=\"main\" class=\"one two\"><template><b>tpl</b></template>\
+             <svg><circle r=\"1\"></circle></svg></div></body></html>",
+        );
+        document
+            .get_mut()
+            .set_url(Url::parse("https://example.com/").unwrap());
+        let json = document.get().to_json().unwrap();
+
+        let loaded = Document::from_json(&json).unwrap();
+        assert_eq!(loaded.get().to_json().unwrap(), json);
+
+        let doc = loaded.get();
+        assert_eq!(doc.url().unwrap().href(), "https://example.com/");
+        let main = doc.get_node_by_named_id("main").unwrap();
+        let NodeData::Element(element) = &main.data else {
+            panic!()
+        };
+        assert!(element.classes.contains("two"));
+    }
+
+    #[test]
+    fn roundtrip_shadow_root() {
+        let document =
+            parse_html("<div><template shadowrootmode=\"closed\"><slot></slot></template>x</div>");
+        let json = document.get().to_json().unwrap();
+        assert!(json.contains("\"shadow_root\""));
+
+        let loaded = Document::from_json(&json).unwrap();
+        assert_eq!(loaded.get().to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert!(Document::from_json("{").is_err());
+        assert!(Document::from_json(
+            r#"{"version": 99, "document_type": "html", "quirks_mode": "quirks", "children": []}"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,762 @@
+//! Plain-text rendering of documents, similar to `lynx -dump`
+//!
+//! Block-level elements start on a new line, and paragraphs, headings, lists and tables are
+//! separated by an empty line. Inline content is whitespace-collapsed and wrapped to the
+//! configured width, except inside preformatted elements, which are kept as-is. Lists get bullets
+//! or numbers, tables are laid out in columns, and content that is not rendered (like scripts,
+//! styles and hidden elements) is skipped.
+//!
+//! Hyperlinks are numbered in the text (`[1]link`) and listed with their resolved URLs in a
+//! references section at the bottom.
+use crate::html5_parser::node::{Node, NodeData, NodeId, HTML_NAMESPACE};
+use crate::html5_parser::parser::document::Document;
+use crate::url::Url;
+use std::mem::take;
+
+/// Number of spaces lists are indented with
+const LIST_INDENT: usize = 2;
+
+/// Number of spaces blockquotes and definitions are indented with
+const BLOCK_INDENT: usize = 4;
+
+/// Number of spaces between table columns
+const COLUMN_GAP: usize = 2;
+
+/// Bullets of unordered lists, by nesting level
+static BULLETS: [&str; 4] = ["*", "+", "o", "#"];
+
+/// Elements that are separated from their surroundings by an empty line
+static SPACED_ELEMENTS: [&str; 11] = [
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "dl", "figure", "hr", "table",
+];
+
+/// Options for rendering text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRenderOptions {
+    /// Maximum number of characters on a line. Words that are longer than this, and
+    /// preformatted text, can still exceed it.
+    pub width: usize,
+    /// Add a list of the numbered links at the bottom
+    pub references: bool,
+}
+
+impl Default for TextRenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            references: true,
+        }
+    }
+}
+
+/// The rendered text of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedText {
+    /// The rendered text, including the references section (when enabled)
+    pub text: String,
+    /// The URLs of the links, in order of their numbers (link `[1]` is the first entry). URLs
+    /// that cannot be resolved are kept as they are written in the document.
+    pub links: Vec<String>,
+}
+
+/// Part of the inline content of a block
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Space,
+    Break,
+}
+
+/// Lines of a rendered block, with the number of empty lines wanted before and after them
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Rendered {
+    lines: Vec<String>,
+    top_margin: usize,
+    bottom_margin: usize,
+}
+
+impl Rendered {
+    fn indent(mut self, indent: usize) -> Self {
+        for line in self.lines.iter_mut().filter(|line| !line.is_empty()) {
+            line.insert_str(0, &" ".repeat(indent));
+        }
+        self
+    }
+}
+
+/// Collects the lines of a block-level element and the inline content between them
+struct Block {
+    width: usize,
+    preformatted: bool,
+    lines: Vec<String>,
+    inline: Vec<Token>,
+    top_margin: usize,
+    pending_margin: usize,
+}
+
+impl Block {
+    fn new(width: usize, preformatted: bool) -> Self {
+        Self {
+            width: width.max(1),
+            preformatted,
+            lines: vec![],
+            inline: vec![],
+            top_margin: 0,
+            pending_margin: 0,
+        }
+    }
+
+    fn add_text(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\n' if self.preformatted => self.inline.push(Token::Break),
+                '\r' if self.preformatted => {}
+                c if c.is_ascii_whitespace() && !self.preformatted => {
+                    if self.inline.last() != Some(&Token::Space) {
+                        self.inline.push(Token::Space);
+                    }
+                }
+                c => match self.inline.last_mut() {
+                    Some(Token::Word(word)) => word.push(c),
+                    _ => self.inline.push(Token::Word(c.to_string())),
+                },
+            }
+        }
+    }
+
+    fn line_break(&mut self) {
+        self.inline.push(Token::Break);
+    }
+
+    /// Adds the lines of a child block, separated by at least the given number of empty lines
+    fn add_block(&mut self, block: Rendered, margin: usize) {
+        self.flush_inline();
+        if block.lines.is_empty() {
+            return;
+        }
+
+        let before = self.pending_margin.max(margin).max(block.top_margin);
+        if self.lines.is_empty() {
+            self.top_margin = self.top_margin.max(before);
+        } else {
+            self.lines
+                .extend(std::iter::repeat(String::new()).take(before));
+        }
+        self.lines.extend(block.lines);
+        self.pending_margin = margin.max(block.bottom_margin);
+    }
+
+    /// Wraps the inline content collected so far into lines
+    fn flush_inline(&mut self) {
+        let tokens = take(&mut self.inline);
+
+        let mut lines = vec![];
+        let mut line = String::new();
+        let mut line_length = 0;
+        let mut space = false;
+        for token in tokens {
+            match token {
+                Token::Space => space = line_length > 0,
+                Token::Word(word) => {
+                    let length = word.chars().count();
+                    let space_length = usize::from(space);
+                    if !self.preformatted
+                        && line_length > 0
+                        && line_length + space_length + length > self.width
+                    {
+                        lines.push(take(&mut line));
+                        line_length = 0;
+                        space = false;
+                    }
+                    if space {
+                        line.push(' ');
+                        line_length += 1;
+                        space = false;
+                    }
+                    line.push_str(&word);
+                    line_length += length;
+                }
+                Token::Break => {
+                    lines.push(take(&mut line));
+                    line_length = 0;
+                    space = false;
+                }
+            }
+        }
+        if line_length > 0 {
+            lines.push(line);
+        }
+
+        if !lines.is_empty() {
+            self.add_block(
+                Rendered {
+                    lines,
+                    ..Default::default()
+                },
+                0,
+            );
+        }
+    }
+
+    fn finish(mut self) -> Rendered {
+        self.flush_inline();
+        Rendered {
+            lines: self.lines,
+            top_margin: self.top_margin,
+            bottom_margin: self.pending_margin,
+        }
+    }
+}
+
+struct TextRenderer<'a> {
+    document: &'a Document,
+    /// Base URL of the document, to resolve the links against
+    base_url: Option<Url>,
+    width: usize,
+    links: Vec<String>,
+    list_depth: usize,
+}
+
+impl Document {
+    /// Renders the node and its descendants as plain text
+    pub fn render_text(&self, node_id: NodeId, options: &TextRenderOptions) -> RenderedText {
+        let mut renderer = TextRenderer {
+            document: self,
+            base_url: self.base_url(),
+            width: options.width.max(1),
+            links: vec![],
+            list_depth: 0,
+        };
+
+        let mut block = Block::new(renderer.width, false);
+        renderer.render_node(node_id, &mut block);
+        let mut lines: Vec<String> = block
+            .finish()
+            .lines
+            .into_iter()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+
+        if options.references && !renderer.links.is_empty() {
+            lines.push(String::new());
+            lines.push("References".to_string());
+            lines.push(String::new());
+            for (idx, link) in renderer.links.iter().enumerate() {
+                lines.push(format!("{:>4}. {}", idx + 1, link));
+            }
+        }
+
+        RenderedText {
+            text: lines.join("\n"),
+            links: renderer.links,
+        }
+    }
+}
+
+impl TextRenderer<'_> {
+    /// Renders the children of the node in the flat tree, so shadow trees are rendered and
+    /// slotted nodes appear in their slots
+    fn render_children(&mut self, node: &Node, block: &mut Block) {
+        for child_id in self.document.flat_tree_children(node.id) {
+            self.render_node(child_id, block);
+        }
+    }
+
+    fn render_node(&mut self, node_id: NodeId, block: &mut Block) {
+        let document = self.document;
+        let Some(node) = document.get_node_by_id(node_id) else {
+            return;
+        };
+        if !document.is_rendered(node) {
+            return;
+        }
+
+        let element = match &node.data {
+            NodeData::Text(text) => return block.add_text(text.value()),
+            NodeData::Element(element) => element,
+            NodeData::Document(_) | NodeData::ShadowRoot(_) => {
+                return self.render_children(node, block)
+            }
+            NodeData::Comment(_) => return,
+        };
+        if node.namespace.as_deref() != Some(HTML_NAMESPACE) {
+            return self.render_children(node, block);
+        }
+
+        let width = block.width;
+        match node.name.as_str() {
+            "br" => block.line_break(),
+            "img" => {
+                let alt = element.attributes.get("alt").map(|alt| alt.trim());
+                if let Some(alt) = alt.filter(|alt| !alt.is_empty()) {
+                    block.add_text(&format!("[{}]", alt));
+                }
+            }
+            "a" if element.attributes.contains("href") => {
+                let href = element.attributes.get("href").cloned().unwrap_or_default();
+                let link = Url::parse_with_base(&href, self.base_url.as_ref())
+                    .map(|url| url.href())
+                    .unwrap_or(href);
+                self.links.push(link);
+                block.add_text(&format!("[{}]", self.links.len()));
+                self.render_children(node, block);
+            }
+            "ul" | "ol" | "menu" => {
+                let margin = usize::from(self.list_depth == 0);
+                let list = self.render_list(node, width);
+                block.add_block(list, margin);
+            }
+            "table" => {
+                let table = self.render_table(node, width);
+                block.add_block(table, 1);
+            }
+            "hr" => {
+                let rule = Rendered {
+                    lines: vec!["-".repeat(width.min(self.width))],
+                    ..Default::default()
+                };
+                block.add_block(rule, 1);
+            }
+            "blockquote" => {
+                let quote = self.render_block(node, width.saturating_sub(BLOCK_INDENT), block);
+                block.add_block(quote.indent(BLOCK_INDENT), 1);
+            }
+            "dd" => {
+                let definition = self.render_block(node, width.saturating_sub(BLOCK_INDENT), block);
+                block.add_block(definition.indent(BLOCK_INDENT), 0);
+            }
+            "pre" | "listing" | "xmp" | "plaintext" | "textarea" => {
+                let mut pre = Block::new(width, true);
+                self.render_children(node, &mut pre);
+                let margin = usize::from(node.name != "textarea");
+                block.add_block(pre.finish(), margin);
+            }
+            name if SPACED_ELEMENTS.contains(&name) => {
+                let rendered = self.render_block(node, width, block);
+                block.add_block(rendered, 1);
+            }
+            _ if document.is_block_level(node) => {
+                let rendered = self.render_block(node, width, block);
+                block.add_block(rendered, 0);
+            }
+            _ => self.render_children(node, block),
+        }
+    }
+
+    /// Renders the children of the node into a new block, which is preformatted when the parent
+    /// block is
+    fn render_block(&mut self, node: &Node, width: usize, parent: &Block) -> Rendered {
+        let mut block = Block::new(width, parent.preformatted);
+        self.render_children(node, &mut block);
+        block.finish()
+    }
+
+    fn render_list(&mut self, list: &Node, width: usize) -> Rendered {
+        let document = self.document;
+        let attribute = |node: &Node, name: &str| match &node.data {
+            NodeData::Element(element) => element.attributes.get(name).cloned(),
+            _ => None,
+        };
+
+        let children = document.flat_tree_children(list.id);
+        let items: Vec<&Node> = children
+            .iter()
+            .filter_map(|id| document.get_node_by_id(*id))
+            .filter(|node| is_html_element(node, "li") && document.is_rendered(node))
+            .collect();
+        let ordered = list.name == "ol";
+        let reversed = ordered && attribute(list, "reversed").is_some();
+        let list_type = attribute(list, "type").unwrap_or_default();
+        let mut counter = attribute(list, "start")
+            .and_then(|start| start.trim().parse::<i64>().ok())
+            .unwrap_or(if reversed { items.len() as i64 } else { 1 });
+        let bullet = BULLETS[self.list_depth % BULLETS.len()];
+
+        self.list_depth += 1;
+        let mut block = Block::new(width, false);
+        for child_id in &children {
+            let Some(child) = document.get_node_by_id(*child_id) else {
+                continue;
+            };
+            if !items.iter().any(|item| item.id == child.id) {
+                self.render_node(child.id, &mut block);
+                continue;
+            }
+
+            let marker = if ordered {
+                if let Some(value) = attribute(child, "value").and_then(|v| v.trim().parse().ok()) {
+                    counter = value;
+                }
+                let marker = format!("{}.", list_marker(counter, &list_type));
+                counter += if reversed { -1 } else { 1 };
+                marker
+            } else {
+                bullet.to_string()
+            };
+
+            let prefix_length = LIST_INDENT + marker.chars().count() + 1;
+            let mut item = Block::new(width.saturating_sub(prefix_length), false);
+            self.render_children(child, &mut item);
+            let mut lines = item.finish().lines;
+            if lines.is_empty() {
+                lines.push(String::new());
+            }
+
+            let lines = lines
+                .into_iter()
+                .enumerate()
+                .map(|(idx, line)| match idx {
+                    0 => format!("{}{} {}", " ".repeat(LIST_INDENT), marker, line),
+                    _ if line.is_empty() => line,
+                    _ => format!("{}{}", " ".repeat(prefix_length), line),
+                })
+                .collect();
+            block.add_block(
+                Rendered {
+                    lines,
+                    ..Default::default()
+                },
+                0,
+            );
+        }
+        self.list_depth -= 1;
+
+        block.finish()
+    }
+
+    fn render_table(&mut self, table: &Node, width: usize) -> Rendered {
+        let document = self.document;
+        let mut block = Block::new(width, false);
+
+        for child_id in &table.children {
+            if let Some(caption) = document.get_node_by_id(*child_id) {
+                if is_html_element(caption, "caption") && document.is_rendered(caption) {
+                    let rendered = self.render_block(caption, width, &block);
+                    block.add_block(rendered, 0);
+                }
+            }
+        }
+
+        let mut rows = vec![];
+        document.collect_rows(table.id, &mut rows);
+        let grid: Vec<Vec<(&Node, usize)>> = rows
+            .iter()
+            .filter_map(|id| document.get_node_by_id(*id))
+            .map(|row| {
+                row.children
+                    .iter()
+                    .filter_map(|id| document.get_node_by_id(*id))
+                    .filter(|cell| {
+                        (is_html_element(cell, "td") || is_html_element(cell, "th"))
+                            && document.is_rendered(cell)
+                    })
+                    .map(|cell| (cell, colspan(cell)))
+                    .collect()
+            })
+            .collect();
+
+        let columns = grid
+            .iter()
+            .map(|row| row.iter().map(|(_, span)| span).sum())
+            .max()
+            .unwrap_or(0);
+        if columns == 0 {
+            return block.finish();
+        }
+
+        // Measure the cells without keeping the links they contain; they are added again when
+        // the cells are rendered for real.
+        let link_count = self.links.len();
+        let mut natural = vec![0; columns];
+        let mut minimum = vec![0; columns];
+        for row in &grid {
+            let mut column = 0;
+            for (cell, span) in row {
+                if *span == 1 {
+                    natural[column] = natural[column].max(self.measure(cell, usize::MAX));
+                    minimum[column] = minimum[column].max(self.measure(cell, 1));
+                }
+                column += span;
+            }
+        }
+        self.links.truncate(link_count);
+
+        let available = width.saturating_sub(COLUMN_GAP * (columns - 1));
+        let widths = column_widths(&natural, &minimum, available);
+
+        for row in &grid {
+            let mut cells = vec![];
+            let mut column = 0;
+            for (cell, span) in row {
+                let end = (column + span).min(columns);
+                let cell_width =
+                    widths[column..end].iter().sum::<usize>() + COLUMN_GAP * (end - column - 1);
+                let rendered = self.render_block(cell, cell_width, &block);
+                cells.push((rendered.lines, cell_width));
+                column = end;
+            }
+
+            let height = cells
+                .iter()
+                .map(|(lines, _)| lines.len())
+                .max()
+                .unwrap_or(0);
+            let lines = (0..height)
+                .map(|idx| {
+                    cells
+                        .iter()
+                        .map(|(lines, width)| {
+                            let line = lines.get(idx).map(String::as_str).unwrap_or_default();
+                            format!("{:<width$}", line, width = width)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(&" ".repeat(COLUMN_GAP))
+                        .trim_end()
+                        .to_string()
+                })
+                .collect();
+            block.add_block(
+                Rendered {
+                    lines,
+                    ..Default::default()
+                },
+                0,
+            );
+        }
+
+        block.finish()
+    }
+
+    /// Returns the length of the longest line of the cell when rendered at the width
+    fn measure(&mut self, cell: &Node, width: usize) -> usize {
+        let mut block = Block::new(width, false);
+        self.render_children(cell, &mut block);
+        block
+            .finish()
+            .lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn is_html_element(node: &Node, name: &str) -> bool {
+    matches!(node.data, NodeData::Element(_))
+        && node.name == name
+        && node.namespace.as_deref() == Some(HTML_NAMESPACE)
+}
+
+/// Returns the number of columns the cell spans
+fn colspan(cell: &Node) -> usize {
+    let NodeData::Element(element) = &cell.data else {
+        return 1;
+    };
+    element
+        .attributes
+        .get("colspan")
+        .and_then(|span| span.trim().parse::<usize>().ok())
+        .map_or(1, |span| span.clamp(1, 1000))
+}
+
+/// Divides the available width over the columns. Columns get their natural width when
+/// everything fits, and otherwise shrink towards their minimum width.
+fn column_widths(natural: &[usize], minimum: &[usize], available: usize) -> Vec<usize> {
+    let total_natural: usize = natural.iter().sum();
+    let total_minimum: usize = minimum.iter().sum();
+    if total_natural <= available {
+        return natural.to_vec();
+    }
+    if total_minimum >= available {
+        return minimum.to_vec();
+    }
+
+    let extra = available - total_minimum;
+    let flexible = total_natural - total_minimum;
+    let mut widths: Vec<usize> = natural
+        .iter()
+        .zip(minimum)
+        .map(|(natural, minimum)| minimum + (natural - minimum) * extra / flexible)
+        .collect();
+
+    // Give the width lost by rounding down to the last column that can still grow
+    let remaining = available - widths.iter().sum::<usize>();
+    if let Some(idx) = (0..widths.len())
+        .rev()
+        .find(|idx| widths[*idx] < natural[*idx])
+    {
+        widths[idx] += remaining;
+    }
+    widths
+}
+
+/// Returns the marker of an ordered list item for the list type (`1`, `a`, `A`, `i` or `I`)
+fn list_marker(value: i64, list_type: &str) -> String {
+    match list_type {
+        "a" | "A" if value > 0 => {
+            let mut value = value;
+            let mut marker = vec![];
+            while value > 0 {
+                value -= 1;
+                marker.push((b'a' + (value % 26) as u8) as char);
+                value /= 26;
+            }
+            let marker: String = marker.into_iter().rev().collect();
+            if list_type == "A" {
+                marker.to_ascii_uppercase()
+            } else {
+                marker
+            }
+        }
+        "i" | "I" if value > 0 && value < 4000 => {
+            let numerals = [
+                (1000, "m"),
+                (900, "cm"),
+                (500, "d"),
+                (400, "cd"),
+                (100, "c"),
+                (90, "xc"),
+                (50, "l"),
+                (40, "xl"),
+                (10, "x"),
+                (9, "ix"),
+                (5, "v"),
+                (4, "iv"),
+                (1, "i"),
+            ];
+            let mut value = value;
+            let mut marker = String::new();
+            for (amount, numeral) in numerals {
+                while value >= amount {
+                    marker.push_str(numeral);
+                    value -= amount;
+                }
+            }
+            if list_type == "I" {
+                marker.to_ascii_uppercase()
+            } else {
+                marker
+            }
+        }
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_html_with_url;
+
+    const DOCUMENT_URL: &str = "https://example.com/docs/index.html";
+
+    fn render(html: &str, width: usize) -> RenderedText {
+        let options = TextRenderOptions {
+            width,
+            ..Default::default()
+        };
+        parse_html_with_url(html, DOCUMENT_URL)
+            .get()
+            .render_text(NodeId::root(), &options)
+    }
+
+    #[test]
+    fn blocks_and_wrapping() {
+        let rendered = render(
+            "<head><title>Title</title><style>p {}</style></head><body>\
+             <h1>Heading</h1><p>The quick   brown fox jumps over\nthe lazy dog.</p>\
+             <div>one</div><div>two<br>three</div><script>alert(1)</script>\
+             <p hidden>hidden</p><blockquote>quoted text here</blockquote>\
+             <pre>  keep   this\n    as is</pre>tail<hr></body>",
+            20,
+        );
+        assert_eq!(
+            rendered.text,
+            "Heading\n\nThe quick brown fox\njumps over the lazy\ndog.\n\none\ntwo\nthree\n\n    \
+             quoted text here\n\n  keep   this\n    as is\n\ntail\n\n--------------------"
+        );
+        assert!(rendered.links.is_empty());
+    }
+
+    #[test]
+    fn lists() {
+        let rendered = render(
+            "<ul><li>one<li>two<ul><li>nested item</ul></ul>\
+             <ol start=\"3\"><li>three<li value=\"10\">ten</ol>\
+             <ol type=\"i\" reversed><li>a<li>b</ol>\
+             <dl><dt>Term<dd>Definition</dl>",
+            80,
+        );
+        assert_eq!(
+            rendered.text,
+            "  * one\n  * two\n      + nested item\n\n  3. three\n  10. ten\n\n  ii. a\n  i. b\n\n\
+             Term\n    Definition"
+        );
+    }
+
+    #[test]
+    fn shadow_trees() {
+        let rendered = render(
+            "<div><template shadowrootmode=open><p>Shadow content</p><slot></slot></template>\
+             <span>Light</span></div>",
+            80,
+        );
+        assert_eq!(rendered.text, "Shadow content\n\nLight");
+    }
+
+    #[test]
+    fn links_and_references() {
+        let rendered = render(
+            "<p>See <a href=\"/about\">about us</a> and <a href=\"https://other.example/\">\
+             other</a>, <a name=\"x\">not a link</a>.<img alt=\" logo \"><img src=\"x.png\"></p>",
+            80,
+        );
+        assert_eq!(
+            rendered.links,
+            vec!["https://example.com/about", "https://other.example/"]
+        );
+        assert_eq!(
+            rendered.text,
+            "See [1]about us and [2]other, not a link.[logo]\n\nReferences\n\n   \
+             1. https://example.com/about\n   2. https://other.example/"
+        );
+
+        let rendered = render(
+            "<base href=\"/sub/\"><a href=\"a\">a</a><a href=\"../b\">b</a>",
+            80,
+        );
+        assert_eq!(
+            rendered.links,
+            vec!["https://example.com/sub/a", "https://example.com/b"]
+        );
+
+        let options = TextRenderOptions {
+            references: false,
+            ..Default::default()
+        };
+        let document = parse_html_with_url("<a href=\"x\">x</a>", DOCUMENT_URL);
+        assert_eq!(
+            document.get().render_text(NodeId::root(), &options).text,
+            "[1]x"
+        );
+    }
+
+    #[test]
+    fn tables() {
+        let rendered = render(
+            "<table><caption>Caption</caption><tr><th>Name<th>Description</tr>\
+             <tr><td>a<td>short</tr><tr><td>bb<td>a much longer description of the item</tr>\
+             <tr><td colspan=2>spanning cell</tr></table>",
+            30,
+        );
+        assert_eq!(
+            rendered.text,
+            "Caption\nName  Description\na     short\nbb    a much longer\n      \
+             description of the item\nspanning cell"
+        );
+
+        assert_eq!(list_marker(28, "a"), "ab");
+        assert_eq!(list_marker(1994, "I"), "MCMXCIV");
+        assert_eq!(list_marker(-1, "i"), "-1");
+        assert_eq!(column_widths(&[10, 30], &[2, 10], 20), vec![4, 16]);
+    }
+}
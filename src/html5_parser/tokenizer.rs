@@ -73,7 +73,7 @@ impl<'stream> Tokenizer<'stream> {
         opts: Option<Options>,
         error_logger: Rc<RefCell<ErrorLogger>>,
     ) -> Self {
-        return Tokenizer {
+        Tokenizer {
             stream: input,
             state: opts.as_ref().map_or(State::DataState, |o| o.initial_state),
             last_start_token: opts.map_or(String::new(), |o| o.last_start_tag),
@@ -85,7 +85,7 @@ impl<'stream> Tokenizer<'stream> {
             current_attrs: HashMap::new(),
             temporary_buffer: String::new(),
             error_logger,
-        };
+        }
     }
 
     /// Returns the current position in the stream (with line/col number and position)
@@ -105,7 +105,7 @@ impl<'stream> Tokenizer<'stream> {
     }
 
     /// Returns the error logger
-    pub fn get_error_logger(&self) -> Ref<ErrorLogger> {
+    pub fn get_error_logger(&self) -> Ref<'_, ErrorLogger> {
         self.error_logger.borrow()
     }
 
@@ -138,7 +138,6 @@ impl TokenTrait for Token {
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
 
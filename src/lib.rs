@@ -5,5 +5,8 @@ pub mod api;
 pub mod css;
 #[allow(dead_code)]
 pub mod html5_parser;
+pub mod loader;
+pub mod mime;
 pub mod testing;
 pub mod types;
+pub mod url;
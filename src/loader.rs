@@ -0,0 +1,471 @@
+//! Loading of resources
+//!
+//! A [`ResourceLoader`] fetches the resource a URL points to and returns a [`Response`] with the
+//! status, headers, body and the final URL (which differs from the requested URL after a
+//! redirect). There are loaders for `file:`, `data:` and `http(s):` URLs, a [`SchemeLoader`] that
+//! picks a loader based on the scheme of the URL, and a [`MockLoader`] that serves resources from
+//! memory, so pages can be loaded in tests without network access.
+use crate::html5_parser::input_stream::{Confidence, Encoding, InputStream};
+use crate::loader::cookies::CookieJar;
+use crate::loader::data::DataUrl;
+use crate::mime::{self, MimeType};
+use crate::types::Result;
+use crate::url::Url;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+pub mod archive;
+pub mod cache;
+pub mod cookies;
+pub mod data;
+pub mod http;
+#[cfg(test)]
+mod test_server;
+
+pub use crate::loader::http::HttpLoader;
+
+/// User agent that is sent with HTTP requests
+pub const USER_AGENT: &str = concat!("gosub/", env!("CARGO_PKG_VERSION"));
+
+/// MIME types for file extensions, used for local files
+static MIME_TYPES: [(&str, &str); 16] = [
+    ("css", "text/css"),
+    ("gif", "image/gif"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/x-icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("txt", "text/plain"),
+    ("webp", "image/webp"),
+    ("xht", "application/xhtml+xml"),
+    ("xhtml", "application/xhtml+xml"),
+    ("xml", "text/xml"),
+];
+
+/// Reasons why a resource could not be loaded
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum LoadError {
+    #[error("unsupported scheme: {0}")]
+    UnsupportedScheme(String),
+    #[error("invalid data URL")]
+    InvalidDataUrl,
+    #[error("not a local file: {0}")]
+    InvalidFileUrl(String),
+    #[error("resource not found: {0}")]
+    NotFound(String),
+    #[error("more than {0} redirects")]
+    TooManyRedirects(usize),
+    #[error("invalid redirect location: {0}")]
+    InvalidRedirect(String),
+    #[error("unsupported content encoding: {0}")]
+    UnsupportedContentEncoding(String),
+    #[error("content could not be decoded as {0}")]
+    InvalidContent(String),
+    #[error("decoded content is larger than {0} bytes")]
+    ContentTooLarge(usize),
+    #[error("not in the cache: {0}")]
+    NotCached(String),
+    #[error("invalid archive: {0}")]
+    InvalidArchive(String),
+}
+
+/// The response to loading a resource
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    /// The final URL of the resource, after following redirects
+    pub url: Url,
+    /// The (HTTP) status code. Loaders for other schemes use 200 for success.
+    pub status: u16,
+    /// The headers in the order they were received. Names can occur multiple times.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Returns the first value of the header in the list. Names are matched case-insensitively.
+pub(crate) fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+impl Response {
+    /// Creates a successful response with the content type and body
+    pub fn ok(url: Url, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            url,
+            status: 200,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+        }
+    }
+
+    /// Returns the first value of the header. Names are matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+
+    /// Returns all values of the header, in the order they were received
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("content-type")
+    }
+
+    /// Returns true when the server opted out of content sniffing with
+    /// `X-Content-Type-Options: nosniff`
+    pub fn is_nosniff(&self) -> bool {
+        self.header("x-content-type-options")
+            .is_some_and(mime::is_nosniff)
+    }
+
+    /// Returns the computed MIME type of the body, determined from the `Content-Type` header and
+    /// the first bytes of the body
+    pub fn mime_type(&self) -> MimeType {
+        mime::computed_mime_type(self.content_type(), self.is_nosniff(), &self.body)
+    }
+
+    /// Returns an input stream with the body for the HTML parser. A byte order mark or the charset
+    /// of the `Content-Type` header sets the encoding with certain confidence. Otherwise the body
+    /// is read as UTF-8 with tentative confidence.
+    pub fn input_stream(&self) -> InputStream {
+        let (body, encoding) = match self.body.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+            Some(body) => (body, Some(Encoding::UTF8)),
+            None => (
+                self.body.as_slice(),
+                self.content_type()
+                    .and_then(MimeType::parse)
+                    .and_then(|mime_type| mime_type.charset().and_then(Encoding::from_label)),
+            ),
+        };
+
+        let mut stream = InputStream::new();
+        stream.read_from_bytes(body, encoding);
+        stream.set_confidence(match encoding {
+            Some(_) => Confidence::Certain,
+            None => Confidence::Tentative,
+        });
+        stream
+    }
+
+    /// Returns true for 2xx status codes
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Returns the body as a string. Invalid UTF-8 sequences are replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Loads the resource a URL points to
+pub trait ResourceLoader {
+    fn load(&self, url: &Url) -> Result<Response>;
+
+    /// Loads the resource and sends the extra request headers, like the headers of a conditional
+    /// request. Loaders that do not send headers ignore them.
+    fn load_with_headers(&self, url: &Url, _headers: &[(String, String)]) -> Result<Response> {
+        self.load(url)
+    }
+
+    /// Returns the headers that are sent with a request to the URL, which is needed to match the
+    /// `Vary` header of cached responses
+    fn request_headers(&self, _url: &Url) -> Vec<(String, String)> {
+        vec![]
+    }
+}
+
+/// Loads `file:` URLs from the local file system
+#[derive(Debug, Default)]
+pub struct FileLoader;
+
+impl ResourceLoader for FileLoader {
+    fn load(&self, url: &Url) -> Result<Response> {
+        let path = url
+            .to_file_path()
+            .ok_or_else(|| LoadError::InvalidFileUrl(url.href()))?;
+        let body = fs::read(&path)?;
+
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let content_type = MIME_TYPES
+            .iter()
+            .find(|(known, _)| *known == extension)
+            .map_or("application/octet-stream", |(_, mime_type)| mime_type);
+
+        Ok(Response::ok(url.clone(), content_type, body))
+    }
+}
+
+/// Loads `data:` URLs
+#[derive(Debug, Default)]
+pub struct DataLoader;
+
+impl ResourceLoader for DataLoader {
+    fn load(&self, url: &Url) -> Result<Response> {
+        let data = DataUrl::process(url).ok_or(LoadError::InvalidDataUrl)?;
+        Ok(Response::ok(url.clone(), &data.mime_type, data.body))
+    }
+}
+
+/// Dispatches to a loader based on the scheme of the URL
+pub struct SchemeLoader {
+    loaders: HashMap<String, Box<dyn ResourceLoader>>,
+}
+
+impl SchemeLoader {
+    /// Creates a loader without any schemes
+    pub fn new() -> Self {
+        Self {
+            loaders: HashMap::new(),
+        }
+    }
+
+    /// Creates a loader for `data:`, `file:`, `http:` and `https:` URLs. HTTP requests use the
+    /// cookie jar.
+    pub fn with_cookies(cookies: Rc<RefCell<CookieJar>>) -> Self {
+        let mut loader = Self::new();
+        loader.register("data", Box::<DataLoader>::default());
+        loader.register("file", Box::<FileLoader>::default());
+        loader.register("http", Box::new(HttpLoader::new(Rc::clone(&cookies))));
+        loader.register("https", Box::new(HttpLoader::new(cookies)));
+        loader
+    }
+
+    /// Registers the loader for the scheme, replacing the current one
+    pub fn register(&mut self, scheme: &str, loader: Box<dyn ResourceLoader>) {
+        self.loaders.insert(scheme.to_ascii_lowercase(), loader);
+    }
+}
+
+impl Default for SchemeLoader {
+    /// Creates a loader for `data:`, `file:`, `http:` and `https:` URLs, with an empty cookie jar
+    fn default() -> Self {
+        Self::with_cookies(Rc::default())
+    }
+}
+
+impl SchemeLoader {
+    fn loader(&self, url: &Url) -> Result<&dyn ResourceLoader> {
+        match self.loaders.get(url.scheme()) {
+            Some(loader) => Ok(loader.as_ref()),
+            None => Err(LoadError::UnsupportedScheme(url.scheme().to_string()).into()),
+        }
+    }
+}
+
+impl ResourceLoader for SchemeLoader {
+    fn load(&self, url: &Url) -> Result<Response> {
+        self.loader(url)?.load(url)
+    }
+
+    fn load_with_headers(&self, url: &Url, headers: &[(String, String)]) -> Result<Response> {
+        self.loader(url)?.load_with_headers(url, headers)
+    }
+
+    fn request_headers(&self, url: &Url) -> Vec<(String, String)> {
+        self.loader(url)
+            .map(|loader| loader.request_headers(url))
+            .unwrap_or_default()
+    }
+}
+
+/// Serves responses from memory. Fragments are ignored when looking up URLs. All loaded URLs
+/// are recorded, so tests can check which resources were requested.
+#[derive(Debug, Default)]
+pub struct MockLoader {
+    responses: HashMap<String, Response>,
+    requests: RefCell<Vec<Url>>,
+}
+
+impl MockLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a response, which is served for its URL
+    pub fn add(&mut self, response: Response) {
+        self.responses
+            .insert(response.url.without_fragment().href(), response);
+    }
+
+    /// Adds an HTML page for the URL
+    pub fn add_html(&mut self, url: &str, html: &str) -> Result<()> {
+        let url = Url::parse(url)?;
+        self.add(Response::ok(url, "text/html", html.as_bytes().to_vec()));
+        Ok(())
+    }
+
+    /// Returns the URLs that were loaded, in order
+    pub fn requests(&self) -> Vec<Url> {
+        self.requests.borrow().clone()
+    }
+}
+
+impl ResourceLoader for MockLoader {
+    fn load(&self, url: &Url) -> Result<Response> {
+        self.requests.borrow_mut().push(url.clone());
+        self.responses
+            .get(&url.without_fragment().href())
+            .cloned()
+            .ok_or_else(|| LoadError::NotFound(url.href()).into())
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch
+pub(crate) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Error;
+    use std::io::Write;
+
+    #[test]
+    fn file_and_data_loaders() {
+        let path = std::env::temp_dir().join(format!("gosub-loader-{}.html", uuid::Uuid::new_v4()));
+        fs::File::create(&path)
+            .and_then(|mut file| file.write_all(b"<p>file</p>"))
+            .unwrap();
+
+        let loader = SchemeLoader::default();
+        let url = Url::from_file_path(&path).unwrap();
+        let response = loader.load(&url).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type(), Some("text/html"));
+        assert_eq!(response.text(), "<p>file</p>");
+        assert_eq!(response.url, url);
+        assert!(matches!(loader.load(&url), Err(Error::IO(_))));
+
+        let response = loader
+            .load(&Url::parse("data:text/css;base64,cHtjb2xvcjpyZWR9").unwrap())
+            .unwrap();
+        assert_eq!(response.header("CONTENT-TYPE"), Some("text/css"));
+        assert_eq!(response.text(), "p{color:red}");
+        assert!(matches!(
+            loader.load(&Url::parse("data:nocomma").unwrap()),
+            Err(Error::Load(LoadError::InvalidDataUrl))
+        ));
+
+        assert!(matches!(
+            loader.load(&Url::parse("gopher://example.com/").unwrap()),
+            Err(Error::Load(LoadError::UnsupportedScheme(scheme))) if scheme == "gopher"
+        ));
+    }
+
+    #[test]
+    fn mock_loader() {
+        let mut mock = MockLoader::new();
+        mock.add_html("https://example.com/", "<h1>Home</h1>")
+            .unwrap();
+        mock.add(Response {
+            url: Url::parse("https://example.com/missing").unwrap(),
+            status: 404,
+            headers: vec![
+                ("Set-Cookie".to_string(), "a=1".to_string()),
+                ("set-cookie".to_string(), "b=2".to_string()),
+            ],
+            body: vec![],
+        });
+
+        let mut loader = SchemeLoader::new();
+        loader.register("https", Box::new(mock));
+
+        let response = loader
+            .load(&Url::parse("https://example.com/#top").unwrap())
+            .unwrap();
+        assert!(response.is_success());
+        assert_eq!(response.text(), "<h1>Home</h1>");
+
+        let response = loader
+            .load(&Url::parse("https://example.com/missing").unwrap())
+            .unwrap();
+        assert!(!response.is_success());
+        assert_eq!(response.header_values("set-cookie"), vec!["a=1", "b=2"]);
+
+        assert!(matches!(
+            loader.load(&Url::parse("https://example.com/other").unwrap()),
+            Err(Error::Load(LoadError::NotFound(_)))
+        ));
+        assert!(matches!(
+            loader.load(&Url::parse("http://example.com/").unwrap()),
+            Err(Error::Load(LoadError::UnsupportedScheme(_)))
+        ));
+    }
+
+    #[test]
+    fn sniffed_mime_type_and_encoding() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let mut response = Response::ok(url, "text/plain", b"\x89PNG\r\n\x1a\n".to_vec());
+        assert_eq!(response.mime_type().essence(), "image/png");
+
+        response
+            .headers
+            .push(("X-Content-Type-Options".to_string(), "nosniff".to_string()));
+        assert!(response.is_nosniff());
+        assert_eq!(response.mime_type().essence(), "text/plain");
+
+        response.headers = vec![(
+            "Content-Type".to_string(),
+            "text/html; charset=UTF-8".to_string(),
+        )];
+        response.body = "<p>é</p>".as_bytes().to_vec();
+        assert!(response.mime_type().is_html());
+        let stream = response.input_stream();
+        assert!(stream.is_certain_encoding());
+        assert_eq!(stream.encoding, Encoding::UTF8);
+
+        // US-ASCII is a label of windows-1252, which is not supported
+        response.headers = vec![(
+            "Content-Type".to_string(),
+            "text/html; charset=US-ASCII".to_string(),
+        )];
+        let stream = response.input_stream();
+        assert!(!stream.is_certain_encoding());
+        assert_eq!(stream.encoding, Encoding::UTF8);
+
+        response.headers.clear();
+        assert!(!response.input_stream().is_certain_encoding());
+        response.body.splice(0..0, [0xEF, 0xBB, 0xBF]);
+        assert!(response.input_stream().is_certain_encoding());
+    }
+
+    #[test]
+    fn mock_loader_records_requests() {
+        let mut mock = MockLoader::new();
+        mock.add_html("https://example.com/a", "a").unwrap();
+
+        let _ = mock.load(&Url::parse("https://example.com/a").unwrap());
+        let _ = mock.load(&Url::parse("https://example.com/b").unwrap());
+        assert_eq!(
+            mock.requests()
+                .iter()
+                .map(|url| url.href())
+                .collect::<Vec<_>>(),
+            vec!["https://example.com/a", "https://example.com/b"]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Recording and replaying of web archives
+//!
+//! A [`RecordingLoader`] writes every response it loads to a WARC file. An [`ArchiveLoader`]
+//! serves the responses of a WARC or MHTML file, so recorded pages can be loaded again without
+//! network access.
+use crate::loader::archive::mhtml::read_mhtml;
+use crate::loader::archive::warc::{read_warc, WarcWriter};
+use crate::loader::http::DEFAULT_MAX_REDIRECTS;
+use crate::loader::{LoadError, ResourceLoader, Response};
+use crate::types::Result;
+use crate::url::Url;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+pub mod mhtml;
+pub mod warc;
+
+/// Serves the responses of an archive. Redirects are followed within the archive. Fragments are
+/// ignored when looking up URLs.
+#[derive(Debug, Default)]
+pub struct ArchiveLoader {
+    responses: HashMap<String, Response>,
+    main_url: Option<Url>,
+}
+
+impl ArchiveLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a response, which is served for its URL. A later response for the same URL replaces
+    /// the earlier one. The first response added is the main page.
+    pub fn add(&mut self, response: Response) {
+        let url = response.url.without_fragment();
+        if self.main_url.is_none() {
+            self.main_url = Some(url.clone());
+        }
+        self.responses.insert(url.href(), response);
+    }
+
+    /// Creates a loader for the `response` and `resource` records of a (gzipped) WARC file
+    pub fn from_warc(data: &[u8]) -> Result<Self> {
+        let mut loader = Self::new();
+        for record in read_warc(data)? {
+            if let Some(response) = record.to_response()? {
+                loader.add(response);
+            }
+        }
+        Ok(loader)
+    }
+
+    /// Creates a loader for the parts of an MHTML file. Parts with a `Content-ID` can also be
+    /// loaded with their `cid:` URL.
+    pub fn from_mhtml(data: &[u8]) -> Result<Self> {
+        let mut loader = Self::new();
+        for part in read_mhtml(data)? {
+            for url in [part.location.clone(), part.cid_url()]
+                .into_iter()
+                .flatten()
+            {
+                loader.add(part.to_response(url));
+            }
+        }
+        Ok(loader)
+    }
+
+    /// Opens a WARC file (detected by its version line or gzip header) or else an MHTML file
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = fs::read(path)?;
+        if data.starts_with(b"WARC/") || data.starts_with(&[0x1F, 0x8B]) {
+            Self::from_warc(&data)
+        } else {
+            Self::from_mhtml(&data)
+        }
+    }
+
+    /// Returns the URL of the main page, the first resource in the archive
+    pub fn main_url(&self) -> Option<&Url> {
+        self.main_url.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+}
+
+impl ResourceLoader for ArchiveLoader {
+    fn load(&self, url: &Url) -> Result<Response> {
+        let mut url = url.without_fragment();
+        for _ in 0..=DEFAULT_MAX_REDIRECTS {
+            let response = self
+                .responses
+                .get(&url.href())
+                .ok_or_else(|| LoadError::NotFound(url.href()))?;
+
+            let location = response.header("location");
+            let Some(location) = location.filter(|_| (300..400).contains(&response.status)) else {
+                return Ok(response.clone());
+            };
+            url = url
+                .join(location)
+                .map_err(|_| LoadError::InvalidRedirect(location.to_string()))?
+                .without_fragment();
+        }
+        Err(LoadError::TooManyRedirects(DEFAULT_MAX_REDIRECTS).into())
+    }
+}
+
+/// Writes every response that is loaded by the wrapped loader to a WARC file. When a load was
+/// redirected, a redirect from the requested URL to the final URL is recorded as well, so the
+/// archive can be replayed with the same URLs.
+pub struct RecordingLoader<W: Write> {
+    loader: Box<dyn ResourceLoader>,
+    writer: RefCell<WarcWriter<W>>,
+}
+
+impl<W: Write> RecordingLoader<W> {
+    pub fn new(loader: Box<dyn ResourceLoader>, writer: W) -> Result<Self> {
+        Ok(Self {
+            loader,
+            writer: RefCell::new(WarcWriter::new(writer)?),
+        })
+    }
+
+    /// Returns the writer the WARC file was written to
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner().into_inner()
+    }
+
+    fn record(&self, url: &Url, response: &Response) -> Result<()> {
+        let mut writer = self.writer.borrow_mut();
+        let url = url.without_fragment();
+        if url != response.url {
+            writer.write_redirect(&url, &response.url)?;
+        }
+        writer.write_response(response)
+    }
+}
+
+impl<W: Write> ResourceLoader for RecordingLoader<W> {
+    fn load(&self, url: &Url) -> Result<Response> {
+        self.load_with_headers(url, &[])
+    }
+
+    fn load_with_headers(&self, url: &Url, headers: &[(String, String)]) -> Result<Response> {
+        let response = self.loader.load_with_headers(url, headers)?;
+        self.record(url, &response)?;
+        Ok(response)
+    }
+
+    fn request_headers(&self, url: &Url) -> Vec<(String, String)> {
+        self.loader.request_headers(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html5_parser::input_stream::InputStream;
+    use crate::html5_parser::node::NodeId;
+    use crate::html5_parser::parser::document::Document;
+    use crate::html5_parser::parser::Html5Parser;
+    use crate::html5_parser::text_renderer::TextRenderOptions;
+    use crate::loader::MockLoader;
+
+    fn render(response: &Response) -> String {
+        let mut stream: InputStream = response.input_stream();
+        let mut parser = Html5Parser::new(&mut stream);
+        let mut document = Document::shared();
+        document.get_mut().set_url(response.url.clone());
+        parser.parse(Document::clone(&document)).unwrap();
+        let text = document
+            .get()
+            .render_text(NodeId::root(), &TextRenderOptions::default())
+            .text;
+        text
+    }
+
+    /// Serves a page that redirects, so the recording has a redirect record
+    struct RedirectingLoader(MockLoader);
+
+    impl ResourceLoader for RedirectingLoader {
+        fn load(&self, url: &Url) -> Result<Response> {
+            let mut response = self.0.load(url)?;
+            if response.url.path() == "/" {
+                response.url = response.url.join("/home").unwrap();
+            }
+            Ok(response)
+        }
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let mut mock = MockLoader::new();
+        mock.add_html(
+            "https://example.com/",
+            "<title>Home</title><h1>Welcome</h1><p>Go <a href=\"/about\">about</a> caf\u{e9}</p>",
+        )
+        .unwrap();
+        mock.add_html("https://example.com/about", "<p>About us</p>")
+            .unwrap();
+        mock.add(Response::ok(
+            Url::parse("file:///tmp/style.css").unwrap(),
+            "text/css",
+            b"p { color: red }".to_vec(),
+        ));
+        let recorder = RecordingLoader::new(Box::new(RedirectingLoader(mock)), vec![]).unwrap();
+
+        let urls = [
+            "https://example.com/#top",
+            "https://example.com/about",
+            "file:///tmp/style.css",
+        ];
+        let live: Vec<Response> = urls
+            .iter()
+            .map(|url| recorder.load(&Url::parse(url).unwrap()).unwrap())
+            .collect();
+        assert!(recorder
+            .load(&Url::parse("https://example.com/missing").unwrap())
+            .is_err());
+
+        let replay = ArchiveLoader::from_warc(&recorder.into_inner()).unwrap();
+        assert_eq!(replay.main_url().unwrap().href(), "https://example.com/");
+        assert_eq!(replay.len(), 4);
+        for (url, live) in urls.iter().zip(&live) {
+            let replayed = replay.load(&Url::parse(url).unwrap()).unwrap();
+            assert_eq!(replayed.url, live.url);
+            assert_eq!(replayed.body, live.body);
+            assert_eq!(replayed.mime_type(), live.mime_type());
+            assert_eq!(render(&replayed), render(live));
+        }
+        assert!(matches!(
+            replay.load(&Url::parse("https://example.com/missing").unwrap()),
+            Err(crate::types::Error::Load(LoadError::NotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn open_archives() {
+        // A response record for http://a.test/, compressed as gzip member
+        let warc = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\x0b\x77\x0c\x72\xd6\x37\xd4\x33\
+            \xe0\xe5\x0a\x07\xb2\x74\x43\x2a\x0b\x52\xad\x14\x8a\x52\x8b\x0b\xf2\xf3\x8a\x53\
+            \x61\x82\x89\x45\xe9\xa9\x25\xba\xa1\x41\x9e\x56\x0a\x19\x25\x25\x05\x56\xfa\xfa\
+            \x89\x7a\x25\xa9\xc5\x25\xfa\xbc\x5c\xce\xf9\x79\x25\xa9\x79\x25\xba\x3e\xa9\x79\
+            \xe9\x25\x19\x56\x0a\xa6\xc6\xbc\x5c\xbc\x5c\x1e\x21\x21\x01\x40\x53\x0d\x15\x8c\
+            \x0c\x0c\x14\xfc\xbd\x11\xca\x20\xe6\x97\xa4\x56\x94\xe8\x67\x94\xe4\xe6\x80\xd4\
+            \xda\x14\xd8\xa5\x57\xd9\xe8\x17\xd8\x81\x38\x00\x9e\xe1\x1e\xbe\x8f\x00\x00\x00";
+        let mhtml = "Content-Type: multipart/related; boundary=b\r\n\r\n\
+            --b\r\nContent-Type: text/html\r\nContent-Location: http://b.test/\r\n\
+            Content-ID: <page>\r\n\r\n<p>mhtml</p>\r\n--b--\r\n";
+
+        let dir = std::env::temp_dir().join(format!("gosub-archive-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("page.warc.gz"), warc).unwrap();
+        fs::write(dir.join("page.mhtml"), mhtml).unwrap();
+
+        let loader = ArchiveLoader::open(&dir.join("page.warc.gz")).unwrap();
+        let url = loader.main_url().unwrap().clone();
+        assert_eq!(url.href(), "http://a.test/");
+        assert_eq!(loader.load(&url).unwrap().text(), "<p>gz</p>");
+
+        let loader = ArchiveLoader::open(&dir.join("page.mhtml")).unwrap();
+        let url = loader.main_url().unwrap().clone();
+        assert_eq!(url.href(), "http://b.test/");
+        assert_eq!(loader.load(&url).unwrap().text(), "<p>mhtml</p>");
+        let cid = loader.load(&Url::parse("cid:page").unwrap()).unwrap();
+        assert_eq!(cid.mime_type().essence(), "text/html");
+
+        assert!(ArchiveLoader::open(&dir.join("missing.warc")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
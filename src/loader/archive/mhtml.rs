@@ -0,0 +1,239 @@
+//! Single-file web pages (MHTML, RFC 2557)
+//!
+//! An MHTML file is a MIME message of type `multipart/related`. Every resource of the page is a
+//! part of the message, identified by its `Content-Location` (and optionally by its `Content-ID`,
+//! for `cid:` URLs). The root part is the page itself.
+use crate::loader::archive::warc::{parse_headers, Headers};
+use crate::loader::data::forgiving_base64_decode;
+use crate::loader::{header, LoadError, Response};
+use crate::mime::MimeType;
+use crate::types::Result;
+use crate::url::Url;
+
+/// A part of an MHTML file, with its body decoded
+#[derive(Debug, Clone, PartialEq)]
+pub struct MhtmlPart {
+    pub headers: Headers,
+    pub body: Vec<u8>,
+    /// The `Content-Location`, resolved against the location of the message
+    pub location: Option<Url>,
+}
+
+impl MhtmlPart {
+    /// Returns the value of the header. Names are matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+
+    /// Returns the `cid:` URL of the part, if it has a `Content-ID`
+    pub fn cid_url(&self) -> Option<Url> {
+        let id = self.header("content-id")?;
+        let id = id.trim().trim_start_matches('<').trim_end_matches('>');
+        Url::parse(&format!("cid:{}", id)).ok()
+    }
+
+    /// Returns the part as a response for the URL
+    pub fn to_response(&self, url: Url) -> Response {
+        let content_type = self.header("content-type").unwrap_or("text/plain");
+        Response::ok(url, content_type, self.body.clone())
+    }
+}
+
+/// Reads the parts of an MHTML file. The root part (named by the `start` parameter, or else the
+/// first part) comes first.
+pub fn read_mhtml(data: &[u8]) -> Result<Vec<MhtmlPart>> {
+    let (headers, body) = parse_headers(data)?;
+    let content_type = header(&headers, "content-type")
+        .and_then(MimeType::parse)
+        .filter(|mime_type| mime_type.type_name == "multipart")
+        .ok_or_else(|| invalid("not a multipart message"))?;
+    let boundary = content_type
+        .parameter("boundary")
+        .ok_or_else(|| invalid("multipart message without boundary"))?;
+    let base = header(&headers, "snapshot-content-location")
+        .or_else(|| header(&headers, "content-location"))
+        .and_then(|location| Url::parse(location).ok());
+
+    let mut parts = vec![];
+    for part in split_parts(body, boundary.as_bytes())? {
+        let (headers, body) = parse_headers(part)?;
+        let body = match header(&headers, "content-transfer-encoding")
+            .map(|encoding| encoding.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("base64") => {
+                forgiving_base64_decode(body).ok_or_else(|| invalid("invalid base64 part"))?
+            }
+            Some("quoted-printable") => decode_quoted_printable(body),
+            _ => body.to_vec(),
+        };
+        let location = header(&headers, "content-location")
+            .and_then(|location| Url::parse_with_base(location, base.as_ref()).ok());
+        parts.push(MhtmlPart {
+            headers,
+            body,
+            location,
+        });
+    }
+
+    // The start parameter holds the Content-ID of the root part
+    if let Some(start) = content_type.parameter("start") {
+        let is_root = |part: &MhtmlPart| part.header("content-id") == Some(start);
+        if let Some(idx) = parts.iter().position(is_root) {
+            let root = parts.remove(idx);
+            parts.insert(0, root);
+        }
+    }
+    if parts.is_empty() {
+        return Err(invalid("message without parts").into());
+    }
+    Ok(parts)
+}
+
+fn invalid(message: &str) -> LoadError {
+    LoadError::InvalidArchive(message.to_string())
+}
+
+/// Splits the body of a multipart message into its parts. The preamble and epilogue are
+/// skipped. The line break before a delimiter belongs to the delimiter.
+fn split_parts<'a>(body: &'a [u8], boundary: &[u8]) -> Result<Vec<&'a [u8]>> {
+    let delimiter = [b"--", boundary].concat();
+    let mut parts = vec![];
+    let mut start: Option<usize> = None;
+    let mut pos = 0;
+
+    while pos < body.len() {
+        let line_end = body[pos..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map_or(body.len(), |end| pos + end + 1);
+        let mut line = &body[pos..line_end];
+        while let [rest @ .., last] = line {
+            if !last.is_ascii_whitespace() {
+                break;
+            }
+            line = rest;
+        }
+
+        if let Some(rest) = line.strip_prefix(delimiter.as_slice()) {
+            if rest.is_empty() || rest == b"--" {
+                if let Some(start) = start {
+                    let mut end = pos;
+                    if body[..end].ends_with(b"\n") {
+                        end -= 1;
+                    }
+                    if body[..end].ends_with(b"\r") {
+                        end -= 1;
+                    }
+                    parts.push(&body[start..end.max(start)]);
+                }
+                if rest == b"--" {
+                    return Ok(parts);
+                }
+                start = Some(line_end);
+            }
+        }
+        pos = line_end;
+    }
+    Err(invalid("missing closing boundary").into())
+}
+
+/// Decodes quoted-printable data (RFC 2045 section 6.7). Invalid escapes are kept as they are.
+fn decode_quoted_printable(data: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut idx = 0;
+    while idx < data.len() {
+        if data[idx] != b'=' {
+            decoded.push(data[idx]);
+            idx += 1;
+            continue;
+        }
+
+        // A soft line break joins two lines
+        let rest = &data[idx + 1..];
+        if rest.starts_with(b"\r\n") {
+            idx += 3;
+        } else if rest.starts_with(b"\n") {
+            idx += 2;
+        } else if let Some(byte) = rest
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            idx += 3;
+        } else {
+            decoded.push(b'=');
+            idx += 1;
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parts() {
+        let mhtml = "From: <Saved by Blink>\r\n\
+            Snapshot-Content-Location: https://example.com/dir/page.html\r\n\
+            MIME-Version: 1.0\r\n\
+            Content-Type: multipart/related;\r\n\
+            \ttype=\"text/html\";\r\n\
+            \tstart=\"<root@mhtml>\";\r\n\
+            \tboundary=\"----Boundary--\"\r\n\
+            \r\n\
+            preamble\r\n\
+            ------Boundary--\r\n\
+            Content-Type: text/css\r\n\
+            Content-Transfer-Encoding: base64\r\n\
+            Content-Location: style.css\r\n\
+            Content-ID: <css@mhtml>\r\n\
+            \r\n\
+            cHsgY29sb3I6\r\n\
+            IHJlZCB9\r\n\
+            ------Boundary--\r\n\
+            Content-Type: text/html; charset=utf-8\r\n\
+            Content-Transfer-Encoding: quoted-printable\r\n\
+            Content-ID: <root@mhtml>\r\n\
+            Content-Location: https://example.com/dir/page.html\r\n\
+            \r\n\
+            <p class=3D\"a\">caf=C3=A9 and a long =\r\n\
+            line</p>\r\n\
+            ------Boundary----\r\n";
+
+        let parts = read_mhtml(mhtml.as_bytes()).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(
+            parts[0].location.as_ref().map(Url::href).as_deref(),
+            Some("https://example.com/dir/page.html")
+        );
+        assert_eq!(
+            String::from_utf8(parts[0].body.clone()).unwrap(),
+            "<p class=\"a\">café and a long line</p>"
+        );
+        assert_eq!(
+            parts[1].location.as_ref().map(Url::href).as_deref(),
+            Some("https://example.com/dir/style.css")
+        );
+        assert_eq!(parts[1].body, b"p{ color: red }");
+        assert_eq!(parts[1].cid_url().unwrap().href(), "cid:css@mhtml");
+        assert_eq!(
+            parts[1]
+                .to_response(parts[1].location.clone().unwrap())
+                .content_type(),
+            Some("text/css")
+        );
+
+        assert!(read_mhtml(b"Content-Type: text/html\r\n\r\n<p>").is_err());
+        assert!(read_mhtml(b"Content-Type: multipart/related; boundary=x\r\n\r\n--x\r\n").is_err());
+    }
+
+    #[test]
+    fn quoted_printable() {
+        assert_eq!(decode_quoted_printable(b"a=3D=3db"), b"a==b");
+        assert_eq!(decode_quoted_printable(b"soft=\nbreak"), b"softbreak");
+        assert_eq!(decode_quoted_printable(b"bad=G1 end="), b"bad=G1 end=");
+    }
+}
@@ -0,0 +1,454 @@
+//! Web ARChive files (WARC 1.1, ISO 28500)
+//!
+//! A WARC file is a sequence of records. Each record has a version line, named headers and a
+//! block. HTTP responses are stored in `response` records, with the complete HTTP message as
+//! block. Other resources (like local files) are stored in `resource` records. Compressed files
+//! (`.warc.gz`, one gzip member per record) are read as well.
+use crate::loader::http::{decode_content, read_decoded};
+use crate::loader::{header, LoadError, Response};
+use crate::types::Result;
+use crate::url::Url;
+use flate2::read::MultiGzDecoder;
+use std::io::Write;
+
+/// Header names and values, in order
+pub type Headers = Vec<(String, String)>;
+
+/// Version written in the records
+pub const WARC_VERSION: &str = "WARC/1.1";
+
+/// Versions that can be read
+const SUPPORTED_VERSIONS: [&[u8]; 2] = [b"WARC/1.0", b"WARC/1.1"];
+
+/// Maximum size of a compressed WARC file after decompression
+pub const MAX_DECOMPRESSED_SIZE: usize = 1024 * 1024 * 1024;
+
+/// A WARC record
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarcRecord {
+    pub headers: Headers,
+    pub block: Vec<u8>,
+}
+
+impl WarcRecord {
+    /// Returns the value of the header. Names are matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+
+    /// Returns the type of the record, like `response` or `warcinfo`
+    pub fn record_type(&self) -> Option<&str> {
+        self.header("warc-type")
+    }
+
+    pub fn target_uri(&self) -> Option<Url> {
+        // Some writers put the URI in angle brackets
+        let uri = self.header("warc-target-uri")?;
+        let uri = uri.trim_start_matches('<').trim_end_matches('>');
+        Url::parse(uri).ok()
+    }
+
+    /// Returns the response stored in a `response` or `resource` record. Other records have no
+    /// response.
+    pub fn to_response(&self) -> Result<Option<Response>> {
+        let Some(url) = self.target_uri() else {
+            return Ok(None);
+        };
+        match self.record_type() {
+            Some("response") => parse_http_response(url, &self.block).map(Some),
+            Some("resource") => {
+                let content_type = self
+                    .header("content-type")
+                    .unwrap_or("application/octet-stream");
+                Ok(Some(Response::ok(url, content_type, self.block.clone())))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Reads all records of a WARC file
+pub fn read_warc(data: &[u8]) -> Result<Vec<WarcRecord>> {
+    let decompressed;
+    let mut data = data;
+    if data.starts_with(&[0x1F, 0x8B]) {
+        decompressed = read_decoded(MultiGzDecoder::new(data), MAX_DECOMPRESSED_SIZE).map_err(
+            |err| match err {
+                LoadError::InvalidContent(_) => invalid("invalid gzip data"),
+                err => err,
+            },
+        )?;
+        data = &decompressed;
+    }
+
+    let mut records = vec![];
+    loop {
+        while data.starts_with(b"\r\n") || data.starts_with(b"\n") {
+            data = &data[if data[0] == b'\r' { 2 } else { 1 }..];
+        }
+        if data.is_empty() {
+            return Ok(records);
+        }
+        if !data.starts_with(b"WARC/") {
+            return Err(invalid("expected a WARC version line").into());
+        }
+
+        let (version_line, rest) = split_line(data);
+        if !SUPPORTED_VERSIONS.contains(&version_line) {
+            return Err(invalid("unsupported WARC version").into());
+        }
+        let (headers, rest) = parse_headers(rest)?;
+        let length: usize = header(&headers, "content-length")
+            .and_then(|length| length.trim().parse().ok())
+            .ok_or_else(|| invalid("record without Content-Length"))?;
+        if rest.len() < length {
+            return Err(invalid("truncated record").into());
+        }
+
+        records.push(WarcRecord {
+            headers,
+            block: rest[..length].to_vec(),
+        });
+        data = &rest[length..];
+    }
+}
+
+/// Writes WARC records. A `warcinfo` record is written first.
+pub struct WarcWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WarcWriter<W> {
+    pub fn new(writer: W) -> Result<Self> {
+        let mut warc = Self { writer };
+        let info = format!(
+            "software: gosub/{}\r\nformat: WARC File Format 1.1\r\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        warc.write_record(
+            vec![
+                ("WARC-Type".to_string(), "warcinfo".to_string()),
+                (
+                    "Content-Type".to_string(),
+                    "application/warc-fields".to_string(),
+                ),
+            ],
+            info.as_bytes(),
+        )?;
+        Ok(warc)
+    }
+
+    /// Writes a record with the headers. The version line, `WARC-Record-ID`, `WARC-Date` and
+    /// `Content-Length` are added.
+    pub fn write_record(&mut self, headers: Headers, block: &[u8]) -> Result<()> {
+        let mut record = format!("{}\r\n", WARC_VERSION);
+        record.push_str(&format!(
+            "WARC-Record-ID: <urn:uuid:{}>\r\n",
+            uuid::Uuid::new_v4()
+        ));
+        record.push_str(&format!(
+            "WARC-Date: {}\r\n",
+            format_date(crate::loader::now())
+        ));
+        for (name, value) in headers {
+            record.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        record.push_str(&format!("Content-Length: {}\r\n\r\n", block.len()));
+
+        self.writer.write_all(record.as_bytes())?;
+        self.writer.write_all(block)?;
+        self.writer.write_all(b"\r\n\r\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the response. HTTP responses are written as `response` records, other responses as
+    /// `resource` records. The body is stored decoded, so `Content-Encoding` and
+    /// `Transfer-Encoding` are left out and `Content-Length` is set to the length of the body.
+    pub fn write_response(&mut self, response: &Response) -> Result<()> {
+        let target = ("WARC-Target-URI".to_string(), response.url.href());
+        if !matches!(response.url.scheme(), "http" | "https") {
+            let content_type = response
+                .content_type()
+                .unwrap_or("application/octet-stream");
+            return self.write_record(
+                vec![
+                    ("WARC-Type".to_string(), "resource".to_string()),
+                    target,
+                    ("Content-Type".to_string(), content_type.to_string()),
+                ],
+                &response.body,
+            );
+        }
+
+        let mut message = format!(
+            "HTTP/1.1 {} {}\r\n",
+            response.status,
+            reason_phrase(response.status)
+        );
+        for (name, value) in &response.headers {
+            if !matches!(
+                name.to_ascii_lowercase().as_str(),
+                "content-encoding" | "transfer-encoding" | "content-length"
+            ) {
+                message.push_str(&format!("{}: {}\r\n", name, value));
+            }
+        }
+        message.push_str(&format!("Content-Length: {}\r\n\r\n", response.body.len()));
+
+        self.write_record(
+            vec![
+                ("WARC-Type".to_string(), "response".to_string()),
+                target,
+                (
+                    "Content-Type".to_string(),
+                    "application/http;msgtype=response".to_string(),
+                ),
+            ],
+            &[message.as_bytes(), &response.body].concat(),
+        )
+    }
+
+    /// Writes a redirect from the URL to the final URL of a response
+    pub fn write_redirect(&mut self, from: &Url, to: &Url) -> Result<()> {
+        let response = Response {
+            url: from.clone(),
+            status: 302,
+            headers: vec![("Location".to_string(), to.href())],
+            body: vec![],
+        };
+        self.write_response(&response)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn invalid(message: &str) -> LoadError {
+    LoadError::InvalidArchive(message.to_string())
+}
+
+/// Splits off the first line, without its line ending
+fn split_line(data: &[u8]) -> (&[u8], &[u8]) {
+    match data.iter().position(|byte| *byte == b'\n') {
+        Some(end) => {
+            let line = &data[..end];
+            (line.strip_suffix(b"\r").unwrap_or(line), &data[end + 1..])
+        }
+        None => (data, &[]),
+    }
+}
+
+/// Parses header lines up to an empty line. Lines starting with whitespace continue the previous
+/// header. Returns the headers and the data after the empty line.
+pub(crate) fn parse_headers(data: &[u8]) -> Result<(Headers, &[u8])> {
+    let mut headers: Headers = vec![];
+    let mut data = data;
+    loop {
+        if data.is_empty() {
+            return Ok((headers, data));
+        }
+        let (line, rest) = split_line(data);
+        data = rest;
+        if line.is_empty() {
+            return Ok((headers, data));
+        }
+
+        let line = String::from_utf8_lossy(line);
+        if line.starts_with([' ', '\t']) {
+            match headers.last_mut() {
+                Some((_, value)) => {
+                    if !value.is_empty() {
+                        value.push(' ');
+                    }
+                    value.push_str(line.trim());
+                }
+                None => return Err(invalid("continuation line without header").into()),
+            }
+            continue;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid("header line without colon"))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+}
+
+/// Parses an HTTP response message. Chunked transfer coding and content coding are decoded.
+fn parse_http_response(url: Url, message: &[u8]) -> Result<Response> {
+    let (status_line, rest) = split_line(message);
+    let status_line = String::from_utf8_lossy(status_line);
+    let status = status_line
+        .strip_prefix("HTTP/")
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| invalid("invalid HTTP status line"))?;
+    let (headers, body) = parse_headers(rest)?;
+
+    let mut body = body.to_vec();
+    let chunked = header(&headers, "transfer-encoding")
+        .is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"));
+    if chunked {
+        body = decode_chunked(&body).ok_or_else(|| invalid("invalid chunked body"))?;
+    }
+    if let Some(encoding) = header(&headers, "content-encoding") {
+        body = decode_content(encoding, body)?;
+    }
+
+    Ok(Response {
+        url,
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Decodes a body with chunked transfer coding (RFC 9112 section 7.1)
+fn decode_chunked(data: &[u8]) -> Option<Vec<u8>> {
+    let mut body = vec![];
+    let mut data = data;
+    loop {
+        let (line, rest) = split_line(data);
+        let size = String::from_utf8_lossy(line);
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(rest.get(..size)?);
+        data = rest.get(size..)?;
+        data = data.strip_prefix(b"\r\n").unwrap_or(data);
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        410 => "Gone",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+/// Formats the time (seconds since the Unix epoch) as W3C date, like `2023-11-14T22:13:20Z`
+pub fn format_date(time: i64) -> String {
+    let days = time.div_euclid(86400);
+    let seconds = time.rem_euclid(86400);
+
+    // Civil date from the number of days since 1970-01-01
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read_records() {
+        let mut warc = WarcWriter::new(vec![]).unwrap();
+        let page = Response {
+            url: Url::parse("https://example.com/page").unwrap(),
+            status: 404,
+            headers: vec![
+                ("Content-Type".to_string(), "text/html".to_string()),
+                ("Content-Encoding".to_string(), "gzip".to_string()),
+                ("Set-Cookie".to_string(), "a=1".to_string()),
+            ],
+            body: b"<p>missing</p>".to_vec(),
+        };
+        warc.write_response(&page).unwrap();
+        let file = Response::ok(
+            Url::parse("file:///tmp/a.css").unwrap(),
+            "text/css",
+            b"p{}".to_vec(),
+        );
+        warc.write_response(&file).unwrap();
+
+        let records = read_warc(&warc.into_inner()).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].record_type(), Some("warcinfo"));
+        assert!(records[1]
+            .header("warc-record-id")
+            .is_some_and(|id| id.starts_with("<urn:uuid:")));
+
+        let response = records[1].to_response().unwrap().unwrap();
+        assert_eq!(response.url, page.url);
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, page.body);
+        assert_eq!(response.header("content-encoding"), None);
+        assert_eq!(response.header("content-length"), Some("14"));
+        assert_eq!(response.header("set-cookie"), Some("a=1"));
+
+        assert_eq!(records[2].to_response().unwrap().unwrap(), file);
+        assert_eq!(records[0].to_response().unwrap(), None);
+    }
+
+    #[test]
+    fn read_foreign_records() {
+        let block =
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nContent-Type:\r\n text/html\r\n\r\n\
+            5\r\n<p>ch\r\n6;ext=1\r\nunked<\r\n3\r\n/p>\r\n0\r\n\r\n";
+        let warc = format!(
+            "WARC/1.0\r\nWARC-Type: response\r\nWARC-Target-URI: <http://example.com/>\r\n\
+            Content-Length: {}\r\n\r\n{}\r\n\r\n",
+            block.len(),
+            block
+        );
+        let records = read_warc(warc.as_bytes()).unwrap();
+        let response = records[0].to_response().unwrap().unwrap();
+        assert_eq!(response.url.href(), "http://example.com/");
+        assert_eq!(response.content_type(), Some("text/html"));
+        assert_eq!(response.text(), "<p>chunked</p>");
+
+        assert!(read_warc(b"HTTP/1.1 200 OK\r\n").is_err());
+        assert!(read_warc(b"WARC/2.0\r\nContent-Length: 0\r\n\r\n").is_err());
+        assert!(read_warc(b"WARC/1.1\r\nContent-Length: 10\r\n\r\nshort").is_err());
+        assert_eq!(read_warc(b"").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(format_date(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_date(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(format_date(951_782_400), "2000-02-29T00:00:00Z");
+    }
+}
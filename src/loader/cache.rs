@@ -0,0 +1,739 @@
+//! HTTP caching as specified by RFC 9111 (<https://www.rfc-editor.org/rfc/rfc9111>)
+//!
+//! A [`CachingLoader`] wraps another loader and keeps its `http:` and `https:` responses in a
+//! [`DiskCache`]. Fresh responses are served from the cache, stale responses are validated with
+//! a conditional request (`If-None-Match` and `If-Modified-Since`). This is a private cache, so
+//! responses with `Cache-Control: private` are stored as well. Responses that were redirected are
+//! not stored. In offline mode, the network is never used and stale responses are served.
+use crate::loader::cookies::parse_cookie_date;
+use crate::loader::{header, now, LoadError, ResourceLoader, Response};
+use crate::types::{Error, Result};
+use crate::url::Url;
+use serde_derive::{Deserialize, Serialize};
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default maximum size of the stored bodies (64 MiB)
+pub const DEFAULT_MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Status codes that are heuristically cacheable (RFC 9110 section 15.1)
+static HEURISTICALLY_CACHEABLE: [u16; 12] =
+    [200, 203, 204, 206, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// The heuristic freshness lifetime is this fraction of the time since the last modification
+/// (RFC 9111 section 4.2.2)
+const HEURISTIC_FRACTION: i64 = 10;
+
+/// Headers of a 304 response that do not replace the stored headers
+static NOT_UPDATED_HEADERS: [&str; 3] = ["content-length", "connection", "transfer-encoding"];
+
+/// The directives of `Cache-Control` headers
+#[derive(Debug, Default)]
+struct CacheControl {
+    directives: Vec<(String, Option<String>)>,
+}
+
+impl CacheControl {
+    fn parse(values: &[&str]) -> CacheControl {
+        let mut directives = vec![];
+        for value in values {
+            for directive in split_list(value) {
+                let (name, argument) = match directive.split_once('=') {
+                    Some((name, argument)) => (name, Some(argument.trim().trim_matches('"'))),
+                    None => (directive.as_str(), None),
+                };
+                let name = name.trim().to_ascii_lowercase();
+                if !name.is_empty() {
+                    directives.push((name, argument.map(str::to_string)));
+                }
+            }
+        }
+        CacheControl { directives }
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.directives
+            .iter()
+            .any(|(directive, _)| directive == name)
+    }
+
+    /// Returns the argument of a directive with a number of seconds
+    fn seconds(&self, name: &str) -> Option<i64> {
+        self.directives
+            .iter()
+            .find(|(directive, _)| directive == name)
+            .and_then(|(_, argument)| argument.as_ref()?.parse().ok())
+    }
+}
+
+/// Splits a comma separated header value. Commas in quoted strings do not split.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            ',' if !quoted => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// HTTP dates are parsed with the cookie date algorithm, which accepts all HTTP date formats
+fn parse_http_date(value: &str) -> Option<i64> {
+    parse_cookie_date(value)
+}
+
+/// Returns the age of a response when it was received (RFC 9111 section 4.2.3)
+fn initial_age(response: &Response, now: i64) -> i64 {
+    let date = response
+        .header("date")
+        .and_then(parse_http_date)
+        .unwrap_or(now);
+    let apparent_age = (now - date).max(0);
+    let age_value = response
+        .header("age")
+        .and_then(|age| age.trim().parse().ok())
+        .unwrap_or(0);
+    apparent_age.max(age_value)
+}
+
+/// Returns true when the response can be stored (RFC 9111 section 3)
+fn is_storable(response: &Response) -> bool {
+    let cache_control = CacheControl::parse(&response.header_values("cache-control"));
+    let vary_all = response
+        .header_values("vary")
+        .iter()
+        .flat_map(|value| split_list(value))
+        .any(|name| name == "*");
+
+    // Partial content is not supported
+    if response.status == 206 || !(200..600).contains(&response.status) || response.status == 304 {
+        return false;
+    }
+    if cache_control.has("no-store") || vary_all {
+        return false;
+    }
+    cache_control.has("max-age")
+        || cache_control.has("public")
+        || response.header("expires").is_some()
+        || HEURISTICALLY_CACHEABLE.contains(&response.status)
+}
+
+/// A stored response, without its body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The request headers named by `Vary`, with the values that were sent (None when the header
+    /// was not sent)
+    pub vary: Vec<(String, Option<String>)>,
+    /// When the response was received, in seconds since the Unix epoch
+    pub response_time: i64,
+    /// Age of the response in seconds when it was received
+    pub initial_age: i64,
+    pub body_size: u64,
+}
+
+impl CacheEntry {
+    fn new(response: &Response, request_headers: &[(String, String)], now: i64) -> Self {
+        let vary = response
+            .header_values("vary")
+            .iter()
+            .flat_map(|value| split_list(value))
+            .map(|name| {
+                let value = header(request_headers, &name).map(str::to_string);
+                (name.to_ascii_lowercase(), value)
+            })
+            .collect();
+
+        CacheEntry {
+            url: response.url.href(),
+            status: response.status,
+            headers: response.headers.clone(),
+            vary,
+            response_time: now,
+            initial_age: initial_age(response, now),
+            body_size: response.body.len() as u64,
+        }
+    }
+
+    fn cache_control(&self) -> CacheControl {
+        let values: Vec<&str> = self
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("cache-control"))
+            .map(|(_, value)| value.as_str())
+            .collect();
+        CacheControl::parse(&values)
+    }
+
+    /// Returns how long the response is fresh, in seconds (RFC 9111 section 4.2.1)
+    pub fn freshness_lifetime(&self) -> i64 {
+        if let Some(max_age) = self.cache_control().seconds("max-age") {
+            return max_age;
+        }
+
+        let date = header(&self.headers, "date")
+            .and_then(parse_http_date)
+            .unwrap_or(self.response_time);
+        if let Some(expires) = header(&self.headers, "expires") {
+            // Invalid dates (like "0") mean the response has already expired
+            return parse_http_date(expires).map_or(0, |expires| (expires - date).max(0));
+        }
+
+        match header(&self.headers, "last-modified").and_then(parse_http_date) {
+            Some(last_modified) if HEURISTICALLY_CACHEABLE.contains(&self.status) => {
+                (date - last_modified).max(0) / HEURISTIC_FRACTION
+            }
+            _ => 0,
+        }
+    }
+
+    /// Returns the current age of the response in seconds (RFC 9111 section 4.2.3)
+    pub fn age(&self, now: i64) -> i64 {
+        self.initial_age + (now - self.response_time).max(0)
+    }
+
+    /// Returns true when the response can be served without validating it
+    pub fn is_fresh(&self, now: i64) -> bool {
+        !self.cache_control().has("no-cache") && self.freshness_lifetime() > self.age(now)
+    }
+
+    /// Returns true when a stale response may never be served without validating it
+    fn must_revalidate(&self) -> bool {
+        let cache_control = self.cache_control();
+        cache_control.has("must-revalidate") || cache_control.has("no-cache")
+    }
+
+    /// Returns true when the request headers have the values the response varies on
+    fn matches(&self, request_headers: &[(String, String)]) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| header(request_headers, name) == value.as_deref())
+    }
+
+    /// Replaces the stored headers with the headers of a 304 response (RFC 9111 section 3.2)
+    fn update_headers(&mut self, headers: &[(String, String)]) {
+        for (name, _) in headers {
+            if !NOT_UPDATED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                self.headers
+                    .retain(|(stored, _)| !stored.eq_ignore_ascii_case(name));
+            }
+        }
+        for (name, value) in headers {
+            if !NOT_UPDATED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                self.headers.push((name.clone(), value.clone()));
+            }
+        }
+    }
+
+    /// Returns the stored response, with an `Age` header
+    fn to_response(&self, body: Vec<u8>, now: i64) -> Result<Response> {
+        let mut headers = self.headers.clone();
+        headers.retain(|(name, _)| !name.eq_ignore_ascii_case("age"));
+        headers.push(("Age".to_string(), self.age(now).to_string()));
+        Ok(Response {
+            url: Url::parse(&self.url)?,
+            status: self.status,
+            headers,
+            body,
+        })
+    }
+
+    /// Returns the name the entry is stored under. Variants of a URL have different keys.
+    fn key(&self) -> String {
+        let mut key = self.url.clone();
+        for (name, value) in &self.vary {
+            key.push('\n');
+            key.push_str(name);
+            key.push(':');
+            key.push_str(value.as_deref().unwrap_or_default());
+        }
+        format!("{:016x}", fnv1a(key.as_bytes()))
+    }
+}
+
+/// FNV-1a hash, used for the file names of entries
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Stores cached responses in a directory: the metadata of an entry as JSON, and the body in a
+/// separate file. When the stored bodies exceed the maximum size, the least recently used entries
+/// are removed.
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    max_size: u64,
+    /// The entries by key, with the moment they were last used
+    entries: HashMap<String, (CacheEntry, u64)>,
+    uses: u64,
+}
+
+impl DiskCache {
+    /// Opens the cache in the directory, which is created when it does not exist. Entries that
+    /// cannot be read are removed.
+    pub fn open(dir: &Path, max_size: u64) -> Result<DiskCache> {
+        fs::create_dir_all(dir)?;
+
+        let mut entries = vec![];
+        for file in fs::read_dir(dir)? {
+            let path = file?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "json")
+            {
+                continue;
+            }
+            let entry = fs::read(&path)
+                .ok()
+                .and_then(|data| serde_json::from_slice::<CacheEntry>(&data).ok())
+                .filter(|_| path.with_extension("body").exists());
+            match entry {
+                Some(entry) => entries.push(entry),
+                None => {
+                    let _ = fs::remove_file(&path);
+                    let _ = fs::remove_file(path.with_extension("body"));
+                }
+            }
+        }
+
+        // Entries that were stored last are considered the most recently used
+        entries.sort_by_key(|entry| entry.response_time);
+        let mut cache = DiskCache {
+            dir: dir.to_path_buf(),
+            max_size,
+            entries: HashMap::new(),
+            uses: 0,
+        };
+        for entry in entries {
+            cache.uses += 1;
+            cache.entries.insert(entry.key(), (entry, cache.uses));
+        }
+        cache.evict()?;
+        Ok(cache)
+    }
+
+    /// Returns the total size of the stored bodies
+    pub fn size(&self) -> u64 {
+        self.entries
+            .values()
+            .map(|(entry, _)| entry.body_size)
+            .sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the stored entries for the URL (one for every variant)
+    pub fn entries(&self, url: &Url) -> Vec<&CacheEntry> {
+        let href = url.without_fragment().href();
+        self.entries
+            .values()
+            .map(|(entry, _)| entry)
+            .filter(|entry| entry.url == href)
+            .collect()
+    }
+
+    /// Removes all entries
+    pub fn clear(&mut self) -> Result<()> {
+        let keys: Vec<String> = self.entries.keys().cloned().collect();
+        for key in keys {
+            self.remove(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Finds the entry for the URL that matches the request headers
+    fn find(&self, href: &str, request_headers: &[(String, String)]) -> Option<&CacheEntry> {
+        self.entries
+            .values()
+            .map(|(entry, _)| entry)
+            .find(|entry| entry.url == href && entry.matches(request_headers))
+    }
+
+    /// Reads the body of the entry, and marks the entry as used
+    fn body(&mut self, key: &str) -> Result<Vec<u8>> {
+        self.uses += 1;
+        if let Some((_, used)) = self.entries.get_mut(key) {
+            *used = self.uses;
+        }
+        Ok(fs::read(self.path(key, "body"))?)
+    }
+
+    /// Stores the entry and its body, replacing the entry with the same key. Bodies larger than
+    /// the cache are not stored.
+    fn insert(&mut self, entry: CacheEntry, body: &[u8]) -> Result<()> {
+        let key = entry.key();
+        if body.len() as u64 > self.max_size {
+            return self.remove(&key);
+        }
+        fs::write(self.path(&key, "body"), body)?;
+        self.update(entry)?;
+        self.evict()
+    }
+
+    /// Stores the metadata of the entry
+    fn update(&mut self, entry: CacheEntry) -> Result<()> {
+        let key = entry.key();
+        fs::write(self.path(&key, "json"), serde_json::to_vec(&entry)?)?;
+        self.uses += 1;
+        self.entries.insert(key, (entry, self.uses));
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<()> {
+        if self.entries.remove(key).is_some() {
+            fs::remove_file(self.path(key, "json"))?;
+            fs::remove_file(self.path(key, "body"))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the least recently used entries until the bodies fit in the maximum size
+    fn evict(&mut self) -> Result<()> {
+        while self.size() > self.max_size {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => self.remove(&key)?,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, key: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, extension))
+    }
+}
+
+/// Loads resources through another loader, and caches the `http:` and `https:` responses
+pub struct CachingLoader {
+    loader: Box<dyn ResourceLoader>,
+    cache: RefCell<DiskCache>,
+    offline: bool,
+}
+
+impl CachingLoader {
+    pub fn new(loader: Box<dyn ResourceLoader>, cache: DiskCache) -> Self {
+        Self {
+            loader,
+            cache: RefCell::new(cache),
+            offline: false,
+        }
+    }
+
+    /// In offline mode, only stored responses are served (even when they are stale) and the
+    /// network is not used. Responses with `must-revalidate` or `no-cache` are not served stale.
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    pub fn cache(&self) -> Ref<'_, DiskCache> {
+        self.cache.borrow()
+    }
+
+    /// Serves the stored entry, and marks it as used
+    fn serve(&self, entry: &CacheEntry, now: i64) -> Result<Response> {
+        let body = self.cache.borrow_mut().body(&entry.key())?;
+        entry.to_response(body, now)
+    }
+
+    fn load_at(&self, url: &Url, now: i64) -> Result<Response> {
+        let url = url.without_fragment();
+        if !matches!(url.scheme(), "http" | "https") {
+            return self.loader.load(&url);
+        }
+
+        let request_headers = self.loader.request_headers(&url);
+        let stored = self
+            .cache
+            .borrow()
+            .find(&url.href(), &request_headers)
+            .cloned();
+
+        if let Some(entry) = &stored {
+            if entry.is_fresh(now) || (self.offline && !entry.must_revalidate()) {
+                return self.serve(entry, now);
+            }
+        }
+        if self.offline {
+            return Err(LoadError::NotCached(url.href()).into());
+        }
+
+        let mut conditional = vec![];
+        if let Some(entry) = &stored {
+            if let Some(etag) = header(&entry.headers, "etag") {
+                conditional.push(("If-None-Match".to_string(), etag.to_string()));
+            }
+            if let Some(last_modified) = header(&entry.headers, "last-modified") {
+                conditional.push(("If-Modified-Since".to_string(), last_modified.to_string()));
+            }
+        }
+
+        let response = match (self.loader.load_with_headers(&url, &conditional), &stored) {
+            (Ok(response), _) => response,
+            // When the server cannot be reached, a stale response is better than nothing
+            (Err(Error::Request(_) | Error::IO(_)), Some(entry)) if !entry.must_revalidate() => {
+                return self.serve(entry, now);
+            }
+            (Err(e), _) => return Err(e),
+        };
+
+        if let (304, Some(mut entry)) = (response.status, stored.clone()) {
+            entry.update_headers(&response.headers);
+            entry.response_time = now;
+            entry.initial_age = initial_age(&response, now);
+            self.cache.borrow_mut().update(entry.clone())?;
+            return self.serve(&entry, now);
+        }
+
+        let mut cache = self.cache.borrow_mut();
+        if is_storable(&response) && response.url == url {
+            let entry = CacheEntry::new(&response, &request_headers, now);
+            if let Some(stored) = stored.filter(|stored| stored.key() != entry.key()) {
+                cache.remove(&stored.key())?;
+            }
+            cache.insert(entry, &response.body)?;
+        } else if let Some(stored) = stored {
+            cache.remove(&stored.key())?;
+        }
+        Ok(response)
+    }
+}
+
+impl ResourceLoader for CachingLoader {
+    fn load(&self, url: &Url) -> Result<Response> {
+        self.load_at(url, now())
+    }
+
+    fn request_headers(&self, url: &Url) -> Vec<(String, String)> {
+        self.loader.request_headers(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::cookies::{CookieApi, CookieJar};
+    use crate::loader::test_server::{response, serve, Requests};
+    use crate::loader::HttpLoader;
+    use std::rc::Rc;
+
+    /// Tue, 14 Nov 2023 22:30:00 GMT
+    const NOW: i64 = 1_700_001_000;
+    const LAST_MODIFIED: &str = "Tue, 14 Nov 2023 22:13:20 GMT";
+
+    struct TestCache {
+        base: Url,
+        requests: Requests,
+        cookies: Rc<RefCell<CookieJar>>,
+        dir: PathBuf,
+        loader: CachingLoader,
+    }
+
+    impl TestCache {
+        fn new(max_size: u64) -> Self {
+            let (base, requests) = serve(|request| match request.path.as_str() {
+                "/fresh" => response("200 OK", &["Cache-Control: max-age=60"], b"fresh"),
+                "/etag" if request.header("if-none-match") == Some("\"v1\"") => {
+                    response("304 Not Modified", &["Cache-Control: max-age=10"], b"")
+                }
+                "/etag" => response(
+                    "200 OK",
+                    &["ETag: \"v1\"", "Cache-Control: no-cache"],
+                    b"etag",
+                ),
+                "/modified" if request.header("if-modified-since") == Some(LAST_MODIFIED) => {
+                    response("304 Not Modified", &[], b"")
+                }
+                "/modified" => response(
+                    "200 OK",
+                    &["Last-Modified: Tue, 14 Nov 2023 22:13:20 GMT"],
+                    b"modified",
+                ),
+                "/cookie" => {
+                    let body = request.header("cookie").unwrap_or("anonymous").to_string();
+                    response(
+                        "200 OK",
+                        &["Cache-Control: max-age=60", "Vary: Cookie"],
+                        body.as_bytes(),
+                    )
+                }
+                "/no-store" => response("200 OK", &["Cache-Control: no-store"], b"no-store"),
+                "/vary-all" => response("200 OK", &["Vary: *"], b"vary-all"),
+                "/teapot" => response("418 I'm a teapot", &[], b"teapot"),
+                "/revalidate" => response(
+                    "200 OK",
+                    &["Cache-Control: max-age=1, must-revalidate"],
+                    b"revalidate",
+                ),
+                path => response("200 OK", &["Cache-Control: max-age=1"], path.as_bytes()),
+            });
+
+            let dir = std::env::temp_dir().join(format!("gosub-cache-{}", uuid::Uuid::new_v4()));
+            let cookies = Rc::new(RefCell::new(CookieJar::new()));
+            let loader = CachingLoader::new(
+                Box::new(HttpLoader::new(Rc::clone(&cookies))),
+                DiskCache::open(&dir, max_size).unwrap(),
+            );
+            TestCache {
+                base,
+                requests,
+                cookies,
+                dir,
+                loader,
+            }
+        }
+
+        fn url(&self, path: &str) -> Url {
+            self.base.join(path).unwrap()
+        }
+
+        /// Loads the path at the time, and returns the body
+        fn load(&self, path: &str, now: i64) -> String {
+            self.loader.load_at(&self.url(path), now).unwrap().text()
+        }
+
+        fn request_count(&self) -> usize {
+            self.requests.lock().unwrap().len()
+        }
+    }
+
+    impl Drop for TestCache {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn freshness_and_validation() {
+        let test = TestCache::new(DEFAULT_MAX_SIZE);
+
+        assert_eq!(test.load("fresh", NOW), "fresh");
+        let response = test.loader.load_at(&test.url("fresh"), NOW + 30).unwrap();
+        assert_eq!(response.text(), "fresh");
+        assert_eq!(response.header("age"), Some("30"));
+        assert_eq!(test.request_count(), 1);
+        assert_eq!(test.load("fresh", NOW + 61), "fresh");
+        assert_eq!(test.request_count(), 2);
+
+        // no-cache is always validated, and a 304 updates the stored headers
+        assert_eq!(test.load("etag", NOW), "etag");
+        let response = test.loader.load_at(&test.url("etag"), NOW).unwrap();
+        assert_eq!((response.status, response.text().as_str()), (200, "etag"));
+        assert_eq!(test.request_count(), 4);
+        assert_eq!(
+            test.requests.lock().unwrap()[3].header("if-none-match"),
+            Some("\"v1\"")
+        );
+        assert_eq!(response.header("cache-control"), Some("max-age=10"));
+        assert_eq!(test.load("etag", NOW + 5), "etag");
+        assert_eq!(test.request_count(), 4);
+
+        // Heuristic freshness: 10% of the 1000 seconds since the last modification
+        assert_eq!(test.load("modified", NOW), "modified");
+        assert_eq!(test.load("modified", NOW + 99), "modified");
+        assert_eq!(test.request_count(), 5);
+        assert_eq!(test.load("modified", NOW + 101), "modified");
+        assert_eq!(test.request_count(), 6);
+        assert_eq!(
+            test.requests.lock().unwrap()[5].header("if-modified-since"),
+            Some(LAST_MODIFIED)
+        );
+    }
+
+    #[test]
+    fn vary_and_storability() {
+        let test = TestCache::new(DEFAULT_MAX_SIZE);
+
+        assert_eq!(test.load("cookie", NOW), "anonymous");
+        assert_eq!(test.load("cookie", NOW), "anonymous");
+        assert_eq!(test.request_count(), 1);
+
+        let url = test.url("cookie");
+        test.cookies
+            .borrow_mut()
+            .set_cookie(&url, "user=ann", CookieApi::Http);
+        assert_eq!(test.load("cookie", NOW), "user=ann");
+        assert_eq!(test.request_count(), 2);
+        assert_eq!(test.loader.cache().entries(&url).len(), 2);
+
+        test.cookies.borrow_mut().clear();
+        assert_eq!(test.load("cookie", NOW), "anonymous");
+        assert_eq!(test.request_count(), 2);
+
+        for path in ["no-store", "vary-all", "teapot"] {
+            test.load(path, NOW);
+            assert!(test.loader.cache().entries(&test.url(path)).is_empty());
+        }
+    }
+
+    #[test]
+    fn offline_and_size_limit() {
+        let mut test = TestCache::new(10);
+
+        // Each body is 2 bytes (the path); after the sixth, the oldest is evicted
+        for path in ["/a", "/b", "/c", "/d", "/e", "/f"] {
+            assert_eq!(test.load(path, NOW), path);
+        }
+        assert_eq!(test.load("a", NOW), "/a");
+        assert_eq!(test.loader.cache().size(), 10);
+        assert!(test.loader.cache().entries(&test.url("b")).is_empty());
+
+        // Bodies larger than the cache are not stored
+        assert_eq!(test.load("too-large-body", NOW), "/too-large-body");
+        assert_eq!(test.loader.cache().len(), 5);
+
+        // The entries are kept on disk
+        let cache = DiskCache::open(&test.dir, 10).unwrap();
+        assert_eq!(cache.len(), 5);
+        test.loader = CachingLoader::new(Box::new(HttpLoader::default()), cache);
+
+        test.loader.set_offline(true);
+        let count = test.request_count();
+        assert_eq!(test.load("a", NOW + 1000), "/a");
+        assert!(matches!(
+            test.loader.load_at(&test.url("b"), NOW),
+            Err(Error::Load(LoadError::NotCached(_)))
+        ));
+        assert_eq!(test.request_count(), count);
+
+        let mut test = TestCache::new(DEFAULT_MAX_SIZE);
+        test.load("revalidate", NOW);
+        test.loader.set_offline(true);
+        assert_eq!(test.load("revalidate", NOW), "revalidate");
+        assert!(matches!(
+            test.loader.load_at(&test.url("revalidate"), NOW + 2),
+            Err(Error::Load(LoadError::NotCached(_)))
+        ));
+    }
+}
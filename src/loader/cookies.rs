@@ -0,0 +1,808 @@
+//! Cookie storage as specified by RFC 6265 (<https://www.rfc-editor.org/rfc/rfc6265>)
+//!
+//! The `SameSite` attribute and the `__Secure-` and `__Host-` name prefixes follow the RFC 6265
+//! update (draft-ietf-httpbis-rfc6265bis). Cookies without `SameSite` are treated as `Lax`, like
+//! current browsers do. Cookies cannot be set for a public suffix, like `com` or `co.uk`, unless it
+//! is the host itself.
+use crate::loader::now;
+use crate::types::Result;
+use crate::url::host::Host;
+use crate::url::Url;
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// When a cookie is sent along with cross-site requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SameSite {
+    /// Sent with all requests
+    None,
+    /// Sent with same-site requests and cross-site top-level navigations
+    #[default]
+    Lax,
+    /// Only sent with same-site requests
+    Strict,
+}
+
+/// The API a cookie is set or read through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieApi {
+    /// HTTP requests and responses
+    Http,
+    /// Non-HTTP APIs, like `document.cookie`. These cannot see or set `HttpOnly` cookies.
+    NonHttp,
+}
+
+/// How the site of a request relates to the site that initiated it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiteContext {
+    SameSite,
+    /// A cross-site request that navigates the top-level browsing context
+    CrossSiteNavigation,
+    CrossSite,
+}
+
+/// A stored cookie
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// The domain the cookie is sent to, lowercased and without leading dot
+    pub domain: String,
+    /// True when the cookie is only sent to the exact domain (when it had no `Domain` attribute)
+    pub host_only: bool,
+    pub path: String,
+    /// Expiry time in seconds since the Unix epoch. None for session cookies.
+    pub expires: Option<i64>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: SameSite,
+    /// Creation time in seconds since the Unix epoch
+    pub created: i64,
+}
+
+impl Cookie {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Returns true when the cookie is kept after the session ends
+    pub fn is_persistent(&self) -> bool {
+        self.expires.is_some()
+    }
+}
+
+/// Attributes of a `Set-Cookie` header (section 5.2)
+#[derive(Debug, Default)]
+struct SetCookie {
+    name: String,
+    value: String,
+    expires: Option<i64>,
+    max_age: Option<i64>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl SetCookie {
+    /// Parses the header. Returns None when the cookie must be ignored.
+    fn parse(header: &str, now: i64) -> Option<SetCookie> {
+        let (pair, attributes) = header.split_once(';').unwrap_or((header, ""));
+        let (name, value) = pair.split_once('=')?;
+        let name = name.trim_matches(is_wsp);
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = SetCookie {
+            name: name.to_string(),
+            value: value.trim_matches(is_wsp).to_string(),
+            ..SetCookie::default()
+        };
+
+        for attribute in attributes.split(';') {
+            let (name, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim_matches(is_wsp);
+            match name.trim_matches(is_wsp).to_ascii_lowercase().as_str() {
+                "expires" => {
+                    if let Some(expires) = parse_cookie_date(value) {
+                        cookie.expires = Some(expires);
+                    }
+                }
+                "max-age" => {
+                    let digits = value.strip_prefix('-').unwrap_or(value);
+                    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+                        continue;
+                    }
+                    // Values that do not fit are far enough in the future (or past)
+                    let seconds = value.parse::<i64>().unwrap_or(if value.starts_with('-') {
+                        i64::MIN
+                    } else {
+                        i64::MAX
+                    });
+                    cookie.max_age = Some(if seconds <= 0 {
+                        i64::MIN
+                    } else {
+                        now.saturating_add(seconds)
+                    });
+                }
+                "domain" if !value.is_empty() => {
+                    let domain = value.strip_prefix('.').unwrap_or(value);
+                    cookie.domain = Some(domain.to_ascii_lowercase());
+                }
+                "path" => {
+                    cookie.path = value.starts_with('/').then(|| value.to_string());
+                }
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => {
+                    cookie.same_site = match value.to_ascii_lowercase().as_str() {
+                        "none" => Some(SameSite::None),
+                        "lax" => Some(SameSite::Lax),
+                        "strict" => Some(SameSite::Strict),
+                        _ => None,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(cookie)
+    }
+}
+
+/// Stores the cookies received in responses, and returns the cookies to send with requests
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookieJar {
+    /// Cookies in order of creation
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the persistent cookies that were saved with [`CookieJar::save`]. Expired cookies
+    /// are dropped.
+    pub fn load(path: &Path) -> Result<CookieJar> {
+        let mut cookies: Vec<Cookie> = serde_json::from_slice(&fs::read(path)?)?;
+        let now = now();
+        cookies.retain(|cookie| !cookie.is_expired(now));
+        Ok(CookieJar { cookies })
+    }
+
+    /// Saves the persistent cookies as JSON. Session cookies are not saved.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let now = now();
+        let cookies: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.is_persistent() && !cookie.is_expired(now))
+            .collect();
+        fs::write(path, serde_json::to_string_pretty(&cookies)?)?;
+        Ok(())
+    }
+
+    /// Returns the cookies that have not expired
+    pub fn cookies(&self) -> Vec<&Cookie> {
+        let now = now();
+        self.cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now))
+            .collect()
+    }
+
+    /// Removes all cookies
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// Removes the session cookies, like a browser does when it is closed
+    pub fn end_session(&mut self) {
+        self.cookies.retain(Cookie::is_persistent);
+    }
+
+    /// Stores the cookie of a `Set-Cookie` header received from the URL. Returns false when the
+    /// cookie was ignored.
+    pub fn set_cookie(&mut self, url: &Url, header: &str, api: CookieApi) -> bool {
+        self.set_cookie_at(url, header, api, now())
+    }
+
+    /// Returns the value for the `Cookie` header of a request to the URL, or None when there are
+    /// no cookies to send
+    pub fn cookie_header(&self, url: &Url, api: CookieApi, context: SiteContext) -> Option<String> {
+        self.cookie_header_at(url, api, context, now())
+    }
+
+    /// Storage model (section 5.3)
+    fn set_cookie_at(&mut self, url: &Url, header: &str, api: CookieApi, now: i64) -> bool {
+        let Some(host) = canonical_host(url) else {
+            return false;
+        };
+        let Some(parsed) = SetCookie::parse(header, now) else {
+            return false;
+        };
+        let secure_url = is_secure(url);
+
+        // Cookies for a public suffix would be sent to all sites below it, so they are only
+        // allowed as host-only cookies of the public suffix itself
+        let parsed_domain = match parsed.domain {
+            Some(domain) if is_public_suffix(&domain) => {
+                if host != domain {
+                    return false;
+                }
+                None
+            }
+            domain => domain,
+        };
+
+        let (domain, host_only) = match parsed_domain {
+            Some(domain) if host == domain => (domain, false),
+            Some(domain) => {
+                if !domain_matches(url, &domain) {
+                    return false;
+                }
+                (domain, false)
+            }
+            None => (host, true),
+        };
+
+        let cookie = Cookie {
+            name: parsed.name,
+            value: parsed.value,
+            domain,
+            host_only,
+            path: parsed.path.unwrap_or_else(|| default_path(url)),
+            expires: parsed.max_age.or(parsed.expires),
+            secure: parsed.secure,
+            http_only: parsed.http_only,
+            same_site: parsed.same_site.unwrap_or_default(),
+            created: now,
+        };
+
+        if cookie.http_only && api == CookieApi::NonHttp {
+            return false;
+        }
+        if cookie.secure && !secure_url {
+            return false;
+        }
+        if cookie.same_site == SameSite::None && !cookie.secure {
+            return false;
+        }
+        if cookie.name.starts_with("__Secure-") && !cookie.secure {
+            return false;
+        }
+        if cookie.name.starts_with("__Host-")
+            && (!cookie.secure || !cookie.host_only || cookie.path != "/")
+        {
+            return false;
+        }
+
+        // Insecure URLs cannot overwrite (or shadow) secure cookies
+        if !secure_url
+            && self.cookies.iter().any(|existing| {
+                existing.secure
+                    && existing.name == cookie.name
+                    && (domain_matches_str(&existing.domain, &cookie.domain)
+                        || domain_matches_str(&cookie.domain, &existing.domain))
+                    && path_matches(&cookie.path, &existing.path)
+            })
+        {
+            return false;
+        }
+
+        let existing = self.cookies.iter().position(|existing| {
+            existing.name == cookie.name
+                && existing.domain == cookie.domain
+                && existing.path == cookie.path
+        });
+        match existing {
+            Some(idx) => {
+                if self.cookies[idx].http_only && api == CookieApi::NonHttp {
+                    return false;
+                }
+                let created = self.cookies[idx].created;
+                self.cookies[idx] = Cookie { created, ..cookie };
+            }
+            None => self.cookies.push(cookie),
+        }
+
+        // Setting an expired cookie is how a cookie is deleted
+        self.cookies.retain(|cookie| !cookie.is_expired(now));
+        true
+    }
+
+    /// Retrieval algorithm (section 5.4)
+    fn cookie_header_at(
+        &self,
+        url: &Url,
+        api: CookieApi,
+        context: SiteContext,
+        now: i64,
+    ) -> Option<String> {
+        let host = canonical_host(url)?;
+        let path = url.path();
+        let secure_url = is_secure(url);
+
+        let mut cookies: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|cookie| {
+                let domain_ok = if cookie.host_only {
+                    cookie.domain == host
+                } else {
+                    domain_matches(url, &cookie.domain)
+                };
+                let same_site_ok = match cookie.same_site {
+                    SameSite::None => true,
+                    SameSite::Lax => context != SiteContext::CrossSite,
+                    SameSite::Strict => context == SiteContext::SameSite,
+                };
+                domain_ok
+                    && path_matches(&path, &cookie.path)
+                    && (!cookie.secure || secure_url)
+                    && (!cookie.http_only || api == CookieApi::Http)
+                    && same_site_ok
+                    && !cookie.is_expired(now)
+            })
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+
+        // Longer paths first. The sort is stable, so cookies with equal paths stay in order of
+        // creation.
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        Some(
+            cookies
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+fn is_wsp(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn is_secure(url: &Url) -> bool {
+    matches!(url.scheme(), "https" | "wss")
+}
+
+fn canonical_host(url: &Url) -> Option<String> {
+    match url.host() {
+        Some(Host::Empty) | None => None,
+        Some(_) => Some(url.hostname().to_ascii_lowercase()),
+    }
+}
+
+fn is_public_suffix(domain: &str) -> bool {
+    let host = Host::Domain(domain.to_string());
+    host.public_suffix().is_some_and(|suffix| suffix == domain)
+}
+
+/// Domain matching (section 5.1.3). IP addresses only match themselves.
+fn domain_matches(url: &Url, domain: &str) -> bool {
+    match url.host() {
+        Some(Host::Domain(host)) => domain_matches_str(host, domain),
+        _ => canonical_host(url).is_some_and(|host| host == domain),
+    }
+}
+
+fn domain_matches_str(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Path matching (section 5.1.4)
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || request_path
+            .strip_prefix(cookie_path)
+            .is_some_and(|rest| cookie_path.ends_with('/') || rest.starts_with('/'))
+}
+
+/// The default path of a cookie is the directory of the request path (section 5.1.4)
+fn default_path(url: &Url) -> String {
+    let path = url.path();
+    if !path.starts_with('/') {
+        return "/".to_string();
+    }
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(idx) => path[..idx].to_string(),
+    }
+}
+
+/// Parses a date in a cookie's `Expires` attribute (section 5.1.1). Returns the time in seconds
+/// since the Unix epoch.
+pub fn parse_cookie_date(input: &str) -> Option<i64> {
+    let is_delimiter = |c: char| matches!(c, '\t' | ' '..='/' | ';'..='@' | '['..='`' | '{'..='~');
+
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+    for token in input.split(is_delimiter).filter(|token| !token.is_empty()) {
+        if time.is_none() {
+            if let Some(parsed) = parse_time(token) {
+                time = Some(parsed);
+                continue;
+            }
+        }
+        if day.is_none() {
+            if let Some((value, _)) = leading_digits(token, 1, 2) {
+                day = Some(value);
+                continue;
+            }
+        }
+        if month.is_none() {
+            if let Some(value) = parse_month(token) {
+                month = Some(value);
+                continue;
+            }
+        }
+        if year.is_none() {
+            if let Some((value, _)) = leading_digits(token, 2, 4) {
+                year = Some(value);
+                continue;
+            }
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    let (day, month, mut year) = (day?, month?, year?);
+    if (70..=99).contains(&year) {
+        year += 1900;
+    } else if year <= 69 {
+        year += 2000;
+    }
+    if !(1..=31).contains(&day) || year < 1601 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if day > days_in_month {
+        return None;
+    }
+
+    let days = days_from_civil(i64::from(year), i64::from(month), i64::from(day));
+    Some(days * 86400 + i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second))
+}
+
+/// Parses `1*2DIGIT ":" 1*2DIGIT ":" 1*2DIGIT ( non-digit *OCTET )`
+fn parse_time(token: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = token.splitn(3, ':');
+    let hour = parts.next()?;
+    let minute = parts.next()?;
+    let (second, _) = leading_digits(parts.next()?, 1, 2)?;
+    if !(1..=2).contains(&hour.len()) || !(1..=2).contains(&minute.len()) {
+        return None;
+    }
+    if !hour
+        .bytes()
+        .chain(minute.bytes())
+        .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    Some((hour.parse().ok()?, minute.parse().ok()?, second))
+}
+
+/// Parses `min*maxDIGIT ( non-digit *OCTET )` and returns the number and the rest of the token
+fn leading_digits(token: &str, min: usize, max: usize) -> Option<(u32, &str)> {
+    let length = token
+        .bytes()
+        .position(|byte| !byte.is_ascii_digit())
+        .unwrap_or(token.len());
+    if length < min || length > max {
+        return None;
+    }
+    Some((token[..length].parse().ok()?, &token[length..]))
+}
+
+fn parse_month(token: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let prefix = token.get(..3)?.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| *month == prefix)
+        .map(|idx| idx as u32 + 1)
+}
+
+/// Returns the number of days since 1970-01-01 for a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn url(input: &str) -> Url {
+        Url::parse(input).unwrap()
+    }
+
+    fn header(jar: &CookieJar, input: &str) -> Option<String> {
+        jar.cookie_header_at(&url(input), CookieApi::Http, SiteContext::SameSite, NOW)
+    }
+
+    #[test]
+    fn cookie_dates() {
+        assert_eq!(
+            parse_cookie_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784111777)
+        );
+        assert_eq!(
+            parse_cookie_date("Sunday, 06-Nov-94 08:49:37 GMT"),
+            Some(784111777)
+        );
+        assert_eq!(
+            parse_cookie_date("Sun Nov  6 08:49:37 1994"),
+            Some(784111777)
+        );
+        assert_eq!(parse_cookie_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(parse_cookie_date("29 Feb 2024 12:00:00"), Some(1709208000));
+        assert_eq!(parse_cookie_date("29 Feb 2023 12:00:00"), None);
+        assert_eq!(parse_cookie_date("06 Nov 1994"), None);
+        assert_eq!(parse_cookie_date("06 Nov 1994 24:00:00"), None);
+        assert_eq!(parse_cookie_date("06 Nov 1600 00:00:00"), None);
+    }
+
+    #[test]
+    fn domain_and_path_matching() {
+        let mut jar = CookieJar::new();
+        let set = |jar: &mut CookieJar, from: &str, header: &str| {
+            jar.set_cookie_at(&url(from), header, CookieApi::Http, NOW)
+        };
+
+        assert!(set(
+            &mut jar,
+            "https://www.example.com/docs/a.html",
+            "host=1"
+        ));
+        assert!(set(
+            &mut jar,
+            "https://www.example.com/",
+            "wide=2; Domain=.Example.com; Path=/"
+        ));
+        assert!(set(
+            &mut jar,
+            "https://www.example.com/",
+            "deep=3; Path=/docs/api"
+        ));
+        assert!(!set(
+            &mut jar,
+            "https://www.example.com/",
+            "other=4; Domain=example.org"
+        ));
+        assert!(!set(
+            &mut jar,
+            "https://www.example.com/",
+            "sub=5; Domain=sub.www.example.com"
+        ));
+        assert!(!set(&mut jar, "https://www.example.com/", "=6"));
+        assert!(!set(&mut jar, "https://www.example.com/", "novalue"));
+
+        assert_eq!(jar.cookies()[0].path, "/docs");
+        assert_eq!(
+            header(&jar, "https://www.example.com/docs"),
+            Some("host=1; wide=2".to_string())
+        );
+        assert_eq!(
+            header(&jar, "https://www.example.com/docs/api/x"),
+            Some("deep=3; host=1; wide=2".to_string())
+        );
+        assert_eq!(
+            header(&jar, "https://www.example.com/docsx"),
+            Some("wide=2".to_string())
+        );
+        assert_eq!(
+            header(&jar, "https://example.com/docs"),
+            Some("wide=2".to_string())
+        );
+        assert_eq!(header(&jar, "https://badexample.com/"), None);
+
+        // Replacing keeps the position, an expired cookie deletes it
+        assert!(set(&mut jar, "https://www.example.com/docs/", "host=new"));
+        assert_eq!(
+            header(&jar, "https://www.example.com/docs"),
+            Some("host=new; wide=2".to_string())
+        );
+        assert!(set(
+            &mut jar,
+            "https://www.example.com/docs/",
+            "host=; Max-Age=0"
+        ));
+        assert_eq!(
+            header(&jar, "https://www.example.com/docs"),
+            Some("wide=2".to_string())
+        );
+    }
+
+    #[test]
+    fn public_suffix_domains() {
+        let mut jar = CookieJar::new();
+        let set = |jar: &mut CookieJar, from: &str, header: &str| {
+            jar.set_cookie_at(&url(from), header, CookieApi::Http, NOW)
+        };
+
+        assert!(!set(&mut jar, "https://example.com/", "tld=1; Domain=com"));
+        assert!(!set(&mut jar, "https://example.com/", "tld=1; Domain=.COM"));
+        assert!(!set(
+            &mut jar,
+            "https://a.example.co.uk/",
+            "suffix=2; Domain=co.uk"
+        ));
+        assert!(set(
+            &mut jar,
+            "https://a.example.co.uk/",
+            "site=3; Domain=example.co.uk"
+        ));
+        assert_eq!(header(&jar, "https://other.co.uk/"), None);
+        assert_eq!(
+            header(&jar, "https://b.example.co.uk/"),
+            Some("site=3".to_string())
+        );
+
+        // A public suffix can set a host-only cookie for itself
+        assert!(set(
+            &mut jar,
+            "http://localhost/",
+            "local=4; Domain=localhost"
+        ));
+        assert!(jar
+            .cookies()
+            .iter()
+            .any(|c| c.name == "local" && c.host_only));
+        assert_eq!(header(&jar, "http://a.localhost/"), None);
+    }
+
+    #[test]
+    fn attributes_and_expiry() {
+        let mut jar = CookieJar::new();
+        let http = |jar: &mut CookieJar, from: &str, header: &str| {
+            jar.set_cookie_at(&url(from), header, CookieApi::Http, NOW)
+        };
+
+        assert!(http(&mut jar, "https://a.test/", "s=1; Secure"));
+        assert!(!http(&mut jar, "http://a.test/", "s2=1; Secure"));
+        assert!(!http(&mut jar, "http://a.test/", "s=insecure"));
+        assert!(http(&mut jar, "http://a.test/", "h=2; HttpOnly"));
+        assert!(http(
+            &mut jar,
+            "http://a.test/",
+            "strict=3; SameSite=Strict"
+        ));
+        assert!(http(
+            &mut jar,
+            "https://a.test/",
+            "none=4; SameSite=None; Secure"
+        ));
+        assert!(!http(&mut jar, "http://a.test/", "none2=4; SameSite=None"));
+        assert!(!http(
+            &mut jar,
+            "https://a.test/",
+            "__Host-x=5; Secure; Path=/docs"
+        ));
+        assert!(http(
+            &mut jar,
+            "https://a.test/",
+            "__Host-x=5; Secure; Path=/"
+        ));
+        assert!(!jar.set_cookie_at(&url("http://a.test/"), "h=3", CookieApi::NonHttp, NOW));
+        assert!(!jar.set_cookie_at(
+            &url("http://a.test/"),
+            "j=3; HttpOnly",
+            CookieApi::NonHttp,
+            NOW
+        ));
+
+        assert_eq!(
+            header(&jar, "http://a.test/"),
+            Some("h=2; strict=3".to_string())
+        );
+        assert_eq!(
+            jar.cookie_header_at(
+                &url("https://a.test/"),
+                CookieApi::NonHttp,
+                SiteContext::SameSite,
+                NOW
+            ),
+            Some("s=1; strict=3; none=4; __Host-x=5".to_string())
+        );
+        assert_eq!(
+            jar.cookie_header_at(
+                &url("https://a.test/"),
+                CookieApi::Http,
+                SiteContext::CrossSiteNavigation,
+                NOW
+            ),
+            Some("s=1; h=2; none=4; __Host-x=5".to_string())
+        );
+        assert_eq!(
+            jar.cookie_header_at(
+                &url("https://a.test/"),
+                CookieApi::Http,
+                SiteContext::CrossSite,
+                NOW
+            ),
+            Some("none=4".to_string())
+        );
+
+        // Max-Age takes precedence over Expires
+        jar.clear();
+        assert!(http(
+            &mut jar,
+            "http://a.test/",
+            "a=1; Max-Age=60; Expires=Thu, 01 Jan 1970 00:00:00 GMT"
+        ));
+        assert!(http(
+            &mut jar,
+            "http://a.test/",
+            "b=2; Expires=Thu, 01 Jan 2099 00:00:00 GMT"
+        ));
+        assert!(http(&mut jar, "http://a.test/", "c=3"));
+        assert_eq!(jar.cookies[0].expires, Some(NOW + 60));
+        assert!(jar.cookies[2].expires.is_none());
+        assert_eq!(
+            jar.cookie_header_at(
+                &url("http://a.test/"),
+                CookieApi::Http,
+                SiteContext::SameSite,
+                NOW + 61
+            ),
+            Some("b=2; c=3".to_string())
+        );
+    }
+
+    #[test]
+    fn persist_cookies() {
+        let mut jar = CookieJar::new();
+        jar.set_cookie(
+            &url("https://a.test/"),
+            "persistent=1; Max-Age=3600",
+            CookieApi::Http,
+        );
+        jar.set_cookie(&url("https://a.test/"), "session=2", CookieApi::Http);
+
+        let path =
+            std::env::temp_dir().join(format!("gosub-cookies-{}.json", uuid::Uuid::new_v4()));
+        jar.save(&path).unwrap();
+        let loaded = CookieJar::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        jar.end_session();
+        assert_eq!(loaded, jar);
+        assert_eq!(
+            loaded.cookie_header(
+                &url("https://a.test/"),
+                CookieApi::Http,
+                SiteContext::SameSite
+            ),
+            Some("persistent=1".to_string())
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! `data:` URLs (<https://fetch.spec.whatwg.org/#data-urls>, RFC 2397)
+use crate::url::percent_encoding::percent_decode;
+use crate::url::Url;
+
+/// MIME type of data URLs that do not specify one
+pub const DEFAULT_MIME_TYPE: &str = "text/plain;charset=US-ASCII";
+
+/// The contents of a data URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataUrl {
+    pub mime_type: String,
+    pub body: Vec<u8>,
+}
+
+impl DataUrl {
+    /// Processes the data URL. Returns None when it is not a valid data URL.
+    pub fn process(url: &Url) -> Option<DataUrl> {
+        if url.scheme() != "data" {
+            return None;
+        }
+
+        let href = url.without_fragment().href();
+        let input = &href["data:".len()..];
+        let (mime_type, body) = input.split_once(',')?;
+        let mut mime_type = mime_type.trim_matches(|c: char| c.is_ascii_whitespace());
+        let mut body = percent_decode(body.as_bytes());
+
+        if let Some(stripped) = strip_base64_suffix(mime_type) {
+            mime_type = stripped;
+            body = forgiving_base64_decode(&body)?;
+        }
+
+        let mime_type = if mime_type.starts_with(';') {
+            format!("text/plain{}", mime_type)
+        } else if mime_type.contains('/') {
+            mime_type.to_string()
+        } else {
+            DEFAULT_MIME_TYPE.to_string()
+        };
+
+        Some(DataUrl { mime_type, body })
+    }
+}
+
+/// Removes `;base64` (with optional spaces before `base64`) from the end of the MIME type
+fn strip_base64_suffix(mime_type: &str) -> Option<&str> {
+    let length = mime_type.len();
+    if length < "base64".len()
+        || !mime_type[length - "base64".len()..].eq_ignore_ascii_case("base64")
+    {
+        return None;
+    }
+
+    let rest = mime_type[..length - "base64".len()].trim_end_matches(' ');
+    let rest = rest.strip_suffix(';')?;
+    Some(rest.trim_end_matches(|c: char| c.is_ascii_whitespace()))
+}
+
+/// Decodes base64 the way browsers do (<https://infra.spec.whatwg.org/#forgiving-base64-decode>):
+/// whitespace is ignored and padding is optional
+pub fn forgiving_base64_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut data: Vec<u8> = input
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+
+    if data.len() % 4 == 0 {
+        for _ in 0..2 {
+            if data.last() == Some(&b'=') {
+                data.pop();
+            }
+        }
+    }
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in data {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = buffer << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(input: &str) -> Option<DataUrl> {
+        DataUrl::process(&Url::parse(input).unwrap())
+    }
+
+    #[test]
+    fn data_urls() {
+        let data = process("data:,Hello%2C%20World!").unwrap();
+        assert_eq!(data.mime_type, DEFAULT_MIME_TYPE);
+        assert_eq!(data.body, b"Hello, World!");
+
+        let data = process("data:text/html;charset=utf-8,<p>%C3%A9</p>#fragment").unwrap();
+        assert_eq!(data.mime_type, "text/html;charset=utf-8");
+        assert_eq!(String::from_utf8(data.body).unwrap(), "<p>é</p>");
+
+        let data = process("data:text/plain;BASE64,SGVs bG8=").unwrap();
+        assert_eq!(data.mime_type, "text/plain");
+        assert_eq!(data.body, b"Hello");
+
+        let data = process("data:;charset=utf-8;base64,SGk").unwrap();
+        assert_eq!(data.mime_type, "text/plain;charset=utf-8");
+        assert_eq!(data.body, b"Hi");
+
+        assert_eq!(process("data:text/plain"), None);
+        assert_eq!(process("data:;base64,SGV*"), None);
+        assert_eq!(process("https://example.com/,x"), None);
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(forgiving_base64_decode(b"").unwrap(), b"");
+        assert_eq!(forgiving_base64_decode(b"Zg==").unwrap(), b"f");
+        assert_eq!(forgiving_base64_decode(b"Zm8").unwrap(), b"fo");
+        assert_eq!(forgiving_base64_decode(b"Zm9v\nYmFy").unwrap(), b"foobar");
+        assert_eq!(forgiving_base64_decode(b"Zm9vY"), None);
+        assert_eq!(forgiving_base64_decode(b"Zg=a"), None);
+    }
+}
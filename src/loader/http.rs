@@ -0,0 +1,409 @@
+//! Loading of `http:` and `https:` URLs
+//!
+//! Redirects are followed by the loader itself (instead of by ureq), so cookies are stored and
+//! sent for every request in the chain. Responses are decoded according to their
+//! `Content-Encoding`.
+use crate::loader::cookies::{CookieApi, CookieJar, SiteContext};
+use crate::loader::{LoadError, ResourceLoader, Response, USER_AGENT};
+use crate::types::{Error, Result};
+use crate::url::Url;
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use std::cell::RefCell;
+use std::io::Read;
+use std::rc::Rc;
+
+/// Maximum number of redirects that are followed by default, like browsers do
+pub const DEFAULT_MAX_REDIRECTS: usize = 20;
+
+/// Content encodings that can be decoded
+const ACCEPT_ENCODING: &str = "gzip, deflate";
+
+/// Maximum size of a decoded body. Compressed content can be a thousand times smaller than the
+/// decoded content, so small responses could otherwise use up all memory.
+pub const MAX_DECODED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Loads `http:` and `https:` URLs. HTTP error statuses (like 404) are returned as responses,
+/// not as errors.
+pub struct HttpLoader {
+    agent: ureq::Agent,
+    max_redirects: usize,
+    cookies: Rc<RefCell<CookieJar>>,
+}
+
+impl Default for HttpLoader {
+    fn default() -> Self {
+        Self::new(Rc::default())
+    }
+}
+
+impl HttpLoader {
+    /// Creates a loader that stores cookies in the jar. Loaders can share a jar.
+    pub fn new(cookies: Rc<RefCell<CookieJar>>) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .user_agent(USER_AGENT)
+                .redirects(0)
+                .build(),
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            cookies,
+        }
+    }
+
+    /// Sets the maximum number of redirects to follow. With 0, redirect responses are returned.
+    pub fn set_max_redirects(&mut self, max_redirects: usize) {
+        self.max_redirects = max_redirects;
+    }
+
+    pub fn cookies(&self) -> Rc<RefCell<CookieJar>> {
+        Rc::clone(&self.cookies)
+    }
+
+    /// Performs a single GET request and stores the cookies of the response
+    fn request(
+        &self,
+        url: &Url,
+        context: SiteContext,
+        extra_headers: &[(String, String)],
+    ) -> Result<Response> {
+        let mut request = self
+            .agent
+            .get(&url.href())
+            .set("Accept-Encoding", ACCEPT_ENCODING);
+        let cookie = self
+            .cookies
+            .borrow()
+            .cookie_header(url, CookieApi::Http, context);
+        if let Some(cookie) = cookie {
+            request = request.set("Cookie", &cookie);
+        }
+        for (name, value) in extra_headers {
+            request = request.set(name, value);
+        }
+
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(Error::Request(Box::new(e))),
+        };
+
+        let status = response.status();
+        let mut headers = vec![];
+        for name in response.headers_names() {
+            for value in response.all(&name) {
+                headers.push((name.clone(), value.to_string()));
+            }
+        }
+        let mut body = vec![];
+        response.into_reader().read_to_end(&mut body)?;
+
+        let response = Response {
+            url: url.clone(),
+            status,
+            headers,
+            body,
+        };
+
+        let mut cookies = self.cookies.borrow_mut();
+        for header in response.header_values("set-cookie") {
+            cookies.set_cookie(url, header, CookieApi::Http);
+        }
+        Ok(response)
+    }
+}
+
+impl ResourceLoader for HttpLoader {
+    fn load(&self, url: &Url) -> Result<Response> {
+        self.load_with_headers(url, &[])
+    }
+
+    /// Loads the URL. The extra headers are only sent with the first request, not when
+    /// following redirects.
+    fn load_with_headers(&self, url: &Url, headers: &[(String, String)]) -> Result<Response> {
+        let mut url = url.without_fragment();
+        let mut context = SiteContext::SameSite;
+        let mut redirects = 0;
+        let mut extra_headers = headers;
+
+        loop {
+            let mut response = self.request(&url, context, extra_headers)?;
+            extra_headers = &[];
+
+            let location = response.header("location");
+            if !is_redirect(response.status) || location.is_none() || self.max_redirects == 0 {
+                let content_encoding = response
+                    .header("content-encoding")
+                    .unwrap_or_default()
+                    .to_string();
+                // Responses like 304 Not Modified have no body to decode
+                if !response.body.is_empty() {
+                    response.body = decode_content(&content_encoding, response.body)?;
+                }
+                return Ok(response);
+            }
+
+            redirects += 1;
+            if redirects > self.max_redirects {
+                return Err(LoadError::TooManyRedirects(self.max_redirects).into());
+            }
+
+            let location = location.unwrap_or_default();
+            let next = url
+                .join(location)
+                .ok()
+                .filter(|next| matches!(next.scheme(), "http" | "https"))
+                .ok_or_else(|| LoadError::InvalidRedirect(location.to_string()))?;
+
+            // Once the chain leaves the site, it is a cross-site navigation
+            if !next.is_same_site(&url) {
+                context = SiteContext::CrossSiteNavigation;
+            }
+            url = next.without_fragment();
+        }
+    }
+
+    fn request_headers(&self, url: &Url) -> Vec<(String, String)> {
+        let mut headers = vec![
+            ("User-Agent".to_string(), USER_AGENT.to_string()),
+            ("Accept-Encoding".to_string(), ACCEPT_ENCODING.to_string()),
+        ];
+        let cookie =
+            self.cookies
+                .borrow()
+                .cookie_header(url, CookieApi::Http, SiteContext::SameSite);
+        if let Some(cookie) = cookie {
+            headers.push(("Cookie".to_string(), cookie));
+        }
+        headers
+    }
+}
+
+fn is_redirect(status: u16) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+/// Decodes the body according to the `Content-Encoding` header. Encodings are listed in the
+/// order they were applied, so they are undone in reverse order. Fails when the decoded body is
+/// larger than [`MAX_DECODED_SIZE`].
+pub(crate) fn decode_content(content_encoding: &str, body: Vec<u8>) -> Result<Vec<u8>> {
+    let mut body = body;
+    for encoding in content_encoding.rsplit(',') {
+        let encoding = encoding.trim().to_ascii_lowercase();
+        body = match encoding.as_str() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => read_decoded(MultiGzDecoder::new(&body[..]), MAX_DECODED_SIZE),
+            // Some servers send raw DEFLATE data instead of zlib data
+            "deflate" => match read_decoded(ZlibDecoder::new(&body[..]), MAX_DECODED_SIZE) {
+                Err(LoadError::InvalidContent(_)) => {
+                    read_decoded(DeflateDecoder::new(&body[..]), MAX_DECODED_SIZE)
+                }
+                result => result,
+            },
+            _ => return Err(LoadError::UnsupportedContentEncoding(encoding).into()),
+        }
+        .map_err(|err| match err {
+            LoadError::InvalidContent(_) => LoadError::InvalidContent(encoding),
+            err => err,
+        })?;
+    }
+    Ok(body)
+}
+
+/// Reads all decoded data from the decoder. Fails with `InvalidContent` when the data is not
+/// valid, and with `ContentTooLarge` when there are more than `limit` bytes.
+pub(crate) fn read_decoded(
+    decoder: impl Read,
+    limit: usize,
+) -> std::result::Result<Vec<u8>, LoadError> {
+    let mut decoded = vec![];
+    decoder
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|err| LoadError::InvalidContent(err.to_string()))?;
+    if decoded.len() > limit {
+        return Err(LoadError::ContentTooLarge(limit));
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::test_server::{response, serve_routes};
+
+    #[test]
+    fn follows_redirects_with_cookies() {
+        let (base, requests) = serve_routes(vec![
+            (
+                "/start",
+                response(
+                    "302 Found",
+                    &["Location: /login?next=1", "Set-Cookie: visit=1; Path=/"],
+                    b"",
+                ),
+            ),
+            (
+                "/login?next=1",
+                response(
+                    "301 Moved Permanently",
+                    &["Location: final#top", "Set-Cookie: session=abc; HttpOnly"],
+                    b"",
+                ),
+            ),
+            (
+                "/final",
+                response("200 OK", &["Content-Type: text/html"], b"<p>done</p>"),
+            ),
+            ("/loop", response("302 Found", &["Location: /loop"], b"")),
+        ]);
+
+        let loader = HttpLoader::default();
+        let response = loader.load(&base.join("start").unwrap()).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.url, base.join("final").unwrap());
+        assert_eq!(response.text(), "<p>done</p>");
+
+        let requests = requests.lock().unwrap().clone();
+        let paths: Vec<&str> = requests
+            .iter()
+            .map(|request| request.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["/start", "/login?next=1", "/final"]);
+        assert_eq!(requests[0].header("cookie"), None);
+        assert_eq!(requests[1].header("cookie"), Some("visit=1"));
+        assert_eq!(requests[2].header("cookie"), Some("visit=1; session=abc"));
+        assert_eq!(requests[0].header("accept-encoding"), Some("gzip, deflate"));
+
+        assert!(matches!(
+            loader.load(&base.join("loop").unwrap()),
+            Err(Error::Load(LoadError::TooManyRedirects(
+                DEFAULT_MAX_REDIRECTS
+            )))
+        ));
+
+        let mut loader = HttpLoader::default();
+        loader.set_max_redirects(0);
+        let response = loader.load(&base.join("start").unwrap()).unwrap();
+        assert_eq!(response.status, 302);
+        assert_eq!(response.header("location"), Some("/login?next=1"));
+    }
+
+    #[test]
+    fn decodes_content() {
+        let gzip =
+            b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xb3\x29\xb0\x4b\xaf\xca\x2c\xb0\xd1\x2f\
+            \xb0\x03\x00\xec\x4c\xe0\x7a\x0b\x00\x00\x00";
+        let deflate =
+            b"\x78\xda\xb3\x29\xb0\x4b\x49\x4d\xcb\x49\x2c\x49\xb5\xd1\x2f\xb0\x03\x00\x25\x15\
+            \x04\xd9";
+        let (base, _) = serve_routes(vec![
+            (
+                "/gzip",
+                response("200 OK", &["Content-Encoding: gzip"], gzip),
+            ),
+            (
+                "/deflate",
+                response("200 OK", &["Content-Encoding: Deflate"], deflate),
+            ),
+            (
+                "/missing",
+                response("404 Not Found", &["Content-Encoding: gzip"], gzip),
+            ),
+            (
+                "/broken",
+                response("200 OK", &["Content-Encoding: gzip"], b"plain"),
+            ),
+            ("/br", response("200 OK", &["Content-Encoding: br"], b"?")),
+        ]);
+
+        let loader = HttpLoader::default();
+        let load = |path: &str| loader.load(&base.join(path).unwrap());
+        assert_eq!(load("gzip").unwrap().text(), "<p>gzip</p>");
+        assert_eq!(load("deflate").unwrap().text(), "<p>deflate</p>");
+
+        let response = load("missing").unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.text(), "<p>gzip</p>");
+
+        assert!(matches!(
+            load("broken"),
+            Err(Error::Load(LoadError::InvalidContent(encoding))) if encoding == "gzip"
+        ));
+        assert!(matches!(
+            load("br"),
+            Err(Error::Load(LoadError::UnsupportedContentEncoding(encoding))) if encoding == "br"
+        ));
+    }
+
+    #[test]
+    fn content_encodings() {
+        let deflate = b"\x01\x06\x00\xf9\xffstored";
+        assert_eq!(decode_content("", b"plain".to_vec()).unwrap(), b"plain");
+        assert_eq!(
+            decode_content("identity", b"plain".to_vec()).unwrap(),
+            b"plain"
+        );
+        assert_eq!(
+            decode_content("deflate", deflate.to_vec()).unwrap(),
+            b"stored"
+        );
+        assert_eq!(
+            decode_content("deflate, identity", deflate.to_vec()).unwrap(),
+            b"stored"
+        );
+
+        let zlib = b"\x78\xda\xf3\x48\xcd\xc9\xc9\xd7\x51\xf0\x40\xa2\x14\x01\x46\x3e\x06\x96";
+        assert_eq!(
+            decode_content("deflate", zlib.to_vec()).unwrap(),
+            b"Hello, Hello, Hello!"
+        );
+        let gzip =
+            b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xb3\x29\xb0\x4b\xaf\xca\x2c\xb0\xd1\x2f\
+            \xb0\x03\x00\xec\x4c\xe0\x7a\x0b\x00\x00\x00";
+        assert_eq!(
+            decode_content("gzip", [gzip.as_slice(), gzip.as_slice()].concat()).unwrap(),
+            b"<p>gzip</p><p>gzip</p>"
+        );
+
+        // Wrong checksum and truncated input
+        let mut bad_checksum = gzip.to_vec();
+        bad_checksum[gzip.len() - 5] ^= 1;
+        for (encoding, body) in [
+            ("gzip", bad_checksum),
+            ("gzip", gzip[..gzip.len() - 1].to_vec()),
+            ("gzip", b"\x78\xda".to_vec()),
+        ] {
+            assert!(matches!(
+                decode_content(encoding, body),
+                Err(Error::Load(LoadError::InvalidContent(e))) if e == encoding
+            ));
+        }
+    }
+
+    #[test]
+    fn decoded_size_limit() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(vec![], Compression::best());
+        encoder.write_all(&vec![0; MAX_DECODED_SIZE + 1]).unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert!(bomb.len() < 100 * 1024);
+        assert!(matches!(
+            decode_content("gzip", bomb),
+            Err(Error::Load(LoadError::ContentTooLarge(MAX_DECODED_SIZE)))
+        ));
+
+        let mut encoder = GzEncoder::new(vec![], Compression::fast());
+        encoder.write_all(b"0123456789").unwrap();
+        let data = encoder.finish().unwrap();
+        assert_eq!(
+            read_decoded(MultiGzDecoder::new(&data[..]), 10).unwrap(),
+            b"0123456789"
+        );
+        assert!(matches!(
+            read_decoded(MultiGzDecoder::new(&data[..]), 9),
+            Err(LoadError::ContentTooLarge(9))
+        ));
+    }
+}
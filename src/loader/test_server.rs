@@ -0,0 +1,87 @@
+//! A minimal HTTP server on a local port, so loaders can be tested without network access
+use crate::loader::header;
+use crate::url::Url;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A request received by the server
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// The path and query of the request target
+    pub path: String,
+    /// The headers, with lowercased names
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+}
+
+/// Requests received by the server, in order
+pub type Requests = Arc<Mutex<Vec<Request>>>;
+
+/// Starts a server that answers every request with the raw response the handler returns.
+/// Returns the base URL and the requests it received.
+pub fn serve(handler: impl Fn(&Request) -> Vec<u8> + Send + 'static) -> (Url, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    let requests = Arc::new(Mutex::new(vec![]));
+
+    let received = Arc::clone(&requests);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut lines = BufReader::new(&stream).lines().map_while(|line| line.ok());
+            let request_line = lines.next().unwrap_or_default();
+            let request = Request {
+                path: request_line.split(' ').nth(1).unwrap_or("/").to_string(),
+                headers: lines
+                    .take_while(|line| !line.is_empty())
+                    .filter_map(|line| {
+                        line.split_once(':').map(|(name, value)| {
+                            (name.to_ascii_lowercase(), value.trim().to_string())
+                        })
+                    })
+                    .collect(),
+            };
+            let response = handler(&request);
+            // Recorded before responding, so the request is visible once the response arrives
+            received.lock().unwrap().push(request);
+            let _ = stream.write_all(&response);
+        }
+    });
+
+    (base, requests)
+}
+
+/// Starts a server that serves fixed responses for the paths, and 404 for other paths
+pub fn serve_routes(routes: Vec<(&'static str, Vec<u8>)>) -> (Url, Requests) {
+    serve(move |request| {
+        routes
+            .iter()
+            .find(|(route, _)| *route == request.path)
+            .map(|(_, response)| response.clone())
+            .unwrap_or_else(|| response("404 Not Found", &[], b"not found"))
+    })
+}
+
+/// Builds a raw HTTP/1.1 response. The connection is closed after the response.
+pub fn response(status: &str, headers: &[&str], body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        body.len()
+    );
+    for header in headers {
+        response.push_str(header);
+        response.push_str("\r\n");
+    }
+    response.push_str("\r\n");
+    [response.as_bytes(), body].concat()
+}
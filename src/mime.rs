@@ -0,0 +1,653 @@
+//! MIME types and content sniffing as specified by the WHATWG MIME Sniffing standard
+//! (<https://mimesniff.spec.whatwg.org/>)
+//!
+//! The MIME type a server supplies in the `Content-Type` header is not always correct. The
+//! computed MIME type of a resource is determined from the supplied type and the first bytes of
+//! the resource. Servers can opt out of sniffing with `X-Content-Type-Options: nosniff`.
+use std::fmt;
+
+/// Number of bytes of a resource that are used for sniffing
+pub const SNIFF_LENGTH: usize = 1445;
+
+/// A parsed MIME type, like `text/html;charset=utf-8`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MimeType {
+    /// The type (like `text`), lowercased
+    pub type_name: String,
+    /// The subtype (like `html`), lowercased
+    pub subtype: String,
+    /// The parameters in order, with lowercased names
+    pub parameters: Vec<(String, String)>,
+}
+
+impl MimeType {
+    /// Creates a MIME type without parameters
+    pub fn new(type_name: &str, subtype: &str) -> Self {
+        Self {
+            type_name: type_name.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            parameters: vec![],
+        }
+    }
+
+    /// Parses a MIME type (<https://mimesniff.spec.whatwg.org/#parse-a-mime-type>). Returns None
+    /// when the input is not a valid MIME type.
+    pub fn parse(input: &str) -> Option<MimeType> {
+        let input: Vec<char> = input.trim_matches(is_http_whitespace).chars().collect();
+        let mut pos = 0;
+
+        let type_name = collect(&input, &mut pos, |c| c != '/');
+        if type_name.is_empty() || !type_name.chars().all(is_token) || pos >= input.len() {
+            return None;
+        }
+        pos += 1;
+
+        let subtype = collect(&input, &mut pos, |c| c != ';');
+        let subtype = subtype.trim_end_matches(is_http_whitespace);
+        if subtype.is_empty() || !subtype.chars().all(is_token) {
+            return None;
+        }
+
+        let mut mime_type = MimeType::new(&type_name, subtype);
+
+        while pos < input.len() {
+            // Skip the ; and whitespace before the name
+            pos += 1;
+            collect(&input, &mut pos, is_http_whitespace);
+
+            let name = collect(&input, &mut pos, |c| c != ';' && c != '=').to_ascii_lowercase();
+            if pos < input.len() {
+                if input[pos] == ';' {
+                    continue;
+                }
+                pos += 1;
+            }
+            if pos >= input.len() {
+                break;
+            }
+
+            let value = if input[pos] == '"' {
+                let value = collect_quoted_string(&input, &mut pos);
+                collect(&input, &mut pos, |c| c != ';');
+                value
+            } else {
+                let value = collect(&input, &mut pos, |c| c != ';');
+                let value = value.trim_end_matches(is_http_whitespace).to_string();
+                if value.is_empty() {
+                    continue;
+                }
+                value
+            };
+
+            if !name.is_empty()
+                && name.chars().all(is_token)
+                && value.chars().all(is_quoted_string_token)
+                && mime_type.parameter(&name).is_none()
+            {
+                mime_type.parameters.push((name, value));
+            }
+        }
+
+        Some(mime_type)
+    }
+
+    /// Returns the type and subtype, without parameters (like `text/html`)
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_name, self.subtype)
+    }
+
+    /// Returns the value of the parameter. Names are matched case-insensitively.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the value of the charset parameter
+    pub fn charset(&self) -> Option<&str> {
+        self.parameter("charset")
+    }
+
+    pub fn is_html(&self) -> bool {
+        self.essence() == "text/html"
+    }
+
+    /// Returns true for `*/*+xml`, `text/xml` and `application/xml`
+    pub fn is_xml(&self) -> bool {
+        self.subtype.ends_with("+xml")
+            || self.essence() == "text/xml"
+            || self.essence() == "application/xml"
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.type_name == "image"
+    }
+
+    pub fn is_audio_or_video(&self) -> bool {
+        self.type_name == "audio"
+            || self.type_name == "video"
+            || self.essence() == "application/ogg"
+    }
+
+    pub fn is_javascript(&self) -> bool {
+        matches!(
+            self.essence().as_str(),
+            "application/ecmascript"
+                | "application/javascript"
+                | "application/x-ecmascript"
+                | "application/x-javascript"
+                | "text/ecmascript"
+                | "text/javascript"
+                | "text/javascript1.0"
+                | "text/javascript1.1"
+                | "text/javascript1.2"
+                | "text/javascript1.3"
+                | "text/javascript1.4"
+                | "text/javascript1.5"
+                | "text/jscript"
+                | "text/livescript"
+                | "text/x-ecmascript"
+                | "text/x-javascript"
+        )
+    }
+
+    /// Returns true for `*/*+json`, `application/json` and `text/json`
+    pub fn is_json(&self) -> bool {
+        self.subtype.ends_with("+json")
+            || self.essence() == "application/json"
+            || self.essence() == "text/json"
+    }
+
+    /// Returns true for MIME types that do not tell what the resource is
+    fn is_unknown(&self) -> bool {
+        matches!(
+            self.essence().as_str(),
+            "unknown/unknown" | "application/unknown" | "*/*"
+        )
+    }
+}
+
+impl fmt::Display for MimeType {
+    /// Serializes the MIME type (<https://mimesniff.spec.whatwg.org/#serialize-a-mime-type>)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.type_name, self.subtype)?;
+        for (name, value) in &self.parameters {
+            write!(f, ";{}=", name)?;
+            if !value.is_empty() && value.chars().all(is_token) {
+                write!(f, "{}", value)?;
+            } else {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "\"{}\"", escaped)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_http_whitespace(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | ' ')
+}
+
+fn is_token(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+fn is_quoted_string_token(c: char) -> bool {
+    c == '\t' || (' '..='~').contains(&c) || ('\u{80}'..='\u{FF}').contains(&c)
+}
+
+/// Collects the code points from the position while the condition holds
+fn collect(input: &[char], pos: &mut usize, condition: impl Fn(char) -> bool) -> String {
+    let start = *pos;
+    while *pos < input.len() && condition(input[*pos]) {
+        *pos += 1;
+    }
+    input[start..*pos].iter().collect()
+}
+
+/// Collects an HTTP quoted string starting at the opening quote, and returns its value
+/// (<https://fetch.spec.whatwg.org/#collect-an-http-quoted-string>)
+fn collect_quoted_string(input: &[char], pos: &mut usize) -> String {
+    let mut value = String::new();
+    *pos += 1;
+    loop {
+        value.push_str(&collect(input, pos, |c| c != '"' && c != '\\'));
+        if *pos >= input.len() {
+            break;
+        }
+        let quote_or_backslash = input[*pos];
+        *pos += 1;
+        if quote_or_backslash == '\\' {
+            if *pos >= input.len() {
+                value.push('\\');
+                break;
+            }
+            value.push(input[*pos]);
+            *pos += 1;
+        } else {
+            break;
+        }
+    }
+    value
+}
+
+/// Returns true when the `X-Content-Type-Options` header value opts out of sniffing
+pub fn is_nosniff(header: &str) -> bool {
+    header.split(',').next().is_some_and(|value| {
+        value
+            .trim_matches(is_http_whitespace)
+            .eq_ignore_ascii_case("nosniff")
+    })
+}
+
+/// Determines the computed MIME type of a resource
+/// (<https://mimesniff.spec.whatwg.org/#determining-the-computed-mime-type-of-a-resource>)
+///
+/// `content_type` is the value of the `Content-Type` header (if any), and `bytes` the start of
+/// the resource (only the first [`SNIFF_LENGTH`] bytes are looked at).
+pub fn computed_mime_type(content_type: Option<&str>, no_sniff: bool, bytes: &[u8]) -> MimeType {
+    let bytes = &bytes[..bytes.len().min(SNIFF_LENGTH)];
+    let supplied = content_type.and_then(MimeType::parse);
+
+    let supplied = match supplied {
+        Some(supplied) if !supplied.is_unknown() => supplied,
+        _ => return identify_unknown(bytes, !no_sniff),
+    };
+    if no_sniff {
+        return supplied;
+    }
+
+    // Apache sends text/plain for everything it does not know
+    let apache_bug = content_type.is_some_and(|content_type| {
+        matches!(
+            content_type,
+            "text/plain"
+                | "text/plain; charset=ISO-8859-1"
+                | "text/plain; charset=iso-8859-1"
+                | "text/plain; charset=UTF-8"
+        )
+    });
+    if apache_bug {
+        return text_or_binary(bytes);
+    }
+
+    if supplied.is_xml() {
+        return supplied;
+    }
+    if supplied.is_html() {
+        return feed_or_html(bytes);
+    }
+    if supplied.is_image() {
+        if let Some(image) = match_image(bytes) {
+            return image;
+        }
+    }
+    if supplied.is_audio_or_video() {
+        if let Some(media) = match_audio_or_video(bytes) {
+            return media;
+        }
+    }
+    supplied
+}
+
+/// Returns true when the byte pattern matches the start of the input, where the mask selects the
+/// significant bits. Leading whitespace is skipped when `skip_whitespace` is set.
+fn matches_pattern(input: &[u8], pattern: &[u8], mask: &[u8], skip_whitespace: bool) -> bool {
+    let mut input = input;
+    if skip_whitespace {
+        while let Some(byte) = input.first() {
+            if !matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ') {
+                break;
+            }
+            input = &input[1..];
+        }
+    }
+    input.len() >= pattern.len()
+        && pattern
+            .iter()
+            .zip(mask)
+            .zip(input)
+            .all(|((pattern, mask), byte)| byte & mask == *pattern)
+}
+
+/// Returns true when the input starts with the case-insensitive tag name, followed by a space or
+/// `>` (after skipping whitespace)
+fn matches_html_tag(input: &[u8], tag: &[u8]) -> bool {
+    let start = input
+        .iter()
+        .position(|byte| !matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' '))
+        .unwrap_or(input.len());
+    let input = &input[start..];
+    input.len() > tag.len()
+        && input[..tag.len()].eq_ignore_ascii_case(tag)
+        && matches!(input[tag.len()], b' ' | b'>')
+}
+
+/// Tags that identify HTML documents
+static HTML_TAGS: [&[u8]; 17] = [
+    b"<!DOCTYPE HTML",
+    b"<HTML",
+    b"<HEAD",
+    b"<SCRIPT",
+    b"<IFRAME",
+    b"<H1",
+    b"<DIV",
+    b"<FONT",
+    b"<TABLE",
+    b"<A",
+    b"<STYLE",
+    b"<TITLE",
+    b"<B",
+    b"<BODY",
+    b"<BR",
+    b"<P",
+    b"<!--",
+];
+
+/// Identifies a resource without a (known) supplied MIME type
+/// (<https://mimesniff.spec.whatwg.org/#rules-for-identifying-an-unknown-mime-type>)
+fn identify_unknown(bytes: &[u8], sniff_scriptable: bool) -> MimeType {
+    if sniff_scriptable {
+        if HTML_TAGS.iter().any(|tag| matches_html_tag(bytes, tag)) {
+            return MimeType::new("text", "html");
+        }
+        if matches_pattern(bytes, b"<?xml", &[0xFF; 5], true) {
+            return MimeType::new("text", "xml");
+        }
+    }
+
+    if bytes.starts_with(b"%PDF-") {
+        return MimeType::new("application", "pdf");
+    }
+    if bytes.starts_with(b"%!PS-Adobe-") {
+        return MimeType::new("application", "postscript");
+    }
+    if bytes.starts_with(&[0xFE, 0xFF])
+        || bytes.starts_with(&[0xFF, 0xFE])
+        || bytes.starts_with(&[0xEF, 0xBB, 0xBF])
+    {
+        return MimeType::new("text", "plain");
+    }
+    if let Some(mime_type) = match_image(bytes)
+        .or_else(|| match_audio_or_video(bytes))
+        .or_else(|| match_archive(bytes))
+    {
+        return mime_type;
+    }
+    if !bytes.iter().any(|byte| is_binary_data_byte(*byte)) {
+        return MimeType::new("text", "plain");
+    }
+    MimeType::new("application", "octet-stream")
+}
+
+/// Rules for distinguishing if a resource is text or binary
+/// (<https://mimesniff.spec.whatwg.org/#rules-for-text-or-binary>)
+fn text_or_binary(bytes: &[u8]) -> MimeType {
+    if bytes.starts_with(&[0xFE, 0xFF])
+        || bytes.starts_with(&[0xFF, 0xFE])
+        || bytes.starts_with(&[0xEF, 0xBB, 0xBF])
+        || !bytes.iter().any(|byte| is_binary_data_byte(*byte))
+    {
+        return MimeType::new("text", "plain");
+    }
+    identify_unknown(bytes, false)
+}
+
+fn is_binary_data_byte(byte: u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0B | 0x0E..=0x1A | 0x1C..=0x1F)
+}
+
+/// Image type pattern matching (<https://mimesniff.spec.whatwg.org/#matching-an-image-type-pattern>)
+fn match_image(bytes: &[u8]) -> Option<MimeType> {
+    let subtype = if bytes.starts_with(&[0x00, 0x00, 0x01, 0x00])
+        || bytes.starts_with(&[0x00, 0x00, 0x02, 0x00])
+    {
+        "x-icon"
+    } else if bytes.starts_with(b"BM") {
+        "bmp"
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        "gif"
+    } else if bytes.len() >= 14 && bytes.starts_with(b"RIFF") && &bytes[8..14] == b"WEBPVP" {
+        "webp"
+    } else if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        "png"
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "jpeg"
+    } else {
+        return None;
+    };
+    Some(MimeType::new("image", subtype))
+}
+
+/// Audio or video type pattern matching
+/// (<https://mimesniff.spec.whatwg.org/#matching-an-audio-or-video-type-pattern>). MP3 files
+/// without an ID3 tag are not detected.
+fn match_audio_or_video(bytes: &[u8]) -> Option<MimeType> {
+    let riff_type =
+        |form: &[u8]| bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == form;
+
+    let (type_name, subtype) =
+        if bytes.len() >= 12 && bytes.starts_with(b"FORM") && &bytes[8..12] == b"AIFF" {
+            ("audio", "aiff")
+        } else if bytes.starts_with(b"ID3") {
+            ("audio", "mpeg")
+        } else if bytes.starts_with(b"OggS\x00") {
+            ("application", "ogg")
+        } else if bytes.starts_with(b"MThd\x00\x00\x00\x06") {
+            ("audio", "midi")
+        } else if riff_type(b"AVI ") {
+            ("video", "avi")
+        } else if riff_type(b"WAVE") {
+            ("audio", "wave")
+        } else if is_mp4(bytes) {
+            ("video", "mp4")
+        } else if bytes.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+            ("video", "webm")
+        } else {
+            return None;
+        };
+    Some(MimeType::new(type_name, subtype))
+}
+
+/// Returns true when the bytes start with an MP4 file type box with an `mp4` brand
+/// (<https://mimesniff.spec.whatwg.org/#signature-for-mp4>)
+fn is_mp4(bytes: &[u8]) -> bool {
+    if bytes.len() < 12 {
+        return false;
+    }
+    let box_size = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    if box_size > bytes.len() || box_size % 4 != 0 || &bytes[4..8] != b"ftyp" {
+        return false;
+    }
+    if &bytes[8..11] == b"mp4" {
+        return true;
+    }
+    (16..box_size)
+        .step_by(4)
+        .any(|offset| offset + 3 <= bytes.len() && &bytes[offset..offset + 3] == b"mp4")
+}
+
+/// Archive type pattern matching (<https://mimesniff.spec.whatwg.org/#matching-an-archive-type-pattern>)
+fn match_archive(bytes: &[u8]) -> Option<MimeType> {
+    let subtype = if bytes.starts_with(&[0x1F, 0x8B, 0x08]) {
+        "x-gzip"
+    } else if bytes.starts_with(b"PK\x03\x04") {
+        "zip"
+    } else if bytes.starts_with(b"Rar!\x1A\x07\x00") {
+        "x-rar-compressed"
+    } else {
+        return None;
+    };
+    Some(MimeType::new("application", subtype))
+}
+
+/// Distinguishes RSS and Atom feeds from HTML
+/// (<https://mimesniff.spec.whatwg.org/#rules-for-distinguishing-if-a-resource-is-a-feed-or-html>)
+fn feed_or_html(bytes: &[u8]) -> MimeType {
+    let html = MimeType::new("text", "html");
+    let mut pos = if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        3
+    } else {
+        0
+    };
+
+    loop {
+        while pos < bytes.len() && matches!(bytes[pos], b'\t' | b'\n' | b'\r' | b' ') {
+            pos += 1;
+        }
+        if pos >= bytes.len() || bytes[pos] != b'<' {
+            return html;
+        }
+        pos += 1;
+
+        let rest = &bytes[pos..];
+        if rest.starts_with(b"!--") {
+            match find(rest, b"-->") {
+                Some(end) => pos += end + 3,
+                None => return html,
+            }
+        } else if rest.starts_with(b"!") {
+            match find(rest, b">") {
+                Some(end) => pos += end + 1,
+                None => return html,
+            }
+        } else if rest.starts_with(b"?") {
+            match find(rest, b"?>") {
+                Some(end) => pos += end + 2,
+                None => return html,
+            }
+        } else if rest.starts_with(b"rss") {
+            return MimeType::new("application", "rss+xml");
+        } else if rest.starts_with(b"feed") {
+            return MimeType::new("application", "atom+xml");
+        } else if rest.starts_with(b"rdf:RDF") {
+            if find(rest, b"http://purl.org/rss/1.0/").is_some()
+                && find(rest, b"http://www.w3.org/1999/02/22-rdf-syntax-ns#").is_some()
+            {
+                return MimeType::new("application", "rss+xml");
+            }
+            return html;
+        } else {
+            return html;
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn essence(content_type: Option<&str>, no_sniff: bool, bytes: &[u8]) -> String {
+        computed_mime_type(content_type, no_sniff, bytes).essence()
+    }
+
+    #[test]
+    fn parse_mime_types() {
+        let mime_type =
+            MimeType::parse(" Text/HTML ; Charset=\"utf-8\" ; foo=bar;foo=baz").unwrap();
+        assert_eq!(mime_type.essence(), "text/html");
+        assert_eq!(mime_type.charset(), Some("utf-8"));
+        assert_eq!(mime_type.parameter("FOO"), Some("bar"));
+        assert_eq!(mime_type.to_string(), "text/html;charset=utf-8;foo=bar");
+
+        let mime_type = MimeType::parse("text/plain;a=\"b\\\"c\";d;e=;f=\"g h\"x").unwrap();
+        assert_eq!(mime_type.parameter("a"), Some("b\"c"));
+        assert_eq!(mime_type.parameter("d"), None);
+        assert_eq!(mime_type.parameter("e"), None);
+        assert_eq!(mime_type.to_string(), "text/plain;a=\"b\\\"c\";f=\"g h\"");
+
+        assert_eq!(MimeType::parse("text"), None);
+        assert_eq!(MimeType::parse("text/"), None);
+        assert_eq!(MimeType::parse("te xt/html"), None);
+        assert_eq!(MimeType::parse("/html"), None);
+
+        assert!(MimeType::parse("image/svg+xml").unwrap().is_xml());
+        assert!(MimeType::parse("application/ld+json").unwrap().is_json());
+        assert!(MimeType::parse("text/javascript").unwrap().is_javascript());
+        assert!(is_nosniff("NoSniff, other"));
+        assert!(!is_nosniff("other, nosniff"));
+    }
+
+    #[test]
+    fn sniff_unknown_types() {
+        assert_eq!(essence(None, false, b"  <!doctype html><p>"), "text/html");
+        assert_eq!(essence(None, false, b"<P>hi"), "text/html");
+        assert_eq!(essence(None, false, b"<!-- c -->"), "text/html");
+        assert_eq!(essence(None, false, b"<pre>x"), "text/plain");
+        assert_eq!(essence(None, false, b"<?xml version=\"1.0\"?>"), "text/xml");
+        assert_eq!(essence(Some("*/*"), false, b"%PDF-1.4"), "application/pdf");
+        assert_eq!(essence(None, false, b"\x89PNG\r\n\x1a\n...."), "image/png");
+        assert_eq!(essence(None, false, b"GIF89a"), "image/gif");
+        assert_eq!(essence(None, false, b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(essence(None, false, b"ID3\x03"), "audio/mpeg");
+        assert_eq!(
+            essence(None, false, b"\0\0\0\x18ftypmp42\0\0\0\0mp41isom"),
+            "video/mp4"
+        );
+        assert_eq!(
+            essence(None, false, b"\x1f\x8b\x08\0"),
+            "application/x-gzip"
+        );
+        assert_eq!(essence(None, false, b"plain text"), "text/plain");
+        assert_eq!(
+            essence(None, false, b"\0\x01binary"),
+            "application/octet-stream"
+        );
+
+        // Scriptable types are not sniffed when sniffing is disabled
+        assert_eq!(essence(None, true, b"<html>"), "text/plain");
+    }
+
+    #[test]
+    fn sniff_supplied_types() {
+        assert_eq!(
+            essence(Some("text/html"), false, b"<?xml?><rss>"),
+            "application/rss+xml"
+        );
+        assert_eq!(
+            essence(Some("text/html"), false, b"\xEF\xBB\xBF<!-- x --><feed>"),
+            "application/atom+xml"
+        );
+        assert_eq!(
+            essence(
+                Some("text/html"),
+                false,
+                b"<rdf:RDF xmlns=\"http://purl.org/rss/1.0/\" \
+                  xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">"
+            ),
+            "application/rss+xml"
+        );
+        assert_eq!(essence(Some("text/html"), false, b"<html>"), "text/html");
+        assert_eq!(essence(Some("text/html"), true, b"<rss>"), "text/html");
+
+        // Apache bug: text/plain is checked for binary data
+        assert_eq!(
+            essence(Some("text/plain"), false, b"\x89PNG\r\n\x1a\n"),
+            "image/png"
+        );
+        assert_eq!(essence(Some("text/plain"), false, b"<html>"), "text/plain");
+        assert_eq!(
+            essence(Some("text/plain; charset=utf-8"), false, b"\0\x01"),
+            "text/plain"
+        );
+
+        assert_eq!(essence(Some("image/png"), false, b"GIF87a"), "image/gif");
+        assert_eq!(essence(Some("image/png"), false, b"????"), "image/png");
+        assert_eq!(
+            essence(Some("application/xml"), false, b"<html>"),
+            "application/xml"
+        );
+        assert_eq!(
+            essence(Some("application/json"), false, b"<html>"),
+            "application/json"
+        );
+        assert_eq!(essence(Some("invalid"), false, b"<html>"), "text/html");
+    }
+}
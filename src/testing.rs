@@ -1,4 +1,34 @@
+pub mod css;
 pub mod tokenizer;
 pub mod tree_construction;
+pub mod url;
+
+use crate::html5_parser::input_stream::{Encoding, InputStream};
+use crate::html5_parser::parser::document::{Document, DocumentHandle};
+use crate::html5_parser::parser::Html5Parser;
+use crate::url::Url;
 
 pub const FIXTURE_ROOT: &str = "./tests/data/html5lib-tests";
+
+/// Parses the given UTF-8 html into a new document
+pub fn parse_html(html: &str) -> DocumentHandle {
+    parse_into(html, Document::shared())
+}
+
+/// Parses the given UTF-8 html into a new document with the given document url
+pub fn parse_html_with_url(html: &str, url: &str) -> DocumentHandle {
+    let mut document = Document::shared();
+    document
+        .get_mut()
+        .set_url(Url::parse(url).expect("document url"));
+    parse_into(html, document)
+}
+
+fn parse_into(html: &str, document: DocumentHandle) -> DocumentHandle {
+    let mut stream = InputStream::new();
+    stream.read_from_str(html, Some(Encoding::UTF8));
+
+    let mut parser = Html5Parser::new(&mut stream);
+    parser.parse(Document::clone(&document)).expect("parse");
+    document
+}
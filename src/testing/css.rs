@@ -0,0 +1,223 @@
+//! Runs fixtures in the format of [css-parsing-tests](https://github.com/SimonSapin/css-parsing-tests).
+//!
+//! A fixture file is a JSON array of alternating inputs and expected results. Component values
+//! are written as JSON: whitespace as `" "`, simple tokens and delims as their text (`":"`,
+//! `"~="`, `"."`) and other tokens as arrays like `["ident", "foo"]` or
+//! `["number", "1.5", 1.5, "number"]`. Blocks and functions contain their component values, like
+//! `["{}", ...]` and `["function", "rgb", ...]`.
+use crate::css::color::Color;
+use crate::css::tokenizer::Tokenizer;
+use crate::css::tokens::{NumericValue, Token, TokenType};
+use crate::types::Result;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+
+/// Fixtures from the css-parsing-tests repository are in `css-parsing-tests`, fixtures in the same
+/// format that are written for this repository are in `css`
+pub const CSS_FIXTURE_ROOT: &str = "./tests/data";
+
+/// A single input with its expected result
+#[derive(Debug, Clone, PartialEq)]
+pub struct Test {
+    pub input: String,
+    pub expected: Value,
+}
+
+impl Test {
+    /// Tokenizes the input as a list of component values and compares them with the expected
+    /// result
+    pub fn assert_valid(&self) {
+        let actual = Value::Array(component_value_list(&self.input));
+        assert!(
+            json_matches(&actual, &self.expected),
+            "input: {:?}\nexpected: {}\nactual:   {}",
+            self.input,
+            self.expected,
+            actual
+        );
+    }
+
+    /// Tokenizes the input as a single component value and compares it with the expected
+    /// result, which is `["error", "empty"]` or `["error", "extra-input"]` when the input does
+    /// not contain exactly one component value
+    pub fn assert_valid_one_component_value(&self) {
+        let actual = one_component_value(&self.input);
+        assert!(
+            json_matches(&actual, &self.expected),
+            "input: {:?}\nexpected: {}\nactual:   {}",
+            self.input,
+            self.expected,
+            actual
+        );
+    }
+
+    /// Parses the input as a color and compares it with the expected result: `null` for invalid
+    /// colors, `[red, green, blue, alpha]` from 0 to 255 for sRGB colors (the upstream format)
+    /// or the serialization of the color
+    pub fn assert_valid_color(&self) {
+        let color = Color::parse(&self.input);
+        let actual = match (&color, &self.expected) {
+            (None, _) => Value::Null,
+            (Some(color), Value::Array(_)) => match color.to_rgba() {
+                Some(rgba) => json!([
+                    rgba.red.round(),
+                    rgba.green.round(),
+                    rgba.blue.round(),
+                    (rgba.alpha * 255.0).round()
+                ]),
+                None => json!(color.to_string()),
+            },
+            (Some(color), _) => json!(color.to_string()),
+        };
+        assert!(
+            json_matches(&actual, &self.expected),
+            "input: {:?}\nexpected: {}\nactual:   {}",
+            self.input,
+            self.expected,
+            actual
+        );
+    }
+}
+
+pub fn fixture_from_filename(filename: &str) -> Result<Vec<Test>> {
+    let path = PathBuf::from(CSS_FIXTURE_ROOT).join(filename);
+    let contents = fs::read_to_string(path)?;
+    let values: Vec<Value> = serde_json::from_str(&contents)?;
+
+    Ok(values
+        .chunks(2)
+        .map(|pair| Test {
+            input: pair[0].as_str().unwrap_or_default().to_string(),
+            expected: pair.get(1).cloned().unwrap_or(Value::Null),
+        })
+        .collect())
+}
+
+/// Returns the component values of the input in the JSON format of the fixtures
+pub fn component_value_list(input: &str) -> Vec<Value> {
+    let mut tokens = Tokenizer::tokenize(input).into_iter();
+    consume_values(&mut tokens, None)
+}
+
+/// [Parses a component value](https://www.w3.org/TR/css-syntax-3/#parse-component-value): the
+/// input may only have whitespace around it
+pub fn one_component_value(input: &str) -> Value {
+    let whitespace = json!(" ");
+    let values = component_value_list(input);
+    let mut values = values.iter().filter(|value| **value != whitespace);
+    match (values.next(), values.next()) {
+        (None, _) => json!(["error", "empty"]),
+        (Some(value), None) => value.clone(),
+        (Some(_), Some(_)) => json!(["error", "extra-input"]),
+    }
+}
+
+/// Consumes component values until the closing token (or the end of the input)
+fn consume_values(
+    tokens: &mut impl Iterator<Item = Token>,
+    closing: Option<TokenType>,
+) -> Vec<Value> {
+    let mut values = vec![];
+    while let Some(token) = tokens.next() {
+        if Some(&token.token_type) == closing.as_ref() {
+            break;
+        }
+        let value = match token.token_type {
+            TokenType::LCurly => block("{}", TokenType::RCurly, tokens),
+            TokenType::LBracket => block("[]", TokenType::RBracket, tokens),
+            TokenType::LParen => block("()", TokenType::RParen, tokens),
+            TokenType::Function(name) => {
+                let mut function = vec![json!("function"), json!(name)];
+                function.extend(consume_values(tokens, Some(TokenType::RParen)));
+                Value::Array(function)
+            }
+            token_type => token_to_json(token_type),
+        };
+        values.push(value);
+    }
+    values
+}
+
+fn block(name: &str, closing: TokenType, tokens: &mut impl Iterator<Item = Token>) -> Value {
+    let mut block = vec![json!(name)];
+    block.extend(consume_values(tokens, Some(closing)));
+    Value::Array(block)
+}
+
+fn numeric(kind: &str, number: NumericValue) -> Vec<Value> {
+    vec![
+        json!(kind),
+        json!(number.repr),
+        match number.int_value {
+            Some(value) => json!(value),
+            None => json!(number.value),
+        },
+        json!(if number.is_integer() {
+            "integer"
+        } else {
+            "number"
+        }),
+    ]
+}
+
+fn token_to_json(token_type: TokenType) -> Value {
+    match token_type {
+        TokenType::Ident(value) => json!(["ident", value]),
+        TokenType::AtKeyword(value) => json!(["at-keyword", value]),
+        TokenType::Hash { value, is_id } => {
+            json!(["hash", value, if is_id { "id" } else { "unrestricted" }])
+        }
+        TokenType::String(value) => json!(["string", value]),
+        TokenType::BadString => json!(["error", "bad-string"]),
+        TokenType::Url(value) => json!(["url", value]),
+        TokenType::BadUrl => json!(["error", "bad-url"]),
+        TokenType::Delim(c) => json!(c.to_string()),
+        TokenType::Number(number) => Value::Array(numeric("number", number)),
+        TokenType::Percentage(number) => Value::Array(numeric("percentage", number)),
+        TokenType::Dimension(number, unit) => {
+            let mut dimension = numeric("dimension", number);
+            dimension.push(json!(unit));
+            Value::Array(dimension)
+        }
+        TokenType::UnicodeRange(start, end) => json!(["unicode-range", start, end]),
+        TokenType::IncludeMatch => json!("~="),
+        TokenType::DashMatch => json!("|="),
+        TokenType::PrefixMatch => json!("^="),
+        TokenType::SuffixMatch => json!("$="),
+        TokenType::SubstringMatch => json!("*="),
+        TokenType::Column => json!("||"),
+        TokenType::Whitespace => json!(" "),
+        TokenType::CDO => json!("<!--"),
+        TokenType::CDC => json!("-->"),
+        TokenType::Colon => json!(":"),
+        TokenType::Semicolon => json!(";"),
+        TokenType::Comma => json!(","),
+        // Closing tokens without an opening token
+        TokenType::RBracket => json!(["error", "]"]),
+        TokenType::RParen => json!(["error", ")"]),
+        TokenType::RCurly => json!(["error", "}"]),
+        token_type => json!(format!("{:?}", token_type)),
+    }
+}
+
+/// Compares JSON values. Numbers are compared with the precision of the tokenizer (f32).
+fn json_matches(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Number(actual), Value::Number(expected)) => {
+            let (actual, expected) = (
+                actual.as_f64().unwrap_or(f64::NAN),
+                expected.as_f64().unwrap_or(f64::NAN),
+            );
+            (actual - expected).abs() <= 1e-6 * expected.abs().max(1.0)
+        }
+        (Value::Array(actual), Value::Array(expected)) => {
+            actual.len() == expected.len()
+                && actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(actual, expected)| json_matches(actual, expected))
+        }
+        _ => actual == expected,
+    }
+}
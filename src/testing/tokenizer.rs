@@ -183,7 +183,7 @@ impl Test {
 
         let double_escaped = self.double_escaped.unwrap_or(false);
 
-        let tp = expected.get(0).unwrap();
+        let tp = expected.first().unwrap();
 
         let expected_token_type = match tp.as_str().unwrap() {
             "DOCTYPE" => TokenType::DocTypeToken,
@@ -1,4 +1,9 @@
 use super::FIXTURE_ROOT;
+use crate::html5_parser::node::{
+    NodeData, NodeId, HTML_NAMESPACE, MATHML_NAMESPACE, SVG_NAMESPACE,
+};
+use crate::html5_parser::parser::attr_replacements::XML_ADJUSTMENTS;
+use crate::html5_parser::parser::document::{Document, DocumentHandle};
 use crate::types::Result;
 use regex::Regex;
 use std::{
@@ -71,6 +76,7 @@ pub fn fixture_from_path(path: &PathBuf) -> Result<FixtureFile> {
         document_fragment: vec![],
     };
     let mut section: Option<&str> = None;
+    let error_re = Regex::new(r"\((?P<line>\d+),(?P<col>\d+)\): (?P<code>.+)").unwrap();
 
     for (line_num, line) in reader.lines().enumerate() {
         let line = line?;
@@ -102,8 +108,7 @@ pub fn fixture_from_path(path: &PathBuf) -> Result<FixtureFile> {
             match sec {
                 "data" => current_test.data.push_str(&line),
                 "errors" => {
-                    let re = Regex::new(r"\((?P<line>\d+),(?P<col>\d+)\): (?P<code>.+)").unwrap();
-                    if let Some(caps) = re.captures(&line) {
+                    if let Some(caps) = error_re.captures(&line) {
                         let line = caps.name("line").unwrap().as_str().parse::<i64>().unwrap();
                         let col = caps.name("col").unwrap().as_str().parse::<i64>().unwrap();
                         let code = caps.name("code").unwrap().as_str().to_string();
@@ -133,6 +138,67 @@ pub fn fixture_from_path(path: &PathBuf) -> Result<FixtureFile> {
     })
 }
 
+/// Returns the tree of the document in the format of the `#document` section of the tests, with
+/// one line per node
+pub fn document_tree(document: &DocumentHandle) -> Vec<String> {
+    let mut lines = vec![];
+    let document = document.get();
+    for child in &document.get_root().children {
+        add_tree_lines(&document, *child, 1, &mut lines);
+    }
+    lines
+}
+
+fn add_tree_lines(document: &Document, node_id: NodeId, depth: usize, lines: &mut Vec<String>) {
+    let Some(node) = document.get_node_by_id(node_id) else {
+        return;
+    };
+    let indent = format!("|{}", " ".repeat(depth * 2 - 1));
+    let mut child_depth = depth + 1;
+
+    match &node.data {
+        NodeData::Text(text) => lines.push(format!("{}\"{}\"", indent, text.value)),
+        NodeData::Comment(comment) => lines.push(format!("{}<!-- {} -->", indent, comment.value)),
+        NodeData::Element(element) => {
+            let is_foreign = node.namespace.as_deref() != Some(HTML_NAMESPACE);
+            let prefix = match node.namespace.as_deref() {
+                Some(SVG_NAMESPACE) => "svg ",
+                Some(MATHML_NAMESPACE) => "math ",
+                _ => "",
+            };
+            lines.push(format!("{}<{}{}>", indent, prefix, node.name));
+
+            // Attributes in the XLink, XML and XMLNS namespaces show their prefix and local name
+            let mut attributes: Vec<_> = element
+                .attributes
+                .iter()
+                .map(|(name, value)| match XML_ADJUSTMENTS.get(name.as_str()) {
+                    Some((prefix, local, _)) if is_foreign => {
+                        let local = if local.is_empty() { prefix } else { local };
+                        (format!("{} {}", prefix, local), value)
+                    }
+                    _ => (name.clone(), value),
+                })
+                .collect();
+            attributes.sort();
+            for (name, value) in attributes {
+                lines.push(format!("{}  {}=\"{}\"", indent, name, value));
+            }
+
+            // The parser keeps the contents of a template as its children
+            if !is_foreign && node.name == "template" {
+                lines.push(format!("{}  content", indent));
+                child_depth += 1;
+            }
+        }
+        NodeData::Document(_) | NodeData::ShadowRoot(_) => {}
+    }
+
+    for child in &node.children {
+        add_tree_lines(document, *child, child_depth, lines);
+    }
+}
+
 fn use_fixture(filenames: &[&str], path: &Path) -> bool {
     if !path.is_file() || path.extension().expect("file ending") != "dat" {
         return false;
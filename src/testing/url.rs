@@ -0,0 +1,175 @@
+//! Runs the URL tests of the [web platform tests](https://github.com/web-platform-tests/wpt/tree/master/url)
+//! and the [UTS #46 conformance tests](https://www.unicode.org/Public/idna/) (`IdnaTestV2.txt`).
+use crate::types::Result;
+use crate::url::idna::domain_to_ascii;
+use crate::url::Url;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+pub const URL_FIXTURE_ROOT: &str = "./tests/data/wpt";
+
+/// UTS #46 status codes for checks that the URL standard turns off: CheckHyphens (V2, V3) and
+/// VerifyDnsLength (A4_1, A4_2)
+const IGNORED_IDNA_STATUSES: [&str; 4] = ["V2", "V3", "A4_1", "A4_2"];
+
+/// Lines of `IdnaTestV2.txt` that expect U+18C4E KHITAN SMALL SCRIPT CHARACTER-18C4E to be
+/// invalid, although the mapping table of the same Unicode version lists it as valid
+const EXPECTED_IDNA_FAILURES: [usize; 2] = [3075, 3076];
+
+/// A single test from `urltestdata.json`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Test {
+    pub input: String,
+    pub base: Option<String>,
+    /// The expected serialization, or None when parsing must fail
+    pub href: Option<String>,
+    pub hostname: Option<String>,
+}
+
+impl Test {
+    /// Parses the input against its base and compares the result with the expectation
+    pub fn assert_valid(&self) {
+        let base = self.base.as_ref().and_then(|base| Url::parse(base).ok());
+        let actual = Url::parse_with_base(&self.input, base.as_ref()).ok();
+
+        assert_eq!(
+            actual.as_ref().map(Url::href),
+            self.href,
+            "input: {:?}, base: {:?}",
+            self.input,
+            self.base
+        );
+        if let (Some(url), Some(hostname)) = (&actual, &self.hostname) {
+            assert_eq!(&url.hostname(), hostname, "input: {:?}", self.input);
+        }
+    }
+}
+
+pub fn fixture_from_filename(filename: &str) -> Result<Vec<Test>> {
+    let contents = fs::read_to_string(PathBuf::from(URL_FIXTURE_ROOT).join(filename))?;
+    let values: Vec<Value> = serde_json::from_str(&contents)?;
+
+    // Strings in between the tests are comments
+    Ok(values
+        .iter()
+        .filter_map(Value::as_object)
+        .map(|test| {
+            let string = |key: &str| test.get(key).and_then(Value::as_str).map(str::to_string);
+            let failure = test.get("failure").and_then(Value::as_bool) == Some(true);
+            Test {
+                input: string("input").unwrap_or_default(),
+                base: string("base"),
+                href: string("href").filter(|_| !failure),
+                hostname: string("hostname").filter(|_| !failure),
+            }
+        })
+        .collect())
+}
+
+/// A nontransitional ToASCII test from `IdnaTestV2.txt`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdnaTest {
+    pub line: usize,
+    pub source: String,
+    /// The ASCII domain, or None when conversion must fail
+    pub expected: Option<String>,
+}
+
+impl IdnaTest {
+    pub fn assert_valid(&self) {
+        assert_eq!(
+            domain_to_ascii(&self.source),
+            self.expected,
+            "line {}: {:?}",
+            self.line,
+            self.source
+        );
+    }
+}
+
+/// Reads the ToASCII tests that apply to URLs. Tests with an empty result are skipped, as the
+/// URL parser handles empty hosts before converting them.
+pub fn idna_tests_from_filename(filename: &str) -> Result<Vec<IdnaTest>> {
+    let contents = fs::read_to_string(PathBuf::from(URL_FIXTURE_ROOT).join(filename))?;
+
+    let mut tests = vec![];
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // Empty columns inherit the value of the column to their left
+        let columns: Vec<String> = line.split(';').map(|c| unescape(c.trim())).collect();
+        let to_unicode = non_empty(&columns[1]).unwrap_or(&columns[0]);
+        let to_unicode_status = &columns[2];
+        let to_ascii = non_empty(&columns[3]).unwrap_or(to_unicode);
+        let to_ascii_status = non_empty(&columns[4]).unwrap_or(to_unicode_status);
+
+        let failed = to_ascii_status
+            .trim_matches(|c| c == '[' || c == ']')
+            .split(',')
+            .map(str::trim)
+            .any(|status| !status.is_empty() && !IGNORED_IDNA_STATUSES.contains(&status));
+        if to_ascii.is_empty() && !failed
+            || disallowed_by_std3_rules(&columns[0])
+            || EXPECTED_IDNA_FAILURES.contains(&(idx + 1))
+        {
+            continue;
+        }
+
+        tests.push(IdnaTest {
+            line: idx + 1,
+            source: columns[0].clone(),
+            expected: (!failed).then(|| to_ascii.to_string()),
+        });
+    }
+    Ok(tests)
+}
+
+/// This version of the file does not tell apart code points that are only disallowed with
+/// UseSTD3ASCIIRules, which the URL standard turns off, and marks them with P1 as well
+fn disallowed_by_std3_rules(source: &str) -> bool {
+    let config = idna::Config::default();
+    config.to_ascii(source).is_ok() && config.use_std3_ascii_rules(true).to_ascii(source).is_err()
+}
+
+fn non_empty(column: &str) -> Option<&str> {
+    (!column.is_empty()).then_some(column)
+}
+
+/// Replaces `\uXXXX` and `\x{XXXX}` escapes
+fn unescape(input: &str) -> String {
+    let mut output = String::new();
+    let mut rest = input;
+    while let Some(idx) = rest.find('\\') {
+        output.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        let (hex, len) = if let Some(hex) = rest.strip_prefix("\\u") {
+            (hex.get(..4), 6)
+        } else if let Some(hex) = rest.strip_prefix("\\x{") {
+            let end = hex.find('}').unwrap_or_default();
+            (hex.get(..end), end + 4)
+        } else {
+            (None, 1)
+        };
+
+        match hex
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32)
+        {
+            Some(c) => {
+                output.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                output.push('\\');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
@@ -17,6 +17,18 @@ pub enum Error {
 
     #[error("json parsing error: {0}")]
     JsonSerde(#[from] serde_json::Error),
+
+    #[error("dom patch error: {0}")]
+    Patch(String),
+
+    #[error("url error: {0}")]
+    Url(#[from] crate::url::UrlError),
+
+    #[error("load error: {0}")]
+    Load(#[from] crate::loader::LoadError),
+
+    #[error("css value error: {0}")]
+    CssValue(#[from] crate::css::values::ValueError),
 }
 
 /// Result that can be returned which holds either T or an Error
@@ -0,0 +1,484 @@
+//! Host parsing and serialization (<https://url.spec.whatwg.org/#hosts-(domains-and-ip-addresses)>)
+use crate::url::idna::domain_to_ascii;
+use crate::url::percent_encoding::{percent_decode, utf8_percent_encode, EncodeSet};
+use crate::url::UrlError;
+use lazy_static::lazy_static;
+use publicsuffix::{List, Psl};
+use std::fmt;
+
+lazy_static! {
+    /// The [public suffix list](https://publicsuffix.org/)
+    static ref PUBLIC_SUFFIX_LIST: List = include_str!("public_suffix_list.dat")
+        .parse()
+        .expect("valid public suffix list");
+}
+
+/// Host of a URL
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Host {
+    /// ASCII domain name (already converted with IDNA)
+    Domain(String),
+    /// IPv4 address
+    Ipv4(u32),
+    /// IPv6 address, as eight 16-bit pieces
+    Ipv6([u16; 8]),
+    /// Host of a non-special URL, which is kept (percent-encoded) as-is
+    Opaque(String),
+    /// Empty host (e.g. `file:///`)
+    Empty,
+}
+
+impl Host {
+    /// Parses the input as a host. Non-special URLs have opaque hosts.
+    pub fn parse(input: &str, is_opaque: bool) -> Result<Host, UrlError> {
+        if let Some(address) = input.strip_prefix('[') {
+            let Some(address) = address.strip_suffix(']') else {
+                return Err(UrlError::InvalidIpv6Address);
+            };
+            return parse_ipv6(address).map(Host::Ipv6);
+        }
+
+        if is_opaque {
+            return parse_opaque_host(input);
+        }
+
+        if input.is_empty() {
+            return Err(UrlError::EmptyHost);
+        }
+
+        let domain = String::from_utf8_lossy(&percent_decode(input.as_bytes())).into_owned();
+        let ascii_domain = domain_to_ascii(&domain).ok_or(UrlError::InvalidDomain)?;
+
+        if ends_in_a_number(&ascii_domain) {
+            return parse_ipv4(&ascii_domain).map(Host::Ipv4);
+        }
+
+        Ok(Host::Domain(ascii_domain))
+    }
+
+    /// Returns the [public suffix](https://url.spec.whatwg.org/#host-public-suffix) of a domain,
+    /// like `co.uk` for `www.example.co.uk`. Unlisted top-level domains are public suffixes too.
+    pub fn public_suffix(&self) -> Option<String> {
+        let Host::Domain(domain) = self else {
+            return None;
+        };
+        let (domain, trailing_dot) = match domain.strip_suffix('.') {
+            Some(domain) => (domain, "."),
+            None => (domain.as_str(), ""),
+        };
+        let suffix = PUBLIC_SUFFIX_LIST.suffix(domain.as_bytes())?;
+        Some(format!(
+            "{}{}",
+            String::from_utf8_lossy(suffix.as_bytes()),
+            trailing_dot
+        ))
+    }
+
+    /// Returns the [registrable domain](https://url.spec.whatwg.org/#host-registrable-domain):
+    /// the public suffix plus the label before it, like `example.co.uk` for `www.example.co.uk`.
+    /// Returns None for public suffixes and hosts that are not domains.
+    pub fn registrable_domain(&self) -> Option<String> {
+        let Host::Domain(domain) = self else {
+            return None;
+        };
+        let (domain, trailing_dot) = match domain.strip_suffix('.') {
+            Some(domain) => (domain, "."),
+            None => (domain.as_str(), ""),
+        };
+        let registrable = PUBLIC_SUFFIX_LIST.domain(domain.as_bytes())?;
+        Some(format!(
+            "{}{}",
+            String::from_utf8_lossy(registrable.as_bytes()),
+            trailing_dot
+        ))
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Domain(domain) | Host::Opaque(domain) => write!(f, "{}", domain),
+            Host::Ipv4(address) => write!(
+                f,
+                "{}.{}.{}.{}",
+                address >> 24,
+                (address >> 16) & 0xFF,
+                (address >> 8) & 0xFF,
+                address & 0xFF
+            ),
+            Host::Ipv6(pieces) => write!(f, "[{}]", serialize_ipv6(pieces)),
+            Host::Empty => Ok(()),
+        }
+    }
+}
+
+/// Returns true for code points that are not allowed in a host
+pub fn is_forbidden_host_code_point(c: char) -> bool {
+    matches!(
+        c,
+        '\0' | '\t'
+            | '\n'
+            | '\r'
+            | ' '
+            | '#'
+            | '/'
+            | ':'
+            | '<'
+            | '>'
+            | '?'
+            | '@'
+            | '['
+            | '\\'
+            | ']'
+            | '^'
+            | '|'
+    )
+}
+
+fn parse_opaque_host(input: &str) -> Result<Host, UrlError> {
+    if input.chars().any(is_forbidden_host_code_point) {
+        return Err(UrlError::InvalidDomain);
+    }
+    Ok(Host::Opaque(utf8_percent_encode(
+        input,
+        EncodeSet::C0Control,
+    )))
+}
+
+/// Returns true when the last label of the domain is a number, in which case the domain must be
+/// parsed as an IPv4 address
+fn ends_in_a_number(domain: &str) -> bool {
+    let mut parts: Vec<&str> = domain.split('.').collect();
+    if parts.last() == Some(&"") {
+        if parts.len() == 1 {
+            return false;
+        }
+        parts.pop();
+    }
+
+    let last = parts.last().copied().unwrap_or_default();
+    if !last.is_empty() && last.chars().all(|c| c.is_ascii_digit()) {
+        return true;
+    }
+    parse_ipv4_number(last).is_some()
+}
+
+/// Parses a single part of an IPv4 address, which can be decimal, octal (leading 0) or
+/// hexadecimal (leading 0x)
+fn parse_ipv4_number(input: &str) -> Option<u64> {
+    if input.is_empty() {
+        return None;
+    }
+
+    let (digits, radix) = if let Some(hex) = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        (hex, 16)
+    } else if input.len() >= 2 && input.starts_with('0') {
+        (&input[1..], 8)
+    } else {
+        (input, 10)
+    };
+
+    if digits.is_empty() {
+        return Some(0);
+    }
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+
+    // Numbers that do not fit are too large for any part of an address anyway
+    Some(u64::from_str_radix(digits, radix).unwrap_or(u64::MAX))
+}
+
+fn parse_ipv4(input: &str) -> Result<u32, UrlError> {
+    let mut parts: Vec<&str> = input.split('.').collect();
+    if parts.last() == Some(&"") && parts.len() > 1 {
+        parts.pop();
+    }
+    if parts.len() > 4 {
+        return Err(UrlError::InvalidIpv4Address);
+    }
+
+    let mut numbers = vec![];
+    for part in parts {
+        numbers.push(parse_ipv4_number(part).ok_or(UrlError::InvalidIpv4Address)?);
+    }
+
+    let last = numbers.pop().unwrap_or_default();
+    if numbers.iter().any(|n| *n > 255) || last >= 256u64.pow(4 - numbers.len() as u32) {
+        return Err(UrlError::InvalidIpv4Address);
+    }
+
+    let mut address = last;
+    for (idx, number) in numbers.iter().enumerate() {
+        address += number * 256u64.pow(3 - idx as u32);
+    }
+    Ok(address as u32)
+}
+
+fn parse_ipv6(input: &str) -> Result<[u16; 8], UrlError> {
+    let input: Vec<char> = input.chars().collect();
+    let mut address = [0u16; 8];
+    let mut piece_index = 0;
+    let mut compress: Option<usize> = None;
+    let mut pointer = 0;
+    let c = |pointer: usize| input.get(pointer).copied();
+
+    if c(pointer) == Some(':') {
+        if c(pointer + 1) != Some(':') {
+            return Err(UrlError::InvalidIpv6Address);
+        }
+        pointer += 2;
+        piece_index += 1;
+        compress = Some(piece_index);
+    }
+
+    while c(pointer).is_some() {
+        if piece_index == 8 {
+            return Err(UrlError::InvalidIpv6Address);
+        }
+        if c(pointer) == Some(':') {
+            if compress.is_some() {
+                return Err(UrlError::InvalidIpv6Address);
+            }
+            pointer += 1;
+            piece_index += 1;
+            compress = Some(piece_index);
+            continue;
+        }
+
+        let mut value: u16 = 0;
+        let mut length = 0;
+        while length < 4 {
+            let Some(digit) = c(pointer).and_then(|c| c.to_digit(16)) else {
+                break;
+            };
+            value = value * 0x10 + digit as u16;
+            pointer += 1;
+            length += 1;
+        }
+
+        if c(pointer) == Some('.') {
+            // IPv4 address in the last two pieces
+            if length == 0 {
+                return Err(UrlError::InvalidIpv6Address);
+            }
+            pointer -= length;
+            if piece_index > 6 {
+                return Err(UrlError::InvalidIpv6Address);
+            }
+
+            let mut numbers_seen = 0;
+            while c(pointer).is_some() {
+                let mut ipv4_piece: Option<u16> = None;
+                if numbers_seen > 0 {
+                    if c(pointer) == Some('.') && numbers_seen < 4 {
+                        pointer += 1;
+                    } else {
+                        return Err(UrlError::InvalidIpv6Address);
+                    }
+                }
+                if !c(pointer).is_some_and(|c| c.is_ascii_digit()) {
+                    return Err(UrlError::InvalidIpv6Address);
+                }
+                while let Some(number) = c(pointer).and_then(|c| c.to_digit(10)) {
+                    ipv4_piece = match ipv4_piece {
+                        None => Some(number as u16),
+                        Some(0) => return Err(UrlError::InvalidIpv6Address),
+                        Some(piece) => Some(piece * 10 + number as u16),
+                    };
+                    if ipv4_piece.unwrap_or_default() > 255 {
+                        return Err(UrlError::InvalidIpv6Address);
+                    }
+                    pointer += 1;
+                }
+                address[piece_index] =
+                    address[piece_index] * 0x100 + ipv4_piece.unwrap_or_default();
+                numbers_seen += 1;
+                if numbers_seen == 2 || numbers_seen == 4 {
+                    piece_index += 1;
+                }
+            }
+            if numbers_seen != 4 {
+                return Err(UrlError::InvalidIpv6Address);
+            }
+            break;
+        } else if c(pointer) == Some(':') {
+            pointer += 1;
+            if c(pointer).is_none() {
+                return Err(UrlError::InvalidIpv6Address);
+            }
+        } else if c(pointer).is_some() {
+            return Err(UrlError::InvalidIpv6Address);
+        }
+
+        address[piece_index] = value;
+        piece_index += 1;
+    }
+
+    if let Some(compress) = compress {
+        let mut swaps = piece_index - compress;
+        piece_index = 7;
+        while piece_index != 0 && swaps > 0 {
+            address.swap(piece_index, compress + swaps - 1);
+            piece_index -= 1;
+            swaps -= 1;
+        }
+    } else if piece_index != 8 {
+        return Err(UrlError::InvalidIpv6Address);
+    }
+
+    Ok(address)
+}
+
+fn serialize_ipv6(pieces: &[u16; 8]) -> String {
+    // Find the first longest sequence of at least two zero pieces
+    let mut compress: Option<(usize, usize)> = None;
+    let mut idx = 0;
+    while idx < 8 {
+        if pieces[idx] != 0 {
+            idx += 1;
+            continue;
+        }
+        let start = idx;
+        while idx < 8 && pieces[idx] == 0 {
+            idx += 1;
+        }
+        let length = idx - start;
+        if length > 1 && compress.map_or(true, |(_, l)| length > l) {
+            compress = Some((start, length));
+        }
+    }
+
+    let mut output = String::new();
+    let mut idx = 0;
+    while idx < 8 {
+        if let Some((start, length)) = compress {
+            if idx == start {
+                output.push_str(if idx == 0 { "::" } else { ":" });
+                idx += length;
+                continue;
+            }
+        }
+        output.push_str(&format!("{:x}", pieces[idx]));
+        if idx != 7 {
+            output.push(':');
+        }
+        idx += 1;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_suffixes() {
+        let domain = |input: &str| Host::parse(input, false).unwrap();
+
+        assert_eq!(domain("www.example.com").public_suffix().unwrap(), "com");
+        assert_eq!(
+            domain("www.example.com").registrable_domain().unwrap(),
+            "example.com"
+        );
+        assert_eq!(domain("a.example.co.uk").public_suffix().unwrap(), "co.uk");
+        assert_eq!(
+            domain("a.example.co.uk").registrable_domain().unwrap(),
+            "example.co.uk"
+        );
+        assert_eq!(domain("example.com.").public_suffix().unwrap(), "com.");
+        assert_eq!(
+            domain("a.example.com.").registrable_domain().unwrap(),
+            "example.com."
+        );
+        // Wildcard and exception rules
+        assert_eq!(domain("a.b.ck").registrable_domain().unwrap(), "a.b.ck");
+        assert_eq!(domain("www.ck").registrable_domain().unwrap(), "www.ck");
+        // Unlisted top-level domains
+        assert_eq!(domain("localhost").public_suffix().unwrap(), "localhost");
+        assert_eq!(domain("a.b.example").public_suffix().unwrap(), "example");
+
+        assert!(domain("com").registrable_domain().is_none());
+        assert!(domain("co.uk").registrable_domain().is_none());
+        assert!(domain("127.0.0.1").public_suffix().is_none());
+        assert!(domain("127.0.0.1").registrable_domain().is_none());
+    }
+
+    #[test]
+    fn ipv4() {
+        let cases = [
+            ("127.0.0.1", "127.0.0.1"),
+            ("0x7f.1", "127.0.0.1"),
+            ("0177.0.0.1", "127.0.0.1"),
+            ("2130706433", "127.0.0.1"),
+            ("192.168.257", "192.168.1.1"),
+            ("1.2.3.4.", "1.2.3.4"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Host::parse(input, false).unwrap().to_string(), expected);
+        }
+
+        assert!(Host::parse("1.2.3.4.5", false).is_err());
+        assert!(Host::parse("256.0.0.1", false).is_err());
+        assert!(Host::parse("192.168.0.257", false).is_err());
+        assert!(Host::parse("4294967296", false).is_err());
+        assert!(Host::parse("1.09", false).is_err());
+        // Not ending in a number, so this is a domain
+        assert_eq!(
+            Host::parse("1.2.3.example", false).unwrap(),
+            Host::Domain("1.2.3.example".into())
+        );
+    }
+
+    #[test]
+    fn ipv6() {
+        let cases = [
+            ("[::1]", "[::1]"),
+            ("[0:0:0:0:0:0:0:1]", "[::1]"),
+            ("[2001:DB8::0:0:1]", "[2001:db8::1]"),
+            ("[1:0:0:2:0:0:0:3]", "[1:0:0:2::3]"),
+            ("[1:2:3:4:5:6:7:8]", "[1:2:3:4:5:6:7:8]"),
+            ("[::ffff:192.168.0.1]", "[::ffff:c0a8:1]"),
+            ("[::]", "[::]"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Host::parse(input, false).unwrap().to_string(), expected);
+        }
+
+        for invalid in [
+            "[::1",
+            "[1:2:3:4:5:6:7:8:9]",
+            "[1::2::3]",
+            "[:1]",
+            "[1:]",
+            "[::1.2.3]",
+            "[::1.2.3.04]",
+            "[g::]",
+        ] {
+            assert!(Host::parse(invalid, false).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn domains_and_opaque_hosts() {
+        assert_eq!(
+            Host::parse("EXAMPLE.com", false).unwrap(),
+            Host::Domain("example.com".into())
+        );
+        assert_eq!(
+            Host::parse("ex%41mple.com", false).unwrap(),
+            Host::Domain("example.com".into())
+        );
+        assert!(Host::parse("exa mple.com", false).is_err());
+        assert!(Host::parse("", false).is_err());
+
+        assert_eq!(
+            Host::parse("EXAMPLE.é", true).unwrap(),
+            Host::Opaque("EXAMPLE.%C3%A9".into())
+        );
+        assert!(Host::parse("a<b", true).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Conversion of international domain names to ASCII (<https://url.spec.whatwg.org/#idna>)
+//!
+//! The UTS #46 mapping, normalization, validity and bidi rules come from the `idna` crate, with
+//! the options that the URL standard uses. The crate does not check the CheckJoiners rules, so
+//! those are checked here on the decoded labels.
+mod joining_types;
+
+use joining_types::JOINING_TYPES;
+use std::cmp::Ordering;
+use unicode_normalization::char::canonical_combining_class;
+
+/// Prefix of ASCII-compatible encoded labels
+const ACE_PREFIX: &str = "xn--";
+
+/// Canonical combining class of virama characters
+const VIRAMA: u8 = 9;
+
+const ZERO_WIDTH_NON_JOINER: char = '\u{200C}';
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JoiningType {
+    Left,
+    Dual,
+    Right,
+    Transparent,
+}
+
+/// Converts the domain into its ASCII form. Returns None when the domain is not valid.
+pub fn domain_to_ascii(domain: &str) -> Option<String> {
+    let is_ascii = domain.is_ascii()
+        && !domain
+            .split('.')
+            .any(|label| label.len() >= 4 && label[..4].eq_ignore_ascii_case(ACE_PREFIX));
+
+    let result = if is_ascii {
+        domain.to_ascii_lowercase()
+    } else {
+        // UseSTD3ASCIIRules, CheckHyphens, Transitional_Processing and VerifyDnsLength are off
+        let ascii = idna::Config::default().to_ascii(domain).ok()?;
+        let (unicode, _) = idna::Config::default().to_unicode(&ascii);
+        if !unicode.split('.').all(passes_joiner_rules) {
+            return None;
+        }
+        ascii
+    };
+
+    if result.is_empty() || result.chars().any(is_forbidden_domain_code_point) {
+        return None;
+    }
+    Some(result)
+}
+
+/// Converts an ASCII domain back to unicode, decoding all punycode labels. Labels that cannot be
+/// decoded are kept as-is.
+pub fn domain_to_unicode(domain: &str) -> String {
+    idna::Config::default().to_unicode(domain).0
+}
+
+/// Returns true for code points that are not allowed in a domain
+pub fn is_forbidden_domain_code_point(c: char) -> bool {
+    super::host::is_forbidden_host_code_point(c)
+        || c.is_ascii_control()
+        || c == '%'
+        || c == '\u{7F}'
+}
+
+/// The [CONTEXTJ rules](https://www.rfc-editor.org/rfc/rfc5892#appendix-A.1) for zero width
+/// joiners and non-joiners
+fn passes_joiner_rules(label: &str) -> bool {
+    let chars: Vec<char> = label.chars().collect();
+    chars.iter().enumerate().all(|(idx, c)| match *c {
+        ZERO_WIDTH_JOINER => follows_virama(&chars[..idx]),
+        ZERO_WIDTH_NON_JOINER => {
+            follows_virama(&chars[..idx])
+                || (joins(chars[..idx].iter().rev(), |t| {
+                    matches!(t, JoiningType::Left | JoiningType::Dual)
+                }) && joins(chars[idx + 1..].iter(), |t| {
+                    matches!(t, JoiningType::Right | JoiningType::Dual)
+                }))
+        }
+        _ => true,
+    })
+}
+
+fn follows_virama(before: &[char]) -> bool {
+    before
+        .last()
+        .is_some_and(|c| canonical_combining_class(*c) == VIRAMA)
+}
+
+/// Returns true when the first character that is not transparent has the wanted joining type
+fn joins<'a>(
+    mut chars: impl Iterator<Item = &'a char>,
+    wanted: impl Fn(JoiningType) -> bool,
+) -> bool {
+    chars
+        .find(|c| joining_type(**c) != Some(JoiningType::Transparent))
+        .and_then(|c| joining_type(*c))
+        .is_some_and(wanted)
+}
+
+fn joining_type(c: char) -> Option<JoiningType> {
+    JOINING_TYPES
+        .binary_search_by(|(start, end, _)| {
+            if c < *start {
+                Ordering::Greater
+            } else if c > *end {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|idx| JOINING_TYPES[idx].2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_ascii() {
+        assert_eq!(domain_to_ascii("Example.COM").unwrap(), "example.com");
+        assert_eq!(
+            domain_to_ascii("Bücher.example").unwrap(),
+            "xn--bcher-kva.example"
+        );
+        assert_eq!(
+            domain_to_ascii("ｅｘａｍｐｌｅ。com").unwrap(),
+            "example.com"
+        );
+        assert_eq!(
+            domain_to_ascii("xn--bcher-kva.example").unwrap(),
+            "xn--bcher-kva.example"
+        );
+        assert!(domain_to_ascii("xn--a.example").is_none());
+        assert!(domain_to_ascii("exa%mple.com").is_none());
+        assert!(domain_to_ascii("\u{AD}").is_none());
+    }
+
+    #[test]
+    fn uts46_mapping() {
+        assert_eq!(domain_to_ascii("Ⅷ.com").unwrap(), "viii.com");
+        assert_eq!(domain_to_ascii("ﬁ.com").unwrap(), "fi.com");
+        assert_eq!(domain_to_ascii("①.com").unwrap(), "1.com");
+        // Deviation characters are kept (nontransitional processing)
+        assert_eq!(domain_to_ascii("faß.de").unwrap(), "xn--fa-hia.de");
+    }
+
+    #[test]
+    fn joiners() {
+        assert!(domain_to_ascii("a\u{200D}b.com").is_none());
+        assert!(domain_to_ascii("a\u{200C}b.com").is_none());
+        // After a virama
+        assert_eq!(
+            domain_to_ascii("क्\u{200D}ष.com").unwrap(),
+            "xn--11b2ezcw70k.com"
+        );
+        // Between an Arabic dual joining and a right joining letter
+        assert!(domain_to_ascii("\u{628}\u{200C}\u{627}.com").is_some());
+        assert!(domain_to_ascii("\u{627}\u{200C}\u{628}.com").is_none());
+    }
+
+    #[test]
+    fn to_unicode() {
+        assert_eq!(domain_to_unicode("xn--bcher-kva.example"), "bücher.example");
+    }
+}
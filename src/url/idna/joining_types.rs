@@ -0,0 +1,509 @@
+//! Joining types from the Unicode Character Database (`DerivedJoiningType.txt`, Unicode 15.1.0),
+//! for the CheckJoiners rules of UTS #46. Only the joining types used by those rules are listed.
+
+use super::JoiningType::{self, *};
+
+/// Sorted, non-overlapping ranges of code points with their joining type
+pub(super) static JOINING_TYPES: [(char, char, JoiningType); 501] = [
+    ('\u{AD}', '\u{AD}', Transparent),
+    ('\u{300}', '\u{36F}', Transparent),
+    ('\u{483}', '\u{489}', Transparent),
+    ('\u{591}', '\u{5BD}', Transparent),
+    ('\u{5BF}', '\u{5BF}', Transparent),
+    ('\u{5C1}', '\u{5C2}', Transparent),
+    ('\u{5C4}', '\u{5C5}', Transparent),
+    ('\u{5C7}', '\u{5C7}', Transparent),
+    ('\u{610}', '\u{61A}', Transparent),
+    ('\u{61C}', '\u{61C}', Transparent),
+    ('\u{620}', '\u{620}', Dual),
+    ('\u{622}', '\u{625}', Right),
+    ('\u{626}', '\u{626}', Dual),
+    ('\u{627}', '\u{627}', Right),
+    ('\u{628}', '\u{628}', Dual),
+    ('\u{629}', '\u{629}', Right),
+    ('\u{62A}', '\u{62E}', Dual),
+    ('\u{62F}', '\u{632}', Right),
+    ('\u{633}', '\u{63F}', Dual),
+    ('\u{641}', '\u{647}', Dual),
+    ('\u{648}', '\u{648}', Right),
+    ('\u{649}', '\u{64A}', Dual),
+    ('\u{64B}', '\u{65F}', Transparent),
+    ('\u{66E}', '\u{66F}', Dual),
+    ('\u{670}', '\u{670}', Transparent),
+    ('\u{671}', '\u{673}', Right),
+    ('\u{675}', '\u{677}', Right),
+    ('\u{678}', '\u{687}', Dual),
+    ('\u{688}', '\u{699}', Right),
+    ('\u{69A}', '\u{6BF}', Dual),
+    ('\u{6C0}', '\u{6C0}', Right),
+    ('\u{6C1}', '\u{6C2}', Dual),
+    ('\u{6C3}', '\u{6CB}', Right),
+    ('\u{6CC}', '\u{6CC}', Dual),
+    ('\u{6CD}', '\u{6CD}', Right),
+    ('\u{6CE}', '\u{6CE}', Dual),
+    ('\u{6CF}', '\u{6CF}', Right),
+    ('\u{6D0}', '\u{6D1}', Dual),
+    ('\u{6D2}', '\u{6D3}', Right),
+    ('\u{6D5}', '\u{6D5}', Right),
+    ('\u{6D6}', '\u{6DC}', Transparent),
+    ('\u{6DF}', '\u{6E4}', Transparent),
+    ('\u{6E7}', '\u{6E8}', Transparent),
+    ('\u{6EA}', '\u{6ED}', Transparent),
+    ('\u{6EE}', '\u{6EF}', Right),
+    ('\u{6FA}', '\u{6FC}', Dual),
+    ('\u{6FF}', '\u{6FF}', Dual),
+    ('\u{70F}', '\u{70F}', Transparent),
+    ('\u{710}', '\u{710}', Right),
+    ('\u{711}', '\u{711}', Transparent),
+    ('\u{712}', '\u{714}', Dual),
+    ('\u{715}', '\u{719}', Right),
+    ('\u{71A}', '\u{71D}', Dual),
+    ('\u{71E}', '\u{71E}', Right),
+    ('\u{71F}', '\u{727}', Dual),
+    ('\u{728}', '\u{728}', Right),
+    ('\u{729}', '\u{729}', Dual),
+    ('\u{72A}', '\u{72A}', Right),
+    ('\u{72B}', '\u{72B}', Dual),
+    ('\u{72C}', '\u{72C}', Right),
+    ('\u{72D}', '\u{72E}', Dual),
+    ('\u{72F}', '\u{72F}', Right),
+    ('\u{730}', '\u{74A}', Transparent),
+    ('\u{74D}', '\u{74D}', Right),
+    ('\u{74E}', '\u{758}', Dual),
+    ('\u{759}', '\u{75B}', Right),
+    ('\u{75C}', '\u{76A}', Dual),
+    ('\u{76B}', '\u{76C}', Right),
+    ('\u{76D}', '\u{770}', Dual),
+    ('\u{771}', '\u{771}', Right),
+    ('\u{772}', '\u{772}', Dual),
+    ('\u{773}', '\u{774}', Right),
+    ('\u{775}', '\u{777}', Dual),
+    ('\u{778}', '\u{779}', Right),
+    ('\u{77A}', '\u{77F}', Dual),
+    ('\u{7A6}', '\u{7B0}', Transparent),
+    ('\u{7CA}', '\u{7EA}', Dual),
+    ('\u{7EB}', '\u{7F3}', Transparent),
+    ('\u{7FD}', '\u{7FD}', Transparent),
+    ('\u{816}', '\u{819}', Transparent),
+    ('\u{81B}', '\u{823}', Transparent),
+    ('\u{825}', '\u{827}', Transparent),
+    ('\u{829}', '\u{82D}', Transparent),
+    ('\u{840}', '\u{840}', Right),
+    ('\u{841}', '\u{845}', Dual),
+    ('\u{846}', '\u{847}', Right),
+    ('\u{848}', '\u{848}', Dual),
+    ('\u{849}', '\u{849}', Right),
+    ('\u{84A}', '\u{853}', Dual),
+    ('\u{854}', '\u{854}', Right),
+    ('\u{855}', '\u{855}', Dual),
+    ('\u{856}', '\u{858}', Right),
+    ('\u{859}', '\u{85B}', Transparent),
+    ('\u{860}', '\u{860}', Dual),
+    ('\u{862}', '\u{865}', Dual),
+    ('\u{867}', '\u{867}', Right),
+    ('\u{868}', '\u{868}', Dual),
+    ('\u{869}', '\u{86A}', Right),
+    ('\u{870}', '\u{882}', Right),
+    ('\u{886}', '\u{886}', Dual),
+    ('\u{889}', '\u{88D}', Dual),
+    ('\u{88E}', '\u{88E}', Right),
+    ('\u{898}', '\u{89F}', Transparent),
+    ('\u{8A0}', '\u{8A9}', Dual),
+    ('\u{8AA}', '\u{8AC}', Right),
+    ('\u{8AE}', '\u{8AE}', Right),
+    ('\u{8AF}', '\u{8B0}', Dual),
+    ('\u{8B1}', '\u{8B2}', Right),
+    ('\u{8B3}', '\u{8B8}', Dual),
+    ('\u{8B9}', '\u{8B9}', Right),
+    ('\u{8BA}', '\u{8C8}', Dual),
+    ('\u{8CA}', '\u{8E1}', Transparent),
+    ('\u{8E3}', '\u{902}', Transparent),
+    ('\u{93A}', '\u{93A}', Transparent),
+    ('\u{93C}', '\u{93C}', Transparent),
+    ('\u{941}', '\u{948}', Transparent),
+    ('\u{94D}', '\u{94D}', Transparent),
+    ('\u{951}', '\u{957}', Transparent),
+    ('\u{962}', '\u{963}', Transparent),
+    ('\u{981}', '\u{981}', Transparent),
+    ('\u{9BC}', '\u{9BC}', Transparent),
+    ('\u{9C1}', '\u{9C4}', Transparent),
+    ('\u{9CD}', '\u{9CD}', Transparent),
+    ('\u{9E2}', '\u{9E3}', Transparent),
+    ('\u{9FE}', '\u{9FE}', Transparent),
+    ('\u{A01}', '\u{A02}', Transparent),
+    ('\u{A3C}', '\u{A3C}', Transparent),
+    ('\u{A41}', '\u{A42}', Transparent),
+    ('\u{A47}', '\u{A48}', Transparent),
+    ('\u{A4B}', '\u{A4D}', Transparent),
+    ('\u{A51}', '\u{A51}', Transparent),
+    ('\u{A70}', '\u{A71}', Transparent),
+    ('\u{A75}', '\u{A75}', Transparent),
+    ('\u{A81}', '\u{A82}', Transparent),
+    ('\u{ABC}', '\u{ABC}', Transparent),
+    ('\u{AC1}', '\u{AC5}', Transparent),
+    ('\u{AC7}', '\u{AC8}', Transparent),
+    ('\u{ACD}', '\u{ACD}', Transparent),
+    ('\u{AE2}', '\u{AE3}', Transparent),
+    ('\u{AFA}', '\u{AFF}', Transparent),
+    ('\u{B01}', '\u{B01}', Transparent),
+    ('\u{B3C}', '\u{B3C}', Transparent),
+    ('\u{B3F}', '\u{B3F}', Transparent),
+    ('\u{B41}', '\u{B44}', Transparent),
+    ('\u{B4D}', '\u{B4D}', Transparent),
+    ('\u{B55}', '\u{B56}', Transparent),
+    ('\u{B62}', '\u{B63}', Transparent),
+    ('\u{B82}', '\u{B82}', Transparent),
+    ('\u{BC0}', '\u{BC0}', Transparent),
+    ('\u{BCD}', '\u{BCD}', Transparent),
+    ('\u{C00}', '\u{C00}', Transparent),
+    ('\u{C04}', '\u{C04}', Transparent),
+    ('\u{C3C}', '\u{C3C}', Transparent),
+    ('\u{C3E}', '\u{C40}', Transparent),
+    ('\u{C46}', '\u{C48}', Transparent),
+    ('\u{C4A}', '\u{C4D}', Transparent),
+    ('\u{C55}', '\u{C56}', Transparent),
+    ('\u{C62}', '\u{C63}', Transparent),
+    ('\u{C81}', '\u{C81}', Transparent),
+    ('\u{CBC}', '\u{CBC}', Transparent),
+    ('\u{CBF}', '\u{CBF}', Transparent),
+    ('\u{CC6}', '\u{CC6}', Transparent),
+    ('\u{CCC}', '\u{CCD}', Transparent),
+    ('\u{CE2}', '\u{CE3}', Transparent),
+    ('\u{D00}', '\u{D01}', Transparent),
+    ('\u{D3B}', '\u{D3C}', Transparent),
+    ('\u{D41}', '\u{D44}', Transparent),
+    ('\u{D4D}', '\u{D4D}', Transparent),
+    ('\u{D62}', '\u{D63}', Transparent),
+    ('\u{D81}', '\u{D81}', Transparent),
+    ('\u{DCA}', '\u{DCA}', Transparent),
+    ('\u{DD2}', '\u{DD4}', Transparent),
+    ('\u{DD6}', '\u{DD6}', Transparent),
+    ('\u{E31}', '\u{E31}', Transparent),
+    ('\u{E34}', '\u{E3A}', Transparent),
+    ('\u{E47}', '\u{E4E}', Transparent),
+    ('\u{EB1}', '\u{EB1}', Transparent),
+    ('\u{EB4}', '\u{EBC}', Transparent),
+    ('\u{EC8}', '\u{ECE}', Transparent),
+    ('\u{F18}', '\u{F19}', Transparent),
+    ('\u{F35}', '\u{F35}', Transparent),
+    ('\u{F37}', '\u{F37}', Transparent),
+    ('\u{F39}', '\u{F39}', Transparent),
+    ('\u{F71}', '\u{F7E}', Transparent),
+    ('\u{F80}', '\u{F84}', Transparent),
+    ('\u{F86}', '\u{F87}', Transparent),
+    ('\u{F8D}', '\u{F97}', Transparent),
+    ('\u{F99}', '\u{FBC}', Transparent),
+    ('\u{FC6}', '\u{FC6}', Transparent),
+    ('\u{102D}', '\u{1030}', Transparent),
+    ('\u{1032}', '\u{1037}', Transparent),
+    ('\u{1039}', '\u{103A}', Transparent),
+    ('\u{103D}', '\u{103E}', Transparent),
+    ('\u{1058}', '\u{1059}', Transparent),
+    ('\u{105E}', '\u{1060}', Transparent),
+    ('\u{1071}', '\u{1074}', Transparent),
+    ('\u{1082}', '\u{1082}', Transparent),
+    ('\u{1085}', '\u{1086}', Transparent),
+    ('\u{108D}', '\u{108D}', Transparent),
+    ('\u{109D}', '\u{109D}', Transparent),
+    ('\u{135D}', '\u{135F}', Transparent),
+    ('\u{1712}', '\u{1714}', Transparent),
+    ('\u{1732}', '\u{1733}', Transparent),
+    ('\u{1752}', '\u{1753}', Transparent),
+    ('\u{1772}', '\u{1773}', Transparent),
+    ('\u{17B4}', '\u{17B5}', Transparent),
+    ('\u{17B7}', '\u{17BD}', Transparent),
+    ('\u{17C6}', '\u{17C6}', Transparent),
+    ('\u{17C9}', '\u{17D3}', Transparent),
+    ('\u{17DD}', '\u{17DD}', Transparent),
+    ('\u{1807}', '\u{1807}', Dual),
+    ('\u{180B}', '\u{180D}', Transparent),
+    ('\u{180F}', '\u{180F}', Transparent),
+    ('\u{1820}', '\u{1878}', Dual),
+    ('\u{1885}', '\u{1886}', Transparent),
+    ('\u{1887}', '\u{18A8}', Dual),
+    ('\u{18A9}', '\u{18A9}', Transparent),
+    ('\u{18AA}', '\u{18AA}', Dual),
+    ('\u{1920}', '\u{1922}', Transparent),
+    ('\u{1927}', '\u{1928}', Transparent),
+    ('\u{1932}', '\u{1932}', Transparent),
+    ('\u{1939}', '\u{193B}', Transparent),
+    ('\u{1A17}', '\u{1A18}', Transparent),
+    ('\u{1A1B}', '\u{1A1B}', Transparent),
+    ('\u{1A56}', '\u{1A56}', Transparent),
+    ('\u{1A58}', '\u{1A5E}', Transparent),
+    ('\u{1A60}', '\u{1A60}', Transparent),
+    ('\u{1A62}', '\u{1A62}', Transparent),
+    ('\u{1A65}', '\u{1A6C}', Transparent),
+    ('\u{1A73}', '\u{1A7C}', Transparent),
+    ('\u{1A7F}', '\u{1A7F}', Transparent),
+    ('\u{1AB0}', '\u{1ACE}', Transparent),
+    ('\u{1B00}', '\u{1B03}', Transparent),
+    ('\u{1B34}', '\u{1B34}', Transparent),
+    ('\u{1B36}', '\u{1B3A}', Transparent),
+    ('\u{1B3C}', '\u{1B3C}', Transparent),
+    ('\u{1B42}', '\u{1B42}', Transparent),
+    ('\u{1B6B}', '\u{1B73}', Transparent),
+    ('\u{1B80}', '\u{1B81}', Transparent),
+    ('\u{1BA2}', '\u{1BA5}', Transparent),
+    ('\u{1BA8}', '\u{1BA9}', Transparent),
+    ('\u{1BAB}', '\u{1BAD}', Transparent),
+    ('\u{1BE6}', '\u{1BE6}', Transparent),
+    ('\u{1BE8}', '\u{1BE9}', Transparent),
+    ('\u{1BED}', '\u{1BED}', Transparent),
+    ('\u{1BEF}', '\u{1BF1}', Transparent),
+    ('\u{1C2C}', '\u{1C33}', Transparent),
+    ('\u{1C36}', '\u{1C37}', Transparent),
+    ('\u{1CD0}', '\u{1CD2}', Transparent),
+    ('\u{1CD4}', '\u{1CE0}', Transparent),
+    ('\u{1CE2}', '\u{1CE8}', Transparent),
+    ('\u{1CED}', '\u{1CED}', Transparent),
+    ('\u{1CF4}', '\u{1CF4}', Transparent),
+    ('\u{1CF8}', '\u{1CF9}', Transparent),
+    ('\u{1DC0}', '\u{1DFF}', Transparent),
+    ('\u{200B}', '\u{200B}', Transparent),
+    ('\u{200E}', '\u{200F}', Transparent),
+    ('\u{202A}', '\u{202E}', Transparent),
+    ('\u{2060}', '\u{2064}', Transparent),
+    ('\u{206A}', '\u{206F}', Transparent),
+    ('\u{20D0}', '\u{20F0}', Transparent),
+    ('\u{2CEF}', '\u{2CF1}', Transparent),
+    ('\u{2D7F}', '\u{2D7F}', Transparent),
+    ('\u{2DE0}', '\u{2DFF}', Transparent),
+    ('\u{302A}', '\u{302D}', Transparent),
+    ('\u{3099}', '\u{309A}', Transparent),
+    ('\u{A66F}', '\u{A672}', Transparent),
+    ('\u{A674}', '\u{A67D}', Transparent),
+    ('\u{A69E}', '\u{A69F}', Transparent),
+    ('\u{A6F0}', '\u{A6F1}', Transparent),
+    ('\u{A802}', '\u{A802}', Transparent),
+    ('\u{A806}', '\u{A806}', Transparent),
+    ('\u{A80B}', '\u{A80B}', Transparent),
+    ('\u{A825}', '\u{A826}', Transparent),
+    ('\u{A82C}', '\u{A82C}', Transparent),
+    ('\u{A840}', '\u{A871}', Dual),
+    ('\u{A872}', '\u{A872}', Left),
+    ('\u{A8C4}', '\u{A8C5}', Transparent),
+    ('\u{A8E0}', '\u{A8F1}', Transparent),
+    ('\u{A8FF}', '\u{A8FF}', Transparent),
+    ('\u{A926}', '\u{A92D}', Transparent),
+    ('\u{A947}', '\u{A951}', Transparent),
+    ('\u{A980}', '\u{A982}', Transparent),
+    ('\u{A9B3}', '\u{A9B3}', Transparent),
+    ('\u{A9B6}', '\u{A9B9}', Transparent),
+    ('\u{A9BC}', '\u{A9BD}', Transparent),
+    ('\u{A9E5}', '\u{A9E5}', Transparent),
+    ('\u{AA29}', '\u{AA2E}', Transparent),
+    ('\u{AA31}', '\u{AA32}', Transparent),
+    ('\u{AA35}', '\u{AA36}', Transparent),
+    ('\u{AA43}', '\u{AA43}', Transparent),
+    ('\u{AA4C}', '\u{AA4C}', Transparent),
+    ('\u{AA7C}', '\u{AA7C}', Transparent),
+    ('\u{AAB0}', '\u{AAB0}', Transparent),
+    ('\u{AAB2}', '\u{AAB4}', Transparent),
+    ('\u{AAB7}', '\u{AAB8}', Transparent),
+    ('\u{AABE}', '\u{AABF}', Transparent),
+    ('\u{AAC1}', '\u{AAC1}', Transparent),
+    ('\u{AAEC}', '\u{AAED}', Transparent),
+    ('\u{AAF6}', '\u{AAF6}', Transparent),
+    ('\u{ABE5}', '\u{ABE5}', Transparent),
+    ('\u{ABE8}', '\u{ABE8}', Transparent),
+    ('\u{ABED}', '\u{ABED}', Transparent),
+    ('\u{FB1E}', '\u{FB1E}', Transparent),
+    ('\u{FE00}', '\u{FE0F}', Transparent),
+    ('\u{FE20}', '\u{FE2F}', Transparent),
+    ('\u{FEFF}', '\u{FEFF}', Transparent),
+    ('\u{FFF9}', '\u{FFFB}', Transparent),
+    ('\u{101FD}', '\u{101FD}', Transparent),
+    ('\u{102E0}', '\u{102E0}', Transparent),
+    ('\u{10376}', '\u{1037A}', Transparent),
+    ('\u{10A01}', '\u{10A03}', Transparent),
+    ('\u{10A05}', '\u{10A06}', Transparent),
+    ('\u{10A0C}', '\u{10A0F}', Transparent),
+    ('\u{10A38}', '\u{10A3A}', Transparent),
+    ('\u{10A3F}', '\u{10A3F}', Transparent),
+    ('\u{10AC0}', '\u{10AC4}', Dual),
+    ('\u{10AC5}', '\u{10AC5}', Right),
+    ('\u{10AC7}', '\u{10AC7}', Right),
+    ('\u{10AC9}', '\u{10ACA}', Right),
+    ('\u{10ACD}', '\u{10ACD}', Left),
+    ('\u{10ACE}', '\u{10AD2}', Right),
+    ('\u{10AD3}', '\u{10AD6}', Dual),
+    ('\u{10AD7}', '\u{10AD7}', Left),
+    ('\u{10AD8}', '\u{10ADC}', Dual),
+    ('\u{10ADD}', '\u{10ADD}', Right),
+    ('\u{10ADE}', '\u{10AE0}', Dual),
+    ('\u{10AE1}', '\u{10AE1}', Right),
+    ('\u{10AE4}', '\u{10AE4}', Right),
+    ('\u{10AE5}', '\u{10AE6}', Transparent),
+    ('\u{10AEB}', '\u{10AEE}', Dual),
+    ('\u{10AEF}', '\u{10AEF}', Right),
+    ('\u{10B80}', '\u{10B80}', Dual),
+    ('\u{10B81}', '\u{10B81}', Right),
+    ('\u{10B82}', '\u{10B82}', Dual),
+    ('\u{10B83}', '\u{10B85}', Right),
+    ('\u{10B86}', '\u{10B88}', Dual),
+    ('\u{10B89}', '\u{10B89}', Right),
+    ('\u{10B8A}', '\u{10B8B}', Dual),
+    ('\u{10B8C}', '\u{10B8C}', Right),
+    ('\u{10B8D}', '\u{10B8D}', Dual),
+    ('\u{10B8E}', '\u{10B8F}', Right),
+    ('\u{10B90}', '\u{10B90}', Dual),
+    ('\u{10B91}', '\u{10B91}', Right),
+    ('\u{10BA9}', '\u{10BAC}', Right),
+    ('\u{10BAD}', '\u{10BAE}', Dual),
+    ('\u{10D00}', '\u{10D00}', Left),
+    ('\u{10D01}', '\u{10D21}', Dual),
+    ('\u{10D22}', '\u{10D22}', Right),
+    ('\u{10D23}', '\u{10D23}', Dual),
+    ('\u{10D24}', '\u{10D27}', Transparent),
+    ('\u{10EAB}', '\u{10EAC}', Transparent),
+    ('\u{10EFD}', '\u{10EFF}', Transparent),
+    ('\u{10F30}', '\u{10F32}', Dual),
+    ('\u{10F33}', '\u{10F33}', Right),
+    ('\u{10F34}', '\u{10F44}', Dual),
+    ('\u{10F46}', '\u{10F50}', Transparent),
+    ('\u{10F51}', '\u{10F53}', Dual),
+    ('\u{10F54}', '\u{10F54}', Right),
+    ('\u{10F70}', '\u{10F73}', Dual),
+    ('\u{10F74}', '\u{10F75}', Right),
+    ('\u{10F76}', '\u{10F81}', Dual),
+    ('\u{10F82}', '\u{10F85}', Transparent),
+    ('\u{10FB0}', '\u{10FB0}', Dual),
+    ('\u{10FB2}', '\u{10FB3}', Dual),
+    ('\u{10FB4}', '\u{10FB6}', Right),
+    ('\u{10FB8}', '\u{10FB8}', Dual),
+    ('\u{10FB9}', '\u{10FBA}', Right),
+    ('\u{10FBB}', '\u{10FBC}', Dual),
+    ('\u{10FBD}', '\u{10FBD}', Right),
+    ('\u{10FBE}', '\u{10FBF}', Dual),
+    ('\u{10FC1}', '\u{10FC1}', Dual),
+    ('\u{10FC2}', '\u{10FC3}', Right),
+    ('\u{10FC4}', '\u{10FC4}', Dual),
+    ('\u{10FC9}', '\u{10FC9}', Right),
+    ('\u{10FCA}', '\u{10FCA}', Dual),
+    ('\u{10FCB}', '\u{10FCB}', Left),
+    ('\u{11001}', '\u{11001}', Transparent),
+    ('\u{11038}', '\u{11046}', Transparent),
+    ('\u{11070}', '\u{11070}', Transparent),
+    ('\u{11073}', '\u{11074}', Transparent),
+    ('\u{1107F}', '\u{11081}', Transparent),
+    ('\u{110B3}', '\u{110B6}', Transparent),
+    ('\u{110B9}', '\u{110BA}', Transparent),
+    ('\u{110C2}', '\u{110C2}', Transparent),
+    ('\u{11100}', '\u{11102}', Transparent),
+    ('\u{11127}', '\u{1112B}', Transparent),
+    ('\u{1112D}', '\u{11134}', Transparent),
+    ('\u{11173}', '\u{11173}', Transparent),
+    ('\u{11180}', '\u{11181}', Transparent),
+    ('\u{111B6}', '\u{111BE}', Transparent),
+    ('\u{111C9}', '\u{111CC}', Transparent),
+    ('\u{111CF}', '\u{111CF}', Transparent),
+    ('\u{1122F}', '\u{11231}', Transparent),
+    ('\u{11234}', '\u{11234}', Transparent),
+    ('\u{11236}', '\u{11237}', Transparent),
+    ('\u{1123E}', '\u{1123E}', Transparent),
+    ('\u{11241}', '\u{11241}', Transparent),
+    ('\u{112DF}', '\u{112DF}', Transparent),
+    ('\u{112E3}', '\u{112EA}', Transparent),
+    ('\u{11300}', '\u{11301}', Transparent),
+    ('\u{1133B}', '\u{1133C}', Transparent),
+    ('\u{11340}', '\u{11340}', Transparent),
+    ('\u{11366}', '\u{1136C}', Transparent),
+    ('\u{11370}', '\u{11374}', Transparent),
+    ('\u{11438}', '\u{1143F}', Transparent),
+    ('\u{11442}', '\u{11444}', Transparent),
+    ('\u{11446}', '\u{11446}', Transparent),
+    ('\u{1145E}', '\u{1145E}', Transparent),
+    ('\u{114B3}', '\u{114B8}', Transparent),
+    ('\u{114BA}', '\u{114BA}', Transparent),
+    ('\u{114BF}', '\u{114C0}', Transparent),
+    ('\u{114C2}', '\u{114C3}', Transparent),
+    ('\u{115B2}', '\u{115B5}', Transparent),
+    ('\u{115BC}', '\u{115BD}', Transparent),
+    ('\u{115BF}', '\u{115C0}', Transparent),
+    ('\u{115DC}', '\u{115DD}', Transparent),
+    ('\u{11633}', '\u{1163A}', Transparent),
+    ('\u{1163D}', '\u{1163D}', Transparent),
+    ('\u{1163F}', '\u{11640}', Transparent),
+    ('\u{116AB}', '\u{116AB}', Transparent),
+    ('\u{116AD}', '\u{116AD}', Transparent),
+    ('\u{116B0}', '\u{116B5}', Transparent),
+    ('\u{116B7}', '\u{116B7}', Transparent),
+    ('\u{1171D}', '\u{1171F}', Transparent),
+    ('\u{11722}', '\u{11725}', Transparent),
+    ('\u{11727}', '\u{1172B}', Transparent),
+    ('\u{1182F}', '\u{11837}', Transparent),
+    ('\u{11839}', '\u{1183A}', Transparent),
+    ('\u{1193B}', '\u{1193C}', Transparent),
+    ('\u{1193E}', '\u{1193E}', Transparent),
+    ('\u{11943}', '\u{11943}', Transparent),
+    ('\u{119D4}', '\u{119D7}', Transparent),
+    ('\u{119DA}', '\u{119DB}', Transparent),
+    ('\u{119E0}', '\u{119E0}', Transparent),
+    ('\u{11A01}', '\u{11A0A}', Transparent),
+    ('\u{11A33}', '\u{11A38}', Transparent),
+    ('\u{11A3B}', '\u{11A3E}', Transparent),
+    ('\u{11A47}', '\u{11A47}', Transparent),
+    ('\u{11A51}', '\u{11A56}', Transparent),
+    ('\u{11A59}', '\u{11A5B}', Transparent),
+    ('\u{11A8A}', '\u{11A96}', Transparent),
+    ('\u{11A98}', '\u{11A99}', Transparent),
+    ('\u{11C30}', '\u{11C36}', Transparent),
+    ('\u{11C38}', '\u{11C3D}', Transparent),
+    ('\u{11C3F}', '\u{11C3F}', Transparent),
+    ('\u{11C92}', '\u{11CA7}', Transparent),
+    ('\u{11CAA}', '\u{11CB0}', Transparent),
+    ('\u{11CB2}', '\u{11CB3}', Transparent),
+    ('\u{11CB5}', '\u{11CB6}', Transparent),
+    ('\u{11D31}', '\u{11D36}', Transparent),
+    ('\u{11D3A}', '\u{11D3A}', Transparent),
+    ('\u{11D3C}', '\u{11D3D}', Transparent),
+    ('\u{11D3F}', '\u{11D45}', Transparent),
+    ('\u{11D47}', '\u{11D47}', Transparent),
+    ('\u{11D90}', '\u{11D91}', Transparent),
+    ('\u{11D95}', '\u{11D95}', Transparent),
+    ('\u{11D97}', '\u{11D97}', Transparent),
+    ('\u{11EF3}', '\u{11EF4}', Transparent),
+    ('\u{11F00}', '\u{11F01}', Transparent),
+    ('\u{11F36}', '\u{11F3A}', Transparent),
+    ('\u{11F40}', '\u{11F40}', Transparent),
+    ('\u{11F42}', '\u{11F42}', Transparent),
+    ('\u{13430}', '\u{13440}', Transparent),
+    ('\u{13447}', '\u{13455}', Transparent),
+    ('\u{16AF0}', '\u{16AF4}', Transparent),
+    ('\u{16B30}', '\u{16B36}', Transparent),
+    ('\u{16F4F}', '\u{16F4F}', Transparent),
+    ('\u{16F8F}', '\u{16F92}', Transparent),
+    ('\u{16FE4}', '\u{16FE4}', Transparent),
+    ('\u{1BC9D}', '\u{1BC9E}', Transparent),
+    ('\u{1BCA0}', '\u{1BCA3}', Transparent),
+    ('\u{1CF00}', '\u{1CF2D}', Transparent),
+    ('\u{1CF30}', '\u{1CF46}', Transparent),
+    ('\u{1D167}', '\u{1D169}', Transparent),
+    ('\u{1D173}', '\u{1D182}', Transparent),
+    ('\u{1D185}', '\u{1D18B}', Transparent),
+    ('\u{1D1AA}', '\u{1D1AD}', Transparent),
+    ('\u{1D242}', '\u{1D244}', Transparent),
+    ('\u{1DA00}', '\u{1DA36}', Transparent),
+    ('\u{1DA3B}', '\u{1DA6C}', Transparent),
+    ('\u{1DA75}', '\u{1DA75}', Transparent),
+    ('\u{1DA84}', '\u{1DA84}', Transparent),
+    ('\u{1DA9B}', '\u{1DA9F}', Transparent),
+    ('\u{1DAA1}', '\u{1DAAF}', Transparent),
+    ('\u{1E000}', '\u{1E006}', Transparent),
+    ('\u{1E008}', '\u{1E018}', Transparent),
+    ('\u{1E01B}', '\u{1E021}', Transparent),
+    ('\u{1E023}', '\u{1E024}', Transparent),
+    ('\u{1E026}', '\u{1E02A}', Transparent),
+    ('\u{1E08F}', '\u{1E08F}', Transparent),
+    ('\u{1E130}', '\u{1E136}', Transparent),
+    ('\u{1E2AE}', '\u{1E2AE}', Transparent),
+    ('\u{1E2EC}', '\u{1E2EF}', Transparent),
+    ('\u{1E4EC}', '\u{1E4EF}', Transparent),
+    ('\u{1E8D0}', '\u{1E8D6}', Transparent),
+    ('\u{1E900}', '\u{1E943}', Dual),
+    ('\u{1E944}', '\u{1E94B}', Transparent),
+    ('\u{E0001}', '\u{E0001}', Transparent),
+    ('\u{E0020}', '\u{E007F}', Transparent),
+    ('\u{E0100}', '\u{E01EF}', Transparent),
+];
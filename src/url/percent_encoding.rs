@@ -0,0 +1,144 @@
+//! Percent-encoding and decoding as described in <https://url.spec.whatwg.org/#percent-encoded-bytes>
+
+/// The percent-encode sets from the URL specification. Each set contains the previous one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EncodeSet {
+    /// C0 controls and all code points above U+007E (~)
+    C0Control,
+    /// C0 control set, plus space, ", <, > and `
+    Fragment,
+    /// C0 control set, plus space, ", #, < and >
+    Query,
+    /// Query set, plus '
+    SpecialQuery,
+    /// Query set, plus ?, `, { and }
+    Path,
+    /// Path set, plus /, :, ;, =, @, [ to ^, and |
+    Userinfo,
+    /// Userinfo set, plus $ to &, + and ,
+    Component,
+    /// Component set, plus !, ' to ) and ~
+    FormUrlencoded,
+}
+
+impl EncodeSet {
+    /// Returns true when the byte must be percent-encoded in this set
+    pub fn contains(self, byte: u8) -> bool {
+        if !(0x20..=0x7E).contains(&byte) {
+            return true;
+        }
+
+        match self {
+            EncodeSet::C0Control => false,
+            EncodeSet::Fragment => matches!(byte, b' ' | b'"' | b'<' | b'>' | b'`'),
+            EncodeSet::Query => matches!(byte, b' ' | b'"' | b'#' | b'<' | b'>'),
+            EncodeSet::SpecialQuery => byte == b'\'' || EncodeSet::Query.contains(byte),
+            EncodeSet::Path => {
+                matches!(byte, b'?' | b'`' | b'{' | b'}') || EncodeSet::Query.contains(byte)
+            }
+            EncodeSet::Userinfo => {
+                matches!(byte, b'/' | b':' | b';' | b'=' | b'@' | b'['..=b'^' | b'|')
+                    || EncodeSet::Path.contains(byte)
+            }
+            EncodeSet::Component => {
+                matches!(byte, b'$'..=b'&' | b'+' | b',') || EncodeSet::Userinfo.contains(byte)
+            }
+            EncodeSet::FormUrlencoded => {
+                matches!(byte, b'!' | b'\''..=b')' | b'~') || EncodeSet::Component.contains(byte)
+            }
+        }
+    }
+}
+
+/// Percent-encodes all bytes of the UTF-8 encoded string that are in the encode set
+pub fn utf8_percent_encode(input: &str, set: EncodeSet) -> String {
+    percent_encode_bytes(input.as_bytes(), set)
+}
+
+/// Percent-encodes all bytes that are in the encode set
+pub fn percent_encode_bytes(input: &[u8], set: EncodeSet) -> String {
+    let mut output = String::with_capacity(input.len());
+    for byte in input {
+        if set.contains(*byte) {
+            output.push_str(&format!("%{:02X}", byte));
+        } else {
+            output.push(*byte as char);
+        }
+    }
+    output
+}
+
+/// Decodes all percent-encoded bytes. Percent signs that are not followed by two hex digits are
+/// kept as-is.
+pub fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut idx = 0;
+    while idx < input.len() {
+        let byte = input[idx];
+        if byte == b'%' && idx + 2 < input.len() {
+            if let (Some(high), Some(low)) = (hex_value(input[idx + 1]), hex_value(input[idx + 2]))
+            {
+                output.push(high << 4 | low);
+                idx += 3;
+                continue;
+            }
+        }
+        output.push(byte);
+        idx += 1;
+    }
+    output
+}
+
+/// Decodes all percent-encoded bytes, and converts the result into a string (replacing invalid
+/// UTF-8 sequences)
+pub fn percent_decode_str(input: &str) -> String {
+    String::from_utf8_lossy(&percent_decode(input.as_bytes())).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_sets() {
+        assert_eq!(
+            utf8_percent_encode("a b\"#", EncodeSet::Fragment),
+            "a%20b%22#"
+        );
+        assert_eq!(
+            utf8_percent_encode("a b\"#", EncodeSet::Query),
+            "a%20b%22%23"
+        );
+        assert_eq!(utf8_percent_encode("'{x}", EncodeSet::Query), "'{x}");
+        assert_eq!(
+            utf8_percent_encode("'{x}", EncodeSet::SpecialQuery),
+            "%27{x}"
+        );
+        assert_eq!(utf8_percent_encode("'{x}?", EncodeSet::Path), "'%7Bx%7D%3F");
+        assert_eq!(
+            utf8_percent_encode("a:b@c", EncodeSet::Userinfo),
+            "a%3Ab%40c"
+        );
+        assert_eq!(
+            utf8_percent_encode("a+b&c", EncodeSet::Component),
+            "a%2Bb%26c"
+        );
+        assert_eq!(
+            utf8_percent_encode("(~)", EncodeSet::FormUrlencoded),
+            "%28%7E%29"
+        );
+        assert_eq!(utf8_percent_encode("é", EncodeSet::C0Control), "%C3%A9");
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(percent_decode_str("%41b%c3%A9"), "Abé");
+        assert_eq!(percent_decode_str("100%"), "100%");
+        assert_eq!(percent_decode_str("%4"), "%4");
+        assert_eq!(percent_decode_str("%zz%41"), "%zzA");
+    }
+}
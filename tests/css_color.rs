@@ -0,0 +1,19 @@
+use test_case::test_case;
+
+use gosub_engine::testing::css;
+
+#[test_case("css-parsing-tests/color3.json")]
+#[test_case("css-parsing-tests/color3_hsl.json")]
+#[test_case("css-parsing-tests/color3_keywords.json")]
+#[test_case("css-parsing-tests/color4_hwb.json")]
+#[test_case("css/color3.json")]
+#[test_case("css/color3_hsl.json")]
+#[test_case("css/color3_keywords.json")]
+#[test_case("css/color4_hwb.json")]
+#[test_case("css/color4_lab_lch_oklab_oklch.json")]
+#[test_case("css/color4_color_function.json")]
+fn colors(filename: &str) {
+    for test in css::fixture_from_filename(filename).unwrap() {
+        test.assert_valid_color();
+    }
+}
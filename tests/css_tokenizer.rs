@@ -0,0 +1,19 @@
+use test_case::test_case;
+
+use gosub_engine::testing::css;
+
+#[test_case("css/tokens.json")]
+#[test_case("css-parsing-tests/component_value_list.json" => ignore["upstream fixture not vendored yet"])]
+fn tokenization(filename: &str) {
+    for test in css::fixture_from_filename(filename).unwrap() {
+        test.assert_valid();
+    }
+}
+
+#[test_case("css/one_component_value.json")]
+#[test_case("css-parsing-tests/one_component_value.json" => ignore["upstream fixture not vendored yet"])]
+fn one_component_value(filename: &str) {
+    for test in css::fixture_from_filename(filename).unwrap() {
+        test.assert_valid_one_component_value();
+    }
+}
@@ -0,0 +1,15 @@
+use gosub_engine::testing::url;
+
+#[test]
+fn urltestdata() {
+    for test in url::fixture_from_filename("urltestdata.json").unwrap() {
+        test.assert_valid();
+    }
+}
+
+#[test]
+fn idna_test_v2() {
+    for test in url::idna_tests_from_filename("IdnaTestV2.txt").unwrap() {
+        test.assert_valid();
+    }
+}